@@ -1,12 +1,17 @@
 //! System broadcast messaging functionality
 
-use super::SystemMaintenance;
+use super::{SystemMaintenance, SystemMessage};
 use crate::access::AdminPermission;
 use crate::error::{AdminError, AdminResult};
+use chrono::Utc;
+use std::time::Duration;
 
 impl SystemMaintenance {
     /// Broadcasts a message to all active sessions
     ///
+    /// The message is delivered through the channel given to
+    /// [`with_notices`](SystemMaintenance::with_notices), if any.
+    ///
     /// # Arguments
     /// * `admin_user_id` - ID of the administrator sending the broadcast
     /// * `message` - Message content to broadcast
@@ -33,11 +38,17 @@ impl SystemMaintenance {
             ));
         }
 
-        let sessions = self.sessions.read().await;
-        let receiver_count = sessions.len();
+        let receiver_count = match &self.notices {
+            Some(notices) => notices
+                .send(SystemMessage::Broadcast {
+                    from_admin: admin_user_id,
+                    message: message.clone(),
+                    timestamp: Utc::now(),
+                })
+                .unwrap_or(0),
+            None => self.sessions.read().await.len(),
+        };
 
-        // In production, this would actually send the message via broadcast channel
-        // For testing, we just log it
         tracing::info!(
             admin_user_id = admin_user_id,
             receiver_count = receiver_count,
@@ -158,6 +169,50 @@ impl SystemMaintenance {
 
         Ok(receiver_count)
     }
+
+    /// Sends one step of a shutdown countdown to all active sessions
+    ///
+    /// # Arguments
+    /// * `admin_user_id` - ID of the administrator initiating the shutdown
+    /// * `remaining` - Time left until system shutdown
+    pub async fn broadcast_shutdown_countdown(
+        &self,
+        admin_user_id: i32,
+        remaining: Duration,
+    ) -> AdminResult<usize> {
+        let seconds = remaining.as_secs();
+        if seconds == 0 {
+            return Err(AdminError::InvalidInput(
+                "Shutdown countdown must be at least one second".to_string(),
+            ));
+        }
+
+        let when = if seconds.is_multiple_of(60) {
+            format!("{} minute(s)", seconds / 60)
+        } else {
+            format!("{} second(s)", seconds)
+        };
+        let message = format!(
+            "SYSTEM SHUTDOWN: The system will shut down in {}. Please save your work and log off.",
+            when
+        );
+
+        let receiver_count = self.broadcast_message(admin_user_id, message).await?;
+
+        self.audit
+            .log_action(
+                admin_user_id,
+                "broadcast_shutdown_countdown",
+                None::<String>,
+                Some(format!(
+                    "receivers={}, seconds_remaining={}",
+                    receiver_count, seconds
+                )),
+            )
+            .await;
+
+        Ok(receiver_count)
+    }
 }
 
 #[cfg(test)]
@@ -166,7 +221,7 @@ mod tests {
     use crate::access::AdminAccessControl;
     use crate::audit::AuditLogger;
     use crate::system::ActiveSession;
-    use chrono::Utc;
+    use tokio::sync::broadcast;
 
     fn create_test_session(user_id: i32, username: &str) -> ActiveSession {
         ActiveSession {
@@ -321,6 +376,82 @@ mod tests {
         assert!(matches!(result.unwrap_err(), AdminError::InvalidInput(_)));
     }
 
+    #[tokio::test]
+    async fn test_broadcast_message_delivers_to_callers() {
+        let access = AdminAccessControl::new(200, 200);
+        let audit = AuditLogger::new();
+        let (sender, mut caller) = broadcast::channel(4);
+        let maint = SystemMaintenance::new(access, audit).with_notices(sender);
+
+        let result = maint.broadcast_message(7, "Test message".to_string()).await;
+        assert_eq!(result.unwrap(), 1);
+
+        match caller.recv().await.unwrap() {
+            SystemMessage::Broadcast {
+                from_admin,
+                message,
+                ..
+            } => {
+                assert_eq!(from_admin, 7);
+                assert_eq!(message, "Test message");
+            }
+            other => panic!("Wrong message type: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_message_no_callers_connected() {
+        let access = AdminAccessControl::new(200, 200);
+        let audit = AuditLogger::new();
+        let (sender, _) = broadcast::channel(4);
+        let maint = SystemMaintenance::new(access, audit).with_notices(sender);
+
+        let result = maint.broadcast_message(1, "Test message".to_string()).await;
+        assert_eq!(result.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_shutdown_countdown() {
+        let access = AdminAccessControl::new(200, 200);
+        let audit = AuditLogger::new();
+        let (sender, mut caller) = broadcast::channel(4);
+        let maint = SystemMaintenance::new(access, audit.clone()).with_notices(sender);
+
+        maint
+            .broadcast_shutdown_countdown(0, Duration::from_secs(120))
+            .await
+            .unwrap();
+        maint
+            .broadcast_shutdown_countdown(0, Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        let mut messages = Vec::new();
+        while let Ok(SystemMessage::Broadcast { message, .. }) = caller.try_recv() {
+            messages.push(message);
+        }
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].contains("in 2 minute(s)"));
+        assert!(messages[1].contains("in 10 second(s)"));
+
+        let entries = audit
+            .get_entries_by_action("broadcast_shutdown_countdown")
+            .await;
+        assert_eq!(entries.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_shutdown_countdown_invalid() {
+        let access = AdminAccessControl::new(200, 200);
+        let audit = AuditLogger::new();
+        let maint = SystemMaintenance::new(access, audit);
+
+        let result = maint
+            .broadcast_shutdown_countdown(1, Duration::from_millis(500))
+            .await;
+        assert!(matches!(result.unwrap_err(), AdminError::InvalidInput(_)));
+    }
+
     #[tokio::test]
     async fn test_audit_log_broadcast_message() {
         let access = AdminAccessControl::new(200, 200);
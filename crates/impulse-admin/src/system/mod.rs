@@ -47,6 +47,8 @@ pub struct SystemMaintenance {
     sessions: SessionStore,
     audit: AuditLogger,
     access_control: AdminAccessControl,
+    /// Channel that delivers system messages to connected callers
+    notices: Option<tokio::sync::broadcast::Sender<SystemMessage>>,
 }
 
 impl SystemMaintenance {
@@ -56,6 +58,7 @@ impl SystemMaintenance {
             sessions: Arc::new(RwLock::new(HashMap::new())),
             audit,
             access_control,
+            notices: None,
         }
     }
 
@@ -72,9 +75,18 @@ impl SystemMaintenance {
             sessions: Arc::new(RwLock::new(session_map)),
             audit,
             access_control,
+            notices: None,
         }
     }
 
+    /// Delivers broadcasts to connected callers through `notices`
+    ///
+    /// Without a channel, broadcasts are only logged and audited.
+    pub fn with_notices(mut self, notices: tokio::sync::broadcast::Sender<SystemMessage>) -> Self {
+        self.notices = Some(notices);
+        self
+    }
+
     /// Returns a reference to the access control
     pub fn access_control(&self) -> &AdminAccessControl {
         &self.access_control
@@ -15,8 +15,8 @@ use std::sync::Arc;
 pub enum LoginFlowResult {
     /// Login successful - user authenticated
     Success {
-        /// Authenticated user
-        user: User,
+        /// Authenticated user (boxed to keep the enum small)
+        user: Box<User>,
         /// Session token for subsequent requests
         session_token: String,
     },
@@ -149,7 +149,7 @@ impl LoginFlow {
                     "Login successful"
                 );
                 LoginFlowResult::Success {
-                    user,
+                    user: Box::new(user),
                     session_token: token.to_string(),
                 }
             }
@@ -110,7 +110,9 @@ pub enum RegistrationResult {
 /// Registration flow handler
 ///
 /// Coordinates new user registration by validating all inputs,
-/// checking username availability, and creating new user accounts.
+/// checking username availability, and creating new user accounts with an
+/// Argon2 hash of the chosen password. Storing the account is left to the
+/// caller's user manager.
 ///
 /// # Examples
 ///
@@ -140,7 +142,6 @@ pub enum RegistrationResult {
 /// # }
 /// ```
 pub struct RegistrationFlow {
-    #[allow(dead_code)] // Will be used for user storage lookup in future
     auth_service: Arc<AuthService>,
     min_password_strength: PasswordStrength,
 }
@@ -253,25 +254,45 @@ impl RegistrationFlow {
             return RegistrationResult::EmailInvalid;
         }
 
-        // Create user
-        match User::new(&request.username) {
-            Ok(user) => {
-                tracing::info!(
+        // Create user with the hashed password
+        let mut user = match User::new(&request.username) {
+            Ok(user) => user,
+            Err(e) => {
+                tracing::error!(
                     username = %request.username,
-                    user_id = ?user.id(),
-                    "User registered successfully"
+                    error = %e,
+                    "Failed to create user"
                 );
-                RegistrationResult::Success { user }
+                return RegistrationResult::ValidationError(format!(
+                    "Failed to create user: {}",
+                    e
+                ));
             }
+        };
+
+        match self.auth_service.hash_password(&request.password) {
+            Ok(hash) => user.password_hash = Some(hash),
             Err(e) => {
                 tracing::error!(
                     username = %request.username,
                     error = %e,
-                    "Failed to create user"
+                    "Failed to hash password"
                 );
-                RegistrationResult::ValidationError(format!("Failed to create user: {}", e))
+                return RegistrationResult::ValidationError(format!(
+                    "Failed to create user: {}",
+                    e
+                ));
             }
         }
+        user.email = request.email.clone();
+        user.real_name = request.real_name.clone();
+
+        tracing::info!(
+            username = %request.username,
+            user_id = ?user.id(),
+            "User registered successfully"
+        );
+        RegistrationResult::Success { user }
     }
 
     /// Check if username is available
@@ -345,6 +366,14 @@ mod tests {
         match result {
             RegistrationResult::Success { user } => {
                 assert_eq!(user.username(), "newuser");
+
+                // The stored hash verifies against the chosen password
+                let hash = user.password_hash.as_deref().expect("password hash set");
+                assert!(
+                    crate::PasswordHasher::new()
+                        .verify_password("SecureP@ss123", hash)
+                        .is_ok()
+                );
             }
             other => panic!("Expected Success, got {:?}", other),
         }
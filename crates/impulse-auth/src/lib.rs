@@ -56,7 +56,7 @@ use impulse_types::{
     user::{User, UserId},
 };
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock, PoisonError};
 use std::time::{Duration, SystemTime};
 use thiserror::Error;
 use tokio::sync::RwLock;
@@ -268,8 +268,8 @@ pub struct SessionManager {
     /// Active sessions by token
     sessions: Arc<RwLock<HashMap<SessionToken, Session>>>,
 
-    /// Default session timeout
-    default_timeout: Duration,
+    /// Default session timeout, shared with clones
+    default_timeout: Arc<std::sync::RwLock<Duration>>,
 }
 
 impl SessionManager {
@@ -277,13 +277,31 @@ impl SessionManager {
     pub fn new(default_timeout: Duration) -> Self {
         Self {
             sessions: Arc::new(RwLock::new(HashMap::new())),
-            default_timeout,
+            default_timeout: Arc::new(std::sync::RwLock::new(default_timeout)),
         }
     }
 
+    /// Timeout given to new sessions
+    pub fn default_timeout(&self) -> Duration {
+        *self
+            .default_timeout
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Change the timeout given to new sessions
+    ///
+    /// Sessions that already exist keep the timeout they were created with.
+    pub fn set_default_timeout(&self, timeout: Duration) {
+        *self
+            .default_timeout
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = timeout;
+    }
+
     /// Create a new session for a user
     pub async fn create_session(&self, user_id: UserId) -> SessionToken {
-        let session = Session::new(user_id, self.default_timeout);
+        let session = Session::new(user_id, self.default_timeout());
         let token = session.token().clone();
 
         let mut sessions = self.sessions.write().await;
@@ -480,7 +498,7 @@ impl Clone for SessionManager {
     fn clone(&self) -> Self {
         Self {
             sessions: Arc::clone(&self.sessions),
-            default_timeout: self.default_timeout,
+            default_timeout: Arc::clone(&self.default_timeout),
         }
     }
 }
@@ -539,6 +557,28 @@ impl AuthService {
         }
     }
 
+    /// Enable rate limiting of login attempts
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use impulse_auth::{AuthService, rate_limit::RateLimiter};
+    /// use std::time::Duration;
+    ///
+    /// let auth = AuthService::new(Duration::from_secs(1800))
+    ///     .with_rate_limiter(RateLimiter::new(10, Duration::from_secs(60)));
+    /// ```
+    pub fn with_rate_limiter(mut self, rate_limiter: rate_limit::RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Enable account lockout after repeated login failures
+    pub fn with_lockout(mut self, lockout: lockout::AccountLockout) -> Self {
+        self.lockout = Some(lockout);
+        self
+    }
+
     /// Hash a password for storage
     ///
     /// # Errors
@@ -548,6 +588,16 @@ impl AuthService {
         self.hasher.hash_password(password)
     }
 
+    /// Timeout given to new sessions
+    pub fn session_timeout(&self) -> Duration {
+        self.sessions.default_timeout()
+    }
+
+    /// Change the timeout given to sessions created from now on
+    pub fn set_session_timeout(&self, timeout: Duration) {
+        self.sessions.set_default_timeout(timeout);
+    }
+
     /// Authenticate a user and create a session
     ///
     /// Checks rate limiting and account lockout before attempting authentication.
@@ -635,6 +685,21 @@ impl AuthService {
         }
     }
 
+    /// Turn away a login for an account that does not exist or has no password
+    ///
+    /// The password is still checked against a throwaway hash, so a rejected
+    /// login takes as long whether or not the username exists. Always returns
+    /// `AuthError::InvalidCredentials`.
+    pub fn reject_unknown(&self, password: &str) -> AuthError {
+        static DUMMY_HASH: OnceLock<Option<String>> = OnceLock::new();
+
+        let dummy = DUMMY_HASH.get_or_init(|| self.hasher.hash_password("impulse-dummy").ok());
+        if let Some(hash) = dummy {
+            let _ = self.hasher.verify_password(password, hash);
+        }
+        AuthError::InvalidCredentials
+    }
+
     /// Authenticate a user with username (convenience method)
     ///
     /// Looks up user and calls login. Includes rate limiting and account lockout.
@@ -984,6 +1049,16 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_auth_service_reject_unknown() {
+        let auth = AuthService::new(Duration::from_secs(3600));
+
+        assert!(matches!(
+            auth.reject_unknown("any_password"),
+            AuthError::InvalidCredentials
+        ));
+    }
+
     #[tokio::test]
     async fn test_auth_service_logout() {
         let auth = AuthService::new(Duration::from_secs(3600));
@@ -1104,6 +1179,22 @@ mod tests {
         assert!(info.expires_at > chrono::Utc::now());
     }
 
+    #[tokio::test]
+    async fn test_session_manager_set_default_timeout() {
+        let manager = SessionManager::new(Duration::from_secs(3600));
+        let old_token = manager.create_session(UserId::new()).await;
+
+        manager.clone().set_default_timeout(Duration::from_secs(60));
+        assert_eq!(manager.default_timeout(), Duration::from_secs(60));
+
+        // Only sessions created afterwards get the new timeout
+        let new_token = manager.create_session(UserId::new()).await;
+        let new_session = manager.get_session(&new_token).await.unwrap();
+        assert!(new_session.remaining_time().unwrap() <= Duration::from_secs(60));
+        let old_session = manager.get_session(&old_token).await.unwrap();
+        assert!(old_session.remaining_time().unwrap() > Duration::from_secs(60));
+    }
+
     #[tokio::test]
     async fn test_session_manager_active_sessions_for_user() {
         let manager = SessionManager::new(Duration::from_secs(3600));
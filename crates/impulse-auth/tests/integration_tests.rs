@@ -133,6 +133,23 @@ async fn test_account_lockout_flow() {
     assert!(result.is_ok(), "Login should succeed after lockout expires");
 }
 
+#[tokio::test]
+async fn test_lockout_without_rate_limiting() {
+    let auth = AuthService::new(Duration::from_secs(1800))
+        .with_lockout(AccountLockout::new(2, Duration::from_secs(60)));
+
+    let user = User::new("testuser").unwrap();
+    let hash = auth.hash_password("CorrectPassword123!").unwrap();
+
+    for _ in 0..2 {
+        assert!(auth.login(&user, "WrongPassword", &hash).await.is_err());
+    }
+
+    // Even the correct password is refused while locked
+    let result = auth.login(&user, "CorrectPassword123!", &hash).await;
+    assert!(matches!(result, Err(e) if e.to_string().contains("locked")));
+}
+
 #[tokio::test]
 async fn test_registration_flow() {
     // Setup auth service
@@ -6,7 +6,6 @@ use notify::{
     Watcher as NotifyWatcher,
 };
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio::time;
@@ -128,20 +127,34 @@ impl ConfigWatcher {
     /// }
     /// ```
     pub async fn watch(self) {
-        let path = Arc::new(self.path);
+        let path = self.path;
         let tx = self.tx;
         let debounce_duration = self.debounce_duration;
 
         // Create channel for notify events
         let (notify_tx, mut notify_rx) = mpsc::channel(100);
 
+        // Watch the containing directory rather than the file itself, so
+        // editors that save by writing a new file and renaming it over the
+        // old one keep triggering reloads
+        let file_name = path.file_name().map(|name| name.to_os_string());
+        let watch_dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
         // Set up file watcher
-        let notify_path = Arc::clone(&path);
         let mut watcher = match RecommendedWatcher::new(
             move |res: notify::Result<Event>| {
                 if let Ok(event) = res {
-                    // Only care about modify events
-                    if matches!(event.kind, EventKind::Modify(_)) {
+                    // Only care about the config file being modified or replaced
+                    let is_change =
+                        matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_));
+                    let is_config = event
+                        .paths
+                        .iter()
+                        .any(|p| p.file_name().map(|name| name.to_os_string()) == file_name);
+                    if is_change && is_config {
                         let _ = notify_tx.blocking_send(event);
                     }
                 }
@@ -155,9 +168,9 @@ impl ConfigWatcher {
             }
         };
 
-        // Start watching the file
-        if let Err(e) = watcher.watch(&notify_path, RecursiveMode::NonRecursive) {
-            eprintln!("Failed to watch file {}: {}", notify_path.display(), e);
+        // Start watching the directory
+        if let Err(e) = watcher.watch(&watch_dir, RecursiveMode::NonRecursive) {
+            eprintln!("Failed to watch directory {}: {}", watch_dir.display(), e);
             return;
         }
 
@@ -276,4 +289,31 @@ mod tests {
         let result2 = timeout(Duration::from_millis(200), rx.recv()).await;
         assert!(result2.is_err()); // Timeout = good, means only one notification
     }
+
+    #[tokio::test]
+    async fn test_watch_survives_file_replacement() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        std::fs::write(&path, "original").unwrap();
+
+        let (watcher, mut rx) =
+            ConfigWatcher::with_debounce(&path, Duration::from_millis(100)).unwrap();
+
+        tokio::spawn(async move {
+            watcher.watch().await;
+        });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Editors often save by renaming a new file over the old one
+        for content in ["first save", "second save"] {
+            let staged = temp_dir.path().join("config.toml.new");
+            std::fs::write(&staged, content).unwrap();
+            std::fs::rename(&staged, &path).unwrap();
+
+            let result = timeout(Duration::from_secs(2), rx.recv()).await;
+            assert!(result.is_ok());
+            assert!(result.unwrap().is_some());
+        }
+    }
 }
@@ -0,0 +1,756 @@
+//! BBS orchestration: listeners, sessions and shutdown
+
+use crate::connection::CallerConnection;
+use crate::error::{CoreError, Result};
+use crate::handler::{Caller, SessionHandler};
+use async_trait::async_trait;
+use chrono::Utc;
+use impulse_admin::system::SystemMessage;
+use impulse_session::{Connection, SessionId, SessionManager};
+use impulse_ssh::{PasswordAuthenticator, SshServer, SshServerConfig};
+use impulse_telnet::TelnetServer;
+use impulse_types::config::{BbsConfig, Protocol};
+use impulse_web::WebServer;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, watch};
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{error, info, warn};
+
+/// Number of undelivered notices kept per caller
+const NOTICE_QUEUE_SIZE: usize = 16;
+
+/// Time sessions get to log off after the shutdown notice
+const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_secs(30);
+
+/// Notice shown to callers when the BBS shuts down
+const DEFAULT_SHUTDOWN_REASON: &str = "The BBS is shutting down. Please call again later.";
+
+/// The running BBS: listeners, sessions and shutdown
+///
+/// `BbsCore` binds every listener in the configuration, registers each
+/// caller with the [`SessionManager`] and hands them to a
+/// [`SessionHandler`], which provides the BBS itself. [`stop`](Self::stop)
+/// shuts the whole system down gracefully.
+///
+/// # Example
+///
+/// ```no_run
+/// use impulse_core::{BbsCore, Caller, SessionHandler};
+/// use impulse_session::{SessionConfig, SessionManager};
+/// use impulse_types::config::BbsConfig;
+/// use std::net::SocketAddr;
+/// use std::sync::Arc;
+///
+/// struct Hello;
+///
+/// #[async_trait::async_trait]
+/// impl SessionHandler for Hello {
+///     type Identity = ();
+///     type Error = impulse_session::ConnectionError;
+///
+///     async fn authenticate(&self, _: &str, _: &str, _: SocketAddr) -> Option<()> {
+///         None
+///     }
+///
+///     async fn handle_session(&self, mut caller: Caller<()>) -> Result<(), Self::Error> {
+///         caller.connection.send_text("Hello!\r\n").await
+///     }
+/// }
+///
+/// # async fn example() -> impulse_core::Result<()> {
+/// let sessions = Arc::new(SessionManager::new(SessionConfig::default()));
+/// let bbs = BbsCore::new(BbsConfig::default(), Arc::new(Hello), sessions);
+/// bbs.start().await?;
+/// bbs.stop().await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct BbsCore<H: SessionHandler> {
+    /// Configuration the listeners are bound from
+    config: BbsConfig,
+    /// State shared with listener and session tasks
+    shared: Arc<Shared<H>>,
+    /// Listeners and background tasks, present while running
+    running: Mutex<Option<Running>>,
+    /// Time sessions get to end after the shutdown notice
+    shutdown_grace: Duration,
+    /// Notice shown to callers on shutdown
+    shutdown_reason: String,
+    /// Set once [`stop`](Self::stop) has been called
+    stopping: AtomicBool,
+    /// Set once the BBS has stopped
+    stopped: watch::Sender<bool>,
+}
+
+/// State shared with listener and session tasks
+struct Shared<H: SessionHandler> {
+    handler: Arc<H>,
+    session_manager: Arc<SessionManager>,
+    /// System notices delivered to every caller
+    notices: broadcast::Sender<SystemMessage>,
+    /// Running session tasks
+    sessions: Mutex<JoinSet<()>>,
+    /// Set once the BBS stops admitting callers
+    closing: AtomicBool,
+}
+
+/// Resources held while the BBS is running
+struct Running {
+    listeners: Vec<Listener>,
+    cleanup_task: JoinHandle<()>,
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        self.cleanup_task.abort();
+    }
+}
+
+/// A bound listener and the task accepting its callers
+struct Listener {
+    protocol: Protocol,
+    local_addr: SocketAddr,
+    accept_task: JoinHandle<()>,
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        // Dropping the server inside the task closes the listening socket
+        self.accept_task.abort();
+    }
+}
+
+impl<H: SessionHandler> BbsCore<H> {
+    /// Create a BBS serving callers with `handler`
+    ///
+    /// Nothing is bound until [`start`](Self::start) is called.
+    pub fn new(config: BbsConfig, handler: Arc<H>, session_manager: Arc<SessionManager>) -> Self {
+        Self {
+            config,
+            shared: Arc::new(Shared {
+                handler,
+                session_manager,
+                notices: broadcast::channel(NOTICE_QUEUE_SIZE).0,
+                sessions: Mutex::new(JoinSet::new()),
+                closing: AtomicBool::new(false),
+            }),
+            running: Mutex::new(None),
+            shutdown_grace: DEFAULT_SHUTDOWN_GRACE,
+            shutdown_reason: DEFAULT_SHUTDOWN_REASON.to_string(),
+            stopping: AtomicBool::new(false),
+            stopped: watch::Sender::new(false),
+        }
+    }
+
+    /// Set how long sessions get to log off after the shutdown notice
+    pub fn with_shutdown_grace(mut self, grace: Duration) -> Self {
+        self.shutdown_grace = grace;
+        self
+    }
+
+    /// Set the notice shown to callers on shutdown
+    pub fn with_shutdown_reason(mut self, reason: impl Into<String>) -> Self {
+        self.shutdown_reason = reason.into();
+        self
+    }
+
+    /// Get the session handler
+    pub fn handler(&self) -> &Arc<H> {
+        &self.shared.handler
+    }
+
+    /// Get the session manager
+    pub fn session_manager(&self) -> &Arc<SessionManager> {
+        &self.shared.session_manager
+    }
+
+    /// Bind every configured listener and start admitting callers
+    ///
+    /// TLS and raw TCP listeners are not supported yet and are skipped with
+    /// a warning.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a listener cannot be bound, if no usable listener is
+    /// configured, or if the BBS is already running or shutting down.
+    pub async fn start(&self) -> Result<()> {
+        if self.shared.closing.load(Ordering::SeqCst) {
+            return Err(CoreError::ShuttingDown);
+        }
+        if self.running.lock().unwrap().is_some() {
+            return Err(CoreError::AlreadyRunning);
+        }
+
+        let mut listeners = Vec::new();
+        for server in &self.config.servers {
+            let address = format!("{}:{}", server.bind_address, server.port);
+
+            if server.enable_tls {
+                warn!("TLS is not supported yet; skipping listener on {}", address);
+                continue;
+            }
+
+            let listener = match server.protocol {
+                Protocol::Telnet => self.bind_telnet(&address).await?,
+                Protocol::Ssh => self.bind_ssh(&address).await?,
+                Protocol::Web => self.bind_web(&address).await?,
+                Protocol::Raw => {
+                    warn!(
+                        "Raw listeners are not supported yet; skipping listener on {}",
+                        address
+                    );
+                    continue;
+                }
+            };
+            info!(
+                "{:?} listener ready on {}",
+                listener.protocol, listener.local_addr
+            );
+            listeners.push(listener);
+        }
+
+        if listeners.is_empty() {
+            return Err(CoreError::NoListeners);
+        }
+
+        let started = Running {
+            listeners,
+            cleanup_task: self.shared.session_manager.spawn_cleanup_task(),
+        };
+
+        let mut running = self.running.lock().unwrap();
+        if running.is_some() {
+            return Err(CoreError::AlreadyRunning);
+        }
+        *running = Some(started);
+        Ok(())
+    }
+
+    /// Get the protocol and local address of every bound listener
+    ///
+    /// Useful when listeners are configured on port 0.
+    pub fn listeners(&self) -> Vec<(Protocol, SocketAddr)> {
+        self.running
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|running| {
+                running
+                    .listeners
+                    .iter()
+                    .map(|listener| (listener.protocol, listener.local_addr))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Admit a caller that arrived outside the configured listeners
+    ///
+    /// Registers a session and serves it with the handler, exactly like a
+    /// caller accepted by a listener. Returns the new session's ID.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the BBS is shutting down or the session manager refuses the
+    /// caller; the caller is told and disconnected.
+    pub async fn admit(
+        &self,
+        connection: Box<dyn Connection>,
+        identity: Option<H::Identity>,
+    ) -> Result<SessionId> {
+        self.shared.admit(connection, identity).await
+    }
+
+    /// Show a message to every caller at their next prompt
+    ///
+    /// Returns the number of callers the message was queued for.
+    pub fn broadcast(&self, message: impl Into<String>) -> usize {
+        self.shared
+            .notices
+            .send(SystemMessage::Broadcast {
+                from_admin: 0,
+                message: message.into(),
+                timestamp: Utc::now(),
+            })
+            .unwrap_or(0)
+    }
+
+    /// Get a sender for the system notices delivered to callers
+    ///
+    /// Lets other components (e.g. the admin interface) reach every caller
+    /// through the same channel as [`broadcast`](Self::broadcast).
+    pub fn notice_sender(&self) -> broadcast::Sender<SystemMessage> {
+        self.shared.notices.clone()
+    }
+
+    /// Stop accepting callers while letting current sessions carry on
+    ///
+    /// Closes every listener; callers arriving afterwards, including through
+    /// [`admit`](Self::admit), are turned away. Use this to warn callers
+    /// before calling [`stop`](Self::stop).
+    pub fn stop_accepting(&self) {
+        self.shared.closing.store(true, Ordering::SeqCst);
+        let running = self.running.lock().unwrap().take();
+        if running.is_some() {
+            drop(running);
+            info!("Stopped accepting callers");
+        }
+    }
+
+    /// Shut the BBS down gracefully
+    ///
+    /// Stops accepting callers and shows every caller the shutdown notice,
+    /// which ends their input so sessions log off through their normal
+    /// path. Sessions still running after the grace period are aborted and
+    /// any sessions left in the session manager are terminated. Finally the
+    /// handler flushes its state.
+    ///
+    /// Calling `stop` again after the BBS has stopped does nothing.
+    pub async fn stop(&self) {
+        if self.stopping.swap(true, Ordering::SeqCst) {
+            self.stopped().await;
+            return;
+        }
+
+        self.stop_accepting();
+
+        // Warn callers; this also ends their input
+        self.shared
+            .notices
+            .send(SystemMessage::Disconnect {
+                reason: self.shutdown_reason.clone(),
+            })
+            .ok();
+
+        let mut sessions = std::mem::take(&mut *self.shared.sessions.lock().unwrap());
+        if !sessions.is_empty() {
+            info!(
+                sessions = sessions.len(),
+                "Waiting up to {:?} for callers to log off", self.shutdown_grace
+            );
+        }
+        let drained = tokio::time::timeout(self.shutdown_grace, async {
+            while sessions.join_next().await.is_some() {}
+        })
+        .await;
+        if drained.is_err() {
+            warn!(
+                sessions = sessions.len(),
+                "Sessions still running after the grace period; aborting them"
+            );
+            sessions.shutdown().await;
+        }
+
+        // Clean up sessions whose tasks were aborted
+        let session_manager = &self.shared.session_manager;
+        for session in session_manager.list_all_sessions().await {
+            session_manager.terminate_session(session.id()).await.ok();
+        }
+
+        self.shared.handler.shutdown().await;
+        info!("BBS stopped");
+        self.stopped.send_replace(true);
+    }
+
+    /// Wait until the BBS has stopped
+    pub async fn stopped(&self) {
+        let mut stopped = self.stopped.subscribe();
+        stopped.wait_for(|stopped| *stopped).await.ok();
+    }
+
+    async fn bind_telnet(&self, address: &str) -> Result<Listener> {
+        let server = TelnetServer::bind(address)
+            .await
+            .map_err(|e| bind_error(Protocol::Telnet, address, e))?;
+        let local_addr = server.local_addr();
+        let accept_task = tokio::spawn(accept_telnet(server, self.shared.clone()));
+        Ok(Listener {
+            protocol: Protocol::Telnet,
+            local_addr,
+            accept_task,
+        })
+    }
+
+    async fn bind_ssh(&self, address: &str) -> Result<Listener> {
+        let ssh_config =
+            SshServerConfig::new(self.config.paths.data_dir.join("ssh_host_ed25519_key"))
+                .with_max_auth_attempts(usize::from(self.config.limits.max_password_attempts));
+        let authenticator = Arc::new(HandlerAuthenticator(self.shared.handler.clone()));
+        let server = SshServer::bind(address, ssh_config, authenticator)
+            .await
+            .map_err(|e| bind_error(Protocol::Ssh, address, e))?;
+        let local_addr = server.local_addr();
+        let accept_task = tokio::spawn(accept_ssh(server, self.shared.clone()));
+        Ok(Listener {
+            protocol: Protocol::Ssh,
+            local_addr,
+            accept_task,
+        })
+    }
+
+    async fn bind_web(&self, address: &str) -> Result<Listener> {
+        let server = WebServer::bind(address)
+            .await
+            .map_err(|e| bind_error(Protocol::Web, address, e))?;
+        let local_addr = server.local_addr();
+        let accept_task = tokio::spawn(accept_web(server, self.shared.clone()));
+        Ok(Listener {
+            protocol: Protocol::Web,
+            local_addr,
+            accept_task,
+        })
+    }
+}
+
+impl<H: SessionHandler> Shared<H> {
+    /// Register a session for the caller and serve it in a new task
+    async fn admit(
+        self: &Arc<Self>,
+        mut connection: Box<dyn Connection>,
+        identity: Option<H::Identity>,
+    ) -> Result<SessionId> {
+        let peer_addr = connection.remote_addr();
+        info!("New connection from {}", peer_addr);
+
+        let created = if self.closing.load(Ordering::SeqCst) {
+            Err(CoreError::ShuttingDown)
+        } else {
+            self.session_manager
+                .create_session(peer_addr.clone())
+                .await
+                .map_err(CoreError::from)
+        };
+        let session_id = match created {
+            Ok(id) => id,
+            Err(e) => {
+                warn!("Turning away {}: {}", peer_addr, e);
+                let notice = match e {
+                    CoreError::ShuttingDown => "The BBS is shutting down. Please try again later.",
+                    _ => "Server is full. Please try again later.",
+                };
+                connection.send_text(&format!("{}\r\n", notice)).await.ok();
+                connection.close().await.ok();
+                if let Some(identity) = identity {
+                    self.handler.reject(identity).await;
+                }
+                return Err(e);
+            }
+        };
+        info!(session_id = %session_id, "Session created for {}", peer_addr);
+
+        let caller = Caller {
+            session_id,
+            connection: Box::new(CallerConnection::new(connection, self.notices.subscribe())),
+            identity,
+        };
+        let shared = self.clone();
+
+        let mut sessions = self.sessions.lock().unwrap();
+        // Reap finished sessions so the set only holds running ones
+        while sessions.try_join_next().is_some() {}
+        sessions.spawn(async move {
+            if let Err(e) = shared.handler.handle_session(caller).await {
+                error!(session_id = %session_id, "Connection error: {}", e);
+            }
+            shared
+                .session_manager
+                .terminate_session(session_id)
+                .await
+                .ok();
+        });
+
+        Ok(session_id)
+    }
+}
+
+/// Lets the session handler authenticate SSH logins
+struct HandlerAuthenticator<H>(Arc<H>);
+
+#[async_trait]
+impl<H: SessionHandler> PasswordAuthenticator for HandlerAuthenticator<H> {
+    type Identity = H::Identity;
+
+    async fn authenticate(
+        &self,
+        username: &str,
+        password: &str,
+        peer_addr: SocketAddr,
+    ) -> Option<Self::Identity> {
+        self.0.authenticate(username, password, peer_addr).await
+    }
+}
+
+/// Accept telnet callers and negotiate options before admitting them
+async fn accept_telnet<H: SessionHandler>(server: TelnetServer, shared: Arc<Shared<H>>) {
+    loop {
+        match server.accept().await {
+            Ok(mut connection) => {
+                // Initialize telnet session (negotiate options)
+                if let Err(e) = connection.initialize().await {
+                    warn!(
+                        "Telnet negotiation with {} failed: {}",
+                        connection.peer_addr(),
+                        e
+                    );
+                    continue;
+                }
+                shared.admit(Box::new(connection), None).await.ok();
+            }
+            Err(e) => error!("Failed to accept telnet connection: {}", e),
+        }
+    }
+}
+
+/// Accept SSH callers, which arrive already authenticated
+async fn accept_ssh<H: SessionHandler>(server: SshServer<H::Identity>, shared: Arc<Shared<H>>) {
+    loop {
+        match server.accept().await {
+            Ok((connection, identity)) => {
+                shared
+                    .admit(Box::new(connection), Some(identity))
+                    .await
+                    .ok();
+            }
+            Err(e) => {
+                error!("SSH server stopped accepting connections: {}", e);
+                return;
+            }
+        }
+    }
+}
+
+/// Accept browser callers from the web terminal
+async fn accept_web<H: SessionHandler>(server: WebServer, shared: Arc<Shared<H>>) {
+    loop {
+        match server.accept().await {
+            Ok(connection) => {
+                shared.admit(Box::new(connection), None).await.ok();
+            }
+            Err(e) => {
+                error!("Web server stopped accepting connections: {}", e);
+                return;
+            }
+        }
+    }
+}
+
+fn bind_error(
+    protocol: Protocol,
+    address: &str,
+    source: impl std::error::Error + Send + Sync + 'static,
+) -> CoreError {
+    CoreError::Bind {
+        protocol,
+        address: address.to_string(),
+        source: Box::new(source),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use impulse_session::{ConnectionError, MemoryConnection, SessionConfig};
+    use impulse_types::config::ServerConfig;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpStream;
+
+    /// Records every line callers type; a stuck handler never returns
+    #[derive(Default)]
+    struct TestHandler {
+        lines: Mutex<Vec<String>>,
+        stuck: bool,
+        shutdowns: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl SessionHandler for TestHandler {
+        type Identity = ();
+        type Error = ConnectionError;
+
+        async fn authenticate(&self, _: &str, _: &str, _: SocketAddr) -> Option<()> {
+            None
+        }
+
+        async fn handle_session(
+            &self,
+            mut caller: Caller<()>,
+        ) -> std::result::Result<(), ConnectionError> {
+            if self.stuck {
+                std::future::pending::<()>().await;
+            }
+            caller.connection.send_text("Welcome\r\n").await?;
+            loop {
+                match caller.connection.read_line().await {
+                    Ok(line) => self.lines.lock().unwrap().push(line),
+                    Err(ConnectionError::Closed) => return Ok(()),
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        async fn shutdown(&self) {
+            self.shutdowns.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn config(protocols: &[Protocol]) -> BbsConfig {
+        BbsConfig {
+            servers: protocols
+                .iter()
+                .map(|&protocol| ServerConfig {
+                    bind_address: "127.0.0.1".to_string(),
+                    port: 0,
+                    protocol,
+                    enable_tls: false,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn core(protocols: &[Protocol], handler: TestHandler) -> BbsCore<TestHandler> {
+        let sessions = Arc::new(SessionManager::new(SessionConfig::default()));
+        BbsCore::new(config(protocols), Arc::new(handler), sessions)
+            .with_shutdown_grace(Duration::from_secs(5))
+    }
+
+    async fn wait_for_sessions(core: &BbsCore<TestHandler>, count: usize) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while core.session_manager().active_session_count().await != count {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("session count not reached");
+    }
+
+    async fn read_until(stream: &mut TcpStream, needle: &str) -> String {
+        let mut received = Vec::new();
+        let mut buf = [0u8; 1024];
+        while !String::from_utf8_lossy(&received).contains(needle) {
+            let n = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf))
+                .await
+                .expect("timed out waiting for output")
+                .unwrap();
+            if n == 0 {
+                break;
+            }
+            received.extend_from_slice(&buf[..n]);
+        }
+        String::from_utf8_lossy(&received).into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_admit_runs_handler() {
+        let core = core(&[], TestHandler::default());
+
+        let conn = MemoryConnection::new("hello\rworld\r");
+        core.admit(Box::new(conn), None).await.unwrap();
+
+        wait_for_sessions(&core, 0).await;
+        assert_eq!(*core.handler().lines.lock().unwrap(), ["hello", "world"]);
+    }
+
+    #[tokio::test]
+    async fn test_start_requires_listener() {
+        let core = core(&[Protocol::Raw], TestHandler::default());
+        assert!(matches!(core.start().await, Err(CoreError::NoListeners)));
+    }
+
+    #[tokio::test]
+    async fn test_start_broadcast_and_stop() {
+        let core = core(&[Protocol::Telnet, Protocol::Web], TestHandler::default());
+        core.start().await.unwrap();
+        assert!(matches!(core.start().await, Err(CoreError::AlreadyRunning)));
+
+        let listeners = core.listeners();
+        assert_eq!(listeners.len(), 2);
+        let (_, telnet_addr) = listeners
+            .iter()
+            .find(|(protocol, _)| *protocol == Protocol::Telnet)
+            .copied()
+            .unwrap();
+
+        let mut client = TcpStream::connect(telnet_addr).await.unwrap();
+        read_until(&mut client, "Welcome").await;
+        wait_for_sessions(&core, 1).await;
+
+        // Callers at a prompt see broadcasts
+        assert_eq!(core.broadcast("Hello callers"), 1);
+        assert!(
+            read_until(&mut client, "Hello callers")
+                .await
+                .contains("***")
+        );
+
+        core.stop().await;
+        assert!(
+            read_until(&mut client, "shutting down")
+                .await
+                .contains("shutting down")
+        );
+
+        assert_eq!(core.session_manager().active_session_count().await, 0);
+        assert_eq!(core.handler().shutdowns.load(Ordering::SeqCst), 1);
+        assert!(core.listeners().is_empty());
+        assert!(matches!(core.start().await, Err(CoreError::ShuttingDown)));
+
+        // A second stop is a no-op
+        core.stop().await;
+        assert_eq!(core.handler().shutdowns.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stop_accepting_keeps_sessions() {
+        let core = core(&[Protocol::Telnet], TestHandler::default());
+        core.start().await.unwrap();
+        let (_, telnet_addr) = core.listeners()[0];
+
+        let mut client = TcpStream::connect(telnet_addr).await.unwrap();
+        read_until(&mut client, "Welcome").await;
+        wait_for_sessions(&core, 1).await;
+
+        core.stop_accepting();
+        assert!(core.listeners().is_empty());
+        let refused = core.admit(Box::new(MemoryConnection::new("")), None).await;
+        assert!(matches!(refused, Err(CoreError::ShuttingDown)));
+
+        // Callers already online still get notices until the BBS stops
+        assert_eq!(core.broadcast("Going down in 10 seconds"), 1);
+        read_until(&mut client, "10 seconds").await;
+        assert_eq!(core.session_manager().active_session_count().await, 1);
+
+        core.stop().await;
+        assert!(
+            read_until(&mut client, "shutting down")
+                .await
+                .contains("shutting down")
+        );
+        assert_eq!(core.handler().shutdowns.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stop_aborts_stuck_sessions() {
+        let handler = TestHandler {
+            stuck: true,
+            ..Default::default()
+        };
+        let core = core(&[], handler).with_shutdown_grace(Duration::from_millis(50));
+
+        core.admit(Box::new(MemoryConnection::new("")), None)
+            .await
+            .unwrap();
+        wait_for_sessions(&core, 1).await;
+
+        core.stop().await;
+        assert_eq!(core.session_manager().active_session_count().await, 0);
+
+        // Nobody is admitted once shutdown has begun
+        let refused = core.admit(Box::new(MemoryConnection::new("")), None).await;
+        assert!(matches!(refused, Err(CoreError::ShuttingDown)));
+    }
+}
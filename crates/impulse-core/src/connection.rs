@@ -0,0 +1,179 @@
+//! Caller connections with system notice delivery
+
+use async_trait::async_trait;
+use impulse_admin::system::SystemMessage;
+use impulse_session::{Connection, ConnectionError, ConnectionType};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Wraps a caller's connection so system notices reach them at any prompt
+///
+/// Broadcasts are written to the caller while a read is waiting for input,
+/// and the read carries on afterwards. A disconnect notice is shown and ends
+/// input: every further read fails with [`ConnectionError::Closed`], so the
+/// session unwinds through its normal logoff path.
+///
+/// Line and password input are built on [`read_char`](Connection::read_char)
+/// so a notice does not discard what the caller has typed so far. Raw
+/// [`recv`](Connection::recv) is passed through untouched, keeping file
+/// transfers and door I/O free of injected text.
+pub(crate) struct CallerConnection {
+    /// Transport connection
+    inner: Box<dyn Connection>,
+    /// System notices; `None` once the sender is gone
+    notices: Option<broadcast::Receiver<SystemMessage>>,
+    /// Set once a disconnect notice has been delivered
+    disconnected: bool,
+}
+
+impl CallerConnection {
+    /// Wrap `inner`, delivering notices from `notices`
+    pub(crate) fn new(
+        inner: Box<dyn Connection>,
+        notices: broadcast::Receiver<SystemMessage>,
+    ) -> Self {
+        Self {
+            inner,
+            notices: Some(notices),
+            disconnected: false,
+        }
+    }
+
+    /// Write a notice to the caller
+    async fn deliver(&mut self, notice: SystemMessage) -> Result<(), ConnectionError> {
+        match notice {
+            SystemMessage::Broadcast { message, .. } => {
+                let text = format!("\r\n\x07*** {} ***\r\n", message);
+                self.inner.send_text(&text).await
+            }
+            SystemMessage::Disconnect { reason } => {
+                self.disconnected = true;
+                let text = format!("\r\n\r\n*** {} ***\r\n", reason);
+                self.inner.send_text(&text).await
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Connection for CallerConnection {
+    fn connection_type(&self) -> ConnectionType {
+        self.inner.connection_type()
+    }
+
+    fn remote_addr(&self) -> String {
+        self.inner.remote_addr()
+    }
+
+    async fn send_text(&mut self, data: &str) -> Result<(), ConnectionError> {
+        self.inner.send_text(data).await
+    }
+
+    async fn send_bytes(&mut self, data: &[u8]) -> Result<(), ConnectionError> {
+        self.inner.send_bytes(data).await
+    }
+
+    async fn recv(&mut self) -> Result<Option<Vec<u8>>, ConnectionError> {
+        self.inner.recv().await
+    }
+
+    async fn close(&mut self) -> Result<(), ConnectionError> {
+        self.inner.close().await
+    }
+
+    fn is_connected(&self) -> bool {
+        !self.disconnected && self.inner.is_connected()
+    }
+
+    async fn read_char(&mut self) -> Result<char, ConnectionError> {
+        loop {
+            if self.disconnected {
+                return Err(ConnectionError::Closed);
+            }
+
+            let Some(notices) = self.notices.as_mut() else {
+                return self.inner.read_char().await;
+            };
+
+            // Safe to abandon: read_char never loses input when cancelled
+            let notice = tokio::select! {
+                biased;
+                notice = notices.recv() => notice,
+                ch = self.inner.read_char() => return ch,
+            };
+
+            match notice {
+                Ok(notice) => self.deliver(notice).await?,
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => self.notices = None,
+            }
+        }
+    }
+
+    fn terminal_size(&self) -> (u16, u16) {
+        self.inner.terminal_size()
+    }
+
+    #[cfg(unix)]
+    fn socket(&self) -> Option<std::os::fd::BorrowedFd<'_>> {
+        self.inner.socket()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use impulse_session::MemoryConnection;
+
+    fn broadcast(message: &str) -> SystemMessage {
+        SystemMessage::Broadcast {
+            from_admin: 0,
+            message: message.to_string(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_keeps_typed_input() {
+        let (sender, receiver) = broadcast::channel(4);
+        let mut conn = CallerConnection::new(Box::new(MemoryConnection::new("ab")), receiver);
+
+        assert_eq!(conn.read_char().await.unwrap(), 'a');
+        sender.send(broadcast("Hello callers")).unwrap();
+        assert_eq!(conn.read_char().await.unwrap(), 'b');
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_ends_input() {
+        let (sender, receiver) = broadcast::channel(4);
+        let mut conn = CallerConnection::new(Box::new(MemoryConnection::new("abc")), receiver);
+
+        sender
+            .send(SystemMessage::Disconnect {
+                reason: "Going down".to_string(),
+            })
+            .unwrap();
+        assert!(matches!(
+            conn.read_char().await,
+            Err(ConnectionError::Closed)
+        ));
+        assert!(matches!(
+            conn.read_line().await,
+            Err(ConnectionError::Closed)
+        ));
+        assert!(!conn.is_connected());
+
+        // Raw reads are left alone
+        assert_eq!(conn.recv().await.unwrap().unwrap(), b"abc");
+    }
+
+    #[tokio::test]
+    async fn test_closed_notice_channel() {
+        let (sender, receiver) = broadcast::channel(4);
+        let mut conn = CallerConnection::new(Box::new(MemoryConnection::new("hi\r")), receiver);
+        drop(sender);
+
+        assert_eq!(conn.read_line().await.unwrap(), "hi");
+    }
+}
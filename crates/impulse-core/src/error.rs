@@ -0,0 +1,40 @@
+//! Error types for the BBS core
+
+use impulse_session::SessionError;
+use impulse_types::config::Protocol;
+use thiserror::Error;
+
+/// Result type alias for BBS core operations
+pub type Result<T> = std::result::Result<T, CoreError>;
+
+/// Errors that can occur while running the BBS
+#[derive(Error, Debug)]
+pub enum CoreError {
+    /// A configured listener could not be bound
+    #[error("Failed to bind {protocol:?} listener to {address}: {source}")]
+    Bind {
+        /// Protocol of the listener
+        protocol: Protocol,
+        /// Address the listener was configured for
+        address: String,
+        /// Underlying transport error
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// The configuration has no listener that can be started
+    #[error("No usable listeners configured")]
+    NoListeners,
+
+    /// [`BbsCore::start`](crate::BbsCore::start) was called twice
+    #[error("BBS is already running")]
+    AlreadyRunning,
+
+    /// The BBS is shutting down and no longer admits callers
+    #[error("BBS is shutting down")]
+    ShuttingDown,
+
+    /// The session manager refused the caller (e.g. the BBS is full)
+    #[error("Session error: {0}")]
+    Session(#[from] SessionError),
+}
@@ -0,0 +1,59 @@
+//! Extension point for the code that serves each caller
+
+use async_trait::async_trait;
+use impulse_session::{Connection, SessionId};
+use std::fmt::Display;
+use std::net::SocketAddr;
+
+/// A caller admitted to the BBS
+pub struct Caller<I> {
+    /// Session registered for the caller in the `SessionManager`
+    pub session_id: SessionId,
+    /// Connection to the caller's terminal
+    ///
+    /// System notices are written to the caller while they sit at a prompt,
+    /// and input ends once the BBS shuts down.
+    pub connection: Box<dyn Connection>,
+    /// Identity established by the transport, if it authenticates callers
+    ///
+    /// Set for SSH callers, who log in during the handshake.
+    pub identity: Option<I>,
+}
+
+/// Serves callers admitted by a [`BbsCore`](crate::BbsCore)
+///
+/// The core owns the listeners and sessions; the handler provides the BBS
+/// itself (login, menus, message and file areas) and owns the state behind
+/// it.
+#[async_trait]
+pub trait SessionHandler: Send + Sync + 'static {
+    /// Identity produced by an SSH login and handed over with the caller
+    type Identity: Send + 'static;
+
+    /// Error returned when a session ends abnormally
+    type Error: Display + Send + 'static;
+
+    /// Check an SSH username/password pair
+    ///
+    /// Returning `None` rejects the login attempt.
+    async fn authenticate(
+        &self,
+        username: &str,
+        password: &str,
+        peer_addr: SocketAddr,
+    ) -> Option<Self::Identity>;
+
+    /// Serve a caller until they log off or hang up
+    ///
+    /// The core terminates the caller's session once this returns.
+    async fn handle_session(&self, caller: Caller<Self::Identity>) -> Result<(), Self::Error>;
+
+    /// Release the identity of an authenticated caller that was turned away
+    async fn reject(&self, _identity: Self::Identity) {}
+
+    /// Clean up once every session has ended or been cut off
+    ///
+    /// Sessions aborted at the end of the shutdown grace period never ran
+    /// their own cleanup, so anything they held is released here.
+    async fn shutdown(&self) {}
+}
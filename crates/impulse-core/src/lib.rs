@@ -1,29 +1,64 @@
 //! Core BBS logic for Impulse 7.1
+//!
+//! This crate runs the BBS as a whole: it binds the configured telnet, SSH
+//! and web listeners, registers every caller with the session manager and
+//! hands them to a [`SessionHandler`] that provides the BBS itself. The same
+//! core runs the `impulse-server` binary and lets tests and embedding tools
+//! start and stop a complete BBS in-process.
+//!
+//! # Features
+//!
+//! - One [`BbsCore`] owning listeners, sessions and shutdown
+//! - SSH logins checked by the session handler during the handshake
+//! - System notices shown to callers at any prompt
+//! - Graceful shutdown: stop accepting, warn callers, drain sessions, flush
+//!
+//! # Example
+//!
+//! ```no_run
+//! use impulse_core::{BbsCore, Caller, SessionHandler};
+//! use impulse_session::{Connection, ConnectionError, SessionConfig, SessionManager};
+//! use impulse_types::config::BbsConfig;
+//! use std::net::SocketAddr;
+//! use std::sync::Arc;
+//!
+//! struct Echo;
+//!
+//! #[async_trait::async_trait]
+//! impl SessionHandler for Echo {
+//!     type Identity = ();
+//!     type Error = ConnectionError;
+//!
+//!     async fn authenticate(&self, _: &str, _: &str, _: SocketAddr) -> Option<()> {
+//!         None
+//!     }
+//!
+//!     async fn handle_session(&self, mut caller: Caller<()>) -> Result<(), ConnectionError> {
+//!         let line = caller.connection.read_line().await?;
+//!         caller.connection.send_text(&format!("\r\nYou said: {}\r\n", line)).await
+//!     }
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> impulse_core::Result<()> {
+//!     let sessions = Arc::new(SessionManager::new(SessionConfig::default()));
+//!     let bbs = BbsCore::new(BbsConfig::default(), Arc::new(Echo), sessions);
+//!     bbs.start().await?;
+//!
+//!     tokio::signal::ctrl_c().await.ok();
+//!     bbs.stop().await;
+//!     Ok(())
+//! }
+//! ```
 
 #![warn(missing_docs)]
 #![warn(clippy::all)]
 
-use async_trait::async_trait;
-use impulse_types::session::Session;
-use std::error::Error;
+mod bbs;
+mod connection;
+mod error;
+mod handler;
 
-/// BBS core functionality trait
-#[async_trait]
-pub trait BbsCore: Send + Sync {
-    /// Start the BBS server
-    async fn start(&self) -> Result<(), Box<dyn Error>>;
-
-    /// Stop the BBS server
-    async fn stop(&self) -> Result<(), Box<dyn Error>>;
-
-    /// Handle a new session
-    async fn handle_session(&self, session: Session) -> Result<(), Box<dyn Error>>;
-}
-
-#[cfg(test)]
-mod tests {
-    #[tokio::test]
-    async fn test_placeholder() {
-        // Placeholder test - will be replaced with actual tests in later sprints
-    }
-}
+pub use bbs::BbsCore;
+pub use error::{CoreError, Result};
+pub use handler::{Caller, SessionHandler};
@@ -6,7 +6,7 @@
 use crate::dropfiles::DropfileType;
 use crate::error::{DoorError, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Door game configuration.
 ///
@@ -24,10 +24,16 @@ pub struct DoorConfig {
     pub directory: PathBuf,
     /// Type of dropfile to generate
     pub dropfile_type: DropfileType,
+    /// How the door is connected to the caller
+    #[serde(default)]
+    pub io_mode: DoorIoMode,
     /// Minimum security level required (0-255)
     pub min_security_level: u8,
     /// Maximum time limit in minutes (0 = unlimited)
     pub max_time_minutes: u16,
+    /// Only one node may run this door at a time
+    #[serde(default)]
+    pub single_node: bool,
     /// Use DOSBox to run this door
     pub use_dosbox: bool,
     /// DOSBox configuration (if use_dosbox is true)
@@ -43,8 +49,10 @@ impl DoorConfig {
             executable,
             directory,
             dropfile_type: DropfileType::DoorSys,
+            io_mode: DoorIoMode::Stdio,
             min_security_level: 0,
             max_time_minutes: 60,
+            single_node: false,
             use_dosbox: false,
             dosbox_config: None,
         }
@@ -72,6 +80,12 @@ impl DoorConfig {
             ));
         }
 
+        if self.use_dosbox && self.io_mode == DoorIoMode::Socket {
+            return Err(DoorError::InvalidConfig(
+                "Socket mode is not available for DOSBox doors".to_string(),
+            ));
+        }
+
         Ok(())
     }
 
@@ -83,6 +97,35 @@ impl DoorConfig {
         Ok(config)
     }
 
+    /// The DOS command line that starts this door inside DOSBox.
+    ///
+    /// Uses `dosbox_config.command` when set, with `{node}` replaced by the
+    /// node number and `{dropfile}` by the dropfile's path on drive D:.
+    /// Otherwise the executable is run from its place under the door
+    /// directory, which is drive C:.
+    pub fn dos_command(&self, node_id: u16) -> String {
+        if let Some(command) = self
+            .dosbox_config
+            .as_ref()
+            .and_then(|dosbox| dosbox.command.as_ref())
+        {
+            let dropfile = format!("D:\\{}", self.dropfile_type.filename(node_id));
+            return command
+                .replace("{node}", &node_id.to_string())
+                .replace("{dropfile}", &dropfile);
+        }
+
+        let relative = self
+            .executable
+            .strip_prefix(&self.directory)
+            .unwrap_or_else(|_| Path::new(self.executable.file_name().unwrap_or_default()));
+        let path: Vec<_> = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect();
+        format!("C:\\{}", path.join("\\"))
+    }
+
     /// Save door configuration to a TOML file.
     pub fn to_file(&self, path: &std::path::Path) -> Result<()> {
         let content = toml::to_string_pretty(self)?;
@@ -91,6 +134,19 @@ impl DoorConfig {
     }
 }
 
+/// How a door is connected to the caller.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DoorIoMode {
+    /// The door's standard input and output are relayed through the BBS
+    #[default]
+    Stdio,
+    /// The door is handed the caller's TCP socket and talks to it directly
+    ///
+    /// Meant for DOOR32.SYS doors; only telnet callers have a socket to
+    /// hand over.
+    Socket,
+}
+
 /// DOSBox configuration for running legacy DOS doors.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DosBoxConfig {
@@ -103,6 +159,9 @@ pub struct DosBoxConfig {
     /// Mount points as (drive_letter, path) pairs
     #[serde(default)]
     pub mount_points: Vec<(char, PathBuf)>,
+    /// DOS command line that starts the door (defaults to the executable)
+    #[serde(default)]
+    pub command: Option<String>,
 }
 
 impl DosBoxConfig {
@@ -113,6 +172,7 @@ impl DosBoxConfig {
             machine: "svga_s3".to_string(),
             memsize: 16,
             mount_points: Vec::new(),
+            command: None,
         }
     }
 
@@ -123,14 +183,7 @@ impl DosBoxConfig {
 
     /// Generate DOSBox configuration file content.
     pub fn generate_config(&self) -> String {
-        let mut config = String::new();
-
-        config.push_str("[cpu]\n");
-        config.push_str(&format!("cycles={}\n\n", self.cycles));
-
-        config.push_str("[dosbox]\n");
-        config.push_str(&format!("machine={}\n", self.machine));
-        config.push_str(&format!("memsize={}\n\n", self.memsize));
+        let mut config = self.machine_sections();
 
         config.push_str("[autoexec]\n");
         for (drive, path) in &self.mount_points {
@@ -143,6 +196,57 @@ impl DosBoxConfig {
 
         config
     }
+
+    /// Generate the DOSBox configuration that runs a door.
+    ///
+    /// COM1 is a nullmodem that dials `serial_port` on localhost, where the
+    /// BBS relays it to the caller. The door directory is mounted as C: and
+    /// the node directory holding the dropfile as D:, followed by any other
+    /// mount points. DOSBox exits once `command` returns.
+    pub fn generate_door_config(
+        &self,
+        door_dir: &Path,
+        node_dir: &Path,
+        command: &str,
+        serial_port: u16,
+    ) -> String {
+        let mut config = self.machine_sections();
+
+        config.push_str("[serial]\n");
+        config.push_str(&format!(
+            "serial1=nullmodem server:127.0.0.1 port:{} transparent:1\n\n",
+            serial_port
+        ));
+
+        config.push_str("[autoexec]\n");
+        config.push_str(&format!("mount C \"{}\"\n", door_dir.display()));
+        config.push_str(&format!("mount D \"{}\"\n", node_dir.display()));
+        for (drive, path) in &self.mount_points {
+            let drive = drive.to_ascii_uppercase();
+            if drive != 'C' && drive != 'D' {
+                config.push_str(&format!("mount {} \"{}\"\n", drive, path.display()));
+            }
+        }
+        config.push_str("C:\n");
+        config.push_str(&format!("{}\n", command));
+        config.push_str("exit\n");
+
+        config
+    }
+
+    /// The `[cpu]` and `[dosbox]` sections.
+    fn machine_sections(&self) -> String {
+        let mut config = String::new();
+
+        config.push_str("[cpu]\n");
+        config.push_str(&format!("cycles={}\n\n", self.cycles));
+
+        config.push_str("[dosbox]\n");
+        config.push_str(&format!("machine={}\n", self.machine));
+        config.push_str(&format!("memsize={}\n\n", self.memsize));
+
+        config
+    }
 }
 
 impl Default for DosBoxConfig {
@@ -163,6 +267,7 @@ impl Serialize for DropfileType {
             DropfileType::DorinfoDef => "DorinfoDef",
             DropfileType::ChainTxt => "ChainTxt",
             DropfileType::CallInfo => "CallInfo",
+            DropfileType::Door32Sys => "Door32Sys",
         };
         serializer.serialize_str(s)
     }
@@ -181,6 +286,7 @@ impl<'de> Deserialize<'de> for DropfileType {
             "DorinfoDef" => Ok(DropfileType::DorinfoDef),
             "ChainTxt" => Ok(DropfileType::ChainTxt),
             "CallInfo" => Ok(DropfileType::CallInfo),
+            "Door32Sys" => Ok(DropfileType::Door32Sys),
             _ => Err(serde::de::Error::custom(format!(
                 "Unknown dropfile type: {}",
                 s
@@ -231,8 +337,10 @@ mod tests {
             executable: test_executable(),
             directory: test_directory(),
             dropfile_type: DropfileType::DoorSys,
+            io_mode: DoorIoMode::Stdio,
             min_security_level: 0,
             max_time_minutes: 60,
+            single_node: false,
             use_dosbox: false,
             dosbox_config: None,
         };
@@ -276,6 +384,35 @@ mod tests {
         assert!(matches!(result, Err(DoorError::InvalidConfig(_))));
     }
 
+    #[test]
+    fn test_door_config_validate_dosbox_socket_mode() {
+        let mut config = DoorConfig::new("test".to_string(), test_executable(), test_directory());
+        config.use_dosbox = true;
+        config.dosbox_config = Some(DosBoxConfig::new());
+        config.io_mode = DoorIoMode::Socket;
+
+        let result = config.validate();
+        assert!(matches!(result, Err(DoorError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_door_config_io_mode_defaults_to_stdio() {
+        let toml_str = format!(
+            "name = \"test\"\ndescription = \"\"\nexecutable = {:?}\ndirectory = {:?}\n\
+             dropfile_type = \"Door32Sys\"\nmin_security_level = 0\n\
+             max_time_minutes = 60\nuse_dosbox = false\n",
+            test_executable(),
+            test_directory()
+        );
+        let config: DoorConfig = toml::from_str(&toml_str).unwrap();
+        assert_eq!(config.io_mode, DoorIoMode::Stdio);
+        assert_eq!(config.dropfile_type, DropfileType::Door32Sys);
+
+        let config: DoorConfig =
+            toml::from_str(&format!("{}io_mode = \"Socket\"\n", toml_str)).unwrap();
+        assert_eq!(config.io_mode, DoorIoMode::Socket);
+    }
+
     #[test]
     fn test_dosbox_config_new() {
         let config = DosBoxConfig::new();
@@ -320,6 +457,53 @@ mod tests {
         assert!(content.contains("mount C"));
     }
 
+    #[test]
+    fn test_dosbox_config_generate_door_config() {
+        let mut config = DosBoxConfig::new();
+        config.add_mount('c', PathBuf::from("/elsewhere"));
+        config.add_mount('E', PathBuf::from("/dos/utils"));
+
+        let content = config.generate_door_config(
+            Path::new("/doors/lord"),
+            Path::new("/bbs/nodes/node2"),
+            "C:\\START.BAT 2",
+            40123,
+        );
+
+        assert!(content.contains("serial1=nullmodem server:127.0.0.1 port:40123 transparent:1"));
+        assert!(content.contains("mount C \"/doors/lord\"\nmount D \"/bbs/nodes/node2\"\n"));
+        assert!(content.contains("mount E \"/dos/utils\""));
+        assert!(!content.contains("/elsewhere")); // C: is the door directory
+        assert!(content.ends_with("C:\nC:\\START.BAT 2\nexit\n"));
+    }
+
+    #[test]
+    fn test_dos_command_from_executable() {
+        let mut config = DoorConfig::new(
+            "lord".to_string(),
+            PathBuf::from("/doors/lord/bin/LORD.EXE"),
+            PathBuf::from("/doors/lord"),
+        );
+        assert_eq!(config.dos_command(1), "C:\\bin\\LORD.EXE");
+
+        config.executable = PathBuf::from("/other/TW2002.EXE");
+        assert_eq!(config.dos_command(1), "C:\\TW2002.EXE");
+    }
+
+    #[test]
+    fn test_dos_command_substitutes_node_and_dropfile() {
+        let mut config = DoorConfig::new(
+            "lord".to_string(),
+            PathBuf::from("/doors/lord/START.BAT"),
+            PathBuf::from("/doors/lord"),
+        );
+        let mut dosbox = DosBoxConfig::new();
+        dosbox.command = Some("START.BAT {node} {dropfile}".to_string());
+        config.dosbox_config = Some(dosbox);
+
+        assert_eq!(config.dos_command(3), "START.BAT 3 D:\\DOOR.SYS");
+    }
+
     #[test]
     fn test_dropfile_type_serialize() {
         // Test within a struct context (TOML requires key-value pairs)
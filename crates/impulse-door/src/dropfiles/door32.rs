@@ -0,0 +1,206 @@
+//! DOOR32.SYS dropfile format implementation.
+//!
+//! DOOR32.SYS is the 11-line dropfile used by 32-bit doors. Instead of a COM
+//! port it names the connection type and a handle the door can use to talk
+//! to the caller directly, usually a telnet socket.
+
+use crate::error::{DoorError, Result};
+use crate::session::DoorSession;
+use std::fs;
+use std::path::Path;
+
+/// Line 1 value for a local (console) session
+pub const COMM_TYPE_LOCAL: u8 = 0;
+/// Line 1 value for a serial port
+pub const COMM_TYPE_SERIAL: u8 = 1;
+/// Line 1 value for a telnet socket
+pub const COMM_TYPE_TELNET: u8 = 2;
+
+/// DOOR32.SYS dropfile format - 11 line format.
+///
+/// This structure represents the DOOR32.SYS dropfile specification. Doors
+/// that receive a telnet comm type read and write the socket named by
+/// `comm_handle` themselves.
+#[derive(Debug, Clone)]
+pub struct Door32SysDropfile {
+    /// Line 1: Comm type (0 = local, 1 = serial, 2 = telnet)
+    pub comm_type: u8,
+    /// Line 2: Comm or socket handle
+    pub comm_handle: i64,
+    /// Line 3: Baud rate
+    pub baud_rate: u32,
+    /// Line 4: BBS software name and version
+    pub bbs_id: String,
+    /// Line 5: User record number (1-based)
+    pub user_record_number: u32,
+    /// Line 6: User's real name
+    pub user_name: String,
+    /// Line 7: User's handle (alias)
+    pub user_alias: String,
+    /// Line 8: Security level (0-255)
+    pub security_level: u8,
+    /// Line 9: Time remaining (minutes)
+    pub time_remaining: u32,
+    /// Line 10: Emulation (0 = ASCII, 1 = ANSI, 2 = Avatar, 3 = RIP, 4 = MaxGfx)
+    pub emulation: u8,
+    /// Line 11: Node number
+    pub node_number: u16,
+}
+
+impl Door32SysDropfile {
+    /// Create a new DOOR32.SYS dropfile with default values.
+    pub fn new() -> Self {
+        Self {
+            comm_type: COMM_TYPE_LOCAL,
+            comm_handle: 0,
+            baud_rate: 115200,
+            bbs_id: format!("Impulse-Next BBS {}", env!("CARGO_PKG_VERSION")),
+            user_record_number: 1,
+            user_name: "Guest User".to_string(),
+            user_alias: "Guest".to_string(),
+            security_level: 10,
+            time_remaining: 60,
+            emulation: 1, // ANSI
+            node_number: 1,
+        }
+    }
+
+    /// Create a DOOR32.SYS dropfile from a door session.
+    ///
+    /// The comm type is local until a socket is attached with
+    /// [`with_socket`](Self::with_socket).
+    pub fn from_session(session: &DoorSession) -> Self {
+        let mut dropfile = Self::new();
+        dropfile.user_name = session.user_name.clone();
+        dropfile.user_alias = session
+            .user_alias
+            .clone()
+            .unwrap_or_else(|| session.user_name.clone());
+        dropfile.security_level = session.security_level;
+        dropfile.time_remaining = session.time_remaining_seconds / 60;
+        dropfile.emulation = if session.ansi_enabled { 1 } else { 0 };
+        dropfile.node_number = session.node_id;
+        dropfile
+    }
+
+    /// Point the door at the caller's telnet socket.
+    pub fn with_socket(mut self, handle: i64) -> Self {
+        self.comm_type = COMM_TYPE_TELNET;
+        self.comm_handle = handle;
+        self
+    }
+
+    /// Write the DOOR32.SYS dropfile to a file.
+    pub fn write_to_file(&self, path: &Path) -> Result<()> {
+        let content = self.format_dropfile();
+        fs::write(path, content).map_err(|e| {
+            DoorError::DropfileCreation(format!("Failed to write DOOR32.SYS: {}", e))
+        })?;
+        Ok(())
+    }
+
+    /// Format the dropfile as an 11-line string.
+    pub fn format_dropfile(&self) -> String {
+        format!(
+            "{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n",
+            self.comm_type,
+            self.comm_handle,
+            self.baud_rate,
+            self.bbs_id,
+            self.user_record_number,
+            self.user_name,
+            self.user_alias,
+            self.security_level,
+            self.time_remaining,
+            self.emulation,
+            self.node_number
+        )
+    }
+}
+
+impl Default for Door32SysDropfile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn create_test_session() -> DoorSession {
+        DoorSession {
+            node_id: 4,
+            user_name: "John Doe".to_string(),
+            user_alias: Some("JDoe".to_string()),
+            location: "Portland, OR".to_string(),
+            security_level: 75,
+            time_remaining_seconds: 2400, // 40 minutes
+            ansi_enabled: true,
+            login_time: Utc::now(),
+            total_calls: 25,
+            last_call_date: "11/26/25".to_string(),
+            upload_kb: 512,
+            download_kb: 1024,
+        }
+    }
+
+    #[test]
+    fn test_door32_new() {
+        let dropfile = Door32SysDropfile::new();
+        assert_eq!(dropfile.comm_type, COMM_TYPE_LOCAL);
+        assert_eq!(dropfile.comm_handle, 0);
+        assert!(dropfile.bbs_id.starts_with("Impulse-Next BBS"));
+        assert_eq!(dropfile.emulation, 1);
+    }
+
+    #[test]
+    fn test_door32_from_session() {
+        let dropfile = Door32SysDropfile::from_session(&create_test_session());
+        assert_eq!(dropfile.comm_type, COMM_TYPE_LOCAL);
+        assert_eq!(dropfile.user_name, "John Doe");
+        assert_eq!(dropfile.user_alias, "JDoe");
+        assert_eq!(dropfile.security_level, 75);
+        assert_eq!(dropfile.time_remaining, 40);
+        assert_eq!(dropfile.node_number, 4);
+    }
+
+    #[test]
+    fn test_door32_alias_falls_back_to_name() {
+        let mut session = create_test_session();
+        session.user_alias = None;
+        session.ansi_enabled = false;
+
+        let dropfile = Door32SysDropfile::from_session(&session);
+        assert_eq!(dropfile.user_alias, "John Doe");
+        assert_eq!(dropfile.emulation, 0);
+    }
+
+    #[test]
+    fn test_door32_with_socket() {
+        let dropfile = Door32SysDropfile::from_session(&create_test_session()).with_socket(7);
+        let content = dropfile.format_dropfile();
+        let lines: Vec<&str> = content.lines().collect();
+
+        assert_eq!(lines.len(), 11, "DOOR32.SYS must have exactly 11 lines");
+        assert_eq!(lines[0], "2");
+        assert_eq!(lines[1], "7");
+        assert_eq!(lines[5], "John Doe");
+        assert_eq!(lines[6], "JDoe");
+        assert_eq!(lines[8], "40");
+        assert_eq!(lines[10], "4");
+    }
+
+    #[test]
+    fn test_door32_write_to_file() {
+        let dropfile = Door32SysDropfile::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("DOOR32.SYS");
+
+        dropfile.write_to_file(&file_path).unwrap();
+
+        let content = fs::read_to_string(&file_path).unwrap();
+        assert_eq!(content.lines().count(), 11);
+    }
+}
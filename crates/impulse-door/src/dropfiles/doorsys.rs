@@ -7,6 +7,11 @@ use crate::error::{DoorError, Result};
 use crate::session::DoorSession;
 use std::fs;
 use std::path::Path;
+use std::str::FromStr;
+use tracing::warn;
+
+/// Lines a DOOR.SYS must have for the time remaining to be read back
+const MIN_READ_LINES: usize = 19;
 
 /// DOOR.SYS dropfile format - 52 line standard format.
 ///
@@ -212,6 +217,135 @@ impl DoorSysDropfile {
         Ok(())
     }
 
+    /// Read a DOOR.SYS dropfile back from a file.
+    pub fn read_from_file(path: &Path) -> Result<Self> {
+        let content = fs::read(path)
+            .map_err(|e| DoorError::DropfileParse(format!("Failed to read DOOR.SYS: {}", e)))?;
+        Self::parse(&String::from_utf8_lossy(&content))
+    }
+
+    /// Parse a DOOR.SYS dropfile.
+    ///
+    /// Doors that rewrite DOOR.SYS sometimes write only the older, shorter
+    /// layout, so lines past the time remaining (line 19) are optional and
+    /// keep their default values when missing or blank.
+    pub fn parse(content: &str) -> Result<Self> {
+        let lines = DoorSysLines(content.lines().map(str::trim).collect());
+        if lines.0.len() < MIN_READ_LINES {
+            return Err(DoorError::DropfileParse(format!(
+                "DOOR.SYS has {} lines, expected at least {}",
+                lines.0.len(),
+                MIN_READ_LINES
+            )));
+        }
+
+        let mut dropfile = Self::new();
+        lines.text(1, &mut dropfile.com_port);
+        lines.number(2, &mut dropfile.baud_rate)?;
+        lines.number(3, &mut dropfile.parity)?;
+        lines.number(4, &mut dropfile.node_number)?;
+        lines.number(5, &mut dropfile.dtr_drop_time)?;
+        lines.flag(6, &mut dropfile.screen_display);
+        lines.flag(7, &mut dropfile.printer_toggle);
+        lines.flag(8, &mut dropfile.page_bell);
+        lines.flag(9, &mut dropfile.caller_alarm);
+        lines.text(10, &mut dropfile.user_name);
+        lines.text(11, &mut dropfile.location);
+        lines.text(12, &mut dropfile.phone_home);
+        lines.text(13, &mut dropfile.phone_work);
+        lines.text(14, &mut dropfile.password);
+        lines.number(15, &mut dropfile.security_level)?;
+        lines.number(16, &mut dropfile.total_calls)?;
+        lines.text(17, &mut dropfile.last_call_date);
+        lines.number(18, &mut dropfile.seconds_remaining)?;
+        lines.number(19, &mut dropfile.minutes_remaining)?;
+        lines.text(20, &mut dropfile.graphics_mode);
+        lines.number(21, &mut dropfile.page_length)?;
+        lines.flag(22, &mut dropfile.expert_mode);
+        lines.text(23, &mut dropfile.conferences);
+        lines.number(24, &mut dropfile.current_conference)?;
+        lines.text(25, &mut dropfile.expiration_date);
+        lines.number(26, &mut dropfile.user_record_number)?;
+        if let Some(protocol) = lines.get(27).and_then(|line| line.chars().next()) {
+            dropfile.default_protocol = protocol;
+        }
+        lines.number(28, &mut dropfile.total_uploads)?;
+        lines.number(29, &mut dropfile.total_downloads)?;
+        lines.number(30, &mut dropfile.daily_download_kb)?;
+        lines.number(31, &mut dropfile.daily_downloaded_kb)?;
+        lines.text(32, &mut dropfile.birthdate);
+        lines.text(33, &mut dropfile.user_directory);
+        lines.text(34, &mut dropfile.bbs_directory);
+        lines.flag(35, &mut dropfile.is_sysop);
+        lines.flag(36, &mut dropfile.is_co_sysop);
+        lines.flag(37, &mut dropfile.ansi_enabled);
+        lines.flag(38, &mut dropfile.use_full_editor);
+        lines.flag(39, &mut dropfile.screen_clearing);
+        lines.flag(40, &mut dropfile.mail_waiting);
+        lines.text(41, &mut dropfile.conference_name);
+        lines.number(42, &mut dropfile.screen_width)?;
+        lines.text(43, &mut dropfile.bbs_software);
+        lines.text(44, &mut dropfile.user_alias);
+        lines.text(45, &mut dropfile.login_time);
+        lines.number(46, &mut dropfile.time_limit)?;
+        lines.number(47, &mut dropfile.upload_kb_limit)?;
+        lines.number(48, &mut dropfile.download_kb_limit)?;
+        lines.text(49, &mut dropfile.ul_dl_ratio);
+        lines.number(50, &mut dropfile.upload_kb_total)?;
+        lines.number(51, &mut dropfile.download_kb_total)?;
+        lines.text(52, &mut dropfile.user_comment);
+
+        Ok(dropfile)
+    }
+
+    /// Carry the changes a door made to its DOOR.SYS into the session.
+    ///
+    /// `written` is the dropfile as it was handed to the door. Time the door
+    /// added or took away is applied on top of the session's time remaining,
+    /// from which the time spent in the door has already been deducted; a
+    /// changed upload/download total replaces the session's. A door may
+    /// lower the security level but never raise it, since anything that can
+    /// write the dropfile could otherwise make the caller a SysOp.
+    ///
+    /// # Returns
+    ///
+    /// Whether the door changed anything
+    pub fn sync_session(&self, written: &Self, session: &mut DoorSession) -> bool {
+        let mut changed = false;
+
+        // Doors that bank time may rewrite either time line
+        let time_delta = if self.seconds_remaining != written.seconds_remaining {
+            i64::from(self.seconds_remaining) - i64::from(written.seconds_remaining)
+        } else {
+            (i64::from(self.minutes_remaining) - i64::from(written.minutes_remaining)) * 60
+        };
+        if time_delta != 0 {
+            let remaining = i64::from(session.time_remaining_seconds) + time_delta;
+            session.time_remaining_seconds = u32::try_from(remaining.max(0)).unwrap_or(u32::MAX);
+            changed = true;
+        }
+
+        if self.security_level < written.security_level {
+            session.security_level = self.security_level;
+            changed = true;
+        } else if self.security_level > written.security_level {
+            warn!(
+                "Ignoring security level {} from DOOR.SYS for user '{}' (was {})",
+                self.security_level, session.user_name, written.security_level
+            );
+        }
+        if self.upload_kb_total != written.upload_kb_total {
+            session.upload_kb = self.upload_kb_total;
+            changed = true;
+        }
+        if self.download_kb_total != written.download_kb_total {
+            session.download_kb = self.download_kb_total;
+            changed = true;
+        }
+
+        changed
+    }
+
     /// Format the dropfile as a 52-line string.
     pub fn format_dropfile(&self) -> String {
         format!(
@@ -283,6 +417,43 @@ impl Default for DoorSysDropfile {
     }
 }
 
+/// The lines of a DOOR.SYS being read back, numbered from 1
+struct DoorSysLines<'a>(Vec<&'a str>);
+
+impl<'a> DoorSysLines<'a> {
+    /// The given line, if present and not blank
+    fn get(&self, line: usize) -> Option<&'a str> {
+        self.0
+            .get(line - 1)
+            .copied()
+            .filter(|value| !value.is_empty())
+    }
+
+    fn text(&self, line: usize, field: &mut String) {
+        if let Some(value) = self.get(line) {
+            *field = value.to_string();
+        }
+    }
+
+    fn flag(&self, line: usize, field: &mut bool) {
+        if let Some(value) = self.get(line) {
+            *field = value.eq_ignore_ascii_case("Y");
+        }
+    }
+
+    fn number<T: FromStr>(&self, line: usize, field: &mut T) -> Result<()> {
+        if let Some(value) = self.get(line) {
+            *field = value.parse().map_err(|_| {
+                DoorError::DropfileParse(format!(
+                    "DOOR.SYS line {} is not a number: {:?}",
+                    line, value
+                ))
+            })?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -446,6 +617,114 @@ mod tests {
         assert_eq!(lines[42], "Impulse-Next BBS"); // Line 43
     }
 
+    fn create_test_session() -> DoorSession {
+        DoorSession {
+            node_id: 3,
+            user_name: "John Doe".to_string(),
+            user_alias: Some("JDoe".to_string()),
+            location: "Seattle, WA".to_string(),
+            security_level: 50,
+            time_remaining_seconds: 1800,
+            ansi_enabled: true,
+            login_time: Utc::now(),
+            total_calls: 12,
+            last_call_date: "11/26/25".to_string(),
+            upload_kb: 100,
+            download_kb: 200,
+        }
+    }
+
+    #[test]
+    fn test_doorsys_parse_round_trip() {
+        let mut dropfile = DoorSysDropfile::from_session(&create_test_session());
+        dropfile.default_protocol = 'Y';
+        dropfile.expert_mode = true;
+
+        let parsed = DoorSysDropfile::parse(&dropfile.format_dropfile()).unwrap();
+        assert_eq!(parsed.format_dropfile(), dropfile.format_dropfile());
+    }
+
+    #[test]
+    fn test_doorsys_parse_short_file() {
+        let content = DoorSysDropfile::new().format_dropfile();
+        let short: Vec<&str> = content.lines().take(31).collect();
+
+        let parsed = DoorSysDropfile::parse(&short.join("\r\n")).unwrap();
+        assert_eq!(parsed.minutes_remaining, 60);
+        assert_eq!(parsed.bbs_software, "Impulse-Next BBS");
+    }
+
+    #[test]
+    fn test_doorsys_parse_errors() {
+        assert!(matches!(
+            DoorSysDropfile::parse("COM1\n38400\n"),
+            Err(DoorError::DropfileParse(_))
+        ));
+
+        let content = DoorSysDropfile::new()
+            .format_dropfile()
+            .replacen("\n10\n", "\nten\n", 1);
+        match DoorSysDropfile::parse(&content) {
+            Err(DoorError::DropfileParse(msg)) => assert!(msg.contains("line 15")),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_doorsys_sync_session() {
+        let mut session = create_test_session();
+        let written = DoorSysDropfile::from_session(&session);
+
+        // The BBS has already taken 5 minutes off for the time in the door
+        session.deduct_time(300);
+
+        // A time bank hands back 10 minutes and the door lowers security
+        let mut read = written.clone();
+        read.minutes_remaining += 10;
+        read.security_level = 40;
+        read.download_kb_total = 250;
+
+        assert!(read.sync_session(&written, &mut session));
+        assert_eq!(session.time_remaining_seconds, 1800 - 300 + 600);
+        assert_eq!(session.security_level, 40);
+        assert_eq!(session.upload_kb, 100);
+        assert_eq!(session.download_kb, 250);
+    }
+
+    #[test]
+    fn test_doorsys_sync_session_refuses_higher_security() {
+        let mut session = create_test_session();
+        let written = DoorSysDropfile::from_session(&session);
+
+        let mut read = written.clone();
+        read.security_level = 255;
+
+        assert!(!read.sync_session(&written, &mut session));
+        assert_eq!(session.security_level, 50);
+    }
+
+    #[test]
+    fn test_doorsys_sync_session_unchanged() {
+        let mut session = create_test_session();
+        let written = DoorSysDropfile::from_session(&session);
+
+        assert!(!written.clone().sync_session(&written, &mut session));
+        assert_eq!(session.time_remaining_seconds, 1800);
+    }
+
+    #[test]
+    fn test_doorsys_sync_session_deposit_to_zero() {
+        let mut session = create_test_session();
+        let written = DoorSysDropfile::from_session(&session);
+        session.deduct_time(600);
+
+        let mut read = written.clone();
+        read.seconds_remaining = 0;
+
+        assert!(read.sync_session(&written, &mut session));
+        assert_eq!(session.time_remaining_seconds, 0);
+    }
+
     #[test]
     fn test_doorsys_default_protocol() {
         let mut dropfile = DoorSysDropfile::new();
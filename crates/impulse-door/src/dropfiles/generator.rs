@@ -7,6 +7,7 @@ use crate::error::{DoorError, Result};
 use crate::session::DoorSession;
 use std::path::{Path, PathBuf};
 
+use super::door32::Door32SysDropfile;
 use super::doorsys::DoorSysDropfile;
 use super::dorinfo::DorinfoDropfile;
 
@@ -23,6 +24,8 @@ pub enum DropfileType {
     ChainTxt,
     /// CALLINFO.BBS format (used by WildCat! doors)
     CallInfo,
+    /// DOOR32.SYS format (11 lines, used by 32-bit doors)
+    Door32Sys,
 }
 
 impl DropfileType {
@@ -34,6 +37,7 @@ impl DropfileType {
             DropfileType::DorinfoDef => format!("DORINFO{}.DEF", node_id),
             DropfileType::ChainTxt => "CHAIN.TXT".to_string(),
             DropfileType::CallInfo => "CALLINFO.BBS".to_string(),
+            DropfileType::Door32Sys => "DOOR32.SYS".to_string(),
         }
     }
 
@@ -45,6 +49,7 @@ impl DropfileType {
             DropfileType::DorinfoDef => "DORINFOx.DEF (node-specific 13-line format)",
             DropfileType::ChainTxt => "CHAIN.TXT (WWIV format)",
             DropfileType::CallInfo => "CALLINFO.BBS (WildCat! format)",
+            DropfileType::Door32Sys => "DOOR32.SYS (11-line 32-bit door format)",
         }
     }
 }
@@ -86,6 +91,10 @@ impl DropfileGenerator {
                 let dropfile = DorinfoDropfile::from_session(session);
                 dropfile.write_to_file(&file_path)?;
             }
+            DropfileType::Door32Sys => {
+                let dropfile = Door32SysDropfile::from_session(session);
+                dropfile.write_to_file(&file_path)?;
+            }
             DropfileType::ChainTxt => {
                 return Err(DoorError::DropfileCreation(
                     "CHAIN.TXT format not yet implemented".to_string(),
@@ -167,6 +176,12 @@ mod tests {
         assert_eq!(filename, "CALLINFO.BBS");
     }
 
+    #[test]
+    fn test_dropfile_type_filename_door32() {
+        let filename = DropfileType::Door32Sys.filename(1);
+        assert_eq!(filename, "DOOR32.SYS");
+    }
+
     #[test]
     fn test_dropfile_type_description() {
         assert_eq!(
@@ -228,6 +243,23 @@ mod tests {
         assert_eq!(path.file_name().unwrap(), "DORINFO3.DEF");
     }
 
+    #[test]
+    fn test_generate_door32() {
+        let session = create_test_session();
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let path = DropfileGenerator::generate(DropfileType::Door32Sys, &session, temp_dir.path())
+            .unwrap();
+        assert_eq!(path.file_name().unwrap(), "DOOR32.SYS");
+
+        // Local comm type on node 3
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 11);
+        assert_eq!(lines[0], "0");
+        assert_eq!(lines[10], "3");
+    }
+
     #[test]
     fn test_generate_chain_txt_not_implemented() {
         let session = create_test_session();
@@ -3,10 +3,12 @@
 //! This module provides support for generating various dropfile formats
 //! used by BBS door games to receive user and system information.
 
+pub mod door32;
 pub mod doorsys;
 pub mod dorinfo;
 pub mod generator;
 
+pub use door32::Door32SysDropfile;
 pub use doorsys::DoorSysDropfile;
 pub use dorinfo::DorinfoDropfile;
 pub use generator::{DropfileGenerator, DropfileType};
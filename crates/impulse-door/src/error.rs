@@ -23,6 +23,10 @@ pub enum DoorError {
     #[error("Failed to create dropfile: {0}")]
     DropfileCreation(String),
 
+    /// Failed to read back a dropfile.
+    #[error("Failed to parse dropfile: {0}")]
+    DropfileParse(String),
+
     /// Door execution failed.
     #[error("Door execution failed: {0}")]
     ExecutionFailed(String),
@@ -43,6 +47,10 @@ pub enum DoorError {
     #[error("User time expired")]
     TimeExpired,
 
+    /// The caller left the door with ESC.
+    #[error("Door aborted by the caller")]
+    Aborted,
+
     /// Invalid door configuration.
     #[error("Invalid door configuration: {0}")]
     InvalidConfig(String),
@@ -74,6 +82,10 @@ pub enum DoorError {
     /// Failed to acquire node lock.
     #[error("Failed to acquire node lock: {0}")]
     NodeLockFailed(String),
+
+    /// Single-node door already being played on another node.
+    #[error("Door {name} is in use by node {node}")]
+    DoorInUse { name: String, node: u16 },
 }
 
 #[cfg(test)]
@@ -96,6 +108,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_dropfile_parse_error() {
+        let error = DoorError::DropfileParse("line 15 is not a number".to_string());
+        assert_eq!(
+            error.to_string(),
+            "Failed to parse dropfile: line 15 is not a number"
+        );
+    }
+
     #[test]
     fn test_dropfile_creation_error() {
         let error = DoorError::DropfileCreation("Invalid format".to_string());
@@ -135,6 +156,12 @@ mod tests {
         assert_eq!(error.to_string(), "User time expired");
     }
 
+    #[test]
+    fn test_aborted_error() {
+        let error = DoorError::Aborted;
+        assert_eq!(error.to_string(), "Door aborted by the caller");
+    }
+
     #[test]
     fn test_invalid_config_error() {
         let error = DoorError::InvalidConfig("Empty door name".to_string());
@@ -182,6 +209,15 @@ mod tests {
         assert_eq!(error.to_string(), "Node directory 5 is already in use");
     }
 
+    #[test]
+    fn test_door_in_use_error() {
+        let error = DoorError::DoorInUse {
+            name: "lord".to_string(),
+            node: 2,
+        };
+        assert_eq!(error.to_string(), "Door lord is in use by node 2");
+    }
+
     #[test]
     fn test_node_lock_failed_error() {
         let error = DoorError::NodeLockFailed("Permission denied".to_string());
@@ -4,17 +4,27 @@
 //! including support for native executables and DOSBox for legacy DOS doors.
 
 use crate::config::DoorConfig;
-use crate::dropfiles::{DropfileGenerator, DropfileType};
+#[cfg(unix)]
+use crate::dropfiles::Door32SysDropfile;
+use crate::dropfiles::{DoorSysDropfile, DropfileGenerator, DropfileType};
 use crate::error::{DoorError, Result};
-use crate::io::DoorIoHandler;
+use crate::io::{DoorExit, DoorIoHandler, relay_serial};
 use crate::manager::DoorManager;
 use crate::session::DoorSession;
+use std::net::Ipv4Addr;
+#[cfg(unix)]
+use std::os::fd::{BorrowedFd, OwnedFd};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::process::Command;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::process::{Child, Command};
 use tracing::{debug, info, warn};
 
+/// How long DOSBox has to start and connect a door's COM port
+const SERIAL_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// Result of a door execution.
 #[derive(Debug, Clone)]
 pub struct DoorResult {
@@ -22,7 +32,8 @@ pub struct DoorResult {
     pub exit_code: i32,
     /// Runtime in seconds
     pub runtime_seconds: u64,
-    /// Whether user statistics were updated
+    /// Whether the door changed the user's time, security level or
+    /// transfer totals by rewriting DOOR.SYS
     pub user_stats_updated: bool,
 }
 
@@ -39,8 +50,8 @@ impl DoorResult {
 /// 1. Validate user access
 /// 2. Create node directory and dropfiles
 /// 3. Launch the door process (native or via DOSBox)
-/// 4. Monitor execution and time limits
-/// 5. Clean up resources
+/// 4. Connect the door to the caller and enforce time limits
+/// 5. Clean up resources and restore the caller's terminal
 pub struct DoorExecutor {
     /// Door manager for accessing door configurations
     door_manager: Arc<DoorManager>,
@@ -70,17 +81,146 @@ impl DoorExecutor {
         }
     }
 
+    /// Use the DOSBox executable at `path` instead of searching PATH.
+    pub fn with_dosbox_path(mut self, path: PathBuf) -> Self {
+        self.dosbox_path = Some(path);
+        self
+    }
+
     /// Execute a door game.
     ///
+    /// The door's input and output are connected to `caller` for as long
+    /// as it runs: a native door's stdio, or the COM1 port of a DOS door,
+    /// which DOSBox connects back to the BBS over a nullmodem.
+    ///
     /// # Arguments
     ///
     /// * `door_name` - The name of the door to execute
     /// * `session` - The door session containing user information
+    /// * `caller` - The caller's byte stream
     ///
     /// # Returns
     ///
-    /// The result of the door execution
-    pub async fn execute(&self, door_name: &str, session: &mut DoorSession) -> Result<DoorResult> {
+    /// The result of the door execution. A native door left with ESC fails
+    /// with [`DoorError::Aborted`], and any door that runs out of time with
+    /// [`DoorError::Timeout`]; the time spent is deducted either way. Changes
+    /// a door that exits normally makes to DOOR.SYS are applied to `session`.
+    /// A `single_node` door already running on another node fails with
+    /// [`DoorError::DoorInUse`].
+    pub async fn execute<S>(
+        &self,
+        door_name: &str,
+        session: &mut DoorSession,
+        caller: &mut S,
+    ) -> Result<DoorResult>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let config = self.authorize(door_name, session)?;
+        let _lock = self.door_manager.lock_door(config, session.node_id)?;
+
+        info!(
+            "Executing door '{}' for user '{}' on node {}",
+            door_name, session.user_name, session.node_id
+        );
+
+        // Execute based on configuration
+        if config.use_dosbox {
+            self.execute_dosbox(config, session, caller).await
+        } else {
+            self.execute_native(config, session, caller).await
+        }
+    }
+
+    /// Execute a door on the caller's own TCP socket.
+    ///
+    /// Instead of relaying piped input and output, the door is handed a
+    /// copy of `socket` as its standard input and output, and a DOOR32.SYS
+    /// dropfile names it as telnet handle 0. The BBS stays off the socket
+    /// until the door exits, so the door handles the telnet protocol and
+    /// ESC itself; it is still killed when its time runs out.
+    ///
+    /// # Arguments
+    ///
+    /// * `door_name` - The name of the door to execute
+    /// * `session` - The door session containing user information
+    /// * `socket` - The caller's connected TCP socket
+    #[cfg(unix)]
+    pub async fn execute_with_socket(
+        &self,
+        door_name: &str,
+        session: &mut DoorSession,
+        socket: BorrowedFd<'_>,
+    ) -> Result<DoorResult> {
+        let config = self.authorize(door_name, session)?;
+        if config.use_dosbox {
+            return Err(DoorError::InvalidConfig(
+                "Socket mode is not available for DOSBox doors".to_string(),
+            ));
+        }
+        let _lock = self.door_manager.lock_door(config, session.node_id)?;
+
+        info!(
+            "Executing door '{}' on the socket of user '{}' on node {}",
+            door_name, session.user_name, session.node_id
+        );
+
+        // Prepare node directory
+        let node_dir = self.door_manager.get_node_dir(session.node_id);
+        tokio::fs::create_dir_all(&node_dir).await?;
+
+        // Generate dropfiles
+        if config.dropfile_type == DropfileType::Door32Sys {
+            let path = node_dir.join(DropfileType::Door32Sys.filename(session.node_id));
+            Door32SysDropfile::from_session(session)
+                .with_socket(0)
+                .write_to_file(&path)?;
+        } else {
+            self.generate_dropfiles(config.dropfile_type, session, &node_dir)
+                .await?;
+        }
+        let written = Self::written_door_sys(config, session);
+
+        // The copy shares the socket's file status flags, and doors expect
+        // blocking reads
+        let socket = std::net::TcpStream::from(socket.try_clone_to_owned()?);
+        let result = match socket.set_nonblocking(false) {
+            Ok(()) => self.run_socket_door(config, session, &socket).await,
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = socket.set_nonblocking(true) {
+            warn!(
+                "Failed to restore non-blocking mode on caller socket: {}",
+                e
+            );
+        }
+        let user_stats_updated =
+            result.is_ok() && Self::sync_door_sys(written.as_ref(), session, &node_dir);
+
+        // Clean up dropfiles
+        self.cleanup_dropfiles(&node_dir, session.node_id).await;
+
+        if let Ok(mut caller) = tokio::net::TcpStream::from_std(socket) {
+            Self::restore_terminal(&mut caller, session.ansi_enabled).await;
+        }
+
+        let (exit_code, runtime) = result?;
+        info!(
+            "Door '{}' exited with code {} after {}s",
+            config.name,
+            exit_code,
+            runtime.as_secs()
+        );
+
+        Ok(DoorResult {
+            exit_code,
+            runtime_seconds: runtime.as_secs(),
+            user_stats_updated,
+        })
+    }
+
+    /// Look up a door and check that the caller may run it.
+    fn authorize(&self, door_name: &str, session: &DoorSession) -> Result<&DoorConfig> {
         // Get door configuration
         let config = self
             .door_manager
@@ -100,25 +240,19 @@ impl DoorExecutor {
             return Err(DoorError::TimeExpired);
         }
 
-        info!(
-            "Executing door '{}' for user '{}' on node {}",
-            door_name, session.user_name, session.node_id
-        );
-
-        // Execute based on configuration
-        if config.use_dosbox {
-            self.execute_dosbox(config, session).await
-        } else {
-            self.execute_native(config, session).await
-        }
+        Ok(config)
     }
 
     /// Execute a native door (not via DOSBox).
-    async fn execute_native(
+    async fn execute_native<S>(
         &self,
         config: &DoorConfig,
         session: &mut DoorSession,
-    ) -> Result<DoorResult> {
+        caller: &mut S,
+    ) -> Result<DoorResult>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
         debug!("Executing native door: {}", config.name);
 
         // Prepare node directory
@@ -128,49 +262,25 @@ impl DoorExecutor {
         // Generate dropfiles
         self.generate_dropfiles(config.dropfile_type, session, &node_dir)
             .await?;
+        let written = Self::written_door_sys(config, session);
 
         // Start the door process
-        let start_time = Instant::now();
-        let mut child = Command::new(&config.executable)
+        let child = Command::new(&config.executable)
             .current_dir(&config.directory)
             .stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::null())
+            .kill_on_drop(true)
             .spawn()?;
 
-        // Set up I/O handler
-        let _io_handler = DoorIoHandler::new(&mut child).await?;
-
-        // Wait for process with timeout
-        let timeout_duration = if config.max_time_minutes > 0 {
-            Duration::from_secs(config.max_time_minutes as u64 * 60)
-        } else {
-            Duration::from_secs(session.time_remaining_seconds as u64)
-        };
-
-        let exit_status = tokio::time::timeout(timeout_duration, child.wait()).await;
-
-        let exit_code = match exit_status {
-            Ok(Ok(status)) => status.code().unwrap_or(-1),
-            Ok(Err(e)) => {
-                warn!("Door process error: {}", e);
-                -1
-            }
-            Err(_) => {
-                warn!("Door execution timeout, killing process");
-                let _ = child.kill().await;
-                return Err(DoorError::Timeout(timeout_duration.as_secs()));
-            }
-        };
-
-        let runtime = start_time.elapsed();
-
-        // Update session time
-        session.deduct_time(runtime.as_secs() as u32);
+        let result = self.run_door(config, session, child, None, caller).await;
+        let user_stats_updated =
+            result.is_ok() && Self::sync_door_sys(written.as_ref(), session, &node_dir);
 
         // Clean up dropfiles
-        self.cleanup_dropfiles(&node_dir).await;
+        self.cleanup_dropfiles(&node_dir, session.node_id).await;
 
+        let (exit_code, runtime) = result?;
         info!(
             "Door '{}' exited with code {} after {}s",
             config.name,
@@ -181,16 +291,20 @@ impl DoorExecutor {
         Ok(DoorResult {
             exit_code,
             runtime_seconds: runtime.as_secs(),
-            user_stats_updated: false,
+            user_stats_updated,
         })
     }
 
     /// Execute a door via DOSBox.
-    async fn execute_dosbox(
+    async fn execute_dosbox<S>(
         &self,
         config: &DoorConfig,
         session: &mut DoorSession,
-    ) -> Result<DoorResult> {
+        caller: &mut S,
+    ) -> Result<DoorResult>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
         debug!("Executing DOSBox door: {}", config.name);
 
         // Check if DOSBox is available
@@ -206,55 +320,41 @@ impl DoorExecutor {
         // Generate dropfiles
         self.generate_dropfiles(config.dropfile_type, session, &node_dir)
             .await?;
+        let written = Self::written_door_sys(config, session);
+
+        // The door's COM1 dials in here
+        let serial = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let serial_port = serial.local_addr()?.port();
 
         // Create DOSBox configuration
-        let dosbox_config_path = self.create_dosbox_config(config, &node_dir).await?;
+        let dosbox_config_path = self
+            .create_dosbox_config(config, session.node_id, &node_dir, serial_port)
+            .await?;
 
-        // Start DOSBox with the configuration
-        let start_time = Instant::now();
-        let mut child = Command::new(dosbox_path)
+        // Start DOSBox with the configuration, without a window
+        let child = Command::new(dosbox_path)
             .arg("-conf")
             .arg(&dosbox_config_path)
             .current_dir(&config.directory)
-            .stdin(std::process::Stdio::piped())
-            .stdout(std::process::Stdio::piped())
+            .env("SDL_VIDEODRIVER", "dummy")
+            .env("SDL_AUDIODRIVER", "dummy")
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
             .stderr(std::process::Stdio::null())
+            .kill_on_drop(true)
             .spawn()?;
 
-        // Set up I/O handler
-        let _io_handler = DoorIoHandler::new(&mut child).await?;
-
-        // Wait for process with timeout
-        let timeout_duration = if config.max_time_minutes > 0 {
-            Duration::from_secs(config.max_time_minutes as u64 * 60)
-        } else {
-            Duration::from_secs(session.time_remaining_seconds as u64)
-        };
-
-        let exit_status = tokio::time::timeout(timeout_duration, child.wait()).await;
-
-        let exit_code = match exit_status {
-            Ok(Ok(status)) => status.code().unwrap_or(-1),
-            Ok(Err(e)) => {
-                warn!("DOSBox process error: {}", e);
-                -1
-            }
-            Err(_) => {
-                warn!("DOSBox execution timeout, killing process");
-                let _ = child.kill().await;
-                return Err(DoorError::Timeout(timeout_duration.as_secs()));
-            }
-        };
-
-        let runtime = start_time.elapsed();
-
-        // Update session time
-        session.deduct_time(runtime.as_secs() as u32);
+        let result = self
+            .run_door(config, session, child, Some(serial), caller)
+            .await;
+        let user_stats_updated =
+            result.is_ok() && Self::sync_door_sys(written.as_ref(), session, &node_dir);
 
         // Clean up
-        self.cleanup_dropfiles(&node_dir).await;
+        self.cleanup_dropfiles(&node_dir, session.node_id).await;
         let _ = tokio::fs::remove_file(dosbox_config_path).await;
 
+        let (exit_code, runtime) = result?;
         info!(
             "DOSBox door '{}' exited with code {} after {}s",
             config.name,
@@ -265,19 +365,220 @@ impl DoorExecutor {
         Ok(DoorResult {
             exit_code,
             runtime_seconds: runtime.as_secs(),
-            user_stats_updated: false,
+            user_stats_updated,
         })
     }
 
+    /// Connect a started door to the caller and wait for it to finish.
+    ///
+    /// A door with a `serial` listener is relayed from the connection its
+    /// COM port makes there; any other is relayed from its stdio. The door
+    /// is killed if the caller aborts, hangs up or runs out of time.
+    /// Afterwards the time spent is deducted from the session and the
+    /// caller's terminal is reset.
+    ///
+    /// # Returns
+    ///
+    /// The door's exit code and how long it ran
+    async fn run_door<S>(
+        &self,
+        config: &DoorConfig,
+        session: &mut DoorSession,
+        mut child: Child,
+        serial: Option<TcpListener>,
+        caller: &mut S,
+    ) -> Result<(i32, Duration)>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let start_time = Instant::now();
+        let time_limit = Self::time_limit(config, session);
+
+        let exit = match serial {
+            Some(listener) => {
+                Self::relay_serial_port(&mut child, listener, caller, time_limit).await
+            }
+            None => {
+                let mut io_handler = DoorIoHandler::new(&mut child).await?;
+                io_handler.pump(caller, time_limit).await
+            }
+        };
+
+        let exit_code = match exit {
+            // The door closed its output; give it what is left of its time
+            // to exit
+            Ok(DoorExit::Exited) => {
+                let remaining = time_limit.saturating_sub(start_time.elapsed());
+                match tokio::time::timeout(remaining, child.wait()).await {
+                    Ok(Ok(status)) => status.code().unwrap_or(-1),
+                    Ok(Err(e)) => {
+                        warn!("Door process error: {}", e);
+                        -1
+                    }
+                    Err(_) => {
+                        warn!("Door '{}' did not exit, killing process", config.name);
+                        let _ = child.kill().await;
+                        -1
+                    }
+                }
+            }
+            _ => {
+                let _ = child.kill().await;
+                -1
+            }
+        };
+
+        let runtime = start_time.elapsed();
+
+        // Update session time
+        session.deduct_time(runtime.as_secs() as u32);
+
+        if !matches!(exit, Ok(DoorExit::Disconnected)) {
+            Self::restore_terminal(caller, session.ansi_enabled).await;
+        }
+
+        match exit? {
+            DoorExit::Exited => Ok((exit_code, runtime)),
+            DoorExit::Aborted => {
+                info!("Door '{}' aborted by the caller", config.name);
+                Err(DoorError::Aborted)
+            }
+            DoorExit::TimeExpired => {
+                warn!("Door '{}' time limit reached, killed process", config.name);
+                Err(DoorError::Timeout(time_limit.as_secs()))
+            }
+            DoorExit::Disconnected => Err(std::io::Error::new(
+                std::io::ErrorKind::ConnectionAborted,
+                "Caller disconnected during door",
+            )
+            .into()),
+        }
+    }
+
+    /// Start a door on the caller's socket and wait for it to finish.
+    ///
+    /// The door is killed if it outlives its time limit. The time spent is
+    /// deducted from the session.
+    ///
+    /// # Returns
+    ///
+    /// The door's exit code and how long it ran
+    #[cfg(unix)]
+    async fn run_socket_door(
+        &self,
+        config: &DoorConfig,
+        session: &mut DoorSession,
+        socket: &std::net::TcpStream,
+    ) -> Result<(i32, Duration)> {
+        let time_limit = Self::time_limit(config, session);
+
+        let mut child = Command::new(&config.executable)
+            .current_dir(&config.directory)
+            .stdin(OwnedFd::from(socket.try_clone()?))
+            .stdout(OwnedFd::from(socket.try_clone()?))
+            .stderr(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let start_time = Instant::now();
+        let status = tokio::time::timeout(time_limit, child.wait()).await;
+        if status.is_err() {
+            let _ = child.kill().await;
+        }
+        let runtime = start_time.elapsed();
+
+        // Update session time
+        session.deduct_time(runtime.as_secs() as u32);
+
+        match status {
+            Ok(status) => Ok((status?.code().unwrap_or(-1), runtime)),
+            Err(_) => {
+                warn!("Door '{}' time limit reached, killed process", config.name);
+                Err(DoorError::Timeout(time_limit.as_secs()))
+            }
+        }
+    }
+
+    /// Wait for a door's COM port to dial in, then relay it to the caller.
+    async fn relay_serial_port<S>(
+        child: &mut Child,
+        listener: TcpListener,
+        caller: &mut S,
+        time_limit: Duration,
+    ) -> Result<DoorExit>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let start_time = Instant::now();
+        let (mut serial, _) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = child.wait() => {
+                return Err(DoorError::ExecutionFailed(
+                    "DOSBox exited before opening its serial port".to_string(),
+                ));
+            }
+            _ = tokio::time::sleep(SERIAL_CONNECT_TIMEOUT) => {
+                return Err(DoorError::ExecutionFailed(
+                    "DOSBox did not open its serial port".to_string(),
+                ));
+            }
+        };
+        serial.set_nodelay(true)?;
+
+        let remaining = time_limit.saturating_sub(start_time.elapsed());
+        relay_serial(caller, &mut serial, remaining).await
+    }
+
+    /// How long a door may run: its own limit, but never longer than the
+    /// caller has left.
+    fn time_limit(config: &DoorConfig, session: &DoorSession) -> Duration {
+        let remaining = u64::from(session.time_remaining_seconds);
+        let limit = if config.max_time_minutes > 0 {
+            remaining.min(u64::from(config.max_time_minutes) * 60)
+        } else {
+            remaining
+        };
+        Duration::from_secs(limit)
+    }
+
+    /// Put the caller's terminal back the way the BBS expects it.
+    ///
+    /// Doors can leave colours set, the cursor hidden or a scrolling region
+    /// in place.
+    async fn restore_terminal<S>(caller: &mut S, ansi_enabled: bool)
+    where
+        S: AsyncWrite + Unpin,
+    {
+        let reset: &[u8] = if ansi_enabled {
+            b"\x1b[0m\x1b[r\x1b[?25h\r\n"
+        } else {
+            b"\r\n"
+        };
+        if caller.write_all(reset).await.is_ok() {
+            let _ = caller.flush().await;
+        }
+    }
+
     /// Create a DOSBox configuration file for the door.
-    async fn create_dosbox_config(&self, config: &DoorConfig, node_path: &Path) -> Result<PathBuf> {
+    async fn create_dosbox_config(
+        &self,
+        config: &DoorConfig,
+        node_id: u16,
+        node_path: &Path,
+        serial_port: u16,
+    ) -> Result<PathBuf> {
         let dosbox_config = config
             .dosbox_config
             .as_ref()
             .ok_or_else(|| DoorError::Config("DOSBox configuration missing".to_string()))?;
 
         let config_path = node_path.join("dosbox.conf");
-        let config_content = dosbox_config.generate_config();
+        let config_content = dosbox_config.generate_door_config(
+            &config.directory,
+            node_path,
+            &config.dos_command(node_id),
+            serial_port,
+        );
 
         tokio::fs::write(&config_path, config_content).await?;
 
@@ -296,12 +597,59 @@ impl DoorExecutor {
         Ok(())
     }
 
-    /// Clean up dropfiles after door execution.
-    async fn cleanup_dropfiles(&self, node_dir: &Path) {
-        let dropfiles = ["DOOR.SYS", "DORINFO1.DEF", "CHAIN.TXT", "CALLINFO.BBS"];
+    /// The DOOR.SYS handed to a door, to compare against once it exits.
+    fn written_door_sys(config: &DoorConfig, session: &DoorSession) -> Option<DoorSysDropfile> {
+        (config.dropfile_type == DropfileType::DoorSys)
+            .then(|| DoorSysDropfile::from_session(session))
+    }
 
-        for filename in &dropfiles {
-            let path = node_dir.join(filename);
+    /// Read back a door's DOOR.SYS and apply its changes to the session.
+    ///
+    /// # Returns
+    ///
+    /// Whether the door changed the user's time, security level or
+    /// transfer totals
+    fn sync_door_sys(
+        written: Option<&DoorSysDropfile>,
+        session: &mut DoorSession,
+        node_dir: &Path,
+    ) -> bool {
+        let Some(written) = written else {
+            return false;
+        };
+
+        let path = node_dir.join(DropfileType::DoorSys.filename(session.node_id));
+        match DoorSysDropfile::read_from_file(&path) {
+            Ok(dropfile) => {
+                let changed = dropfile.sync_session(written, session);
+                if changed {
+                    info!(
+                        "Door updated DOOR.SYS for user '{}' on node {}",
+                        session.user_name, session.node_id
+                    );
+                }
+                changed
+            }
+            Err(e) => {
+                warn!("Ignoring DOOR.SYS left by door: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Clean up dropfiles after door execution.
+    async fn cleanup_dropfiles(&self, node_dir: &Path, node_id: u16) {
+        let dropfiles = [
+            DropfileType::DoorSys,
+            DropfileType::Door32Sys,
+            DropfileType::Dorinfo1Def,
+            DropfileType::DorinfoDef,
+            DropfileType::ChainTxt,
+            DropfileType::CallInfo,
+        ];
+
+        for dropfile in dropfiles {
+            let path = node_dir.join(dropfile.filename(node_id));
             if path.exists() && tokio::fs::remove_file(&path).await.is_err() {
                 warn!("Failed to remove dropfile {:?}", path);
             }
@@ -326,6 +674,11 @@ mod tests {
         }
     }
 
+    // A caller that never types anything
+    fn caller() -> tokio::io::DuplexStream {
+        tokio::io::duplex(1024).0
+    }
+
     // Helper function to get a platform-specific test directory path
     fn test_directory() -> PathBuf {
         std::env::temp_dir()
@@ -362,7 +715,9 @@ mod tests {
         let executor = create_test_executor().await;
         // Executor should be created successfully - verify non-existent door returns error
         let mut session = create_test_session();
-        let result = executor.execute("__nonexistent__", &mut session).await;
+        let result = executor
+            .execute("__nonexistent__", &mut session, &mut caller())
+            .await;
         assert!(result.is_err());
     }
 
@@ -371,7 +726,9 @@ mod tests {
         let executor = create_test_executor().await;
         let mut session = create_test_session();
 
-        let result = executor.execute("nonexistent-door", &mut session).await;
+        let result = executor
+            .execute("nonexistent-door", &mut session, &mut caller())
+            .await;
         assert!(result.is_err());
         assert!(matches!(result, Err(DoorError::DoorNotFound(_))));
     }
@@ -400,7 +757,9 @@ mod tests {
         let mut session = create_test_session();
         session.security_level = 50;
 
-        let result = executor.execute("secure-door", &mut session).await;
+        let result = executor
+            .execute("secure-door", &mut session, &mut caller())
+            .await;
         assert!(result.is_err());
         assert!(matches!(
             result,
@@ -426,11 +785,199 @@ mod tests {
         let mut session = create_test_session();
         session.time_remaining_seconds = 0;
 
-        let result = executor.execute("test-door", &mut session).await;
+        let result = executor
+            .execute("test-door", &mut session, &mut caller())
+            .await;
         assert!(result.is_err());
         assert!(matches!(result, Err(DoorError::TimeExpired)));
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_connects_door_to_caller() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let game_dir = temp_dir.path().join("game");
+        std::fs::create_dir_all(&game_dir).unwrap();
+        std::fs::write(game_dir.join("SCORES.DAT"), "").unwrap();
+
+        let mut manager =
+            DoorManager::new(temp_dir.path().join("doors"), temp_dir.path().join("nodes"))
+                .await
+                .unwrap();
+        manager
+            .add_door(DoorConfig::new(
+                "lister".to_string(),
+                test_executable(),
+                game_dir,
+            ))
+            .unwrap();
+        let executor = DoorExecutor::new(Arc::new(manager));
+
+        let mut session = create_test_session();
+        let (mut caller, mut terminal) = tokio::io::duplex(4096);
+        let result = executor
+            .execute("lister", &mut session, &mut caller)
+            .await
+            .unwrap();
+        drop(caller);
+
+        assert!(result.is_success());
+        let mut output = Vec::new();
+        tokio::io::AsyncReadExt::read_to_end(&mut terminal, &mut output)
+            .await
+            .unwrap();
+        let output = String::from_utf8_lossy(&output);
+        assert!(output.starts_with("SCORES.DAT\r\n"));
+        assert!(output.ends_with("\x1b[0m\x1b[r\x1b[?25h\r\n")); // Terminal restored
+        assert!(!temp_dir.path().join("nodes/node1/DOOR.SYS").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_reads_back_door_sys() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let node_dir = temp_dir.path().join("nodes");
+
+        // A time bank: hands back 30 minutes and bumps the download total
+        let door = temp_dir.path().join("bank.sh");
+        std::fs::write(
+            &door,
+            format!(
+                "#!/bin/sh\nsed -i -e '19s/.*/90/' -e '51s/.*/64/' {}\n",
+                node_dir.join("node1/DOOR.SYS").display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&door, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut manager = DoorManager::new(temp_dir.path().join("doors"), node_dir)
+            .await
+            .unwrap();
+        manager
+            .add_door(DoorConfig::new(
+                "bank".to_string(),
+                door,
+                temp_dir.path().to_path_buf(),
+            ))
+            .unwrap();
+        let executor = DoorExecutor::new(Arc::new(manager));
+
+        let mut session = create_test_session();
+        let (mut caller, _terminal) = tokio::io::duplex(1024);
+        let result = executor
+            .execute("bank", &mut session, &mut caller)
+            .await
+            .unwrap();
+
+        assert!(result.user_stats_updated);
+        let spent = result.runtime_seconds as u32;
+        assert_eq!(session.time_remaining_seconds, 3600 - spent + 30 * 60);
+        assert_eq!(session.download_kb, 64);
+        assert_eq!(session.upload_kb, 0);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_dosbox_relays_serial_port() {
+        use std::os::unix::fs::PermissionsExt;
+        use tokio::io::AsyncReadExt;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let game_dir = temp_dir.path().join("lord");
+        std::fs::create_dir_all(&game_dir).unwrap();
+        std::fs::write(game_dir.join("START.BAT"), "").unwrap();
+
+        // Stands in for DOSBox: dials the nullmodem port from its config,
+        // reports the dropfile on D: and echoes one key
+        let dosbox = temp_dir.path().join("dosbox");
+        std::fs::write(
+            &dosbox,
+            "#!/bin/bash\n\
+             port=$(sed -n 's/^serial1=nullmodem server:127.0.0.1 port:\\([0-9]*\\).*/\\1/p' \"$2\")\n\
+             node=$(sed -n 's/^mount D \"\\(.*\\)\"$/\\1/p' \"$2\")\n\
+             exec 3<>/dev/tcp/127.0.0.1/$port\n\
+             [ -f \"$node/DOOR.SYS\" ] && printf 'DOOR.SYS on D:\\r\\n' >&3\n\
+             read -r -n 1 key <&3\n\
+             printf 'Got %s\\r\\n' \"$key\" >&3\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&dosbox, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut manager =
+            DoorManager::new(temp_dir.path().join("doors"), temp_dir.path().join("nodes"))
+                .await
+                .unwrap();
+        let mut config = DoorConfig::new("lord".to_string(), game_dir.join("START.BAT"), game_dir);
+        config.use_dosbox = true;
+        config.dosbox_config = Some(crate::config::DosBoxConfig::new());
+        manager.add_door(config).unwrap();
+        let executor = DoorExecutor::new(Arc::new(manager)).with_dosbox_path(dosbox);
+
+        let mut session = create_test_session();
+        let (mut caller, mut terminal) = tokio::io::duplex(4096);
+        let (result, output) =
+            tokio::join!(executor.execute("lord", &mut session, &mut caller), async {
+                let mut output = vec![0u8; 16];
+                terminal.read_exact(&mut output).await.unwrap();
+                terminal.write_all(b"Y").await.unwrap();
+                output
+            });
+        drop(caller);
+
+        assert!(result.unwrap().is_success());
+        assert_eq!(output, b"DOOR.SYS on D:\r\n");
+        let mut rest = Vec::new();
+        terminal.read_to_end(&mut rest).await.unwrap();
+        assert_eq!(rest, b"Got Y\r\n\x1b[0m\x1b[r\x1b[?25h\r\n");
+        assert!(!temp_dir.path().join("nodes/node1/DOOR.SYS").exists());
+        assert!(!temp_dir.path().join("nodes/node1/dosbox.conf").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_with_socket_hands_door_the_socket() {
+        use std::os::fd::AsFd;
+        use tokio::io::AsyncReadExt;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let game_dir = temp_dir.path().join("game");
+        std::fs::create_dir_all(&game_dir).unwrap();
+        std::fs::write(game_dir.join("SCORES.DAT"), "").unwrap();
+
+        let mut manager =
+            DoorManager::new(temp_dir.path().join("doors"), temp_dir.path().join("nodes"))
+                .await
+                .unwrap();
+        let mut config = DoorConfig::new("lister".to_string(), test_executable(), game_dir);
+        config.dropfile_type = DropfileType::Door32Sys;
+        manager.add_door(config).unwrap();
+        let executor = DoorExecutor::new(Arc::new(manager));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut terminal = tokio::net::TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (mut caller, _) = listener.accept().await.unwrap();
+
+        let mut session = create_test_session();
+        let result = executor
+            .execute_with_socket("lister", &mut session, caller.as_fd())
+            .await
+            .unwrap();
+        assert!(result.is_success());
+        assert!(!temp_dir.path().join("nodes/node1/DOOR32.SYS").exists());
+
+        // The BBS can carry on using the socket afterwards
+        caller.write_all(b"back").await.unwrap();
+        drop(caller);
+
+        let mut output = Vec::new();
+        terminal.read_to_end(&mut output).await.unwrap();
+        assert_eq!(output, b"SCORES.DAT\n\x1b[0m\x1b[r\x1b[?25h\r\nback");
+    }
+
     #[tokio::test]
     async fn test_door_result_is_success() {
         let result = DoorResult {
@@ -482,10 +1029,34 @@ mod tests {
         let manager = Arc::new(DoorManager::new(door_dir, node_dir).await.unwrap());
         let executor = DoorExecutor::new(manager);
 
-        executor.cleanup_dropfiles(&test_node_dir).await;
+        executor.cleanup_dropfiles(&test_node_dir, 1).await;
 
         // Files should be removed
         assert!(!test_node_dir.join("DOOR.SYS").exists());
         assert!(!test_node_dir.join("DORINFO1.DEF").exists());
     }
+
+    #[tokio::test]
+    async fn test_executor_cleanup_node_dropfiles() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let door_dir = temp_dir.path().join("doors");
+        let node_dir = temp_dir.path().join("nodes");
+        let test_node_dir = node_dir.join("node3");
+
+        tokio::fs::create_dir_all(&test_node_dir).await.unwrap();
+        tokio::fs::write(test_node_dir.join("DORINFO3.DEF"), "test")
+            .await
+            .unwrap();
+        tokio::fs::write(test_node_dir.join("DOOR32.SYS"), "test")
+            .await
+            .unwrap();
+
+        let manager = Arc::new(DoorManager::new(door_dir, node_dir).await.unwrap());
+        let executor = DoorExecutor::new(manager);
+
+        executor.cleanup_dropfiles(&test_node_dir, 3).await;
+
+        assert!(!test_node_dir.join("DORINFO3.DEF").exists());
+        assert!(!test_node_dir.join("DOOR32.SYS").exists());
+    }
 }
@@ -1,14 +1,40 @@
 //! I/O handler for door game processes.
 //!
 //! This module provides asynchronous I/O handling between the BBS and
-//! door game processes, managing stdin/stdout communication.
+//! door game processes, managing stdin/stdout communication, and the pump
+//! that connects a running door to the caller.
 
 use crate::error::Result;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::process::Child;
 use tokio::sync::mpsc;
+use tokio::time::{Instant, sleep_until};
 use tracing::{debug, trace, warn};
 
+/// Escape key, which the caller presses on its own to leave a door
+const ESC: u8 = 0x1B;
+
+/// How long an escape key must stand alone to count as an abort
+///
+/// Cursor keys and other terminal sequences also start with ESC, but the
+/// rest of the sequence follows within a few milliseconds.
+const ESCAPE_ABORT_DELAY: Duration = Duration::from_millis(300);
+
+/// How a door session connected by [`DoorIoHandler::pump`] or
+/// [`relay_serial`] ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DoorExit {
+    /// The door closed its output, normally by exiting
+    Exited,
+    /// The caller pressed ESC on its own
+    Aborted,
+    /// The time limit ran out
+    TimeExpired,
+    /// The caller's connection closed
+    Disconnected,
+}
+
 /// Door I/O handler for managing communication with a door game process.
 ///
 /// This handler manages bidirectional communication between the BBS and
@@ -152,6 +178,163 @@ impl DoorIoHandler {
     pub async fn send_byte(&self, byte: u8) -> Result<()> {
         self.send_input(&[byte]).await
     }
+
+    /// Connect the door to the caller until one of them is done.
+    ///
+    /// Door output is written to `caller` and the caller's keys are sent
+    /// to the door, with line endings translated as a terminal would: the
+    /// door's bare LFs become CRLF and the caller's Enter becomes LF. A
+    /// lone ESC from the caller aborts the door.
+    ///
+    /// # Arguments
+    ///
+    /// * `caller` - The caller's byte stream
+    /// * `time_limit` - How long the door may run
+    ///
+    /// # Returns
+    ///
+    /// Why the session ended. The door process is left running unless it
+    /// exited by itself.
+    pub async fn pump<S>(&mut self, caller: &mut S, time_limit: Duration) -> Result<DoorExit>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let deadline = Instant::now() + time_limit;
+        let mut discipline = LineDiscipline::default();
+        let mut buffer = vec![0u8; 1024];
+        let mut pending_escape: Option<Instant> = None;
+
+        loop {
+            tokio::select! {
+                output = self.stdout_rx.recv() => match output {
+                    Some(data) => {
+                        caller.write_all(&discipline.output(&data)).await?;
+                        caller.flush().await?;
+                    }
+                    None => return Ok(DoorExit::Exited),
+                },
+                read = caller.read(&mut buffer) => {
+                    let mut data = &buffer[..read?];
+                    if data.is_empty() {
+                        return Ok(DoorExit::Disconnected);
+                    }
+
+                    // An ESC held back from the last read was the start of
+                    // a sequence after all
+                    let mut input = Vec::with_capacity(data.len() + 1);
+                    if pending_escape.take().is_some() {
+                        input.push(ESC);
+                    }
+                    if let [rest @ .., ESC] = data {
+                        data = rest;
+                        pending_escape = Some(Instant::now() + ESCAPE_ABORT_DELAY);
+                    }
+                    input.extend(discipline.input(data));
+
+                    // A door that stopped reading may still be writing
+                    if !input.is_empty() && self.send_input(&input).await.is_err() {
+                        debug!("Door stdin closed; dropping caller input");
+                    }
+                }
+                _ = sleep_until(pending_escape.unwrap_or(deadline)), if pending_escape.is_some() => {
+                    return Ok(DoorExit::Aborted);
+                }
+                _ = sleep_until(deadline) => return Ok(DoorExit::TimeExpired),
+            }
+        }
+    }
+}
+
+/// Connect a door's serial line to the caller until one of them is done.
+///
+/// DOS doors under DOSBox talk to a COM port, which reaches the BBS as
+/// `serial`. Bytes pass through untouched both ways, as over a modem, so
+/// ESC is just another key to the door.
+///
+/// # Arguments
+///
+/// * `caller` - The caller's byte stream
+/// * `serial` - The door's serial connection
+/// * `time_limit` - How long the door may run
+///
+/// # Returns
+///
+/// Why the session ended; [`DoorExit::Exited`] once the door side closes.
+pub async fn relay_serial<C, S>(
+    caller: &mut C,
+    serial: &mut S,
+    time_limit: Duration,
+) -> Result<DoorExit>
+where
+    C: AsyncRead + AsyncWrite + Unpin,
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let deadline = Instant::now() + time_limit;
+    let mut output = vec![0u8; 4096];
+    let mut input = vec![0u8; 1024];
+
+    loop {
+        tokio::select! {
+            read = serial.read(&mut output) => {
+                let n = read?;
+                if n == 0 {
+                    return Ok(DoorExit::Exited);
+                }
+                caller.write_all(&output[..n]).await?;
+                caller.flush().await?;
+            }
+            read = caller.read(&mut input) => {
+                let n = read?;
+                if n == 0 {
+                    return Ok(DoorExit::Disconnected);
+                }
+                serial.write_all(&input[..n]).await?;
+            }
+            _ = sleep_until(deadline) => return Ok(DoorExit::TimeExpired),
+        }
+    }
+}
+
+/// Line ending translation between a door and the caller's terminal
+///
+/// Native doors expect the line handling of a Unix terminal, so output
+/// gets the CRLF a terminal's ONLCR would add and input gets ICRNL's CR to
+/// LF. The LF or NUL a telnet client sends after CR is dropped.
+#[derive(Debug, Default)]
+struct LineDiscipline {
+    /// Last byte written to the caller
+    last_output: u8,
+    /// Last byte received from the caller
+    last_input: u8,
+}
+
+impl LineDiscipline {
+    /// Translate door output for the caller
+    fn output(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut translated = Vec::with_capacity(data.len() + data.len() / 16);
+        for &byte in data {
+            if byte == b'\n' && self.last_output != b'\r' {
+                translated.push(b'\r');
+            }
+            translated.push(byte);
+            self.last_output = byte;
+        }
+        translated
+    }
+
+    /// Translate caller input for the door
+    fn input(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut translated = Vec::with_capacity(data.len());
+        for &byte in data {
+            match byte {
+                b'\r' => translated.push(b'\n'),
+                b'\n' | 0 if self.last_input == b'\r' => {}
+                _ => translated.push(byte),
+            }
+            self.last_input = byte;
+        }
+        translated
+    }
 }
 
 #[cfg(test)]
@@ -362,4 +545,142 @@ mod tests {
         // Clean up
         let _ = child.kill().await;
     }
+
+    fn spawn_cat() -> tokio::process::Child {
+        Command::new("cat")
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_pump_relays_both_directions() {
+        let mut child = spawn_cat();
+        let mut handler = DoorIoHandler::new(&mut child).await.unwrap();
+        let (mut caller, mut terminal) = tokio::io::duplex(1024);
+
+        let (exit, echoed) = tokio::join!(
+            handler.pump(&mut caller, Duration::from_secs(10)),
+            async move {
+                // Enter arrives as CR NUL from telnet clients
+                terminal.write_all(b"hello\r\0").await.unwrap();
+                let mut echoed = vec![0u8; 7];
+                terminal.read_exact(&mut echoed).await.unwrap();
+                echoed
+            }
+        );
+
+        // The caller hung up after reading the echo
+        assert_eq!(exit.unwrap(), DoorExit::Disconnected);
+        assert_eq!(echoed, b"hello\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_pump_door_exit() {
+        let mut child = Command::new("echo")
+            .arg("goodbye")
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut handler = DoorIoHandler::new(&mut child).await.unwrap();
+        let (mut caller, mut terminal) = tokio::io::duplex(1024);
+
+        let exit = handler.pump(&mut caller, Duration::from_secs(10)).await;
+        drop(caller);
+
+        assert_eq!(exit.unwrap(), DoorExit::Exited);
+        let mut output = Vec::new();
+        terminal.read_to_end(&mut output).await.unwrap();
+        assert_eq!(output, b"goodbye\r\n");
+        let _ = child.wait().await;
+    }
+
+    #[tokio::test]
+    async fn test_pump_lone_escape_aborts() {
+        let mut child = spawn_cat();
+        let mut handler = DoorIoHandler::new(&mut child).await.unwrap();
+        let (mut caller, mut terminal) = tokio::io::duplex(1024);
+
+        terminal.write_all(&[ESC]).await.unwrap();
+        let exit = handler.pump(&mut caller, Duration::from_secs(10)).await;
+
+        assert_eq!(exit.unwrap(), DoorExit::Aborted);
+    }
+
+    #[tokio::test]
+    async fn test_pump_escape_sequence_reaches_door() {
+        let mut child = spawn_cat();
+        let mut handler = DoorIoHandler::new(&mut child).await.unwrap();
+        let (mut caller, mut terminal) = tokio::io::duplex(1024);
+
+        let (exit, echoed) = tokio::join!(
+            handler.pump(&mut caller, Duration::from_secs(10)),
+            async move {
+                // A cursor key split across two reads
+                terminal.write_all(&[ESC]).await.unwrap();
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                terminal.write_all(b"[A").await.unwrap();
+                let mut echoed = vec![0u8; 3];
+                terminal.read_exact(&mut echoed).await.unwrap();
+                echoed
+            }
+        );
+
+        assert_eq!(exit.unwrap(), DoorExit::Disconnected);
+        assert_eq!(echoed, b"\x1b[A");
+    }
+
+    #[tokio::test]
+    async fn test_relay_serial_passes_bytes_untouched() {
+        let (mut caller, mut terminal) = tokio::io::duplex(1024);
+        let (mut serial, mut com_port) = tokio::io::duplex(1024);
+
+        let (exit, _) = tokio::join!(
+            relay_serial(&mut caller, &mut serial, Duration::from_secs(10)),
+            async move {
+                terminal.write_all(b"Y\r\x1b").await.unwrap();
+                let mut received = vec![0u8; 3];
+                com_port.read_exact(&mut received).await.unwrap();
+                assert_eq!(received, b"Y\r\x1b");
+
+                com_port.write_all(b"\x1b[2JBye\n").await.unwrap();
+                let mut shown = vec![0u8; 8];
+                terminal.read_exact(&mut shown).await.unwrap();
+                assert_eq!(shown, b"\x1b[2JBye\n");
+                // DOSBox exits and drops the line
+                drop(com_port);
+                terminal
+            }
+        );
+
+        assert_eq!(exit.unwrap(), DoorExit::Exited);
+    }
+
+    #[tokio::test]
+    async fn test_relay_serial_hangup_and_time_limit() {
+        let (mut caller, terminal) = tokio::io::duplex(1024);
+        let (mut serial, _com_port) = tokio::io::duplex(1024);
+        drop(terminal);
+        let exit = relay_serial(&mut caller, &mut serial, Duration::from_secs(10)).await;
+        assert_eq!(exit.unwrap(), DoorExit::Disconnected);
+
+        let (mut caller, _terminal) = tokio::io::duplex(1024);
+        let (mut serial, _com_port) = tokio::io::duplex(1024);
+        let exit = relay_serial(&mut caller, &mut serial, Duration::from_millis(100)).await;
+        assert_eq!(exit.unwrap(), DoorExit::TimeExpired);
+    }
+
+    #[tokio::test]
+    async fn test_pump_time_limit() {
+        let mut child = spawn_cat();
+        let mut handler = DoorIoHandler::new(&mut child).await.unwrap();
+        let (mut caller, _terminal) = tokio::io::duplex(1024);
+
+        let exit = handler.pump(&mut caller, Duration::from_millis(100)).await;
+
+        assert_eq!(exit.unwrap(), DoorExit::TimeExpired);
+    }
 }
@@ -3,11 +3,13 @@
 //! This crate provides comprehensive support for running BBS door games,
 //! including:
 //!
-//! - Dropfile generation (DOOR.SYS, DORINFO1.DEF, etc.)
+//! - Dropfile generation (DOOR.SYS, DOOR32.SYS, DORINFO1.DEF, etc.)
 //! - Door configuration and management
 //! - Native and DOSBox execution support
 //! - Session management with time tracking
-//! - Async I/O handling
+//! - Async I/O bridging between the door and the caller
+//! - Socket-handle launch for DOOR32.SYS doors
+//! - Single-node door locking across nodes
 //!
 //! # Examples
 //!
@@ -44,8 +46,9 @@
 //!     download_kb: 200,
 //! };
 //!
-//! // Execute door
-//! let result = executor.execute("tradewars", &mut session).await?;
+//! // Execute door, connected to the caller's byte stream
+//! let mut caller = tokio::net::TcpStream::connect("127.0.0.1:2323").await?;
+//! let result = executor.execute("tradewars", &mut session, &mut caller).await?;
 //! println!("Door exited with code: {}", result.exit_code);
 //! # Ok(())
 //! # }
@@ -95,10 +98,12 @@ pub mod manager;
 pub mod session;
 
 // Re-export commonly used types
-pub use config::{DoorConfig, DosBoxConfig};
-pub use dropfiles::{DoorSysDropfile, DorinfoDropfile, DropfileGenerator, DropfileType};
+pub use config::{DoorConfig, DoorIoMode, DosBoxConfig};
+pub use dropfiles::{
+    Door32SysDropfile, DoorSysDropfile, DorinfoDropfile, DropfileGenerator, DropfileType,
+};
 pub use error::{DoorError, Result};
 pub use executor::{DoorExecutor, DoorResult};
-pub use io::DoorIoHandler;
-pub use manager::DoorManager;
+pub use io::{DoorExit, DoorIoHandler};
+pub use manager::{DoorLock, DoorManager};
 pub use session::DoorSession;
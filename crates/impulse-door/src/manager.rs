@@ -7,6 +7,7 @@ use crate::config::DoorConfig;
 use crate::error::{DoorError, Result};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
 use tracing::{debug, info, warn};
 
 /// Door manager for managing multiple door configurations.
@@ -14,6 +15,7 @@ use tracing::{debug, info, warn};
 /// The door manager handles loading door configurations from TOML files,
 /// managing the collection of available doors, and providing access to
 /// door configurations by name.
+#[derive(Debug, Clone)]
 pub struct DoorManager {
     /// Map of door name to door configuration
     doors: HashMap<String, DoorConfig>,
@@ -21,6 +23,26 @@ pub struct DoorManager {
     door_dir: PathBuf,
     /// Directory for node-specific files (dropfiles, temp files)
     node_dir: PathBuf,
+    /// Single-node doors being played, by name, with the node playing them
+    ///
+    /// Shared by clones so a reloaded manager still sees running doors.
+    in_use: Arc<Mutex<HashMap<String, u16>>>,
+}
+
+/// Claim on a single-node door, released when dropped.
+#[derive(Debug)]
+pub struct DoorLock {
+    in_use: Arc<Mutex<HashMap<String, u16>>>,
+    name: String,
+}
+
+impl Drop for DoorLock {
+    fn drop(&mut self) {
+        self.in_use
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.name);
+    }
 }
 
 impl DoorManager {
@@ -39,6 +61,7 @@ impl DoorManager {
             doors: HashMap::new(),
             door_dir,
             node_dir,
+            in_use: Arc::new(Mutex::new(HashMap::new())),
         };
 
         // Create directories if they don't exist
@@ -159,6 +182,35 @@ impl DoorManager {
         self.node_dir.join(format!("node{}", node_id))
     }
 
+    /// Claim a door for a node.
+    ///
+    /// Doors marked `single_node` can be claimed by one node at a time;
+    /// other doors are never locked.
+    ///
+    /// # Returns
+    ///
+    /// A lock held until dropped (`None` for a door any number of nodes
+    /// may play), or [`DoorError::DoorInUse`] naming the node playing it
+    pub fn lock_door(&self, config: &DoorConfig, node_id: u16) -> Result<Option<DoorLock>> {
+        if !config.single_node {
+            return Ok(None);
+        }
+
+        let mut in_use = self.in_use.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(&node) = in_use.get(&config.name) {
+            return Err(DoorError::DoorInUse {
+                name: config.name.clone(),
+                node,
+            });
+        }
+        in_use.insert(config.name.clone(), node_id);
+
+        Ok(Some(DoorLock {
+            in_use: self.in_use.clone(),
+            name: config.name.clone(),
+        }))
+    }
+
     /// Check if a door exists.
     ///
     /// # Arguments
@@ -377,6 +429,40 @@ mod tests {
         assert_eq!(manager.door_count(), 2);
     }
 
+    #[tokio::test]
+    async fn test_lock_single_node_door() {
+        let manager = create_test_manager().await;
+        let mut config = create_test_config("lord", 10);
+        config.single_node = true;
+
+        let lock = manager.lock_door(&config, 1).unwrap();
+        assert!(lock.is_some());
+
+        // A clone (as made on reload) sees the same claim
+        let reloaded = manager.clone();
+        match reloaded.lock_door(&config, 2) {
+            Err(DoorError::DoorInUse { name, node }) => {
+                assert_eq!(name, "lord");
+                assert_eq!(node, 1);
+            }
+            other => panic!("expected DoorInUse, got {:?}", other),
+        }
+
+        drop(lock);
+        assert!(reloaded.lock_door(&config, 2).unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_multi_node_door_is_not_locked() {
+        let manager = create_test_manager().await;
+        let config = create_test_config("tw2002", 10);
+
+        let first = manager.lock_door(&config, 1).unwrap();
+        let second = manager.lock_door(&config, 2).unwrap();
+        assert!(first.is_none());
+        assert!(second.is_none());
+    }
+
     #[tokio::test]
     async fn test_add_door_validation() {
         let mut manager = create_test_manager().await;
@@ -172,6 +172,17 @@ impl FileAreaManager for InMemoryFileAreaManager {
 
         Ok(())
     }
+
+    async fn record_download(&mut self, file_id: u64) -> Result<u32> {
+        let mut files = self.files.write().await;
+        let file = files
+            .iter_mut()
+            .find(|f| f.id as u64 == file_id)
+            .ok_or(FileError::FileNotFound(file_id))?;
+
+        file.record_download();
+        Ok(file.download_count)
+    }
 }
 
 #[cfg(test)]
@@ -332,6 +343,32 @@ mod tests {
         assert_eq!(manager.count_files(1).await.unwrap(), 2);
     }
 
+    #[tokio::test]
+    async fn test_record_download() {
+        let mut manager = InMemoryFileAreaManager::new();
+        manager
+            .add_area(create_test_area(1, "General"))
+            .await
+            .unwrap();
+        manager
+            .add_file(create_test_file(7, 1, "file1.zip"))
+            .await
+            .unwrap();
+
+        // create_test_file starts the count at id * 2
+        assert_eq!(manager.record_download(7).await.unwrap(), 15);
+        assert_eq!(manager.record_download(7).await.unwrap(), 16);
+        assert_eq!(
+            manager.get_file(7).await.unwrap().unwrap().download_count,
+            16
+        );
+
+        assert!(matches!(
+            manager.record_download(99).await,
+            Err(FileError::FileNotFound(99))
+        ));
+    }
+
     #[tokio::test]
     async fn test_security_level_filtering() {
         let mut manager = InMemoryFileAreaManager::new();
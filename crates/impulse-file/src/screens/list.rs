@@ -111,7 +111,8 @@ impl FileListScreen {
                 file.human_readable_size(),
                 Self::format_date(&file.upload_date),
                 file.download_count,
-                truncate(&file.description, 35)
+                // FILE_ID.DIZ descriptions span lines; list the first
+                truncate(file.description.lines().next().unwrap_or(""), 35)
             ));
         }
 
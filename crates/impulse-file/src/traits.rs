@@ -117,4 +117,15 @@ pub trait FileAreaManager: Send + Sync {
     ///
     /// * `file` - The file entry to add
     async fn add_file(&mut self, file: FileEntry) -> Result<()>;
+
+    /// Record a completed download of a file
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - The file ID
+    ///
+    /// # Returns
+    ///
+    /// The file's new download count
+    async fn record_download(&mut self, file_id: u64) -> Result<u32>;
 }
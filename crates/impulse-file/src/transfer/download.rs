@@ -6,7 +6,15 @@
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
-use impulse_protocol::zmodem::{SenderConfig, TransferProgress, ZmodemSender};
+use impulse_protocol::xmodem::{
+    AsyncXmodemSender, SenderConfig as XmodemSenderConfig, XmodemError, XmodemVariant,
+};
+use impulse_protocol::ymodem::{FileMetadata, YmodemSender};
+use impulse_protocol::zmodem::{
+    FileProgress, SenderConfig, TransferProgress, TransferStats, ZmodemError, ZmodemFileInfo,
+    ZmodemSender,
+};
+use tokio::fs::File;
 use tokio::io::{AsyncRead, AsyncWrite};
 
 use super::{Protocol, TransferConfig, TransferStatus};
@@ -181,54 +189,50 @@ impl<S: AsyncRead + AsyncWrite + Unpin> DownloadManager<S> {
         match self.config.protocol {
             Protocol::Zmodem => self.download_zmodem(file_path, &filename, progress).await,
             Protocol::Xmodem | Protocol::Ymodem | Protocol::YmodemG => {
-                // Placeholder for other protocols
-                Ok(DownloadResult::failed(file_path.to_path_buf(), filename))
+                self.download_blocks(file_path, &filename, progress).await
             }
         }
     }
 
     /// Download using Zmodem protocol
+    ///
+    /// The receiver may ask to resume from the size of a partial file left
+    /// behind by an interrupted transfer; the result records the offset.
     async fn download_zmodem<P: TransferProgress>(
         &mut self,
         file_path: &Path,
         filename: &str,
-        _progress: &mut P,
+        progress: &mut P,
     ) -> Result<DownloadResult> {
         // Create Zmodem sender config from transfer config
+        // Subpackets stay within the classic 1K limit that every receiver
+        // accepts; streaming keeps larger buffers from mattering
         let sender_config = SenderConfig {
-            block_size: self.config.buffer_size,
+            block_size: self.config.buffer_size.min(1024),
             timeout_ms: self.config.timeout_ms,
             max_retries: self.config.max_retries,
             use_crc32: self.config.use_crc32,
             escape_control: self.config.escape_control,
             escape_8bit: self.config.escape_8bit,
+            resume: self.config.enable_resume,
         };
 
-        // We need to take ownership of stream temporarily
-        // In a real implementation, you'd use a reference or Arc
-        let stream = std::mem::replace(&mut self.stream, unsafe {
-            // This is a placeholder - in production, use proper stream handling
-            std::mem::zeroed()
-        });
-
-        let mut sender = ZmodemSender::new(stream, sender_config);
+        let mut sender = ZmodemSender::new(&mut self.stream, sender_config);
 
         // Initialize the protocol
         self.status = TransferStatus::Initializing;
-        let _params = match sender.init().await {
-            Ok(params) => params,
-            Err(_) => {
-                self.status = TransferStatus::Failed;
-                return Ok(DownloadResult::failed(
-                    file_path.to_path_buf(),
-                    filename.to_string(),
-                ));
-            }
-        };
+        if let Err(e) = sender.init().await {
+            progress.on_error(&e);
+            self.status = TransferStatus::Failed;
+            return Ok(DownloadResult::failed(
+                file_path.to_path_buf(),
+                filename.to_string(),
+            ));
+        }
 
         // Send the file
         self.status = TransferStatus::InProgress;
-        let stats = match sender.send_file(file_path).await {
+        let stats = match sender.send_file_with_progress(file_path, progress).await {
             Ok(stats) => stats,
             Err(_) => {
                 self.status = TransferStatus::Failed;
@@ -240,7 +244,8 @@ impl<S: AsyncRead + AsyncWrite + Unpin> DownloadManager<S> {
         };
 
         // Finish the session
-        if sender.finish().await.is_err() {
+        if let Err(e) = sender.finish().await {
+            progress.on_error(&e);
             self.status = TransferStatus::Failed;
             return Ok(DownloadResult::failed(
                 file_path.to_path_buf(),
@@ -251,14 +256,100 @@ impl<S: AsyncRead + AsyncWrite + Unpin> DownloadManager<S> {
         self.status = TransferStatus::Completed;
         let duration = self.start_time.map(|t| t.elapsed()).unwrap_or_default();
 
-        // Note: TransferStats doesn't track resume, so we report false/0 for now
         Ok(DownloadResult::success(
             file_path.to_path_buf(),
             filename.to_string(),
-            stats.bytes_sent,
+            stats.bytes_sent - stats.start_position,
             duration,
-            false, // Resume tracking not in current TransferStats
-            0,     // Resume offset
+            stats.was_resumed(),
+            stats.start_position,
+        ))
+    }
+
+    /// Download using Xmodem, Ymodem or Ymodem-G
+    ///
+    /// Ymodem sends the file as a single-file batch; whether it streams is
+    /// up to the receiver, which asks for Ymodem-G by sending 'G'.
+    async fn download_blocks<P: TransferProgress>(
+        &mut self,
+        file_path: &Path,
+        filename: &str,
+        progress: &mut P,
+    ) -> Result<DownloadResult> {
+        let mut file = File::open(file_path).await?;
+        let file_size = file.metadata().await?.len();
+        let file_info = ZmodemFileInfo::new(filename, file_size);
+
+        let sender_config = XmodemSenderConfig {
+            variant: XmodemVariant::Crc,
+            max_retries: self.config.max_retries as usize,
+            timeout: Duration::from_millis(self.config.timeout_ms),
+        };
+
+        let mut file_progress = FileProgress {
+            file_index: 0,
+            total_files: 1,
+            file_path: file_path.to_path_buf(),
+            file_name: filename.to_string(),
+            bytes_sent: 0,
+            bytes_total: file_size,
+            retries: 0,
+        };
+
+        // The protocol reports start only once the receiver asks for data,
+        // so the caller's screen shows the transfer as initializing until then
+        self.status = TransferStatus::Initializing;
+        progress.on_file_start(&file_info);
+        self.status = TransferStatus::InProgress;
+
+        let on_block = |sent| {
+            file_progress.bytes_sent = sent;
+            progress.on_progress(&file_progress);
+        };
+        let sent = match self.config.protocol {
+            Protocol::Xmodem => {
+                AsyncXmodemSender::new(&mut self.stream, sender_config)
+                    .send_file(&mut file, on_block)
+                    .await
+            }
+            _ => {
+                let metadata = FileMetadata::with_size(filename, file_size);
+                let mut sender = YmodemSender::new(&mut self.stream, sender_config);
+                match sender.send_file(&metadata, &mut file, on_block).await {
+                    Ok(stats) => sender.finish().await.map(|_| stats),
+                    Err(e) => Err(e),
+                }
+            }
+        };
+
+        let send_stats = match sent {
+            Ok(stats) => stats,
+            Err(e) => {
+                progress.on_error(&xmodem_to_zmodem_error(e));
+                self.status = TransferStatus::Failed;
+                return Ok(DownloadResult::failed(
+                    file_path.to_path_buf(),
+                    filename.to_string(),
+                ));
+            }
+        };
+
+        let mut stats = TransferStats::new(file_size);
+        stats.bytes_sent = send_stats.bytes_sent;
+        stats.retries = send_stats.blocks_retried as u32;
+        stats.complete();
+        progress.on_file_complete(&file_info, &stats);
+
+        self.status = TransferStatus::Completed;
+        let duration = self.start_time.map(|t| t.elapsed()).unwrap_or_default();
+
+        Ok(DownloadResult::success(
+            file_path.to_path_buf(),
+            filename.to_string(),
+            send_stats.bytes_sent,
+            duration,
+            false,
+            0,
         ))
     }
 
@@ -297,6 +388,18 @@ impl<S: AsyncRead + AsyncWrite + Unpin> DownloadManager<S> {
     }
 }
 
+/// Report an Xmodem-family failure through the Zmodem progress interface
+pub(super) fn xmodem_to_zmodem_error(error: XmodemError) -> ZmodemError {
+    match error {
+        XmodemError::Timeout => ZmodemError::Timeout,
+        XmodemError::Cancelled => ZmodemError::Cancelled,
+        XmodemError::UnexpectedEof => ZmodemError::UnexpectedEof,
+        XmodemError::Io(e) => ZmodemError::Io(e),
+        XmodemError::MaxRetriesExceeded { .. } => ZmodemError::MaxRetriesExceeded,
+        other => ZmodemError::InvalidFrame(other.to_string()),
+    }
+}
+
 /// Create a download manager from a file entry
 ///
 /// Helper function to initiate a download from the file browser
@@ -360,6 +463,50 @@ mod tests {
         assert_eq!(manager.status(), TransferStatus::Idle);
     }
 
+    #[tokio::test]
+    async fn test_download_file_xmodem() {
+        use crate::screens::progress::{TransferProgressScreen, TransferStatus as ScreenStatus};
+        use impulse_protocol::xmodem::{ACK, CRC_MODE, EOT, SOH};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hello.txt");
+        std::fs::write(&path, b"Hello from the file area").unwrap();
+
+        let (server, mut caller) = tokio::io::duplex(4096);
+        let receiver = tokio::spawn(async move {
+            caller.write_all(&[CRC_MODE]).await.unwrap();
+            let mut data = Vec::new();
+            loop {
+                match caller.read_u8().await.unwrap() {
+                    SOH => {
+                        let mut packet = [0u8; 2 + 128 + 2];
+                        caller.read_exact(&mut packet).await.unwrap();
+                        data.extend_from_slice(&packet[2..130]);
+                    }
+                    EOT => {
+                        caller.write_all(&[ACK]).await.unwrap();
+                        return data;
+                    }
+                    other => panic!("unexpected byte {other:02x}"),
+                }
+                caller.write_all(&[ACK]).await.unwrap();
+            }
+        });
+
+        let config = TransferConfig::default().with_protocol(Protocol::Xmodem);
+        let mut manager = DownloadManager::new(server, config);
+        let mut progress = TransferProgressScreen::download("hello.txt".to_string());
+        let result = manager.download_file(&path, &mut progress).await.unwrap();
+
+        assert_eq!(result.status, TransferStatus::Completed);
+        assert_eq!(result.bytes_transferred, 24);
+        assert!(matches!(progress.status, ScreenStatus::Complete));
+
+        let data = receiver.await.unwrap();
+        assert_eq!(&data[..24], b"Hello from the file area");
+    }
+
     #[test]
     fn test_prepare_download() {
         let file = FileEntry {
@@ -1,14 +1,23 @@
 //! Upload integration with file areas
 //!
 //! This module provides the upload manager that coordinates between
-//! the Zmodem protocol and file area processing, including FILE_ID.DIZ extraction.
+//! the transfer protocols and file area processing, including FILE_ID.DIZ extraction.
 
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
-use impulse_protocol::zmodem::{ReceiverConfig, TransferProgress, ZmodemReceiver};
+use impulse_protocol::xmodem::{
+    AsyncXmodemReceiver, ReceiveStats as XmodemReceiveStats,
+    ReceiverConfig as XmodemReceiverConfig, XmodemVariant,
+};
+use impulse_protocol::ymodem::YmodemReceiver;
+use impulse_protocol::zmodem::{
+    FileProgress, ReceiverConfig, TransferProgress, TransferStats, ZmodemFileInfo, ZmodemReceiver,
+};
+use tokio::fs::File;
 use tokio::io::{AsyncRead, AsyncWrite};
 
+use super::download::xmodem_to_zmodem_error;
 use super::{Protocol, TransferConfig, TransferStatus};
 use crate::diz;
 use crate::error::Result;
@@ -113,6 +122,9 @@ pub struct UploadManager<S> {
 
     /// Upload directory path
     upload_dir: PathBuf,
+
+    /// Name for an Xmodem upload, which carries none
+    filename: Option<String>,
 }
 
 impl<S: AsyncRead + AsyncWrite + Unpin> UploadManager<S> {
@@ -125,6 +137,7 @@ impl<S: AsyncRead + AsyncWrite + Unpin> UploadManager<S> {
             start_time: None,
             target_area: None,
             upload_dir,
+            filename: None,
         }
     }
 
@@ -133,6 +146,14 @@ impl<S: AsyncRead + AsyncWrite + Unpin> UploadManager<S> {
         self.target_area = Some(area);
     }
 
+    /// Set the name to store an Xmodem upload under
+    ///
+    /// Xmodem does not send the file name, so the caller is asked for it
+    /// before the transfer. Zmodem and Ymodem use the sender's name.
+    pub fn set_filename(&mut self, filename: String) {
+        self.filename = Some(filename);
+    }
+
     /// Get the current transfer status
     pub fn status(&self) -> TransferStatus {
         self.status
@@ -146,31 +167,28 @@ impl<S: AsyncRead + AsyncWrite + Unpin> UploadManager<S> {
     ///
     /// # Returns
     ///
-    /// Result containing upload information including extracted description
+    /// Result containing upload information including extracted description.
+    /// If the sender offers several files, the first is returned.
     pub async fn receive_file<P: TransferProgress>(
         &mut self,
         progress: &mut P,
     ) -> Result<UploadResult> {
-        self.status = TransferStatus::Initializing;
-        self.start_time = Some(Instant::now());
-
-        match self.config.protocol {
-            Protocol::Zmodem => self.receive_zmodem(progress).await,
-            Protocol::Xmodem | Protocol::Ymodem | Protocol::YmodemG => {
-                // Placeholder for other protocols
-                Ok(UploadResult::failed("unknown".to_string()))
-            }
-        }
+        let results = self.receive_batch(progress).await;
+        Ok(results
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| UploadResult::failed("unknown".to_string())))
     }
 
     /// Receive using Zmodem protocol
-    async fn receive_zmodem<P: TransferProgress>(
+    async fn receive_batch_zmodem<P: TransferProgress>(
         &mut self,
-        _progress: &mut P,
-    ) -> Result<UploadResult> {
-        // Create Zmodem receiver config from transfer config
+        progress: &mut P,
+    ) -> Vec<UploadResult> {
+        // Data is written to disk as it arrives, so the sender may stream
+        // the whole file without waiting for acknowledgements
         let receiver_config = ReceiverConfig {
-            buffer_size: self.config.buffer_size,
+            buffer_size: 0,
             timeout_ms: self.config.timeout_ms,
             max_retries: self.config.max_retries,
             use_crc32: self.config.use_crc32,
@@ -180,71 +198,228 @@ impl<S: AsyncRead + AsyncWrite + Unpin> UploadManager<S> {
             overwrite_existing: self.config.overwrite_existing,
         };
 
-        // We need to take ownership of stream temporarily
-        let stream = std::mem::replace(&mut self.stream, unsafe {
-            // This is a placeholder - in production, use proper stream handling
-            std::mem::zeroed()
-        });
-
-        let mut receiver = ZmodemReceiver::new(stream, receiver_config);
+        let mut receiver = ZmodemReceiver::new(&mut self.stream, receiver_config);
 
         // Initialize the protocol
         self.status = TransferStatus::Initializing;
-        let _params = match receiver.init().await {
-            Ok(params) => params,
-            Err(_) => {
-                self.status = TransferStatus::Failed;
-                return Ok(UploadResult::failed("unknown".to_string()));
-            }
-        };
+        if let Err(e) = receiver.init().await {
+            progress.on_error(&e);
+            self.status = TransferStatus::Failed;
+            return vec![UploadResult::failed("init_failed".to_string())];
+        }
 
-        // Receive files
+        // Receive all files
         self.status = TransferStatus::InProgress;
-        let received_files = match receiver.receive_files(&self.upload_dir).await {
+        let received_files = match receiver
+            .receive_files_with_progress(&self.upload_dir, progress)
+            .await
+        {
             Ok(files) => files,
             Err(_) => {
+                let _ = receiver.abort().await;
                 self.status = TransferStatus::Failed;
-                return Ok(UploadResult::failed("unknown".to_string()));
+                return vec![UploadResult::failed("receive_failed".to_string())];
             }
         };
 
-        // Finish the session
-        if receiver.finish().await.is_err() {
-            self.status = TransferStatus::Failed;
-            return Ok(UploadResult::failed("unknown".to_string()));
-        }
+        // The files are complete even if the sender hangs up without "OO"
+        let _ = receiver.finish().await;
 
-        // Process the first received file (for single file upload)
-        if let Some(received) = received_files.into_iter().next() {
-            self.status = TransferStatus::Completed;
-            let duration = self.start_time.map(|t| t.elapsed()).unwrap_or_default();
+        let duration = self.start_time.map(|t| t.elapsed()).unwrap_or_default();
 
-            // Try to extract FILE_ID.DIZ
-            let description = self.extract_description(&received.saved_path).await;
+        // Process each received file
+        let mut results = Vec::with_capacity(received_files.len());
+        for received in received_files {
+            let Some(filename) = local_file_name(&received.file_info.name) else {
+                results.push(UploadResult::failed(received.file_info.name));
+                continue;
+            };
+            let description = Self::extract_description(&received.saved_path).await;
 
-            Ok(UploadResult::success(
+            results.push(UploadResult::success(
                 received.saved_path,
-                received.file_info.name.clone(),
-                received.stats.bytes_received,
+                filename,
+                received.stats.bytes_received - received.stats.resume_position,
                 duration,
                 received.stats.was_resumed,
                 received.stats.resume_position,
                 description,
-            ))
+            ));
+        }
+
+        self.status = TransferStatus::Completed;
+        results
+    }
+
+    /// Receive using Ymodem or Ymodem-G
+    ///
+    /// Ymodem-G is requested from the sender with 'G'; it cannot recover
+    /// from errors, so the first one ends the batch.
+    async fn receive_batch_ymodem<P: TransferProgress>(
+        &mut self,
+        progress: &mut P,
+    ) -> Vec<UploadResult> {
+        let streaming = self.config.protocol == Protocol::YmodemG;
+        let mut receiver = YmodemReceiver::new(
+            &mut self.stream,
+            block_receiver_config(&self.config),
+            streaming,
+        );
+        let mut results = Vec::new();
+
+        self.status = TransferStatus::Initializing;
+        loop {
+            let metadata = match receiver.next_file().await {
+                Ok(Some(metadata)) => metadata,
+                Ok(None) => break,
+                Err(e) => {
+                    progress.on_error(&xmodem_to_zmodem_error(e));
+                    let _ = receiver.cancel().await;
+                    results.push(UploadResult::failed("unknown".to_string()));
+                    break;
+                }
+            };
+
+            let Some(filename) = local_file_name(&metadata.name) else {
+                let _ = receiver.cancel().await;
+                results.push(UploadResult::failed(metadata.name));
+                break;
+            };
+            let file_path = self.upload_dir.join(&filename);
+            let file_info = ZmodemFileInfo::new(&filename, metadata.size.unwrap_or(0));
+            let mut file_progress = FileProgress {
+                file_index: results.len(),
+                total_files: results.len() + 1,
+                file_path: file_path.clone(),
+                file_name: filename.clone(),
+                bytes_sent: 0,
+                bytes_total: file_info.size,
+                retries: 0,
+            };
+
+            self.status = TransferStatus::InProgress;
+            progress.on_file_start(&file_info);
+            let received = match File::create(&file_path).await {
+                Ok(mut file) => {
+                    receiver
+                        .receive_file(&metadata, &mut file, |received| {
+                            file_progress.bytes_sent = received;
+                            progress.on_progress(&file_progress);
+                        })
+                        .await
+                }
+                Err(e) => Err(e.into()),
+            };
+
+            match received {
+                Ok(stats) => {
+                    progress.on_file_complete(&file_info, &transfer_stats(&file_info, &stats));
+                    let duration = self.start_time.map(|t| t.elapsed()).unwrap_or_default();
+                    let description = Self::extract_description(&file_path).await;
+                    results.push(UploadResult::success(
+                        file_path,
+                        filename,
+                        stats.bytes_received,
+                        duration,
+                        false,
+                        0,
+                        description,
+                    ));
+                }
+                Err(e) => {
+                    progress.on_error(&xmodem_to_zmodem_error(e));
+                    let _ = receiver.cancel().await;
+                    results.push(UploadResult::failed(filename));
+                    break;
+                }
+            }
+        }
+
+        self.status = if results
+            .iter()
+            .all(|r| r.status == TransferStatus::Completed)
+        {
+            TransferStatus::Completed
         } else {
+            TransferStatus::Failed
+        };
+        results
+    }
+
+    /// Receive using Xmodem
+    ///
+    /// The file is stored under the name given with
+    /// [`set_filename`](Self::set_filename).
+    async fn receive_xmodem<P: TransferProgress>(&mut self, progress: &mut P) -> Vec<UploadResult> {
+        let Some(filename) = self.filename.as_deref().and_then(local_file_name) else {
             self.status = TransferStatus::Failed;
-            Ok(UploadResult::failed("unknown".to_string()))
-        }
+            return vec![UploadResult::failed("unknown".to_string())];
+        };
+        let file_path = self.upload_dir.join(&filename);
+        let file_info = ZmodemFileInfo::new(&filename, 0);
+        let mut file_progress = FileProgress {
+            file_index: 0,
+            total_files: 1,
+            file_path: file_path.clone(),
+            file_name: filename.clone(),
+            bytes_sent: 0,
+            bytes_total: 0,
+            retries: 0,
+        };
+
+        self.status = TransferStatus::InProgress;
+        progress.on_file_start(&file_info);
+        let mut receiver =
+            AsyncXmodemReceiver::new(&mut self.stream, block_receiver_config(&self.config));
+        let received = match File::create(&file_path).await {
+            Ok(mut file) => {
+                receiver
+                    .receive_file(&mut file, |received| {
+                        file_progress.bytes_sent = received;
+                        progress.on_progress(&file_progress);
+                    })
+                    .await
+            }
+            Err(e) => Err(e.into()),
+        };
+
+        let stats = match received {
+            Ok(stats) => stats,
+            Err(e) => {
+                progress.on_error(&xmodem_to_zmodem_error(e));
+                let _ = receiver.cancel().await;
+                self.status = TransferStatus::Failed;
+                return vec![UploadResult::failed(filename)];
+            }
+        };
+
+        progress.on_file_complete(&file_info, &transfer_stats(&file_info, &stats));
+        self.status = TransferStatus::Completed;
+        let duration = self.start_time.map(|t| t.elapsed()).unwrap_or_default();
+        let description = Self::extract_description(&file_path).await;
+
+        vec![UploadResult::success(
+            file_path,
+            filename,
+            stats.bytes_received,
+            duration,
+            false,
+            0,
+            description,
+        )]
     }
 
     /// Extract FILE_ID.DIZ from an uploaded file
-    async fn extract_description(&self, file_path: &Path) -> Option<String> {
+    async fn extract_description(file_path: &Path) -> Option<String> {
         // Use the auto-detecting extraction function
         diz::extract_file_id_diz(file_path).await.ok().flatten()
     }
 
     /// Receive multiple files in batch mode
     ///
+    /// Xmodem receives a single file. Files received before a failure are
+    /// returned ahead of the failed result.
+    ///
     /// # Arguments
     ///
     /// * `progress` - Progress callback
@@ -259,73 +434,45 @@ impl<S: AsyncRead + AsyncWrite + Unpin> UploadManager<S> {
         self.status = TransferStatus::Initializing;
         self.start_time = Some(Instant::now());
 
+        if let Err(e) = tokio::fs::create_dir_all(&self.upload_dir).await {
+            progress.on_error(&e.into());
+            self.status = TransferStatus::Failed;
+            return vec![UploadResult::failed("unknown".to_string())];
+        }
+
         match self.config.protocol {
             Protocol::Zmodem => self.receive_batch_zmodem(progress).await,
-            _ => vec![UploadResult::failed("unsupported".to_string())],
+            Protocol::Ymodem | Protocol::YmodemG => self.receive_batch_ymodem(progress).await,
+            Protocol::Xmodem => self.receive_xmodem(progress).await,
         }
     }
+}
 
-    /// Receive batch using Zmodem protocol
-    async fn receive_batch_zmodem<P: TransferProgress>(
-        &mut self,
-        _progress: &mut P,
-    ) -> Vec<UploadResult> {
-        let receiver_config = ReceiverConfig {
-            buffer_size: self.config.buffer_size,
-            timeout_ms: self.config.timeout_ms,
-            max_retries: self.config.max_retries,
-            use_crc32: self.config.use_crc32,
-            escape_control: self.config.escape_control,
-            escape_8bit: self.config.escape_8bit,
-            allow_resume: self.config.enable_resume,
-            overwrite_existing: self.config.overwrite_existing,
-        };
-
-        let stream = std::mem::replace(&mut self.stream, unsafe { std::mem::zeroed() });
-
-        let mut receiver = ZmodemReceiver::new(stream, receiver_config);
-
-        // Initialize
-        self.status = TransferStatus::Initializing;
-        if receiver.init().await.is_err() {
-            self.status = TransferStatus::Failed;
-            return vec![UploadResult::failed("init_failed".to_string())];
-        }
-
-        // Receive all files
-        self.status = TransferStatus::InProgress;
-        let received_files = match receiver.receive_files(&self.upload_dir).await {
-            Ok(files) => files,
-            Err(_) => {
-                self.status = TransferStatus::Failed;
-                return vec![UploadResult::failed("receive_failed".to_string())];
-            }
-        };
-
-        // Finish session
-        let _ = receiver.finish().await;
-
-        let duration = self.start_time.map(|t| t.elapsed()).unwrap_or_default();
-
-        // Process each received file
-        let mut results = Vec::with_capacity(received_files.len());
-        for received in received_files {
-            let description = self.extract_description(&received.saved_path).await;
+/// Xmodem-family receiver settings from the transfer configuration
+fn block_receiver_config(config: &TransferConfig) -> XmodemReceiverConfig {
+    XmodemReceiverConfig {
+        variant: XmodemVariant::Crc,
+        max_retries: config.max_retries as usize,
+        timeout: Duration::from_millis(config.timeout_ms),
+    }
+}
 
-            results.push(UploadResult::success(
-                received.saved_path,
-                received.file_info.name.clone(),
-                received.stats.bytes_received,
-                duration,
-                received.stats.was_resumed,
-                received.stats.resume_position,
-                description,
-            ));
-        }
+/// Report an Xmodem-family transfer through the Zmodem progress interface
+fn transfer_stats(file_info: &ZmodemFileInfo, stats: &XmodemReceiveStats) -> TransferStats {
+    let mut transfer_stats = TransferStats::new(file_info.size.max(stats.bytes_received));
+    transfer_stats.bytes_sent = stats.bytes_received;
+    transfer_stats.retries = (stats.blocks_retried + stats.errors) as u32;
+    transfer_stats.complete();
+    transfer_stats
+}
 
-        self.status = TransferStatus::Completed;
-        results
-    }
+/// The name a sender-supplied file name is stored under
+///
+/// Only the final path component is kept, so an upload can never land
+/// outside the upload directory.
+fn local_file_name(name: &str) -> Option<String> {
+    let base = name.rsplit(['/', '\\']).next()?.trim();
+    (!base.is_empty() && base != "." && base != "..").then(|| base.to_string())
 }
 
 #[cfg(test)]
@@ -384,4 +531,38 @@ mod tests {
         assert!(manager.target_area.is_some());
         assert_eq!(manager.target_area.as_ref().unwrap().name, "Games");
     }
+
+    #[tokio::test]
+    async fn test_receive_file_xmodem() {
+        use crate::screens::progress::{TransferProgressScreen, TransferStatus as ScreenStatus};
+        use impulse_protocol::xmodem::{AsyncXmodemSender, SenderConfig};
+
+        let dir = tempfile::tempdir().unwrap();
+        let (server, caller) = tokio::io::duplex(4096);
+        let sender = tokio::spawn(async move {
+            AsyncXmodemSender::new(caller, SenderConfig::default())
+                .send_file(&mut &b"Hello from the caller"[..], |_| {})
+                .await
+                .unwrap()
+        });
+
+        let config = TransferConfig::default().with_protocol(Protocol::Xmodem);
+        let mut manager = UploadManager::new(server, config, dir.path().join("incoming"));
+        manager.set_filename("../hello.txt".to_string());
+        let mut progress = TransferProgressScreen::upload("hello.txt".to_string());
+        let result = manager.receive_file(&mut progress).await.unwrap();
+
+        sender.await.unwrap();
+        assert_eq!(result.status, TransferStatus::Completed);
+        assert_eq!(result.bytes_received, 21);
+        assert_eq!(
+            result.file_path,
+            dir.path().join("incoming").join("hello.txt")
+        );
+        assert!(matches!(progress.status, ScreenStatus::Complete));
+        assert_eq!(
+            std::fs::read(&result.file_path).unwrap(),
+            b"Hello from the caller"
+        );
+    }
 }
@@ -32,6 +32,18 @@ pub struct UploadConfig {
 
     /// Enable duplicate detection
     pub enable_duplicate_check: bool,
+
+    /// File points awarded per `file_point_base_kb` uploaded (0 = none)
+    #[serde(default)]
+    pub file_point_ratio: u8,
+
+    /// Upload size in KB that earns `file_point_ratio` file points
+    #[serde(default = "default_file_point_base_kb")]
+    pub file_point_base_kb: u32,
+}
+
+fn default_file_point_base_kb() -> u32 {
+    100
 }
 
 impl Default for UploadConfig {
@@ -49,6 +61,8 @@ impl Default for UploadConfig {
             clamav_socket: "/var/run/clamav/clamd.ctl".to_string(),
             quarantine_dir: "/var/bbs/quarantine".to_string(),
             enable_duplicate_check: true,
+            file_point_ratio: 0,
+            file_point_base_kb: default_file_point_base_kb(),
         }
     }
 }
@@ -95,6 +109,12 @@ impl UploadConfig {
         self
     }
 
+    /// Set the directory infected uploads are moved to
+    pub fn with_quarantine_dir(mut self, dir: impl Into<String>) -> Self {
+        self.quarantine_dir = dir.into();
+        self
+    }
+
     /// Disable virus scanning
     pub fn disable_virus_scan(mut self) -> Self {
         self.enable_virus_scan = false;
@@ -107,6 +127,31 @@ impl UploadConfig {
         self
     }
 
+    /// Award file points for uploads
+    ///
+    /// Every `base_kb` uploaded earns `ratio` file points.
+    pub fn with_file_points(mut self, ratio: u8, base_kb: u32) -> Self {
+        self.file_point_ratio = ratio;
+        self.file_point_base_kb = base_kb;
+        self
+    }
+
+    /// File points earned by uploading a file of `size_bytes`
+    ///
+    /// Follows the original auto file point compensation: the size in KB
+    /// over the base size, times the ratio, rounded, and at least one point
+    /// per file. No points are awarded when compensation is off.
+    pub fn file_points(&self, size_bytes: u64) -> i16 {
+        if self.file_point_ratio == 0 || self.file_point_base_kb == 0 {
+            return 0;
+        }
+
+        let kilobytes = size_bytes as f64 / 1024.0;
+        let points =
+            kilobytes / f64::from(self.file_point_base_kb) * f64::from(self.file_point_ratio);
+        points.round().clamp(1.0, f64::from(i16::MAX)) as i16
+    }
+
     /// Check if extension is allowed
     pub fn is_extension_allowed(&self, ext: &str) -> bool {
         let ext_lower = ext.to_lowercase();
@@ -203,4 +248,15 @@ mod tests {
         assert!(config.is_extension_allowed("Zip"));
         assert!(config.is_extension_allowed("zip"));
     }
+
+    #[test]
+    fn test_file_points() {
+        let config = UploadConfig::new();
+        assert_eq!(config.file_points(500 * 1024), 0); // Compensation off
+
+        let config = UploadConfig::new().with_file_points(2, 100);
+        assert_eq!(config.file_points(500 * 1024), 10);
+        assert_eq!(config.file_points(1024), 1); // At least one point
+        assert_eq!(config.file_points(125 * 1024), 3); // 2.5 rounds up
+    }
 }
@@ -1,35 +1,42 @@
 //! Upload processing pipeline
 
+use crate::diz;
 use crate::error::{FileError, Result};
-use crate::scanning::VirusScanner;
+use crate::scanning::{NotificationSender, QuarantineManager, VirusNotification, VirusScanner};
 use crate::traits::FileAreaManager;
+use crate::types::SearchCriteria;
 use crate::upload::cleanup::UploadRollback;
 use crate::upload::config::UploadConfig;
 use crate::upload::metadata::create_file_entry;
-use crate::upload::stats::{AreaStats, UploadStats};
+use crate::upload::stats::UploadStats;
 use crate::upload::storage::{FileStorage, PendingUpload};
 use crate::validation::{
-    check_duplicate, check_extension, check_permissions, check_quota, check_size,
+    check_duplicate, check_extension, check_file_name, check_permissions, check_quota, check_size,
 };
+use chrono::{Local, NaiveDate};
 use impulse_types::file::FileEntry;
+use std::path::PathBuf;
 use std::sync::Arc;
+use tokio::sync::RwLock;
 
 /// Upload processor
 ///
 /// Orchestrates the complete upload pipeline:
 /// 1. Validation (size, extension, quota, permissions)
 /// 2. Duplicate detection (SHA-256 hash)
-/// 3. Virus scanning (ClamAV)
+/// 3. Virus scanning (ClamAV), quarantining infected files
 /// 4. FILE_ID.DIZ extraction
 /// 5. File storage (atomic)
 /// 6. Database record creation
 /// 7. Statistics update
+///
+/// Any failure removes the received file and anything already stored.
 pub struct UploadProcessor {
     /// Virus scanner
     scanner: Arc<dyn VirusScanner>,
 
     /// File area manager
-    file_manager: Arc<dyn FileAreaManager>,
+    file_manager: Arc<RwLock<dyn FileAreaManager>>,
 
     /// Upload configuration
     config: UploadConfig,
@@ -37,39 +44,84 @@ pub struct UploadProcessor {
     /// File storage
     storage: FileStorage,
 
+    /// Where infected uploads are moved
+    quarantine: QuarantineManager,
+
+    /// Who is told about infected uploads
+    notifier: Option<Arc<dyn NotificationSender>>,
+
     /// Upload statistics (per-user)
     user_stats: std::collections::HashMap<u32, UploadStats>,
+
+    /// Day the daily statistics were last reset
+    stats_date: NaiveDate,
 }
 
 impl UploadProcessor {
     /// Create a new upload processor
+    ///
+    /// Files are stored in the area's configured path, or under
+    /// `storage_base` for areas without one.
     pub fn new(
         scanner: Arc<dyn VirusScanner>,
-        file_manager: Arc<dyn FileAreaManager>,
+        file_manager: Arc<RwLock<dyn FileAreaManager>>,
         config: UploadConfig,
         storage_base: impl Into<std::path::PathBuf>,
     ) -> Self {
+        let quarantine = QuarantineManager::new(&config.quarantine_dir);
         Self {
             scanner,
             file_manager,
             config,
             storage: FileStorage::new(storage_base),
+            quarantine,
+            notifier: None,
             user_stats: std::collections::HashMap::new(),
+            stats_date: Local::now().date_naive(),
         }
     }
 
+    /// Send a notification for every infected upload
+    pub fn with_notifier(mut self, notifier: Arc<dyn NotificationSender>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Get the upload configuration
+    pub fn config(&self) -> &UploadConfig {
+        &self.config
+    }
+
     /// Process a pending upload through the complete pipeline
+    ///
+    /// On success the file has been moved out of `upload.temp_path` into
+    /// its area and catalogued. On failure the temporary file is removed
+    /// (or quarantined, if infected).
     pub async fn process(&mut self, upload: PendingUpload) -> Result<FileEntry> {
         // Create rollback manager
         let mut rollback = UploadRollback::new();
         rollback.add_temp_file(upload.temp_path.clone());
 
+        // Daily quotas start over at midnight
+        let today = Local::now().date_naive();
+        if self.stats_date != today {
+            self.reset_all_daily_stats();
+            self.stats_date = today;
+        }
+
         // 1. Validate upload
         self.validate(&upload).await?;
 
         // 2. Check for duplicates (if enabled)
         if self.config.enable_duplicate_check {
-            check_duplicate(&upload.temp_path, &*self.file_manager).await?;
+            let file_manager = self.file_manager.read().await;
+            check_duplicate(
+                &upload.temp_path,
+                upload.size_bytes,
+                &*file_manager,
+                &self.storage,
+            )
+            .await?;
         }
 
         // 3. Scan for viruses (if enabled)
@@ -77,43 +129,36 @@ impl UploadProcessor {
             let scan_result = self.scanner.scan_file(&upload.temp_path).await?;
 
             if !scan_result.is_clean {
-                // Quarantine the file
-                if let Some(threat) = &scan_result.threat_name {
-                    return Err(FileError::VirusDetected(threat.clone()));
-                } else {
-                    return Err(FileError::VirusDetected("Unknown threat".to_string()));
-                }
+                let threat = scan_result
+                    .threat_name
+                    .unwrap_or_else(|| "Unknown threat".to_string());
+                self.quarantine_upload(&upload, &threat).await;
+                return Err(FileError::VirusDetected(threat));
             }
         }
 
-        // 4. Extract FILE_ID.DIZ (will be implemented in enhanced diz module)
-        let description = self.extract_description(&upload)?;
+        // 4. Extract FILE_ID.DIZ, falling back to the uploader's description
+        let description = diz::extract_file_id_diz(&upload.temp_path)
+            .await
+            .ok()
+            .flatten()
+            .or_else(|| upload.manual_description.clone());
 
         // 5. Store file atomically
-        let final_path = self
-            .storage
-            .store_file(&upload.temp_path, upload.area_id, &upload.filename)
-            .await?;
-
-        rollback.add_stored_file(final_path.clone());
-
-        // 6. Create database record
-        let file_id = self.generate_file_id().await?;
-        let file_entry = create_file_entry(&upload, file_id, description)?;
-
-        // 7. Add to file manager
-        let mut fm = self.file_manager.clone();
-        Arc::get_mut(&mut fm)
-            .ok_or_else(|| FileError::InvalidPath("File manager is locked".to_string()))?
-            .add_file(file_entry.clone())
-            .await?;
-
-        // 8. Update area statistics
-        if let Some(mut area) = self.file_manager.get_area(upload.area_id).await? {
-            AreaStats::increment_file_count(&mut area);
-        }
-
-        // 9. Update user statistics
+        let final_path = self.store_file(&upload).await?;
+        rollback.add_stored_file(final_path);
+
+        // 6. Create database record and add it to the area, which also
+        //    counts it in the area's statistics
+        let file_entry = {
+            let mut file_manager = self.file_manager.write().await;
+            let file_id = next_file_id(&*file_manager).await?;
+            let file_entry = create_file_entry(&upload, file_id, description)?;
+            file_manager.add_file(file_entry.clone()).await?;
+            file_entry
+        };
+
+        // 7. Update user statistics
         let stats = self.user_stats.entry(upload.uploader_id).or_default();
         stats.record_upload(upload.size_bytes);
 
@@ -125,6 +170,9 @@ impl UploadProcessor {
 
     /// Validate upload against all constraints
     async fn validate(&self, upload: &PendingUpload) -> Result<()> {
+        // Check the name cannot escape the area directory
+        check_file_name(&upload.filename)?;
+
         // Check file size
         check_size(upload.size_bytes, self.config.max_file_size)?;
 
@@ -145,26 +193,81 @@ impl UploadProcessor {
         )?;
 
         // Check area permissions
-        if let Some(area) = self.file_manager.get_area(upload.area_id).await? {
+        let file_manager = self.file_manager.read().await;
+        if let Some(area) = file_manager.get_area(upload.area_id).await? {
             check_permissions(&area)?;
         } else {
             return Err(FileError::AreaNotFound(upload.area_id));
         }
 
+        // Check the area does not already list a file by this name
+        let taken = file_manager
+            .search_files(&SearchCriteria::new().in_area(upload.area_id))
+            .await?
+            .iter()
+            .any(|file| file.filename.eq_ignore_ascii_case(&upload.filename));
+        if taken {
+            return Err(FileError::InvalidPath(format!(
+                "File {} already exists in area {}",
+                upload.filename, upload.area_id
+            )));
+        }
+
         Ok(())
     }
 
-    /// Extract file description (placeholder - will use enhanced diz module)
-    fn extract_description(&self, upload: &PendingUpload) -> Result<Option<String>> {
-        // This will be replaced with enhanced DIZ extraction
-        Ok(upload.manual_description.clone())
+    /// Move an infected upload to quarantine and notify the SysOp
+    ///
+    /// If the file cannot be quarantined it is left for the rollback to
+    /// delete.
+    async fn quarantine_upload(&self, upload: &PendingUpload, threat: &str) {
+        let mut notification = VirusNotification::new(
+            upload.uploader_name.clone(),
+            upload.uploader_id,
+            upload.filename.clone(),
+            threat.to_string(),
+            upload.area_id,
+        );
+        if let Ok(path) = self
+            .quarantine
+            .quarantine_file(&upload.temp_path, threat)
+            .await
+        {
+            notification = notification.with_quarantine_path(path);
+        }
+
+        if let Some(notifier) = &self.notifier {
+            let _ = notifier.send_notification(&notification).await;
+        }
     }
 
-    /// Generate unique file ID
-    async fn generate_file_id(&self) -> Result<u32> {
-        // Simple implementation - in production, this would query the database
-        // for the next available ID
-        Ok(1)
+    /// Move the upload into its area's directory
+    async fn store_file(&self, upload: &PendingUpload) -> Result<PathBuf> {
+        let area_path = self
+            .file_manager
+            .read()
+            .await
+            .get_area(upload.area_id)
+            .await?
+            .and_then(|area| area.path);
+
+        let Some(area_path) = area_path else {
+            return self
+                .storage
+                .store_file(&upload.temp_path, upload.area_id, &upload.filename)
+                .await;
+        };
+
+        tokio::fs::create_dir_all(&area_path).await?;
+        let target_path = area_path.join(&upload.filename);
+        if target_path.exists() {
+            return Err(FileError::InvalidPath(format!(
+                "File {} already exists in area {}",
+                upload.filename, upload.area_id
+            )));
+        }
+        tokio::fs::rename(&upload.temp_path, &target_path).await?;
+        Ok(target_path)
     }
 
     /// Get user upload statistics
@@ -187,6 +290,12 @@ impl UploadProcessor {
     }
 }
 
+/// The next free file ID across all areas
+async fn next_file_id(file_manager: &dyn FileAreaManager) -> Result<u32> {
+    let files = file_manager.search_files(&SearchCriteria::new()).await?;
+    Ok(files.iter().map(|file| file.id).max().unwrap_or(0) + 1)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -216,7 +325,7 @@ mod tests {
         let area = FileArea::new(1, "Test".to_string(), "Test area".to_string()).allow_uploads();
         file_manager.add_area(area).await.unwrap();
 
-        let file_manager: Arc<dyn FileAreaManager> = Arc::new(file_manager);
+        let file_manager: Arc<RwLock<dyn FileAreaManager>> = Arc::new(RwLock::new(file_manager));
         let config = UploadConfig::new().disable_duplicate_check();
         let temp_dir = tempdir().unwrap();
 
@@ -254,6 +363,16 @@ mod tests {
         assert!(matches!(result, Err(FileError::ExtensionNotAllowed(_))));
     }
 
+    #[tokio::test]
+    async fn test_validate_path_in_file_name() {
+        let (processor, _temp) = create_test_processor().await;
+        let mut upload = create_test_upload();
+        upload.filename = "../../x.zip".to_string();
+
+        let result = processor.validate(&upload).await;
+        assert!(matches!(result, Err(FileError::InvalidPath(_))));
+    }
+
     #[tokio::test]
     async fn test_validate_area_not_found() {
         let (processor, _temp) = create_test_processor().await;
@@ -267,7 +386,8 @@ mod tests {
     #[test]
     fn test_get_user_stats() {
         let scanner = Arc::new(MockScanner::new(true));
-        let file_manager: Arc<dyn FileAreaManager> = Arc::new(InMemoryFileAreaManager::new());
+        let file_manager: Arc<RwLock<dyn FileAreaManager>> =
+            Arc::new(RwLock::new(InMemoryFileAreaManager::new()));
         let config = UploadConfig::new();
         let temp = tempdir().unwrap();
 
@@ -288,7 +408,8 @@ mod tests {
     #[test]
     fn test_reset_user_daily_stats() {
         let scanner = Arc::new(MockScanner::new(true));
-        let file_manager: Arc<dyn FileAreaManager> = Arc::new(InMemoryFileAreaManager::new());
+        let file_manager: Arc<RwLock<dyn FileAreaManager>> =
+            Arc::new(RwLock::new(InMemoryFileAreaManager::new()));
         let config = UploadConfig::new();
         let temp = tempdir().unwrap();
 
@@ -305,4 +426,101 @@ mod tests {
         assert_eq!(user_stats.bytes_today, 0);
         assert_eq!(user_stats.total_files, 1); // Preserved
     }
+
+    /// A processor storing under a temp dir, with one upload-enabled area
+    async fn create_pipeline(
+        scanner: MockScanner,
+    ) -> (
+        UploadProcessor,
+        Arc<RwLock<InMemoryFileAreaManager>>,
+        tempfile::TempDir,
+    ) {
+        let mut file_manager = InMemoryFileAreaManager::new();
+        let area = FileArea::new(1, "Test".to_string(), "Test area".to_string()).allow_uploads();
+        file_manager.add_area(area).await.unwrap();
+        let file_manager = Arc::new(RwLock::new(file_manager));
+
+        let temp_dir = tempdir().unwrap();
+        std::fs::create_dir(temp_dir.path().join("incoming")).unwrap();
+        let config = UploadConfig::new()
+            .with_quarantine_dir(temp_dir.path().join("quarantine").to_string_lossy());
+        let processor = UploadProcessor::new(
+            Arc::new(scanner),
+            file_manager.clone(),
+            config,
+            temp_dir.path(),
+        );
+
+        (processor, file_manager, temp_dir)
+    }
+
+    /// Write a received file into the incoming directory
+    fn received(temp_dir: &tempfile::TempDir, filename: &str, content: &[u8]) -> PendingUpload {
+        let temp_path = temp_dir.path().join("incoming").join(filename);
+        std::fs::write(&temp_path, content).unwrap();
+        PendingUpload {
+            temp_path,
+            filename: filename.to_string(),
+            size_bytes: content.len() as u64,
+            ..create_test_upload()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_process_catalogues_upload() {
+        let (mut processor, file_manager, temp) = create_pipeline(MockScanner::clean()).await;
+        let upload = received(&temp, "test.zip", b"uploaded content");
+        let temp_path = upload.temp_path.clone();
+
+        let entry = processor.process(upload).await.unwrap();
+
+        assert_eq!(entry.id, 1);
+        assert_eq!(entry.description, "Test file");
+        assert_eq!(entry.size_bytes, 16);
+        assert!(!temp_path.exists());
+        assert!(temp.path().join("area_001").join("test.zip").is_file());
+
+        let file_manager = file_manager.read().await;
+        assert_eq!(file_manager.count_files(1).await.unwrap(), 1);
+        assert_eq!(
+            file_manager.get_area(1).await.unwrap().unwrap().file_count,
+            1
+        );
+        assert_eq!(processor.get_user_stats(42).unwrap().total_files, 1);
+    }
+
+    #[tokio::test]
+    async fn test_process_rejects_duplicate() {
+        let (mut processor, file_manager, temp) = create_pipeline(MockScanner::clean()).await;
+        let first = received(&temp, "first.zip", b"same content");
+        processor.process(first).await.unwrap();
+
+        let second = received(&temp, "second.zip", b"same content");
+        let temp_path = second.temp_path.clone();
+        let result = processor.process(second).await;
+
+        assert!(matches!(result, Err(FileError::DuplicateFile(_))));
+        assert!(!temp_path.exists()); // Rolled back
+        assert_eq!(file_manager.read().await.count_files(1).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_process_quarantines_infected_upload() {
+        let (mut processor, file_manager, temp) =
+            create_pipeline(MockScanner::infected("EICAR-Test")).await;
+        let upload = received(&temp, "test.zip", b"infected content");
+        let temp_path = upload.temp_path.clone();
+
+        let result = processor.process(upload).await;
+
+        assert!(matches!(result, Err(FileError::VirusDetected(ref t)) if t == "EICAR-Test"));
+        assert!(!temp_path.exists());
+        assert_eq!(
+            std::fs::read_dir(temp.path().join("quarantine"))
+                .unwrap()
+                .count(),
+            1
+        );
+        assert_eq!(file_manager.read().await.count_files(1).await.unwrap(), 0);
+    }
 }
@@ -1,8 +1,11 @@
 //! Duplicate file detection using SHA-256 hashing
 
-use crate::error::Result;
+use crate::error::{FileError, Result};
 use crate::traits::FileAreaManager;
+use crate::types::SearchCriteria;
+use crate::upload::storage::FileStorage;
 use hex;
+use impulse_types::file::FileEntry;
 use sha2::{Digest, Sha256};
 use std::path::Path;
 use tokio::fs::File;
@@ -34,33 +37,57 @@ pub async fn calculate_file_hash(path: &Path) -> Result<String> {
     Ok(hex::encode(hash))
 }
 
-/// Check if file is a duplicate based on hash
+/// Check if file is a duplicate of one already in the file areas
 ///
-/// In a real implementation, this would query a database of file hashes.
-/// This is a placeholder that always returns Ok (no duplicate).
+/// Catalogued files of the same size are hashed and compared with the
+/// upload. Files in areas with a configured path are looked up there;
+/// others in the upload storage layout. Catalogued files missing from disk
+/// are skipped.
 ///
-/// # Current Behavior
+/// # Arguments
 ///
-/// This function currently:
-/// 1. Calculates the SHA-256 hash of the file
-/// 2. Returns Ok without checking for duplicates
+/// * `path` - Path to the uploaded file
+/// * `size_bytes` - Size of the uploaded file
+/// * `file_manager` - File areas to compare against
+/// * `storage` - Storage for areas without a configured path
 ///
-/// # Production Implementation
+/// # Errors
 ///
-/// For production use, this should:
-/// 1. Query the database for existing files with the calculated hash
-/// 2. Compare file sizes as a quick preliminary check
-/// 3. Return Err(FileError::DuplicateFile(hash)) if duplicate found
-/// 4. Consider implementing configurable duplicate handling policies:
-///    - Reject duplicates (current planned behavior)
-///    - Allow duplicates with different names
-///    - Replace existing file
-pub async fn check_duplicate(path: &Path, _file_manager: &dyn FileAreaManager) -> Result<()> {
-    // Calculate hash
-    let _hash = calculate_file_hash(path).await?;
-
-    // NOTE: Stub implementation - always returns Ok (no duplicate detected).
-    // See function documentation for production implementation requirements.
+/// Returns `FileError::DuplicateFile` with the upload's hash if a file
+/// with the same content exists.
+pub async fn check_duplicate(
+    path: &Path,
+    size_bytes: u64,
+    file_manager: &dyn FileAreaManager,
+    storage: &FileStorage,
+) -> Result<()> {
+    let candidates: Vec<FileEntry> = file_manager
+        .search_files(&SearchCriteria::new())
+        .await?
+        .into_iter()
+        .filter(|file| file.size_bytes == size_bytes)
+        .collect();
+    if candidates.is_empty() {
+        return Ok(());
+    }
+
+    let hash = calculate_file_hash(path).await?;
+    for file in candidates {
+        let existing = match file_manager
+            .get_area(file.area_id)
+            .await?
+            .and_then(|a| a.path)
+        {
+            Some(area_path) => area_path.join(&file.filename),
+            None => storage.file_path(file.area_id, &file.filename),
+        };
+        if !existing.is_file() || existing == path {
+            continue;
+        }
+        if calculate_file_hash(&existing).await? == hash {
+            return Err(FileError::DuplicateFile(hash));
+        }
+    }
 
     Ok(())
 }
@@ -126,4 +153,49 @@ mod tests {
             "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
         );
     }
+
+    #[tokio::test]
+    async fn test_check_duplicate() {
+        use crate::manager::InMemoryFileAreaManager;
+        use crate::types::FileArea;
+
+        let temp = tempfile::tempdir().unwrap();
+        let storage = FileStorage::new(temp.path());
+        let mut file_manager = InMemoryFileAreaManager::new();
+        file_manager
+            .add_area(FileArea::new(1, "Test".to_string(), String::new()))
+            .await
+            .unwrap();
+
+        storage.ensure_area_dir(1).await.unwrap();
+        std::fs::write(storage.file_path(1, "stored.txt"), b"stored content").unwrap();
+        file_manager
+            .add_file(FileEntry {
+                id: 1,
+                filename: "stored.txt".to_string(),
+                description: String::new(),
+                uploader: "testuser".to_string(),
+                uploader_id: 1,
+                size_bytes: 14,
+                upload_date: chrono::Utc::now(),
+                area_id: 1,
+                download_count: 0,
+                is_offline: false,
+                is_missing: false,
+                password: None,
+                cost_credits: None,
+            })
+            .await
+            .unwrap();
+
+        let same = temp.path().join("same.txt");
+        std::fs::write(&same, b"stored content").unwrap();
+        let result = check_duplicate(&same, 14, &file_manager, &storage).await;
+        assert!(matches!(result, Err(FileError::DuplicateFile(_))));
+
+        let different = temp.path().join("different.txt");
+        std::fs::write(&different, b"other content!").unwrap();
+        let result = check_duplicate(&different, 14, &file_manager, &storage).await;
+        assert!(result.is_ok());
+    }
 }
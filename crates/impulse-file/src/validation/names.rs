@@ -0,0 +1,63 @@
+//! File name validation
+
+use crate::error::{FileError, Result};
+
+/// Check that a file name names a file inside its area
+///
+/// Names are joined to the area directory when the upload is stored, so
+/// anything with a path separator, a drive prefix or a `..` component is
+/// refused rather than trusted.
+///
+/// # Arguments
+///
+/// * `filename` - File name as given by the uploader
+///
+/// # Returns
+///
+/// Ok if the name is a plain file name, error otherwise
+pub fn check_file_name(filename: &str) -> Result<()> {
+    let trimmed = filename.trim();
+    let plain = !trimmed.is_empty()
+        && trimmed != "."
+        && !filename.contains("..")
+        && !filename.contains(['/', '\\', ':', '\0']);
+    if plain {
+        Ok(())
+    } else {
+        Err(FileError::InvalidPath(format!(
+            "Invalid file name: {}",
+            filename
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_file_name_plain() {
+        assert!(check_file_name("GAME.ZIP").is_ok());
+        assert!(check_file_name("my file.txt").is_ok());
+        assert!(check_file_name(".profile").is_ok());
+    }
+
+    #[test]
+    fn test_check_file_name_rejects_paths() {
+        for name in [
+            "../../x",
+            "..",
+            ".",
+            "",
+            "dir/file.zip",
+            "C:\\DOS\\GAME.ZIP",
+            "C:GAME.ZIP",
+            "file..zip",
+        ] {
+            assert!(
+                matches!(check_file_name(name), Err(FileError::InvalidPath(_))),
+                "{name:?} should be refused"
+            );
+        }
+    }
+}
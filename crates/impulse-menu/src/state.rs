@@ -147,6 +147,25 @@ impl MenuState {
     pub fn clear_stack(&mut self) {
         self.menu_stack.clear();
     }
+
+    /// Replace all menu definitions with those of `other`, keeping navigation
+    ///
+    /// Used when menus are reloaded while callers are online. Menus that no
+    /// longer exist are dropped from the navigation stack; if the current menu
+    /// was removed, the state falls back to the most recent remaining menu on
+    /// the stack, or to `other`'s current menu.
+    pub fn replace_menus(&mut self, other: &MenuState) {
+        self.menus = other.menus.clone();
+        self.menu_stack
+            .retain(|name| other.menus.contains_key(name));
+
+        if !self.menus.contains_key(&self.current_menu) {
+            self.current_menu = self
+                .menu_stack
+                .pop()
+                .unwrap_or_else(|| other.current_menu.clone());
+        }
+    }
 }
 
 #[cfg(test)]
@@ -406,4 +425,29 @@ min_security = 0
         assert!(result.is_ok());
         assert_eq!(state.menu_names().len(), 0);
     }
+
+    #[test]
+    fn test_replace_menus_keeps_navigation() {
+        let mut state = MenuState::new("main");
+        state.add_menu(create_test_menu("main", "Main Menu"));
+        state.add_menu(create_test_menu("files", "File Areas"));
+        state.add_menu(create_test_menu("upload", "Upload"));
+        state.navigate_to("files").unwrap();
+
+        let mut reloaded = MenuState::new("main");
+        reloaded.add_menu(create_test_menu("main", "Main Menu"));
+        reloaded.add_menu(create_test_menu("files", "Reloaded File Areas"));
+
+        state.replace_menus(&reloaded);
+        assert_eq!(state.current_name(), "files");
+        assert_eq!(state.current().unwrap().menu.title, "Reloaded File Areas");
+        assert!(!state.has_menu("upload"));
+
+        // A removed current menu falls back to the previous one
+        state.add_menu(create_test_menu("upload", "Upload"));
+        state.navigate_to("upload").unwrap();
+        state.replace_menus(&reloaded);
+        assert_eq!(state.current_name(), "files");
+        assert_eq!(state.breadcrumbs(), vec!["main", "files"]);
+    }
 }
@@ -0,0 +1,323 @@
+//! Message conferences and areas
+//!
+//! Areas are the boards callers read and post in; conferences group them
+//! the way MCONF.DAT grouped BOARDS.DAT. Areas without a conference are
+//! shown in every conference.
+
+use crate::formats::{HudsonMessageBase, JamMessageBase};
+use crate::traits::MessageBase;
+use impulse_types::config::{MessageAreaConfig, MessageBaseFormat, MessageConferenceConfig};
+use std::path::PathBuf;
+
+/// A message conference
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conference {
+    /// Short tag
+    pub tag: String,
+    /// Name shown to callers
+    pub name: String,
+    /// Security level needed to join
+    pub security: u8,
+}
+
+impl Conference {
+    /// Create a conference anyone can join
+    pub fn new(tag: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            tag: tag.into(),
+            name: name.into(),
+            security: 0,
+        }
+    }
+
+    /// Set the security level needed to join
+    pub fn with_security(mut self, security: u8) -> Self {
+        self.security = security;
+        self
+    }
+}
+
+impl From<&MessageConferenceConfig> for Conference {
+    fn from(config: &MessageConferenceConfig) -> Self {
+        Self::new(&config.tag, &config.name).with_security(config.security)
+    }
+}
+
+/// A message area
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageArea {
+    /// Short unique tag
+    pub tag: String,
+    /// Board number in callers' newscan records (0 for none)
+    pub board: u8,
+    /// Name shown to callers
+    pub name: String,
+    /// Base path without extension
+    pub path: PathBuf,
+    /// Storage format
+    pub format: MessageBaseFormat,
+    /// Security level needed to read
+    pub read_security: u8,
+    /// Security level needed to post
+    pub post_security: u8,
+    /// Echomail tag, if the area is exchanged over FidoNet
+    pub echo_tag: Option<String>,
+    /// Conference the area belongs to
+    pub conference: Option<String>,
+}
+
+impl MessageArea {
+    /// Create a JAM area anyone can read and post in
+    pub fn new(tag: impl Into<String>, name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            tag: tag.into(),
+            board: 0,
+            name: name.into(),
+            path: path.into(),
+            format: MessageBaseFormat::Jam,
+            read_security: 0,
+            post_security: 0,
+            echo_tag: None,
+            conference: None,
+        }
+    }
+
+    /// Set the board number
+    pub fn with_board(mut self, board: u8) -> Self {
+        self.board = board;
+        self
+    }
+
+    /// Set the storage format
+    pub fn with_format(mut self, format: MessageBaseFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set the security levels needed to read and post
+    pub fn with_security(mut self, read: u8, post: u8) -> Self {
+        self.read_security = read;
+        self.post_security = post;
+        self
+    }
+
+    /// Set the echomail tag
+    pub fn with_echo_tag(mut self, tag: impl Into<String>) -> Self {
+        self.echo_tag = Some(tag.into());
+        self
+    }
+
+    /// Put the area in a conference
+    pub fn with_conference(mut self, tag: impl Into<String>) -> Self {
+        self.conference = Some(tag.into());
+        self
+    }
+
+    /// Whether a caller at `level` may read the area
+    pub fn can_read(&self, level: u8) -> bool {
+        level >= self.read_security
+    }
+
+    /// Whether a caller at `level` may post in the area
+    ///
+    /// Hudson areas are read-only.
+    pub fn can_post(&self, level: u8) -> bool {
+        self.format == MessageBaseFormat::Jam && self.can_read(level) && level >= self.post_security
+    }
+
+    /// Open the area's message base
+    pub fn open(&self) -> Box<dyn MessageBase> {
+        match self.format {
+            MessageBaseFormat::Jam => Box::new(JamMessageBase::new(&self.path)),
+            MessageBaseFormat::Hudson => Box::new(HudsonMessageBase::new(&self.path)),
+        }
+    }
+}
+
+impl From<&MessageAreaConfig> for MessageArea {
+    fn from(config: &MessageAreaConfig) -> Self {
+        let mut area = Self::new(&config.tag, &config.name, &config.path)
+            .with_board(config.board)
+            .with_format(config.format)
+            .with_security(config.read_security, config.post_security);
+        area.echo_tag = config.echo_tag.clone();
+        area.conference = config.conference.clone();
+        area
+    }
+}
+
+/// The conferences and areas of a system
+#[derive(Debug, Clone, Default)]
+pub struct MessageAreaRegistry {
+    /// Conferences in the order they are listed
+    conferences: Vec<Conference>,
+    /// Areas in the order they are listed
+    areas: Vec<MessageArea>,
+}
+
+impl MessageAreaRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the registry from the configured conferences and areas
+    pub fn from_config(
+        conferences: &[MessageConferenceConfig],
+        areas: &[MessageAreaConfig],
+    ) -> Self {
+        Self {
+            conferences: conferences.iter().map(Conference::from).collect(),
+            areas: areas.iter().map(MessageArea::from).collect(),
+        }
+    }
+
+    /// Add a conference
+    pub fn with_conference(mut self, conference: Conference) -> Self {
+        self.conferences.push(conference);
+        self
+    }
+
+    /// Add an area
+    pub fn with_area(mut self, area: MessageArea) -> Self {
+        self.areas.push(area);
+        self
+    }
+
+    /// Every area
+    pub fn areas(&self) -> &[MessageArea] {
+        &self.areas
+    }
+
+    /// Find an area by tag
+    pub fn area(&self, tag: &str) -> Option<&MessageArea> {
+        self.areas
+            .iter()
+            .find(|area| area.tag.eq_ignore_ascii_case(tag))
+    }
+
+    /// Find the area exchanged under an echomail tag
+    pub fn echo_area(&self, echo_tag: &str) -> Option<&MessageArea> {
+        self.areas.iter().find(|area| {
+            area.echo_tag
+                .as_deref()
+                .is_some_and(|tag| tag.eq_ignore_ascii_case(echo_tag))
+        })
+    }
+
+    /// Find a conference by tag
+    pub fn conference(&self, tag: &str) -> Option<&Conference> {
+        self.conferences
+            .iter()
+            .find(|conference| conference.tag.eq_ignore_ascii_case(tag))
+    }
+
+    /// Conferences a caller at `level` may join
+    pub fn conferences(&self, level: u8) -> Vec<&Conference> {
+        self.conferences
+            .iter()
+            .filter(|conference| level >= conference.security)
+            .collect()
+    }
+
+    /// Areas a caller at `level` may read in a conference
+    ///
+    /// With `None`, or when no conferences are set up, every readable area
+    /// is listed.
+    pub fn areas_in(&self, conference: Option<&str>, level: u8) -> Vec<&MessageArea> {
+        self.areas
+            .iter()
+            .filter(|area| area.can_read(level))
+            .filter(|area| match (conference, &area.conference) {
+                (Some(wanted), Some(tag)) => tag.eq_ignore_ascii_case(wanted),
+                _ => true,
+            })
+            .filter(|area| {
+                area.conference
+                    .as_deref()
+                    .and_then(|tag| self.conference(tag))
+                    .is_none_or(|conference| level >= conference.security)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> MessageAreaRegistry {
+        MessageAreaRegistry::new()
+            .with_conference(Conference::new("local", "Local"))
+            .with_conference(Conference::new("fido", "FidoNet").with_security(20))
+            .with_area(MessageArea::new("general", "General", "/msg/general"))
+            .with_area(
+                MessageArea::new("sysop", "SysOp Only", "/msg/sysop")
+                    .with_security(200, 200)
+                    .with_conference("local"),
+            )
+            .with_area(
+                MessageArea::new("fidotest", "Fido Test", "/msg/fidotest")
+                    .with_echo_tag("FIDO.TEST")
+                    .with_conference("fido"),
+            )
+            .with_area(
+                MessageArea::new("old", "Old Hudson", "/msg/old")
+                    .with_format(MessageBaseFormat::Hudson)
+                    .with_conference("local"),
+            )
+    }
+
+    fn tags(areas: Vec<&MessageArea>) -> Vec<&str> {
+        areas.into_iter().map(|area| area.tag.as_str()).collect()
+    }
+
+    #[test]
+    fn test_areas_in_conference() {
+        let registry = registry();
+        assert_eq!(
+            tags(registry.areas_in(Some("local"), 10)),
+            ["general", "old"]
+        );
+        assert_eq!(
+            tags(registry.areas_in(Some("local"), 255)),
+            ["general", "sysop", "old"]
+        );
+        assert_eq!(tags(registry.areas_in(Some("fido"), 10)), ["general"]);
+        assert_eq!(
+            tags(registry.areas_in(None, 20)),
+            ["general", "fidotest", "old"]
+        );
+        assert_eq!(registry.conferences(10).len(), 1);
+    }
+
+    #[test]
+    fn test_lookup_and_access() {
+        let registry = registry();
+        assert_eq!(registry.echo_area("fido.test").unwrap().tag, "fidotest");
+        assert!(registry.area("GENERAL").is_some());
+        assert!(registry.area("missing").is_none());
+
+        let sysop = registry.area("sysop").unwrap();
+        assert!(!sysop.can_read(100) && sysop.can_post(200));
+        assert!(!registry.area("old").unwrap().can_post(255));
+    }
+
+    #[test]
+    fn test_from_config() {
+        let config = MessageAreaConfig {
+            tag: "general".to_string(),
+            board: 3,
+            name: "General".to_string(),
+            path: PathBuf::from("/msg/general"),
+            echo_tag: Some("LOCAL.GENERAL".to_string()),
+            ..Default::default()
+        };
+        let registry = MessageAreaRegistry::from_config(&[], &[config]);
+        let area = registry.area("general").unwrap();
+        assert_eq!(area.board, 3);
+        assert_eq!(area.post_security, 10);
+        assert_eq!(area.echo_tag.as_deref(), Some("LOCAL.GENERAL"));
+        assert_eq!(area.format, MessageBaseFormat::Jam);
+    }
+}
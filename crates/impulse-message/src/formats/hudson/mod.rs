@@ -447,6 +447,23 @@ impl MessageBase for HudsonMessageBase {
         Ok(())
     }
 
+    async fn last_read(&self, _user_name: &str, _user_id: u32) -> Result<u32> {
+        // Hudson keeps lastread pointers per board in LASTREAD.BBS, indexed
+        // by the other BBS's user file, so there is nothing to look up here
+        Ok(0)
+    }
+
+    async fn set_last_read(
+        &mut self,
+        _user_name: &str,
+        _user_id: u32,
+        _msg_num: u32,
+    ) -> Result<()> {
+        Err(MessageError::WriteError(
+            "Hudson format is read-only. Use JAM format for writing messages.".to_string(),
+        ))
+    }
+
     async fn message_exists(&self, msg_num: u32) -> Result<bool> {
         let total = self.message_count().await?;
         Ok(msg_num >= 1 && msg_num <= total)
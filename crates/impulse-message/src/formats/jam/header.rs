@@ -10,6 +10,18 @@ use binrw::{BinRead, binread};
 use chrono::{DateTime, TimeZone, Utc};
 use std::io::{Read, Seek};
 
+/// Size of the base header at the start of the .JHR file
+pub const JAM_BASE_HEADER_SIZE: u64 = 24;
+
+/// Size of a message header in the .JHR file, not counting its subfields
+pub const JAM_MESSAGE_HEADER_SIZE: usize = 68;
+
+/// Offset of the attribute word within a message header
+pub const JAM_ATTRIBUTE_OFFSET: u64 = 52;
+
+/// Size of a record in the .JDX index file
+pub const JAM_INDEX_RECORD_SIZE: u64 = 8;
+
 /// JAM base header (stored in .JHR file)
 #[binread]
 #[derive(Debug, Clone)]
@@ -135,6 +147,10 @@ pub enum SubfieldType {
     Path = 7,
     /// Seen-by
     SeenBy = 8,
+    /// Path of a file attached to the message
+    EnclosedFile = 9,
+    /// Any other FTN kludge line, stored as "NAME: value"
+    FtsKludge = 2000,
     /// Unknown/other
     Unknown = 0xFFFF,
 }
@@ -151,6 +167,8 @@ impl From<u16> for SubfieldType {
             6 => SubfieldType::Subject,
             7 => SubfieldType::Path,
             8 => SubfieldType::SeenBy,
+            9 => SubfieldType::EnclosedFile,
+            2000 => SubfieldType::FtsKludge,
             _ => SubfieldType::Unknown,
         }
     }
@@ -320,6 +338,8 @@ mod tests {
         assert_eq!(SubfieldType::from(0), SubfieldType::SendName);
         assert_eq!(SubfieldType::from(1), SubfieldType::RecvName);
         assert_eq!(SubfieldType::from(6), SubfieldType::Subject);
+        assert_eq!(SubfieldType::from(9), SubfieldType::EnclosedFile);
+        assert_eq!(SubfieldType::from(2000), SubfieldType::FtsKludge);
         assert_eq!(SubfieldType::from(99), SubfieldType::Unknown);
     }
 }
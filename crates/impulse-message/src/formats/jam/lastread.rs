@@ -0,0 +1,169 @@
+//! JAM lastread file (.JLR)
+//!
+//! The .jlr file holds one 16-byte record per user who has read the base:
+//! the JAM CRC of the user's name, their user ID, the last message they read
+//! and the highest message they have read.
+
+use super::write::jam_crc;
+use crate::atomic::AtomicWriter;
+use crate::error::Result;
+use std::path::Path;
+
+/// Size of a .jlr record
+pub const JAM_LASTREAD_RECORD_SIZE: usize = 16;
+
+/// A user's lastread record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JamLastRead {
+    /// JAM CRC of the user name
+    pub user_crc: u32,
+    /// Unique user ID
+    pub user_id: u32,
+    /// Last message read
+    pub last_read_msg: u32,
+    /// Highest message read
+    pub high_read_msg: u32,
+}
+
+impl JamLastRead {
+    /// Create a record for a user who has read nothing yet
+    pub fn new(user_name: &str, user_id: u32) -> Self {
+        Self {
+            user_crc: jam_crc(user_name),
+            user_id,
+            last_read_msg: 0,
+            high_read_msg: 0,
+        }
+    }
+
+    /// Parse a record
+    pub fn from_bytes(bytes: &[u8; JAM_LASTREAD_RECORD_SIZE]) -> Self {
+        let field = |index: usize| {
+            u32::from_le_bytes([
+                bytes[index * 4],
+                bytes[index * 4 + 1],
+                bytes[index * 4 + 2],
+                bytes[index * 4 + 3],
+            ])
+        };
+        Self {
+            user_crc: field(0),
+            user_id: field(1),
+            last_read_msg: field(2),
+            high_read_msg: field(3),
+        }
+    }
+
+    /// Serialize the record
+    pub fn to_bytes(&self) -> [u8; JAM_LASTREAD_RECORD_SIZE] {
+        let mut bytes = [0u8; JAM_LASTREAD_RECORD_SIZE];
+        for (index, value) in [
+            self.user_crc,
+            self.user_id,
+            self.last_read_msg,
+            self.high_read_msg,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            bytes[index * 4..index * 4 + 4].copy_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Whether the record belongs to the user with this name CRC and ID
+    ///
+    /// User IDs are folded down to 32 bits and can collide, so records are
+    /// matched on both, as JAM readers do.
+    pub fn is_for(&self, user_crc: u32, user_id: u32) -> bool {
+        self.user_crc == user_crc && self.user_id == user_id
+    }
+
+    /// Record that `msg_num` was read, raising the high-read mark if needed
+    pub fn read_through(&mut self, msg_num: u32) {
+        self.last_read_msg = msg_num;
+        self.high_read_msg = self.high_read_msg.max(msg_num);
+    }
+}
+
+/// Read every record in a .jlr file
+///
+/// A missing file has no records; a trailing partial record is ignored.
+pub async fn read_lastread_file(path: impl AsRef<Path>) -> Result<Vec<JamLastRead>> {
+    let data = match tokio::fs::read(path).await {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(data
+        .chunks_exact(JAM_LASTREAD_RECORD_SIZE)
+        .filter_map(|chunk| chunk.try_into().ok())
+        .map(JamLastRead::from_bytes)
+        .collect())
+}
+
+/// Store a user's record in a .jlr file, replacing the user's old record
+///
+/// Records are matched by name CRC and user ID, so two users whose IDs
+/// collide keep their own records.
+pub async fn write_lastread(path: impl AsRef<Path>, record: JamLastRead) -> Result<()> {
+    let path = path.as_ref();
+    let mut records = read_lastread_file(path).await?;
+    match records
+        .iter_mut()
+        .find(|existing| existing.is_for(record.user_crc, record.user_id))
+    {
+        Some(existing) => *existing = record,
+        None => records.push(record),
+    }
+
+    let data: Vec<u8> = records.iter().flat_map(JamLastRead::to_bytes).collect();
+    AtomicWriter::new(path).write(&data).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_round_trip() {
+        let mut record = JamLastRead::new("Alice", 7);
+        record.read_through(12);
+        record.read_through(5);
+
+        assert_eq!(record.last_read_msg, 5);
+        assert_eq!(record.high_read_msg, 12);
+        assert_eq!(record.user_crc, jam_crc("alice"));
+        assert_eq!(JamLastRead::from_bytes(&record.to_bytes()), record);
+    }
+
+    #[tokio::test]
+    async fn test_write_replaces_record() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("general.jlr");
+        assert!(read_lastread_file(&path).await.unwrap().is_empty());
+
+        let mut alice = JamLastRead::new("Alice", 1);
+        alice.read_through(3);
+        write_lastread(&path, alice).await.unwrap();
+        write_lastread(&path, JamLastRead::new("Bob", 2))
+            .await
+            .unwrap();
+        alice.read_through(4);
+        write_lastread(&path, alice).await.unwrap();
+        // Carol's ID collides with Alice's
+        write_lastread(&path, JamLastRead::new("Carol", 1))
+            .await
+            .unwrap();
+
+        let records = read_lastread_file(&path).await.unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0], alice);
+        assert_eq!(records[1].user_id, 2);
+        assert!(records[2].is_for(jam_crc("Carol"), 1));
+        assert_eq!(
+            tokio::fs::metadata(&path).await.unwrap().len(),
+            3 * JAM_LASTREAD_RECORD_SIZE as u64
+        );
+    }
+}
@@ -2,22 +2,28 @@
 
 mod header;
 mod kludge;
+mod lastread;
+mod search;
 mod write;
 
 pub use header::*;
 pub use kludge::*;
+pub use lastread::*;
+pub use search::*;
 pub use write::*;
 
 use crate::error::{MessageError, Result};
 use crate::sanitize::MessageSanitizer;
 use crate::traits::MessageBase;
 use crate::types::{
-    FullMessage, MessageBaseStats, MessageHeader, MessageThread, NewMessage, SearchCriteria,
+    FullMessage, KludgeLine, MessageBaseStats, MessageHeader, MessageThread, NewMessage,
+    SearchCriteria,
 };
 use crate::validation::MessageValidator;
 use async_trait::async_trait;
 use binrw::BinRead;
 use chrono::Utc;
+use fs2::FileExt;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -26,17 +32,22 @@ use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use tokio::sync::RwLock;
 
 /// JAM message base implementation
+///
+/// Any number of `JamMessageBase` values, in this process or another, may
+/// have the same base open. Changes are made under an exclusive lock on the
+/// base's `.lck` file, and the base header is read afresh each time, so
+/// every writer sees the messages the others added.
 pub struct JamMessageBase {
     /// Base path (without extension)
     base_path: PathBuf,
-    /// Base header (cached)
-    base_header: Arc<RwLock<Option<JamBaseHeader>>>,
     /// Message header cache (msg_num -> header) - reserved for future use
     #[allow(dead_code)]
     header_cache: Arc<RwLock<HashMap<u32, JamMessageHeader>>>,
     /// Subfield cache (msg_num -> subfields) - reserved for future use
     #[allow(dead_code)]
     subfield_cache: Arc<RwLock<HashMap<u32, Vec<JamSubfield>>>>,
+    /// Full-text search index, loaded on first search
+    search_index: Arc<RwLock<Option<SearchIndex>>>,
 }
 
 impl JamMessageBase {
@@ -48,9 +59,9 @@ impl JamMessageBase {
     pub fn new(base_path: impl AsRef<Path>) -> Self {
         Self {
             base_path: base_path.as_ref().to_path_buf(),
-            base_header: Arc::new(RwLock::new(None)),
             header_cache: Arc::new(RwLock::new(HashMap::new())),
             subfield_cache: Arc::new(RwLock::new(HashMap::new())),
+            search_index: Arc::new(RwLock::new(None)),
         }
     }
 
@@ -65,25 +76,52 @@ impl JamMessageBase {
     }
 
     /// Get the path to the index file
-    #[allow(dead_code)]
     fn jdx_path(&self) -> PathBuf {
         self.base_path.with_extension("jdx")
     }
 
+    /// Get the path to the lastread file
+    fn jlr_path(&self) -> PathBuf {
+        self.base_path.with_extension("jlr")
+    }
+
+    /// Get the path to the search index
+    fn jsi_path(&self) -> PathBuf {
+        self.base_path.with_extension("jsi")
+    }
+
+    /// Get the path to the lock file
+    fn lock_path(&self) -> PathBuf {
+        self.base_path.with_extension("lck")
+    }
+
+    /// Wait for the base's write lock
+    ///
+    /// The lock is an exclusive lock on the `.lck` file rather than the
+    /// `.jhr`, since updating the base header replaces the `.jhr`. It is
+    /// released when the returned file is dropped.
+    async fn lock(&self) -> Result<std::fs::File> {
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.lock_path())
+            .await?
+            .into_std()
+            .await;
+        let file = tokio::task::spawn_blocking(move || file.lock_exclusive().map(|()| file))
+            .await
+            .map_err(std::io::Error::other)??;
+        Ok(file)
+    }
+
     /// Load the base header
+    ///
+    /// Not cached: another writer may have added messages since.
     async fn load_base_header(&self) -> Result<JamBaseHeader> {
-        // Check cache
-        {
-            let cache = self.base_header.read().await;
-            if let Some(header) = cache.as_ref() {
-                return Ok(header.clone());
-            }
-        }
-
-        // Load from file
         let mut file = File::open(self.jhr_path()).await?;
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer).await?;
+        let mut buffer = vec![0u8; JAM_BASE_HEADER_SIZE as usize];
+        file.read_exact(&mut buffer).await?;
 
         let mut cursor = std::io::Cursor::new(buffer);
         let header = JamBaseHeader::read(&mut cursor)
@@ -95,12 +133,6 @@ impl JamMessageBase {
             ));
         }
 
-        // Cache it
-        {
-            let mut cache = self.base_header.write().await;
-            *cache = Some(header.clone());
-        }
-
         Ok(header)
     }
 
@@ -112,7 +144,7 @@ impl JamMessageBase {
         let mut file = File::open(self.jhr_path()).await?;
         file.seek(std::io::SeekFrom::Start(position)).await?;
 
-        let mut buffer = vec![0u8; std::mem::size_of::<JamMessageHeader>()];
+        let mut buffer = vec![0u8; JAM_MESSAGE_HEADER_SIZE];
         file.read_exact(&mut buffer).await?;
 
         let mut cursor = std::io::Cursor::new(buffer);
@@ -142,6 +174,302 @@ impl JamMessageBase {
         Ok((header, subfields))
     }
 
+    /// Find the .jhr offset of a message's header
+    ///
+    /// Uses the .jdx index, falling back to walking the headers for bases
+    /// written before the index was kept.
+    async fn header_offset(&self, msg_num: u32) -> Result<u64> {
+        let base_header = self.load_base_header().await?;
+        if msg_num < base_header.base_msg_num
+            || msg_num >= base_header.base_msg_num + base_header.active
+        {
+            return Err(MessageError::MessageNotFound(msg_num));
+        }
+
+        let record = u64::from(msg_num - base_header.base_msg_num) * JAM_INDEX_RECORD_SIZE;
+        if let Ok(mut file) = File::open(self.jdx_path()).await
+            && file.metadata().await?.len() >= record + JAM_INDEX_RECORD_SIZE
+        {
+            file.seek(std::io::SeekFrom::Start(record + 4)).await?;
+            let mut offset = [0u8; 4];
+            file.read_exact(&mut offset).await?;
+            return Ok(u64::from(u32::from_le_bytes(offset)));
+        }
+
+        let jhr_len = tokio::fs::metadata(self.jhr_path()).await?.len();
+        let mut position = JAM_BASE_HEADER_SIZE;
+        while position + JAM_MESSAGE_HEADER_SIZE as u64 <= jhr_len {
+            let (header, _) = self.load_message_header(position).await?;
+            if header.msg_num == msg_num {
+                return Ok(position);
+            }
+            position += JAM_MESSAGE_HEADER_SIZE as u64 + u64::from(header.subfield_len);
+        }
+
+        Err(MessageError::MessageNotFound(msg_num))
+    }
+
+    /// Load a message header and its subfields by message number
+    async fn load_message(&self, msg_num: u32) -> Result<(JamMessageHeader, Vec<JamSubfield>)> {
+        let position = self.header_offset(msg_num).await?;
+        self.load_message_header(position).await
+    }
+
+    /// Create the base files if they do not exist yet
+    pub async fn create_if_missing(&self) -> Result<()> {
+        if tokio::fs::try_exists(self.jhr_path()).await? {
+            return Ok(());
+        }
+        if let Some(parent) = self.base_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        JamWriter::new(&self.base_path).initialize_base().await
+    }
+
+    /// Add a message with its network information to the base
+    ///
+    /// Unlike [`MessageBase::post_message`], the message is stored as given,
+    /// without validation or sanitizing. Used for messages arriving from a
+    /// network and for messages already checked by the caller.
+    ///
+    /// # Returns
+    /// The number of the new message
+    pub async fn add_message(&self, message: &JamMessage) -> Result<u32> {
+        let _lock = self.lock().await?;
+        let base_header = self.load_base_header().await?;
+        let next_msg_num = base_header.base_msg_num + base_header.active;
+
+        let writer = JamWriter::new(&self.base_path);
+        let current_offset = writer.get_jdt_size().await?;
+        let header_offset = writer.get_jhr_size().await?;
+
+        let (jhr_data, jdt_data, _next_offset) = writer
+            .write_jam_message(message, next_msg_num, current_offset)
+            .await?;
+
+        writer.append_message(&jhr_data, &jdt_data).await?;
+        let to_crc = jam_crc(&message.to);
+        writer.append_index(to_crc, header_offset).await?;
+        writer.update_base_header(base_header.active + 1).await?;
+
+        let (_, body) = parse_kludges(&message.body);
+        let entry = IndexEntry::new(next_msg_num, (to_crc, header_offset as u32), message, &body);
+        self.index_message(base_header.base_msg_num, entry).await;
+
+        Ok(next_msg_num)
+    }
+
+    /// Add a new message to the search index
+    ///
+    /// The message is already stored, so a failure here is not reported:
+    /// the index is left behind the base and catches up on the next search.
+    async fn index_message(&self, base_msg_num: u32, entry: IndexEntry) {
+        let mut cache = self.search_index.write().await;
+        let appended = match cache.as_ref() {
+            Some(index) if index.next_msg_num() != entry.message.msg_num => false,
+            _ => SearchIndex::append(self.jsi_path(), base_msg_num, &entry)
+                .await
+                .unwrap_or(false),
+        };
+        match cache.as_mut() {
+            Some(index) if appended => index.insert(entry),
+            Some(_) => *cache = None,
+            None => {}
+        }
+    }
+
+    /// Read the .jdx file as (recipient CRC, header offset) records
+    async fn read_jdx(&self) -> Result<Vec<(u32, u32)>> {
+        let data = match tokio::fs::read(self.jdx_path()).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(data
+            .chunks_exact(JAM_INDEX_RECORD_SIZE as usize)
+            .map(|record| {
+                let field = |at: usize| {
+                    u32::from_le_bytes([record[at], record[at + 1], record[at + 2], record[at + 3]])
+                };
+                (field(0), field(4))
+            })
+            .collect())
+    }
+
+    /// Build the search index entry of a stored message
+    async fn index_entry(&self, msg_num: u32, jdx: (u32, u32)) -> IndexEntry {
+        match self.read_jam_message(msg_num).await {
+            Ok(message) => {
+                let (_, body) = parse_kludges(&message.body);
+                IndexEntry::new(msg_num, jdx, &message, &body)
+            }
+            Err(_) => IndexEntry::unreadable(msg_num, jdx),
+        }
+    }
+
+    /// Make sure the cached search index covers every message
+    ///
+    /// The index is loaded on first use. Messages added since it was last
+    /// written, for example by a program that does not keep it, are indexed
+    /// and appended; an index that no longer matches the .jdx, or cannot be
+    /// read, is rebuilt. So is one holding a message that could not be read
+    /// before but can be now.
+    async fn sync_search_index(&self) -> Result<()> {
+        let base_msg_num = self.load_base_header().await?.base_msg_num;
+        let jdx = self.read_jdx().await?;
+
+        // Other programs may have added to the file since it was loaded
+        let mut cache = self.search_index.write().await;
+        if cache.as_ref().is_none_or(|index| index.len() < jdx.len()) {
+            *cache = SearchIndex::load(self.jsi_path()).await?;
+        }
+        let mut index = match cache.take() {
+            Some(index) if index.agrees_with(base_msg_num, &jdx) => index,
+            _ => {
+                *cache = Some(self.build_search_index(base_msg_num, &jdx).await?);
+                return Ok(());
+            }
+        };
+
+        for (position, record) in jdx.iter().enumerate().skip(index.len()) {
+            let entry = self
+                .index_entry(base_msg_num + position as u32, *record)
+                .await;
+            if !SearchIndex::append(self.jsi_path(), base_msg_num, &entry).await? {
+                *cache = Some(self.build_search_index(base_msg_num, &jdx).await?);
+                return Ok(());
+            }
+            index.insert(entry);
+        }
+
+        for &msg_num in index.unreadable() {
+            if self.read_jam_message(msg_num).await.is_ok() {
+                *cache = Some(self.build_search_index(base_msg_num, &jdx).await?);
+                return Ok(());
+            }
+        }
+        *cache = Some(index);
+        Ok(())
+    }
+
+    /// Index every message and write a new .jsi file
+    async fn build_search_index(
+        &self,
+        base_msg_num: u32,
+        jdx: &[(u32, u32)],
+    ) -> Result<SearchIndex> {
+        let mut index = SearchIndex::new(base_msg_num);
+        let mut records = Vec::new();
+        for (position, record) in jdx.iter().enumerate() {
+            let entry = self
+                .index_entry(base_msg_num + position as u32, *record)
+                .await;
+            records.extend_from_slice(&entry.to_bytes());
+            index.insert(entry);
+        }
+        SearchIndex::write_file(self.jsi_path(), base_msg_num, index.len() as u32, &records)
+            .await?;
+        Ok(index)
+    }
+
+    /// Rebuild the search index from the message base
+    pub async fn rebuild_search_index(&self) -> Result<()> {
+        let base_msg_num = self.load_base_header().await?.base_msg_num;
+        let jdx = self.read_jdx().await?;
+        let index = self.build_search_index(base_msg_num, &jdx).await?;
+        *self.search_index.write().await = Some(index);
+        Ok(())
+    }
+
+    /// Read a message with its network information
+    pub async fn read_jam_message(&self, msg_num: u32) -> Result<JamMessage> {
+        let (header, subfields) = self.load_message(msg_num).await?;
+        let body = self
+            .load_message_text(header.offset, header.text_len)
+            .await?;
+
+        let mut message = JamMessage::new(
+            Self::get_subfield_value(&subfields, SubfieldType::SendName).unwrap_or_default(),
+            Self::get_subfield_value(&subfields, SubfieldType::RecvName).unwrap_or_default(),
+            Self::get_subfield_value(&subfields, SubfieldType::Subject).unwrap_or_default(),
+        )
+        .with_body(body)
+        .with_attributes(header.attribute);
+        message.date_written = header.written_date().unwrap_or_else(Utc::now);
+        message.reply_to = (header.reply_to != 0).then_some(header.reply_to);
+        message.from_address = Self::get_subfield_value(&subfields, SubfieldType::SendAddr);
+        message.to_address = Self::get_subfield_value(&subfields, SubfieldType::RecvAddr);
+        message.msgid = Self::get_subfield_value(&subfields, SubfieldType::MsgId);
+        message.reply_id = Self::get_subfield_value(&subfields, SubfieldType::ReplyId);
+
+        for subfield in &subfields {
+            match subfield.subfield_type() {
+                SubfieldType::SeenBy => message.seen_by.push(subfield.as_string()),
+                SubfieldType::Path => message.path.push(subfield.as_string()),
+                SubfieldType::EnclosedFile => message.attachments.push(subfield.as_string()),
+                SubfieldType::FtsKludge => message
+                    .kludges
+                    .push(Self::split_kludge(&subfield.as_string())),
+                _ => {}
+            }
+        }
+
+        Ok(message)
+    }
+
+    /// Replace the attribute flags of a message
+    pub async fn set_attributes(&self, msg_num: u32, attributes: u32) -> Result<()> {
+        let _lock = self.lock().await?;
+        let position = self.header_offset(msg_num).await?;
+        JamWriter::new(&self.base_path)
+            .update_attributes(position as u32, attributes)
+            .await
+    }
+
+    /// Load a user's lastread record, matched by name and ID
+    ///
+    /// # Returns
+    /// `None` if the user has never read this base
+    pub async fn lastread(&self, user_name: &str, user_id: u32) -> Result<Option<JamLastRead>> {
+        let user_crc = jam_crc(user_name);
+        Ok(read_lastread_file(self.jlr_path())
+            .await?
+            .into_iter()
+            .find(|record| record.is_for(user_crc, user_id)))
+    }
+
+    /// Split a stored "NAME: value" kludge line
+    fn split_kludge(line: &str) -> KludgeLine {
+        let (kludge_type, value) = line.split_once(':').unwrap_or((line, ""));
+        KludgeLine {
+            kludge_type: kludge_type.trim().to_string(),
+            value: value.trim().to_string(),
+        }
+    }
+
+    /// Build kludge lines from a message's network subfields
+    fn subfield_kludges(subfields: &[JamSubfield]) -> Vec<KludgeLine> {
+        subfields
+            .iter()
+            .filter_map(|subfield| {
+                let kludge_type = match subfield.subfield_type() {
+                    SubfieldType::MsgId => "MSGID",
+                    SubfieldType::ReplyId => "REPLY",
+                    SubfieldType::SeenBy => "SEEN-BY",
+                    SubfieldType::Path => "PATH",
+                    SubfieldType::FtsKludge => {
+                        return Some(Self::split_kludge(&subfield.as_string()));
+                    }
+                    _ => return None,
+                };
+                Some(KludgeLine {
+                    kludge_type: kludge_type.to_string(),
+                    value: subfield.as_string(),
+                })
+            })
+            .collect()
+    }
+
     /// Load message text from .jdt file
     async fn load_message_text(&self, offset: u32, length: u32) -> Result<String> {
         let mut file = File::open(self.jdt_path()).await?;
@@ -203,20 +531,7 @@ impl JamMessageBase {
 #[async_trait]
 impl MessageBase for JamMessageBase {
     async fn read_message(&self, msg_num: u32) -> Result<FullMessage> {
-        // For JAM, msg_num is 1-based, and messages are stored sequentially
-        // Base header is at position 0, each message header follows
-        let base_header = self.load_base_header().await?;
-
-        if msg_num < base_header.base_msg_num {
-            return Err(MessageError::MessageNotFound(msg_num));
-        }
-
-        // Calculate position in .jhr file
-        let header_size = std::mem::size_of::<JamBaseHeader>() as u64;
-        // Note: This is simplified; actual JAM needs to account for subfields
-        let position = header_size;
-
-        let (header, subfields) = self.load_message_header(position).await?;
+        let (header, subfields) = self.load_message(msg_num).await?;
 
         // Extract fields from subfields
         let from = Self::get_subfield_value(&subfields, SubfieldType::SendName)
@@ -230,7 +545,9 @@ impl MessageBase for JamMessageBase {
         let text = self
             .load_message_text(header.offset, header.text_len)
             .await?;
-        let (kludges, body) = parse_kludges(&text);
+        let (text_kludges, body) = parse_kludges(&text);
+        let mut kludges = Self::subfield_kludges(&subfields);
+        kludges.extend(text_kludges);
 
         let msg_header = MessageHeader {
             msg_num,
@@ -261,72 +578,34 @@ impl MessageBase for JamMessageBase {
     }
 
     async fn search(&self, criteria: &SearchCriteria) -> Result<Vec<u32>> {
-        let count = self.message_count().await?;
-        let mut results = Vec::new();
-
-        for msg_num in 1..=count {
-            match self.read_message(msg_num).await {
-                Ok(msg) => {
-                    let mut matches = true;
-
-                    if let Some(ref subject) = criteria.subject
-                        && !msg
-                            .header
-                            .subject
-                            .to_lowercase()
-                            .contains(&subject.to_lowercase())
-                    {
-                        matches = false;
-                    }
-
-                    if let Some(ref from) = criteria.from
-                        && !msg
-                            .header
-                            .from
-                            .to_lowercase()
-                            .contains(&from.to_lowercase())
-                    {
-                        matches = false;
-                    }
-
-                    if let Some(ref to) = criteria.to
-                        && !msg.header.to.to_lowercase().contains(&to.to_lowercase())
-                    {
-                        matches = false;
-                    }
-
-                    if let Some(ref body_search) = criteria.body
-                        && !msg
-                            .body
-                            .to_lowercase()
-                            .contains(&body_search.to_lowercase())
-                    {
-                        matches = false;
-                    }
-
-                    if criteria.unread_only && msg.header.is_read {
-                        matches = false;
-                    }
-
-                    if criteria.private_only && !msg.header.is_private {
-                        matches = false;
-                    }
+        self.sync_search_index().await?;
+        let found = match self.search_index.read().await.as_ref() {
+            Some(index) => index.find(criteria),
+            None => return Err(MessageError::SearchError("index unavailable".to_string())),
+        };
+        if !criteria.unread_only && !criteria.private_only {
+            return Ok(found);
+        }
 
-                    if matches {
-                        results.push(msg_num);
-                    }
-                }
-                Err(_) => continue,
+        // Read and private flags live in the message headers
+        let mut results = Vec::new();
+        for msg_num in found {
+            let Ok((header, _)) = self.load_message(msg_num).await else {
+                continue;
+            };
+            let attributes = header.attributes();
+            if (criteria.unread_only && attributes.is_read())
+                || (criteria.private_only && !attributes.is_private())
+            {
+                continue;
             }
+            results.push(msg_num);
         }
-
         Ok(results)
     }
 
     async fn get_thread(&self, msg_num: u32) -> Result<MessageThread> {
-        let _msg = self.read_message(msg_num).await?;
-        let position = std::mem::size_of::<JamBaseHeader>() as u64;
-        let (header, _) = self.load_message_header(position).await?;
+        let (header, _) = self.load_message(msg_num).await?;
         self.build_thread(msg_num, &header).await
     }
 
@@ -357,10 +636,30 @@ impl MessageBase for JamMessageBase {
         })
     }
 
-    async fn mark_read(&mut self, _msg_num: u32) -> Result<()> {
-        // Would need to update the message header in .jhr file
-        // This requires write access, which we'll implement later
-        Ok(())
+    async fn mark_read(&mut self, msg_num: u32) -> Result<()> {
+        let (header, _) = self.load_message(msg_num).await?;
+        if header.attributes().is_read() {
+            return Ok(());
+        }
+        self.set_attributes(msg_num, header.attribute | MessageAttributes::READ)
+            .await
+    }
+
+    async fn last_read(&self, user_name: &str, user_id: u32) -> Result<u32> {
+        Ok(self
+            .lastread(user_name, user_id)
+            .await?
+            .map_or(0, |record| record.last_read_msg))
+    }
+
+    async fn set_last_read(&mut self, user_name: &str, user_id: u32, msg_num: u32) -> Result<()> {
+        let _lock = self.lock().await?;
+        let mut record = self
+            .lastread(user_name, user_id)
+            .await?
+            .unwrap_or_else(|| JamLastRead::new(user_name, user_id));
+        record.read_through(msg_num);
+        write_lastread(self.jlr_path(), record).await
     }
 
     async fn message_exists(&self, msg_num: u32) -> Result<bool> {
@@ -384,34 +683,18 @@ impl MessageBase for JamMessageBase {
         let sanitizer = MessageSanitizer::new();
         let sanitized = sanitizer.sanitize(&message);
 
-        // Get current message count
-        let base_header = self.load_base_header().await?;
-        let next_msg_num = base_header.base_msg_num + base_header.active;
-
-        // Create writer
-        let writer = JamWriter::new(&self.base_path);
-
-        // Get current .JDT offset
-        let current_offset = writer.get_jdt_size().await?;
-
-        // Write message
-        let (jhr_data, jdt_data, _next_offset) = writer
-            .write_message(&sanitized, next_msg_num, current_offset)
-            .await?;
-
-        // Append to files
-        writer.append_message(&jhr_data, &jdt_data).await?;
-
-        // Update base header
-        writer.update_base_header(base_header.active + 1).await?;
-
-        // Invalidate cache
-        {
-            let mut cache = self.base_header.write().await;
-            *cache = None;
+        // Build message attributes
+        let mut attributes = MessageAttributes::LOCAL;
+        if sanitized.is_private {
+            attributes |= MessageAttributes::PRIVATE;
         }
 
-        Ok(next_msg_num)
+        let mut jam_message = JamMessage::new(sanitized.from, sanitized.to, sanitized.subject)
+            .with_body(sanitized.body)
+            .with_attributes(attributes);
+        jam_message.reply_to = sanitized.reply_to;
+
+        self.add_message(&jam_message).await
     }
 
     async fn reply_to_message(
@@ -448,4 +731,275 @@ mod tests {
         assert_eq!(base.jdt_path(), PathBuf::from("/msg/general.jdt"));
         assert_eq!(base.jdx_path(), PathBuf::from("/msg/general.jdx"));
     }
+
+    #[tokio::test]
+    async fn test_post_and_read_several_messages() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut base = JamMessageBase::new(temp_dir.path().join("general"));
+        base.create_if_missing().await.unwrap();
+
+        for n in 1..=3 {
+            let message = NewMessage::new("Alice", "Bob", format!("Subject {}", n))
+                .with_body(format!("Body of message {}", n));
+            assert_eq!(base.post_message(message).await.unwrap(), n);
+        }
+
+        assert_eq!(base.message_count().await.unwrap(), 3);
+        let second = base.read_message(2).await.unwrap();
+        assert_eq!(second.header.subject, "Subject 2");
+        assert_eq!(second.body, "Body of message 2");
+        assert!(matches!(
+            base.read_message(4).await,
+            Err(MessageError::MessageNotFound(4))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_network_message_round_trip() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let base = JamMessageBase::new(temp_dir.path().join("echo"));
+        base.create_if_missing().await.unwrap();
+
+        let mut message = JamMessage::new("Carol", "All", "Hello").with_body("Hi there\r");
+        message.from_address = Some("1:234/5".to_string());
+        message.msgid = Some("1:234/5 0badcafe".to_string());
+        message.seen_by = vec!["234/5 6".to_string()];
+        message.path = vec!["234/5".to_string()];
+        message.kludges.push(KludgeLine {
+            kludge_type: "TZUTC".to_string(),
+            value: "0000".to_string(),
+        });
+        base.add_message(&message).await.unwrap();
+
+        let read = base.read_jam_message(1).await.unwrap();
+        assert_eq!(read.from_address.as_deref(), Some("1:234/5"));
+        assert_eq!(read.msgid, message.msgid);
+        assert_eq!(read.seen_by, message.seen_by);
+        assert_eq!(read.path, message.path);
+        assert_eq!(read.kludges[0].kludge_type, "TZUTC");
+
+        let full = base.read_message(1).await.unwrap();
+        assert!(full.kludges.iter().any(|k| k.kludge_type == "MSGID"));
+        assert!(full.kludges.iter().any(|k| k.kludge_type == "SEEN-BY"));
+    }
+
+    #[tokio::test]
+    async fn test_writers_on_the_same_base() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let base_path = temp_dir.path().join("shared");
+        JamMessageBase::new(&base_path)
+            .create_if_missing()
+            .await
+            .unwrap();
+
+        // Two handles on one base, as the server and the tosser have
+        let writers = [
+            JamMessageBase::new(&base_path),
+            JamMessageBase::new(&base_path),
+        ]
+        .map(|base| {
+            tokio::spawn(async move {
+                for n in 0..10 {
+                    let message = JamMessage::new("Alice", "All", format!("Message {}", n));
+                    base.add_message(&message).await.unwrap();
+                }
+                base
+            })
+        });
+        let [first, second] = writers;
+        let first = first.await.unwrap();
+        second.await.unwrap();
+
+        let base = JamMessageBase::new(&base_path);
+        assert_eq!(base.message_count().await.unwrap(), 20);
+        for msg_num in 1..=20 {
+            assert!(base.read_jam_message(msg_num).await.is_ok());
+        }
+
+        // A handle that has been open all along sees the other's messages
+        assert_eq!(first.message_count().await.unwrap(), 20);
+    }
+
+    #[tokio::test]
+    async fn test_set_attributes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let base = JamMessageBase::new(temp_dir.path().join("echo"));
+        base.create_if_missing().await.unwrap();
+        base.add_message(&JamMessage::new("A", "B", "One"))
+            .await
+            .unwrap();
+        base.add_message(&JamMessage::new("A", "B", "Two"))
+            .await
+            .unwrap();
+
+        base.set_attributes(2, MessageAttributes::LOCAL | MessageAttributes::SENT)
+            .await
+            .unwrap();
+
+        let read = base.read_jam_message(2).await.unwrap();
+        assert!(read.attributes().has(MessageAttributes::SENT));
+        assert_eq!(base.read_jam_message(1).await.unwrap().attributes, 0);
+    }
+
+    #[tokio::test]
+    async fn test_mark_read() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut base = JamMessageBase::new(temp_dir.path().join("general"));
+        base.create_if_missing().await.unwrap();
+        base.post_message(NewMessage::new("Alice", "Bob", "Hello").with_body("Hi Bob"))
+            .await
+            .unwrap();
+
+        assert!(!base.read_message(1).await.unwrap().header.is_read);
+        base.mark_read(1).await.unwrap();
+        assert!(base.read_message(1).await.unwrap().header.is_read);
+        assert!(
+            base.read_jam_message(1)
+                .await
+                .unwrap()
+                .attributes()
+                .is_local()
+        );
+        assert!(matches!(
+            base.mark_read(2).await,
+            Err(MessageError::MessageNotFound(2))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_indexed_search() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let base_path = temp_dir.path().join("search");
+        let mut base = JamMessageBase::new(&base_path);
+        base.create_if_missing().await.unwrap();
+
+        for (subject, body) in [
+            ("BinkP", "The mailer dials out nightly"),
+            ("Echomail", "Tossing echo mail"),
+            ("Re: BinkP", "Out of the mailer, nightly"),
+        ] {
+            base.post_message(NewMessage::new("Alice", "All", subject).with_body(body))
+                .await
+                .unwrap();
+        }
+        // Posting keeps the index up to date without a search
+        let index = SearchIndex::load(base.jsi_path()).await.unwrap().unwrap();
+        assert_eq!(index.len(), 3);
+
+        let body = |query: &str| SearchCriteria::new().with_body(query);
+        assert_eq!(base.search(&body("\"mailer dials\"")).await.unwrap(), [1]);
+        assert_eq!(
+            base.search(&body("tossing OR dials")).await.unwrap(),
+            [1, 2]
+        );
+        assert_eq!(
+            base.search(&SearchCriteria::new().with_subject("binkp").unread_only())
+                .await
+                .unwrap(),
+            [1, 3]
+        );
+
+        // Messages added by another handle are picked up from the file
+        JamMessageBase::new(&base_path)
+            .add_message(&JamMessage::new("Bob", "All", "Late").with_body("dials again"))
+            .await
+            .unwrap();
+        assert_eq!(base.search(&body("dials")).await.unwrap(), [1, 4]);
+        let index = SearchIndex::load(base.jsi_path()).await.unwrap().unwrap();
+        assert_eq!(index.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_search_index_rebuilds_on_drift() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let base_path = temp_dir.path().join("drift");
+        let mut base = JamMessageBase::new(&base_path);
+        base.create_if_missing().await.unwrap();
+        base.post_message(NewMessage::new("Alice", "All", "One").with_body("first"))
+            .await
+            .unwrap();
+
+        // A damaged index is rebuilt
+        tokio::fs::write(base.jsi_path(), b"garbage").await.unwrap();
+        let fresh = JamMessageBase::new(&base_path);
+        let criteria = SearchCriteria::new().with_body("first");
+        assert_eq!(fresh.search(&criteria).await.unwrap(), [1]);
+
+        // So is one left over from a base that was replaced
+        let old_index = tokio::fs::read(base.jsi_path()).await.unwrap();
+        for extension in ["jhr", "jdt", "jdx", "jsi"] {
+            tokio::fs::remove_file(base_path.with_extension(extension))
+                .await
+                .unwrap();
+        }
+        let mut base = JamMessageBase::new(&base_path);
+        base.create_if_missing().await.unwrap();
+        base.post_message(NewMessage::new("Bob", "Carol", "Two").with_body("second"))
+            .await
+            .unwrap();
+        tokio::fs::write(base.jsi_path(), old_index).await.unwrap();
+
+        let fresh = JamMessageBase::new(&base_path);
+        assert!(fresh.search(&criteria).await.unwrap().is_empty());
+        assert_eq!(
+            fresh
+                .search(&SearchCriteria::new().with_body("second"))
+                .await
+                .unwrap(),
+            [1]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_index_retries_unreadable_messages() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let base_path = temp_dir.path().join("damaged");
+        let mut base = JamMessageBase::new(&base_path);
+        base.create_if_missing().await.unwrap();
+        for body in ["first", "second"] {
+            base.post_message(NewMessage::new("Alice", "All", "Hi").with_body(body))
+                .await
+                .unwrap();
+        }
+
+        // Damage the first message's header signature
+        let jhr = tokio::fs::read(base.jhr_path()).await.unwrap();
+        let offset = base.read_jdx().await.unwrap()[0].1 as usize;
+        let mut damaged = jhr.clone();
+        damaged[offset..offset + 4].copy_from_slice(b"XXXX");
+        tokio::fs::write(base.jhr_path(), &damaged).await.unwrap();
+
+        base.rebuild_search_index().await.unwrap();
+        let criteria = SearchCriteria::new().with_body("first");
+        assert!(base.search(&criteria).await.unwrap().is_empty());
+        let index = SearchIndex::load(base.jsi_path()).await.unwrap().unwrap();
+        assert_eq!(index.unreadable(), [1]);
+
+        // Once it reads again it is indexed properly
+        tokio::fs::write(base.jhr_path(), &jhr).await.unwrap();
+        assert_eq!(base.search(&criteria).await.unwrap(), [1]);
+        let index = SearchIndex::load(base.jsi_path()).await.unwrap().unwrap();
+        assert!(index.unreadable().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_last_read_is_per_user() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut base = JamMessageBase::new(temp_dir.path().join("general"));
+        base.create_if_missing().await.unwrap();
+
+        assert_eq!(base.last_read("Alice", 1).await.unwrap(), 0);
+        base.set_last_read("Alice", 1, 4).await.unwrap();
+        base.set_last_read("Bob", 2, 2).await.unwrap();
+        base.set_last_read("Alice", 1, 3).await.unwrap();
+        // Carol's ID collides with Alice's
+        base.set_last_read("Carol", 1, 7).await.unwrap();
+
+        assert_eq!(base.last_read("Alice", 1).await.unwrap(), 3);
+        assert_eq!(base.last_read("Bob", 2).await.unwrap(), 2);
+        assert_eq!(base.last_read("Carol", 1).await.unwrap(), 7);
+        let alice = base.lastread("Alice", 1).await.unwrap().unwrap();
+        assert_eq!(alice.high_read_msg, 4);
+        assert_eq!(alice.user_crc, jam_crc("Alice"));
+    }
 }
@@ -0,0 +1,803 @@
+//! JAM full-text search index (.JSI)
+//!
+//! The .jsi file is an inverted index of a base's subjects and bodies, kept
+//! next to the JAM files. It starts with a 24-byte header (magic, base
+//! message number, record count and the length of the record data) followed
+//! by one record per message, in message order. A record holds the message
+//! number, its .jdx record, the date written, whether the message could be
+//! read, the lowercased sender and recipient, and the words of the subject and body in order, so phrases
+//! can be matched.
+//!
+//! Records are appended as messages are added and the header is patched
+//! afterwards, so a write cut short leaves only unused bytes past the end of
+//! the data. Each record carries its .jdx record so the index can tell when
+//! it no longer describes the base. Messages that could not be read keep
+//! their place with an empty record and are tried again on later syncs.
+
+use super::write::JamMessage;
+use crate::atomic::AtomicWriter;
+use crate::error::Result;
+use crate::types::SearchCriteria;
+use std::collections::HashMap;
+use std::path::Path;
+use tokio::fs::OpenOptions;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+/// Signature at the start of a .jsi file
+pub const JAM_SEARCH_INDEX_MAGIC: [u8; 8] = *b"IMPJSI\x02\0";
+
+/// Size of the .jsi header
+pub const JAM_SEARCH_INDEX_HEADER_SIZE: usize = 24;
+
+/// Longest word kept, in characters
+const MAX_TERM_CHARS: usize = 32;
+
+/// Split text into lowercase words
+///
+/// Anything that is not a letter or digit separates words; words longer
+/// than 32 characters are cut short.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase().chars().take(MAX_TERM_CHARS).collect())
+        .collect()
+}
+
+/// A parsed subject or body query
+///
+/// Words must all appear; `AND` may be written between them but is implied.
+/// `OR` separates alternatives, and double quotes make a phrase whose words
+/// must appear together and in order. Words match whole words only, so
+/// `mail` does not find `mailer`; matching ignores case and punctuation, so
+/// `e-mail` is the phrase `"e mail"`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// Alternatives, each a list of phrases that must all match
+    alternatives: Vec<Vec<Vec<String>>>,
+}
+
+impl SearchQuery {
+    /// Parse a query
+    pub fn parse(query: &str) -> Self {
+        let mut alternatives = Vec::new();
+        let mut current = Vec::new();
+        let mut rest = query;
+
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                break;
+            }
+
+            let (piece, quoted) = if let Some(after) = rest.strip_prefix('"') {
+                let end = after.find('"').unwrap_or(after.len());
+                rest = after.get(end + 1..).unwrap_or("");
+                (&after[..end], true)
+            } else {
+                let end = rest
+                    .find(|ch: char| ch.is_whitespace() || ch == '"')
+                    .unwrap_or(rest.len());
+                let piece = &rest[..end];
+                rest = &rest[end..];
+                (piece, false)
+            };
+
+            match piece {
+                "OR" if !quoted => {
+                    if !current.is_empty() {
+                        alternatives.push(std::mem::take(&mut current));
+                    }
+                }
+                "AND" if !quoted => {}
+                _ => {
+                    let words = tokenize(piece);
+                    if !words.is_empty() {
+                        current.push(words);
+                    }
+                }
+            }
+        }
+        if !current.is_empty() {
+            alternatives.push(current);
+        }
+
+        Self { alternatives }
+    }
+
+    /// Whether the query has no words, and so places no constraint
+    pub fn is_empty(&self) -> bool {
+        self.alternatives.is_empty()
+    }
+}
+
+/// A message as the index knows it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedMessage {
+    /// Message number
+    pub msg_num: u32,
+    /// The message's .jdx record: recipient CRC and header offset
+    pub jdx: (u32, u32),
+    /// Date written, as a Unix timestamp
+    pub date: i64,
+    /// Whether the message could be read when it was indexed
+    pub readable: bool,
+    /// Sender, lowercased
+    pub from: String,
+    /// Recipient, lowercased
+    pub to: String,
+}
+
+/// One record of the index file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// The message
+    pub message: IndexedMessage,
+    /// Words of the subject
+    pub subject: Vec<String>,
+    /// Words of the body
+    pub body: Vec<String>,
+}
+
+impl IndexEntry {
+    /// Build the entry for a message
+    ///
+    /// `body` is the message text without kludge lines.
+    pub fn new(msg_num: u32, jdx: (u32, u32), message: &JamMessage, body: &str) -> Self {
+        Self {
+            message: IndexedMessage {
+                msg_num,
+                jdx,
+                date: message.date_written.timestamp(),
+                readable: true,
+                from: message.from.to_lowercase(),
+                to: message.to.to_lowercase(),
+            },
+            subject: tokenize(&message.subject),
+            body: tokenize(body),
+        }
+    }
+
+    /// Entry for a message that could not be read, keeping its place
+    pub fn unreadable(msg_num: u32, jdx: (u32, u32)) -> Self {
+        Self {
+            message: IndexedMessage {
+                msg_num,
+                jdx,
+                date: 0,
+                readable: false,
+                from: String::new(),
+                to: String::new(),
+            },
+            subject: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Serialize the record, length first
+    ///
+    /// Words are stored once per record and the subject and body as
+    /// positions in that list.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut words: Vec<&str> = Vec::new();
+        let mut ids: HashMap<&str, u32> = HashMap::new();
+        for word in self.subject.iter().chain(&self.body).map(String::as_str) {
+            ids.entry(word).or_insert_with(|| {
+                words.push(word);
+                words.len() as u32 - 1
+            });
+        }
+
+        let mut record = Vec::new();
+        record.extend_from_slice(&self.message.msg_num.to_le_bytes());
+        record.extend_from_slice(&self.message.jdx.0.to_le_bytes());
+        record.extend_from_slice(&self.message.jdx.1.to_le_bytes());
+        record.extend_from_slice(&self.message.date.to_le_bytes());
+        record.push(u8::from(self.message.readable));
+        put_string(&mut record, &self.message.from);
+        put_string(&mut record, &self.message.to);
+        put_varint(&mut record, words.len() as u32);
+        for word in &words {
+            record.push(word.len() as u8);
+            record.extend_from_slice(word.as_bytes());
+        }
+        for field in [&self.subject, &self.body] {
+            put_varint(&mut record, field.len() as u32);
+            for word in field {
+                put_varint(&mut record, ids[word.as_str()]);
+            }
+        }
+
+        let mut bytes = Vec::with_capacity(record.len() + 4);
+        bytes.extend_from_slice(&(record.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&record);
+        bytes
+    }
+
+    /// Parse a record from the start of `data`
+    ///
+    /// # Returns
+    /// The record and the number of bytes it took, or `None` if the data
+    /// is cut short or malformed
+    pub fn from_bytes(data: &[u8]) -> Option<(Self, usize)> {
+        let mut reader = Reader::new(data);
+        let len = reader.u32()? as usize;
+        let mut reader = Reader::new(reader.bytes(len)?);
+
+        let message = IndexedMessage {
+            msg_num: reader.u32()?,
+            jdx: (reader.u32()?, reader.u32()?),
+            date: i64::from_le_bytes(reader.bytes(8)?.try_into().ok()?),
+            readable: reader.bytes(1)?[0] != 0,
+            from: reader.string()?,
+            to: reader.string()?,
+        };
+        let mut words = Vec::new();
+        for _ in 0..reader.varint()? {
+            let len = usize::from(reader.bytes(1)?[0]);
+            words.push(String::from_utf8(reader.bytes(len)?.to_vec()).ok()?);
+        }
+        let mut field = || -> Option<Vec<String>> {
+            (0..reader.varint()?)
+                .map(|_| words.get(reader.varint()? as usize).cloned())
+                .collect()
+        };
+        let subject = field()?;
+        let body = field()?;
+
+        Some((
+            Self {
+                message,
+                subject,
+                body,
+            },
+            len + 4,
+        ))
+    }
+}
+
+/// Where a word appears in one message
+#[derive(Debug, Clone)]
+struct Posting {
+    /// Position of the message in the index
+    doc: u32,
+    /// Word positions, ascending
+    positions: Vec<u32>,
+}
+
+/// In-memory form of a .jsi file
+#[derive(Debug, Clone)]
+pub struct SearchIndex {
+    /// Number of the base's first message
+    base_msg_num: u32,
+    /// Messages in index order
+    messages: Vec<IndexedMessage>,
+    /// Subject words
+    subject: HashMap<String, Vec<Posting>>,
+    /// Body words
+    body: HashMap<String, Vec<Posting>>,
+    /// Numbers of the messages that could not be read
+    unreadable: Vec<u32>,
+}
+
+impl SearchIndex {
+    /// Create an empty index for a base whose first message is `base_msg_num`
+    pub fn new(base_msg_num: u32) -> Self {
+        Self {
+            base_msg_num,
+            messages: Vec::new(),
+            subject: HashMap::new(),
+            body: HashMap::new(),
+            unreadable: Vec::new(),
+        }
+    }
+
+    /// Number of messages indexed
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Whether no messages are indexed
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Number the next message added to the base will get
+    pub fn next_msg_num(&self) -> u32 {
+        self.base_msg_num + self.messages.len() as u32
+    }
+
+    /// Numbers of the indexed messages that could not be read
+    pub fn unreadable(&self) -> &[u32] {
+        &self.unreadable
+    }
+
+    /// Add a message to the end of the index
+    pub fn insert(&mut self, entry: IndexEntry) {
+        let doc = self.messages.len() as u32;
+        for (postings, words) in [
+            (&mut self.subject, &entry.subject),
+            (&mut self.body, &entry.body),
+        ] {
+            for (position, word) in words.iter().enumerate() {
+                let list = postings.entry(word.clone()).or_default();
+                match list.last_mut() {
+                    Some(posting) if posting.doc == doc => posting.positions.push(position as u32),
+                    _ => list.push(Posting {
+                        doc,
+                        positions: vec![position as u32],
+                    }),
+                }
+            }
+        }
+        if !entry.message.readable {
+            self.unreadable.push(entry.message.msg_num);
+        }
+        self.messages.push(entry.message);
+    }
+
+    /// Whether the index still describes a base
+    ///
+    /// `jdx` is the base's .jdx file as (recipient CRC, header offset)
+    /// records. The index may be behind the .jdx, but its first and last
+    /// messages must match their .jdx records.
+    pub fn agrees_with(&self, base_msg_num: u32, jdx: &[(u32, u32)]) -> bool {
+        if self.base_msg_num != base_msg_num || self.messages.len() > jdx.len() {
+            return false;
+        }
+        let matches = |doc: usize| self.messages[doc].jdx == jdx[doc];
+        self.messages.is_empty() || (matches(0) && matches(self.messages.len() - 1))
+    }
+
+    /// Numbers of the messages matching a search, ascending
+    ///
+    /// The subject and body are matched word for word as [`SearchQuery`]s,
+    /// the sender and recipient as case-insensitive substrings, and the date
+    /// range inclusively. Read and private flags are not indexed and are left
+    /// to the caller.
+    pub fn find(&self, criteria: &SearchCriteria) -> Vec<u32> {
+        let mut docs: Option<Vec<u32>> = None;
+        for (postings, query) in [
+            (&self.subject, &criteria.subject),
+            (&self.body, &criteria.body),
+        ] {
+            let Some(query) = query.as_deref().map(SearchQuery::parse) else {
+                continue;
+            };
+            if query.is_empty() {
+                continue;
+            }
+            let found = query_docs(postings, &query);
+            docs = Some(match docs {
+                Some(docs) => intersect(&docs, &found),
+                None => found,
+            });
+        }
+
+        let from = criteria.from.as_deref().map(str::to_lowercase);
+        let to = criteria.to.as_deref().map(str::to_lowercase);
+        let date_from = criteria.date_from.map(|date| date.timestamp());
+        let date_to = criteria.date_to.map(|date| date.timestamp());
+        let wanted = |message: &IndexedMessage| {
+            from.as_deref()
+                .is_none_or(|from| message.from.contains(from))
+                && to.as_deref().is_none_or(|to| message.to.contains(to))
+                && date_from.is_none_or(|date| message.date >= date)
+                && date_to.is_none_or(|date| message.date <= date)
+        };
+
+        let candidates: Box<dyn Iterator<Item = &IndexedMessage>> = match docs {
+            Some(docs) => Box::new(docs.into_iter().map(|doc| &self.messages[doc as usize])),
+            None => Box::new(self.messages.iter()),
+        };
+        candidates
+            .filter(|message| wanted(message))
+            .map(|message| message.msg_num)
+            .collect()
+    }
+
+    /// Load a .jsi file
+    ///
+    /// # Returns
+    /// `None` if there is no index or it cannot be read back, in which case
+    /// it should be rebuilt
+    pub async fn load(path: impl AsRef<Path>) -> Result<Option<Self>> {
+        let data = match tokio::fs::read(path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let Some((base_msg_num, count, data_len)) = parse_header(&data) else {
+            return Ok(None);
+        };
+        let Some(mut records) = data
+            .get(JAM_SEARCH_INDEX_HEADER_SIZE..)
+            .and_then(|records| records.get(..data_len as usize))
+        else {
+            return Ok(None);
+        };
+
+        let mut index = Self::new(base_msg_num);
+        for _ in 0..count {
+            let Some((entry, len)) = IndexEntry::from_bytes(records) else {
+                return Ok(None);
+            };
+            if entry.message.msg_num != index.next_msg_num() {
+                return Ok(None);
+            }
+            index.insert(entry);
+            records = &records[len..];
+        }
+        Ok(Some(index))
+    }
+
+    /// Append a record to a .jsi file
+    ///
+    /// The record is only written if it is the next message the file
+    /// expects; a missing file is started when the record is the base's
+    /// first message.
+    ///
+    /// # Returns
+    /// Whether the record was written
+    pub async fn append(
+        path: impl AsRef<Path>,
+        base_msg_num: u32,
+        entry: &IndexEntry,
+    ) -> Result<bool> {
+        let path = path.as_ref();
+        let mut file = match OpenOptions::new().read(true).write(true).open(path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                if entry.message.msg_num != base_msg_num {
+                    return Ok(false);
+                }
+                let mut file = OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .create_new(true)
+                    .open(path)
+                    .await?;
+                file.write_all(&header_bytes(base_msg_num, 0, 0)).await?;
+                file
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut header = [0u8; JAM_SEARCH_INDEX_HEADER_SIZE];
+        file.seek(std::io::SeekFrom::Start(0)).await?;
+        if file.read_exact(&mut header).await.is_err() {
+            return Ok(false);
+        }
+        let Some((file_base, count, data_len)) = parse_header(&header) else {
+            return Ok(false);
+        };
+        if file_base != base_msg_num || file_base + count != entry.message.msg_num {
+            return Ok(false);
+        }
+
+        let record = entry.to_bytes();
+        file.seek(std::io::SeekFrom::Start(
+            JAM_SEARCH_INDEX_HEADER_SIZE as u64 + data_len,
+        ))
+        .await?;
+        file.write_all(&record).await?;
+        file.seek(std::io::SeekFrom::Start(0)).await?;
+        file.write_all(&header_bytes(
+            base_msg_num,
+            count + 1,
+            data_len + record.len() as u64,
+        ))
+        .await?;
+        file.flush().await?;
+        Ok(true)
+    }
+
+    /// Write a complete .jsi file, replacing any old one
+    ///
+    /// `records` holds `count` serialized [`IndexEntry`]s in message order.
+    pub async fn write_file(
+        path: impl AsRef<Path>,
+        base_msg_num: u32,
+        count: u32,
+        records: &[u8],
+    ) -> Result<()> {
+        let mut data = header_bytes(base_msg_num, count, records.len() as u64);
+        data.extend_from_slice(records);
+        AtomicWriter::new(path).write(&data).await
+    }
+}
+
+/// Messages matching a query, by position in the index
+fn query_docs(postings: &HashMap<String, Vec<Posting>>, query: &SearchQuery) -> Vec<u32> {
+    let mut docs = Vec::new();
+    for alternative in &query.alternatives {
+        let mut found: Option<Vec<u32>> = None;
+        for phrase in alternative {
+            let phrase_docs = phrase_docs(postings, phrase);
+            found = Some(match found {
+                Some(found) => intersect(&found, &phrase_docs),
+                None => phrase_docs,
+            });
+        }
+        docs.extend(found.unwrap_or_default());
+    }
+    docs.sort_unstable();
+    docs.dedup();
+    docs
+}
+
+/// Messages where the words of a phrase appear together and in order
+fn phrase_docs(postings: &HashMap<String, Vec<Posting>>, phrase: &[String]) -> Vec<u32> {
+    let Some(lists) = phrase
+        .iter()
+        .map(|word| postings.get(word))
+        .collect::<Option<Vec<_>>>()
+    else {
+        return Vec::new();
+    };
+
+    let mut docs = Vec::new();
+    'docs: for first in lists[0] {
+        let mut rest = Vec::with_capacity(lists.len() - 1);
+        for list in &lists[1..] {
+            match list.binary_search_by_key(&first.doc, |posting| posting.doc) {
+                Ok(found) => rest.push(&list[found]),
+                Err(_) => continue 'docs,
+            }
+        }
+        let in_order = |start: &u32| {
+            rest.iter().enumerate().all(|(offset, posting)| {
+                posting
+                    .positions
+                    .binary_search(&(start + offset as u32 + 1))
+                    .is_ok()
+            })
+        };
+        if first.positions.iter().any(in_order) {
+            docs.push(first.doc);
+        }
+    }
+    docs
+}
+
+/// Values in both ascending lists
+fn intersect(a: &[u32], b: &[u32]) -> Vec<u32> {
+    a.iter()
+        .filter(|value| b.binary_search(value).is_ok())
+        .copied()
+        .collect()
+}
+
+/// Build a .jsi header
+fn header_bytes(base_msg_num: u32, count: u32, data_len: u64) -> Vec<u8> {
+    let mut header = Vec::with_capacity(JAM_SEARCH_INDEX_HEADER_SIZE);
+    header.extend_from_slice(&JAM_SEARCH_INDEX_MAGIC);
+    header.extend_from_slice(&base_msg_num.to_le_bytes());
+    header.extend_from_slice(&count.to_le_bytes());
+    header.extend_from_slice(&data_len.to_le_bytes());
+    header
+}
+
+/// Parse a .jsi header into (base message number, count, data length)
+fn parse_header(data: &[u8]) -> Option<(u32, u32, u64)> {
+    let mut reader = Reader::new(data);
+    if reader.bytes(8)? != JAM_SEARCH_INDEX_MAGIC {
+        return None;
+    }
+    let base_msg_num = reader.u32()?;
+    let count = reader.u32()?;
+    let data_len = u64::from_le_bytes(reader.bytes(8)?.try_into().ok()?);
+    Some((base_msg_num, count, data_len))
+}
+
+/// Append a string with a 16-bit length
+fn put_string(out: &mut Vec<u8>, value: &str) {
+    let mut end = value.len().min(usize::from(u16::MAX));
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    out.extend_from_slice(&(end as u16).to_le_bytes());
+    out.extend_from_slice(&value.as_bytes()[..end]);
+}
+
+/// Append a LEB128 number
+fn put_varint(out: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Bounds-checked reader over record bytes
+struct Reader<'a> {
+    /// The bytes
+    data: &'a [u8],
+    /// Read position
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Read from the start of `data`
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    /// The next `len` bytes
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position.checked_add(len)?)?;
+        self.position += len;
+        Some(bytes)
+    }
+
+    /// A little-endian u32
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.bytes(4)?.try_into().ok()?))
+    }
+
+    /// A string with a 16-bit length
+    fn string(&mut self) -> Option<String> {
+        let len = u16::from_le_bytes(self.bytes(2)?.try_into().ok()?);
+        String::from_utf8(self.bytes(usize::from(len))?.to_vec()).ok()
+    }
+
+    /// A LEB128 number
+    fn varint(&mut self) -> Option<u32> {
+        let mut value = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.bytes(1)?[0];
+            value |= u32::from(byte & 0x7f).checked_shl(shift)?;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn entry(msg_num: u32, from: &str, subject: &str, body: &str, day: u32) -> IndexEntry {
+        let mut message = JamMessage::new(from, "All", subject).with_body(body);
+        message.date_written = Utc.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap();
+        IndexEntry::new(msg_num, (msg_num, msg_num * 100), &message, body)
+    }
+
+    fn index() -> SearchIndex {
+        let mut index = SearchIndex::new(1);
+        index.insert(entry(
+            1,
+            "Alice",
+            "BinkP mailer",
+            "The mailer dials out nightly.",
+            1,
+        ));
+        index.insert(entry(
+            2,
+            "Bob",
+            "Echo areas",
+            "Tossing echo mail with the mailer.",
+            2,
+        ));
+        index.insert(entry(
+            3,
+            "Carol",
+            "Re: BinkP mailer",
+            "Out of the mailer, nightly.",
+            3,
+        ));
+        index
+    }
+
+    fn find(index: &SearchIndex, criteria: SearchCriteria) -> Vec<u32> {
+        index.find(&criteria)
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query = SearchQuery::parse("binkp AND \"echo mail\" OR e-mail");
+        assert_eq!(
+            query.alternatives,
+            [
+                vec![
+                    vec!["binkp".to_string()],
+                    vec!["echo".into(), "mail".into()]
+                ],
+                vec![vec!["e".to_string(), "mail".into()]],
+            ]
+        );
+        assert!(SearchQuery::parse(" -- ").is_empty());
+    }
+
+    #[test]
+    fn test_find() {
+        let index = index();
+        let body = |query: &str| find(&index, SearchCriteria::new().with_body(query));
+
+        assert_eq!(body("MAILER"), [1, 2, 3]);
+        assert_eq!(body("\"mailer dials\""), [1]);
+        assert_eq!(body("\"dials mailer\""), Vec::<u32>::new());
+        assert_eq!(body("nightly out"), [1, 3]);
+        assert_eq!(body("tossing OR dials"), [1, 2]);
+        assert_eq!(
+            find(
+                &index,
+                SearchCriteria::new().with_subject("binkp").with_from("CAR")
+            ),
+            [3]
+        );
+        assert_eq!(
+            find(
+                &index,
+                SearchCriteria::new().with_date_range(
+                    Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap(),
+                    Utc.with_ymd_and_hms(2024, 1, 3, 0, 0, 0).unwrap(),
+                )
+            ),
+            [2]
+        );
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let entry = entry(7, "Alice", "Hello there", "hello hello world", 1);
+        let mut bytes = entry.to_bytes();
+        bytes.extend_from_slice(b"next");
+
+        let (parsed, len) = IndexEntry::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, entry);
+        assert_eq!(len, bytes.len() - 4);
+        assert!(IndexEntry::from_bytes(&bytes[..len - 1]).is_none());
+
+        let unreadable = IndexEntry::unreadable(8, (8, 800));
+        let (parsed, _) = IndexEntry::from_bytes(&unreadable.to_bytes()).unwrap();
+        assert_eq!(parsed, unreadable);
+
+        let mut index = SearchIndex::new(7);
+        index.insert(entry);
+        index.insert(parsed);
+        assert_eq!(index.unreadable(), [8]);
+    }
+
+    #[tokio::test]
+    async fn test_append_and_load() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("general.jsi");
+
+        // Only the first message may start a file
+        assert!(
+            !SearchIndex::append(&path, 1, &entry(2, "A", "s", "b", 1))
+                .await
+                .unwrap()
+        );
+        assert!(
+            SearchIndex::append(&path, 1, &entry(1, "A", "one", "b", 1))
+                .await
+                .unwrap()
+        );
+        assert!(
+            SearchIndex::append(&path, 1, &entry(2, "B", "two", "b", 2))
+                .await
+                .unwrap()
+        );
+        assert!(
+            !SearchIndex::append(&path, 1, &entry(4, "C", "four", "b", 3))
+                .await
+                .unwrap()
+        );
+
+        let index = SearchIndex::load(&path).await.unwrap().unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.next_msg_num(), 3);
+        assert!(index.agrees_with(1, &[(1, 100), (2, 200), (3, 300)]));
+        assert!(!index.agrees_with(1, &[(1, 100), (2, 999)]));
+        assert!(!index.agrees_with(1, &[(1, 100)]));
+
+        tokio::fs::write(&path, b"not an index").await.unwrap();
+        assert!(SearchIndex::load(&path).await.unwrap().is_none());
+    }
+}
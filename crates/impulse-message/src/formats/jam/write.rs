@@ -1,11 +1,101 @@
 //! JAM format writing functionality
 
-use super::{JamBaseHeader, JamMessageHeader, MessageAttributes, SubfieldType};
+use super::{
+    JAM_ATTRIBUTE_OFFSET, JamBaseHeader, JamMessageHeader, MessageAttributes, SubfieldType,
+};
 use crate::atomic::{AtomicMultiWriter, AtomicWriter};
 use crate::error::{MessageError, Result};
-use crate::types::NewMessage;
-use chrono::Utc;
+use crate::types::{KludgeLine, NewMessage};
+use chrono::{DateTime, Utc};
 use std::path::Path;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+/// A message as stored in a JAM base, including its network control lines
+///
+/// Used to write messages exactly as received from a network, without the
+/// validation and sanitizing applied to messages posted by callers, and to
+/// read them back for export.
+#[derive(Debug, Clone)]
+pub struct JamMessage {
+    /// Sender name
+    pub from: String,
+    /// Recipient name
+    pub to: String,
+    /// Subject
+    pub subject: String,
+    /// Message text, without kludge, SEEN-BY or PATH lines
+    pub body: String,
+    /// When the message was written
+    pub date_written: DateTime<Utc>,
+    /// Attribute flags (see [`MessageAttributes`])
+    pub attributes: u32,
+    /// Parent message number (if reply)
+    pub reply_to: Option<u32>,
+    /// Sender's network address
+    pub from_address: Option<String>,
+    /// Recipient's network address
+    pub to_address: Option<String>,
+    /// MSGID kludge value
+    pub msgid: Option<String>,
+    /// REPLY kludge value
+    pub reply_id: Option<String>,
+    /// SEEN-BY lines, without the "SEEN-BY:" prefix
+    pub seen_by: Vec<String>,
+    /// PATH lines, without the "PATH:" prefix
+    pub path: Vec<String>,
+    /// Any other kludge lines
+    pub kludges: Vec<KludgeLine>,
+    /// Paths of attached files
+    pub attachments: Vec<String>,
+}
+
+impl JamMessage {
+    /// Create a message with no network information
+    pub fn new(from: impl Into<String>, to: impl Into<String>, subject: impl Into<String>) -> Self {
+        Self {
+            from: from.into(),
+            to: to.into(),
+            subject: subject.into(),
+            body: String::new(),
+            date_written: Utc::now(),
+            attributes: 0,
+            reply_to: None,
+            from_address: None,
+            to_address: None,
+            msgid: None,
+            reply_id: None,
+            seen_by: Vec::new(),
+            path: Vec::new(),
+            kludges: Vec::new(),
+            attachments: Vec::new(),
+        }
+    }
+
+    /// Set the message text
+    pub fn with_body(mut self, body: impl Into<String>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Set attribute flags
+    pub fn with_attributes(mut self, attributes: u32) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Get the attribute flags
+    pub fn attributes(&self) -> MessageAttributes {
+        MessageAttributes::new(self.attributes)
+    }
+}
+
+/// JAM CRC-32 of a string, as used for the index and MSGID/REPLY CRCs
+///
+/// The string is lowercased first, and unlike standard CRC-32 the result is
+/// not inverted.
+pub fn jam_crc(value: &str) -> u32 {
+    !crc32fast::hash(value.to_lowercase().as_bytes())
+}
 
 /// JAM message writer
 pub struct JamWriter {
@@ -35,9 +125,38 @@ impl JamWriter {
         message: &NewMessage,
         msg_num: u32,
         current_offset: u32,
+    ) -> Result<(Vec<u8>, Vec<u8>, u32)> {
+        // Build message attributes
+        let mut attributes = MessageAttributes::LOCAL;
+        if message.is_private {
+            attributes |= MessageAttributes::PRIVATE;
+        }
+
+        let mut jam_message = JamMessage::new(&message.from, &message.to, &message.subject)
+            .with_body(&message.body)
+            .with_attributes(attributes);
+        jam_message.reply_to = message.reply_to;
+
+        self.write_jam_message(&jam_message, msg_num, current_offset)
+            .await
+    }
+
+    /// Write a message with its network information to the JAM base
+    ///
+    /// The MSGID, REPLY, addresses, SEEN-BY, PATH, other kludges and
+    /// attached files are stored as subfields, and the MSGID and REPLY CRCs
+    /// are filled in.
+    ///
+    /// # Returns
+    /// Tuple of (.JHR bytes, .JDT bytes, next offset)
+    pub async fn write_jam_message(
+        &self,
+        message: &JamMessage,
+        msg_num: u32,
+        current_offset: u32,
     ) -> Result<(Vec<u8>, Vec<u8>, u32)> {
         // Build subfields
-        let subfields = vec![
+        let mut subfields = vec![
             // From field
             Self::create_subfield(SubfieldType::SendName, message.from.as_bytes()),
             // To field
@@ -45,16 +164,40 @@ impl JamWriter {
             // Subject field
             Self::create_subfield(SubfieldType::Subject, message.subject.as_bytes()),
         ];
+        let optional = [
+            (SubfieldType::SendAddr, &message.from_address),
+            (SubfieldType::RecvAddr, &message.to_address),
+            (SubfieldType::MsgId, &message.msgid),
+            (SubfieldType::ReplyId, &message.reply_id),
+        ];
+        for (field_type, value) in optional {
+            if let Some(value) = value {
+                subfields.push(Self::create_subfield(field_type, value.as_bytes()));
+            }
+        }
+        for kludge in &message.kludges {
+            let line = format!("{}: {}", kludge.kludge_type, kludge.value);
+            subfields.push(Self::create_subfield(
+                SubfieldType::FtsKludge,
+                line.as_bytes(),
+            ));
+        }
+        for line in &message.seen_by {
+            subfields.push(Self::create_subfield(SubfieldType::SeenBy, line.as_bytes()));
+        }
+        for line in &message.path {
+            subfields.push(Self::create_subfield(SubfieldType::Path, line.as_bytes()));
+        }
+        for file in &message.attachments {
+            subfields.push(Self::create_subfield(
+                SubfieldType::EnclosedFile,
+                file.as_bytes(),
+            ));
+        }
 
         // Calculate subfield total length
         let subfield_len: u32 = subfields.iter().map(|s| s.len() as u32).sum();
 
-        // Build message attributes
-        let mut attributes = MessageAttributes::LOCAL;
-        if message.is_private {
-            attributes |= MessageAttributes::PRIVATE;
-        }
-
         // Get current timestamp
         let now = Utc::now().timestamp() as u32;
 
@@ -65,16 +208,16 @@ impl JamWriter {
             reserved: 0,
             subfield_len,
             times_read: 0,
-            msg_id_crc: 0, // Would calculate CRC in production
-            reply_id_crc: 0,
+            msg_id_crc: message.msgid.as_deref().map_or(0, jam_crc),
+            reply_id_crc: message.reply_id.as_deref().map_or(0, jam_crc),
             reply_to: message.reply_to.unwrap_or(0),
             reply_1st: 0,
             reply_next: 0,
-            date_written: now,
+            date_written: message.date_written.timestamp() as u32,
             date_received: now,
             date_processed: 0,
             msg_num,
-            attribute: attributes,
+            attribute: message.attributes,
             attribute2: 0,
             offset: current_offset,
             text_len: message.body.len() as u32,
@@ -220,6 +363,39 @@ impl JamWriter {
         Ok(())
     }
 
+    /// Append a record to the .JDX index
+    ///
+    /// # Arguments
+    /// * `to_crc` - [`jam_crc`] of the recipient's name
+    /// * `header_offset` - Offset of the message header in the .JHR file
+    pub async fn append_index(&self, to_crc: u32, header_offset: u32) -> Result<()> {
+        let mut record = Vec::with_capacity(8);
+        record.extend_from_slice(&to_crc.to_le_bytes());
+        record.extend_from_slice(&header_offset.to_le_bytes());
+
+        let jdx_writer = AtomicWriter::new(format!("{}.jdx", self.base_path));
+        jdx_writer.append(&record).await
+    }
+
+    /// Overwrite the attribute word of the message header at `header_offset`
+    ///
+    /// Only the four attribute bytes change, so this is done in place.
+    pub async fn update_attributes(&self, header_offset: u32, attributes: u32) -> Result<()> {
+        let jhr_path = format!("{}.jhr", self.base_path);
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .open(&jhr_path)
+            .await
+            .map_err(|e| MessageError::WriteError(format!("Failed to open {}: {}", jhr_path, e)))?;
+        file.seek(std::io::SeekFrom::Start(
+            u64::from(header_offset) + JAM_ATTRIBUTE_OFFSET,
+        ))
+        .await?;
+        file.write_all(&attributes.to_le_bytes()).await?;
+        file.sync_all().await?;
+        Ok(())
+    }
+
     /// Initialize a new JAM message base
     pub async fn initialize_base(&self) -> Result<()> {
         let jhr_path = format!("{}.jhr", self.base_path);
@@ -266,6 +442,16 @@ impl JamWriter {
             Err(_) => Ok(0),
         }
     }
+
+    /// Get current .JHR file size
+    pub async fn get_jhr_size(&self) -> Result<u32> {
+        let jhr_path = format!("{}.jhr", self.base_path);
+
+        match tokio::fs::metadata(&jhr_path).await {
+            Ok(metadata) => Ok(metadata.len() as u32),
+            Err(_) => Ok(0),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,231 @@
+//! BinkP frames
+//!
+//! Every frame starts with a two-byte big-endian header. The top bit marks a
+//! command frame and the other 15 bits give the length of what follows. A
+//! command frame's first byte is the command number and the rest is its
+//! argument text; a data frame carries part of the file being sent.
+
+use crate::ftn::error::{FtnError, Result};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Largest payload a frame can carry
+pub const MAX_FRAME_SIZE: usize = 0x7FFF;
+
+/// Bit marking a command frame in the header
+const COMMAND_BIT: u16 = 0x8000;
+
+/// BinkP commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Information about the system, ignored by the protocol
+    Nul = 0,
+    /// The sender's addresses
+    Adr = 1,
+    /// Session password
+    Pwd = 2,
+    /// Start of a file
+    File = 3,
+    /// Password accepted
+    Ok = 4,
+    /// No more files to send
+    Eob = 5,
+    /// File received
+    Got = 6,
+    /// Fatal error, the session ends
+    Err = 7,
+    /// System busy, the session ends
+    Bsy = 8,
+    /// Resend a file from an offset
+    Get = 9,
+    /// Skip a file for now
+    Skip = 10,
+}
+
+impl Command {
+    /// Command for a command number
+    fn from_id(id: u8) -> Option<Self> {
+        Some(match id {
+            0 => Command::Nul,
+            1 => Command::Adr,
+            2 => Command::Pwd,
+            3 => Command::File,
+            4 => Command::Ok,
+            5 => Command::Eob,
+            6 => Command::Got,
+            7 => Command::Err,
+            8 => Command::Bsy,
+            9 => Command::Get,
+            10 => Command::Skip,
+            _ => return None,
+        })
+    }
+}
+
+/// A BinkP frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    /// Command with its argument text
+    Command(Command, String),
+    /// Command this implementation does not know, which is ignored
+    Unknown(u8),
+    /// Part of a file
+    Data(Vec<u8>),
+}
+
+impl Frame {
+    /// Create a command frame
+    pub fn command(command: Command, args: impl Into<String>) -> Self {
+        Frame::Command(command, args.into())
+    }
+}
+
+/// Read the next frame
+///
+/// Returns `None` if the connection closed between frames.
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<Frame>> {
+    let mut header = [0u8; 2];
+    match reader.read_exact(&mut header).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+
+    let header = u16::from_be_bytes(header);
+    let mut payload = vec![0u8; usize::from(header & !COMMAND_BIT)];
+    reader.read_exact(&mut payload).await?;
+
+    if header & COMMAND_BIT == 0 {
+        return Ok(Some(Frame::Data(payload)));
+    }
+
+    let Some((&id, args)) = payload.split_first() else {
+        return Err(FtnError::Session("empty command frame".to_string()));
+    };
+    let Some(command) = Command::from_id(id) else {
+        return Ok(Some(Frame::Unknown(id)));
+    };
+    let args = String::from_utf8_lossy(args)
+        .trim_end_matches('\0')
+        .to_string();
+    Ok(Some(Frame::Command(command, args)))
+}
+
+/// Write a frame
+pub async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, frame: &Frame) -> Result<()> {
+    let (header, payload): (u16, Vec<u8>) = match frame {
+        Frame::Command(command, args) => {
+            let mut payload = Vec::with_capacity(args.len() + 1);
+            payload.push(*command as u8);
+            payload.extend_from_slice(args.as_bytes());
+            (COMMAND_BIT, payload)
+        }
+        Frame::Unknown(id) => (COMMAND_BIT, vec![*id]),
+        Frame::Data(data) => (0, data.clone()),
+    };
+
+    if payload.len() > MAX_FRAME_SIZE {
+        return Err(FtnError::Session(format!(
+            "frame of {} bytes is too large",
+            payload.len()
+        )));
+    }
+
+    let mut bytes = Vec::with_capacity(payload.len() + 2);
+    bytes.extend_from_slice(&(header | payload.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(&payload);
+    writer.write_all(&bytes).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Escape a file name for a command argument
+///
+/// Spaces, backslashes and control characters are written as `\xHH`.
+pub fn escape_name(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        if byte <= b' ' || byte == b'\\' || byte >= 0x7F {
+            escaped.push_str(&format!("\\x{:02x}", byte));
+        } else {
+            escaped.push(byte as char);
+        }
+    }
+    escaped
+}
+
+/// Undo [`escape_name`]
+pub fn unescape_name(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\'
+            && i + 4 <= bytes.len()
+            && bytes[i + 1] == b'x'
+            && let Some(value) = std::str::from_utf8(&bytes[i + 2..i + 4])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            unescaped.push(value);
+            i += 4;
+        } else {
+            unescaped.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&unescaped).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_frame_round_trip() {
+        let frames = vec![
+            Frame::command(Command::Adr, "1:234/5@fidonet 2:5020/1"),
+            Frame::Data(vec![1, 2, 3]),
+            Frame::command(Command::Eob, ""),
+        ];
+
+        let mut bytes = Vec::new();
+        for frame in &frames {
+            write_frame(&mut bytes, frame).await.unwrap();
+        }
+        assert_eq!(&bytes[..3], &[0x80, 25, 1]);
+
+        let mut reader = bytes.as_slice();
+        for frame in &frames {
+            assert_eq!(read_frame(&mut reader).await.unwrap().as_ref(), Some(frame));
+        }
+        assert_eq!(read_frame(&mut reader).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_command_and_trailing_nul() {
+        let bytes = [0x80, 2, 42, 0, 0x80, 4, 7, b'b', b'y', 0];
+        let mut reader = &bytes[..];
+        assert_eq!(
+            read_frame(&mut reader).await.unwrap(),
+            Some(Frame::Unknown(42))
+        );
+        assert_eq!(
+            read_frame(&mut reader).await.unwrap(),
+            Some(Frame::command(Command::Err, "by"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_oversized_frame_is_refused() {
+        let mut bytes = Vec::new();
+        let frame = Frame::Data(vec![0; MAX_FRAME_SIZE + 1]);
+        assert!(write_frame(&mut bytes, &frame).await.is_err());
+    }
+
+    #[test]
+    fn test_name_escaping() {
+        assert_eq!(escape_name("my file.zip"), "my\\x20file.zip");
+        assert_eq!(unescape_name("my\\x20file.zip"), "my file.zip");
+        assert_eq!(unescape_name("odd\\x"), "odd\\x");
+    }
+}
@@ -0,0 +1,369 @@
+//! BinkP mailer
+//!
+//! Exchanges packets, bundles and file attaches with links over TCP using the
+//! BinkP protocol (FTS-1026), with CRAM-MD5 session passwords (FTS-1027).
+//!
+//! Outgoing mail is taken from the BinkleyTerm-style outbound the
+//! [`Scanner`](crate::ftn::Scanner) writes: `.?ut` packets and `.?lo` flow
+//! files, sent crash and direct mail first. Received files are written to the
+//! inbound the [`Tosser`](crate::ftn::Tosser) reads, or to its `insecure`
+//! subdirectory for sessions without a password. Partly received files are
+//! kept and resumed on the next session.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use impulse_message::addressing::FidoAddress;
+//! use impulse_message::ftn::binkp::Mailer;
+//! use impulse_message::ftn::{FtnConfig, FtnLink};
+//! use impulse_message::routing::RouterConfig;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let uplink = FidoAddress::node(1, 234, 1);
+//! let config = FtnConfig::new(
+//!     RouterConfig::new(FidoAddress::node(1, 234, 5)),
+//!     "/ftn/inbound",
+//!     "/ftn/outbound",
+//! )
+//! .with_link(
+//!     FtnLink::new(uplink)
+//!         .with_session_password("SECRET")
+//!         .with_host("hub.example.org"),
+//! );
+//!
+//! let stats = Mailer::new(config).poll(uplink).await?;
+//! println!("Sent {} files, received {}", stats.sent.len(), stats.received.len());
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod frame;
+pub mod outgoing;
+pub mod session;
+
+pub use outgoing::{BusyFlags, OutgoingFile, collect, has_mail};
+pub use session::{Role, SessionStats};
+
+use crate::addressing::FidoAddress;
+use crate::ftn::config::FtnConfig;
+use crate::ftn::error::{FtnError, Result};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+
+/// Port BinkP mailers listen on
+pub const DEFAULT_BINKP_PORT: u16 = 24554;
+
+/// How long to wait for a link to accept a call
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// BinkP mailer for a system
+#[derive(Debug, Clone)]
+pub struct Mailer {
+    /// Mailer configuration
+    config: FtnConfig,
+}
+
+impl Mailer {
+    /// Create a mailer
+    pub fn new(config: FtnConfig) -> Self {
+        Self { config }
+    }
+
+    /// The mailer configuration
+    pub fn config(&self) -> &FtnConfig {
+        &self.config
+    }
+
+    /// Call a link at its configured host and exchange mail
+    ///
+    /// # Errors
+    /// Fails if the link is not configured, has no host, is already in a
+    /// session, or the session fails.
+    pub async fn poll(&self, address: FidoAddress) -> Result<SessionStats> {
+        let link = self
+            .config
+            .link(address)
+            .ok_or_else(|| FtnError::Session(format!("{} is not a configured link", address)))?;
+        let host = link
+            .host
+            .as_deref()
+            .ok_or_else(|| FtnError::Session(format!("no host configured for {}", address)))?;
+
+        let mut busy = BusyFlags::new();
+        if !busy.try_lock(&self.config.outbound, self.config.address().zone, address)? {
+            return Err(FtnError::Session(format!(
+                "{} is already in a session",
+                address
+            )));
+        }
+
+        let stream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(with_port(host)))
+            .await
+            .map_err(|_| FtnError::Timeout)??;
+        session::run_session(
+            &self.config,
+            stream,
+            Role::Originator,
+            Some(address),
+            &mut busy,
+        )
+        .await
+    }
+
+    /// Exchange mail with a link over a connection we opened
+    pub async fn call<S>(&self, stream: S, address: FidoAddress) -> Result<SessionStats>
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let mut busy = BusyFlags::new();
+        session::run_session(
+            &self.config,
+            stream,
+            Role::Originator,
+            Some(address),
+            &mut busy,
+        )
+        .await
+    }
+
+    /// Exchange mail with a system that called us
+    pub async fn answer<S>(&self, stream: S) -> Result<SessionStats>
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let mut busy = BusyFlags::new();
+        session::run_session(&self.config, stream, Role::Answerer, None, &mut busy).await
+    }
+}
+
+/// Add the default port to a host that has none
+fn with_port(host: &str) -> String {
+    if host.parse::<SocketAddr>().is_ok() {
+        return host.to_string();
+    }
+    if let Ok(ip) = host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        return SocketAddr::new(ip, DEFAULT_BINKP_PORT).to_string();
+    }
+    match host.rsplit_once(':') {
+        Some((_, port)) if port.parse::<u16>().is_ok() => host.to_string(),
+        _ => format!("{}:{}", host, DEFAULT_BINKP_PORT),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ftn::config::FtnLink;
+    use crate::ftn::outbound::{Flavour, node_base_path};
+    use crate::routing::RouterConfig;
+    use std::path::Path;
+    use tempfile::TempDir;
+    use tokio::net::TcpListener;
+
+    /// Configuration for a system keeping its mail under `dir`
+    fn config(dir: &Path, address: FidoAddress, link: FtnLink) -> FtnConfig {
+        FtnConfig::new(
+            RouterConfig::new(address),
+            dir.join("inbound"),
+            dir.join("outbound"),
+        )
+        .with_system(format!("System {}", address), "Sysop")
+        .with_link(link)
+    }
+
+    /// Queue a packet from `config` to `address`
+    fn queue(config: &FtnConfig, address: FidoAddress, flavour: Flavour, data: &[u8]) {
+        let base = node_base_path(&config.outbound, config.address().zone, address);
+        std::fs::create_dir_all(base.parent().unwrap()).unwrap();
+        std::fs::write(base.with_extension(flavour.packet_extension()), data).unwrap();
+    }
+
+    /// Run a session between two mailers over loopback
+    async fn exchange(
+        caller: &Mailer,
+        answerer: &Mailer,
+    ) -> (Result<SessionStats>, Result<SessionStats>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let answerer = answerer.clone();
+        let answering = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            answerer.answer(stream).await
+        });
+
+        let mut config = caller.config().clone();
+        let remote = config.links[0].address;
+        config.links[0].host = Some(format!("127.0.0.1:{}", port));
+        let called = Mailer::new(config).poll(remote).await;
+        (called, answering.await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_secure_exchange() {
+        let dir_a = TempDir::new().unwrap();
+        let dir_b = TempDir::new().unwrap();
+        let addr_a = FidoAddress::node(2, 5020, 9);
+        let addr_b = FidoAddress::node(2, 5020, 1);
+
+        let config_a = config(
+            dir_a.path(),
+            addr_a,
+            FtnLink::new(addr_b).with_session_password("SeCrEt"),
+        );
+        let config_b = config(
+            dir_b.path(),
+            addr_b,
+            FtnLink::new(addr_a).with_session_password("SeCrEt"),
+        );
+
+        // A sends a packet and a file attach, B has held mail for A
+        queue(&config_a, addr_b, Flavour::Normal, b"packet from A");
+        let attach = dir_a.path().join("NEWS.ZIP");
+        std::fs::write(&attach, vec![7u8; 10_000]).unwrap();
+        let base = node_base_path(&config_a.outbound, 2, addr_b);
+        std::fs::write(
+            base.with_extension(Flavour::Normal.flow_extension()),
+            format!("^{}\r\n", attach.display()),
+        )
+        .unwrap();
+        queue(&config_b, addr_a, Flavour::Hold, b"packet from B");
+
+        let (called, answered) =
+            exchange(&Mailer::new(config_a.clone()), &Mailer::new(config_b)).await;
+        let called = called.unwrap();
+        let answered = answered.unwrap();
+
+        assert!(called.secure && answered.secure);
+        assert_eq!(called.remote_addresses, vec![addr_b]);
+        assert_eq!(answered.remote_addresses, vec![addr_a]);
+        assert_eq!(answered.remote_system.as_deref(), Some("System 2:5020/9"));
+        assert_eq!(called.sent.len(), 2);
+        assert_eq!(called.received.len(), 1);
+        assert_eq!(answered.received.len(), 2);
+
+        // Everything sent is gone from the outbounds
+        assert!(!attach.exists());
+        assert!(!has_mail(&config_a.outbound, 2, addr_b, &[Flavour::Normal]));
+        assert_eq!(
+            std::fs::read(&called.received[0]).unwrap(),
+            b"packet from B"
+        );
+        assert_eq!(
+            std::fs::read(dir_b.path().join("inbound/NEWS.ZIP")).unwrap(),
+            vec![7u8; 10_000]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wrong_password_is_refused() {
+        let dir_a = TempDir::new().unwrap();
+        let dir_b = TempDir::new().unwrap();
+        let addr_a = FidoAddress::node(2, 5020, 9);
+        let addr_b = FidoAddress::node(2, 5020, 1);
+
+        let config_a = config(
+            dir_a.path(),
+            addr_a,
+            FtnLink::new(addr_b).with_session_password("guess"),
+        );
+        let config_b = config(
+            dir_b.path(),
+            addr_b,
+            FtnLink::new(addr_a).with_session_password("SeCrEt"),
+        );
+        queue(&config_a, addr_b, Flavour::Normal, b"packet from A");
+
+        let (called, answered) =
+            exchange(&Mailer::new(config_a.clone()), &Mailer::new(config_b)).await;
+        assert!(matches!(called, Err(FtnError::Remote(_))));
+        assert!(matches!(answered, Err(FtnError::SessionPassword(address)) if address == addr_a));
+        assert!(has_mail(&config_a.outbound, 2, addr_b, &[Flavour::Normal]));
+    }
+
+    #[tokio::test]
+    async fn test_unprotected_session_uses_insecure_inbound() {
+        let dir_a = TempDir::new().unwrap();
+        let dir_b = TempDir::new().unwrap();
+        let addr_a = FidoAddress::node(2, 5020, 9);
+        let addr_b = FidoAddress::node(2, 5020, 1);
+
+        let config_a = config(dir_a.path(), addr_a, FtnLink::new(addr_b));
+        let config_b = config(dir_b.path(), addr_b, FtnLink::new(addr_a));
+        queue(&config_a, addr_b, Flavour::Crash, b"packet from A");
+
+        let (called, answered) = exchange(&Mailer::new(config_a), &Mailer::new(config_b)).await;
+        assert!(!called.unwrap().secure);
+        let answered = answered.unwrap();
+        assert!(!answered.secure);
+        assert_eq!(answered.received.len(), 1);
+        assert!(answered.received[0].starts_with(dir_b.path().join("inbound/insecure")));
+    }
+
+    #[tokio::test]
+    async fn test_partial_file_is_resumed() {
+        let dir_a = TempDir::new().unwrap();
+        let dir_b = TempDir::new().unwrap();
+        let addr_a = FidoAddress::node(2, 5020, 9);
+        let addr_b = FidoAddress::node(2, 5020, 1);
+
+        let config_a = config(
+            dir_a.path(),
+            addr_a,
+            FtnLink::new(addr_b).with_session_password("pw"),
+        );
+        let config_b = config(
+            dir_b.path(),
+            addr_b,
+            FtnLink::new(addr_a).with_session_password("pw"),
+        );
+
+        let contents: Vec<u8> = (0..20_000u32).map(|i| (i % 251) as u8).collect();
+        let attach = dir_a.path().join("BIG.ZIP");
+        std::fs::write(&attach, &contents).unwrap();
+        let base = node_base_path(&config_a.outbound, 2, addr_b);
+        std::fs::create_dir_all(base.parent().unwrap()).unwrap();
+        std::fs::write(
+            base.with_extension(Flavour::Normal.flow_extension()),
+            format!("{}\n", attach.display()),
+        )
+        .unwrap();
+
+        // B kept the first part of the file from an earlier session
+        let time = std::fs::metadata(&attach)
+            .unwrap()
+            .modified()
+            .unwrap()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let inbound = dir_b.path().join("inbound");
+        std::fs::create_dir_all(&inbound).unwrap();
+        let part = inbound.join(session::part_file_name("BIG.ZIP", 20_000, time));
+        std::fs::write(&part, &contents[..5_000]).unwrap();
+
+        let (called, answered) = exchange(&Mailer::new(config_a), &Mailer::new(config_b)).await;
+        assert_eq!(called.unwrap().sent, vec!["BIG.ZIP".to_string()]);
+        assert_eq!(answered.unwrap().received, vec![inbound.join("BIG.ZIP")]);
+        assert_eq!(std::fs::read(inbound.join("BIG.ZIP")).unwrap(), contents);
+        assert!(!part.exists());
+
+        // The file attach is kept, its flow file is not
+        assert!(attach.exists());
+        assert!(
+            !base
+                .with_extension(Flavour::Normal.flow_extension())
+                .exists()
+        );
+    }
+
+    #[test]
+    fn test_with_port() {
+        assert_eq!(with_port("hub.example.org"), "hub.example.org:24554");
+        assert_eq!(with_port("hub.example.org:24555"), "hub.example.org:24555");
+        assert_eq!(with_port("10.0.0.1"), "10.0.0.1:24554");
+        assert_eq!(with_port("::1"), "[::1]:24554");
+        assert_eq!(with_port("[::1]:1000"), "[::1]:1000");
+    }
+}
@@ -0,0 +1,407 @@
+//! Mail waiting in the outbound for a node
+//!
+//! Besides packet files, a node may have flow files (`.flo`, `.clo`, `.dlo`,
+//! `.hlo` and `.ilo`) listing other files to send, one path per line. A
+//! leading `^` or `-` deletes the file once sent, `#` truncates it, and `~`
+//! or `!` marks a line already dealt with. Sending a node's mail is guarded
+//! by a `.bsy` flag next to its files.
+
+use crate::addressing::FidoAddress;
+use crate::ftn::error::Result;
+use crate::ftn::outbound::{Flavour, node_base_path};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Flavours in the order their mail is sent
+const SEND_ORDER: [Flavour; 4] = [
+    Flavour::Crash,
+    Flavour::Direct,
+    Flavour::Normal,
+    Flavour::Hold,
+];
+
+/// Extension of immediate flow files, sent before anything else
+const IMMEDIATE_FLOW: &str = "ilo";
+
+/// Age after which a busy flag is taken to be left over from a crash
+const STALE_BUSY_FLAG: Duration = Duration::from_secs(60 * 60);
+
+/// What to do with a file once the remote has it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SentAction {
+    /// Leave the file alone
+    Keep,
+    /// Delete the file
+    Delete,
+    /// Truncate the file to zero length
+    Truncate,
+}
+
+/// A file waiting to be sent
+#[derive(Debug, Clone)]
+pub struct OutgoingFile {
+    /// Where the file is
+    pub path: PathBuf,
+    /// Name it is sent under
+    pub name: String,
+    /// Size in bytes
+    pub size: u64,
+    /// Modification time (Unix seconds)
+    pub time: i64,
+    /// What to do once it is sent
+    action: SentAction,
+    /// Flow file and line that listed it
+    flow: Option<(PathBuf, usize)>,
+}
+
+impl OutgoingFile {
+    /// Describe a file, or `None` if it does not exist
+    async fn new(path: PathBuf, name: String, action: SentAction) -> Result<Option<Self>> {
+        let metadata = match tokio::fs::metadata(&path).await {
+            Ok(metadata) if metadata.is_file() => metadata,
+            Ok(_) => return Ok(None),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let time = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |age| age.as_secs() as i64);
+
+        Ok(Some(Self {
+            path,
+            name,
+            size: metadata.len(),
+            time,
+            action,
+            flow: None,
+        }))
+    }
+
+    /// Deal with the file once the remote has it
+    ///
+    /// Sent packets are deleted, and flow file entries are handled as their
+    /// line asks and marked as sent.
+    pub async fn mark_sent(&self) -> Result<()> {
+        match self.action {
+            SentAction::Keep => {}
+            SentAction::Delete => match tokio::fs::remove_file(&self.path).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            },
+            SentAction::Truncate => {
+                tokio::fs::OpenOptions::new()
+                    .write(true)
+                    .truncate(true)
+                    .open(&self.path)
+                    .await?;
+            }
+        }
+
+        if let Some((flow_path, line)) = &self.flow {
+            mark_flow_line(flow_path, *line).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Collect the files waiting for a node
+pub async fn collect(
+    outbound: &Path,
+    local_zone: u16,
+    address: FidoAddress,
+) -> Result<Vec<OutgoingFile>> {
+    let base = node_base_path(outbound, local_zone, address);
+    let mut files = Vec::new();
+
+    files.extend(read_flow(&base.with_extension(IMMEDIATE_FLOW)).await?);
+    for flavour in SEND_ORDER {
+        let packet = base.with_extension(flavour.packet_extension());
+        let name = format!("{:08x}.pkt", rand::random::<u32>());
+        if let Some(file) = OutgoingFile::new(packet, name, SentAction::Delete).await? {
+            files.push(file);
+        }
+        files.extend(read_flow(&base.with_extension(flavour.flow_extension())).await?);
+    }
+
+    Ok(files)
+}
+
+/// Whether a node has packets or flow files of any of `flavours` waiting
+pub fn has_mail(
+    outbound: &Path,
+    local_zone: u16,
+    address: FidoAddress,
+    flavours: &[Flavour],
+) -> bool {
+    let base = node_base_path(outbound, local_zone, address);
+    flavours.iter().any(|flavour| {
+        base.with_extension(flavour.packet_extension()).exists()
+            || base.with_extension(flavour.flow_extension()).exists()
+    })
+}
+
+/// Read the files listed in a flow file
+async fn read_flow(flow_path: &Path) -> Result<Vec<OutgoingFile>> {
+    let content = match tokio::fs::read_to_string(flow_path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut files = Vec::new();
+    let mut pending = false;
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        let (action, path) = match line.chars().next() {
+            None | Some('~') | Some('!') => continue,
+            Some('^') | Some('-') => (SentAction::Delete, &line[1..]),
+            Some('#') => (SentAction::Truncate, &line[1..]),
+            Some('@') => (SentAction::Keep, &line[1..]),
+            Some(_) => (SentAction::Keep, line),
+        };
+
+        let mut path = PathBuf::from(path);
+        if path.is_relative()
+            && let Some(dir) = flow_path.parent()
+        {
+            path = dir.join(path);
+        }
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        match OutgoingFile::new(path, name, action).await? {
+            Some(mut file) => {
+                file.flow = Some((flow_path.to_path_buf(), index));
+                files.push(file);
+                pending = true;
+            }
+            // Files that have gone away are dropped from the list
+            None => mark_flow_line(flow_path, index).await?,
+        }
+    }
+
+    if !pending {
+        remove_if_done(flow_path).await?;
+    }
+    Ok(files)
+}
+
+/// Mark a line of a flow file as sent, removing the file once all are
+async fn mark_flow_line(flow_path: &Path, index: usize) -> Result<()> {
+    let content = match tokio::fs::read_to_string(flow_path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    if let Some(line) = lines.get_mut(index) {
+        let path = line.trim_start_matches(['^', '-', '#', '@', '~', '!']);
+        *line = format!("~{}", path);
+    }
+
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    tokio::fs::write(flow_path, updated).await?;
+    remove_if_done(flow_path).await
+}
+
+/// Remove a flow file that has nothing left to send
+async fn remove_if_done(flow_path: &Path) -> Result<()> {
+    let content = match tokio::fs::read_to_string(flow_path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    let done = content
+        .lines()
+        .map(str::trim)
+        .all(|line| line.is_empty() || line.starts_with('~') || line.starts_with('!'));
+    if done {
+        tokio::fs::remove_file(flow_path).await?;
+    }
+    Ok(())
+}
+
+/// Busy flags held for the length of a session
+///
+/// Flags are removed when this is dropped.
+#[derive(Debug, Default)]
+pub struct BusyFlags {
+    /// Flags created by this session
+    held: Vec<PathBuf>,
+}
+
+impl BusyFlags {
+    /// Hold no flags
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take a node's busy flag
+    ///
+    /// Flags older than an hour are taken to be left over from a crash and
+    /// replaced.
+    ///
+    /// # Returns
+    /// `false` if another session holds the flag
+    pub fn try_lock(
+        &mut self,
+        outbound: &Path,
+        local_zone: u16,
+        address: FidoAddress,
+    ) -> Result<bool> {
+        let path = node_base_path(outbound, local_zone, address).with_extension("bsy");
+        if self.held.contains(&path) {
+            return Ok(true);
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        for _ in 0..2 {
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(_) => {
+                    std::fs::write(&path, std::process::id().to_string())?;
+                    self.held.push(path);
+                    return Ok(true);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let stale = std::fs::metadata(&path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                        .is_some_and(|age| age > STALE_BUSY_FLAG);
+                    if !stale {
+                        return Ok(false);
+                    }
+                    let _ = std::fs::remove_file(&path);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(false)
+    }
+}
+
+impl Drop for BusyFlags {
+    fn drop(&mut self) {
+        for path in &self.held {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const NODE: FidoAddress = FidoAddress {
+        zone: 2,
+        net: 5020,
+        node: 9,
+        point: 0,
+    };
+
+    #[tokio::test]
+    async fn test_collect_packets_and_flow_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let outbound = temp_dir.path().join("outbound");
+        let base = node_base_path(&outbound, 2, NODE);
+        tokio::fs::create_dir_all(&outbound).await.unwrap();
+
+        tokio::fs::write(base.with_extension("out"), b"packet")
+            .await
+            .unwrap();
+        tokio::fs::write(base.with_extension("cut"), b"crash")
+            .await
+            .unwrap();
+        let bundle = temp_dir.path().join("0000fff1.mo0");
+        let keep = temp_dir.path().join("keep.txt");
+        tokio::fs::write(&bundle, b"bundle").await.unwrap();
+        tokio::fs::write(&keep, b"keep").await.unwrap();
+        tokio::fs::write(
+            base.with_extension("flo"),
+            format!(
+                "^{}\n~already.sent\n{}\n/missing/file\n",
+                bundle.display(),
+                keep.display()
+            ),
+        )
+        .await
+        .unwrap();
+
+        let files = collect(&outbound, 2, NODE).await.unwrap();
+        assert_eq!(files.len(), 4);
+        assert_eq!(files[0].size, 5); // crash packet first
+        assert!(files[0].name.ends_with(".pkt"));
+        assert_eq!(files[2].name, "0000fff1.mo0");
+        assert_eq!(files[3].name, "keep.txt");
+
+        for file in &files {
+            file.mark_sent().await.unwrap();
+        }
+        assert!(!base.with_extension("out").exists());
+        assert!(!bundle.exists());
+        assert!(keep.exists());
+        assert!(!base.with_extension("flo").exists());
+        assert!(collect(&outbound, 2, NODE).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_partly_sent_flow_file_is_kept() {
+        let temp_dir = TempDir::new().unwrap();
+        let outbound = temp_dir.path().join("outbound");
+        let base = node_base_path(&outbound, 2, NODE);
+        tokio::fs::create_dir_all(&outbound).await.unwrap();
+        let first = temp_dir.path().join("a.txt");
+        let second = temp_dir.path().join("b.txt");
+        tokio::fs::write(&first, b"a").await.unwrap();
+        tokio::fs::write(&second, b"b").await.unwrap();
+        tokio::fs::write(
+            base.with_extension("hlo"),
+            format!("#{}\n{}\n", first.display(), second.display()),
+        )
+        .await
+        .unwrap();
+        assert!(has_mail(&outbound, 2, NODE, &[Flavour::Hold]));
+        assert!(!has_mail(&outbound, 2, NODE, &[Flavour::Crash]));
+
+        let files = collect(&outbound, 2, NODE).await.unwrap();
+        files[0].mark_sent().await.unwrap();
+
+        assert_eq!(tokio::fs::read(&first).await.unwrap().len(), 0);
+        let remaining = collect(&outbound, 2, NODE).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].name, "b.txt");
+    }
+
+    #[test]
+    fn test_busy_flags() {
+        let temp_dir = TempDir::new().unwrap();
+        let outbound = temp_dir.path().join("outbound");
+        let flag = node_base_path(&outbound, 2, NODE).with_extension("bsy");
+
+        let mut first = BusyFlags::new();
+        assert!(first.try_lock(&outbound, 2, NODE).unwrap());
+        assert!(first.try_lock(&outbound, 2, NODE).unwrap());
+        assert!(flag.exists());
+
+        let mut second = BusyFlags::new();
+        assert!(!second.try_lock(&outbound, 2, NODE).unwrap());
+
+        drop(first);
+        assert!(!flag.exists());
+        assert!(second.try_lock(&outbound, 2, NODE).unwrap());
+    }
+}
@@ -0,0 +1,864 @@
+//! BinkP session
+//!
+//! A session starts with both sides sending their system information and
+//! addresses. The originator then sends its password, answered by `M_OK` or
+//! `M_ERR`. Passwords are sent as a CRAM-MD5 digest of the challenge the
+//! answerer offers in an `OPT` line, or in plain text if none was offered.
+//!
+//! Both sides then send their files at the same time, each as an `M_FILE`
+//! command followed by data frames, and end with `M_EOB`. A receiver holding
+//! part of a file from an earlier session answers its `M_FILE` with `M_GET`
+//! and the sender starts again from that offset. The session is over once
+//! both sides have sent `M_EOB` and every file has been acknowledged with
+//! `M_GOT` or `M_SKIP`.
+
+use super::frame::{Command, Frame, escape_name, read_frame, unescape_name, write_frame};
+use super::outgoing::{BusyFlags, OutgoingFile, collect};
+use crate::addressing::FidoAddress;
+use crate::ftn::config::FtnConfig;
+use crate::ftn::error::{FtnError, Result};
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use std::collections::VecDeque;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::fs::File;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt, WriteHalf};
+use tokio::sync::mpsc;
+
+/// How long to wait for the remote before giving up
+pub const SESSION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Size of the data frames files are sent in
+const BLOCK_SIZE: usize = 4096;
+
+/// Prefix of CRAM-MD5 challenges and responses
+const CRAM_MD5: &str = "CRAM-MD5-";
+
+/// Subdirectory of the inbound that files from unprotected sessions go to
+pub const INSECURE_INBOUND: &str = "insecure";
+
+/// Which side of the session we are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// We called the remote system
+    Originator,
+    /// The remote system called us
+    Answerer,
+}
+
+/// Results of a session
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionStats {
+    /// Addresses the remote system presented
+    pub remote_addresses: Vec<FidoAddress>,
+    /// System name the remote system gave
+    pub remote_system: Option<String>,
+    /// Whether the session was password protected
+    pub secure: bool,
+    /// Names of the files sent and acknowledged
+    pub sent: Vec<String>,
+    /// Where received files were stored
+    pub received: Vec<PathBuf>,
+}
+
+/// A file being sent
+struct Sending {
+    /// The file
+    file: OutgoingFile,
+    /// Open handle, positioned at `offset`
+    handle: File,
+    /// Bytes sent so far
+    offset: u64,
+}
+
+/// A file being received
+struct Receiving {
+    /// Name as the remote sent it, for acknowledging
+    raw_name: String,
+    /// Name it is stored under
+    name: String,
+    /// Size in bytes
+    size: u64,
+    /// Modification time the remote gave
+    time: i64,
+    /// Partial file
+    part: PathBuf,
+    /// Bytes received so far
+    offset: u64,
+    /// Open partial file (none while waiting for the remote to restart the
+    /// file at the offset asked for)
+    handle: Option<File>,
+}
+
+/// Frames read from the remote system, in order
+type Frames = mpsc::UnboundedReceiver<Result<Option<Frame>>>;
+
+/// State of a session in progress
+struct Session<'a, S> {
+    /// Mailer configuration
+    config: &'a FtnConfig,
+    /// Connection to write frames to
+    writer: WriteHalf<S>,
+    /// Frames read from the connection
+    frames: Frames,
+    /// Results so far
+    stats: SessionStats,
+    /// CRAM-MD5 challenge offered by the remote
+    remote_challenge: Option<Vec<u8>>,
+    /// Files not yet started
+    queue: VecDeque<OutgoingFile>,
+    /// File being sent
+    sending: Option<Sending>,
+    /// Files sent but not yet acknowledged
+    unacked: Vec<OutgoingFile>,
+    /// Whether we sent M_EOB
+    eob_sent: bool,
+    /// Directory received files are stored in
+    inbound: PathBuf,
+    /// File being received
+    receiving: Option<Receiving>,
+    /// Whether the remote sent M_EOB
+    remote_eob: bool,
+}
+
+/// Run a session over a connection
+///
+/// # Arguments
+/// * `config` - Mailer configuration
+/// * `stream` - Connection to the remote system
+/// * `role` - Which side of the session we are
+/// * `expected` - Address the remote system must present, when we called it
+/// * `busy` - Busy flags held for this session; flags for the remote's other
+///   addresses are added as their mail is queued
+pub async fn run_session<S>(
+    config: &FtnConfig,
+    stream: S,
+    role: Role,
+    expected: Option<FidoAddress>,
+    busy: &mut BusyFlags,
+) -> Result<SessionStats>
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (mut reader, writer) = tokio::io::split(stream);
+    let (sender, frames) = mpsc::unbounded_channel();
+
+    // Frames are read on their own so that both sides can send at once
+    let reader_task = tokio::spawn(async move {
+        loop {
+            let frame = read_frame(&mut reader).await;
+            let more = matches!(frame, Ok(Some(_)));
+            if sender.send(frame).is_err() || !more {
+                break;
+            }
+        }
+    });
+
+    let mut session = Session {
+        config,
+        writer,
+        frames,
+        stats: SessionStats::default(),
+        remote_challenge: None,
+        queue: VecDeque::new(),
+        sending: None,
+        unacked: Vec::new(),
+        eob_sent: false,
+        inbound: config.inbound.clone(),
+        receiving: None,
+        remote_eob: false,
+    };
+
+    let result = session.run(role, expected, busy).await;
+    reader_task.abort();
+    result.map(|()| session.stats)
+}
+
+impl<S> Session<'_, S>
+where
+    S: AsyncRead + AsyncWrite,
+{
+    /// Authenticate, queue mail and exchange files
+    async fn run(
+        &mut self,
+        role: Role,
+        expected: Option<FidoAddress>,
+        busy: &mut BusyFlags,
+    ) -> Result<()> {
+        let password = match role {
+            Role::Originator => self.originate(expected).await?,
+            Role::Answerer => self.answer().await?,
+        };
+
+        if !self.stats.secure {
+            self.inbound = self.inbound.join(INSECURE_INBOUND);
+        }
+        self.queue_mail(password.as_deref(), expected, busy).await?;
+        self.transfer().await?;
+        let _ = self.writer.shutdown().await;
+        Ok(())
+    }
+
+    /// Handshake as the calling side
+    ///
+    /// # Returns
+    /// The session password used
+    async fn originate(&mut self, expected: Option<FidoAddress>) -> Result<Option<String>> {
+        self.send_info().await?;
+
+        let remote = loop {
+            match self.next_frame().await? {
+                Frame::Command(Command::Nul, args) => self.handle_nul(&args),
+                Frame::Command(Command::Adr, args) => break parse_addresses(&args),
+                Frame::Command(Command::Err | Command::Bsy, args) => {
+                    return Err(FtnError::Remote(args));
+                }
+                _ => {}
+            }
+        };
+        self.stats.remote_addresses = remote.clone();
+
+        if let Some(expected) = expected
+            && !remote.contains(&expected)
+        {
+            let message = format!("{} was not among the addresses presented", expected);
+            self.send(Command::Err, &message).await?;
+            return Err(FtnError::Session(message));
+        }
+
+        let password = expected
+            .and_then(|address| self.config.link(address))
+            .and_then(|link| link.session_password.clone());
+        let response = match (&password, &self.remote_challenge) {
+            (Some(password), Some(challenge)) => {
+                format!("{}{}", CRAM_MD5, cram_md5_digest(password, challenge))
+            }
+            (Some(password), None) => password.clone(),
+            (None, _) => "-".to_string(),
+        };
+        self.send(Command::Pwd, &response).await?;
+
+        loop {
+            match self.next_frame().await? {
+                Frame::Command(Command::Ok, _) => break,
+                Frame::Command(Command::Nul, args) => self.handle_nul(&args),
+                Frame::Command(Command::Err | Command::Bsy, args) => {
+                    return Err(FtnError::Remote(args));
+                }
+                _ => {}
+            }
+        }
+
+        self.stats.secure = password.is_some();
+        Ok(password)
+    }
+
+    /// Handshake as the answering side
+    ///
+    /// # Returns
+    /// The session password checked
+    async fn answer(&mut self) -> Result<Option<String>> {
+        let challenge: [u8; 16] = rand::random();
+        self.send(
+            Command::Nul,
+            &format!("OPT {}{}", CRAM_MD5, to_hex(&challenge)),
+        )
+        .await?;
+        self.send_info().await?;
+
+        let mut remote = Vec::new();
+        let response = loop {
+            match self.next_frame().await? {
+                Frame::Command(Command::Nul, args) => self.handle_nul(&args),
+                Frame::Command(Command::Adr, args) => remote = parse_addresses(&args),
+                Frame::Command(Command::Pwd, args) => break args,
+                Frame::Command(Command::Err | Command::Bsy, args) => {
+                    return Err(FtnError::Remote(args));
+                }
+                _ => {}
+            }
+        };
+
+        if remote.is_empty() {
+            let message = "No addresses presented".to_string();
+            self.send(Command::Err, &message).await?;
+            return Err(FtnError::Session(message));
+        }
+        self.stats.remote_addresses = remote.clone();
+
+        // The first address we have a password for decides the password
+        let protected = remote.iter().find_map(|address| {
+            let link = self.config.link(*address)?;
+            Some((*address, link.session_password.clone()?))
+        });
+
+        match protected {
+            Some((address, password)) => {
+                let accepted = match response.strip_prefix(CRAM_MD5) {
+                    Some(digest) => {
+                        digest.eq_ignore_ascii_case(&cram_md5_digest(&password, &challenge))
+                    }
+                    None => response == password,
+                };
+                if !accepted {
+                    self.send(Command::Err, "Incorrect password").await?;
+                    return Err(FtnError::SessionPassword(address));
+                }
+                self.stats.secure = true;
+                self.send(Command::Ok, "secure").await?;
+                Ok(Some(password))
+            }
+            None => {
+                self.send(Command::Ok, "non-secure").await?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Send our system information and addresses
+    async fn send_info(&mut self) -> Result<()> {
+        let info = [
+            format!("SYS {}", self.config.system_name),
+            format!("ZYZ {}", self.config.sysop),
+            format!("VER {}", version()),
+            format!("TIME {}", chrono::Local::now().to_rfc2822()),
+        ];
+        for line in info {
+            self.send(Command::Nul, &line).await?;
+        }
+
+        let addresses: Vec<String> = self
+            .config
+            .addresses()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        self.send(Command::Adr, &addresses.join(" ")).await
+    }
+
+    /// Note what the remote says about itself
+    fn handle_nul(&mut self, args: &str) {
+        if let Some(system) = args.strip_prefix("SYS ") {
+            self.stats.remote_system = Some(system.trim().to_string());
+        } else if let Some(options) = args.strip_prefix("OPT ") {
+            for option in options.split_whitespace() {
+                if let Some(challenge) = option.strip_prefix(CRAM_MD5) {
+                    self.remote_challenge = from_hex(challenge);
+                }
+            }
+        }
+    }
+
+    /// Queue the mail waiting for the remote's addresses
+    ///
+    /// Mail is sent to addresses that are configured links sharing the
+    /// session's password, or having none in an unprotected session, and
+    /// whose busy flag we can take.
+    async fn queue_mail(
+        &mut self,
+        password: Option<&str>,
+        expected: Option<FidoAddress>,
+        busy: &mut BusyFlags,
+    ) -> Result<()> {
+        let zone = self.config.address().zone;
+        let mut addresses = self.stats.remote_addresses.clone();
+        if let Some(expected) = expected
+            && !addresses.contains(&expected)
+        {
+            addresses.push(expected);
+        }
+
+        for address in addresses {
+            let Some(link) = self.config.link(address) else {
+                continue;
+            };
+            if link.session_password.as_deref() != password {
+                continue;
+            }
+            if !busy.try_lock(&self.config.outbound, zone, address)? {
+                continue;
+            }
+            self.queue
+                .extend(collect(&self.config.outbound, zone, address).await?);
+        }
+        Ok(())
+    }
+
+    /// Exchange files until both sides are done
+    async fn transfer(&mut self) -> Result<()> {
+        loop {
+            // Deal with everything the remote has sent so far
+            while !self.is_done()
+                && let Some(frame) = self.try_next_frame()?
+            {
+                self.handle(frame).await?;
+            }
+            if self.is_done() {
+                return Ok(());
+            }
+
+            if self.sending.is_some() {
+                self.send_block().await?;
+            } else if let Some(file) = self.queue.pop_front() {
+                self.start_file(file, 0).await?;
+            } else if !self.eob_sent {
+                self.send(Command::Eob, "").await?;
+                self.eob_sent = true;
+            } else {
+                let frame = self.next_frame().await?;
+                self.handle(frame).await?;
+            }
+        }
+    }
+
+    /// Whether both sides have sent everything and had it acknowledged
+    fn is_done(&self) -> bool {
+        self.eob_sent
+            && self.remote_eob
+            && self.queue.is_empty()
+            && self.sending.is_none()
+            && self.unacked.is_empty()
+            && self.receiving.is_none()
+    }
+
+    /// Act on a frame received during the file transfer
+    async fn handle(&mut self, frame: Frame) -> Result<()> {
+        let (command, args) = match frame {
+            Frame::Data(data) => return self.receive_data(&data).await,
+            Frame::Unknown(_) => return Ok(()),
+            Frame::Command(command, args) => (command, args),
+        };
+
+        match command {
+            Command::Nul => self.handle_nul(&args),
+            Command::Adr | Command::Pwd | Command::Ok => {}
+            Command::File => self.receive_file(&args).await?,
+            Command::Eob => self.remote_eob = true,
+            Command::Got => self.acknowledged(&args, true).await?,
+            Command::Skip => self.acknowledged(&args, false).await?,
+            Command::Get => self.resend(&args).await?,
+            Command::Err | Command::Bsy => return Err(FtnError::Remote(args)),
+        }
+        Ok(())
+    }
+
+    /// Start sending a file from `offset`
+    async fn start_file(&mut self, file: OutgoingFile, offset: u64) -> Result<()> {
+        if offset > file.size {
+            return Err(FtnError::Session(format!(
+                "asked for {} from offset {} past its end",
+                file.name, offset
+            )));
+        }
+
+        // Files that went away since they were queued are left out
+        let Ok(mut handle) = File::open(&file.path).await else {
+            return Ok(());
+        };
+        handle.seek(SeekFrom::Start(offset)).await?;
+
+        let args = format!(
+            "{} {} {} {}",
+            escape_name(&file.name),
+            file.size,
+            file.time,
+            offset
+        );
+        self.send(Command::File, &args).await?;
+
+        if offset == file.size {
+            self.unacked.push(file);
+        } else {
+            self.sending = Some(Sending {
+                file,
+                handle,
+                offset,
+            });
+        }
+        Ok(())
+    }
+
+    /// Send the next block of the file being sent
+    async fn send_block(&mut self) -> Result<()> {
+        let Some(sending) = self.sending.as_mut() else {
+            return Ok(());
+        };
+
+        let remaining = sending.file.size - sending.offset;
+        let mut block = vec![0u8; remaining.min(BLOCK_SIZE as u64) as usize];
+        sending.handle.read_exact(&mut block).await.map_err(|_| {
+            FtnError::Session(format!("{} changed while it was sent", sending.file.name))
+        })?;
+        write_frame(&mut self.writer, &Frame::Data(block.clone())).await?;
+        sending.offset += block.len() as u64;
+
+        if sending.offset == sending.file.size
+            && let Some(sent) = self.sending.take()
+        {
+            self.unacked.push(sent.file);
+        }
+        Ok(())
+    }
+
+    /// Handle M_GOT or M_SKIP for a file we sent
+    async fn acknowledged(&mut self, args: &str, got: bool) -> Result<()> {
+        let (name, size, time, _) = parse_file_args(args)?;
+        let matches = |file: &OutgoingFile| {
+            escape_name(&file.name) == name && file.size == size && file.time == time
+        };
+
+        // The remote may already have the file being sent, or want it later
+        if self
+            .sending
+            .as_ref()
+            .is_some_and(|sending| matches(&sending.file))
+            && let Some(sending) = self.sending.take()
+        {
+            self.unacked.push(sending.file);
+        }
+
+        if let Some(index) = self.unacked.iter().position(matches) {
+            let file = self.unacked.remove(index);
+            if got {
+                file.mark_sent().await?;
+                self.stats.sent.push(file.name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle M_GET by sending a file again from the offset asked for
+    async fn resend(&mut self, args: &str) -> Result<()> {
+        let (name, size, time, offset) = parse_file_args(args)?;
+        let matches = |file: &OutgoingFile| {
+            escape_name(&file.name) == name && file.size == size && file.time == time
+        };
+
+        let file = if self
+            .sending
+            .as_ref()
+            .is_some_and(|sending| matches(&sending.file))
+        {
+            self.sending.take().map(|sending| sending.file)
+        } else if let Some(index) = self.unacked.iter().position(matches) {
+            Some(self.unacked.remove(index))
+        } else if let Some(index) = self.queue.iter().position(matches) {
+            self.queue.remove(index)
+        } else {
+            None
+        };
+
+        match file {
+            Some(file) => {
+                self.start_file(file, offset.unwrap_or(0).max(0) as u64)
+                    .await
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Handle M_FILE for a file the remote is sending
+    async fn receive_file(&mut self, args: &str) -> Result<()> {
+        let (raw_name, size, time, offset) = parse_file_args(args)?;
+
+        // A new file ends any transfer in progress; what arrived is kept
+        self.receiving = None;
+
+        let Some(name) = safe_name(&unescape_name(&raw_name)) else {
+            return self
+                .send(Command::Skip, &format!("{} {} {}", raw_name, size, time))
+                .await;
+        };
+
+        tokio::fs::create_dir_all(&self.inbound).await?;
+        let part = self.inbound.join(part_file_name(&name, size, time));
+        let have = match tokio::fs::metadata(&part).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        };
+
+        let mut receiving = Receiving {
+            raw_name,
+            name,
+            size,
+            time,
+            part,
+            offset: 0,
+            handle: None,
+        };
+
+        // Ask for the file from what we already have of it
+        let ask = match offset {
+            None | Some(..0) => true,
+            Some(0) => have > 0 && have <= size,
+            Some(offset) => offset as u64 > have,
+        };
+        if ask {
+            receiving.offset = have.min(size);
+            let args = format!(
+                "{} {} {} {}",
+                receiving.raw_name, size, time, receiving.offset
+            );
+            self.receiving = Some(receiving);
+            return self.send(Command::Get, &args).await;
+        }
+
+        let offset = offset.unwrap_or(0) as u64;
+        let mut handle = tokio::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&receiving.part)
+            .await?;
+        handle.set_len(offset).await?;
+        handle.seek(SeekFrom::Start(offset)).await?;
+        receiving.offset = offset;
+        receiving.handle = Some(handle);
+        self.receiving = Some(receiving);
+
+        if offset == size {
+            self.finish_receiving().await?;
+        }
+        Ok(())
+    }
+
+    /// Handle a data frame of the file being received
+    async fn receive_data(&mut self, data: &[u8]) -> Result<()> {
+        // Data for a file being restarted elsewhere is ignored
+        let Some(receiving) = self.receiving.as_mut() else {
+            return Ok(());
+        };
+        let Some(handle) = receiving.handle.as_mut() else {
+            return Ok(());
+        };
+
+        if receiving.offset + data.len() as u64 > receiving.size {
+            return Err(FtnError::Session(format!(
+                "{} is longer than its size",
+                receiving.name
+            )));
+        }
+        handle.write_all(data).await?;
+        receiving.offset += data.len() as u64;
+
+        if receiving.offset == receiving.size {
+            self.finish_receiving().await?;
+        }
+        Ok(())
+    }
+
+    /// Move a complete file into the inbound and acknowledge it
+    async fn finish_receiving(&mut self) -> Result<()> {
+        let Some(receiving) = self.receiving.take() else {
+            return Ok(());
+        };
+        if let Some(mut handle) = receiving.handle {
+            handle.flush().await?;
+            handle.sync_all().await?;
+        }
+
+        let target = unique_path(&self.inbound, &receiving.name);
+        tokio::fs::rename(&receiving.part, &target).await?;
+        self.stats.received.push(target);
+
+        let args = format!(
+            "{} {} {}",
+            receiving.raw_name, receiving.size, receiving.time
+        );
+        self.send(Command::Got, &args).await
+    }
+
+    /// Send a command frame
+    async fn send(&mut self, command: Command, args: &str) -> Result<()> {
+        write_frame(&mut self.writer, &Frame::command(command, args)).await
+    }
+
+    /// Wait for the next frame
+    async fn next_frame(&mut self) -> Result<Frame> {
+        match tokio::time::timeout(SESSION_TIMEOUT, self.frames.recv()).await {
+            Err(_) => Err(FtnError::Timeout),
+            Ok(Some(Ok(Some(frame)))) => Ok(frame),
+            Ok(Some(Err(e))) => Err(e),
+            Ok(Some(Ok(None)) | None) => Err(connection_closed()),
+        }
+    }
+
+    /// Take the next frame if one has arrived
+    fn try_next_frame(&mut self) -> Result<Option<Frame>> {
+        match self.frames.try_recv() {
+            Ok(Ok(Some(frame))) => Ok(Some(frame)),
+            Ok(Err(e)) => Err(e),
+            Err(mpsc::error::TryRecvError::Empty) => Ok(None),
+            Ok(Ok(None)) | Err(mpsc::error::TryRecvError::Disconnected) => Err(connection_closed()),
+        }
+    }
+}
+
+/// Mailer version given to peers
+fn version() -> String {
+    format!("Impulse/{} binkp/1.0", env!("CARGO_PKG_VERSION"))
+}
+
+/// Error for a connection that closed mid-session
+fn connection_closed() -> FtnError {
+    FtnError::Session("connection closed by the remote system".to_string())
+}
+
+/// Parse the addresses in an M_ADR command, dropping any "@domain"
+pub fn parse_addresses(args: &str) -> Vec<FidoAddress> {
+    args.split_whitespace()
+        .filter_map(|address| address.split('@').next()?.parse().ok())
+        .collect()
+}
+
+/// Parse "name size time [offset]" from a file command
+fn parse_file_args(args: &str) -> Result<(String, u64, i64, Option<i64>)> {
+    let invalid = || FtnError::Session(format!("invalid file command \"{}\"", args));
+    let mut parts = args.split_whitespace();
+    let name = parts.next().ok_or_else(invalid)?.to_string();
+    let size = parts
+        .next()
+        .and_then(|size| size.parse().ok())
+        .ok_or_else(invalid)?;
+    let time = parts
+        .next()
+        .and_then(|time| time.parse().ok())
+        .ok_or_else(invalid)?;
+    let offset = match parts.next() {
+        Some(offset) => Some(offset.parse().map_err(|_| invalid())?),
+        None => None,
+    };
+    Ok((name, size, time, offset))
+}
+
+/// CRAM-MD5 response to a challenge: HMAC-MD5 of the challenge keyed with the
+/// password, in lowercase hex
+pub fn cram_md5_digest(password: &str, challenge: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Md5>::new_from_slice(password.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(challenge);
+    to_hex(&mac.finalize().into_bytes())
+}
+
+/// Name a partial file is kept under until it is complete
+pub(super) fn part_file_name(name: &str, size: u64, time: i64) -> String {
+    format!("{}.{:x}-{:x}.part", name, size, time)
+}
+
+/// Keep only the last part of a received file name, refusing names that
+/// could escape the inbound
+fn safe_name(name: &str) -> Option<String> {
+    let name = name.rsplit(['/', '\\']).next()?.trim();
+    if name.is_empty() || name == "." || name == ".." || name.chars().any(char::is_control) {
+        return None;
+    }
+    Some(name.to_string())
+}
+
+/// A path in `dir` for `name` that does not exist yet
+fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    if !path.exists() {
+        return path;
+    }
+
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+        _ => (name, None),
+    };
+    (1..)
+        .map(|n| match extension {
+            Some(extension) => dir.join(format!("{}_{}.{}", stem, n, extension)),
+            None => dir.join(format!("{}_{}", name, n)),
+        })
+        .find(|path| !path.exists())
+        .expect("some numbered name is free")
+}
+
+/// Lowercase hex of some bytes
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Bytes of a hex string
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cram_md5_digest() {
+        // RFC 2195 example
+        let challenge = b"<1896.697170952@postoffice.reston.mci.net>";
+        assert_eq!(
+            cram_md5_digest("tanstaaftanstaaf", challenge),
+            "b913a602c7eda7a495b4e6e7334d3890"
+        );
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let bytes = [0x00, 0x7f, 0xff, 0x10];
+        assert_eq!(to_hex(&bytes), "007fff10");
+        assert_eq!(from_hex("007fff10").unwrap(), bytes);
+        assert!(from_hex("abc").is_none());
+        assert!(from_hex("zz").is_none());
+    }
+
+    #[test]
+    fn test_parse_addresses() {
+        assert_eq!(
+            parse_addresses("2:5020/1@fidonet 2:5020/1.3 bogus"),
+            vec![
+                FidoAddress::node(2, 5020, 1),
+                FidoAddress::point(2, 5020, 1, 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_file_args() {
+        assert_eq!(
+            parse_file_args("a.pkt 100 1700000000 -1").unwrap(),
+            ("a.pkt".to_string(), 100, 1_700_000_000, Some(-1))
+        );
+        assert_eq!(
+            parse_file_args("a.pkt 100 0").unwrap(),
+            ("a.pkt".to_string(), 100, 0, None)
+        );
+        assert!(parse_file_args("a.pkt many 0").is_err());
+    }
+
+    #[test]
+    fn test_safe_name() {
+        assert_eq!(safe_name("../../etc/passwd").as_deref(), Some("passwd"));
+        assert_eq!(safe_name("C:\\MAIL\\A.PKT").as_deref(), Some("A.PKT"));
+        assert_eq!(safe_name(".."), None);
+        assert_eq!(safe_name("dir/"), None);
+    }
+
+    #[test]
+    fn test_unique_path() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        assert_eq!(
+            unique_path(temp_dir.path(), "a.pkt"),
+            temp_dir.path().join("a.pkt")
+        );
+        std::fs::write(temp_dir.path().join("a.pkt"), b"").unwrap();
+        assert_eq!(
+            unique_path(temp_dir.path(), "a.pkt"),
+            temp_dir.path().join("a_1.pkt")
+        );
+    }
+}
@@ -0,0 +1,255 @@
+//! Tosser, scanner and mailer configuration
+
+use crate::addressing::FidoAddress;
+use crate::routing::RouterConfig;
+use std::path::PathBuf;
+
+/// Default origin line text
+pub const DEFAULT_ORIGIN: &str = "Impulse 7.1 BBS";
+
+/// Tearline written on exported messages
+pub const TEARLINE: &str = "--- Impulse 7.1";
+
+/// An echomail area and the links it is exchanged with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EchoArea {
+    /// Area tag, as in the `AREA:` line
+    pub tag: String,
+    /// JAM base path without extension
+    pub base_path: PathBuf,
+    /// Links the area is fed to and from
+    pub links: Vec<FidoAddress>,
+}
+
+impl EchoArea {
+    /// Create an area with no links
+    pub fn new(tag: impl Into<String>, base_path: impl Into<PathBuf>) -> Self {
+        Self {
+            tag: tag.into().to_uppercase(),
+            base_path: base_path.into(),
+            links: Vec::new(),
+        }
+    }
+
+    /// Add a link to the area
+    pub fn with_link(mut self, link: FidoAddress) -> Self {
+        self.links.push(link);
+        self
+    }
+}
+
+/// A system mail is exchanged with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FtnLink {
+    /// Link address
+    pub address: FidoAddress,
+    /// Packet password (none to accept any)
+    pub password: Option<String>,
+    /// BinkP session password (none for unprotected sessions)
+    pub session_password: Option<String>,
+    /// Host name, or "host:port", to call the link at
+    pub host: Option<String>,
+}
+
+impl FtnLink {
+    /// Create a link with no passwords
+    pub fn new(address: FidoAddress) -> Self {
+        Self {
+            address,
+            password: None,
+            session_password: None,
+            host: None,
+        }
+    }
+
+    /// Set the packet password
+    pub fn with_password(mut self, password: impl Into<String>) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Set the BinkP session password
+    pub fn with_session_password(mut self, password: impl Into<String>) -> Self {
+        self.session_password = Some(password.into());
+        self
+    }
+
+    /// Set the host to call the link at
+    pub fn with_host(mut self, host: impl Into<String>) -> Self {
+        self.host = Some(host.into());
+        self
+    }
+}
+
+/// Tosser, scanner and mailer configuration
+#[derive(Debug, Clone)]
+pub struct FtnConfig {
+    /// Routing configuration, including our own address
+    pub router: RouterConfig,
+    /// Further addresses we answer to, besides the main one
+    pub akas: Vec<FidoAddress>,
+    /// System name given to mailer peers
+    pub system_name: String,
+    /// Sysop name given to mailer peers
+    pub sysop: String,
+    /// Directory inbound packets and bundles arrive in
+    pub inbound: PathBuf,
+    /// BinkleyTerm-style outbound directory for our zone
+    pub outbound: PathBuf,
+    /// Echomail areas
+    pub areas: Vec<EchoArea>,
+    /// Links and their packet passwords
+    pub links: Vec<FtnLink>,
+    /// Origin line text
+    pub origin: String,
+    /// File the MSGID history is kept in (none for memory only)
+    pub dupe_file: Option<PathBuf>,
+    /// JAM base NetMail is written to and tossed into (none for no NetMail)
+    pub netmail: Option<PathBuf>,
+    /// JAM base for messages in unknown areas, and NetMail with nowhere else
+    /// to go (none to drop them)
+    pub bad_area: Option<PathBuf>,
+}
+
+impl FtnConfig {
+    /// Create a configuration with no areas or links
+    pub fn new(
+        router: RouterConfig,
+        inbound: impl Into<PathBuf>,
+        outbound: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            router,
+            akas: Vec::new(),
+            system_name: DEFAULT_ORIGIN.to_string(),
+            sysop: String::new(),
+            inbound: inbound.into(),
+            outbound: outbound.into(),
+            areas: Vec::new(),
+            links: Vec::new(),
+            origin: DEFAULT_ORIGIN.to_string(),
+            dupe_file: None,
+            netmail: None,
+            bad_area: None,
+        }
+    }
+
+    /// Add a further address
+    pub fn with_aka(mut self, address: FidoAddress) -> Self {
+        self.akas.push(address);
+        self
+    }
+
+    /// Set the system and sysop names given to mailer peers
+    pub fn with_system(mut self, system_name: impl Into<String>, sysop: impl Into<String>) -> Self {
+        self.system_name = system_name.into();
+        self.sysop = sysop.into();
+        self
+    }
+
+    /// Add an echomail area
+    pub fn with_area(mut self, area: EchoArea) -> Self {
+        self.areas.push(area);
+        self
+    }
+
+    /// Add a link
+    pub fn with_link(mut self, link: FtnLink) -> Self {
+        self.links.push(link);
+        self
+    }
+
+    /// Set the origin line text
+    pub fn with_origin(mut self, origin: impl Into<String>) -> Self {
+        self.origin = origin.into();
+        self
+    }
+
+    /// Keep the MSGID history in a file
+    pub fn with_dupe_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.dupe_file = Some(path.into());
+        self
+    }
+
+    /// Keep NetMail in a JAM base
+    pub fn with_netmail(mut self, base_path: impl Into<PathBuf>) -> Self {
+        self.netmail = Some(base_path.into());
+        self
+    }
+
+    /// Keep messages in unknown areas, and NetMail that is not ours, in a JAM base
+    pub fn with_bad_area(mut self, base_path: impl Into<PathBuf>) -> Self {
+        self.bad_area = Some(base_path.into());
+        self
+    }
+
+    /// Our own address
+    pub fn address(&self) -> FidoAddress {
+        self.router.local_address
+    }
+
+    /// All our addresses, main address first
+    pub fn addresses(&self) -> Vec<FidoAddress> {
+        let mut addresses = vec![self.address()];
+        for aka in &self.akas {
+            if !addresses.contains(aka) {
+                addresses.push(*aka);
+            }
+        }
+        addresses
+    }
+
+    /// Address to send mail to `destination` from: an AKA in the same zone
+    /// if there is one, otherwise the main address
+    pub fn address_for(&self, destination: FidoAddress) -> FidoAddress {
+        self.addresses()
+            .into_iter()
+            .find(|address| address.zone == destination.zone)
+            .unwrap_or_else(|| self.address())
+    }
+
+    /// Find an area by tag (case-insensitive)
+    pub fn area(&self, tag: &str) -> Option<&EchoArea> {
+        self.areas
+            .iter()
+            .find(|area| area.tag.eq_ignore_ascii_case(tag))
+    }
+
+    /// Find a link by address
+    pub fn link(&self, address: FidoAddress) -> Option<&FtnLink> {
+        self.links.iter().find(|link| link.address == address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_lookups() {
+        let local = FidoAddress::node(1, 234, 5);
+        let hub = FidoAddress::node(1, 234, 1);
+        let config = FtnConfig::new(RouterConfig::new(local), "/in", "/out")
+            .with_area(EchoArea::new("test", "/msg/test").with_link(hub))
+            .with_link(FtnLink::new(hub).with_password("PW"));
+
+        assert_eq!(config.address(), local);
+        assert_eq!(config.area("TEST").unwrap().links, vec![hub]);
+        assert!(config.area("OTHER").is_none());
+        assert_eq!(config.link(hub).unwrap().password.as_deref(), Some("PW"));
+        assert_eq!(config.origin, DEFAULT_ORIGIN);
+        assert_eq!(config.addresses(), vec![local]);
+    }
+
+    #[test]
+    fn test_akas() {
+        let local = FidoAddress::node(1, 234, 5);
+        let aka = FidoAddress::node(21, 1, 100);
+        let config = FtnConfig::new(RouterConfig::new(local), "/in", "/out")
+            .with_aka(aka)
+            .with_aka(local);
+        assert_eq!(config.addresses(), vec![local, aka]);
+        assert_eq!(config.address_for(FidoAddress::node(21, 3, 4)), aka);
+        assert_eq!(config.address_for(FidoAddress::node(2, 3, 4)), local);
+    }
+}
@@ -0,0 +1,167 @@
+//! Duplicate message detection by MSGID
+//!
+//! Keeps the most recent "AREA MSGID" keys seen, in memory and optionally in
+//! a text file with one key per line, so that echomail arriving twice over
+//! different links is only tossed once.
+
+use super::error::Result;
+use std::collections::{HashSet, VecDeque};
+use std::path::PathBuf;
+
+/// Default number of keys remembered
+pub const DEFAULT_DUPE_HISTORY: usize = 10_000;
+
+/// History of tossed MSGIDs
+#[derive(Debug)]
+pub struct DupeDatabase {
+    /// File the history is kept in (none for memory only)
+    path: Option<PathBuf>,
+    /// Maximum number of keys remembered
+    capacity: usize,
+    /// Keys in the order they were seen
+    order: VecDeque<String>,
+    /// Keys for fast lookup
+    seen: HashSet<String>,
+    /// Whether keys were added since the last save
+    dirty: bool,
+}
+
+impl DupeDatabase {
+    /// Create an empty history kept only in memory
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            path: None,
+            capacity: capacity.max(1),
+            order: VecDeque::new(),
+            seen: HashSet::new(),
+            dirty: false,
+        }
+    }
+
+    /// Load the history from a file, starting empty if it does not exist
+    pub async fn load(path: impl Into<PathBuf>, capacity: usize) -> Result<Self> {
+        let path = path.into();
+        let mut database = Self::new(capacity);
+
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) => {
+                for key in content.lines().filter(|line| !line.is_empty()) {
+                    database.remember(key.to_string());
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        database.path = Some(path);
+        database.dirty = false;
+        Ok(database)
+    }
+
+    /// Build the key for a message in an area
+    pub fn key(area: &str, msgid: &str) -> String {
+        format!("{} {}", area.to_uppercase(), msgid.trim())
+    }
+
+    /// Check whether a key was seen before, remembering it if not
+    ///
+    /// # Returns
+    /// `true` if the message is a duplicate
+    pub fn check(&mut self, key: &str) -> bool {
+        if self.contains(key) {
+            return true;
+        }
+        self.remember(key.to_string());
+        false
+    }
+
+    /// Whether a key was seen before
+    pub fn contains(&self, key: &str) -> bool {
+        self.seen.contains(key)
+    }
+
+    /// Number of keys remembered
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Whether no keys are remembered
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Write the history back to its file, if it has one and changed
+    pub async fn save(&mut self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let mut content = String::new();
+        for key in &self.order {
+            content.push_str(key);
+            content.push('\n');
+        }
+        crate::atomic::AtomicWriter::new(path)
+            .write(content.as_bytes())
+            .await?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Remember a key, forgetting the oldest when full
+    pub fn remember(&mut self, key: String) {
+        if !self.seen.insert(key.clone()) {
+            return;
+        }
+        self.order.push_back(key);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        self.dirty = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_check_marks_duplicates() {
+        let mut dupes = DupeDatabase::new(10);
+        let key = DupeDatabase::key("test", "1:2/3 abcd");
+        assert_eq!(key, "TEST 1:2/3 abcd");
+        assert!(!dupes.check(&key));
+        assert!(dupes.check(&key));
+        assert!(!dupes.check("OTHER 1:2/3 abcd"));
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut dupes = DupeDatabase::new(2);
+        dupes.check("A");
+        dupes.check("B");
+        dupes.check("C");
+        assert_eq!(dupes.len(), 2);
+        assert!(!dupes.check("A"));
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("dupes.dat");
+
+        let mut dupes = DupeDatabase::load(&path, 100).await.unwrap();
+        assert!(dupes.is_empty());
+        dupes.check("TEST 1:2/3 1");
+        dupes.save().await.unwrap();
+
+        let mut reloaded = DupeDatabase::load(&path, 100).await.unwrap();
+        assert!(reloaded.check("TEST 1:2/3 1"));
+    }
+}
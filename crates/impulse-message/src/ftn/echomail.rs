@@ -0,0 +1,305 @@
+//! Echomail message text handling
+//!
+//! Packed message text carries its control information inline: an `AREA:`
+//! line first, `^A` kludge lines, then the body with its tearline and origin,
+//! then `SEEN-BY:` lines and `^APATH:` lines. This module splits that text
+//! into parts for storing in a message base and rebuilds it for export.
+
+use crate::addressing::FidoAddress;
+use crate::types::KludgeLine;
+
+/// Kludges written as "NAME value" rather than "NAME: value"
+const SPACE_SEPARATED_KLUDGES: [&str; 3] = ["INTL", "FMPT", "TOPT"];
+
+/// Longest SEEN-BY or PATH line, including its prefix
+const MAX_NET_NODE_LINE: usize = 79;
+
+/// An echomail message's text split into its parts
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EchoText {
+    /// Area tag from the `AREA:` line (none for netmail)
+    pub area: Option<String>,
+    /// MSGID kludge value
+    pub msgid: Option<String>,
+    /// REPLY kludge value
+    pub reply_id: Option<String>,
+    /// Other kludge lines
+    pub kludges: Vec<KludgeLine>,
+    /// Message body, lines separated by newlines
+    pub body: String,
+    /// SEEN-BY lines, without the prefix
+    pub seen_by: Vec<String>,
+    /// PATH lines, without the prefix
+    pub path: Vec<String>,
+}
+
+impl EchoText {
+    /// Split packed message text into its parts
+    pub fn parse(text: &str) -> Self {
+        let mut echo = Self::default();
+        let mut body_lines = Vec::new();
+
+        for (index, line) in text.split('\r').enumerate() {
+            let line = line.trim_start_matches('\n').trim_end_matches('\n');
+
+            if index == 0
+                && let Some(tag) = line.strip_prefix("AREA:")
+            {
+                echo.area = Some(tag.trim().to_uppercase());
+            } else if let Some(kludge) = line.strip_prefix('\x01') {
+                let kludge = split_kludge(kludge);
+                match kludge.kludge_type.as_str() {
+                    "MSGID" => echo.msgid = Some(kludge.value),
+                    "REPLY" => echo.reply_id = Some(kludge.value),
+                    "PATH" => echo.path.push(kludge.value),
+                    _ => echo.kludges.push(kludge),
+                }
+            } else if let Some(seen_by) = line.strip_prefix("SEEN-BY:") {
+                echo.seen_by.push(seen_by.trim().to_string());
+            } else {
+                body_lines.push(line);
+            }
+        }
+
+        while body_lines.last().is_some_and(|line| line.is_empty()) {
+            body_lines.pop();
+        }
+        echo.body = body_lines.join("\n");
+        echo
+    }
+
+    /// Rebuild packed message text
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        if let Some(area) = &self.area {
+            text.push_str(&format!("AREA:{}\r", area));
+        }
+        if let Some(msgid) = &self.msgid {
+            text.push_str(&format!("\x01MSGID: {}\r", msgid));
+        }
+        if let Some(reply_id) = &self.reply_id {
+            text.push_str(&format!("\x01REPLY: {}\r", reply_id));
+        }
+        for kludge in &self.kludges {
+            text.push_str(&format_kludge(kludge));
+            text.push('\r');
+        }
+        for line in self.body.lines() {
+            text.push_str(line);
+            text.push('\r');
+        }
+        for line in &self.seen_by {
+            text.push_str(&format!("SEEN-BY: {}\r", line));
+        }
+        for line in &self.path {
+            text.push_str(&format!("\x01PATH: {}\r", line));
+        }
+        text
+    }
+
+    /// Address from the origin line, if the body has one
+    pub fn origin_address(&self) -> Option<FidoAddress> {
+        let line = self
+            .body
+            .lines()
+            .rev()
+            .find(|line| line.starts_with(" * Origin:"))?;
+        let start = line.rfind('(')?;
+        let end = line[start..].find(')')? + start;
+        parse_address(&line[start + 1..end])
+    }
+
+    /// Address from the MSGID kludge, if it starts with one
+    pub fn msgid_address(&self) -> Option<FidoAddress> {
+        self.msgid
+            .as_deref()
+            .and_then(|msgid| msgid.split_whitespace().next())
+            .and_then(parse_address)
+    }
+
+    /// Whether the body already ends with an origin line
+    pub fn has_origin(&self) -> bool {
+        self.body
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .is_some_and(|line| line.starts_with(" * Origin:"))
+    }
+
+    /// Add nodes to the SEEN-BY lines, keeping them sorted and unique
+    pub fn add_seen_by(&mut self, nodes: impl IntoIterator<Item = FidoAddress>) {
+        let mut seen = parse_net_nodes(&self.seen_by);
+        seen.extend(
+            nodes
+                .into_iter()
+                .filter(|address| !address.is_point())
+                .map(|address| (address.net, address.node)),
+        );
+        seen.sort_unstable();
+        seen.dedup();
+        self.seen_by = format_net_nodes(&seen, "SEEN-BY: ".len());
+    }
+
+    /// Add a node to the end of the PATH lines
+    pub fn add_path(&mut self, address: FidoAddress) {
+        let mut path = parse_net_nodes(&self.path);
+        let node = (address.net, address.node);
+        if path.last() != Some(&node) {
+            path.push(node);
+        }
+        self.path = format_net_nodes(&path, "\x01PATH: ".len());
+    }
+}
+
+/// Parse an address that may carry a "@domain" suffix
+fn parse_address(value: &str) -> Option<FidoAddress> {
+    value.split('@').next()?.trim().parse().ok()
+}
+
+/// Split a kludge line (without its leading ^A) into name and value
+fn split_kludge(line: &str) -> KludgeLine {
+    let separator = line.find([':', ' ']).unwrap_or(line.len());
+    let kludge_type = line[..separator].trim().to_string();
+    let value = line[separator..].trim_start_matches(':').trim().to_string();
+    KludgeLine { kludge_type, value }
+}
+
+/// Format a kludge line, including its leading ^A
+pub fn format_kludge(kludge: &KludgeLine) -> String {
+    if SPACE_SEPARATED_KLUDGES.contains(&kludge.kludge_type.as_str()) {
+        format!("\x01{} {}", kludge.kludge_type, kludge.value)
+    } else {
+        format!("\x01{}: {}", kludge.kludge_type, kludge.value)
+    }
+}
+
+/// Parse 2D "net/node node ..." lines into (net, node) pairs
+///
+/// A bare node number belongs to the net named before it. Points are
+/// ignored.
+pub fn parse_net_nodes(lines: &[String]) -> Vec<(u16, u16)> {
+    let mut nodes = Vec::new();
+    let mut net = 0;
+
+    for token in lines.iter().flat_map(|line| line.split_whitespace()) {
+        let node = match token.split_once('/') {
+            Some((net_part, node_part)) => match net_part.parse() {
+                Ok(value) => {
+                    net = value;
+                    node_part
+                }
+                Err(_) => continue,
+            },
+            None => token,
+        };
+        if node.contains('.') {
+            continue;
+        }
+        if let Ok(node) = node.parse() {
+            nodes.push((net, node));
+        }
+    }
+
+    nodes
+}
+
+/// Format (net, node) pairs as 2D lines, each starting with its full net
+///
+/// `prefix_len` is the length of the "SEEN-BY: " or "^APATH: " prefix the
+/// lines will be written with.
+pub fn format_net_nodes(nodes: &[(u16, u16)], prefix_len: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut net = None;
+
+    for &(node_net, node) in nodes {
+        let full = format!("{}/{}", node_net, node);
+        let mut token = if net == Some(node_net) {
+            node.to_string()
+        } else {
+            full.clone()
+        };
+
+        if !line.is_empty() && prefix_len + line.len() + 1 + token.len() > MAX_NET_NODE_LINE {
+            lines.push(std::mem::take(&mut line));
+            token = full;
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&token);
+        net = Some(node_net);
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "AREA:fido.test\r\x01MSGID: 2:5020/1 12345678\r\x01TZUTC: 0300\r\
+                          \x01INTL 2:5020/9 2:5020/1\rHello there\r\r--- \r \
+                          * Origin: Somewhere (2:5020/1@fidonet)\r\
+                          SEEN-BY: 5020/1 9 5030/7\r\x01PATH: 5020/1\r";
+
+    #[test]
+    fn test_parse_echo_text() {
+        let echo = EchoText::parse(SAMPLE);
+        assert_eq!(echo.area.as_deref(), Some("FIDO.TEST"));
+        assert_eq!(echo.msgid.as_deref(), Some("2:5020/1 12345678"));
+        assert_eq!(echo.kludges.len(), 2);
+        assert_eq!(echo.kludges[1].kludge_type, "INTL");
+        assert_eq!(echo.kludges[1].value, "2:5020/9 2:5020/1");
+        assert!(echo.body.starts_with("Hello there\n\n--- "));
+        assert_eq!(echo.seen_by, vec!["5020/1 9 5030/7"]);
+        assert_eq!(echo.path, vec!["5020/1"]);
+        assert_eq!(echo.origin_address(), Some(FidoAddress::node(2, 5020, 1)));
+        assert_eq!(echo.msgid_address(), Some(FidoAddress::node(2, 5020, 1)));
+        assert!(echo.has_origin());
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let echo = EchoText::parse(SAMPLE);
+        assert_eq!(EchoText::parse(&echo.to_text()), echo);
+        assert!(echo.to_text().contains("\x01INTL 2:5020/9 2:5020/1\r"));
+    }
+
+    #[test]
+    fn test_netmail_has_no_area() {
+        let echo = EchoText::parse("\x01INTL 1:2/3 1:2/4\rHi\r");
+        assert_eq!(echo.area, None);
+        assert_eq!(echo.body, "Hi");
+    }
+
+    #[test]
+    fn test_add_seen_by_and_path() {
+        let mut echo = EchoText::parse(SAMPLE);
+        echo.add_seen_by([
+            FidoAddress::node(2, 5020, 2),
+            FidoAddress::node(2, 5020, 9),
+            FidoAddress::point(2, 5020, 2, 1),
+        ]);
+        assert_eq!(echo.seen_by, vec!["5020/1 2 9 5030/7"]);
+
+        echo.add_path(FidoAddress::node(2, 5020, 2));
+        echo.add_path(FidoAddress::node(2, 5020, 2));
+        assert_eq!(echo.path, vec!["5020/1 2"]);
+    }
+
+    #[test]
+    fn test_long_seen_by_wraps() {
+        let nodes: Vec<(u16, u16)> = (1..=40).map(|node| (5020, node * 100)).collect();
+        let lines = format_net_nodes(&nodes, "SEEN-BY: ".len());
+        assert!(lines.len() > 1);
+        for line in &lines {
+            assert!(line.starts_with("5020/"));
+            assert!("SEEN-BY: ".len() + line.len() <= MAX_NET_NODE_LINE);
+        }
+        assert_eq!(parse_net_nodes(&lines), nodes);
+    }
+}
@@ -0,0 +1,76 @@
+//! FidoNet-specific error types
+
+use crate::addressing::FidoAddress;
+use crate::error::MessageError;
+use std::io;
+use thiserror::Error;
+
+/// Errors specific to FidoNet packet tossing and scanning
+#[derive(Debug, Error)]
+pub enum FtnError {
+    /// I/O error during FidoNet operations
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    /// ZIP decompression error in an ARCmail bundle
+    #[error("ZIP error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    /// Error from the message base being tossed into or scanned
+    #[error("Message base error: {0}")]
+    Message(#[from] MessageError),
+
+    /// Invalid or truncated packet
+    #[error("Invalid packet: {0}")]
+    InvalidPacket(String),
+
+    /// Packet password does not match the one configured for the link
+    #[error("Packet password mismatch from {0}")]
+    BadPassword(FidoAddress),
+
+    /// BinkP session password does not match the one configured for the link
+    #[error("Session password mismatch from {0}")]
+    SessionPassword(FidoAddress),
+
+    /// BinkP protocol failure
+    #[error("BinkP session failed: {0}")]
+    Session(String),
+
+    /// The remote system ended the session with an error or busy message
+    #[error("Remote system reported: {0}")]
+    Remote(String),
+
+    /// The remote system stopped responding
+    #[error("BinkP session timed out")]
+    Timeout,
+}
+
+/// FidoNet-specific result type
+pub type Result<T> = std::result::Result<T, FtnError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_display() {
+        let err = FtnError::InvalidPacket("header is truncated".to_string());
+        assert_eq!(err.to_string(), "Invalid packet: header is truncated");
+
+        let err = FtnError::BadPassword(FidoAddress::node(1, 234, 5));
+        assert_eq!(err.to_string(), "Packet password mismatch from 1:234/5");
+
+        let err = FtnError::Remote("Incorrect password".to_string());
+        assert_eq!(
+            err.to_string(),
+            "Remote system reported: Incorrect password"
+        );
+    }
+
+    #[test]
+    fn test_error_from_io() {
+        let io_err = io::Error::new(io::ErrorKind::NotFound, "file not found");
+        let ftn_err: FtnError = io_err.into();
+        assert!(matches!(ftn_err, FtnError::Io(_)));
+    }
+}
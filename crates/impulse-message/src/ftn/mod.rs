@@ -0,0 +1,66 @@
+//! FidoNet packet tossing and scanning
+//!
+//! This module moves echomail between JAM message bases and FTS-0001 /
+//! FSC-0039 Type-2+ packets, doing the job of the original `IMP-MAIL.EXE`.
+//!
+//! # Overview
+//!
+//! - The [`Tosser`] unpacks `.pkt` files and ARCmail bundles from the inbound
+//!   directory into the JAM base of each message's `AREA:` tag, skipping
+//!   duplicates by MSGID and forwarding echomail to the area's other links.
+//! - The [`Scanner`] exports local messages that have not been sent into
+//!   packets in a BinkleyTerm-style outbound, with SEEN-BY and PATH lines,
+//!   addressed to the next hop [`MessageRouter::route`] picks for each link.
+//!   Unsent [`NetMessage`]s in the NetMail base go the same way, with crash,
+//!   hold and direct mail sent straight to its destination.
+//! - The [`Mailer`] exchanges the outbound and inbound with links over BinkP,
+//!   calling them or answering their calls.
+//!
+//! [`MessageRouter::route`]: crate::routing::MessageRouter::route
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use impulse_message::addressing::FidoAddress;
+//! use impulse_message::ftn::{EchoArea, FtnConfig, FtnLink, Scanner, Tosser};
+//! use impulse_message::routing::RouterConfig;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let local = FidoAddress::node(1, 234, 5);
+//! let uplink = FidoAddress::node(1, 234, 1);
+//!
+//! let config = FtnConfig::new(RouterConfig::new(local), "/ftn/inbound", "/ftn/outbound")
+//!     .with_area(EchoArea::new("FIDO.TEST", "/msg/fidotest").with_link(uplink))
+//!     .with_link(FtnLink::new(uplink).with_password("SECRET"))
+//!     .with_dupe_file("/ftn/dupes.dat");
+//!
+//! let stats = Tosser::new(config.clone()).await?.toss().await?;
+//! println!("Tossed {} messages", stats.messages);
+//!
+//! let stats = Scanner::new(config).scan().await?;
+//! println!("Exported {} messages", stats.messages);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod binkp;
+pub mod config;
+pub mod dupes;
+pub mod echomail;
+pub mod error;
+pub mod netmail;
+pub mod outbound;
+pub mod packet;
+pub mod scanner;
+pub mod tosser;
+
+pub use binkp::{Mailer, SessionStats};
+pub use config::{EchoArea, FtnConfig, FtnLink};
+pub use dupes::DupeDatabase;
+pub use echomail::EchoText;
+pub use error::{FtnError, Result};
+pub use netmail::NetMessage;
+pub use outbound::{Flavour, Outbound};
+pub use packet::{PackedMessage, Packet, PacketHeader};
+pub use scanner::{ScanStats, Scanner};
+pub use tosser::{TossStats, Tosser};
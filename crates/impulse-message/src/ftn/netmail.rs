@@ -0,0 +1,293 @@
+//! NetMail
+//!
+//! NetMail is private mail between two FidoNet addresses. It is kept in its
+//! own JAM base, carries INTL, FMPT and TOPT kludges so that the full 4D
+//! addresses survive being packed with only net and node numbers, and is
+//! exported by the [`Scanner`](super::Scanner) through the next hop the
+//! router picks. Crash, hold and direct mail skips routing and goes straight
+//! to the destination with that flavour.
+
+use super::outbound::Flavour;
+use crate::addressing::FidoAddress;
+use crate::formats::jam::{JamMessage, MessageAttributes};
+use crate::types::KludgeLine;
+use std::path::PathBuf;
+
+/// FTS-0001 packed message attribute bits
+pub mod packed_attributes {
+    /// Private message
+    pub const PRIVATE: u16 = 0x0001;
+    /// Crash mail
+    pub const CRASH: u16 = 0x0002;
+    /// Files named in the subject are attached
+    pub const FILE_ATTACH: u16 = 0x0010;
+    /// Held for pickup
+    pub const HOLD: u16 = 0x0200;
+}
+
+/// A NetMail message being written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetMessage {
+    /// Sender name
+    pub from: String,
+    /// Sender address
+    pub from_address: FidoAddress,
+    /// Recipient name
+    pub to: String,
+    /// Recipient address
+    pub to_address: FidoAddress,
+    /// Subject line
+    pub subject: String,
+    /// Message body
+    pub body: String,
+    /// How urgently the message is delivered
+    pub flavour: Flavour,
+    /// Files sent along with the message
+    pub attachments: Vec<PathBuf>,
+}
+
+impl NetMessage {
+    /// Create a normal-flavour message with no body
+    pub fn new(
+        from: impl Into<String>,
+        from_address: FidoAddress,
+        to: impl Into<String>,
+        to_address: FidoAddress,
+        subject: impl Into<String>,
+    ) -> Self {
+        Self {
+            from: from.into(),
+            from_address,
+            to: to.into(),
+            to_address,
+            subject: subject.into(),
+            body: String::new(),
+            flavour: Flavour::Normal,
+            attachments: Vec::new(),
+        }
+    }
+
+    /// Set the body
+    pub fn with_body(mut self, body: impl Into<String>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Set the flavour
+    pub fn with_flavour(mut self, flavour: Flavour) -> Self {
+        self.flavour = flavour;
+        self
+    }
+
+    /// Attach a file
+    pub fn with_attachment(mut self, path: impl Into<PathBuf>) -> Self {
+        self.attachments.push(path.into());
+        self
+    }
+
+    /// Build the JAM message to store in the NetMail base
+    ///
+    /// Attached files are kept in their own subfields; the subject is only
+    /// replaced by their names when the message is packed for export.
+    pub fn to_jam(&self) -> JamMessage {
+        let mut attributes = MessageAttributes::LOCAL | MessageAttributes::PRIVATE;
+        attributes |= match self.flavour {
+            Flavour::Normal => 0,
+            Flavour::Crash => MessageAttributes::CRASH,
+            Flavour::Hold => MessageAttributes::HOLD,
+            Flavour::Direct => MessageAttributes::DIRECT,
+        };
+
+        if !self.attachments.is_empty() {
+            attributes |= MessageAttributes::FILE_ATTACH;
+        }
+
+        let mut jam = JamMessage::new(&self.from, &self.to, &self.subject)
+            .with_body(self.body.clone())
+            .with_attributes(attributes);
+        jam.from_address = Some(self.from_address.to_string());
+        jam.to_address = Some(self.to_address.to_string());
+        jam.kludges = address_kludges(self.from_address, self.to_address);
+        jam.attachments = self
+            .attachments
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        jam
+    }
+}
+
+/// INTL, FMPT and TOPT kludges for mail between two addresses
+pub fn address_kludges(from: FidoAddress, to: FidoAddress) -> Vec<KludgeLine> {
+    let mut kludges = vec![KludgeLine {
+        kludge_type: "INTL".to_string(),
+        value: format!(
+            "{}:{}/{} {}:{}/{}",
+            to.zone, to.net, to.node, from.zone, from.net, from.node
+        ),
+    }];
+    if from.is_point() {
+        kludges.push(KludgeLine {
+            kludge_type: "FMPT".to_string(),
+            value: from.point.to_string(),
+        });
+    }
+    if to.is_point() {
+        kludges.push(KludgeLine {
+            kludge_type: "TOPT".to_string(),
+            value: to.point.to_string(),
+        });
+    }
+    kludges
+}
+
+/// Destination of a NetMail message from its kludges
+///
+/// Uses the INTL kludge and TOPT point if present, otherwise `fallback`
+/// (made from the packed message's net and node).
+pub fn kludge_destination(kludges: &[KludgeLine], fallback: FidoAddress) -> FidoAddress {
+    intl_address(kludges, 0, "TOPT").unwrap_or(fallback)
+}
+
+/// Origin of a NetMail message from its kludges
+///
+/// Uses the INTL kludge and FMPT point if present, otherwise `fallback`.
+pub fn kludge_origin(kludges: &[KludgeLine], fallback: FidoAddress) -> FidoAddress {
+    intl_address(kludges, 1, "FMPT").unwrap_or(fallback)
+}
+
+/// Address `index` of the INTL kludge, with the point from `point_kludge`
+fn intl_address(kludges: &[KludgeLine], index: usize, point_kludge: &str) -> Option<FidoAddress> {
+    let mut address: FidoAddress = kludges
+        .iter()
+        .find(|kludge| kludge.kludge_type == "INTL")?
+        .value
+        .split_whitespace()
+        .nth(index)?
+        .parse()
+        .ok()?;
+    if let Some(point) = kludges
+        .iter()
+        .find(|kludge| kludge.kludge_type == point_kludge)
+        .and_then(|kludge| kludge.value.trim().parse().ok())
+    {
+        address.point = point;
+    }
+    Some(address)
+}
+
+/// Flavour of a stored NetMail message from its JAM attributes
+pub fn flavour_of(attributes: u32) -> Flavour {
+    let attributes = MessageAttributes::new(attributes);
+    if attributes.has(MessageAttributes::CRASH) {
+        Flavour::Crash
+    } else if attributes.has(MessageAttributes::HOLD) {
+        Flavour::Hold
+    } else if attributes.has(MessageAttributes::DIRECT) {
+        Flavour::Direct
+    } else {
+        Flavour::Normal
+    }
+}
+
+/// Packed message attribute bits for a stored NetMail message
+pub fn packed_attribute(attributes: u32) -> u16 {
+    let attributes = MessageAttributes::new(attributes);
+    let mut packed = packed_attributes::PRIVATE;
+    if attributes.has(MessageAttributes::CRASH) {
+        packed |= packed_attributes::CRASH;
+    }
+    if attributes.has(MessageAttributes::HOLD) {
+        packed |= packed_attributes::HOLD;
+    }
+    if attributes.has(MessageAttributes::FILE_ATTACH) {
+        packed |= packed_attributes::FILE_ATTACH;
+    }
+    packed
+}
+
+/// Files attached to a stored NetMail message
+pub fn attachments(message: &JamMessage) -> Vec<PathBuf> {
+    message.attachments.iter().map(PathBuf::from).collect()
+}
+
+/// Subject for an exported file attach: the attached file names without
+/// their directories, as FTS-0001 has it
+pub fn attach_subject(files: &[PathBuf]) -> String {
+    files
+        .iter()
+        .filter_map(|path| path.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_jam() {
+        let from = FidoAddress::point(2, 5020, 9, 1);
+        let to = FidoAddress::node(1, 234, 5);
+        let jam = NetMessage::new("Bob", from, "Alice", to, "Hello")
+            .with_body("Hi there")
+            .with_flavour(Flavour::Crash)
+            .to_jam();
+
+        assert_eq!(jam.subject, "Hello");
+        assert_eq!(jam.to_address.as_deref(), Some("1:234/5"));
+        assert_eq!(jam.from_address.as_deref(), Some("2:5020/9.1"));
+        assert_eq!(jam.kludges[0].value, "1:234/5 2:5020/9");
+        assert_eq!(jam.kludges[1].kludge_type, "FMPT");
+        assert_eq!(jam.kludges.len(), 2);
+        let attributes = jam.attributes();
+        assert!(attributes.is_local() && attributes.is_private());
+        assert_eq!(flavour_of(jam.attributes), Flavour::Crash);
+        assert_eq!(
+            packed_attribute(jam.attributes),
+            packed_attributes::PRIVATE | packed_attributes::CRASH
+        );
+    }
+
+    #[test]
+    fn test_file_attach() {
+        let address = FidoAddress::node(1, 234, 5);
+        let jam = NetMessage::new("Bob", address, "Alice", address, "Files")
+            .with_flavour(Flavour::Hold)
+            .with_attachment("/files/a.zip")
+            .with_attachment("/files/new releases/b.txt")
+            .to_jam();
+
+        assert_eq!(jam.subject, "Files");
+        assert!(jam.attributes().has(MessageAttributes::FILE_ATTACH));
+        let files = attachments(&jam);
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("/files/a.zip"),
+                PathBuf::from("/files/new releases/b.txt")
+            ]
+        );
+        assert_eq!(attach_subject(&files), "a.zip b.txt");
+        assert_eq!(flavour_of(jam.attributes), Flavour::Hold);
+    }
+
+    #[test]
+    fn test_kludge_destination() {
+        let fallback = FidoAddress::node(2, 5020, 1);
+        let kludges = address_kludges(
+            FidoAddress::node(2, 5020, 9),
+            FidoAddress::point(1, 234, 5, 7),
+        );
+        assert_eq!(
+            kludge_destination(&kludges, fallback),
+            FidoAddress::point(1, 234, 5, 7)
+        );
+        assert_eq!(kludge_destination(&[], fallback), fallback);
+        assert_eq!(
+            kludge_origin(&kludges, fallback),
+            FidoAddress::node(2, 5020, 9)
+        );
+    }
+}
@@ -0,0 +1,343 @@
+//! BinkleyTerm-style outbound directory
+//!
+//! Packets for a node are kept as `NNNNnnnn.out` (net and node in hex) in
+//! the outbound directory, with the extension naming the flavour: `.cut`
+//! for crash, `.hut` for hold and `.dut` for direct. Points live in a
+//! `NNNNnnnn.pnt` directory as `0000pppp.out`, and other zones in sibling
+//! directories named `outbound.zzz` with the zone in hex.
+
+use super::config::FtnConfig;
+use super::error::Result;
+use super::packet::{PACKET_HEADER_SIZE, PackedMessage, Packet, PacketHeader};
+use crate::addressing::FidoAddress;
+use crate::atomic::AtomicWriter;
+use crate::routing::MessageRouter;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// How urgently mail for a node should be delivered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Flavour {
+    /// Sent at the next scheduled poll
+    #[default]
+    Normal,
+    /// Sent as soon as possible
+    Crash,
+    /// Held until the node calls in
+    Hold,
+    /// Sent directly, never routed
+    Direct,
+}
+
+impl Flavour {
+    /// Extension of packet files of this flavour
+    pub fn packet_extension(self) -> &'static str {
+        match self {
+            Flavour::Normal => "out",
+            Flavour::Crash => "cut",
+            Flavour::Hold => "hut",
+            Flavour::Direct => "dut",
+        }
+    }
+
+    /// Extension of flow files (lists of files to send) of this flavour
+    pub fn flow_extension(self) -> &'static str {
+        match self {
+            Flavour::Normal => "flo",
+            Flavour::Crash => "clo",
+            Flavour::Hold => "hlo",
+            Flavour::Direct => "dlo",
+        }
+    }
+}
+
+/// Directory holding mail for `zone`
+pub fn zone_dir(outbound: &Path, local_zone: u16, zone: u16) -> PathBuf {
+    if zone == local_zone {
+        return outbound.to_path_buf();
+    }
+    let name = outbound
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "outbound".to_string());
+    outbound.with_file_name(format!("{}.{:03x}", name, zone))
+}
+
+/// Path of a node's outbound files, without extension
+pub fn node_base_path(outbound: &Path, local_zone: u16, address: FidoAddress) -> PathBuf {
+    let node = format!("{:04x}{:04x}", address.net, address.node);
+    let dir = zone_dir(outbound, local_zone, address.zone);
+    if address.is_point() {
+        dir.join(format!("{}.pnt", node))
+            .join(format!("0000{:04x}", address.point))
+    } else {
+        dir.join(node)
+    }
+}
+
+/// Path of a node's packet file of the given flavour
+pub fn packet_path(
+    outbound: &Path,
+    local_zone: u16,
+    address: FidoAddress,
+    flavour: Flavour,
+) -> PathBuf {
+    node_base_path(outbound, local_zone, address).with_extension(flavour.packet_extension())
+}
+
+/// Collects outgoing messages per next hop and writes them as packets
+pub struct Outbound {
+    /// Outbound directory for our zone
+    dir: PathBuf,
+    /// Our own address
+    local: FidoAddress,
+    /// Router deciding the next hop for each link
+    router: MessageRouter,
+    /// Packet passwords by link address
+    passwords: HashMap<FidoAddress, String>,
+    /// Messages waiting to be written, by next hop and flavour
+    pending: HashMap<(FidoAddress, Flavour), Vec<PackedMessage>>,
+    /// Files waiting to be added to flow files, by next hop and flavour
+    attached: HashMap<(FidoAddress, Flavour), Vec<PathBuf>>,
+}
+
+impl Outbound {
+    /// Create an outbound for a configuration
+    pub fn new(config: &FtnConfig) -> Self {
+        Self {
+            dir: config.outbound.clone(),
+            local: config.address(),
+            router: MessageRouter::new(config.router.clone()),
+            passwords: config
+                .links
+                .iter()
+                .filter_map(|link| Some((link.address, link.password.clone()?)))
+                .collect(),
+            pending: HashMap::new(),
+            attached: HashMap::new(),
+        }
+    }
+
+    /// Next hop for mail to `destination`, as decided by the router
+    ///
+    /// Returns `None` for our own address and for unroutable destinations.
+    pub fn next_hop(&self, destination: FidoAddress) -> Option<FidoAddress> {
+        self.router.route(destination).next_hop()
+    }
+
+    /// Queue a message for a next hop
+    pub fn queue(&mut self, hop: FidoAddress, flavour: Flavour, mut message: PackedMessage) {
+        message.orig_net = self.local.net;
+        message.orig_node = self.local.node;
+        message.dest_net = hop.net;
+        message.dest_node = hop.node;
+        self.pending
+            .entry((hop, flavour))
+            .or_default()
+            .push(message);
+    }
+
+    /// Queue a NetMail message for a next hop
+    ///
+    /// Unlike [`queue`](Self::queue), the message keeps the net and node of
+    /// its final destination.
+    pub fn queue_netmail(&mut self, hop: FidoAddress, flavour: Flavour, message: PackedMessage) {
+        self.pending
+            .entry((hop, flavour))
+            .or_default()
+            .push(message);
+    }
+
+    /// Send a file to a next hop along with its mail
+    pub fn attach(&mut self, hop: FidoAddress, flavour: Flavour, path: impl Into<PathBuf>) {
+        self.attached
+            .entry((hop, flavour))
+            .or_default()
+            .push(path.into());
+    }
+
+    /// Queue an echomail message for each of `links`
+    ///
+    /// Links reached through the same next hop get a single copy.
+    ///
+    /// # Returns
+    /// Number of next hops the message was queued for
+    pub fn queue_for_links(&mut self, links: &[FidoAddress], message: &PackedMessage) -> usize {
+        let mut hops: Vec<FidoAddress> = Vec::new();
+        for &link in links {
+            if let Some(hop) = self.next_hop(link)
+                && !hops.contains(&hop)
+            {
+                hops.push(hop);
+            }
+        }
+        for &hop in &hops {
+            self.queue(hop, Flavour::Normal, message.clone());
+        }
+        hops.len()
+    }
+
+    /// Whether no messages or files are waiting to be written
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty() && self.attached.is_empty()
+    }
+
+    /// Write all queued messages to packet files
+    ///
+    /// Messages are appended to a packet already waiting for the same node
+    /// and flavour, and attached files to its flow file.
+    ///
+    /// # Returns
+    /// Paths of the packet files written
+    pub async fn flush(&mut self) -> Result<Vec<PathBuf>> {
+        let mut written = Vec::new();
+        for ((hop, flavour), messages) in std::mem::take(&mut self.pending) {
+            written.push(self.write_packet(hop, flavour, &messages).await?);
+        }
+        for ((hop, flavour), files) in std::mem::take(&mut self.attached) {
+            self.write_flow(hop, flavour, &files).await?;
+        }
+        written.sort();
+        Ok(written)
+    }
+
+    /// Add files to a node's flow file
+    ///
+    /// The files are sent as they are and left in place afterwards.
+    async fn write_flow(
+        &self,
+        dest: FidoAddress,
+        flavour: Flavour,
+        files: &[PathBuf],
+    ) -> Result<()> {
+        let path = node_base_path(&self.dir, self.local.zone, dest)
+            .with_extension(flavour.flow_extension());
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let mut contents = match tokio::fs::read_to_string(&path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            contents.push_str("\r\n");
+        }
+        for file in files {
+            contents.push_str(&format!("{}\r\n", file.display()));
+        }
+
+        AtomicWriter::new(&path).write(contents.as_bytes()).await?;
+        Ok(())
+    }
+
+    /// Write messages to a node's packet file
+    async fn write_packet(
+        &self,
+        dest: FidoAddress,
+        flavour: Flavour,
+        messages: &[PackedMessage],
+    ) -> Result<PathBuf> {
+        let path = packet_path(&self.dir, self.local.zone, dest, flavour);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let bytes = match tokio::fs::read(&path).await {
+            Ok(mut existing) if existing.len() >= PACKET_HEADER_SIZE => {
+                if existing.ends_with(&[0, 0]) {
+                    existing.truncate(existing.len() - 2);
+                }
+                for message in messages {
+                    existing.extend_from_slice(&message.to_bytes());
+                }
+                existing.extend_from_slice(&[0, 0]);
+                existing
+            }
+            _ => {
+                let header = PacketHeader::new(self.local, dest)
+                    .with_password(self.passwords.get(&dest).cloned().unwrap_or_default());
+                let mut packet = Packet::new(header);
+                packet.messages = messages.to_vec();
+                packet.to_bytes()
+            }
+        };
+
+        AtomicWriter::new(&path).write(&bytes).await?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ftn::config::FtnLink;
+    use crate::routing::RouterConfig;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_packet_paths() {
+        let outbound = Path::new("/ftn/outbound");
+        assert_eq!(
+            packet_path(outbound, 2, FidoAddress::node(2, 5020, 9), Flavour::Normal),
+            PathBuf::from("/ftn/outbound/139c0009.out")
+        );
+        assert_eq!(
+            packet_path(
+                outbound,
+                2,
+                FidoAddress::point(2, 5020, 9, 3),
+                Flavour::Crash
+            ),
+            PathBuf::from("/ftn/outbound/139c0009.pnt/00000003.cut")
+        );
+        assert_eq!(
+            packet_path(outbound, 2, FidoAddress::node(1, 1, 2), Flavour::Hold),
+            PathBuf::from("/ftn/outbound.001/00010002.hut")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_queue_and_flush() {
+        let temp_dir = TempDir::new().unwrap();
+        let local = FidoAddress::node(2, 5020, 1);
+        let hub = FidoAddress::node(2, 5020, 0);
+        let near = FidoAddress::node(2, 5020, 9);
+        let far = FidoAddress::node(2, 5030, 7);
+        let far2 = FidoAddress::node(2, 5040, 3);
+        let config = FtnConfig::new(
+            RouterConfig::new(local).with_hub(hub),
+            temp_dir.path().join("inbound"),
+            temp_dir.path().join("outbound"),
+        )
+        .with_link(FtnLink::new(near).with_password("NEARPW"));
+
+        let mut outbound = Outbound::new(&config);
+        let message = PackedMessage::new(local, near, "A", "All", "Hi");
+        assert_eq!(
+            outbound.queue_for_links(&[near, far, far2, local], &message),
+            2
+        );
+
+        let written = outbound.flush().await.unwrap();
+        assert_eq!(written.len(), 2);
+        assert!(outbound.is_empty());
+
+        let near_path = packet_path(&config.outbound, 2, near, Flavour::Normal);
+        let packet = Packet::parse(&tokio::fs::read(&near_path).await.unwrap()).unwrap();
+        assert_eq!(packet.header.dest, near);
+        assert_eq!(packet.header.password, "NEARPW");
+
+        // A second flush appends to the waiting packet
+        outbound.queue(near, Flavour::Normal, message.clone());
+        outbound.flush().await.unwrap();
+        let packet = Packet::parse(&tokio::fs::read(&near_path).await.unwrap()).unwrap();
+        assert_eq!(packet.messages.len(), 2);
+        assert_eq!(packet.messages[1].dest_node, 9);
+
+        let hub_path = packet_path(&config.outbound, 2, hub, Flavour::Normal);
+        let packet = Packet::parse(&tokio::fs::read(&hub_path).await.unwrap()).unwrap();
+        assert_eq!(packet.messages.len(), 1);
+    }
+}
@@ -0,0 +1,499 @@
+//! FTS-0001 / FSC-0039 Type-2+ packet reading and writing
+//!
+//! A packet is a 58-byte header followed by packed messages, each starting
+//! with the message type 2, and ends with two zero bytes. Type-2+ headers
+//! carry the zones and points of both ends, which plain Type-2 headers leave
+//! out; both are accepted when reading.
+
+use super::error::{FtnError, Result};
+use crate::addressing::FidoAddress;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+
+/// Size of a packet header
+pub const PACKET_HEADER_SIZE: usize = 58;
+
+/// Product code written into packet headers (0xFE, no assigned code)
+pub const PRODUCT_CODE: u16 = 0x00FE;
+
+/// Packet type stored in every header
+const PACKET_TYPE: u16 = 2;
+
+/// Message type at the start of every packed message
+const PACKED_MESSAGE_TYPE: u16 = 2;
+
+/// Capability word advertising Type-2+ support
+const CAPABILITY_WORD: u16 = 0x0001;
+
+/// Size of the fixed part of a packed message, before the date
+const PACKED_MESSAGE_FIXED_SIZE: usize = 14;
+
+/// Size of the date field of a packed message
+const PACKED_DATE_SIZE: usize = 20;
+
+/// Maximum lengths of the to, from and subject fields, including the null
+const TO_FROM_MAX: usize = 36;
+const SUBJECT_MAX: usize = 72;
+
+/// Format of the date field of a packed message
+const PACKED_DATE_FORMAT: &str = "%d %b %y  %H:%M:%S";
+
+/// Packet header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PacketHeader {
+    /// Address of the system that created the packet
+    pub orig: FidoAddress,
+    /// Address the packet is for
+    pub dest: FidoAddress,
+    /// When the packet was created
+    pub created: NaiveDateTime,
+    /// Session password (up to 8 characters, empty for none)
+    pub password: String,
+    /// Product code of the program that created the packet
+    pub product_code: u16,
+}
+
+impl PacketHeader {
+    /// Create a header for a packet from `orig` to `dest`
+    pub fn new(orig: FidoAddress, dest: FidoAddress) -> Self {
+        Self {
+            orig,
+            dest,
+            created: Utc::now().naive_utc(),
+            password: String::new(),
+            product_code: PRODUCT_CODE,
+        }
+    }
+
+    /// Set the packet password
+    pub fn with_password(mut self, password: impl Into<String>) -> Self {
+        self.password = password.into();
+        self
+    }
+
+    /// Parse a packet header
+    ///
+    /// Zones and points are only known for Type-2+ packets; for plain Type-2
+    /// packets the points are 0 and the zones come from the QMail fields,
+    /// which may also be 0.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < PACKET_HEADER_SIZE {
+            return Err(FtnError::InvalidPacket(
+                "packet header is truncated".to_string(),
+            ));
+        }
+
+        let packet_type = read_u16(data, 18);
+        if packet_type != PACKET_TYPE {
+            return Err(FtnError::InvalidPacket(format!(
+                "unsupported packet type {}",
+                packet_type
+            )));
+        }
+
+        let mut orig = FidoAddress::node(read_u16(data, 34), read_u16(data, 20), read_u16(data, 0));
+        let mut dest = FidoAddress::node(read_u16(data, 36), read_u16(data, 22), read_u16(data, 2));
+
+        let capability = read_u16(data, 44);
+        let capability_copy = read_u16(data, 40);
+        if capability == capability_copy.swap_bytes() && capability & CAPABILITY_WORD != 0 {
+            orig.zone = read_u16(data, 46);
+            dest.zone = read_u16(data, 48);
+            orig.point = read_u16(data, 50);
+            dest.point = read_u16(data, 52);
+
+            // Points sending under a fake net keep their boss's net here
+            if orig.point != 0 && orig.net == 0xFFFF {
+                orig.net = read_u16(data, 38);
+            }
+        }
+
+        let created = NaiveDate::from_ymd_opt(
+            i32::from(read_u16(data, 4)),
+            u32::from(read_u16(data, 6)) + 1,
+            u32::from(read_u16(data, 8)),
+        )
+        .and_then(|date| {
+            date.and_hms_opt(
+                u32::from(read_u16(data, 10)),
+                u32::from(read_u16(data, 12)),
+                u32::from(read_u16(data, 14)),
+            )
+        })
+        .unwrap_or_default();
+
+        let password = String::from_utf8_lossy(&data[26..34])
+            .trim_end_matches('\0')
+            .trim()
+            .to_string();
+
+        Ok(Self {
+            orig,
+            dest,
+            created,
+            password,
+            product_code: u16::from(data[24]) | (u16::from(data[42]) << 8),
+        })
+    }
+
+    /// Serialize the header as Type-2+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        use chrono::{Datelike, Timelike};
+
+        let mut bytes = Vec::with_capacity(PACKET_HEADER_SIZE);
+        let mut push = |value: u16| bytes.extend_from_slice(&value.to_le_bytes());
+        push(self.orig.node);
+        push(self.dest.node);
+        push(self.created.year() as u16);
+        push(self.created.month0() as u16);
+        push(self.created.day() as u16);
+        push(self.created.hour() as u16);
+        push(self.created.minute() as u16);
+        push(self.created.second() as u16);
+        push(0); // baud
+        push(PACKET_TYPE);
+        push(self.orig.net);
+        push(self.dest.net);
+
+        bytes.push(self.product_code as u8);
+        bytes.push(0); // major revision
+
+        let mut password = [0u8; 8];
+        for (slot, byte) in password.iter_mut().zip(self.password.bytes()) {
+            *slot = byte;
+        }
+        bytes.extend_from_slice(&password);
+
+        let mut push = |value: u16| bytes.extend_from_slice(&value.to_le_bytes());
+        push(self.orig.zone);
+        push(self.dest.zone);
+        push(0); // aux net
+        push(CAPABILITY_WORD.swap_bytes());
+
+        bytes.push((self.product_code >> 8) as u8);
+        bytes.push(0); // minor revision
+
+        let mut push = |value: u16| bytes.extend_from_slice(&value.to_le_bytes());
+        push(CAPABILITY_WORD);
+        push(self.orig.zone);
+        push(self.dest.zone);
+        push(self.orig.point);
+        push(self.dest.point);
+        bytes.extend_from_slice(&[0u8; 4]); // product data
+
+        bytes
+    }
+}
+
+/// A message inside a packet
+///
+/// Only the net and node of each end travel with the message; zones and
+/// points come from the packet header or, for netmail, from kludge lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedMessage {
+    /// Originating net
+    pub orig_net: u16,
+    /// Originating node
+    pub orig_node: u16,
+    /// Destination net
+    pub dest_net: u16,
+    /// Destination node
+    pub dest_node: u16,
+    /// FTS-0001 attribute word
+    pub attribute: u16,
+    /// Cost in cents
+    pub cost: u16,
+    /// Date written, as "DD Mon YY  HH:MM:SS"
+    pub date_time: String,
+    /// Recipient name
+    pub to: String,
+    /// Sender name
+    pub from: String,
+    /// Subject
+    pub subject: String,
+    /// Message text, lines separated by carriage returns
+    pub text: String,
+}
+
+impl PackedMessage {
+    /// Create a message between two addresses
+    pub fn new(
+        orig: FidoAddress,
+        dest: FidoAddress,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        subject: impl Into<String>,
+    ) -> Self {
+        Self {
+            orig_net: orig.net,
+            orig_node: orig.node,
+            dest_net: dest.net,
+            dest_node: dest.node,
+            attribute: 0,
+            cost: 0,
+            date_time: format_date(Utc::now().naive_utc()),
+            to: to.into(),
+            from: from.into(),
+            subject: subject.into(),
+            text: String::new(),
+        }
+    }
+
+    /// Set when the message was written
+    pub fn with_date(mut self, date: NaiveDateTime) -> Self {
+        self.date_time = format_date(date);
+        self
+    }
+
+    /// Set the message text
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.text = text.into();
+        self
+    }
+
+    /// Parse the date field
+    pub fn date(&self) -> Option<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(self.date_time.trim(), PACKED_DATE_FORMAT).ok()
+    }
+
+    /// Serialize the message, including its leading message type
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(PACKED_MESSAGE_FIXED_SIZE + 200 + self.text.len());
+        for value in [
+            PACKED_MESSAGE_TYPE,
+            self.orig_node,
+            self.dest_node,
+            self.orig_net,
+            self.dest_net,
+            self.attribute,
+            self.cost,
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let mut date = [0u8; PACKED_DATE_SIZE];
+        for (slot, byte) in date
+            .iter_mut()
+            .zip(self.date_time.bytes().take(PACKED_DATE_SIZE - 1))
+        {
+            *slot = byte;
+        }
+        bytes.extend_from_slice(&date);
+
+        push_cstring(&mut bytes, &self.to, TO_FROM_MAX);
+        push_cstring(&mut bytes, &self.from, TO_FROM_MAX);
+        push_cstring(&mut bytes, &self.subject, SUBJECT_MAX);
+        bytes.extend_from_slice(self.text.as_bytes());
+        bytes.push(0);
+
+        bytes
+    }
+}
+
+/// A complete packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet {
+    /// Packet header
+    pub header: PacketHeader,
+    /// Messages in the packet
+    pub messages: Vec<PackedMessage>,
+}
+
+impl Packet {
+    /// Create an empty packet
+    pub fn new(header: PacketHeader) -> Self {
+        Self {
+            header,
+            messages: Vec::new(),
+        }
+    }
+
+    /// Parse a packet
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let header = PacketHeader::parse(data)?;
+        let mut messages = Vec::new();
+        let mut pos = PACKET_HEADER_SIZE;
+
+        // Some packers leave off the terminating zero word
+        while pos + 2 <= data.len() {
+            let message_type = read_u16(data, pos);
+            if message_type == 0 {
+                break;
+            }
+            if message_type != PACKED_MESSAGE_TYPE {
+                return Err(FtnError::InvalidPacket(format!(
+                    "unknown message type {} at offset {}",
+                    message_type, pos
+                )));
+            }
+            if pos + PACKED_MESSAGE_FIXED_SIZE + PACKED_DATE_SIZE > data.len() {
+                return Err(FtnError::InvalidPacket(format!(
+                    "message at offset {} is truncated",
+                    pos
+                )));
+            }
+
+            let field = |index: usize| read_u16(data, pos + 2 + index * 2);
+            let mut message = PackedMessage {
+                orig_node: field(0),
+                dest_node: field(1),
+                orig_net: field(2),
+                dest_net: field(3),
+                attribute: field(4),
+                cost: field(5),
+                date_time: String::new(),
+                to: String::new(),
+                from: String::new(),
+                subject: String::new(),
+                text: String::new(),
+            };
+            pos += PACKED_MESSAGE_FIXED_SIZE;
+
+            message.date_time = String::from_utf8_lossy(&data[pos..pos + PACKED_DATE_SIZE])
+                .trim_end_matches('\0')
+                .to_string();
+            pos += PACKED_DATE_SIZE;
+
+            message.to = read_cstring(data, &mut pos)?;
+            message.from = read_cstring(data, &mut pos)?;
+            message.subject = read_cstring(data, &mut pos)?;
+            message.text = read_cstring(data, &mut pos)?;
+            messages.push(message);
+        }
+
+        Ok(Self { header, messages })
+    }
+
+    /// Serialize the packet, including the terminating zero word
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.header.to_bytes();
+        for message in &self.messages {
+            bytes.extend_from_slice(&message.to_bytes());
+        }
+        bytes.extend_from_slice(&[0, 0]);
+        bytes
+    }
+}
+
+/// Format a date for a packed message
+pub fn format_date(date: NaiveDateTime) -> String {
+    date.format(PACKED_DATE_FORMAT).to_string()
+}
+
+/// Read a little-endian u16 at `offset`
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+/// Read a null-terminated string starting at `pos`, moving `pos` past it
+fn read_cstring(data: &[u8], pos: &mut usize) -> Result<String> {
+    let rest = &data[*pos..];
+    let len = rest
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or_else(|| FtnError::InvalidPacket(format!("unterminated string at offset {}", pos)))?;
+    *pos += len + 1;
+    Ok(String::from_utf8_lossy(&rest[..len]).to_string())
+}
+
+/// Append `value` as a null-terminated string of at most `max` bytes
+fn push_cstring(bytes: &mut Vec<u8>, value: &str, max: usize) {
+    let mut end = value.len().min(max - 1);
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    bytes.extend_from_slice(&value.as_bytes()[..end]);
+    bytes.push(0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_packet() -> Packet {
+        let header = PacketHeader::new(
+            FidoAddress::node(2, 5020, 1),
+            FidoAddress::point(2, 5020, 9, 3),
+        )
+        .with_password("SECRET");
+        let mut packet = Packet::new(header);
+        packet.messages.push(
+            PackedMessage::new(
+                FidoAddress::node(2, 5020, 1),
+                FidoAddress::node(2, 5020, 9),
+                "Alice",
+                "All",
+                "Hello",
+            )
+            .with_text("AREA:TEST\rHello everyone\r"),
+        );
+        packet
+    }
+
+    #[test]
+    fn test_header_size() {
+        assert_eq!(sample_packet().header.to_bytes().len(), PACKET_HEADER_SIZE);
+    }
+
+    #[test]
+    fn test_packet_round_trip() {
+        let packet = sample_packet();
+        let bytes = packet.to_bytes();
+        assert_eq!(&bytes[bytes.len() - 2..], &[0, 0]);
+
+        let parsed = Packet::parse(&bytes).unwrap();
+        assert_eq!(parsed.header.orig, FidoAddress::node(2, 5020, 1));
+        assert_eq!(parsed.header.dest, FidoAddress::point(2, 5020, 9, 3));
+        assert_eq!(parsed.header.password, "SECRET");
+        assert_eq!(parsed.messages, packet.messages);
+        assert!(parsed.messages[0].date().is_some());
+    }
+
+    #[test]
+    fn test_plain_type2_header() {
+        let mut bytes = sample_packet().to_bytes();
+        // Clear the capability word and its copy
+        bytes[40..42].copy_from_slice(&[0, 0]);
+        bytes[44..46].copy_from_slice(&[0, 0]);
+
+        let parsed = Packet::parse(&bytes).unwrap();
+        assert_eq!(parsed.header.dest.point, 0);
+        assert_eq!(parsed.header.dest.node, 9);
+        assert_eq!(parsed.messages.len(), 1);
+    }
+
+    #[test]
+    fn test_missing_terminator() {
+        let mut bytes = sample_packet().to_bytes();
+        bytes.truncate(bytes.len() - 2);
+        assert_eq!(Packet::parse(&bytes).unwrap().messages.len(), 1);
+    }
+
+    #[test]
+    fn test_truncated_packets() {
+        let bytes = sample_packet().to_bytes();
+        assert!(Packet::parse(&bytes[..20]).is_err());
+        assert!(Packet::parse(&bytes[..PACKET_HEADER_SIZE + 40]).is_err());
+
+        let mut bad_type = bytes.clone();
+        bad_type[PACKET_HEADER_SIZE] = 7;
+        assert!(Packet::parse(&bad_type).is_err());
+    }
+
+    #[test]
+    fn test_long_fields_are_cut() {
+        let message = PackedMessage::new(
+            FidoAddress::node(1, 1, 1),
+            FidoAddress::node(1, 1, 2),
+            "F".repeat(50),
+            "T",
+            "S".repeat(100),
+        );
+        let mut bytes = vec![0u8; PACKET_HEADER_SIZE];
+        bytes[18] = 2;
+        bytes.extend_from_slice(&message.to_bytes());
+
+        let parsed = Packet::parse(&bytes).unwrap();
+        assert_eq!(parsed.messages[0].from.len(), 35);
+        assert_eq!(parsed.messages[0].subject.len(), 71);
+    }
+}
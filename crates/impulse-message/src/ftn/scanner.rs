@@ -0,0 +1,485 @@
+//! Outbound echomail and NetMail scanning
+//!
+//! Finds local messages in the echomail areas that have not been sent yet,
+//! adds the MSGID, tearline, origin, SEEN-BY and PATH lines they need, and
+//! writes them to outbound packets for each of the area's links. Unsent
+//! NetMail is packed for the next hop towards its destination, along with
+//! any files attached to it.
+//!
+//! Each echomail base keeps a high-water mark, the last message scanned, in
+//! a `.jhw` file beside it, so a scan only reads the messages added since the
+//! last one.
+
+use super::config::{EchoArea, FtnConfig, TEARLINE};
+use super::echomail::EchoText;
+use super::error::Result;
+use super::netmail;
+use super::outbound::{Flavour, Outbound};
+use super::packet::PackedMessage;
+use crate::addressing::FidoAddress;
+use crate::formats::jam::{JamMessage, JamMessageBase, MessageAttributes, jam_crc};
+use crate::traits::MessageBase;
+use crate::types::KludgeLine;
+use std::path::{Path, PathBuf};
+
+/// Counts from a scan run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanStats {
+    /// Messages exported
+    pub messages: usize,
+    /// Packet files written or appended to
+    pub packets: Vec<PathBuf>,
+    /// Messages that could not be read, by base, and were skipped
+    pub bad_messages: Vec<(PathBuf, u32)>,
+}
+
+/// Outbound echomail scanner
+pub struct Scanner {
+    /// Scanner configuration
+    config: FtnConfig,
+}
+
+impl Scanner {
+    /// Create a scanner
+    pub fn new(config: FtnConfig) -> Self {
+        Self { config }
+    }
+
+    /// Export every unsent local message in the echomail areas and the
+    /// NetMail base
+    ///
+    /// Messages are marked as sent, and the high-water marks moved on, once
+    /// their packets are written. Messages that cannot be read are skipped
+    /// and listed in the stats.
+    pub async fn scan(&self) -> Result<ScanStats> {
+        let mut stats = ScanStats::default();
+        let mut outbound = Outbound::new(&self.config);
+        let mut exported = Vec::new();
+        let mut high_water = Vec::new();
+
+        for area in &self.config.areas {
+            let base = JamMessageBase::new(&area.base_path);
+            if !tokio::fs::try_exists(area.base_path.with_extension("jhr")).await? {
+                continue;
+            }
+
+            let count = base.message_count().await?;
+            if count == 0 {
+                continue;
+            }
+            let (first, last) = base.get_message_range().await?;
+            let scanned = read_high_water(&area.base_path).await;
+            if scanned >= last {
+                continue;
+            }
+
+            for msg_num in first.max(scanned + 1)..=last {
+                let Ok(message) = base.read_jam_message(msg_num).await else {
+                    stats.bad_messages.push((area.base_path.clone(), msg_num));
+                    continue;
+                };
+                let attributes = message.attributes();
+                if !attributes.is_local() || attributes.has(MessageAttributes::SENT) {
+                    continue;
+                }
+
+                let packed = self.export_message(area, msg_num, &message);
+                outbound.queue_for_links(&area.links, &packed);
+                exported.push((area.base_path.clone(), msg_num, message.attributes));
+            }
+            high_water.push((area.base_path.clone(), last));
+        }
+
+        if let Some(base_path) = &self.config.netmail {
+            self.scan_netmail(base_path, &mut outbound, &mut exported, &mut stats)
+                .await?;
+        }
+
+        stats.packets = outbound.flush().await?;
+
+        for (base_path, msg_num, attributes) in exported {
+            JamMessageBase::new(base_path)
+                .set_attributes(msg_num, attributes | MessageAttributes::SENT)
+                .await?;
+            stats.messages += 1;
+        }
+
+        for (base_path, last) in high_water {
+            write_high_water(&base_path, last).await?;
+        }
+
+        Ok(stats)
+    }
+
+    /// Queue every unsent local NetMail message
+    ///
+    /// Messages to our own address or with no route stay unsent, so the
+    /// whole base is read every time.
+    async fn scan_netmail(
+        &self,
+        base_path: &Path,
+        outbound: &mut Outbound,
+        exported: &mut Vec<(PathBuf, u32, u32)>,
+        stats: &mut ScanStats,
+    ) -> Result<()> {
+        let base = JamMessageBase::new(base_path);
+        if !tokio::fs::try_exists(base_path.with_extension("jhr")).await?
+            || base.message_count().await? == 0
+        {
+            return Ok(());
+        }
+        let (first, last) = base.get_message_range().await?;
+
+        for msg_num in first..=last {
+            let Ok(message) = base.read_jam_message(msg_num).await else {
+                stats.bad_messages.push((base_path.to_path_buf(), msg_num));
+                continue;
+            };
+            let attributes = message.attributes();
+            if !attributes.is_local() || attributes.has(MessageAttributes::SENT) {
+                continue;
+            }
+            let Some(destination) = message
+                .to_address
+                .as_deref()
+                .and_then(|address| address.parse::<FidoAddress>().ok())
+            else {
+                continue;
+            };
+
+            // Only normal mail is routed
+            let flavour = netmail::flavour_of(message.attributes);
+            let hop = match flavour {
+                Flavour::Normal => outbound.next_hop(destination),
+                _ => Some(destination).filter(|_| !self.config.addresses().contains(&destination)),
+            };
+            let Some(hop) = hop else {
+                continue;
+            };
+
+            let files = if attributes.has(MessageAttributes::FILE_ATTACH) {
+                netmail::attachments(&message)
+            } else {
+                Vec::new()
+            };
+            let packed = self.export_netmail(msg_num, &message, destination, &files);
+            outbound.queue_netmail(hop, flavour, packed);
+            for file in files {
+                outbound.attach(hop, flavour, file);
+            }
+            exported.push((base_path.to_path_buf(), msg_num, message.attributes));
+        }
+
+        Ok(())
+    }
+
+    /// Build the packed message for a local NetMail message
+    fn export_netmail(
+        &self,
+        msg_num: u32,
+        message: &JamMessage,
+        destination: FidoAddress,
+        files: &[PathBuf],
+    ) -> PackedMessage {
+        let from = message
+            .from_address
+            .as_deref()
+            .and_then(|address| address.parse::<FidoAddress>().ok())
+            .unwrap_or_else(|| self.config.address_for(destination));
+
+        let mut kludges = message.kludges.clone();
+        if !kludges.iter().any(|k| k.kludge_type == "INTL") {
+            kludges.splice(0..0, netmail::address_kludges(from, destination));
+        }
+
+        let mut echo = EchoText {
+            area: None,
+            msgid: message.msgid.clone().or_else(|| {
+                let serial = jam_crc(&format!(
+                    "NETMAIL {} {}",
+                    msg_num,
+                    message.date_written.timestamp()
+                ));
+                Some(format!("{} {:08x}", from, serial))
+            }),
+            reply_id: message.reply_id.clone(),
+            kludges,
+            body: message.body.replace("\r\n", "\n").replace('\r', "\n"),
+            seen_by: Vec::new(),
+            path: Vec::new(),
+        };
+        add_utc_kludge(&mut echo);
+        echo.body = format!("{}\n\n{}", echo.body.trim_end(), TEARLINE);
+
+        let subject = if files.is_empty() {
+            message.subject.clone()
+        } else {
+            netmail::attach_subject(files)
+        };
+        let mut packed = PackedMessage::new(from, destination, &message.from, &message.to, subject)
+            .with_date(message.date_written.naive_utc())
+            .with_text(echo.to_text());
+        packed.attribute = netmail::packed_attribute(message.attributes);
+        packed
+    }
+
+    /// Build the packed message for a local message
+    fn export_message(&self, area: &EchoArea, msg_num: u32, message: &JamMessage) -> PackedMessage {
+        let local = self.config.address();
+
+        let mut echo = EchoText {
+            area: Some(area.tag.clone()),
+            msgid: message.msgid.clone().or_else(|| {
+                let serial = jam_crc(&format!(
+                    "{} {} {}",
+                    area.tag,
+                    msg_num,
+                    message.date_written.timestamp()
+                ));
+                Some(format!("{} {:08x}", local, serial))
+            }),
+            reply_id: message.reply_id.clone(),
+            kludges: message.kludges.clone(),
+            body: message.body.replace("\r\n", "\n").replace('\r', "\n"),
+            seen_by: message.seen_by.clone(),
+            path: message.path.clone(),
+        };
+
+        add_utc_kludge(&mut echo);
+
+        if !echo.has_origin() {
+            echo.body = format!(
+                "{}\n\n{}\n * Origin: {} ({})",
+                echo.body.trim_end(),
+                TEARLINE,
+                self.config.origin,
+                local
+            );
+        }
+
+        echo.add_seen_by(area.links.iter().copied().chain([local]));
+        echo.add_path(local);
+
+        PackedMessage::new(local, local, &message.from, &message.to, &message.subject)
+            .with_date(message.date_written.naive_utc())
+            .with_text(echo.to_text())
+    }
+}
+
+/// Path of the file holding a base's high-water mark
+fn high_water_path(base_path: &Path) -> PathBuf {
+    base_path.with_extension("jhw")
+}
+
+/// Read the last message scanned in a base
+///
+/// A missing or damaged mark reads as 0, so the whole base is scanned and
+/// the SENT flags keep messages from going out twice.
+async fn read_high_water(base_path: &Path) -> u32 {
+    match tokio::fs::read(high_water_path(base_path)).await {
+        Ok(data) => data.try_into().map(u32::from_le_bytes).unwrap_or_default(),
+        Err(_) => 0,
+    }
+}
+
+/// Record the last message scanned in a base
+async fn write_high_water(base_path: &Path, msg_num: u32) -> Result<()> {
+    tokio::fs::write(high_water_path(base_path), msg_num.to_le_bytes()).await?;
+    Ok(())
+}
+
+/// Note that dates are written in UTC
+fn add_utc_kludge(echo: &mut EchoText) {
+    if !echo.kludges.iter().any(|k| k.kludge_type == "TZUTC") {
+        echo.kludges.push(KludgeLine {
+            kludge_type: "TZUTC".to_string(),
+            value: "0000".to_string(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ftn::config::FtnLink;
+    use crate::ftn::netmail::NetMessage;
+    use crate::ftn::outbound::packet_path;
+    use crate::ftn::packet::Packet;
+    use crate::routing::RouterConfig;
+    use crate::types::NewMessage;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_scan_exports_local_messages_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let local = FidoAddress::node(2, 5020, 1);
+        let hub = FidoAddress::node(2, 5020, 0);
+        let uplink = FidoAddress::node(2, 5020, 9);
+        let remote = FidoAddress::node(2, 5030, 7);
+        let base_path = temp_dir.path().join("msg/test");
+        let config = FtnConfig::new(
+            RouterConfig::new(local).with_hub(hub),
+            temp_dir.path().join("inbound"),
+            temp_dir.path().join("outbound"),
+        )
+        .with_area(
+            EchoArea::new("TEST", &base_path)
+                .with_link(uplink)
+                .with_link(remote),
+        )
+        .with_link(FtnLink::new(uplink).with_password("UPPW"))
+        .with_origin("Test BBS");
+
+        let mut base = JamMessageBase::new(&base_path);
+        base.create_if_missing().await.unwrap();
+        base.post_message(NewMessage::new("Bob", "All", "Local").with_body("From here"))
+            .await
+            .unwrap();
+        base.add_message(&JamMessage::new("Alice", "All", "Tossed"))
+            .await
+            .unwrap();
+
+        let scanner = Scanner::new(config.clone());
+        let stats = scanner.scan().await.unwrap();
+        assert_eq!(stats.messages, 1);
+        assert_eq!(stats.packets.len(), 2);
+
+        let out = packet_path(&config.outbound, 2, uplink, Flavour::Normal);
+        let packet = Packet::parse(&tokio::fs::read(&out).await.unwrap()).unwrap();
+        assert_eq!(packet.header.password, "UPPW");
+        let message = &packet.messages[0];
+        assert_eq!(message.subject, "Local");
+        assert_eq!(message.dest_node, 9);
+
+        let echo = EchoText::parse(&message.text);
+        assert_eq!(echo.area.as_deref(), Some("TEST"));
+        assert!(echo.msgid.as_deref().unwrap().starts_with("2:5020/1 "));
+        assert!(echo.has_origin());
+        assert_eq!(echo.origin_address(), Some(local));
+        assert!(echo.body.starts_with("From here\n\n--- Impulse 7.1"));
+        assert_eq!(echo.seen_by, vec!["5020/1 9 5030/7"]);
+        assert_eq!(echo.path, vec!["5020/1"]);
+
+        // Routed to the hub for the other net
+        assert!(packet_path(&config.outbound, 2, hub, Flavour::Normal).exists());
+
+        // Already sent messages are not exported again
+        assert_eq!(read_high_water(&base_path).await, 2);
+        assert_eq!(scanner.scan().await.unwrap().messages, 0);
+        let sent = base.read_jam_message(1).await.unwrap();
+        assert!(sent.attributes().has(MessageAttributes::SENT));
+
+        // Only messages past the high-water mark are read
+        base.post_message(NewMessage::new("Bob", "All", "Later").with_body("More"))
+            .await
+            .unwrap();
+        assert_eq!(scanner.scan().await.unwrap().messages, 1);
+        assert_eq!(read_high_water(&base_path).await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_scan_skips_unreadable_messages() {
+        let temp_dir = TempDir::new().unwrap();
+        let local = FidoAddress::node(2, 5020, 1);
+        let uplink = FidoAddress::node(2, 5020, 9);
+        let base_path = temp_dir.path().join("msg/test");
+        let config = FtnConfig::new(
+            RouterConfig::new(local),
+            temp_dir.path().join("inbound"),
+            temp_dir.path().join("outbound"),
+        )
+        .with_area(EchoArea::new("TEST", &base_path).with_link(uplink));
+
+        let mut base = JamMessageBase::new(&base_path);
+        base.create_if_missing().await.unwrap();
+        for subject in ["Broken", "Fine"] {
+            base.post_message(NewMessage::new("Bob", "All", subject).with_body("Text"))
+                .await
+                .unwrap();
+        }
+
+        // Point the first index record past the end of the headers
+        let mut jdx = tokio::fs::read(base_path.with_extension("jdx"))
+            .await
+            .unwrap();
+        jdx[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        tokio::fs::write(base_path.with_extension("jdx"), jdx)
+            .await
+            .unwrap();
+
+        let stats = Scanner::new(config).scan().await.unwrap();
+        assert_eq!(stats.messages, 1);
+        assert_eq!(stats.bad_messages, vec![(base_path.clone(), 1)]);
+        let sent = base.read_jam_message(2).await.unwrap();
+        assert!(sent.attributes().has(MessageAttributes::SENT));
+    }
+
+    #[tokio::test]
+    async fn test_scan_exports_netmail() {
+        let temp_dir = TempDir::new().unwrap();
+        let local = FidoAddress::node(2, 5020, 1);
+        let hub = FidoAddress::node(2, 5020, 0);
+        let far = FidoAddress::point(2, 5030, 7, 2);
+        let netmail_path = temp_dir.path().join("msg/netmail");
+        let config = FtnConfig::new(
+            RouterConfig::new(local).with_hub(hub),
+            temp_dir.path().join("inbound"),
+            temp_dir.path().join("outbound"),
+        )
+        .with_netmail(&netmail_path);
+
+        let attach = temp_dir.path().join("new files/report.zip");
+        let base = JamMessageBase::new(&netmail_path);
+        base.create_if_missing().await.unwrap();
+        for message in [
+            NetMessage::new("Bob", local, "Carol", far, "Routed").with_body("Via the hub"),
+            NetMessage::new("Bob", local, "Carol", far, "Urgent").with_flavour(Flavour::Crash),
+            NetMessage::new("Bob", local, "Dave", far, "Files")
+                .with_flavour(Flavour::Hold)
+                .with_attachment(&attach),
+            NetMessage::new("Bob", local, "Sysop", local, "To myself"),
+        ] {
+            base.add_message(&message.to_jam()).await.unwrap();
+        }
+
+        let stats = Scanner::new(config.clone()).scan().await.unwrap();
+        assert_eq!(stats.messages, 3);
+        assert_eq!(stats.packets.len(), 3);
+
+        // Normal mail is routed through the hub but keeps its destination
+        let routed = packet_path(&config.outbound, 2, hub, Flavour::Normal);
+        let packet = Packet::parse(&tokio::fs::read(&routed).await.unwrap()).unwrap();
+        let message = &packet.messages[0];
+        assert_eq!((message.dest_net, message.dest_node), (5030, 7));
+        assert_eq!(message.attribute, netmail::packed_attributes::PRIVATE);
+        let text = EchoText::parse(&message.text);
+        assert_eq!(text.area, None);
+        assert_eq!(text.kludges[0].value, "2:5030/7 2:5020/1");
+        assert_eq!(text.kludges[1].kludge_type, "TOPT");
+        assert!(text.msgid.as_deref().unwrap().starts_with("2:5020/1 "));
+
+        // Crash and hold mail goes straight to the destination
+        let crash = packet_path(&config.outbound, 2, far, Flavour::Crash);
+        let packet = Packet::parse(&tokio::fs::read(&crash).await.unwrap()).unwrap();
+        assert_ne!(
+            packet.messages[0].attribute & netmail::packed_attributes::CRASH,
+            0
+        );
+
+        let hold = packet_path(&config.outbound, 2, far, Flavour::Hold);
+        let packet = Packet::parse(&tokio::fs::read(&hold).await.unwrap()).unwrap();
+        assert_eq!(packet.messages[0].subject, "report.zip");
+        let stored = base.read_jam_message(3).await.unwrap();
+        assert_eq!(stored.subject, "Files");
+        assert_eq!(stored.attachments, [attach.to_string_lossy()]);
+        let flow = hold.with_extension(Flavour::Hold.flow_extension());
+        assert_eq!(
+            tokio::fs::read_to_string(flow).await.unwrap(),
+            format!("{}\r\n", attach.display())
+        );
+
+        // Mail to ourselves is left alone
+        let own = base.read_jam_message(4).await.unwrap();
+        assert!(!own.attributes().has(MessageAttributes::SENT));
+    }
+}
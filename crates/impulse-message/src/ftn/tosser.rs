@@ -0,0 +1,642 @@
+//! Inbound packet tossing
+//!
+//! Unpacks `.pkt` files and ARCmail bundles from the inbound directory into
+//! the JAM base of each message's area, skipping duplicates by MSGID and
+//! forwarding echomail to the area's other links. Echomail from a system that
+//! is not linked to its area is bad mail. NetMail for one of our addresses
+//! goes to the NetMail base.
+
+use super::config::FtnConfig;
+use super::dupes::{DEFAULT_DUPE_HISTORY, DupeDatabase};
+use super::echomail::EchoText;
+use super::error::{FtnError, Result};
+use super::netmail;
+use super::outbound::Outbound;
+use super::packet::{PackedMessage, Packet};
+use crate::addressing::FidoAddress;
+use crate::formats::jam::{JamMessage, JamMessageBase, MessageAttributes};
+use crate::types::KludgeLine;
+use chrono::Utc;
+use std::io::Read;
+use std::ops::AddAssign;
+use std::path::{Path, PathBuf};
+
+/// ARCmail bundle extensions, without the trailing sequence character
+const BUNDLE_DAYS: [&str; 7] = ["mo", "tu", "we", "th", "fr", "sa", "su"];
+
+/// Counts from a toss run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TossStats {
+    /// Packets tossed
+    pub packets: usize,
+    /// Messages stored in echomail areas
+    pub messages: usize,
+    /// Messages skipped as duplicates
+    pub duplicates: usize,
+    /// Netmail messages found
+    pub netmail: usize,
+    /// Messages for areas that are not configured
+    pub unknown_area: usize,
+    /// Echomail from systems that are not links of its area
+    pub unlinked: usize,
+    /// Copies queued for other links
+    pub forwarded: usize,
+    /// Inbound files renamed to `.bad`
+    pub bad_files: Vec<PathBuf>,
+}
+
+impl AddAssign for TossStats {
+    fn add_assign(&mut self, other: Self) {
+        self.packets += other.packets;
+        self.messages += other.messages;
+        self.duplicates += other.duplicates;
+        self.netmail += other.netmail;
+        self.unknown_area += other.unknown_area;
+        self.unlinked += other.unlinked;
+        self.forwarded += other.forwarded;
+        self.bad_files.extend(other.bad_files);
+    }
+}
+
+/// Inbound packet tosser
+pub struct Tosser {
+    /// Tosser configuration
+    config: FtnConfig,
+    /// MSGID history
+    dupes: DupeDatabase,
+}
+
+impl Tosser {
+    /// Create a tosser, loading the MSGID history if one is configured
+    pub async fn new(config: FtnConfig) -> Result<Self> {
+        let dupes = match &config.dupe_file {
+            Some(path) => DupeDatabase::load(path, DEFAULT_DUPE_HISTORY).await?,
+            None => DupeDatabase::new(DEFAULT_DUPE_HISTORY),
+        };
+        Ok(Self { config, dupes })
+    }
+
+    /// Toss every packet and bundle in the inbound directory
+    ///
+    /// Files are deleted once tossed. Files that cannot be read as packets,
+    /// or whose password does not match, are renamed to `.bad` and left for
+    /// the sysop. The MSGID history is saved after every file, so mail from a
+    /// file that was tossed, even in part, is known on the next run.
+    pub async fn toss(&mut self) -> Result<TossStats> {
+        let mut stats = TossStats::default();
+        let mut files = Vec::new();
+
+        let mut entries = match tokio::fs::read_dir(&self.config.inbound).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(stats),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if entry.file_type().await?.is_file() && (is_packet(&path) || is_bundle(&path)) {
+                files.push(path);
+            }
+        }
+        files.sort();
+
+        for path in files {
+            let result = if is_packet(&path) {
+                let data = tokio::fs::read(&path).await?;
+                self.toss_packet(&data).await
+            } else {
+                self.toss_bundle(&path).await
+            };
+            self.dupes.save().await?;
+
+            match result {
+                Ok(tossed) => {
+                    stats += tossed;
+                    tokio::fs::remove_file(&path).await?;
+                }
+                Err(FtnError::InvalidPacket(_) | FtnError::BadPassword(_) | FtnError::Zip(_)) => {
+                    let bad = path.with_extension("bad");
+                    tokio::fs::rename(&path, &bad).await?;
+                    stats.bad_files.push(bad);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Toss every packet inside an ARCmail bundle
+    pub async fn toss_bundle(&mut self, path: &Path) -> Result<TossStats> {
+        let data = tokio::fs::read(path).await?;
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(data))?;
+
+        let mut packets = Vec::new();
+        for index in 0..archive.len() {
+            let mut file = archive.by_index(index)?;
+            if !is_packet(Path::new(file.name())) {
+                continue;
+            }
+            let mut packet = Vec::new();
+            file.read_to_end(&mut packet)?;
+            packets.push(packet);
+        }
+
+        // Check every packet before tossing any, so a bad bundle is left whole
+        for packet in &packets {
+            self.check_packet(&Packet::parse(packet)?)?;
+        }
+
+        let mut stats = TossStats::default();
+        for packet in packets {
+            stats += self.toss_packet(&packet).await?;
+        }
+        Ok(stats)
+    }
+
+    /// Toss a single packet
+    pub async fn toss_packet(&mut self, data: &[u8]) -> Result<TossStats> {
+        let packet = Packet::parse(data)?;
+        let orig = self.check_packet(&packet)?;
+
+        let mut stats = TossStats {
+            packets: 1,
+            ..TossStats::default()
+        };
+        let mut outbound = Outbound::new(&self.config);
+        let mut tossed = Ok(());
+        for message in &packet.messages {
+            tossed = self
+                .toss_message(message, orig, &mut outbound, &mut stats)
+                .await;
+            if tossed.is_err() {
+                break;
+            }
+        }
+
+        // Copies of the messages stored go out even if a later one failed
+        outbound.flush().await?;
+        tossed.map(|()| stats)
+    }
+
+    /// Toss one message of a packet from `orig`, queueing copies for links
+    async fn toss_message(
+        &mut self,
+        message: &PackedMessage,
+        orig: FidoAddress,
+        outbound: &mut Outbound,
+        stats: &mut TossStats,
+    ) -> Result<()> {
+        let mut echo = EchoText::parse(&message.text);
+        let local = self.config.address();
+
+        let Some(tag) = echo.area.clone() else {
+            stats.netmail += 1;
+            self.store_netmail(message, &echo, orig).await?;
+            return Ok(());
+        };
+
+        let Some(area) = self.config.area(&tag) else {
+            stats.unknown_area += 1;
+            self.store_bad(message, &echo, Some(&tag)).await?;
+            return Ok(());
+        };
+
+        // Only the area's links may feed it
+        if !area.links.contains(&orig) {
+            stats.unlinked += 1;
+            self.store_bad(message, &echo, Some(&tag)).await?;
+            return Ok(());
+        }
+
+        let key = echo
+            .msgid
+            .as_ref()
+            .map(|msgid| DupeDatabase::key(&area.tag, msgid));
+        if key.as_ref().is_some_and(|key| self.dupes.contains(key)) {
+            stats.duplicates += 1;
+            return Ok(());
+        }
+
+        let base = JamMessageBase::new(&area.base_path);
+        base.create_if_missing().await?;
+        base.add_message(&jam_message(message, &echo, orig)).await?;
+        // Only a message that was stored counts as seen
+        if let Some(key) = key {
+            self.dupes.remember(key);
+        }
+        stats.messages += 1;
+
+        // Pass the message on to links that have not seen it
+        let seen = super::echomail::parse_net_nodes(&echo.seen_by);
+        let targets: Vec<FidoAddress> = area
+            .links
+            .iter()
+            .copied()
+            .filter(|link| *link != orig && !seen.contains(&(link.net, link.node)))
+            .collect();
+        if !targets.is_empty() {
+            echo.add_seen_by(area.links.iter().copied().chain([local]));
+            echo.add_path(local);
+            let forward = message.clone().with_text(echo.to_text());
+            stats.forwarded += outbound.queue_for_links(&targets, &forward);
+        }
+        Ok(())
+    }
+
+    /// Check a packet's password against its link, returning its sender
+    ///
+    /// Packets without zones are taken to be from our own zone.
+    fn check_packet(&self, packet: &Packet) -> Result<FidoAddress> {
+        let mut orig = packet.header.orig;
+        if orig.zone == 0 {
+            orig.zone = self.config.address().zone;
+        }
+
+        if let Some(expected) = self
+            .config
+            .link(orig)
+            .and_then(|link| link.password.as_deref())
+            && !expected.eq_ignore_ascii_case(&packet.header.password)
+        {
+            return Err(FtnError::BadPassword(orig));
+        }
+
+        Ok(orig)
+    }
+
+    /// Keep NetMail for one of our addresses in the NetMail base
+    ///
+    /// Other NetMail, and all of it if there is no NetMail base, goes to the
+    /// bad area.
+    async fn store_netmail(
+        &self,
+        message: &PackedMessage,
+        echo: &EchoText,
+        orig: FidoAddress,
+    ) -> Result<()> {
+        let fallback = FidoAddress::node(orig.zone, message.dest_net, message.dest_node);
+        let destination = netmail::kludge_destination(&echo.kludges, fallback);
+        let Some(base_path) = self
+            .config
+            .netmail
+            .as_ref()
+            .filter(|_| self.config.addresses().contains(&destination))
+        else {
+            return self.store_bad(message, echo, None).await;
+        };
+
+        let from = echo.msgid_address().unwrap_or(FidoAddress::node(
+            orig.zone,
+            message.orig_net,
+            message.orig_node,
+        ));
+        let mut jam = jam_message(message, echo, orig);
+        jam.attributes |= MessageAttributes::PRIVATE;
+        jam.from_address = Some(netmail::kludge_origin(&echo.kludges, from).to_string());
+        jam.to_address = Some(destination.to_string());
+
+        let base = JamMessageBase::new(base_path);
+        base.create_if_missing().await?;
+        base.add_message(&jam).await?;
+        Ok(())
+    }
+
+    /// Keep a message that has no area here in the bad area, if configured
+    async fn store_bad(
+        &self,
+        message: &PackedMessage,
+        echo: &EchoText,
+        tag: Option<&str>,
+    ) -> Result<()> {
+        let Some(bad_area) = &self.config.bad_area else {
+            return Ok(());
+        };
+
+        let mut jam = jam_message(message, echo, self.config.address());
+        if let Some(tag) = tag {
+            jam.kludges.insert(
+                0,
+                KludgeLine {
+                    kludge_type: "AREA".to_string(),
+                    value: tag.to_string(),
+                },
+            );
+        }
+
+        let base = JamMessageBase::new(bad_area);
+        base.create_if_missing().await?;
+        base.add_message(&jam).await?;
+        Ok(())
+    }
+}
+
+/// Build the JAM message for a tossed message
+fn jam_message(message: &PackedMessage, echo: &EchoText, orig: FidoAddress) -> JamMessage {
+    let mut jam =
+        JamMessage::new(&message.from, &message.to, &message.subject).with_body(echo.body.clone());
+    jam.date_written = message
+        .date()
+        .map(|date| date.and_utc())
+        .unwrap_or_else(Utc::now);
+    jam.from_address = Some(
+        echo.origin_address()
+            .or_else(|| echo.msgid_address())
+            .unwrap_or(orig)
+            .to_string(),
+    );
+    jam.msgid = echo.msgid.clone();
+    jam.reply_id = echo.reply_id.clone();
+    jam.seen_by = echo.seen_by.clone();
+    jam.path = echo.path.clone();
+    jam.kludges = echo.kludges.clone();
+    jam
+}
+
+/// Whether a file name is a packet
+fn is_packet(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pkt"))
+}
+
+/// Whether a file name is an ARCmail bundle (`*.mo0` to `*.su9`)
+fn is_bundle(path: &Path) -> bool {
+    let Some(ext) = path.extension().and_then(|ext| ext.to_str()) else {
+        return false;
+    };
+    let ext = ext.to_ascii_lowercase();
+    ext.len() == 3
+        && ext.is_char_boundary(2)
+        && BUNDLE_DAYS.contains(&&ext[..2])
+        && ext.as_bytes()[2].is_ascii_alphanumeric()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::jam::MessageAttributes;
+    use crate::ftn::config::{EchoArea, FtnLink};
+    use crate::ftn::outbound::{Flavour, packet_path};
+    use crate::ftn::packet::PacketHeader;
+    use crate::routing::RouterConfig;
+    use crate::traits::MessageBase;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    const LOCAL: FidoAddress = FidoAddress {
+        zone: 2,
+        net: 5020,
+        node: 1,
+        point: 0,
+    };
+    const UPLINK: FidoAddress = FidoAddress {
+        zone: 2,
+        net: 5020,
+        node: 9,
+        point: 0,
+    };
+    const DOWNLINK: FidoAddress = FidoAddress {
+        zone: 2,
+        net: 5020,
+        node: 20,
+        point: 0,
+    };
+
+    fn config(dir: &Path) -> FtnConfig {
+        FtnConfig::new(
+            RouterConfig::new(LOCAL),
+            dir.join("inbound"),
+            dir.join("outbound"),
+        )
+        .with_area(
+            EchoArea::new("TEST", dir.join("msg/test"))
+                .with_link(UPLINK)
+                .with_link(DOWNLINK),
+        )
+        .with_link(FtnLink::new(UPLINK).with_password("UPPW"))
+        .with_dupe_file(dir.join("dupes.dat"))
+        .with_bad_area(dir.join("msg/badmail"))
+    }
+
+    fn packet(password: &str, texts: &[&str]) -> Vec<u8> {
+        let mut packet = Packet::new(PacketHeader::new(UPLINK, LOCAL).with_password(password));
+        for text in texts {
+            packet
+                .messages
+                .push(PackedMessage::new(UPLINK, LOCAL, "Alice", "All", "Hello").with_text(*text));
+        }
+        packet.to_bytes()
+    }
+
+    const ECHO: &str = "AREA:TEST\r\x01MSGID: 2:5020/9 00000001\rHello echo\r--- \r \
+                        * Origin: Uplink (2:5020/9)\rSEEN-BY: 5020/9\r\x01PATH: 5020/9\r";
+
+    #[tokio::test]
+    async fn test_toss_packet_into_area() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = config(temp_dir.path());
+        let mut tosser = Tosser::new(config.clone()).await.unwrap();
+
+        let stats = tosser
+            .toss_packet(&packet("uppw", &[ECHO, ECHO]))
+            .await
+            .unwrap();
+        assert_eq!(stats.messages, 1);
+        assert_eq!(stats.duplicates, 1);
+        assert_eq!(stats.forwarded, 1);
+
+        let base = JamMessageBase::new(temp_dir.path().join("msg/test"));
+        let message = base.read_jam_message(1).await.unwrap();
+        assert_eq!(message.from, "Alice");
+        assert_eq!(message.from_address.as_deref(), Some("2:5020/9"));
+        assert_eq!(message.msgid.as_deref(), Some("2:5020/9 00000001"));
+        assert_eq!(message.seen_by, vec!["5020/9"]);
+        assert!(!message.attributes().has(MessageAttributes::LOCAL));
+        assert_eq!(base.read_message(1).await.unwrap().body, "Hello echo");
+
+        // The downlink gets a copy with us in SEEN-BY and PATH
+        let out = packet_path(&config.outbound, 2, DOWNLINK, Flavour::Normal);
+        let forwarded = Packet::parse(&tokio::fs::read(out).await.unwrap()).unwrap();
+        let echo = EchoText::parse(&forwarded.messages[0].text);
+        assert_eq!(echo.seen_by, vec!["5020/1 9 20"]);
+        assert_eq!(echo.path, vec!["5020/9 1"]);
+        assert_eq!(forwarded.messages[0].orig_node, 1);
+    }
+
+    #[tokio::test]
+    async fn test_bad_password_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut tosser = Tosser::new(config(temp_dir.path())).await.unwrap();
+        assert!(matches!(
+            tosser.toss_packet(&packet("WRONG", &[ECHO])).await,
+            Err(FtnError::BadPassword(UPLINK))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_netmail_and_unknown_areas_go_to_bad_area() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut tosser = Tosser::new(config(temp_dir.path())).await.unwrap();
+
+        let stats = tosser
+            .toss_packet(&packet("UPPW", &["Hi sysop\r", "AREA:ELSEWHERE\rHi\r"]))
+            .await
+            .unwrap();
+        assert_eq!(stats.netmail, 1);
+        assert_eq!(stats.unknown_area, 1);
+
+        let bad = JamMessageBase::new(temp_dir.path().join("msg/badmail"));
+        assert_eq!(bad.message_count().await.unwrap(), 2);
+        let message = bad.read_jam_message(2).await.unwrap();
+        assert_eq!(message.kludges[0].value, "ELSEWHERE");
+    }
+
+    #[tokio::test]
+    async fn test_echomail_from_unlinked_system_goes_to_bad_area() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = config(temp_dir.path()).with_area(
+            EchoArea::new("PRIVATE", temp_dir.path().join("msg/private")).with_link(DOWNLINK),
+        );
+        let mut tosser = Tosser::new(config.clone()).await.unwrap();
+
+        // The uplink feeds TEST but is not a link of PRIVATE
+        let private = ECHO.replace("AREA:TEST", "AREA:PRIVATE");
+        let stats = tosser
+            .toss_packet(&packet("UPPW", &[&private]))
+            .await
+            .unwrap();
+        assert_eq!(stats.unlinked, 1);
+        assert_eq!(stats.messages, 0);
+        assert_eq!(stats.forwarded, 0);
+        assert!(!temp_dir.path().join("msg/private.jhr").exists());
+
+        let bad = JamMessageBase::new(temp_dir.path().join("msg/badmail"));
+        let message = bad.read_jam_message(1).await.unwrap();
+        assert_eq!(message.kludges[0].value, "PRIVATE");
+
+        // It still feeds its own area
+        let stats = tosser.toss_packet(&packet("UPPW", &[ECHO])).await.unwrap();
+        assert_eq!(stats.messages, 1);
+    }
+
+    #[tokio::test]
+    async fn test_netmail_for_us_goes_to_netmail_base() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = config(temp_dir.path()).with_netmail(temp_dir.path().join("msg/netmail"));
+        let mut tosser = Tosser::new(config).await.unwrap();
+
+        let for_us = "\x01INTL 2:5020/1 1:234/5\r\x01FMPT 3\rHi sysop\r";
+        let passing = "\x01INTL 2:5030/7 1:234/5\rNot for you\r";
+        let stats = tosser
+            .toss_packet(&packet("UPPW", &[for_us, passing]))
+            .await
+            .unwrap();
+        assert_eq!(stats.netmail, 2);
+
+        let netmail = JamMessageBase::new(temp_dir.path().join("msg/netmail"));
+        assert_eq!(netmail.message_count().await.unwrap(), 1);
+        let message = netmail.read_jam_message(1).await.unwrap();
+        assert_eq!(message.from_address.as_deref(), Some("1:234/5.3"));
+        assert_eq!(message.to_address.as_deref(), Some("2:5020/1"));
+        assert!(message.attributes().is_private());
+        assert!(!message.attributes().is_local());
+
+        let bad = JamMessageBase::new(temp_dir.path().join("msg/badmail"));
+        assert_eq!(bad.message_count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_toss_inbound_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = config(temp_dir.path());
+        tokio::fs::create_dir_all(&config.inbound).await.unwrap();
+
+        tokio::fs::write(config.inbound.join("0001.pkt"), packet("UPPW", &[ECHO]))
+            .await
+            .unwrap();
+        tokio::fs::write(config.inbound.join("0002.PKT"), b"garbage")
+            .await
+            .unwrap();
+
+        // A bundle holding a second message
+        let second = ECHO.replace("00000001", "00000002");
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        zip.start_file("0003.pkt", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(&packet("UPPW", &[&second])).unwrap();
+        let bundle = zip.finish().unwrap().into_inner();
+        tokio::fs::write(config.inbound.join("00000009.mo0"), bundle)
+            .await
+            .unwrap();
+
+        let mut tosser = Tosser::new(config.clone()).await.unwrap();
+        let stats = tosser.toss().await.unwrap();
+        assert_eq!(stats.packets, 2);
+        assert_eq!(stats.messages, 2);
+        assert_eq!(stats.bad_files, vec![config.inbound.join("0002.bad")]);
+        assert!(!config.inbound.join("0001.pkt").exists());
+        assert!(!config.inbound.join("00000009.mo0").exists());
+
+        // The history survives for the next run
+        let mut tosser = Tosser::new(config.clone()).await.unwrap();
+        let stats = tosser.toss_packet(&packet("UPPW", &[ECHO])).await.unwrap();
+        assert_eq!(stats.duplicates, 1);
+    }
+
+    #[tokio::test]
+    async fn test_history_is_saved_when_tossing_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        // BROKEN's base cannot be created: its directory is a file
+        tokio::fs::write(temp_dir.path().join("blocked"), b"")
+            .await
+            .unwrap();
+        let config = config(temp_dir.path()).with_area(
+            EchoArea::new("BROKEN", temp_dir.path().join("blocked/broken")).with_link(UPLINK),
+        );
+        tokio::fs::create_dir_all(&config.inbound).await.unwrap();
+
+        let second = ECHO.replace("00000001", "00000002");
+        let broken = ECHO
+            .replace("AREA:TEST", "AREA:BROKEN")
+            .replace("00000001", "00000003");
+        tokio::fs::write(config.inbound.join("0001.pkt"), packet("UPPW", &[ECHO]))
+            .await
+            .unwrap();
+        tokio::fs::write(
+            config.inbound.join("0002.pkt"),
+            packet("UPPW", &[&second, &broken]),
+        )
+        .await
+        .unwrap();
+
+        let mut tosser = Tosser::new(config.clone()).await.unwrap();
+        assert!(tosser.toss().await.is_err());
+        assert!(!config.inbound.join("0001.pkt").exists());
+        assert!(config.inbound.join("0002.pkt").exists());
+
+        // Neither the tossed packet nor the first half of the failed one is
+        // imported again
+        let mut tosser = Tosser::new(config.clone()).await.unwrap();
+        let stats = tosser
+            .toss_packet(&packet("UPPW", &[ECHO, &second]))
+            .await
+            .unwrap();
+        assert_eq!(stats.duplicates, 2);
+        assert_eq!(stats.messages, 0);
+
+        // The message that failed is not taken as seen
+        let history = tokio::fs::read_to_string(temp_dir.path().join("dupes.dat"))
+            .await
+            .unwrap();
+        assert!(history.contains("00000002"));
+        assert!(!history.contains("00000003"));
+    }
+
+    #[test]
+    fn test_inbound_file_kinds() {
+        assert!(is_packet(Path::new("a.PKT")));
+        assert!(is_bundle(Path::new("0000fff1.we3")));
+        assert!(is_bundle(Path::new("0000FFF1.SUA")));
+        assert!(!is_bundle(Path::new("readme.txt")));
+        assert!(!is_bundle(Path::new("a.mo")));
+    }
+}
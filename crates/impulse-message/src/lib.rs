@@ -19,6 +19,7 @@
 //! - **QWK Support**: Generate QWK offline mail packets and parse reply packets
 //! - **FidoNet Addressing**: Full FidoNet address parsing (zone:net/node.point)
 //! - **Message Routing**: Intelligent routing decisions for networked messages
+//! - **Echomail**: Toss and scan FidoNet Type-2+ packets for JAM echo areas
 //! - **Import/Export**: Export messages to text/JSON/CSV, import from text/JSON
 //! - **Async**: Fully async API using tokio
 //!
@@ -135,6 +136,9 @@ pub mod traits;
 /// Message format implementations
 pub mod formats;
 
+/// Message conferences and areas
+pub mod areas;
+
 /// UI screens
 pub mod screens;
 
@@ -162,6 +166,9 @@ pub mod addressing;
 /// Message routing
 pub mod routing;
 
+/// FidoNet packet tossing and scanning
+pub mod ftn;
+
 /// Message export
 pub mod export;
 
@@ -169,6 +176,7 @@ pub mod export;
 pub mod import;
 
 // Re-export commonly used types
+pub use areas::{Conference, MessageArea, MessageAreaRegistry};
 pub use error::{MessageError, Result};
 pub use reply::ReplyBuilder;
 pub use sanitize::MessageSanitizer;
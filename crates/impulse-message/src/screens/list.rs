@@ -57,7 +57,7 @@ impl MessageListScreen {
     }
 
     /// Load a page of messages
-    pub async fn load_page<M: MessageBase>(&mut self, base: &M, page: u32) -> Result<()> {
+    pub async fn load_page<M: MessageBase + ?Sized>(&mut self, base: &M, page: u32) -> Result<()> {
         let start = page * self.config.messages_per_page + 1;
         self.messages = base
             .list_messages(start, self.config.messages_per_page)
@@ -68,7 +68,7 @@ impl MessageListScreen {
     }
 
     /// Search and display results
-    pub async fn search<M: MessageBase>(
+    pub async fn search<M: MessageBase + ?Sized>(
         &mut self,
         base: &M,
         criteria: &SearchCriteria,
@@ -77,7 +77,7 @@ impl MessageListScreen {
     }
 
     /// Get next page
-    pub async fn next_page<M: MessageBase>(&mut self, base: &M) -> Result<bool> {
+    pub async fn next_page<M: MessageBase + ?Sized>(&mut self, base: &M) -> Result<bool> {
         let max_page = self.max_page();
         if self.current_page < max_page {
             self.load_page(base, self.current_page + 1).await?;
@@ -88,7 +88,7 @@ impl MessageListScreen {
     }
 
     /// Get previous page
-    pub async fn prev_page<M: MessageBase>(&mut self, base: &M) -> Result<bool> {
+    pub async fn prev_page<M: MessageBase + ?Sized>(&mut self, base: &M) -> Result<bool> {
         if self.current_page > 0 {
             self.load_page(base, self.current_page - 1).await?;
             Ok(true)
@@ -98,7 +98,11 @@ impl MessageListScreen {
     }
 
     /// Jump to specific page
-    pub async fn goto_page<M: MessageBase>(&mut self, base: &M, page: u32) -> Result<bool> {
+    pub async fn goto_page<M: MessageBase + ?Sized>(
+        &mut self,
+        base: &M,
+        page: u32,
+    ) -> Result<bool> {
         let max_page = self.max_page();
         if page <= max_page {
             self.load_page(base, page).await?;
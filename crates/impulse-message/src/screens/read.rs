@@ -54,7 +54,11 @@ impl MessageReadScreen {
     }
 
     /// Load a message
-    pub async fn load_message<M: MessageBase>(&mut self, base: &M, msg_num: u32) -> Result<()> {
+    pub async fn load_message<M: MessageBase + ?Sized>(
+        &mut self,
+        base: &M,
+        msg_num: u32,
+    ) -> Result<()> {
         self.message = Some(base.read_message(msg_num).await?);
         if self.config.show_thread {
             self.thread = Some(base.get_thread(msg_num).await?);
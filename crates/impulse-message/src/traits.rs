@@ -64,6 +64,24 @@ pub trait MessageBase: Send + Sync {
     /// * `msg_num` - The message number
     async fn mark_read(&mut self, msg_num: u32) -> Result<()>;
 
+    /// Get the last message a user has read
+    ///
+    /// # Arguments
+    /// * `user_name` - The user's name
+    /// * `user_id` - The user's numeric ID
+    ///
+    /// # Returns
+    /// The message number, or 0 if the user has not read anything yet
+    async fn last_read(&self, user_name: &str, user_id: u32) -> Result<u32>;
+
+    /// Record the last message a user has read
+    ///
+    /// # Arguments
+    /// * `user_name` - The user's name
+    /// * `user_id` - The user's numeric ID
+    /// * `msg_num` - The message number
+    async fn set_last_read(&mut self, user_name: &str, user_id: u32, msg_num: u32) -> Result<()>;
+
     /// Check if a message exists
     ///
     /// # Arguments
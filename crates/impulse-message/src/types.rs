@@ -55,7 +55,7 @@ pub struct MessageThread {
 }
 
 /// Kludge line (control information in message)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct KludgeLine {
     /// Kludge type (e.g., "MSGID", "REPLY", "SEEN-BY")
     pub kludge_type: String,
@@ -0,0 +1,380 @@
+//! Async Xmodem receiver for network connections.
+//!
+//! The counterpart of [`AsyncXmodemSender`](super::AsyncXmodemSender):
+//! receives a file from a caller's connection and writes it to any
+//! [`AsyncWrite`]. The block-level operations are shared with the Ymodem
+//! receiver.
+
+use super::async_send::HANDSHAKE_TIMEOUT;
+use super::error::{Result, XmodemError};
+use super::receive::{ReceiveStats, ReceiverConfig};
+use super::{ACK, CAN, CRC_MODE, EOT, NAK, SOH, STX, SUB, XmodemBlock, XmodemVariant};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time::timeout;
+
+/// How often the start request is repeated until the sender answers.
+const REQUEST_INTERVAL: Duration = Duration::from_secs(3);
+
+/// How long the line must stay quiet before a NAK after a damaged block.
+const PURGE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// What the sender sent in place of the next block.
+pub(crate) enum Packet {
+    /// A block that passed its checksum or CRC
+    Block(XmodemBlock),
+    /// End of transmission
+    Eot,
+}
+
+/// Async Xmodem file receiver.
+///
+/// Asks for CRC mode with 'C' when the configured variant uses a CRC and for
+/// checksum mode with NAK otherwise; 1K blocks are accepted either way. The
+/// SUB padding of the final block is removed, since Xmodem does not send the
+/// file size.
+///
+/// # Examples
+///
+/// ```no_run
+/// use impulse_protocol::xmodem::{AsyncXmodemReceiver, ReceiverConfig};
+///
+/// # async fn example() -> impulse_protocol::xmodem::Result<()> {
+/// let stream = tokio::net::TcpStream::connect("127.0.0.1:2323").await?;
+/// let mut receiver = AsyncXmodemReceiver::new(stream, ReceiverConfig::default());
+///
+/// let mut file = tokio::fs::File::create("upload.bin").await?;
+/// let stats = receiver
+///     .receive_file(&mut file, |received| println!("{received} bytes"))
+///     .await?;
+/// println!("Received {} blocks", stats.blocks_received);
+/// # Ok(())
+/// # }
+/// ```
+pub struct AsyncXmodemReceiver<S> {
+    stream: S,
+    config: ReceiverConfig,
+    use_crc: bool,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncXmodemReceiver<S> {
+    /// Create a new async Xmodem receiver.
+    pub fn new(stream: S, config: ReceiverConfig) -> Self {
+        let use_crc = config.variant.uses_crc();
+        Self {
+            stream,
+            config,
+            use_crc,
+        }
+    }
+
+    /// Receive a complete file into `writer`.
+    ///
+    /// `progress` is called with the number of file bytes received so far
+    /// after every block.
+    pub async fn receive_file<W: AsyncWrite + Unpin>(
+        &mut self,
+        writer: &mut W,
+        mut progress: impl FnMut(u64),
+    ) -> Result<ReceiveStats> {
+        let start_time = Instant::now();
+        let mut stats = ReceiveStats::default();
+
+        let request = if self.use_crc { CRC_MODE } else { NAK };
+        let first = self.request(request).await?;
+        self.receive_data(writer, first, None, false, &mut stats, &mut progress)
+            .await?;
+
+        stats.duration = start_time.elapsed();
+        Ok(stats)
+    }
+
+    /// Cancel the current transfer.
+    ///
+    /// Sends multiple CAN bytes to abort the transfer.
+    pub async fn cancel(&mut self) -> Result<()> {
+        self.stream.write_all(&[CAN, CAN, CAN, CAN, CAN]).await?;
+        self.stream.flush().await?;
+        Ok(())
+    }
+
+    /// Send `request` until the sender answers with a packet.
+    ///
+    /// 'C' and 'G' select CRC blocks, NAK selects checksum blocks. The
+    /// request is repeated every few seconds, and after a damaged packet,
+    /// for up to [`HANDSHAKE_TIMEOUT`].
+    pub(crate) async fn request(&mut self, request: u8) -> Result<Packet> {
+        self.use_crc = request != NAK;
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+
+        loop {
+            self.send(request).await?;
+            match self.read_packet(REQUEST_INTERVAL).await {
+                Ok(packet) => return Ok(packet),
+                Err(e) if is_line_error(&e) && Instant::now() < deadline => {
+                    self.purge().await?;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Receive numbered data blocks starting with `first`, up to EOT.
+    ///
+    /// With a known `size` the data is cut to it; without, trailing SUB
+    /// padding is removed from the final block. In streaming mode
+    /// (Ymodem-G) blocks are not acknowledged and any error ends the
+    /// transfer, since the sender cannot go back.
+    pub(crate) async fn receive_data<W: AsyncWrite + Unpin>(
+        &mut self,
+        writer: &mut W,
+        first: Packet,
+        size: Option<u64>,
+        streaming: bool,
+        stats: &mut ReceiveStats,
+        progress: &mut impl FnMut(u64),
+    ) -> Result<()> {
+        let mut packet = Ok(first);
+        let mut expected: u8 = 1;
+        let mut consecutive_errors = 0;
+        // Without a size the last block is held back until EOT shows that
+        // its padding can be removed
+        let mut held: Option<Vec<u8>> = None;
+
+        loop {
+            match packet {
+                Ok(Packet::Block(block)) if block.block_num == expected => {
+                    consecutive_errors = 0;
+                    let data = match size {
+                        Some(size) => {
+                            let remaining = size.saturating_sub(stats.bytes_received);
+                            let len = block.data.len().min(remaining as usize);
+                            Some(block.data[..len].to_vec())
+                        }
+                        None => held.replace(block.data),
+                    };
+                    if let Some(data) = data {
+                        writer.write_all(&data).await?;
+                        stats.bytes_received += data.len() as u64;
+                    }
+                    if !streaming {
+                        self.send(ACK).await?;
+                    }
+
+                    stats.blocks_received += 1;
+                    let pending = held.as_ref().map_or(0, |data| data.len() as u64);
+                    progress(stats.bytes_received + pending);
+                    expected = expected.wrapping_add(1);
+                }
+                Ok(Packet::Block(block)) if block.block_num == expected.wrapping_sub(1) => {
+                    // Our ACK was lost and the sender repeated the block
+                    stats.blocks_retried += 1;
+                    if !streaming {
+                        self.send(ACK).await?;
+                    }
+                }
+                Ok(Packet::Block(block)) => {
+                    return Err(XmodemError::InvalidBlockNumber {
+                        expected,
+                        actual: block.block_num,
+                    });
+                }
+                Ok(Packet::Eot) => {
+                    if let Some(mut data) = held.take() {
+                        let padding = data.iter().rev().take_while(|&&b| b == SUB).count();
+                        data.truncate(data.len() - padding);
+                        writer.write_all(&data).await?;
+                        stats.bytes_received += data.len() as u64;
+                    }
+                    writer.flush().await?;
+                    self.send(ACK).await?;
+                    return Ok(());
+                }
+                Err(e) if streaming || !is_line_error(&e) => return Err(e),
+                Err(_) => {
+                    consecutive_errors += 1;
+                    stats.errors += 1;
+                    if consecutive_errors >= self.config.max_retries {
+                        return Err(XmodemError::MaxRetriesExceeded {
+                            attempts: consecutive_errors,
+                        });
+                    }
+                    self.purge().await?;
+                    self.send(NAK).await?;
+                }
+            }
+
+            packet = self.read_packet(self.config.timeout).await;
+        }
+    }
+
+    /// Acknowledge a packet outside the data phase (Ymodem block 0).
+    pub(crate) async fn ack(&mut self) -> Result<()> {
+        self.send(ACK).await
+    }
+
+    /// Read the next block or EOT.
+    ///
+    /// Bytes before a block header are ignored as line noise.
+    async fn read_packet(&mut self, wait: Duration) -> Result<Packet> {
+        let deadline = Instant::now() + wait;
+
+        let header = loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.read_byte(remaining).await? {
+                header @ (SOH | STX) => break header,
+                EOT => return Ok(Packet::Eot),
+                CAN => return Err(XmodemError::Cancelled),
+                _ => continue,
+            }
+        };
+
+        let variant = XmodemVariant::from_header_byte(header, self.use_crc)?;
+        let mut packet = vec![0u8; variant.packet_size()];
+        packet[0] = header;
+        match timeout(
+            self.config.timeout,
+            self.stream.read_exact(&mut packet[1..]),
+        )
+        .await
+        {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                return Err(XmodemError::UnexpectedEof);
+            }
+            Ok(Err(e)) => return Err(e.into()),
+            Err(_) => return Err(XmodemError::Timeout),
+        }
+
+        // 1K blocks always carry a CRC
+        let use_crc = self.use_crc || variant == XmodemVariant::OneK;
+        XmodemBlock::deserialize(&packet, use_crc).map(Packet::Block)
+    }
+
+    /// Discard input until the line goes quiet.
+    async fn purge(&mut self) -> Result<()> {
+        loop {
+            match self.read_byte(PURGE_TIMEOUT).await {
+                Ok(_) => continue,
+                Err(XmodemError::Timeout) => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Send a single control byte.
+    async fn send(&mut self, byte: u8) -> Result<()> {
+        self.stream.write_all(&[byte]).await?;
+        self.stream.flush().await?;
+        Ok(())
+    }
+
+    /// Read one byte from the sender.
+    async fn read_byte(&mut self, wait: Duration) -> Result<u8> {
+        match timeout(wait, self.stream.read_u8()).await {
+            Ok(Ok(byte)) => Ok(byte),
+            Ok(Err(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                Err(XmodemError::UnexpectedEof)
+            }
+            Ok(Err(e)) => Err(e.into()),
+            Err(_) => Err(XmodemError::Timeout),
+        }
+    }
+}
+
+/// Errors the sender recovers from by resending the block.
+fn is_line_error(error: &XmodemError) -> bool {
+    matches!(
+        error,
+        XmodemError::Timeout
+            | XmodemError::ChecksumMismatch { .. }
+            | XmodemError::CrcMismatch { .. }
+            | XmodemError::ComplementMismatch { .. }
+            | XmodemError::InvalidBlockHeader(_)
+            | XmodemError::InvalidVariant(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xmodem::{AsyncXmodemSender, SenderConfig};
+    use tokio::io::duplex;
+
+    #[tokio::test]
+    async fn test_receive_file_from_sender() {
+        let (sender_end, receiver_end) = duplex(4096);
+        let payload: Vec<u8> = (0..300u32).map(|i| i as u8).collect();
+        let sent = payload.clone();
+        let sender = tokio::spawn(async move {
+            let config = SenderConfig {
+                variant: XmodemVariant::Crc,
+                ..Default::default()
+            };
+            AsyncXmodemSender::new(sender_end, config)
+                .send_file(&mut sent.as_slice(), |_| {})
+                .await
+                .unwrap()
+        });
+
+        let mut receiver = AsyncXmodemReceiver::new(receiver_end, ReceiverConfig::default());
+        let mut data = Vec::new();
+        let mut reported = Vec::new();
+        let stats = receiver
+            .receive_file(&mut data, |received| reported.push(received))
+            .await
+            .unwrap();
+
+        assert_eq!(sender.await.unwrap().blocks_sent, 3);
+        assert_eq!(stats.blocks_received, 3);
+        assert_eq!(stats.bytes_received, 300);
+        assert_eq!(reported, vec![128, 256, 384]);
+        assert_eq!(data, payload);
+    }
+
+    #[tokio::test]
+    async fn test_receive_file_naks_damaged_block() {
+        let (mut sender_end, receiver_end) = duplex(4096);
+        let sender = tokio::spawn(async move {
+            assert_eq!(sender_end.read_u8().await.unwrap(), NAK);
+
+            let first = XmodemBlock::new(1, vec![b'x'; 128], XmodemVariant::Checksum).unwrap();
+            sender_end.write_all(&first.serialize()).await.unwrap();
+            assert_eq!(sender_end.read_u8().await.unwrap(), ACK);
+
+            let block = XmodemBlock::new(2, vec![b'y'; 128], XmodemVariant::Checksum).unwrap();
+            let mut damaged = block.serialize();
+            damaged[10] ^= 0xFF;
+            sender_end.write_all(&damaged).await.unwrap();
+            assert_eq!(sender_end.read_u8().await.unwrap(), NAK);
+
+            sender_end.write_all(&block.serialize()).await.unwrap();
+            assert_eq!(sender_end.read_u8().await.unwrap(), ACK);
+            sender_end.write_all(&[EOT]).await.unwrap();
+            assert_eq!(sender_end.read_u8().await.unwrap(), ACK);
+        });
+
+        let config = ReceiverConfig {
+            variant: XmodemVariant::Checksum,
+            ..Default::default()
+        };
+        let mut receiver = AsyncXmodemReceiver::new(receiver_end, config);
+        let mut data = Vec::new();
+        let stats = receiver.receive_file(&mut data, |_| {}).await.unwrap();
+
+        sender.await.unwrap();
+        assert_eq!(stats.errors, 1);
+        assert_eq!(&data[..128], vec![b'x'; 128].as_slice());
+        assert_eq!(&data[128..], vec![b'y'; 128].as_slice());
+    }
+
+    #[tokio::test]
+    async fn test_receive_file_cancelled() {
+        let (mut sender_end, receiver_end) = duplex(4096);
+        sender_end.write_all(&[CAN, CAN]).await.unwrap();
+
+        let mut receiver = AsyncXmodemReceiver::new(receiver_end, ReceiverConfig::default());
+        let result = receiver.receive_file(&mut Vec::new(), |_| {}).await;
+        assert!(matches!(result, Err(XmodemError::Cancelled)));
+    }
+}
@@ -0,0 +1,339 @@
+//! Async Xmodem sender for network connections.
+//!
+//! [`XmodemSender`](super::XmodemSender) works on blocking readers and
+//! writers; this sender drives a caller's connection as an async stream and
+//! reads the file from any [`AsyncRead`]. The block-level operations are
+//! shared with the Ymodem sender.
+
+use super::error::{Result, XmodemError};
+use super::send::{SendStats, SenderConfig};
+use super::{ACK, CAN, CRC_MODE, EOT, NAK, SUB, XmodemBlock, XmodemVariant};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time::timeout;
+
+/// How long to wait for the receiver to start the transfer.
+///
+/// Receivers repeat their start request every few seconds, but the caller
+/// may still be starting the download in their terminal program.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Async Xmodem file sender.
+///
+/// Sends a single file using whichever variant the receiver asks for: NAK
+/// selects checksum mode, 'C' selects CRC mode (with 1K blocks when the
+/// configured variant is [`XmodemVariant::OneK`]).
+///
+/// # Examples
+///
+/// ```no_run
+/// use impulse_protocol::xmodem::{AsyncXmodemSender, SenderConfig, XmodemVariant};
+///
+/// # async fn example() -> impulse_protocol::xmodem::Result<()> {
+/// let stream = tokio::net::TcpStream::connect("127.0.0.1:2323").await?;
+/// let config = SenderConfig {
+///     variant: XmodemVariant::Crc,
+///     ..Default::default()
+/// };
+/// let mut sender = AsyncXmodemSender::new(stream, config);
+///
+/// let mut file = tokio::fs::File::open("test.txt").await?;
+/// let stats = sender
+///     .send_file(&mut file, |sent| println!("{sent} bytes"))
+///     .await?;
+/// println!("Sent {} blocks", stats.blocks_sent);
+/// # Ok(())
+/// # }
+/// ```
+pub struct AsyncXmodemSender<S> {
+    stream: S,
+    config: SenderConfig,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncXmodemSender<S> {
+    /// Create a new async Xmodem sender.
+    pub fn new(stream: S, config: SenderConfig) -> Self {
+        Self { stream, config }
+    }
+
+    /// Send a complete file.
+    ///
+    /// `progress` is called with the number of file bytes acknowledged so far
+    /// after every block.
+    pub async fn send_file<R: AsyncRead + Unpin>(
+        &mut self,
+        reader: &mut R,
+        mut progress: impl FnMut(u64),
+    ) -> Result<SendStats> {
+        let start_time = Instant::now();
+        let mut stats = SendStats::default();
+
+        let variant = match self.wait_for_request(&[NAK, CRC_MODE]).await? {
+            NAK => XmodemVariant::Checksum,
+            _ if self.config.variant == XmodemVariant::OneK => XmodemVariant::OneK,
+            _ => XmodemVariant::Crc,
+        };
+
+        self.send_data(reader, variant, false, &mut stats, &mut progress)
+            .await?;
+        self.send_eot().await?;
+
+        stats.duration = start_time.elapsed();
+        Ok(stats)
+    }
+
+    /// Cancel the current transfer.
+    ///
+    /// Sends multiple CAN bytes to abort the transfer.
+    pub async fn cancel(&mut self) -> Result<()> {
+        self.stream.write_all(&[CAN, CAN, CAN, CAN, CAN]).await?;
+        self.stream.flush().await?;
+        Ok(())
+    }
+
+    /// Wait for the receiver to send one of the `requests` bytes.
+    ///
+    /// Other bytes (line noise, stray keystrokes) are ignored.
+    pub(crate) async fn wait_for_request(&mut self, requests: &[u8]) -> Result<u8> {
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(XmodemError::Timeout);
+            }
+
+            match self.read_byte(remaining).await? {
+                CAN => return Err(XmodemError::Cancelled),
+                byte if requests.contains(&byte) => return Ok(byte),
+                _ => continue,
+            }
+        }
+    }
+
+    /// Send all remaining data from `reader` as numbered blocks.
+    ///
+    /// Block numbering starts at 1. In streaming mode (Ymodem-G) blocks are
+    /// not acknowledged individually.
+    pub(crate) async fn send_data<R: AsyncRead + Unpin>(
+        &mut self,
+        reader: &mut R,
+        variant: XmodemVariant,
+        streaming: bool,
+        stats: &mut SendStats,
+        progress: &mut impl FnMut(u64),
+    ) -> Result<()> {
+        let block_size = variant.block_size();
+        let mut block_num: u8 = 1;
+        let mut buffer = vec![0u8; block_size];
+
+        loop {
+            let bytes_read = read_block_data(reader, &mut buffer).await?;
+            if bytes_read == 0 {
+                break;
+            }
+
+            // Pad partial block with SUB
+            buffer[bytes_read..].fill(SUB);
+
+            let block = XmodemBlock::new(block_num, buffer.clone(), variant)?;
+            if streaming {
+                self.stream.write_all(&block.serialize()).await?;
+            } else {
+                stats.blocks_retried += self.send_block(&block).await?;
+            }
+
+            stats.blocks_sent += 1;
+            stats.bytes_sent += bytes_read as u64;
+            progress(stats.bytes_sent);
+
+            block_num = block_num.wrapping_add(1);
+        }
+
+        self.stream.flush().await?;
+        Ok(())
+    }
+
+    /// Send a single block and wait for it to be acknowledged.
+    ///
+    /// Returns the number of retransmissions needed.
+    pub(crate) async fn send_block(&mut self, block: &XmodemBlock) -> Result<usize> {
+        let packet = block.serialize();
+        let mut retries = 0;
+
+        loop {
+            self.stream.write_all(&packet).await?;
+            self.stream.flush().await?;
+
+            match self.wait_for_ack().await {
+                Ok(ACK) => return Ok(retries),
+                Ok(CAN) => return Err(XmodemError::Cancelled),
+                Ok(_) | Err(XmodemError::Timeout) => {
+                    retries += 1;
+                    if retries >= self.config.max_retries {
+                        return Err(XmodemError::MaxRetriesExceeded { attempts: retries });
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Send End-Of-Transmission and wait for it to be acknowledged.
+    ///
+    /// Many receivers NAK the first EOT to make sure it was not line noise.
+    pub(crate) async fn send_eot(&mut self) -> Result<()> {
+        let mut retries = 0;
+
+        loop {
+            self.stream.write_all(&[EOT]).await?;
+            self.stream.flush().await?;
+
+            match self.wait_for_ack().await {
+                Ok(ACK) => return Ok(()),
+                Ok(CAN) => return Err(XmodemError::Cancelled),
+                Ok(_) | Err(XmodemError::Timeout) => {
+                    retries += 1;
+                    if retries >= self.config.max_retries {
+                        return Err(XmodemError::MaxRetriesExceeded { attempts: retries });
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Wait for ACK, NAK, or CAN response.
+    async fn wait_for_ack(&mut self) -> Result<u8> {
+        let deadline = Instant::now() + self.config.timeout;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(XmodemError::Timeout);
+            }
+
+            match self.read_byte(remaining).await? {
+                byte @ (ACK | NAK | CAN) => return Ok(byte),
+                _ => continue,
+            }
+        }
+    }
+
+    /// Read one byte from the receiver.
+    async fn read_byte(&mut self, wait: Duration) -> Result<u8> {
+        match timeout(wait, self.stream.read_u8()).await {
+            Ok(Ok(byte)) => Ok(byte),
+            Ok(Err(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                Err(XmodemError::UnexpectedEof)
+            }
+            Ok(Err(e)) => Err(e.into()),
+            Err(_) => Err(XmodemError::Timeout),
+        }
+    }
+}
+
+/// Read a full block of data, stopping short only at end of file.
+async fn read_block_data<R: AsyncRead + Unpin>(reader: &mut R, buffer: &mut [u8]) -> Result<usize> {
+    let mut total_read = 0;
+    while total_read < buffer.len() {
+        match reader.read(&mut buffer[total_read..]).await? {
+            0 => break,
+            n => total_read += n,
+        }
+    }
+    Ok(total_read)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xmodem::{SOH, STX};
+    use tokio::io::{DuplexStream, duplex};
+
+    /// Minimal receiver: requests a transfer and ACKs every block.
+    async fn receive(mut stream: DuplexStream, request: u8, nak_first_block: bool) -> Vec<u8> {
+        stream.write_all(&[request]).await.unwrap();
+        let mut data = Vec::new();
+        let mut nak_pending = nak_first_block;
+
+        loop {
+            let header = stream.read_u8().await.unwrap();
+            let block_size = match header {
+                SOH => 128,
+                STX => 1024,
+                EOT => {
+                    stream.write_all(&[ACK]).await.unwrap();
+                    return data;
+                }
+                other => panic!("unexpected header {other:02x}"),
+            };
+            let trailer = if request == NAK { 1 } else { 2 };
+            let mut packet = vec![0u8; 2 + block_size + trailer];
+            stream.read_exact(&mut packet).await.unwrap();
+
+            if nak_pending {
+                nak_pending = false;
+                stream.write_all(&[NAK]).await.unwrap();
+                continue;
+            }
+            data.extend_from_slice(&packet[2..2 + block_size]);
+            stream.write_all(&[ACK]).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_file_crc() {
+        let (sender_end, receiver_end) = duplex(4096);
+        let receiver = tokio::spawn(receive(receiver_end, CRC_MODE, false));
+
+        let config = SenderConfig {
+            variant: XmodemVariant::Crc,
+            ..Default::default()
+        };
+        let mut sender = AsyncXmodemSender::new(sender_end, config);
+        let payload = vec![0x42u8; 300];
+        let mut reported = Vec::new();
+        let stats = sender
+            .send_file(&mut payload.as_slice(), |sent| reported.push(sent))
+            .await
+            .unwrap();
+
+        assert_eq!(stats.bytes_sent, 300);
+        assert_eq!(stats.blocks_sent, 3);
+        assert_eq!(reported, vec![128, 256, 300]);
+
+        let received = receiver.await.unwrap();
+        assert_eq!(received.len(), 384);
+        assert_eq!(&received[..300], payload.as_slice());
+        assert!(received[300..].iter().all(|&b| b == SUB));
+    }
+
+    #[tokio::test]
+    async fn test_send_file_checksum_with_retry() {
+        let (sender_end, receiver_end) = duplex(4096);
+        let receiver = tokio::spawn(receive(receiver_end, NAK, true));
+
+        let mut sender = AsyncXmodemSender::new(sender_end, SenderConfig::default());
+        let payload = b"Hello, Xmodem!".to_vec();
+        let stats = sender
+            .send_file(&mut payload.as_slice(), |_| {})
+            .await
+            .unwrap();
+
+        assert_eq!(stats.blocks_sent, 1);
+        assert_eq!(stats.blocks_retried, 1);
+        let received = receiver.await.unwrap();
+        assert_eq!(&received[..payload.len()], payload.as_slice());
+    }
+
+    #[tokio::test]
+    async fn test_send_file_cancelled() {
+        let (sender_end, mut receiver_end) = duplex(4096);
+        receiver_end.write_all(&[CAN]).await.unwrap();
+
+        let mut sender = AsyncXmodemSender::new(sender_end, SenderConfig::default());
+        let result = sender.send_file(&mut &b"data"[..], |_| {}).await;
+        assert!(matches!(result, Err(XmodemError::Cancelled)));
+    }
+}
@@ -60,6 +60,8 @@
 //! let crc_value = crc::calculate(data);
 //! ```
 
+pub mod async_receive;
+pub mod async_send;
 pub mod block;
 pub mod checksum;
 pub mod crc;
@@ -69,6 +71,8 @@ pub mod send;
 pub mod variants;
 
 // Re-export commonly used types
+pub use async_receive::AsyncXmodemReceiver;
+pub use async_send::AsyncXmodemSender;
 pub use block::XmodemBlock;
 pub use error::{Result, XmodemError};
 pub use receive::{ReceiveStats, ReceiverConfig, XmodemReceiver};
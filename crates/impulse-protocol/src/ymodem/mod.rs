@@ -48,11 +48,15 @@
 
 pub mod batch;
 pub mod metadata;
+pub mod receive;
+pub mod send;
 pub mod streaming;
 
 // Re-export commonly used types
 pub use batch::{BatchFile, YmodemBatch};
 pub use metadata::FileMetadata;
+pub use receive::YmodemReceiver;
+pub use send::YmodemSender;
 pub use streaming::{BatchStats, StreamingConfig, YmodemGReceiver, YmodemGSender};
 
 /// Ymodem always uses 1024-byte blocks (same as Xmodem-1K).
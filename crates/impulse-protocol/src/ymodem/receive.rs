@@ -0,0 +1,167 @@
+//! Async Ymodem batch receiver.
+//!
+//! Receives a batch one file at a time, writing each to any [`AsyncWrite`]
+//! as it arrives. Standard Ymodem is requested with 'C' and acknowledges
+//! every block; Ymodem-G is requested with 'G' and streams.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use impulse_protocol::xmodem::ReceiverConfig;
+//! use impulse_protocol::ymodem::YmodemReceiver;
+//!
+//! # async fn example() -> impulse_protocol::xmodem::Result<()> {
+//! let stream = tokio::net::TcpStream::connect("127.0.0.1:2323").await?;
+//! let mut receiver = YmodemReceiver::new(stream, ReceiverConfig::default(), false);
+//!
+//! while let Some(metadata) = receiver.next_file().await? {
+//!     let mut file = tokio::fs::File::create(&metadata.name).await?;
+//!     receiver.receive_file(&metadata, &mut file, |_| {}).await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use super::FileMetadata;
+use super::streaming::YMODEM_G;
+use crate::xmodem::async_receive::Packet;
+use crate::xmodem::{
+    AsyncXmodemReceiver, CRC_MODE, ReceiveStats, ReceiverConfig, Result, XmodemError,
+};
+use std::time::Instant;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// Async Ymodem / Ymodem-G receiver.
+pub struct YmodemReceiver<S> {
+    inner: AsyncXmodemReceiver<S>,
+    streaming: bool,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> YmodemReceiver<S> {
+    /// Create a new Ymodem receiver.
+    ///
+    /// With `streaming` the sender is asked for Ymodem-G.
+    pub fn new(stream: S, config: ReceiverConfig, streaming: bool) -> Self {
+        Self {
+            inner: AsyncXmodemReceiver::new(stream, config),
+            streaming,
+        }
+    }
+
+    /// Wait for the next file of the batch.
+    ///
+    /// Returns the file's block 0 metadata, or `None` once the sender ends
+    /// the batch.
+    pub async fn next_file(&mut self) -> Result<Option<FileMetadata>> {
+        loop {
+            match self.inner.request(self.request()).await? {
+                Packet::Block(block) if block.block_num == 0 => {
+                    let metadata =
+                        FileMetadata::decode(&block.data).map_err(|_| XmodemError::SyncError)?;
+                    self.inner.ack().await?;
+                    return Ok(metadata);
+                }
+                // The ACK for the previous file's EOT was lost
+                Packet::Eot => self.inner.ack().await?,
+                Packet::Block(block) => {
+                    return Err(XmodemError::InvalidBlockNumber {
+                        expected: 0,
+                        actual: block.block_num,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Receive the data of the file announced by [`next_file`](Self::next_file).
+    ///
+    /// The data is cut to the size from block 0 when the sender gave one.
+    /// `progress` is called with the number of file bytes received so far
+    /// after every block.
+    pub async fn receive_file<W: AsyncWrite + Unpin>(
+        &mut self,
+        metadata: &FileMetadata,
+        writer: &mut W,
+        mut progress: impl FnMut(u64),
+    ) -> Result<ReceiveStats> {
+        let start_time = Instant::now();
+        let mut stats = ReceiveStats::default();
+
+        // The request after block 0 starts the data blocks
+        let first = self.inner.request(self.request()).await?;
+        self.inner
+            .receive_data(
+                writer,
+                first,
+                metadata.size,
+                self.streaming,
+                &mut stats,
+                &mut progress,
+            )
+            .await?;
+
+        stats.duration = start_time.elapsed();
+        Ok(stats)
+    }
+
+    /// Cancel the current transfer.
+    pub async fn cancel(&mut self) -> Result<()> {
+        self.inner.cancel().await
+    }
+
+    fn request(&self) -> u8 {
+        if self.streaming { YMODEM_G } else { CRC_MODE }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xmodem::SenderConfig;
+    use crate::ymodem::YmodemSender;
+    use tokio::io::duplex;
+
+    async fn receive(streaming: bool) -> (FileMetadata, Vec<u8>) {
+        let (sender_end, receiver_end) = duplex(8192);
+        let sender = tokio::spawn(async move {
+            let mut sender = YmodemSender::new(sender_end, SenderConfig::default());
+            let payload = vec![0x5Au8; 1500];
+            let metadata = FileMetadata::with_size("test.bin", payload.len() as u64);
+            sender
+                .send_file(&metadata, &mut payload.as_slice(), |_| {})
+                .await
+                .unwrap();
+            sender.finish().await.unwrap();
+            sender.is_streaming()
+        });
+
+        let mut receiver = YmodemReceiver::new(receiver_end, ReceiverConfig::default(), streaming);
+        let metadata = receiver.next_file().await.unwrap().unwrap();
+        let mut data = Vec::new();
+        receiver
+            .receive_file(&metadata, &mut data, |_| {})
+            .await
+            .unwrap();
+        assert!(receiver.next_file().await.unwrap().is_none());
+
+        assert_eq!(sender.await.unwrap(), streaming);
+        (metadata, data)
+    }
+
+    #[tokio::test]
+    async fn test_receive_file_ymodem() {
+        let (metadata, data) = receive(false).await;
+
+        assert_eq!(metadata.name, "test.bin");
+        assert_eq!(metadata.size, Some(1500));
+        assert_eq!(data, vec![0x5Au8; 1500]);
+    }
+
+    #[tokio::test]
+    async fn test_receive_file_ymodem_g() {
+        let (metadata, data) = receive(true).await;
+
+        assert_eq!(metadata.size, Some(1500));
+        assert_eq!(data, vec![0x5Au8; 1500]);
+    }
+}
@@ -0,0 +1,198 @@
+//! Async Ymodem batch sender.
+//!
+//! Sends files one at a time from any [`AsyncRead`], so large files do not
+//! have to be held in memory. The receiver picks the mode: 'C' runs
+//! standard Ymodem with an ACK per block, 'G' streams the data blocks
+//! without waiting (Ymodem-G).
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use impulse_protocol::xmodem::SenderConfig;
+//! use impulse_protocol::ymodem::{FileMetadata, YmodemSender};
+//!
+//! # async fn example() -> impulse_protocol::xmodem::Result<()> {
+//! let stream = tokio::net::TcpStream::connect("127.0.0.1:2323").await?;
+//! let mut sender = YmodemSender::new(stream, SenderConfig::default());
+//!
+//! let mut file = tokio::fs::File::open("file1.txt").await?;
+//! let size = file.metadata().await?.len();
+//! let metadata = FileMetadata::with_size("file1.txt", size);
+//! sender.send_file(&metadata, &mut file, |_| {}).await?;
+//!
+//! sender.finish().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::FileMetadata;
+use super::streaming::YMODEM_G;
+use crate::xmodem::{
+    AsyncXmodemSender, CRC_MODE, Result, SendStats, SenderConfig, XmodemBlock, XmodemVariant,
+};
+use std::time::Instant;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// Async Ymodem / Ymodem-G sender.
+pub struct YmodemSender<S> {
+    inner: AsyncXmodemSender<S>,
+    streaming: bool,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> YmodemSender<S> {
+    /// Create a new Ymodem sender.
+    pub fn new(stream: S, config: SenderConfig) -> Self {
+        Self {
+            inner: AsyncXmodemSender::new(stream, config),
+            streaming: false,
+        }
+    }
+
+    /// Whether the receiver asked for Ymodem-G streaming.
+    pub fn is_streaming(&self) -> bool {
+        self.streaming
+    }
+
+    /// Send one file of the batch.
+    ///
+    /// `progress` is called with the number of file bytes sent so far after
+    /// every block.
+    pub async fn send_file<R: AsyncRead + Unpin>(
+        &mut self,
+        metadata: &FileMetadata,
+        reader: &mut R,
+        mut progress: impl FnMut(u64),
+    ) -> Result<SendStats> {
+        let start_time = Instant::now();
+        let mut stats = SendStats::default();
+
+        // Block 0 carries the file name and size
+        let request = self.inner.wait_for_request(&[CRC_MODE, YMODEM_G]).await?;
+        self.streaming = request == YMODEM_G;
+        let block0 = XmodemBlock::new(0, metadata.encode(), XmodemVariant::Crc)?;
+        stats.blocks_retried += self.inner.send_block(&block0).await?;
+
+        // The receiver repeats its request once it has opened the file
+        self.inner.wait_for_request(&[CRC_MODE, YMODEM_G]).await?;
+        self.inner
+            .send_data(
+                reader,
+                XmodemVariant::OneK,
+                self.streaming,
+                &mut stats,
+                &mut progress,
+            )
+            .await?;
+        self.inner.send_eot().await?;
+
+        stats.duration = start_time.elapsed();
+        Ok(stats)
+    }
+
+    /// End the batch with an empty block 0.
+    pub async fn finish(&mut self) -> Result<()> {
+        self.inner.wait_for_request(&[CRC_MODE, YMODEM_G]).await?;
+        let block0 = XmodemBlock::new(0, FileMetadata::end_of_batch(), XmodemVariant::Crc)?;
+        self.inner.send_block(&block0).await?;
+        Ok(())
+    }
+
+    /// Cancel the current transfer.
+    pub async fn cancel(&mut self) -> Result<()> {
+        self.inner.cancel().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xmodem::{ACK, EOT, NAK, SOH, STX};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream, duplex};
+
+    /// Minimal receiver for a single-file batch.
+    ///
+    /// Returns the decoded block 0 metadata and the received data.
+    async fn receive(mut stream: DuplexStream, request: u8) -> (FileMetadata, Vec<u8>) {
+        let streaming = request == YMODEM_G;
+        stream.write_all(&[request]).await.unwrap();
+
+        let mut metadata = None;
+        let mut data = Vec::new();
+        let mut eot_seen = false;
+
+        loop {
+            let header = stream.read_u8().await.unwrap();
+            let block_size = match header {
+                SOH => 128,
+                STX => 1024,
+                EOT if !eot_seen => {
+                    // NAK the first EOT like most receivers do
+                    eot_seen = true;
+                    stream.write_all(&[NAK]).await.unwrap();
+                    continue;
+                }
+                EOT => {
+                    stream.write_all(&[ACK, request]).await.unwrap();
+                    continue;
+                }
+                other => panic!("unexpected header {other:02x}"),
+            };
+            let mut packet = vec![0u8; 2 + block_size + 2];
+            stream.read_exact(&mut packet).await.unwrap();
+            let payload = &packet[2..2 + block_size];
+
+            if packet[0] == 0 && data.is_empty() && metadata.is_none() {
+                metadata = FileMetadata::decode(payload).unwrap();
+                stream.write_all(&[ACK, request]).await.unwrap();
+            } else if packet[0] == 0 && eot_seen {
+                // End-of-batch block
+                stream.write_all(&[ACK]).await.unwrap();
+                return (metadata.unwrap(), data);
+            } else {
+                data.extend_from_slice(payload);
+                if !streaming {
+                    stream.write_all(&[ACK]).await.unwrap();
+                }
+            }
+        }
+    }
+
+    async fn send(request: u8) -> (bool, SendStats, FileMetadata, Vec<u8>) {
+        let (sender_end, receiver_end) = duplex(8192);
+        let receiver = tokio::spawn(receive(receiver_end, request));
+
+        let mut sender = YmodemSender::new(sender_end, SenderConfig::default());
+        let payload = vec![0x5Au8; 1500];
+        let metadata = FileMetadata::with_size("test.bin", payload.len() as u64);
+        let stats = sender
+            .send_file(&metadata, &mut payload.as_slice(), |_| {})
+            .await
+            .unwrap();
+        sender.finish().await.unwrap();
+
+        let (received_metadata, data) = receiver.await.unwrap();
+        (sender.is_streaming(), stats, received_metadata, data)
+    }
+
+    #[tokio::test]
+    async fn test_send_file_ymodem() {
+        let (streaming, stats, metadata, data) = send(CRC_MODE).await;
+
+        assert!(!streaming);
+        assert_eq!(stats.bytes_sent, 1500);
+        assert_eq!(stats.blocks_sent, 2);
+        assert_eq!(metadata.name, "test.bin");
+        assert_eq!(metadata.size, Some(1500));
+        assert_eq!(&data[..1500], vec![0x5Au8; 1500].as_slice());
+    }
+
+    #[tokio::test]
+    async fn test_send_file_ymodem_g() {
+        let (streaming, stats, metadata, data) = send(YMODEM_G).await;
+
+        assert!(streaming);
+        assert_eq!(stats.blocks_sent, 2);
+        assert_eq!(metadata.size, Some(1500));
+        assert_eq!(data.len(), 2048);
+    }
+}
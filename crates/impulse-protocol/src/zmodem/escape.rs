@@ -16,6 +16,8 @@ pub const ZCRCE: u8 = 0x68; // CRC next, frame ends, header packet follows
 pub const ZCRCG: u8 = 0x69; // CRC next, frame continues nonstop
 pub const ZCRCQ: u8 = 0x6A; // CRC next, frame continues, ZACK expected
 pub const ZCRCW: u8 = 0x6B; // CRC next, ZACK expected, end of frame
+pub const ZRUB0: u8 = 0x6C; // Escaped DEL (0x7F)
+pub const ZRUB1: u8 = 0x6D; // Escaped 0xFF
 
 /// Characters that must be escaped in ZDLE encoding.
 const ESCAPE_CHARS: &[u8] = &[
@@ -32,7 +34,8 @@ const ESCAPE_CHARS: &[u8] = &[
 
 /// Encode data using ZDLE escaping.
 ///
-/// Characters in the escape set are preceded by ZDLE and XORed with 0x40.
+/// Characters in the escape set are preceded by ZDLE and XORed with 0x40,
+/// except DEL and 0xFF, which are sent as ZDLE [`ZRUB0`] and ZDLE [`ZRUB1`].
 ///
 /// # Arguments
 ///
@@ -57,7 +60,11 @@ pub fn encode(data: &[u8]) -> Vec<u8> {
     for &byte in data {
         if should_escape(byte) {
             encoded.push(ZDLE);
-            encoded.push(byte ^ 0x40);
+            encoded.push(match byte {
+                0x7F => ZRUB0,
+                0xFF => ZRUB1,
+                _ => byte ^ 0x40,
+            });
         } else {
             encoded.push(byte);
         }
@@ -118,6 +125,8 @@ pub fn decode(data: &[u8]) -> Result<Vec<u8>> {
                 ZDLE => {
                     decoded.push(ZDLE);
                 }
+                ZRUB0 => decoded.push(0x7F),
+                ZRUB1 => decoded.push(0xFF),
                 // Normal escape: XOR with 0x40
                 _ => {
                     decoded.push(next ^ 0x40);
@@ -333,6 +342,13 @@ mod tests {
         assert!(!should_escape(0x20)); // space
     }
 
+    #[test]
+    fn test_encode_rubout() {
+        let encoded = encode(&[0x7F, 0xFF]);
+        assert_eq!(encoded, vec![ZDLE, ZRUB0, ZDLE, ZRUB1]);
+        assert_eq!(decode(&encoded).unwrap(), vec![0x7F, 0xFF]);
+    }
+
     #[test]
     fn test_encode_empty() {
         let data = &[];
@@ -7,6 +7,15 @@ use super::error::{Result, ZmodemError};
 use super::frame::{FrameEncoding, FrameType, ZmodemFrame};
 use std::str;
 
+/// ZFILE conversion option (ZF0): binary transfer
+pub const ZCBIN: u8 = 1;
+
+/// ZFILE conversion option (ZF0): resume an interrupted transfer
+///
+/// The receiver answers with a ZRPOS at the end of any partial copy of the
+/// file it already has.
+pub const ZCRESUM: u8 = 3;
+
 /// Zmodem file information.
 ///
 /// Contains metadata about a file being transferred via Zmodem protocol.
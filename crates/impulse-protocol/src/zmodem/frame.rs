@@ -123,6 +123,16 @@ impl FrameEncoding {
     }
 }
 
+/// Index of the ZF0 flag byte in [`ZmodemFrame::flags`].
+///
+/// Position headers (ZRPOS, ZDATA, ...) store a little-endian offset in
+/// ZP0..ZP3, while flag headers number the same bytes from the other end:
+/// ZF0 is the last header byte on the wire.
+pub const ZF0: usize = 3;
+
+/// Index of the ZF1 flag byte in [`ZmodemFrame::flags`].
+pub const ZF1: usize = 2;
+
 /// Zmodem frame structure.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ZmodemFrame {
@@ -130,7 +140,7 @@ pub struct ZmodemFrame {
     pub frame_type: FrameType,
     /// Encoding type
     pub encoding: FrameEncoding,
-    /// Flag bytes (ZF0-ZF3)
+    /// Header bytes ZP0-ZP3 (see [`ZF0`] for flag headers)
     pub flags: [u8; 4],
     /// Optional data payload
     pub data: Option<Vec<u8>>,
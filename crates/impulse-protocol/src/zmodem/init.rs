@@ -4,7 +4,7 @@
 //! frames used to negotiate session parameters.
 
 use super::error::Result;
-use super::frame::{FrameEncoding, FrameType, ZmodemFrame};
+use super::frame::{FrameEncoding, FrameType, ZF0, ZmodemFrame};
 
 /// ZRINIT capability flags (receiver capabilities).
 ///
@@ -169,15 +169,12 @@ impl ZmodemInit {
             zf0 |= ESC8;
         }
 
-        flags[0] = zf0;
+        flags[ZF0] = zf0;
 
-        // ZF1, ZF2: Buffer size (little-endian)
+        // ZP0, ZP1: Buffer size (little-endian)
         let buffer_bytes = self.buffer_size.to_le_bytes();
-        flags[1] = buffer_bytes[0];
-        flags[2] = buffer_bytes[1];
-
-        // ZF3: Reserved (0)
-        flags[3] = 0;
+        flags[0] = buffer_bytes[0];
+        flags[1] = buffer_bytes[1];
 
         ZmodemFrame::new(FrameType::ZRINIT, FrameEncoding::Hex, flags, None)
     }
@@ -203,15 +200,13 @@ impl ZmodemInit {
     /// assert_eq!(parsed, init);
     /// ```
     pub fn from_zrinit(frame: &ZmodemFrame) -> Result<Self> {
-        let zf0 = frame.flags[0];
-        let zf1 = frame.flags[1];
-        let zf2 = frame.flags[2];
+        let zf0 = frame.flags[ZF0];
 
         let escape_ctrl = (zf0 & ESCCTL) != 0;
         let escape_8bit = (zf0 & ESC8) != 0;
         let use_crc32 = (zf0 & CANFC32) != 0;
 
-        let buffer_size = u16::from_le_bytes([zf1, zf2]);
+        let buffer_size = u16::from_le_bytes([frame.flags[0], frame.flags[1]]);
 
         Ok(Self {
             escape_ctrl,
@@ -285,7 +280,7 @@ mod tests {
         assert_eq!(frame.encoding, FrameEncoding::Hex);
 
         // Check flags
-        let zf0 = frame.flags[0];
+        let zf0 = frame.flags[ZF0];
         assert_eq!(zf0 & CANFDX, CANFDX);
         assert_eq!(zf0 & CANOVIO, CANOVIO);
         assert_eq!(zf0 & CANFC32, CANFC32);
@@ -303,7 +298,7 @@ mod tests {
         };
 
         let frame = init.to_zrinit();
-        let zf0 = frame.flags[0];
+        let zf0 = frame.flags[ZF0];
 
         assert_eq!(zf0 & ESCCTL, ESCCTL);
         assert_eq!(zf0 & ESC8, ESC8);
@@ -334,6 +329,17 @@ mod tests {
         assert_eq!(parsed, original);
     }
 
+    #[test]
+    fn test_from_zrinit_wire_layout() {
+        // ZF0 is the last header byte: CANFC32 | CANOVIO | CANFDX, no buffer limit
+        let frame = ZmodemFrame::new(FrameType::ZRINIT, FrameEncoding::Hex, [0, 0, 0, 0x23], None);
+        let parsed = ZmodemInit::from_zrinit(&frame).unwrap();
+
+        assert!(parsed.use_crc32);
+        assert!(!parsed.escape_ctrl);
+        assert_eq!(parsed.buffer_size, 0);
+    }
+
     #[test]
     fn test_buffer_size_encoding() {
         let init = ZmodemInit {
@@ -384,7 +390,7 @@ mod tests {
         };
 
         let frame = init.to_zrinit();
-        let zf0 = frame.flags[0];
+        let zf0 = frame.flags[ZF0];
 
         // All flags should be set
         assert_ne!(zf0 & CANFDX, 0);
@@ -404,7 +410,7 @@ mod tests {
         };
 
         let frame = init.to_zrinit();
-        let zf0 = frame.flags[0];
+        let zf0 = frame.flags[ZF0];
 
         // Only mandatory flags should be set
         assert_ne!(zf0 & CANFDX, 0);
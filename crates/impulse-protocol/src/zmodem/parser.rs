@@ -95,6 +95,7 @@ impl FrameParser {
                         let last_three = &self.buffer[len - 3..];
                         if last_three[0] == ZPAD && last_three[1] == ZPAD && last_three[2] == ZDLE {
                             // Hex frame start: ZPAD ZPAD ZDLE
+                            self.buffer.drain(..len - 3);
                             self.state = ParserState::WaitingForZdle;
                         }
                     }
@@ -103,9 +104,14 @@ impl FrameParser {
                         let last_two = &self.buffer[len - 2..];
                         if last_two[0] == ZPAD && last_two[1] == ZDLE {
                             // Binary frame start: ZPAD ZDLE
+                            self.buffer.drain(..len - 2);
                             self.state = ParserState::WaitingForZdle;
                         }
                     }
+                    // Anything before a frame start is line noise
+                    if self.state == ParserState::WaitingForZpad && len > 2 {
+                        self.buffer.drain(..len - 2);
+                    }
                 }
 
                 ParserState::WaitingForZdle => {
@@ -122,16 +128,15 @@ impl FrameParser {
                 }
 
                 ParserState::ReadingHexFrame => {
-                    // Check for end of hex frame: CR LF|0x80
-                    if len >= 2 {
-                        let last_two = &self.buffer[len - 2..];
-                        if last_two[0] == 0x0D && last_two[1] == 0x8A {
-                            match self.parse_hex_frame(&self.buffer) {
-                                Ok(frame) => frames.push(Ok(frame)),
-                                Err(e) => frames.push(Err(e)),
-                            }
-                            self.reset();
+                    // ZPAD ZPAD ZDLE 'B' plus 14 hex digits; the CR LF
+                    // (or CR LF|0x80) and XON that follow are skipped as
+                    // noise, since senders differ in which they send
+                    if len == 4 + 14 {
+                        match self.parse_hex_frame(&self.buffer) {
+                            Ok(frame) => frames.push(Ok(frame)),
+                            Err(e) => frames.push(Err(e)),
                         }
+                        self.reset();
                     }
                 }
 
@@ -211,11 +216,9 @@ impl FrameParser {
             return Err(ZmodemError::InvalidFrame("Hex frame too short".to_string()));
         }
 
-        // Skip ZPAD ZPAD ZDLE 'B' header (4 bytes)
-        let hex_start = 4;
-        let hex_end = data.len() - 2; // Skip CR LF|0x80
-
-        let hex_data = &data[hex_start..hex_end];
+        // Skip ZPAD ZPAD ZDLE 'B' header (4 bytes); anything after the
+        // 14 hex digits is the line terminator
+        let hex_data = &data[4..];
 
         // Parse hex digits
         if hex_data.len() < 14 {
@@ -507,6 +510,22 @@ mod tests {
         assert!(frames[1].is_ok());
     }
 
+    #[test]
+    fn test_feed_hex_frame_after_noise() {
+        let mut parser = FrameParser::new();
+
+        // Receivers may end hex headers with a plain CR LF and XON
+        let frame = ZmodemFrame::new(FrameType::ZRINIT, FrameEncoding::Hex, [0, 0, 0, 0x23], None);
+        let mut data = b"rz waiting to receive.".to_vec();
+        data.extend_from_slice(&frame.serialize()[..18]);
+        data.extend_from_slice(b"\r\n\x11");
+
+        let frames = parser.feed(&data);
+
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].as_ref().unwrap(), &frame);
+    }
+
     #[test]
     fn test_feed_incomplete_frame() {
         let mut parser = FrameParser::new();
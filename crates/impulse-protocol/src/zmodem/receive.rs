@@ -5,13 +5,13 @@
 //!
 //! # Protocol Flow
 //!
-//! 1. Wait for ZRQINIT and respond with ZRINIT
+//! 1. Send ZRINIT (again whenever the sender's ZRQINIT arrives)
 //! 2. Receive file header (ZFILE) with metadata
 //! 3. Send ZRPOS with starting position (0 for new, >0 for resume)
-//! 4. Receive file data blocks (ZDATA frames)
-//! 5. Verify CRC and request retransmission if needed
-//! 6. Receive EOF (ZEOF) and acknowledge
-//! 7. Repeat for additional files or finish session
+//! 4. Receive the ZDATA header and the stream of data subpackets after it
+//! 5. Verify CRC and request retransmission with ZRPOS if needed
+//! 6. Receive EOF (ZEOF) and answer with ZRINIT
+//! 7. Repeat for additional files, or answer ZFIN to finish the session
 //!
 //! # Examples
 //!
@@ -43,21 +43,31 @@
 //! ```
 
 use super::error::{Result, ZmodemError};
-use super::escape::{self, ZCRCG, ZCRCQ, ZCRCW, ZDLE};
+use super::escape::{XOFF, XON, ZCRCE, ZCRCG, ZCRCQ, ZCRCW, ZDLE, ZRUB0, ZRUB1};
 use super::file::ZmodemFileInfo;
 use super::frame::{FrameEncoding, FrameType, ZmodemFrame};
 use super::init::ZmodemInit;
-use super::negotiate::{CrcType, NegotiatedParams};
+use super::negotiate::{CrcType, EscapeMode, NegotiatedParams};
 use super::parser::FrameParser;
+use super::progress::{BatchStats, FileProgress, NoOpProgress, TransferProgress};
+use super::send::{ABORT_CAN_COUNT, TransferStats};
 use super::state::{ZmodemState, ZmodemStateMachine};
 use super::{crc16, crc32};
-use std::io::SeekFrom;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 use tokio::fs::{File, OpenOptions};
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::time::timeout;
 
+/// Bytes read from the stream at a time.
+const RX_BUFFER_SIZE: usize = 4096;
+
+/// Largest data subpacket accepted (the Zmodem maximum is 8K).
+const MAX_SUBPACKET_SIZE: usize = 8192;
+
+/// How long to wait for the sender's "OO" after our ZFIN.
+const OVER_AND_OUT_TIMEOUT: Duration = Duration::from_secs(2);
+
 /// Configuration for Zmodem receiver.
 ///
 /// # Examples
@@ -273,7 +283,18 @@ pub struct ZmodemReceiver<S> {
     state: ZmodemStateMachine,
     config: ReceiverConfig,
     negotiated: Option<NegotiatedParams>,
-    data_buffer: Vec<u8>,
+    rx_buffer: Vec<u8>,
+    rx_pos: usize,
+    pending: Option<ZmodemFrame>,
+    cancel_count: usize,
+}
+
+/// A byte read from inside a data subpacket.
+enum SubpacketByte {
+    /// A data (or CRC) byte with any ZDLE escape removed
+    Data(u8),
+    /// The frame end (ZCRCE, ZCRCG, ZCRCQ or ZCRCW) closing the subpacket
+    FrameEnd(u8),
 }
 
 impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemReceiver<S> {
@@ -303,13 +324,19 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemReceiver<S> {
             state: ZmodemStateMachine::new(),
             config,
             negotiated: None,
-            data_buffer: Vec::with_capacity(8192),
+            rx_buffer: Vec::with_capacity(RX_BUFFER_SIZE),
+            rx_pos: 0,
+            pending: None,
+            cancel_count: 0,
         }
     }
 
     /// Initialize Zmodem session.
     ///
-    /// Waits for ZRQINIT and responds with ZRINIT to negotiate parameters.
+    /// Sends ZRINIT, which also starts the upload in terminal programs that
+    /// detect Zmodem automatically, and repeats it until the sender offers
+    /// its first file (or ends an empty session). A ZRQINIT from the sender
+    /// is answered with another ZRINIT.
     ///
     /// # Returns
     ///
@@ -333,28 +360,44 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemReceiver<S> {
     /// # }
     /// ```
     pub async fn init(&mut self) -> Result<NegotiatedParams> {
-        // Wait for ZRQINIT
-        let _zrqinit = self
-            .wait_for_frame_type(
-                FrameType::ZRQINIT,
-                Duration::from_millis(self.config.timeout_ms),
-            )
-            .await?;
+        self.send_zrinit().await?;
+        self.state.advance(ZmodemState::InitSent);
 
-        self.state.advance(ZmodemState::InitReceived);
+        let mut retries = 0;
+        loop {
+            let frame = match self.read_header().await {
+                Ok(frame) => frame,
+                Err(e) if is_line_error(&e) => {
+                    retries += 1;
+                    if retries >= self.config.max_retries {
+                        return Err(e);
+                    }
+                    self.send_zrinit().await?;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
 
-        // Create receiver init parameters
-        let receiver_init = ZmodemInit {
-            use_crc32: self.config.use_crc32,
-            escape_ctrl: self.config.escape_control,
-            escape_8bit: self.config.escape_8bit,
-            buffer_size: self.config.buffer_size as u16,
-        };
+            match frame.frame_type {
+                FrameType::ZRQINIT => self.send_zrinit().await?,
+                FrameType::ZSINIT => {
+                    // The attention string is not needed: this receiver
+                    // never interrupts the sender mid-stream
+                    let crc32 = frame.encoding == FrameEncoding::Bin32;
+                    if self.read_subpacket(crc32).await.is_ok() {
+                        self.send_ack(0).await?;
+                    }
+                }
+                FrameType::ZFILE | FrameType::ZFIN => {
+                    self.pending = Some(frame);
+                    break;
+                }
+                FrameType::ZCAN | FrameType::ZABORT => return Err(ZmodemError::Cancelled),
+                _ => continue,
+            }
+        }
 
-        // Send ZRINIT
-        let zrinit = receiver_init.to_zrinit();
-        self.send_frame(&zrinit).await?;
-        self.state.advance(ZmodemState::InitSent);
+        self.state.advance(ZmodemState::InitReceived);
 
         // Create negotiated parameters (we accept our own parameters as baseline)
         let params = NegotiatedParams {
@@ -363,7 +406,7 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemReceiver<S> {
             } else {
                 CrcType::Crc16
             },
-            escape_mode: super::negotiate::EscapeMode::Minimal,
+            escape_mode: EscapeMode::Minimal,
             buffer_size: self.config.buffer_size as u16,
             can_resume: self.config.allow_resume,
         };
@@ -408,36 +451,80 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemReceiver<S> {
     /// # }
     /// ```
     pub async fn receive_files(&mut self, output_dir: &Path) -> Result<Vec<ReceivedFile>> {
+        self.receive_files_with_progress(output_dir, &mut NoOpProgress)
+            .await
+    }
+
+    /// Receive files to a directory, reporting progress.
+    ///
+    /// Files the sender offers under a name that cannot be stored safely,
+    /// or that already exist and may be neither resumed nor overwritten,
+    /// are skipped with ZSKIP.
+    pub async fn receive_files_with_progress<P: TransferProgress>(
+        &mut self,
+        output_dir: &Path,
+        progress: &mut P,
+    ) -> Result<Vec<ReceivedFile>> {
         let mut received_files = Vec::new();
+        let mut retries = 0;
 
         loop {
             // Wait for ZFILE or ZFIN
-            let frame = self
-                .wait_for_frame(Duration::from_millis(self.config.timeout_ms))
-                .await?;
+            let frame = match self.read_header().await {
+                Ok(frame) => frame,
+                Err(e) if is_line_error(&e) => {
+                    retries += 1;
+                    if retries >= self.config.max_retries {
+                        return Err(e);
+                    }
+                    self.send_zrinit().await?;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
 
             match frame.frame_type {
                 FrameType::ZFILE => {
-                    // Parse file info and receive file
-                    let file_info = self.parse_file_info(&frame).await?;
-                    let result = self.receive_file(&file_info, output_dir).await?;
-                    received_files.push(result);
+                    let crc32 = frame.encoding == FrameEncoding::Bin32;
+                    let file_info = match self.read_subpacket(crc32).await {
+                        Ok((data, _)) => ZmodemFileInfo::from_zfile_data(&data)?,
+                        Err(e) if is_line_error(&e) => {
+                            // The sender repeats ZFILE when no ZRPOS comes back
+                            self.send_zrinit().await?;
+                            continue;
+                        }
+                        Err(e) => return Err(e),
+                    };
 
-                    // Send ZRINIT to indicate ready for next file
-                    let receiver_init = ZmodemInit {
-                        use_crc32: self.config.use_crc32,
-                        escape_ctrl: self.config.escape_control,
-                        escape_8bit: self.config.escape_8bit,
-                        buffer_size: self.config.buffer_size as u16,
+                    self.state.advance(ZmodemState::FileHeaderSent);
+                    let Some(output_path) = safe_file_name(&file_info.name)
+                        .map(|name| output_dir.join(name))
+                        .filter(|path| self.can_write(path, &file_info))
+                    else {
+                        self.skip_file().await?;
+                        self.state.advance(ZmodemState::InitReceived);
+                        continue;
                     };
-                    let zrinit = receiver_init.to_zrinit();
-                    self.send_frame(&zrinit).await?;
-                }
-                FrameType::ZFIN => {
-                    // Session complete
-                    self.state.advance(ZmodemState::SessionComplete);
-                    break;
+
+                    let index = received_files.len();
+                    match self
+                        .receive_file(&file_info, &output_path, index, progress)
+                        .await
+                    {
+                        Ok(result) => received_files.push(result),
+                        Err(e) => {
+                            progress.on_error(&e);
+                            return Err(e);
+                        }
+                    }
+
+                    // Send ZRINIT to indicate ready for next file
+                    self.send_zrinit().await?;
+                    self.state.advance(ZmodemState::InitReceived);
+                    retries = 0;
                 }
+                FrameType::ZFIN => break,
+                FrameType::ZRQINIT => self.send_zrinit().await?,
                 FrameType::ZCAN | FrameType::ZABORT => {
                     return Err(ZmodemError::Cancelled);
                 }
@@ -448,6 +535,13 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemReceiver<S> {
             }
         }
 
+        let total_bytes = received_files.iter().map(|f| f.stats.bytes_total).sum();
+        let mut batch_stats = BatchStats::new(received_files.len(), total_bytes);
+        batch_stats.files_completed = received_files.len();
+        batch_stats.bytes_sent = received_files.iter().map(|f| f.stats.bytes_received).sum();
+        batch_stats.total_retries = received_files.iter().map(|f| f.stats.retries).sum();
+        progress.on_complete(&batch_stats);
+
         Ok(received_files)
     }
 
@@ -462,109 +556,70 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemReceiver<S> {
     /// Receive statistics for the completed transfer
     pub async fn receive_single_file(&mut self, output_path: &Path) -> Result<ReceivedFile> {
         // Wait for ZFILE
-        let frame = self
-            .wait_for_frame_type(
-                FrameType::ZFILE,
-                Duration::from_millis(self.config.timeout_ms),
-            )
+        let frame = self.wait_for_frame_type(FrameType::ZFILE).await?;
+        let crc32 = frame.encoding == FrameEncoding::Bin32;
+        let (data, _) = self.read_subpacket(crc32).await?;
+        let file_info = ZmodemFileInfo::from_zfile_data(&data)?;
+
+        self.state.advance(ZmodemState::FileHeaderSent);
+        let result = self
+            .receive_file(&file_info, output_path, 0, &mut NoOpProgress)
             .await?;
-
-        let file_info = self.parse_file_info(&frame).await?;
-
-        // Use parent directory of output_path
-        let output_dir = output_path.parent().unwrap_or(Path::new("."));
-        self.receive_file(&file_info, output_dir).await
+        self.send_zrinit().await?;
+        self.state.advance(ZmodemState::InitReceived);
+        Ok(result)
     }
 
     /// Finish the Zmodem session.
     ///
-    /// Sends ZFIN acknowledgment.
+    /// Answers the sender's ZFIN with our own and waits briefly for the
+    /// "OO" (over and out) that ends the session. Senders that hang up
+    /// without it are not treated as an error.
     ///
     /// # Errors
     ///
     /// Returns error if session termination fails
     pub async fn finish(&mut self) -> Result<()> {
-        // Send ZFIN acknowledgment
-        let zfin = ZmodemFrame::with_defaults(FrameType::ZFIN, self.frame_encoding());
+        let zfin = ZmodemFrame::with_defaults(FrameType::ZFIN, FrameEncoding::Hex);
         self.send_frame(&zfin).await?;
-        self.state.advance(ZmodemState::SessionComplete);
-        Ok(())
-    }
-
-    /// Parse file information from ZFILE frame.
-    async fn parse_file_info(&mut self, _frame: &ZmodemFrame) -> Result<ZmodemFileInfo> {
-        // ZFILE frame data contains filename and metadata
-        // Read the subpacket data following the frame header
-        let file_data = self.read_subpacket_data().await?;
-
-        // Parse file info from the data
-        ZmodemFileInfo::from_zfile_data(&file_data)
-    }
 
-    /// Read subpacket data (following a frame header).
-    async fn read_subpacket_data(&mut self) -> Result<Vec<u8>> {
-        let mut data = Vec::new();
-        let mut raw_buffer = vec![0u8; 1024];
-        let mut in_escape = false;
-
-        let deadline = Instant::now() + Duration::from_millis(self.config.timeout_ms);
-
-        loop {
-            if Instant::now() >= deadline {
-                return Err(ZmodemError::Timeout);
+        let deadline = Instant::now() + OVER_AND_OUT_TIMEOUT;
+        let mut o_count = 0;
+        while o_count < 2 {
+            match self.read_byte(deadline).await {
+                Ok(b'O') => o_count += 1,
+                Ok(_) => o_count = 0,
+                Err(_) => break,
             }
+        }
 
-            let remaining = deadline - Instant::now();
-            let n = match timeout(remaining, self.stream.read(&mut raw_buffer)).await {
-                Ok(Ok(n)) if n > 0 => n,
-                Ok(Ok(_)) => return Err(ZmodemError::UnexpectedEof),
-                Ok(Err(e)) => return Err(e.into()),
-                Err(_) => return Err(ZmodemError::Timeout),
-            };
-
-            for &byte in &raw_buffer[..n] {
-                if in_escape {
-                    in_escape = false;
-                    match byte {
-                        // End of subpacket markers
-                        ZCRCG | ZCRCQ | ZCRCW | 0x68 => {
-                            // ZCRCE = 0x68
-                            // Read and verify CRC
-                            // For now, return data without CRC verification in subpacket
-                            return Ok(data);
-                        }
-                        _ => {
-                            // XOR with 0x40 to decode escaped character
-                            data.push(byte ^ 0x40);
-                        }
-                    }
-                } else if byte == ZDLE {
-                    in_escape = true;
-                } else {
-                    data.push(byte);
-                }
-            }
+        self.state.advance(ZmodemState::SessionComplete);
+        Ok(())
+    }
 
-            // Safety limit on subpacket size
-            if data.len() > 8192 {
-                return Err(ZmodemError::InvalidFrame("Subpacket too large".to_string()));
+    /// Whether a file may be written at `path`, by resuming or overwriting.
+    fn can_write(&self, path: &Path, file_info: &ZmodemFileInfo) -> bool {
+        match std::fs::metadata(path) {
+            Ok(metadata) => {
+                self.config.overwrite_existing
+                    || (self.config.allow_resume && metadata.len() < file_info.size)
             }
+            Err(_) => true,
         }
     }
 
     /// Receive a single file.
-    async fn receive_file(
+    async fn receive_file<P: TransferProgress>(
         &mut self,
         file_info: &ZmodemFileInfo,
-        output_dir: &Path,
+        output_path: &Path,
+        file_index: usize,
+        progress: &mut P,
     ) -> Result<ReceivedFile> {
         self.state.set_current_file(file_info.clone());
 
-        // Determine output path
-        let output_path = output_dir.join(&file_info.name);
-
         // Check for existing file and determine starting position
-        let (mut file, start_pos) = self.open_output_file(&output_path, file_info).await?;
+        let (mut file, start_pos) = self.open_output_file(output_path, file_info).await?;
 
         // Initialize statistics
         let mut stats = if start_pos > 0 {
@@ -572,31 +627,49 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemReceiver<S> {
                 file_info.size,
                 start_pos,
                 file_info.name.clone(),
-                output_path.clone(),
+                output_path.to_path_buf(),
             )
         } else {
             let mut s = ReceiveStats::new(file_info.size);
             s.file_name = file_info.name.clone();
-            s.file_path = output_path.clone();
+            s.file_path = output_path.to_path_buf();
             s
         };
 
+        progress.on_file_start(file_info);
+        let mut file_progress = FileProgress {
+            file_index,
+            total_files: file_index + 1 + file_info.files_remaining.unwrap_or(0) as usize,
+            file_path: output_path.to_path_buf(),
+            file_name: file_info.name.clone(),
+            bytes_sent: start_pos,
+            bytes_total: file_info.size,
+            retries: 0,
+        };
+
         // Send ZRPOS with starting position
         self.send_position(start_pos).await?;
         self.state.set_position(start_pos);
         self.state.advance(ZmodemState::DataTransfer);
 
-        // Receive file data
-        self.receive_file_data(&mut file, &mut stats).await?;
-
-        // Wait for ZEOF
-        self.wait_for_eof(&mut stats).await?;
+        // Receive file data up to the sender's ZEOF
+        self.receive_file_data(&mut file, &mut stats, &mut file_progress, progress)
+            .await?;
+        file.flush().await?;
+        self.state.advance(ZmodemState::FileComplete);
 
         stats.complete();
 
+        let mut transfer_stats = TransferStats::new(stats.bytes_total);
+        transfer_stats.bytes_sent = stats.bytes_received;
+        transfer_stats.retries = stats.retries;
+        transfer_stats.start_position = stats.resume_position;
+        transfer_stats.complete();
+        progress.on_file_complete(file_info, &transfer_stats);
+
         Ok(ReceivedFile {
             file_info: file_info.clone(),
-            saved_path: output_path,
+            saved_path: output_path.to_path_buf(),
             stats,
         })
     }
@@ -616,11 +689,7 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemReceiver<S> {
 
                 if existing_size < file_info.size {
                     // Resume from existing position
-                    let file = OpenOptions::new()
-                        .write(true)
-                        .append(true)
-                        .open(output_path)
-                        .await?;
+                    let file = OpenOptions::new().append(true).open(output_path).await?;
                     return Ok((file, existing_size));
                 }
             }
@@ -638,6 +707,18 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemReceiver<S> {
         Ok((file, 0))
     }
 
+    /// Send ZRINIT with our capabilities.
+    async fn send_zrinit(&mut self) -> Result<()> {
+        let receiver_init = ZmodemInit {
+            use_crc32: self.config.use_crc32,
+            escape_ctrl: self.config.escape_control,
+            escape_8bit: self.config.escape_8bit,
+            buffer_size: self.config.buffer_size as u16,
+        };
+        let zrinit = receiver_init.to_zrinit();
+        self.send_frame(&zrinit).await
+    }
+
     /// Send ZRPOS frame with position.
     async fn send_position(&mut self, position: u64) -> Result<()> {
         let mut zrpos = ZmodemFrame::with_defaults(FrameType::ZRPOS, self.frame_encoding());
@@ -652,24 +733,25 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemReceiver<S> {
         self.send_frame(&zack).await
     }
 
-    /// Receive file data blocks.
-    async fn receive_file_data(&mut self, file: &mut File, stats: &mut ReceiveStats) -> Result<()> {
-        let mut position = stats.bytes_received;
-        let file_size = stats.bytes_total;
+    /// Receive file data until the sender's ZEOF.
+    ///
+    /// Each ZDATA header is followed by a stream of subpackets. A damaged
+    /// subpacket or header is answered with ZRPOS at the last good byte, and
+    /// everything up to the sender's next ZDATA at that offset is discarded.
+    async fn receive_file_data<P: TransferProgress>(
+        &mut self,
+        file: &mut File,
+        stats: &mut ReceiveStats,
+        file_progress: &mut FileProgress,
+        progress: &mut P,
+    ) -> Result<()> {
         let mut retries = 0;
 
-        while position < file_size {
-            // Wait for ZDATA frame
-            let frame = match self
-                .wait_for_frame(Duration::from_millis(self.config.timeout_ms))
-                .await
-            {
-                Ok(f) => f,
-                Err(ZmodemError::Timeout) if retries < self.config.max_retries => {
-                    retries += 1;
-                    stats.retries += 1;
-                    // Request retransmission
-                    self.send_position(position).await?;
+        loop {
+            let frame = match self.read_header().await {
+                Ok(frame) => frame,
+                Err(e) if is_line_error(&e) => {
+                    self.request_resend(stats, &mut retries).await?;
                     continue;
                 }
                 Err(e) => return Err(e),
@@ -677,200 +759,146 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemReceiver<S> {
 
             match frame.frame_type {
                 FrameType::ZDATA => {
-                    // Verify position
-                    let data_pos = frame.flags_as_u32() as u64;
-                    if data_pos != position {
-                        // Out of sync, request correct position
-                        retries += 1;
-                        stats.retries += 1;
-                        if retries >= self.config.max_retries {
-                            return Err(ZmodemError::MaxRetriesExceeded);
-                        }
-                        file.seek(SeekFrom::Start(position)).await?;
-                        self.send_position(position).await?;
+                    if u64::from(frame.flags_as_u32()) != stats.bytes_received {
+                        // Data from before our last ZRPOS; wait for the resend
                         continue;
                     }
 
-                    // Receive data block
-                    match self.receive_data_block().await {
-                        Ok((data, block_type)) => {
-                            // Write data to file
-                            file.write_all(&data).await?;
-                            position += data.len() as u64;
-                            stats.bytes_received = position;
-                            retries = 0;
-
-                            // Send ACK if requested
-                            if block_type == ZCRCQ || block_type == ZCRCW {
-                                self.send_ack(position).await?;
+                    let crc32 = frame.encoding == FrameEncoding::Bin32;
+                    loop {
+                        let (data, frame_end) = match self.read_subpacket(crc32).await {
+                            Ok(subpacket) => subpacket,
+                            Err(e) if is_line_error(&e) => {
+                                self.request_resend(stats, &mut retries).await?;
+                                break;
                             }
+                            Err(e) => return Err(e),
+                        };
+
+                        file.write_all(&data).await?;
+                        stats.bytes_received += data.len() as u64;
+                        self.state.set_position(stats.bytes_received);
+                        retries = 0;
+
+                        file_progress.bytes_sent = stats.bytes_received;
+                        file_progress.retries = stats.retries;
+                        progress.on_progress(file_progress);
+
+                        match frame_end {
+                            ZCRCW => {
+                                self.send_ack(stats.bytes_received).await?;
+                                break;
+                            }
+                            ZCRCQ => self.send_ack(stats.bytes_received).await?,
+                            ZCRCG => {}
+                            _ => break,
                         }
-                        Err(ZmodemError::CrcMismatch { .. })
-                            if retries < self.config.max_retries =>
-                        {
-                            retries += 1;
-                            stats.retries += 1;
-                            // Request retransmission from current position
-                            self.send_position(position).await?;
-                        }
-                        Err(e) => return Err(e),
                     }
                 }
                 FrameType::ZEOF => {
-                    // Premature EOF, but might be valid
-                    let eof_pos = frame.flags_as_u32() as u64;
-                    if eof_pos == position {
-                        // Valid EOF at current position
-                        stats.bytes_total = position;
-                        break;
-                    } else {
-                        // Wrong position, request correct position
-                        self.send_position(position).await?;
+                    // An early ZEOF may have crossed our ZRPOS; the sender
+                    // resends from the requested offset
+                    if u64::from(frame.flags_as_u32()) == stats.bytes_received {
+                        return Ok(());
                     }
                 }
-                FrameType::ZCAN | FrameType::ZABORT => {
-                    return Err(ZmodemError::Cancelled);
+                FrameType::ZFILE => {
+                    // Our ZRPOS was lost and the sender repeated the header
+                    let crc32 = frame.encoding == FrameEncoding::Bin32;
+                    let _ = self.read_subpacket(crc32).await;
+                    self.send_position(stats.bytes_received).await?;
                 }
-                _ => {
-                    // Ignore unexpected frames
-                    continue;
+                FrameType::ZCAN | FrameType::ZABORT | FrameType::ZFIN => {
+                    return Err(ZmodemError::Cancelled);
                 }
+                _ => continue,
             }
         }
-
-        Ok(())
     }
 
-    /// Receive a data block with CRC verification.
-    async fn receive_data_block(&mut self) -> Result<(Vec<u8>, u8)> {
-        let mut data = Vec::new();
-        let mut raw_buffer = vec![0u8; 256];
-        let mut in_escape = false;
-        let mut block_type: Option<u8> = None;
+    /// Ask the sender to resend from the last good byte.
+    async fn request_resend(&mut self, stats: &mut ReceiveStats, retries: &mut u32) -> Result<()> {
+        *retries += 1;
+        stats.retries += 1;
+        if *retries >= self.config.max_retries {
+            return Err(ZmodemError::MaxRetriesExceeded);
+        }
+        self.send_position(stats.bytes_received).await
+    }
 
+    /// Read a data subpacket and check its CRC.
+    ///
+    /// Returns the data and the frame end that closed it. The CRC covers
+    /// the data and the frame end byte; `crc32` follows the encoding of the
+    /// header the subpacket belongs to.
+    async fn read_subpacket(&mut self, crc32: bool) -> Result<(Vec<u8>, u8)> {
         let deadline = Instant::now() + Duration::from_millis(self.config.timeout_ms);
+        let mut data = Vec::with_capacity(1024);
 
-        loop {
-            if Instant::now() >= deadline {
-                return Err(ZmodemError::Timeout);
+        let frame_end = loop {
+            match self.read_subpacket_byte(deadline).await? {
+                SubpacketByte::Data(byte) => data.push(byte),
+                SubpacketByte::FrameEnd(end) => break end,
             }
 
-            let remaining = deadline - Instant::now();
-            let n = match timeout(remaining, self.stream.read(&mut raw_buffer)).await {
-                Ok(Ok(n)) if n > 0 => n,
-                Ok(Ok(_)) => return Err(ZmodemError::UnexpectedEof),
-                Ok(Err(e)) => return Err(e.into()),
-                Err(_) => return Err(ZmodemError::Timeout),
-            };
-
-            for &byte in &raw_buffer[..n] {
-                if let Some(block_end) = block_type {
-                    // Reading CRC after block end
-                    self.data_buffer.push(byte);
-
-                    // Check if we have enough CRC bytes
-                    let crc_len = if self.use_crc32() { 4 } else { 2 };
-                    let expected_crc_bytes = crc_len * 2; // Escaped CRC can be up to 2x size
-
-                    if self.data_buffer.len() >= expected_crc_bytes || !in_escape {
-                        // Try to decode and verify CRC
-                        let decoded_crc = escape::decode(&self.data_buffer)
-                            .unwrap_or_else(|_| self.data_buffer.clone());
-
-                        if decoded_crc.len() >= crc_len {
-                            // Verify CRC
-                            if self.use_crc32() && decoded_crc.len() >= 4 {
-                                let received_crc = u32::from_le_bytes([
-                                    decoded_crc[0],
-                                    decoded_crc[1],
-                                    decoded_crc[2],
-                                    decoded_crc[3],
-                                ]);
-                                let calculated_crc = crc32::calculate(&data);
-                                if received_crc != calculated_crc {
-                                    return Err(ZmodemError::CrcMismatch {
-                                        expected: calculated_crc,
-                                        actual: received_crc,
-                                    });
-                                }
-                            } else if !self.use_crc32() && decoded_crc.len() >= 2 {
-                                let received_crc =
-                                    u16::from_be_bytes([decoded_crc[0], decoded_crc[1]]);
-                                let calculated_crc = crc16::calculate(&data);
-                                if received_crc != calculated_crc {
-                                    return Err(ZmodemError::CrcMismatch {
-                                        expected: calculated_crc as u32,
-                                        actual: received_crc as u32,
-                                    });
-                                }
-                            }
-
-                            self.data_buffer.clear();
-                            return Ok((data, block_end));
-                        }
-                    }
-                    continue;
-                }
+            // Safety limit on subpacket size
+            if data.len() > MAX_SUBPACKET_SIZE {
+                return Err(ZmodemError::InvalidFrame("Subpacket too large".to_string()));
+            }
+        };
 
-                if in_escape {
-                    in_escape = false;
-                    match byte {
-                        // End of block markers
-                        ZCRCG | ZCRCQ | ZCRCW | 0x68 => {
-                            // ZCRCE = 0x68
-                            block_type = Some(byte);
-                            self.data_buffer.clear();
-                        }
-                        _ => {
-                            // XOR with 0x40 to decode escaped character
-                            data.push(byte ^ 0x40);
-                        }
-                    }
-                } else if byte == ZDLE {
-                    in_escape = true;
-                } else {
-                    data.push(byte);
-                }
+        let crc_len = if crc32 { 4 } else { 2 };
+        let mut crc = Vec::with_capacity(crc_len);
+        while crc.len() < crc_len {
+            match self.read_subpacket_byte(deadline).await? {
+                SubpacketByte::Data(byte) => crc.push(byte),
+                SubpacketByte::FrameEnd(_) => return Err(ZmodemError::InvalidEscape),
             }
+        }
 
-            // Safety limit on block size
-            if data.len() > 8192 {
-                return Err(ZmodemError::InvalidFrame(
-                    "Data block too large".to_string(),
-                ));
+        if crc32 {
+            let received = u32::from_le_bytes([crc[0], crc[1], crc[2], crc[3]]);
+            let calculated = crc32::finalize(crc32::update(
+                crc32::update(0xFFFF_FFFF, &data),
+                &[frame_end],
+            ));
+            if received != calculated {
+                return Err(ZmodemError::CrcMismatch {
+                    expected: calculated,
+                    actual: received,
+                });
+            }
+        } else {
+            let received = u16::from_be_bytes([crc[0], crc[1]]);
+            let calculated = crc16::calculate(&[data.as_slice(), &[frame_end]].concat());
+            if received != calculated {
+                return Err(ZmodemError::CrcMismatch {
+                    expected: u32::from(calculated),
+                    actual: u32::from(received),
+                });
             }
         }
+
+        Ok((data, frame_end))
     }
 
-    /// Wait for ZEOF frame.
-    async fn wait_for_eof(&mut self, stats: &mut ReceiveStats) -> Result<()> {
+    /// Read one byte of a subpacket, undoing ZDLE escapes.
+    ///
+    /// Unescaped XON/XOFF are flow control inserted by the link, not data.
+    async fn read_subpacket_byte(&mut self, deadline: Instant) -> Result<SubpacketByte> {
         loop {
-            let frame = self
-                .wait_for_frame(Duration::from_millis(self.config.timeout_ms))
-                .await?;
-
-            match frame.frame_type {
-                FrameType::ZEOF => {
-                    let eof_pos = frame.flags_as_u32() as u64;
-                    if eof_pos == stats.bytes_received {
-                        self.state.advance(ZmodemState::FileComplete);
-                        return Ok(());
-                    } else {
-                        // Wrong EOF position, request correct position
-                        self.send_position(stats.bytes_received).await?;
-                    }
-                }
-                FrameType::ZDATA => {
-                    // More data, process it
-                    // In practice, this shouldn't happen if we're waiting for EOF
-                    continue;
-                }
-                FrameType::ZCAN | FrameType::ZABORT => {
-                    return Err(ZmodemError::Cancelled);
-                }
-                _ => {
-                    continue;
+            match self.read_byte(deadline).await? {
+                ZDLE => {
+                    return match self.read_byte(deadline).await? {
+                        end @ (ZCRCE | ZCRCG | ZCRCQ | ZCRCW) => Ok(SubpacketByte::FrameEnd(end)),
+                        ZRUB0 => Ok(SubpacketByte::Data(0x7F)),
+                        ZRUB1 => Ok(SubpacketByte::Data(0xFF)),
+                        byte if byte & 0x60 == 0x40 => Ok(SubpacketByte::Data(byte ^ 0x40)),
+                        _ => Err(ZmodemError::InvalidEscape),
+                    };
                 }
+                byte if byte & 0x7F == XON || byte & 0x7F == XOFF => continue,
+                byte => return Ok(SubpacketByte::Data(byte)),
             }
         }
     }
@@ -884,49 +912,78 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemReceiver<S> {
     }
 
     /// Wait for a specific frame type.
-    async fn wait_for_frame_type(
-        &mut self,
-        expected_type: FrameType,
-        timeout_duration: Duration,
-    ) -> Result<ZmodemFrame> {
-        let result = timeout(timeout_duration, async {
-            loop {
-                let frame = self.wait_for_frame(timeout_duration).await?;
-                if frame.frame_type == expected_type {
-                    return Ok(frame);
-                }
-                // Handle unexpected frames (ZCAN, ZABORT)
-                if matches!(frame.frame_type, FrameType::ZCAN | FrameType::ZABORT) {
-                    return Err(ZmodemError::Cancelled);
-                }
+    async fn wait_for_frame_type(&mut self, expected_type: FrameType) -> Result<ZmodemFrame> {
+        loop {
+            let frame = self.read_header().await?;
+            if frame.frame_type == expected_type {
+                return Ok(frame);
             }
-        })
-        .await
-        .map_err(|_| ZmodemError::Timeout)??;
+            // Handle unexpected frames (ZCAN, ZABORT)
+            if matches!(frame.frame_type, FrameType::ZCAN | FrameType::ZABORT) {
+                return Err(ZmodemError::Cancelled);
+            }
+        }
+    }
 
-        Ok(result)
+    /// Read the next frame header.
+    ///
+    /// Bytes are fed to the parser one at a time so that a subpacket
+    /// following the header stays in the receive buffer. Anything before
+    /// the header is line noise.
+    async fn read_header(&mut self) -> Result<ZmodemFrame> {
+        if let Some(frame) = self.pending.take() {
+            return Ok(frame);
+        }
+
+        let deadline = Instant::now() + Duration::from_millis(self.config.timeout_ms);
+        loop {
+            let byte = self.read_byte(deadline).await?;
+            if let Some(frame) = self.parser.feed(&[byte]).into_iter().next() {
+                return frame;
+            }
+        }
     }
 
-    /// Wait for any frame.
-    async fn wait_for_frame(&mut self, timeout_duration: Duration) -> Result<ZmodemFrame> {
-        let result = timeout(timeout_duration, async {
-            let mut buf = vec![0u8; 256];
-            loop {
-                let n = self.stream.read(&mut buf).await?;
-                if n == 0 {
-                    return Err(ZmodemError::UnexpectedEof);
+    /// Read one byte from the sender.
+    ///
+    /// A run of CAN bytes aborts with [`ZmodemError::Cancelled`]; inside
+    /// subpackets CAN is always escaped, so it only appears in a run when
+    /// the sender gives up.
+    async fn read_byte(&mut self, deadline: Instant) -> Result<u8> {
+        if self.rx_pos == self.rx_buffer.len() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            self.rx_buffer.resize(RX_BUFFER_SIZE, 0);
+            self.rx_pos = 0;
+            let read = timeout(remaining, self.stream.read(&mut self.rx_buffer)).await;
+            let n = match read {
+                Ok(Ok(n)) => n,
+                Ok(Err(e)) => {
+                    self.rx_buffer.clear();
+                    return Err(e.into());
                 }
-
-                let frames = self.parser.feed(&buf[..n]);
-                if let Some(frame_result) = frames.into_iter().next() {
-                    return frame_result;
+                Err(_) => {
+                    self.rx_buffer.clear();
+                    return Err(ZmodemError::Timeout);
                 }
+            };
+            self.rx_buffer.truncate(n);
+            if n == 0 {
+                return Err(ZmodemError::UnexpectedEof);
             }
-        })
-        .await
-        .map_err(|_| ZmodemError::Timeout)??;
+        }
 
-        Ok(result)
+        let byte = self.rx_buffer[self.rx_pos];
+        self.rx_pos += 1;
+
+        self.cancel_count = if byte == ZDLE {
+            self.cancel_count + 1
+        } else {
+            0
+        };
+        if self.cancel_count >= ABORT_CAN_COUNT {
+            return Err(ZmodemError::Cancelled);
+        }
+        Ok(byte)
     }
 
     /// Get frame encoding based on negotiated parameters.
@@ -970,11 +1027,34 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemReceiver<S> {
         let cancel_seq = [0x18, 0x18, 0x18, 0x18, 0x18, 0x08, 0x08, 0x08, 0x08, 0x08];
         self.stream.write_all(&cancel_seq).await?;
         self.stream.flush().await?;
-        self.state.advance(ZmodemState::SessionComplete);
+        self.state.reset();
         Ok(())
     }
 }
 
+/// Errors the sender recovers from when asked to resend.
+fn is_line_error(error: &ZmodemError) -> bool {
+    matches!(
+        error,
+        ZmodemError::Timeout
+            | ZmodemError::CrcMismatch { .. }
+            | ZmodemError::InvalidEscape
+            | ZmodemError::InvalidFrame(_)
+            | ZmodemError::InvalidFrameType(_)
+            | ZmodemError::InvalidFrameEncoding(_)
+    )
+}
+
+/// The final component of a sender-supplied file name.
+///
+/// Senders may include directories (with either separator); only the base
+/// name is used so a file can never be written outside the output
+/// directory.
+fn safe_file_name(name: &str) -> Option<&str> {
+    let base = name.rsplit(['/', '\\']).next()?.trim();
+    (!base.is_empty() && base != "." && base != "..").then_some(base)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1202,4 +1282,51 @@ mod tests {
         let receiver = ZmodemReceiver::new(stream, config);
         assert_eq!(receiver.frame_encoding(), FrameEncoding::Bin16);
     }
+
+    #[test]
+    fn test_safe_file_name() {
+        assert_eq!(safe_file_name("game.zip"), Some("game.zip"));
+        assert_eq!(safe_file_name("../../etc/passwd"), Some("passwd"));
+        assert_eq!(safe_file_name("C:\\DOS\\GAME.ZIP"), Some("GAME.ZIP"));
+        assert_eq!(safe_file_name("uploads/.."), None);
+        assert_eq!(safe_file_name(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_receive_from_sender() {
+        use crate::zmodem::{SenderConfig, ZmodemSender};
+
+        let dir = std::env::temp_dir().join(format!("impulse-zrecv-{}", std::process::id()));
+        let (source, target) = (dir.join("source"), dir.join("target"));
+        std::fs::create_dir_all(&source).unwrap();
+        std::fs::create_dir_all(&target).unwrap();
+
+        // Every byte value, so escaping of ZDLE, XON/XOFF and DEL is covered
+        let payload: Vec<u8> = (0..5000u32).map(|i| (i * 7 % 256) as u8).collect();
+        let path = source.join("test.bin");
+        std::fs::write(&path, &payload).unwrap();
+
+        let (sender_end, receiver_end) = tokio::io::duplex(8192);
+        let sender = tokio::spawn(async move {
+            let mut sender = ZmodemSender::new(sender_end, SenderConfig::default());
+            sender.init().await.unwrap();
+            let stats = sender.send_file(&path).await.unwrap();
+            sender.finish().await.unwrap();
+            stats
+        });
+
+        let mut receiver = ZmodemReceiver::new(receiver_end, ReceiverConfig::default());
+        receiver.init().await.unwrap();
+        let files = receiver.receive_files(&target).await.unwrap();
+        receiver.finish().await.unwrap();
+
+        let stats = sender.await.unwrap();
+        assert_eq!(stats.bytes_sent, 5000);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].file_info.name, "test.bin");
+        assert_eq!(files[0].stats.bytes_received, 5000);
+        assert_eq!(std::fs::read(&files[0].saved_path).unwrap(), payload);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -40,14 +40,16 @@
 //! ```
 
 use super::error::{Result, ZmodemError};
-use super::escape::{self, ZCRCG, ZCRCQ, ZCRCW, ZDLE};
-use super::file::ZmodemFileInfo;
-use super::frame::{FrameEncoding, FrameType, ZmodemFrame};
+use super::escape::{self, ZCRCE, ZCRCG, ZCRCQ, ZCRCW, ZDLE};
+use super::file::{ZCBIN, ZCRESUM, ZmodemFileInfo};
+use super::frame::{FrameEncoding, FrameType, ZF0, ZmodemFrame};
 use super::init::ZmodemInit;
 use super::negotiate::{CrcType, NegotiatedParams};
 use super::parser::FrameParser;
+use super::progress::{FileProgress, NoOpProgress, TransferProgress};
 use super::state::{ZmodemState, ZmodemStateMachine};
 use super::{crc16, crc32};
+use std::collections::VecDeque;
 use std::io::SeekFrom;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
@@ -55,6 +57,13 @@ use tokio::fs::File;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
 use tokio::time::timeout;
 
+/// Data subpackets streamed between acknowledgements when the receiver
+/// does not limit its buffer.
+const STREAM_WINDOW: usize = 16;
+
+/// Consecutive CAN bytes that abort a session.
+pub(crate) const ABORT_CAN_COUNT: usize = 5;
+
 /// Configuration for Zmodem sender.
 ///
 /// # Examples
@@ -89,6 +98,10 @@ pub struct SenderConfig {
 
     /// Escape 8-bit characters (default: false)
     pub escape_8bit: bool,
+
+    /// Offer crash recovery, letting the receiver resume a partial file
+    /// (default: false)
+    pub resume: bool,
 }
 
 impl Default for SenderConfig {
@@ -100,6 +113,7 @@ impl Default for SenderConfig {
             use_crc32: true,
             escape_control: false,
             escape_8bit: false,
+            resume: false,
         }
     }
 }
@@ -131,6 +145,9 @@ pub struct TransferStats {
 
     /// Number of retransmissions
     pub retries: u32,
+
+    /// Offset the receiver asked to start from (non-zero when resuming)
+    pub start_position: u64,
 }
 
 impl TransferStats {
@@ -142,6 +159,7 @@ impl TransferStats {
             start_time: Instant::now(),
             end_time: None,
             retries: 0,
+            start_position: 0,
         }
     }
 
@@ -175,6 +193,11 @@ impl TransferStats {
     pub fn complete(&mut self) {
         self.end_time = Some(Instant::now());
     }
+
+    /// Check whether the receiver resumed a partial file.
+    pub fn was_resumed(&self) -> bool {
+        self.start_position > 0
+    }
 }
 
 /// Zmodem file sender.
@@ -210,6 +233,8 @@ pub struct ZmodemSender<S> {
     state: ZmodemStateMachine,
     config: SenderConfig,
     negotiated: Option<NegotiatedParams>,
+    pending: VecDeque<ZmodemFrame>,
+    cancel_count: usize,
 }
 
 impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemSender<S> {
@@ -239,6 +264,8 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemSender<S> {
             state: ZmodemStateMachine::new(),
             config,
             negotiated: None,
+            pending: VecDeque::new(),
+            cancel_count: 0,
         }
     }
 
@@ -276,18 +303,27 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemSender<S> {
             buffer_size: self.config.block_size as u16,
         };
 
-        // Send ZRQINIT
+        // "rz\r" starts the receiver on terminals that auto-detect downloads;
+        // ZRQINIT is repeated until the receiver answers
+        self.stream.write_all(b"rz\r").await?;
         let zrqinit = ZmodemInit::create_zrqinit();
-        self.send_frame(&zrqinit).await?;
         self.state.advance(ZmodemState::InitSent);
 
-        // Wait for ZRINIT response
-        let zrinit = self
-            .wait_for_frame_type(
-                FrameType::ZRINIT,
-                Duration::from_millis(self.config.timeout_ms),
-            )
-            .await?;
+        let mut attempts = 0;
+        let zrinit = loop {
+            self.send_frame(&zrqinit).await?;
+            match self
+                .wait_for_frame_type(
+                    FrameType::ZRINIT,
+                    Duration::from_millis(self.config.timeout_ms),
+                )
+                .await
+            {
+                Ok(frame) => break frame,
+                Err(ZmodemError::Timeout) if attempts < self.config.max_retries => attempts += 1,
+                Err(e) => return Err(e),
+            }
+        };
 
         // Parse receiver capabilities from ZRINIT
         let receiver_init = ZmodemInit::from_zrinit(&zrinit)?;
@@ -334,6 +370,54 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemSender<S> {
     /// # }
     /// ```
     pub async fn send_file(&mut self, path: &Path) -> Result<TransferStats> {
+        self.send_file_with_progress(path, &mut NoOpProgress).await
+    }
+
+    /// Send a single file, reporting progress as blocks are acknowledged.
+    ///
+    /// Behaves like [`send_file`](Self::send_file), calling `progress` when
+    /// the file starts, after every block, and when the file completes or
+    /// fails.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use impulse_protocol::zmodem::send::{ZmodemSender, SenderConfig};
+    /// # use impulse_protocol::zmodem::progress::ConsoleProgress;
+    /// # use tokio::net::TcpStream;
+    /// # use std::path::Path;
+    /// # async fn example() -> impulse_protocol::zmodem::Result<()> {
+    /// # let stream = TcpStream::connect("127.0.0.1:2323").await?;
+    /// # let mut sender = ZmodemSender::new(stream, SenderConfig::default());
+    /// # sender.init().await?;
+    /// let mut progress = ConsoleProgress::new();
+    /// let stats = sender
+    ///     .send_file_with_progress(Path::new("document.pdf"), &mut progress)
+    ///     .await?;
+    /// if stats.was_resumed() {
+    ///     println!("Resumed at byte {}", stats.start_position);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_file_with_progress<P: TransferProgress + ?Sized>(
+        &mut self,
+        path: &Path,
+        progress: &mut P,
+    ) -> Result<TransferStats> {
+        let result = self.transmit_file(path, progress).await;
+        if let Err(e) = &result {
+            progress.on_error(e);
+        }
+        result
+    }
+
+    /// Transmit one file from header to EOF.
+    async fn transmit_file<P: TransferProgress + ?Sized>(
+        &mut self,
+        path: &Path,
+        progress: &mut P,
+    ) -> Result<TransferStats> {
         // Open file
         let mut file = File::open(path).await?;
         let metadata = file.metadata().await?;
@@ -352,21 +436,34 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemSender<S> {
 
         // Send file header and wait for position
         let start_pos = self.send_file_header(&file_info).await?;
+        progress.on_file_start(&file_info);
 
         // Seek to start position if resuming
         if start_pos > 0 {
             file.seek(SeekFrom::Start(start_pos)).await?;
             stats.bytes_sent = start_pos;
+            stats.start_position = start_pos;
         }
 
-        // Send file data
-        self.send_file_data(&mut file, start_pos, &mut stats)
-            .await?;
-
-        // Send EOF
-        self.send_eof(file_size, &mut stats).await?;
+        // Send file data and EOF
+        let mut file_progress = FileProgress {
+            file_index: 0,
+            total_files: 1,
+            file_path: path.to_path_buf(),
+            file_name: file_info.name.clone(),
+            bytes_sent: stats.bytes_sent,
+            bytes_total: file_size,
+            retries: 0,
+        };
+        self.send_file_data(&mut file, start_pos, &mut stats, |stats| {
+            file_progress.bytes_sent = stats.bytes_sent;
+            file_progress.retries = stats.retries;
+            progress.on_progress(&file_progress);
+        })
+        .await?;
 
         stats.complete();
+        progress.on_file_complete(&file_info, &stats);
         Ok(stats)
     }
 
@@ -440,10 +537,27 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemSender<S> {
     /// # }
     /// ```
     pub async fn finish(&mut self) -> Result<()> {
-        let zfin = ZmodemFrame::with_defaults(FrameType::ZFIN, self.frame_encoding());
+        let zfin = ZmodemFrame::with_defaults(FrameType::ZFIN, FrameEncoding::Hex);
         self.send_frame(&zfin).await?;
         self.state.advance(ZmodemState::SessionComplete);
-        Ok(())
+
+        // The receiver answers with its own ZFIN and waits for "over and out";
+        // one that has already gone away needs nothing more
+        match self
+            .wait_for_frame_type(
+                FrameType::ZFIN,
+                Duration::from_millis(self.config.timeout_ms),
+            )
+            .await
+        {
+            Ok(_) => {
+                self.stream.write_all(b"OO").await?;
+                self.stream.flush().await?;
+                Ok(())
+            }
+            Err(ZmodemError::Timeout | ZmodemError::UnexpectedEof) => Ok(()),
+            Err(e) => Err(e),
+        }
     }
 
     /// Send a frame to the stream.
@@ -479,7 +593,14 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemSender<S> {
     }
 
     /// Wait for any frame.
+    ///
+    /// Damaged headers are dropped (the receiver repeats them), and a run of
+    /// CAN bytes aborts with [`ZmodemError::Cancelled`].
     async fn wait_for_frame(&mut self, timeout_duration: Duration) -> Result<ZmodemFrame> {
+        if let Some(frame) = self.pending.pop_front() {
+            return Ok(frame);
+        }
+
         let result = timeout(timeout_duration, async {
             let mut buf = vec![0u8; 256];
             loop {
@@ -488,9 +609,22 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemSender<S> {
                     return Err(ZmodemError::UnexpectedEof);
                 }
 
+                for &byte in &buf[..n] {
+                    self.cancel_count = if byte == ZDLE {
+                        self.cancel_count + 1
+                    } else {
+                        0
+                    };
+                    if self.cancel_count >= ABORT_CAN_COUNT {
+                        return Err(ZmodemError::Cancelled);
+                    }
+                }
+
                 let frames = self.parser.feed(&buf[..n]);
-                if let Some(frame_result) = frames.into_iter().next() {
-                    return frame_result;
+                self.pending
+                    .extend(frames.into_iter().filter_map(|frame| frame.ok()));
+                if let Some(frame) = self.pending.pop_front() {
+                    return Ok(frame);
                 }
             }
         })
@@ -506,169 +640,216 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ZmodemSender<S> {
     async fn send_file_header(&mut self, file_info: &ZmodemFileInfo) -> Result<u64> {
         self.state.set_current_file(file_info.clone());
 
-        let zfile = file_info.to_zfile_frame();
-        self.send_frame(&zfile).await?;
+        let mut zfile = file_info.to_zfile_frame();
+        zfile.encoding = self.frame_encoding();
+        zfile.flags[ZF0] = if self.config.resume { ZCRESUM } else { ZCBIN };
+        let info = file_info.serialize();
         self.state.advance(ZmodemState::FileHeaderSent);
 
-        // Wait for ZRPOS or ZSKIP
-        let response = self
-            .wait_for_frame(Duration::from_millis(self.config.timeout_ms))
-            .await?;
-
-        match response.frame_type {
-            FrameType::ZRPOS => {
-                // Extract position from flags
-                let pos = response.flags_as_u32() as u64;
-                self.state.set_position(pos);
-                self.state.advance(ZmodemState::DataTransfer);
-                Ok(pos)
-            }
-            FrameType::ZSKIP => {
-                // Receiver wants to skip this file
-                self.state.clear_current_file();
-                self.state.advance(ZmodemState::InitReceived);
-                Err(ZmodemError::InvalidFrame(
-                    "File skipped by receiver".to_string(),
-                ))
+        let mut attempts = 0;
+        loop {
+            self.send_frame(&zfile).await?;
+            self.send_subpacket(&info, ZCRCW).await?;
+
+            let response = match self
+                .wait_for_frame(Duration::from_millis(self.config.timeout_ms))
+                .await
+            {
+                Ok(response) => response,
+                Err(ZmodemError::Timeout) if attempts < self.config.max_retries => {
+                    attempts += 1;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            match response.frame_type {
+                FrameType::ZRPOS => {
+                    // Extract position from flags
+                    let pos = response.flags_as_u32() as u64;
+                    self.state.set_position(pos);
+                    self.state.advance(ZmodemState::DataTransfer);
+                    return Ok(pos);
+                }
+                FrameType::ZSKIP => {
+                    // Receiver wants to skip this file
+                    self.state.clear_current_file();
+                    self.state.advance(ZmodemState::InitReceived);
+                    return Err(ZmodemError::InvalidFrame(
+                        "File skipped by receiver".to_string(),
+                    ));
+                }
+                FrameType::ZCAN | FrameType::ZABORT => return Err(ZmodemError::Cancelled),
+                // A repeated ZRINIT or a ZNAK means the header was lost
+                FrameType::ZRINIT | FrameType::ZNAK if attempts < self.config.max_retries => {
+                    attempts += 1;
+                }
+                FrameType::ZRINIT | FrameType::ZNAK => {
+                    return Err(ZmodemError::MaxRetriesExceeded);
+                }
+                _ => {}
             }
-            _ => Err(ZmodemError::InvalidFrame(format!(
-                "Unexpected response to ZFILE: {:?}",
-                response.frame_type
-            ))),
         }
     }
 
-    /// Send file data.
+    /// Send file data followed by ZEOF.
+    ///
+    /// Data subpackets are streamed after a single ZDATA header, with a
+    /// ZCRCQ acknowledgement requested once per window. Whenever the
+    /// receiver asks for a retransmission with ZRPOS, sending restarts from
+    /// that offset under a new ZDATA header. `on_block` is called with the
+    /// updated statistics after every block.
     async fn send_file_data(
         &mut self,
         file: &mut File,
         start_pos: u64,
         stats: &mut TransferStats,
+        mut on_block: impl FnMut(&TransferStats),
     ) -> Result<()> {
-        let mut position = start_pos;
         let file_size = stats.bytes_total;
-        let mut retries = 0;
-
-        while position < file_size {
-            // Read block
-            let block_size = self.config.block_size.min((file_size - position) as usize);
-            let mut buffer = vec![0u8; block_size];
-            let bytes_read = file.read(&mut buffer).await?;
-            buffer.truncate(bytes_read);
-
-            // Determine block type
-            let block_type = if position + bytes_read as u64 >= file_size {
-                ZCRCW // End of file, wait for ACK
-            } else {
-                ZCRCG // More data coming
-            };
+        let block_size = self.config.block_size.max(1);
+        let window = match self.negotiated.as_ref().map_or(0, |p| p.buffer_size) {
+            0 => STREAM_WINDOW,
+            buffer_size => (usize::from(buffer_size) / block_size).max(1),
+        };
+        let mut position = start_pos;
+        let mut buffer = vec![0u8; block_size];
 
-            // Send data block
-            match self.send_data_block(&buffer, position, block_type).await {
-                Ok(()) => {
+        loop {
+            let mut resend_from = None;
+
+            if position < file_size {
+                file.seek(SeekFrom::Start(position)).await?;
+                let mut zdata = ZmodemFrame::with_defaults(FrameType::ZDATA, self.frame_encoding());
+                zdata.set_flags_from_u32(position as u32);
+                self.send_frame(&zdata).await?;
+
+                let mut unacknowledged = 0;
+                while position < file_size {
+                    let wanted = block_size.min((file_size - position) as usize);
+                    let bytes_read = file.read(&mut buffer[..wanted]).await?;
+                    if bytes_read == 0 {
+                        return Err(ZmodemError::UnexpectedEof);
+                    }
+
+                    unacknowledged += 1;
+                    let frame_end = if position + bytes_read as u64 >= file_size {
+                        ZCRCE // End of file, ZEOF follows
+                    } else if unacknowledged >= window {
+                        ZCRCQ // Continue once acknowledged
+                    } else {
+                        ZCRCG // More data coming
+                    };
+
+                    self.send_subpacket(&buffer[..bytes_read], frame_end)
+                        .await?;
                     position += bytes_read as u64;
                     stats.bytes_sent = position;
-                    retries = 0;
-                }
-                Err(ZmodemError::Cancelled) => {
-                    return Err(ZmodemError::Cancelled);
+                    on_block(stats);
+
+                    if frame_end == ZCRCQ {
+                        unacknowledged = 0;
+                        resend_from = self.wait_for_data_ack().await?;
+                        if resend_from.is_some() {
+                            break;
+                        }
+                    }
                 }
-                Err(_) if retries < self.config.max_retries => {
-                    // Handle retransmission request
-                    retries += 1;
-                    stats.retries += 1;
-
-                    // Wait for ZRPOS
-                    let response = self
-                        .wait_for_frame_type(
-                            FrameType::ZRPOS,
-                            Duration::from_millis(self.config.timeout_ms),
-                        )
-                        .await?;
+            }
 
-                    let retry_pos = response.flags_as_u32() as u64;
-                    file.seek(SeekFrom::Start(retry_pos)).await?;
-                    position = retry_pos;
-                    stats.bytes_sent = retry_pos;
-                }
-                Err(e) => return Err(e),
+            if resend_from.is_none() {
+                resend_from = self.send_eof(file_size).await?;
             }
-        }
 
-        Ok(())
+            // Retransmit from where the receiver lost data
+            let Some(retry_pos) = resend_from else {
+                return Ok(());
+            };
+            stats.retries += 1;
+            if stats.retries > self.config.max_retries {
+                return Err(ZmodemError::MaxRetriesExceeded);
+            }
+            position = retry_pos.min(file_size);
+            stats.bytes_sent = position;
+        }
     }
 
-    /// Send a data block with CRC.
-    async fn send_data_block(&mut self, data: &[u8], position: u64, block_type: u8) -> Result<()> {
-        // Send ZDATA frame
-        let mut zdata = ZmodemFrame::with_defaults(FrameType::ZDATA, self.frame_encoding());
-        zdata.set_flags_from_u32(position as u32);
-        self.send_frame(&zdata).await?;
-
-        // Encode data
-        let encoded_data = escape::encode(data);
-
-        // Send encoded data
-        self.stream.write_all(&encoded_data).await?;
-
-        // Send block type (ZCRCE, ZCRCG, ZCRCQ, ZCRCW)
-        self.stream.write_all(&[ZDLE, block_type]).await?;
+    /// Send a data subpacket ending with `frame_end`.
+    ///
+    /// The CRC covers the data and the frame end byte.
+    async fn send_subpacket(&mut self, data: &[u8], frame_end: u8) -> Result<()> {
+        let mut packet = escape::encode(data);
+        packet.extend_from_slice(&[ZDLE, frame_end]);
 
-        // Calculate and send CRC
         let crc = if self.use_crc32() {
-            let crc_val = crc32::calculate(data);
+            let crc_val = crc32::finalize(crc32::update(
+                crc32::update(0xFFFF_FFFF, data),
+                &[frame_end],
+            ));
             crc_val.to_le_bytes().to_vec()
         } else {
-            let crc_val = crc16::calculate(data);
+            let crc_val = crc16::calculate(&[data, &[frame_end]].concat());
             crc_val.to_be_bytes().to_vec()
         };
+        packet.extend_from_slice(&escape::encode(&crc));
 
-        let encoded_crc = escape::encode(&crc);
-        self.stream.write_all(&encoded_crc).await?;
+        self.stream.write_all(&packet).await?;
         self.stream.flush().await?;
+        Ok(())
+    }
 
-        // Wait for ACK if needed
-        if block_type == ZCRCW || block_type == ZCRCQ {
-            self.wait_for_frame_type(
-                FrameType::ZACK,
-                Duration::from_millis(self.config.timeout_ms),
-            )
-            .await?;
-        }
+    /// Wait for the ZACK to a ZCRCQ subpacket.
+    ///
+    /// Returns the retransmission offset if the receiver sent ZRPOS instead.
+    async fn wait_for_data_ack(&mut self) -> Result<Option<u64>> {
+        loop {
+            let response = self
+                .wait_for_frame(Duration::from_millis(self.config.timeout_ms))
+                .await?;
 
-        Ok(())
+            match response.frame_type {
+                FrameType::ZACK => return Ok(None),
+                FrameType::ZRPOS => return Ok(Some(response.flags_as_u32() as u64)),
+                FrameType::ZCAN | FrameType::ZABORT => return Err(ZmodemError::Cancelled),
+                _ => continue,
+            }
+        }
     }
 
     /// Send EOF and wait for acknowledgment.
-    async fn send_eof(&mut self, file_size: u64, _stats: &mut TransferStats) -> Result<()> {
+    ///
+    /// Returns the retransmission offset if the receiver is missing data.
+    async fn send_eof(&mut self, file_size: u64) -> Result<Option<u64>> {
         let mut zeof = ZmodemFrame::with_defaults(FrameType::ZEOF, self.frame_encoding());
         zeof.set_flags_from_u32(file_size as u32);
+
+        let mut attempts = 0;
         self.send_frame(&zeof).await?;
 
         loop {
-            let response = self
+            let response = match self
                 .wait_for_frame(Duration::from_millis(self.config.timeout_ms))
-                .await?;
+                .await
+            {
+                Ok(response) => response,
+                Err(ZmodemError::Timeout) if attempts < self.config.max_retries => {
+                    attempts += 1;
+                    self.send_frame(&zeof).await?;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
 
             match response.frame_type {
                 FrameType::ZRINIT => {
                     // Ready for next file
                     self.state.advance(ZmodemState::FileComplete);
                     self.state.advance(ZmodemState::InitReceived);
-                    return Ok(());
-                }
-                FrameType::ZRPOS => {
-                    // Need to retransmit
-                    // Retransmit from requested position
-                    let _pos = response.flags_as_u32() as u64;
-                    // Would need to reopen file and retransmit, for now return error
-                    return Err(ZmodemError::InvalidFrame(
-                        "EOF retransmission not yet implemented".to_string(),
-                    ));
-                }
-                _ => {
-                    continue;
+                    return Ok(None);
                 }
+                FrameType::ZRPOS => return Ok(Some(response.flags_as_u32() as u64)),
+                FrameType::ZCAN | FrameType::ZABORT => return Err(ZmodemError::Cancelled),
+                _ => continue,
             }
         }
     }
@@ -742,6 +923,15 @@ mod tests {
         assert!(stats.end_time.is_some());
     }
 
+    #[test]
+    fn test_transfer_stats_was_resumed() {
+        let mut stats = TransferStats::new(1024);
+        assert!(!stats.was_resumed());
+
+        stats.start_position = 512;
+        assert!(stats.was_resumed());
+    }
+
     #[test]
     fn test_transfer_stats_bytes_per_second() {
         let mut stats = TransferStats::new(1000);
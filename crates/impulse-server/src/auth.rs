@@ -4,11 +4,13 @@
 
 use crate::state::ServerState;
 use anyhow::Result;
-use impulse_auth::SessionToken;
-use impulse_telnet::TelnetConnection;
+use impulse_auth::flows::register::{RegistrationFlow, RegistrationRequest, RegistrationResult};
+use impulse_auth::{AuthError, SessionToken};
+use impulse_session::Connection;
 use impulse_terminal::{AnsiRenderer, Color};
+use impulse_types::error::Error;
 use impulse_types::user::User;
-use impulse_user::UserManager;
+use std::net::SocketAddr;
 use tracing::{info, warn};
 
 /// Authentication result
@@ -24,18 +26,21 @@ pub enum AuthResult {
 }
 
 /// Handle authentication flow (login or new user registration)
+///
+/// The caller is disconnected after `max_login_attempts` failed logins.
 pub async fn authenticate(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     state: &ServerState,
 ) -> Result<AuthResult> {
     let mut renderer = AnsiRenderer::new();
+    let mut failed_logins = 0u8;
 
     loop {
         // Display welcome screen
         renderer.clear_screen();
         display_welcome_screen(&mut renderer);
         connection
-            .send_raw(renderer.take_output().as_bytes())
+            .send_bytes(renderer.take_output().as_bytes())
             .await?;
 
         // Display login menu
@@ -57,7 +62,7 @@ pub async fn authenticate(
         renderer.write_text("Choice: ");
         renderer.reset();
         connection
-            .send_raw(renderer.take_output().as_bytes())
+            .send_bytes(renderer.take_output().as_bytes())
             .await?;
 
         // Read choice
@@ -69,27 +74,38 @@ pub async fn authenticate(
                 match choice {
                     'L' => {
                         // Login flow
-                        if let Some(result) = handle_login(connection, state, &mut renderer).await?
+                        if let Some(result) =
+                            handle_login(connection, state, &mut renderer, &mut failed_logins)
+                                .await?
                         {
                             return Ok(result);
                         }
+
+                        if failed_logins >= state.max_login_attempts {
+                            warn!(
+                                peer = %connection.remote_addr(),
+                                attempts = failed_logins,
+                                "Too many failed logins, disconnecting"
+                            );
+                            renderer.write_line("\r\n");
+                            renderer.set_foreground(Color::BrightRed);
+                            renderer.write_line("Too many failed login attempts. Goodbye!");
+                            renderer.reset();
+                            renderer.write_line("\r\n");
+                            connection
+                                .send_bytes(renderer.take_output().as_bytes())
+                                .await?;
+                            return Ok(AuthResult::Quit);
+                        }
                         // If login failed or cancelled, loop back to menu
                     }
                     'N' => {
                         // Registration flow
-                        renderer.write_line("\r\n");
-                        renderer.set_foreground(Color::BrightRed);
-                        renderer.write_line("New user registration is not yet implemented.");
-                        renderer.write_line("Please contact the SysOp for an account.");
-                        renderer.reset();
-                        renderer.write_line("\r\n");
-                        renderer.set_foreground(Color::BrightYellow);
-                        renderer.write_line("Press any key to continue...");
-                        renderer.reset();
-                        connection
-                            .send_raw(renderer.take_output().as_bytes())
-                            .await?;
-                        connection.read_char().await.ok();
+                        if let Some(result) =
+                            handle_registration(connection, state, &mut renderer).await?
+                        {
+                            return Ok(result);
+                        }
                     }
                     'Q' => {
                         // Quit
@@ -99,24 +115,18 @@ pub async fn authenticate(
                         renderer.reset();
                         renderer.write_line("\r\n");
                         connection
-                            .send_raw(renderer.take_output().as_bytes())
+                            .send_bytes(renderer.take_output().as_bytes())
                             .await?;
                         return Ok(AuthResult::Quit);
                     }
                     _ => {
                         // Invalid choice
-                        renderer.write_line("\r\n");
-                        renderer.set_foreground(Color::BrightRed);
-                        renderer.write_line("Invalid choice. Please try again.");
-                        renderer.reset();
-                        renderer.write_line("\r\n");
-                        renderer.set_foreground(Color::BrightYellow);
-                        renderer.write_line("Press any key to continue...");
-                        renderer.reset();
-                        connection
-                            .send_raw(renderer.take_output().as_bytes())
-                            .await?;
-                        connection.read_char().await.ok();
+                        show_error(
+                            connection,
+                            &mut renderer,
+                            "Invalid choice. Please try again.",
+                        )
+                        .await?;
                     }
                 }
             }
@@ -129,10 +139,13 @@ pub async fn authenticate(
 }
 
 /// Handle login flow
+///
+/// Increments `failed_logins` for every rejected password.
 async fn handle_login(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     state: &ServerState,
     renderer: &mut AnsiRenderer,
+    failed_logins: &mut u8,
 ) -> Result<Option<AuthResult>> {
     // Prompt for username
     renderer.write_line("\r\n");
@@ -140,140 +153,359 @@ async fn handle_login(
     renderer.write_line("=== LOGIN ===");
     renderer.reset();
     renderer.write_line("");
-    renderer.set_foreground(Color::BrightGreen);
-    renderer.write_text("Username: ");
-    renderer.reset();
-    connection
-        .send_raw(renderer.take_output().as_bytes())
-        .await?;
 
-    let username = match connection.read_line().await {
-        Ok(line) => line.trim().to_string(),
-        Err(_) => return Ok(Some(AuthResult::Quit)),
+    let Some(username) = prompt(connection, renderer, "Username: ", false).await? else {
+        return Ok(Some(AuthResult::Quit));
     };
 
     if username.is_empty() {
         renderer.clear();
-        renderer.write_line("\r\n");
-        renderer.set_foreground(Color::BrightRed);
-        renderer.write_line("Login cancelled.");
-        renderer.reset();
-        renderer.write_line("\r\n");
-        renderer.set_foreground(Color::BrightYellow);
-        renderer.write_line("Press any key to continue...");
-        renderer.reset();
-        connection
-            .send_raw(renderer.take_output().as_bytes())
-            .await?;
-        connection.read_char().await.ok();
+        show_error(connection, renderer, "Login cancelled.").await?;
         return Ok(None);
     }
 
-    // Prompt for password (add newline after username input)
-    renderer.clear();
-    renderer.write_line(""); // Add newline to move to next line
-    renderer.set_foreground(Color::BrightGreen);
-    renderer.write_text("Password: ");
-    renderer.reset();
-    connection
-        .send_raw(renderer.take_output().as_bytes())
-        .await?;
-
     // Read password securely (without echoing)
-    let _password = match connection.read_password(true).await {
-        Ok(line) => line.trim().to_string(),
-        Err(_) => return Ok(Some(AuthResult::Quit)),
+    let Some(password) = prompt(connection, renderer, "Password: ", true).await? else {
+        return Ok(Some(AuthResult::Quit));
     };
 
     renderer.clear();
 
     // Look up user
-    let user_manager = state.user_manager.read().await;
-    let user_opt = user_manager.find_by_username(&username).await?;
-
-    match user_opt {
-        Some(user) => {
-            // For demo purposes, accept any password for existing users
-            // In production, use state.auth_service.login(&user, &password, &stored_hash).await
-
-            // Create mock hash for demonstration
-            let hash = state.auth_service.hash_password("demo123").unwrap();
-
-            // Authenticate with demo password
-            match state.auth_service.login(&user, "demo123", &hash).await {
-                Ok(token) => {
-                    info!(
-                        username = %username,
-                        user_id = ?user.id(),
-                        "User logged in successfully"
-                    );
-
-                    renderer.write_line("\r\n");
-                    renderer.set_foreground(Color::BrightGreen);
-                    renderer.write_line(&format!("Login successful! Welcome back, {}!", username));
-                    renderer.reset();
-                    renderer.write_line("");
-                    renderer.set_foreground(Color::Yellow);
-                    renderer.write_line(&format!(
-                        "Security Level: {}",
-                        user.security_level().value()
-                    ));
-                    renderer.reset();
-                    renderer.write_line("\r\n");
-                    renderer.set_foreground(Color::BrightYellow);
-                    renderer.write_line("Press any key to continue...");
-                    renderer.reset();
-                    connection
-                        .send_raw(renderer.take_output().as_bytes())
-                        .await?;
-                    connection.read_char().await.ok();
+    let user_opt = state
+        .user_manager
+        .read()
+        .await
+        .find_by_username(&username)
+        .await?;
 
-                    Ok(Some(AuthResult::Authenticated {
-                        user: Box::new(user),
-                        token,
-                    }))
-                }
-                Err(e) => {
-                    warn!(username = %username, error = %e, "Login failed");
-
-                    renderer.write_line("\r\n");
-                    renderer.set_foreground(Color::BrightRed);
-                    renderer.write_line("Login failed: Invalid credentials");
-                    renderer.reset();
-                    renderer.write_line("\r\n");
-                    renderer.set_foreground(Color::BrightYellow);
-                    renderer.write_line("Press any key to continue...");
-                    renderer.reset();
-                    connection
-                        .send_raw(renderer.take_output().as_bytes())
-                        .await?;
-                    connection.read_char().await.ok();
+    // Unknown users get the same answer as a wrong password
+    let Some(user) = user_opt else {
+        warn!(username = %username, "Login failed: user not found");
+        state.auth_service.reject_unknown(&password);
+        *failed_logins += 1;
+        show_error(
+            connection,
+            renderer,
+            "Login failed: Invalid username or password",
+        )
+        .await?;
+        return Ok(None);
+    };
 
-                    Ok(None)
-                }
-            }
-        }
-        None => {
-            warn!(username = %username, "Login failed: user not found");
+    match verify_password(state, &user, &password).await {
+        Ok(token) => {
+            info!(
+                username = %username,
+                user_id = ?user.id(),
+                "User logged in successfully"
+            );
 
             renderer.write_line("\r\n");
-            renderer.set_foreground(Color::BrightRed);
-            renderer.write_line("Login failed: User not found");
+            renderer.set_foreground(Color::BrightGreen);
+            renderer.write_line(&format!(
+                "Login successful! Welcome back, {}!",
+                user.username()
+            ));
+            renderer.reset();
+            renderer.write_line("");
+            renderer.set_foreground(Color::Yellow);
+            renderer.write_line(&format!(
+                "Security Level: {}",
+                user.security_level().value()
+            ));
             renderer.reset();
             renderer.write_line("\r\n");
             renderer.set_foreground(Color::BrightYellow);
             renderer.write_line("Press any key to continue...");
             renderer.reset();
             connection
-                .send_raw(renderer.take_output().as_bytes())
+                .send_bytes(renderer.take_output().as_bytes())
                 .await?;
             connection.read_char().await.ok();
 
+            Ok(Some(AuthResult::Authenticated {
+                user: Box::new(user),
+                token,
+            }))
+        }
+        Err(e) => {
+            warn!(username = %username, error = %e, "Login failed");
+            *failed_logins += 1;
+
+            // Lockout and rate limiting are reported as generic errors
+            let message = match e {
+                AuthError::Generic(_) => "Too many failed attempts. Please try again later.",
+                _ => "Login failed: Invalid username or password",
+            };
+            show_error(connection, renderer, message).await?;
+
             Ok(None)
         }
     }
 }
 
+/// Handle new user registration
+///
+/// The account is stored with a hash of the chosen password and the caller
+/// is logged straight in.
+async fn handle_registration(
+    connection: &mut dyn Connection,
+    state: &ServerState,
+    renderer: &mut AnsiRenderer,
+) -> Result<Option<AuthResult>> {
+    let flow = RegistrationFlow::new(state.auth_service.clone())
+        .with_min_password_strength(state.min_password_strength);
+
+    renderer.write_line("\r\n");
+    renderer.set_foreground(Color::BrightWhite);
+    renderer.write_line("=== NEW USER REGISTRATION ===");
+    renderer.reset();
+    renderer.write_line("");
+
+    let Some(username) = prompt(connection, renderer, "Username: ", false).await? else {
+        return Ok(Some(AuthResult::Quit));
+    };
+
+    if username.is_empty() {
+        renderer.clear();
+        show_error(connection, renderer, "Registration cancelled.").await?;
+        return Ok(None);
+    }
+
+    if state
+        .user_manager
+        .read()
+        .await
+        .find_by_username(&username)
+        .await?
+        .is_some()
+    {
+        show_error(connection, renderer, "That username is already taken.").await?;
+        return Ok(None);
+    }
+
+    renderer.set_foreground(Color::Yellow);
+    renderer.write_line(&flow.password_requirements());
+    renderer.reset();
+
+    let Some(password) = prompt(connection, renderer, "Password: ", true).await? else {
+        return Ok(Some(AuthResult::Quit));
+    };
+    let Some(password_confirm) = prompt(connection, renderer, "Confirm password: ", true).await?
+    else {
+        return Ok(Some(AuthResult::Quit));
+    };
+
+    renderer.clear();
+
+    let request = RegistrationRequest::new(username, password.clone(), password_confirm);
+
+    let message = match flow.execute(&request).await {
+        RegistrationResult::Success { user } => {
+            return complete_registration(connection, state, renderer, user, &password).await;
+        }
+        RegistrationResult::UsernameExists => "That username is already taken.".to_string(),
+        RegistrationResult::UsernameTooShort => "That username is too short.".to_string(),
+        RegistrationResult::UsernameTooLong => "That username is too long.".to_string(),
+        RegistrationResult::UsernameInvalidChars => {
+            "Usernames may only contain letters, numbers, '_' and '-'.".to_string()
+        }
+        RegistrationResult::PasswordTooWeak { requirements, .. } => requirements,
+        RegistrationResult::PasswordMismatch => "Passwords do not match.".to_string(),
+        RegistrationResult::EmailInvalid => "That email address is invalid.".to_string(),
+        RegistrationResult::ValidationError(e) => e,
+    };
+
+    show_error(connection, renderer, &message).await?;
+    Ok(None)
+}
+
+/// Store a newly registered account and log the caller in
+async fn complete_registration(
+    connection: &mut dyn Connection,
+    state: &ServerState,
+    renderer: &mut AnsiRenderer,
+    user: User,
+    password: &str,
+) -> Result<Option<AuthResult>> {
+    match state
+        .user_manager
+        .write()
+        .await
+        .create_user(user.clone())
+        .await
+    {
+        Ok(()) => {}
+        Err(Error::AlreadyExists(_)) => {
+            show_error(connection, renderer, "That username is already taken.").await?;
+            return Ok(None);
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    info!(
+        username = %user.username(),
+        user_id = ?user.id(),
+        "New user registered"
+    );
+
+    let token = match verify_password(state, &user, password).await {
+        Ok(token) => token,
+        Err(e) => {
+            warn!(username = %user.username(), error = %e, "Login after registration failed");
+            show_error(
+                connection,
+                renderer,
+                "Your account was created. Please log in.",
+            )
+            .await?;
+            return Ok(None);
+        }
+    };
+
+    renderer.write_line("\r\n");
+    renderer.set_foreground(Color::BrightGreen);
+    renderer.write_line(&format!(
+        "Account created! Welcome aboard, {}!",
+        user.username()
+    ));
+    renderer.reset();
+    renderer.write_line("\r\n");
+    renderer.set_foreground(Color::BrightYellow);
+    renderer.write_line("Press any key to continue...");
+    renderer.reset();
+    connection
+        .send_bytes(renderer.take_output().as_bytes())
+        .await?;
+    connection.read_char().await.ok();
+
+    Ok(Some(AuthResult::Authenticated {
+        user: Box::new(user),
+        token,
+    }))
+}
+
+/// Prompt for a line of input
+///
+/// Returns `None` if the caller disconnected. Input is trimmed; when `secret`
+/// is set it is read without echo and only the line ending is removed, since
+/// spaces are part of a password.
+async fn prompt(
+    connection: &mut dyn Connection,
+    renderer: &mut AnsiRenderer,
+    label: &str,
+    secret: bool,
+) -> Result<Option<String>> {
+    renderer.set_foreground(Color::BrightGreen);
+    renderer.write_text(label);
+    renderer.reset();
+    connection
+        .send_bytes(renderer.take_output().as_bytes())
+        .await?;
+
+    let line = if secret {
+        connection.read_password(true).await
+    } else {
+        // Move to the next line after the echoed input
+        let line = connection.read_line().await;
+        renderer.write_line("");
+        line
+    };
+
+    Ok(line.ok().map(|line| {
+        if secret {
+            line.trim_end_matches(['\r', '\n']).to_string()
+        } else {
+            line.trim().to_string()
+        }
+    }))
+}
+
+/// Show an error message and wait for a key
+async fn show_error(
+    connection: &mut dyn Connection,
+    renderer: &mut AnsiRenderer,
+    message: &str,
+) -> Result<()> {
+    renderer.write_line("\r\n");
+    renderer.set_foreground(Color::BrightRed);
+    renderer.write_line(message);
+    renderer.reset();
+    renderer.write_line("\r\n");
+    renderer.set_foreground(Color::BrightYellow);
+    renderer.write_line("Press any key to continue...");
+    renderer.reset();
+    connection
+        .send_bytes(renderer.take_output().as_bytes())
+        .await?;
+    connection.read_char().await.ok();
+    Ok(())
+}
+
+/// Check a password against the user's stored hash and open an auth session
+///
+/// Goes through `AuthService::login`, so account lockout and rate limiting
+/// apply. Accounts without a password hash cannot log in.
+async fn verify_password(
+    state: &ServerState,
+    user: &User,
+    password: &str,
+) -> std::result::Result<SessionToken, AuthError> {
+    let Some(hash) = user.password_hash.as_deref() else {
+        warn!(username = %user.username(), "Login refused: account has no password");
+        return Err(state.auth_service.reject_unknown(password));
+    };
+    state.auth_service.login(user, password, hash).await
+}
+
+/// Check the credentials an SSH caller gave during the handshake
+///
+/// Callers authenticate with the same credentials they would type at the
+/// telnet login prompt, so SSH sessions skip the login menu and go straight
+/// to the main menu.
+pub async fn authenticate_ssh(
+    state: &ServerState,
+    username: &str,
+    password: &str,
+    peer_addr: SocketAddr,
+) -> Option<(Box<User>, SessionToken)> {
+    let user = match state
+        .user_manager
+        .read()
+        .await
+        .find_by_username(username)
+        .await
+    {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            warn!(username = %username, peer = %peer_addr, "SSH login failed: user not found");
+            state.auth_service.reject_unknown(password);
+            return None;
+        }
+        Err(e) => {
+            warn!(username = %username, error = %e, "SSH login failed: user lookup error");
+            return None;
+        }
+    };
+
+    match verify_password(state, &user, password).await {
+        Ok(token) => {
+            info!(
+                username = %username,
+                user_id = ?user.id(),
+                peer = %peer_addr,
+                "User logged in via SSH"
+            );
+            Some((Box::new(user), token))
+        }
+        Err(e) => {
+            warn!(username = %username, peer = %peer_addr, error = %e, "SSH login failed");
+            None
+        }
+    }
+}
+
 /// Display welcome screen
 fn display_welcome_screen(renderer: &mut AnsiRenderer) {
     renderer.clear_screen();
@@ -0,0 +1,143 @@
+//! Session handler serving callers admitted by the BBS core
+//!
+//! Runs the login flow and main menu for every caller, whichever listener
+//! they arrived on.
+
+use crate::auth::{AuthResult, authenticate, authenticate_ssh};
+use crate::menus::{MenuSession, display_main_menu};
+use crate::state::ServerState;
+use async_trait::async_trait;
+use impulse_auth::SessionToken;
+use impulse_core::{Caller, SessionHandler};
+use impulse_session::SessionId;
+use impulse_types::user::User;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{error, info};
+
+/// Serves the BBS to each caller
+pub struct BbsHandler {
+    state: Arc<ServerState>,
+    /// Auth sessions of callers past the login screen
+    signed_in: std::sync::Mutex<HashMap<SessionId, SessionToken>>,
+}
+
+impl BbsHandler {
+    /// Create a handler backed by the server's state
+    pub fn new(state: Arc<ServerState>) -> Self {
+        Self {
+            state,
+            signed_in: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl SessionHandler for BbsHandler {
+    type Identity = (Box<User>, SessionToken);
+    type Error = anyhow::Error;
+
+    async fn authenticate(
+        &self,
+        username: &str,
+        password: &str,
+        peer_addr: SocketAddr,
+    ) -> Option<Self::Identity> {
+        authenticate_ssh(&self.state, username, password, peer_addr).await
+    }
+
+    /// Handle a single BBS connection
+    ///
+    /// SSH callers have already authenticated during the handshake and arrive
+    /// with an identity; telnet and web callers go through the login menu.
+    async fn handle_session(&self, caller: Caller<Self::Identity>) -> anyhow::Result<()> {
+        let Caller {
+            session_id,
+            mut connection,
+            identity,
+        } = caller;
+        info!(session_id = %session_id, "Starting connection handler");
+
+        // Authentication phase
+        let auth_result = match identity {
+            Some((user, token)) => AuthResult::Authenticated { user, token },
+            None => {
+                info!(session_id = %session_id, "Starting authentication");
+                authenticate(connection.as_mut(), &self.state).await?
+            }
+        };
+
+        let (user, token) = match auth_result {
+            AuthResult::Authenticated { user, token } => (user, token),
+            AuthResult::Quit => {
+                info!(session_id = %session_id, "User quit during authentication");
+                connection.close().await.ok();
+                return Ok(());
+            }
+        };
+
+        info!(
+            session_id = %session_id,
+            username = %user.username(),
+            user_id = ?user.id(),
+            "User authenticated successfully"
+        );
+
+        self.signed_in
+            .lock()
+            .unwrap()
+            .insert(session_id, token.clone());
+
+        // Main menu loop
+        let session = Arc::new(MenuSession {
+            connection: Mutex::new(connection),
+            user: *user,
+            session_id,
+            state: self.state.clone(),
+            session_manager: self.state.session_manager.clone(),
+        });
+        if let Err(e) = display_main_menu(session.clone()).await {
+            error!(
+                session_id = %session_id,
+                error = %e,
+                "Error in main menu"
+            );
+        }
+
+        // Logout
+        self.signed_in.lock().unwrap().remove(&session_id);
+        self.state.auth_service.logout(&token).await;
+        info!(
+            session_id = %session_id,
+            username = %session.user.username(),
+            "User logged out"
+        );
+
+        session.connection.lock().await.close().await.ok();
+
+        Ok(())
+    }
+
+    async fn reject(&self, (_, token): Self::Identity) {
+        self.state.auth_service.logout(&token).await;
+    }
+
+    async fn shutdown(&self) {
+        // Sessions aborted at the end of the grace period never reached
+        // their logout
+        let tokens: Vec<_> = self.signed_in.lock().unwrap().drain().collect();
+        for (session_id, token) in tokens {
+            self.state.auth_service.logout(&token).await;
+            info!(session_id = %session_id, "Logged out session cut off by shutdown");
+        }
+
+        // Message bases write through on every post, so there is nothing to
+        // flush; taking each write lock waits out a post still in flight
+        for base in self.state.message_areas.bases() {
+            let _message_base = base.write().await;
+        }
+        info!("No message posts in flight");
+    }
+}
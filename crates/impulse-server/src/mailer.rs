@@ -0,0 +1,285 @@
+//! FidoNet mail exchange
+//!
+//! Runs the BinkP mailer configured in the `[fidonet]` section: answers calls
+//! on `binkp_bind`, calls links on their poll schedule or as soon as crash
+//! mail is waiting for them, scans echomail out before calls and tosses what
+//! arrives.
+
+use anyhow::{Context, Result, bail};
+use impulse_message::addressing::FidoAddress;
+use impulse_message::ftn::binkp::has_mail;
+use impulse_message::ftn::{
+    EchoArea, Flavour, FtnConfig, FtnLink, Mailer, Scanner, SessionStats, Tosser,
+};
+use impulse_message::routing::RouterConfig;
+use impulse_types::config::{BbsConfig, MessageBaseFormat};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// How often outgoing echomail is scanned and polls are considered
+const POLL_TICK: Duration = Duration::from_secs(60);
+
+/// Flavours of mail that are sent without waiting for a scheduled poll
+const URGENT: [Flavour; 2] = [Flavour::Crash, Flavour::Direct];
+
+/// Build the tosser, scanner and mailer configuration
+///
+/// # Returns
+/// `None` if FidoNet is not configured
+pub fn ftn_config(config: &BbsConfig) -> Result<Option<FtnConfig>> {
+    let Some(fidonet) = &config.fidonet else {
+        return Ok(None);
+    };
+
+    let mut addresses = fidonet
+        .addresses
+        .iter()
+        .map(|address| parse_address(address));
+    let main = addresses
+        .next()
+        .context("FidoNet needs at least one address")??;
+
+    let mut router = RouterConfig::new(main);
+    if let Some(hub) = &fidonet.hub {
+        router = router.with_hub(parse_address(hub)?);
+    }
+
+    let mut ftn = FtnConfig::new(router, &fidonet.inbound, &fidonet.outbound)
+        .with_system(&config.name, &config.sysop)
+        .with_origin(fidonet.origin.as_deref().unwrap_or(&config.name))
+        .with_dupe_file(config.paths.data_dir.join("dupes.dat"))
+        .with_netmail(
+            fidonet
+                .netmail_path
+                .clone()
+                .unwrap_or_else(|| config.paths.messages_dir.join("netmail")),
+        )
+        .with_bad_area(config.paths.messages_dir.join("badmail"));
+    for aka in addresses {
+        ftn = ftn.with_aka(aka?);
+    }
+
+    for link in &fidonet.links {
+        let mut ftn_link = FtnLink::new(parse_address(&link.address)?);
+        if let Some(password) = &link.packet_password {
+            ftn_link = ftn_link.with_password(password);
+        }
+        if let Some(password) = &link.session_password {
+            ftn_link = ftn_link.with_session_password(password);
+        }
+        if let Some(host) = &link.host {
+            ftn_link = ftn_link.with_host(host);
+        }
+        ftn = ftn.with_link(ftn_link);
+    }
+
+    for area in &config.message_areas {
+        let Some(tag) = &area.echo_tag else {
+            continue;
+        };
+        if area.format != MessageBaseFormat::Jam {
+            bail!("Echomail area {} must be a JAM base", area.tag);
+        }
+        let mut echo_area = EchoArea::new(tag, &area.path);
+        for link in &area.echo_links {
+            echo_area = echo_area.with_link(parse_address(link)?);
+        }
+        ftn = ftn.with_area(echo_area);
+    }
+
+    Ok(Some(ftn))
+}
+
+/// Start the mailer if FidoNet is configured
+pub async fn spawn_mailer(config: &BbsConfig) -> Result<()> {
+    let Some(ftn) = ftn_config(config)? else {
+        return Ok(());
+    };
+    let Some(fidonet) = &config.fidonet else {
+        return Ok(());
+    };
+
+    tokio::fs::create_dir_all(&ftn.inbound).await?;
+    tokio::fs::create_dir_all(&ftn.outbound).await?;
+
+    let mail = Arc::new(MailProcessor {
+        mailer: Mailer::new(ftn),
+        processing: Mutex::new(()),
+    });
+    info!(
+        "FidoNet mailer started for {}",
+        mail.mailer.config().address()
+    );
+
+    if let Some(bind) = &fidonet.binkp_bind {
+        let listener = TcpListener::bind(bind)
+            .await
+            .with_context(|| format!("Failed to bind BinkP listener on {}", bind))?;
+        info!("BinkP listening on {}", bind);
+        tokio::spawn(answer_calls(listener, mail.clone()));
+    }
+
+    let schedule = fidonet
+        .links
+        .iter()
+        .filter(|link| link.host.is_some())
+        .map(|link| {
+            let minutes = u64::from(link.poll_minutes);
+            Ok((
+                parse_address(&link.address)?,
+                (minutes > 0).then(|| Duration::from_secs(minutes * 60)),
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    tokio::spawn(poll_links(schedule, mail));
+
+    Ok(())
+}
+
+/// The mailer, with tossing and scanning kept to one task at a time
+struct MailProcessor {
+    /// BinkP mailer
+    mailer: Mailer,
+    /// Held while packets are tossed or scanned
+    processing: Mutex<()>,
+}
+
+impl MailProcessor {
+    /// Export local echomail to the outbound
+    async fn scan(&self) {
+        let _processing = self.processing.lock().await;
+        match Scanner::new(self.mailer.config().clone()).scan().await {
+            Ok(stats) => {
+                if stats.messages > 0 {
+                    info!(
+                        "Scanned {} echomail messages into {} packets",
+                        stats.messages,
+                        stats.packets.len()
+                    );
+                }
+                for (base_path, msg_num) in &stats.bad_messages {
+                    warn!(
+                        "Skipped unreadable message {} in {}",
+                        msg_num,
+                        base_path.display()
+                    );
+                }
+            }
+            Err(e) => warn!("Echomail scan failed: {}", e),
+        }
+    }
+
+    /// Toss what a session received
+    async fn toss(&self, session: &SessionStats) {
+        if session.received.is_empty() {
+            return;
+        }
+
+        let _processing = self.processing.lock().await;
+        let tossed = match Tosser::new(self.mailer.config().clone()).await {
+            Ok(mut tosser) => tosser.toss().await,
+            Err(e) => Err(e),
+        };
+        match tossed {
+            Ok(stats) => {
+                info!(
+                    "Tossed {} messages from {} packets ({} duplicates, {} forwarded)",
+                    stats.messages, stats.packets, stats.duplicates, stats.forwarded
+                );
+                if stats.unlinked > 0 {
+                    warn!(
+                        "{} echomail messages came from systems not linked to their area",
+                        stats.unlinked
+                    );
+                }
+                for path in &stats.bad_files {
+                    warn!("Could not toss {}", path.display());
+                }
+            }
+            Err(e) => warn!("Toss failed: {}", e),
+        }
+    }
+
+    /// Log a finished session and toss what it brought
+    async fn finish(&self, session: SessionStats) {
+        let remote = session
+            .remote_addresses
+            .first()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        info!(
+            "BinkP session with {} ({}) done: sent {}, received {}{}",
+            remote,
+            session.remote_system.as_deref().unwrap_or("unknown system"),
+            session.sent.len(),
+            session.received.len(),
+            if session.secure { "" } else { " (unprotected)" }
+        );
+        self.toss(&session).await;
+    }
+}
+
+/// Answer incoming BinkP calls
+async fn answer_calls(listener: TcpListener, mail: Arc<MailProcessor>) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("BinkP accept failed: {}", e);
+                continue;
+            }
+        };
+
+        let mail = mail.clone();
+        tokio::spawn(async move {
+            info!("BinkP call from {}", peer);
+            match mail.mailer.answer(stream).await {
+                Ok(session) => mail.finish(session).await,
+                Err(e) => warn!("BinkP session from {} failed: {}", peer, e),
+            }
+        });
+    }
+}
+
+/// Call links when their poll interval passes or urgent mail is waiting
+async fn poll_links(schedule: Vec<(FidoAddress, Option<Duration>)>, mail: Arc<MailProcessor>) {
+    let mut last_poll: HashMap<FidoAddress, Instant> = HashMap::new();
+    let mut ticks = tokio::time::interval(POLL_TICK);
+
+    loop {
+        ticks.tick().await;
+        mail.scan().await;
+
+        let config = mail.mailer.config();
+        for (address, interval) in &schedule {
+            let scheduled = interval.is_some_and(|interval| {
+                last_poll
+                    .get(address)
+                    .is_none_or(|last| last.elapsed() >= interval)
+            });
+            let urgent = has_mail(&config.outbound, config.address().zone, *address, &URGENT);
+            if !scheduled && !urgent {
+                continue;
+            }
+
+            last_poll.insert(*address, Instant::now());
+            info!("Calling {}", address);
+            match mail.mailer.poll(*address).await {
+                Ok(session) => mail.finish(session).await,
+                Err(e) => warn!("BinkP call to {} failed: {}", address, e),
+            }
+        }
+    }
+}
+
+/// Parse a configured FidoNet address
+fn parse_address(address: &str) -> Result<FidoAddress> {
+    address
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid FidoNet address {}: {}", address, e))
+}
@@ -3,38 +3,69 @@
 //! Modern BBS server implementation in Rust
 
 mod auth;
+mod handler;
+mod mailer;
 mod menus;
+mod reload;
 mod state;
 
-use anyhow::Result;
-use auth::{AuthResult, authenticate};
-use impulse_session::{SessionConfig, SessionManager};
-use impulse_telnet::TelnetServer;
-use menus::display_main_menu;
+use anyhow::{Context, Result};
+use clap::Parser;
+use handler::BbsHandler;
+use impulse_admin::SystemMaintenance;
+use impulse_config::{Config, ValidationOptions, validate_config};
+use impulse_core::BbsCore;
+use impulse_types::config::BbsConfig;
+use impulse_user::import_user_lst;
 use state::ServerState;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
-use tracing::{error, info, warn};
+use tokio::time::Instant;
+use tracing::{info, warn};
 
-/// Server configuration
-struct ServerConfig {
-    telnet_address: String,
-    session_idle_timeout: Duration,
-    max_sessions: usize,
-}
+/// Configuration file used when `--config` is not given
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
 
-impl Default for ServerConfig {
-    fn default() -> Self {
-        Self {
-            telnet_address: "0.0.0.0:2323".to_string(),
-            session_idle_timeout: Duration::from_secs(900), // 15 minutes
-            max_sessions: 100,
-        }
-    }
+/// Seconds before shutdown at which callers are warned again
+const COUNTDOWN_WARNINGS: [u64; 6] = [600, 300, 120, 60, 30, 10];
+
+/// Admin ID the server uses for its own broadcasts
+const SYSTEM_ADMIN_ID: i32 = 0;
+
+/// Impulse 7.1 BBS server
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Path to the BBS configuration file
+    ///
+    /// Defaults to "config.toml" in the current directory; built-in defaults
+    /// are used if that file does not exist.
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+
+    /// Import users from a legacy Impulse 7.1 USER.LST into the configured
+    /// user store, then exit
+    #[arg(long, value_name = "PATH")]
+    import_user_lst: Option<PathBuf>,
+
+    /// Seconds callers are warned for before the BBS shuts down
+    ///
+    /// The countdown ends early once nobody is online. A second SIGTERM or
+    /// Ctrl+C skips it.
+    #[arg(long, value_name = "SECS", default_value_t = 30)]
+    shutdown_countdown: u64,
+
+    /// Seconds file transfers and doors get to finish once the countdown
+    /// ends, before their sessions are cut off
+    #[arg(long, value_name = "SECS", default_value_t = 60)]
+    shutdown_grace: u64,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
     // Initialize tracing/logging
     tracing_subscriber::fmt()
         .with_env_filter(
@@ -46,149 +77,204 @@ async fn main() -> Result<()> {
     info!("Impulse 7.1 BBS Server v0.3.1");
     info!("=================================");
 
-    // Load configuration
-    let config = ServerConfig::default();
+    // Load and validate configuration
+    let (loaded, config_path) = load_config(cli.config.as_deref())?;
+    let config = loaded.inner().clone();
     info!("Configuration loaded");
-    info!("  Telnet address: {}", config.telnet_address);
-    info!("  Session timeout: {:?}", config.session_idle_timeout);
-    info!("  Max sessions: {}", config.max_sessions);
+    info!("  BBS name: {}", config.name);
+    info!("  Data directory: {}", config.paths.data_dir.display());
+    info!(
+        "  Session timeout: {} min",
+        config.limits.max_time_per_session
+    );
+    info!("  Idle timeout: {} min", config.limits.idle_timeout_minutes);
+    info!("  Max connections: {}", config.limits.max_connections);
+
+    if let Some(user_lst) = cli.import_user_lst {
+        return import_users(&config, &user_lst).await;
+    }
 
     // Initialize server state (user managers, message bases, etc.)
     info!("Initializing server state...");
-    let server_state = Arc::new(ServerState::new().await?);
+    let server_state = Arc::new(ServerState::new(&config).await?);
     info!("Server state initialized");
 
-    // Create session manager
-    let session_config = SessionConfig::new()
-        .with_idle_timeout(config.session_idle_timeout)
-        .with_max_total_sessions(config.max_sessions);
-
-    let session_manager = Arc::new(SessionManager::new(session_config));
-    info!("Session manager initialized");
+    // Watch the configuration file for changes
+    if let Some(path) = config_path {
+        reload::spawn_config_reload(path, loaded, &server_state).await?;
+    }
 
-    // Spawn session cleanup task
-    let _cleanup_handle = session_manager.spawn_cleanup_task();
-    info!("Session cleanup task started");
+    // Exchange FidoNet mail if configured
+    mailer::spawn_mailer(&config)
+        .await
+        .context("Failed to start the FidoNet mailer")?;
 
-    // Bind telnet server
-    info!("Binding telnet server to {}...", config.telnet_address);
-    let telnet_server = TelnetServer::bind(&config.telnet_address).await?;
-    info!("Telnet server listening on {}", telnet_server.local_addr());
+    // Bind the configured listeners
+    let core = BbsCore::new(
+        config,
+        Arc::new(BbsHandler::new(server_state.clone())),
+        server_state.session_manager.clone(),
+    )
+    .with_shutdown_grace(Duration::from_secs(cli.shutdown_grace));
+    core.start().await.context("Failed to start listeners")?;
 
     info!("Server initialization complete - ready to accept connections");
     info!("Press Ctrl+C to stop the server");
-    println!();
-    println!("Demo credentials:");
-    println!("  Username: sysop   (security level 255)");
-    println!("  Username: testuser (security level 10)");
-    println!("  Password: demo123 (for any user)");
-    println!();
-
-    // Main server loop
-    loop {
-        match telnet_server.accept().await {
-            Ok(mut connection) => {
-                let peer_addr = connection.peer_addr();
-                info!("New connection from {}", peer_addr);
-
-                // Create session
-                let session_id = match session_manager.create_session(peer_addr.to_string()).await {
-                    Ok(id) => {
-                        info!(session_id = %id, "Session created for {}", peer_addr);
-                        id
-                    }
-                    Err(e) => {
-                        warn!("Failed to create session for {}: {}", peer_addr, e);
-                        let _ = connection
-                            .send_line("Server is full. Please try again later.")
-                            .await;
-                        continue;
-                    }
-                };
-
-                // Spawn handler for this connection
-                let session_mgr = session_manager.clone();
-                let state = server_state.clone();
-                tokio::spawn(async move {
-                    if let Err(e) =
-                        handle_connection(connection, session_id, session_mgr, state).await
-                    {
-                        error!(session_id = %session_id, "Connection error: {}", e);
-                    }
-                });
-            }
-            Err(e) => {
-                error!("Failed to accept connection: {}", e);
-            }
+
+    let signal = shutdown_signal().await?;
+    info!("{} received; shutting down", signal);
+    core.stop_accepting();
+
+    let maintenance = SystemMaintenance::new(
+        (*server_state.admin_access).clone(),
+        (*server_state.audit_logger).clone(),
+    )
+    .with_notices(core.notice_sender());
+    let countdown = Duration::from_secs(cli.shutdown_countdown);
+    tokio::select! {
+        _ = shutdown_countdown(&core, &maintenance, countdown) => {}
+        signal = shutdown_signal() => {
+            info!("{} received again; skipping the shutdown countdown", signal?);
         }
     }
+
+    // Callers are disconnected at their next prompt; transfers and doors
+    // read raw input, so they run on until they finish or the grace period
+    // runs out
+    core.stop().await;
+
+    Ok(())
 }
 
-/// Handle a single BBS connection
-async fn handle_connection(
-    mut connection: impulse_telnet::TelnetConnection,
-    session_id: impulse_session::SessionId,
-    session_manager: Arc<SessionManager>,
-    state: Arc<ServerState>,
-) -> Result<()> {
-    info!(session_id = %session_id, "Starting connection handler");
-
-    // Initialize telnet session (negotiate options)
-    connection.initialize().await?;
-
-    // Authentication phase
-    info!(session_id = %session_id, "Starting authentication");
-    match authenticate(&mut connection, &state).await? {
-        AuthResult::Authenticated { user, token } => {
-            info!(
-                session_id = %session_id,
-                username = %user.username(),
-                user_id = ?user.id(),
-                "User authenticated successfully"
-            );
+/// Wait for Ctrl+C or, on Unix, SIGTERM
+///
+/// Returns the name of the signal received.
+async fn shutdown_signal() -> Result<&'static str> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
 
-            // Main menu loop
-            loop {
-                // Update activity
-                session_manager.update_activity(session_id).await.ok();
-
-                // Display main menu and handle commands
-                match display_main_menu(&mut connection, &user, &token, &state, &session_manager)
-                    .await
-                {
-                    Ok(should_continue) => {
-                        if !should_continue {
-                            // User logged out
-                            break;
-                        }
-                    }
-                    Err(e) => {
-                        error!(
-                            session_id = %session_id,
-                            error = %e,
-                            "Error in main menu"
-                        );
-                        break;
-                    }
-                }
+        let mut terminate =
+            signal(SignalKind::terminate()).context("Failed to listen for SIGTERM")?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => {
+                result.context("Failed to listen for Ctrl+C")?;
+                Ok("Ctrl+C")
             }
+            _ = terminate.recv() => Ok("SIGTERM"),
+        }
+    }
 
-            // Logout
-            state.auth_service.logout(&token).await;
-            info!(
-                session_id = %session_id,
-                username = %user.username(),
-                "User logged out"
-            );
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c()
+            .await
+            .context("Failed to listen for Ctrl+C")?;
+        Ok("Ctrl+C")
+    }
+}
+
+/// Warn callers of the shutdown and give them `countdown` to log off
+///
+/// Warnings go out when the countdown starts and again at each of
+/// [`COUNTDOWN_WARNINGS`]. Returns early once nobody is online.
+async fn shutdown_countdown(
+    core: &BbsCore<BbsHandler>,
+    maintenance: &SystemMaintenance,
+    countdown: Duration,
+) {
+    let deadline = Instant::now() + countdown;
+    let total = countdown.as_secs();
+    let warnings = std::iter::once(total)
+        .chain(COUNTDOWN_WARNINGS.into_iter().filter(|&secs| secs < total))
+        .filter(|&secs| secs > 0);
+
+    for remaining in warnings {
+        let remaining = Duration::from_secs(remaining);
+        if !callers_online_until(core, deadline - remaining).await {
+            return;
+        }
+        if let Err(e) = maintenance
+            .broadcast_shutdown_countdown(SYSTEM_ADMIN_ID, remaining)
+            .await
+        {
+            warn!("Failed to warn callers of the shutdown: {}", e);
+        }
+    }
+
+    callers_online_until(core, deadline).await;
+}
+
+/// Wait until `until`, returning `false` as soon as nobody is online
+async fn callers_online_until(core: &BbsCore<BbsHandler>, until: Instant) -> bool {
+    loop {
+        if core.session_manager().active_session_count().await == 0 {
+            return false;
         }
-        AuthResult::Quit => {
-            info!(session_id = %session_id, "User quit during authentication");
+        let now = Instant::now();
+        if now >= until {
+            return true;
         }
+        tokio::time::sleep((until - now).min(Duration::from_secs(1))).await;
     }
+}
+
+/// Load the BBS configuration and check it against the filesystem
+///
+/// An explicit `--config` path must exist. Without one, `config.toml` in the
+/// current directory is used if present, otherwise the built-in defaults.
+/// The data directory is created before validation so that a fresh install
+/// can start, but any other validation failure stops the server.
+///
+/// Returns the configuration and the file it was loaded from, if any.
+fn load_config(path: Option<&Path>) -> Result<(Config, Option<PathBuf>)> {
+    let path = match path {
+        Some(path) => Some(path.to_path_buf()),
+        None if Path::new(DEFAULT_CONFIG_FILE).exists() => Some(PathBuf::from(DEFAULT_CONFIG_FILE)),
+        None => None,
+    };
+
+    let loaded = match &path {
+        Some(path) => {
+            info!("Loading configuration from {}", path.display());
+            Config::load(path)?
+        }
+        None => {
+            warn!(
+                "No {} found and no --config given; using built-in defaults",
+                DEFAULT_CONFIG_FILE
+            );
+            Config::with_defaults()
+        }
+    };
+    let config = loaded.inner();
+
+    std::fs::create_dir_all(&config.paths.data_dir).with_context(|| {
+        format!(
+            "Failed to create data directory {}",
+            config.paths.data_dir.display()
+        )
+    })?;
+
+    validate_config(config, &ValidationOptions::deployment())
+        .context("Configuration failed validation; refusing to start")?;
+
+    Ok((loaded, path))
+}
+
+/// Import a legacy USER.LST into the configured user store
+async fn import_users(config: &BbsConfig, user_lst: &Path) -> Result<()> {
+    std::fs::create_dir_all(&config.paths.users_dir)?;
+    let mut user_manager = state::open_user_store(config).await?;
 
-    // Terminate session
-    session_manager.terminate_session(session_id).await.ok();
-    connection.close().await.ok();
+    info!("Importing users from {}...", user_lst.display());
+    let summary = import_user_lst(user_manager.as_mut(), user_lst)
+        .await
+        .with_context(|| format!("Failed to import {}", user_lst.display()))?;
 
+    info!(
+        "Imported {} users ({} already existed, {} deleted records skipped)",
+        summary.imported, summary.skipped_existing, summary.skipped_deleted
+    );
     Ok(())
 }
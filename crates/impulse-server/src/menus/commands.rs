@@ -0,0 +1,289 @@
+//! Menu command registration
+//!
+//! Exposes every menu handler to the TOML-driven menu system as a named
+//! `CommandHandler`, so menu files can bind any key to any feature.
+
+use crate::menus::{handlers, main_menu};
+use crate::state::ServerState;
+use async_trait::async_trait;
+use impulse_menu::commands::register_builtin_commands;
+use impulse_menu::{CommandContext, CommandError, CommandHandler, CommandResult, CommandRouter};
+use impulse_session::{Connection, Session, SessionId, SessionManager};
+use impulse_terminal::AnsiRenderer;
+use impulse_types::user::User;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Everything a menu command needs to serve one logged-in caller
+pub struct MenuSession {
+    /// The caller's connection
+    pub connection: Mutex<Box<dyn Connection>>,
+
+    /// The authenticated user
+    pub user: User,
+
+    /// Session ID in the session manager
+    pub session_id: SessionId,
+
+    /// Shared server state
+    pub state: Arc<ServerState>,
+
+    /// Session manager
+    pub session_manager: Arc<SessionManager>,
+}
+
+impl MenuSession {
+    /// Seconds the caller has left this call, or `None` when nothing limits it
+    pub async fn time_left(&self) -> Option<u32> {
+        let caller = self
+            .session_manager
+            .get_session(self.session_id)
+            .await
+            .ok()?;
+        time_left(
+            self.state.max_session_minutes,
+            &caller,
+            self.user.stats.time_left_today,
+        )
+    }
+}
+
+/// Seconds a caller has left this call, or `None` when nothing limits it
+///
+/// What remains of this call's session limit, capped by the user's time left
+/// for the day once that is being counted down, plus any time doors have
+/// given or taken away this call.
+pub fn time_left(max_session_minutes: u32, caller: &Session, time_left_today: i16) -> Option<u32> {
+    let session = (max_session_minutes > 0).then(|| {
+        let on_for = u32::try_from(caller.age().as_secs()).unwrap_or(u32::MAX);
+        max_session_minutes
+            .saturating_mul(60)
+            .saturating_sub(on_for)
+    });
+    let today = (time_left_today > 0).then(|| u32::from(time_left_today.unsigned_abs()) * 60);
+
+    let limit = match (session, today) {
+        (Some(session), Some(today)) => session.min(today),
+        (session, today) => session.or(today)?,
+    };
+    let left = i64::from(limit).saturating_add(caller.time_credit());
+    Some(u32::try_from(left.max(0)).unwrap_or(u32::MAX))
+}
+
+/// Server features available to menu files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ServerCommand {
+    Messages,
+    NewScan,
+    Files,
+    Doors,
+    UserProfile,
+    WhosOnline,
+    Theme,
+    Stats,
+    Admin,
+    Help,
+}
+
+impl ServerCommand {
+    const ALL: [ServerCommand; 10] = [
+        Self::Messages,
+        Self::NewScan,
+        Self::Files,
+        Self::Doors,
+        Self::UserProfile,
+        Self::WhosOnline,
+        Self::Theme,
+        Self::Stats,
+        Self::Admin,
+        Self::Help,
+    ];
+
+    /// Command name used in menu files
+    fn name(self) -> &'static str {
+        match self {
+            Self::Messages => "messages",
+            Self::NewScan => "new_scan",
+            Self::Files => "files",
+            Self::Doors => "doors",
+            Self::UserProfile => "user_settings",
+            Self::WhosOnline => "who",
+            Self::Theme => "theme",
+            Self::Stats => "stats",
+            Self::Admin => "admin",
+            Self::Help => "help",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Self::Messages => "Message areas",
+            Self::NewScan => "Scan all areas for new messages",
+            Self::Files => "File areas",
+            Self::Doors => "Door games",
+            Self::UserProfile => "User profile and settings",
+            Self::WhosOnline => "Who's online",
+            Self::Theme => "Change theme",
+            Self::Stats => "System statistics",
+            Self::Admin => "System administration",
+            Self::Help => "Display help information",
+        }
+    }
+}
+
+/// Runs a server menu handler on behalf of a caller
+struct ServerCommandHandler {
+    command: ServerCommand,
+    min_security: u8,
+    session: Arc<MenuSession>,
+}
+
+#[async_trait]
+impl CommandHandler for ServerCommandHandler {
+    async fn execute(&self, _ctx: &mut CommandContext) -> Result<CommandResult, CommandError> {
+        let session = &self.session;
+        let mut connection = session.connection.lock().await;
+        let connection = connection.as_mut();
+        let user = &session.user;
+        let state = &session.state;
+        let mut renderer = AnsiRenderer::new();
+
+        match self.command {
+            ServerCommand::Messages => {
+                handlers::handle_messages(connection, user, state, &mut renderer).await?
+            }
+            ServerCommand::NewScan => {
+                handlers::handle_new_scan(connection, user, state, &mut renderer).await?
+            }
+            ServerCommand::Files => {
+                handlers::handle_files(connection, user, state, &mut renderer).await?
+            }
+            ServerCommand::Doors => {
+                handlers::handle_doors(
+                    connection,
+                    user,
+                    state,
+                    &session.session_manager,
+                    session.session_id,
+                    &mut renderer,
+                )
+                .await?
+            }
+            ServerCommand::UserProfile => {
+                handlers::handle_user_profile(connection, user, state, &mut renderer).await?
+            }
+            ServerCommand::WhosOnline => {
+                handlers::handle_whos_online(connection, &session.session_manager, &mut renderer)
+                    .await?
+            }
+            ServerCommand::Theme => {
+                handlers::handle_theme_selection(connection, user, state, &mut renderer).await?
+            }
+            ServerCommand::Stats => {
+                handlers::handle_system_stats(connection, &session.session_manager, &mut renderer)
+                    .await?
+            }
+            ServerCommand::Admin => {
+                handlers::handle_admin(connection, user, state, &mut renderer).await?
+            }
+            ServerCommand::Help => main_menu::handle_help(connection, &mut renderer).await?,
+        }
+
+        Ok(CommandResult::Continue)
+    }
+
+    fn name(&self) -> &str {
+        self.command.name()
+    }
+
+    fn description(&self) -> &str {
+        self.command.description()
+    }
+
+    fn min_security(&self) -> u8 {
+        self.min_security
+    }
+}
+
+/// Build a command router serving `session`
+///
+/// Registers the built-in navigation commands (`back`, `main`, `goodbye`,
+/// `where`) followed by every server feature; the server's `help` screen
+/// replaces the built-in one.
+pub fn build_router(session: &Arc<MenuSession>) -> CommandRouter {
+    let mut router = CommandRouter::new();
+    register_builtin_commands(&mut router);
+
+    let sysop_level = session.state.admin_access.sysop_level();
+    for command in ServerCommand::ALL {
+        let min_security = match command {
+            ServerCommand::Admin => sysop_level,
+            _ => 0,
+        };
+        router.register(Arc::new(ServerCommandHandler {
+            command,
+            min_security,
+            session: session.clone(),
+        }));
+    }
+
+    router
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use impulse_menu::parser::MenuParser;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_command_names_are_unique() {
+        let names: HashSet<_> = ServerCommand::ALL.iter().map(|c| c.name()).collect();
+        assert_eq!(names.len(), ServerCommand::ALL.len());
+    }
+
+    #[test]
+    fn test_main_menu_commands_are_registered() {
+        let mut builtins = CommandRouter::new();
+        register_builtin_commands(&mut builtins);
+
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../config/menus/main.toml");
+        let menu = MenuParser::parse_file(std::path::Path::new(path)).unwrap();
+        for option in &menu.option {
+            assert!(
+                builtins.has_handler(&option.command)
+                    || ServerCommand::ALL
+                        .iter()
+                        .any(|command| command.name() == option.command),
+                "'{}' is not a command",
+                option.command
+            );
+        }
+    }
+
+    #[test]
+    fn test_time_left_session_limit() {
+        let caller = Session::new("127.0.0.1:0".to_string());
+        assert_eq!(time_left(60, &caller, 0), Some(3600));
+
+        // The day's allowance caps the session limit once it counts down
+        assert_eq!(time_left(60, &caller, 20), Some(1200));
+        assert_eq!(time_left(0, &caller, 20), Some(1200));
+    }
+
+    #[test]
+    fn test_time_left_credit() {
+        let mut caller = Session::new("127.0.0.1:0".to_string());
+        caller.add_time_credit(600);
+        assert_eq!(time_left(60, &caller, 0), Some(4200));
+
+        caller.add_time_credit(-7200);
+        assert_eq!(time_left(60, &caller, 0), Some(0));
+    }
+
+    #[test]
+    fn test_time_left_unlimited() {
+        let caller = Session::new("127.0.0.1:0".to_string());
+        assert_eq!(time_left(0, &caller, 0), None);
+    }
+}
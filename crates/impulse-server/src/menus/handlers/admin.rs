@@ -3,14 +3,13 @@
 use crate::state::ServerState;
 use anyhow::Result;
 use impulse_file::FileAreaManager;
-use impulse_telnet::TelnetConnection;
+use impulse_session::Connection;
 use impulse_terminal::{AnsiRenderer, Color};
 use impulse_types::user::User;
-use impulse_user::UserManager;
 
 /// Handle administration menu
 pub async fn handle_admin(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     user: &User,
     state: &ServerState,
     renderer: &mut AnsiRenderer,
@@ -31,7 +30,7 @@ pub async fn handle_admin(
         renderer.write_line("Press any key to continue...");
         renderer.reset();
         connection
-            .send_raw(renderer.take_output().as_bytes())
+            .send_bytes(renderer.take_output().as_bytes())
             .await?;
         connection.read_char().await.ok();
         return Ok(());
@@ -115,7 +114,7 @@ pub async fn handle_admin(
         renderer.reset();
 
         connection
-            .send_raw(renderer.take_output().as_bytes())
+            .send_bytes(renderer.take_output().as_bytes())
             .await?;
 
         // Read command
@@ -153,7 +152,7 @@ pub async fn handle_admin(
                         renderer.write_line("Press any key to continue...");
                         renderer.reset();
                         connection
-                            .send_raw(renderer.take_output().as_bytes())
+                            .send_bytes(renderer.take_output().as_bytes())
                             .await?;
                         connection.read_char().await.ok();
                     }
@@ -168,7 +167,7 @@ pub async fn handle_admin(
 
 /// Show user management screen
 async fn show_user_management(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     admin_user: &User,
     state: &ServerState,
     renderer: &mut AnsiRenderer,
@@ -236,7 +235,7 @@ async fn show_user_management(
         renderer.reset();
 
         connection
-            .send_raw(renderer.take_output().as_bytes())
+            .send_bytes(renderer.take_output().as_bytes())
             .await?;
 
         match connection.read_char().await {
@@ -252,7 +251,7 @@ async fn show_user_management(
                         renderer.write_text("Enter user number to edit: ");
                         renderer.reset();
                         connection
-                            .send_raw(renderer.take_output().as_bytes())
+                            .send_bytes(renderer.take_output().as_bytes())
                             .await?;
 
                         if let Ok(input) = connection.read_line().await
@@ -283,7 +282,7 @@ async fn show_user_management(
                         renderer.write_text("Enter user number to delete: ");
                         renderer.reset();
                         connection
-                            .send_raw(renderer.take_output().as_bytes())
+                            .send_bytes(renderer.take_output().as_bytes())
                             .await?;
 
                         if let Ok(input) = connection.read_line().await
@@ -297,7 +296,7 @@ async fn show_user_management(
                             renderer.write_text("Type 'DELETE' to confirm: ");
                             renderer.reset();
                             connection
-                                .send_raw(renderer.take_output().as_bytes())
+                                .send_bytes(renderer.take_output().as_bytes())
                                 .await?;
 
                             if let Ok(confirm) = connection.read_line().await {
@@ -334,7 +333,7 @@ async fn show_user_management(
                         renderer.write_text("Enter user number to ban: ");
                         renderer.reset();
                         connection
-                            .send_raw(renderer.take_output().as_bytes())
+                            .send_bytes(renderer.take_output().as_bytes())
                             .await?;
 
                         if let Ok(input) = connection.read_line().await
@@ -347,7 +346,7 @@ async fn show_user_management(
                             renderer.write_text("Ban reason: ");
                             renderer.reset();
                             connection
-                                .send_raw(renderer.take_output().as_bytes())
+                                .send_bytes(renderer.take_output().as_bytes())
                                 .await?;
 
                             if let Ok(reason) = connection.read_line().await {
@@ -377,7 +376,7 @@ async fn show_user_management(
                         renderer.write_text("Search username: ");
                         renderer.reset();
                         connection
-                            .send_raw(renderer.take_output().as_bytes())
+                            .send_bytes(renderer.take_output().as_bytes())
                             .await?;
 
                         if let Ok(query) = connection.read_line().await {
@@ -440,7 +439,7 @@ async fn show_user_management(
 
 /// Edit a specific user
 async fn edit_user(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     _admin_user: &User,
     state: &ServerState,
     renderer: &mut AnsiRenderer,
@@ -487,7 +486,7 @@ async fn edit_user(
     renderer.reset();
 
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
 
     if let Ok(ch) = connection.read_char().await {
@@ -498,7 +497,7 @@ async fn edit_user(
                 renderer.write_text("New security level (0-255): ");
                 renderer.reset();
                 connection
-                    .send_raw(renderer.take_output().as_bytes())
+                    .send_bytes(renderer.take_output().as_bytes())
                     .await?;
 
                 if let Ok(input) = connection.read_line().await
@@ -531,7 +530,7 @@ async fn edit_user(
                 renderer.write_text("New email address: ");
                 renderer.reset();
                 connection
-                    .send_raw(renderer.take_output().as_bytes())
+                    .send_bytes(renderer.take_output().as_bytes())
                     .await?;
 
                 if let Ok(input) = connection.read_line().await {
@@ -566,7 +565,7 @@ async fn edit_user(
                 renderer.write_text("Daily time limit (minutes, 0=unlimited): ");
                 renderer.reset();
                 connection
-                    .send_raw(renderer.take_output().as_bytes())
+                    .send_bytes(renderer.take_output().as_bytes())
                     .await?;
 
                 if let Ok(input) = connection.read_line().await
@@ -630,16 +629,13 @@ async fn edit_user(
 }
 
 /// Helper to wait for key press
-async fn wait_for_key(
-    connection: &mut TelnetConnection,
-    renderer: &mut AnsiRenderer,
-) -> Result<()> {
+async fn wait_for_key(connection: &mut dyn Connection, renderer: &mut AnsiRenderer) -> Result<()> {
     renderer.write_line("\r\n");
     renderer.set_foreground(Color::BrightYellow);
     renderer.write_line("Press any key to continue...");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
     connection.read_char().await.ok();
     Ok(())
@@ -647,7 +643,7 @@ async fn wait_for_key(
 
 /// Show file area management screen
 async fn show_file_area_management(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     state: &ServerState,
     _admin_user: &User,
     renderer: &mut AnsiRenderer,
@@ -702,7 +698,7 @@ async fn show_file_area_management(
         renderer.reset();
 
         connection
-            .send_raw(renderer.take_output().as_bytes())
+            .send_bytes(renderer.take_output().as_bytes())
             .await?;
 
         match connection.read_char().await {
@@ -723,7 +719,7 @@ async fn show_file_area_management(
                         renderer.write_text("Area name: ");
                         renderer.reset();
                         connection
-                            .send_raw(renderer.take_output().as_bytes())
+                            .send_bytes(renderer.take_output().as_bytes())
                             .await?;
                         let name = connection.read_line().await?.trim().to_string();
 
@@ -739,7 +735,7 @@ async fn show_file_area_management(
                         renderer.write_text("Description: ");
                         renderer.reset();
                         connection
-                            .send_raw(renderer.take_output().as_bytes())
+                            .send_bytes(renderer.take_output().as_bytes())
                             .await?;
                         let description = connection.read_line().await?.trim().to_string();
 
@@ -747,7 +743,7 @@ async fn show_file_area_management(
                         renderer.write_text("Download security level (0-255): ");
                         renderer.reset();
                         connection
-                            .send_raw(renderer.take_output().as_bytes())
+                            .send_bytes(renderer.take_output().as_bytes())
                             .await?;
                         let dl_level: u8 =
                             connection.read_line().await?.trim().parse().unwrap_or(10);
@@ -756,7 +752,7 @@ async fn show_file_area_management(
                         renderer.write_text("Upload security level (0-255): ");
                         renderer.reset();
                         connection
-                            .send_raw(renderer.take_output().as_bytes())
+                            .send_bytes(renderer.take_output().as_bytes())
                             .await?;
                         let ul_level: u8 =
                             connection.read_line().await?.trim().parse().unwrap_or(20);
@@ -788,7 +784,7 @@ async fn show_file_area_management(
                         renderer.write_text("Enter area number to edit: ");
                         renderer.reset();
                         connection
-                            .send_raw(renderer.take_output().as_bytes())
+                            .send_bytes(renderer.take_output().as_bytes())
                             .await?;
 
                         if let Ok(input) = connection.read_line().await
@@ -807,7 +803,7 @@ async fn show_file_area_management(
                             renderer.write_text("New description (blank to keep current): ");
                             renderer.reset();
                             connection
-                                .send_raw(renderer.take_output().as_bytes())
+                                .send_bytes(renderer.take_output().as_bytes())
                                 .await?;
                             let new_desc = connection.read_line().await?.trim().to_string();
 
@@ -840,7 +836,7 @@ async fn show_file_area_management(
                         renderer.write_text("Enter area number to delete: ");
                         renderer.reset();
                         connection
-                            .send_raw(renderer.take_output().as_bytes())
+                            .send_bytes(renderer.take_output().as_bytes())
                             .await?;
 
                         if let Ok(input) = connection.read_line().await
@@ -858,7 +854,7 @@ async fn show_file_area_management(
                             renderer.write_text("Type 'DELETE' to confirm: ");
                             renderer.reset();
                             connection
-                                .send_raw(renderer.take_output().as_bytes())
+                                .send_bytes(renderer.take_output().as_bytes())
                                 .await?;
 
                             if let Ok(confirm) = connection.read_line().await {
@@ -894,7 +890,7 @@ async fn show_file_area_management(
                         renderer.write_text("Enter area number: ");
                         renderer.reset();
                         connection
-                            .send_raw(renderer.take_output().as_bytes())
+                            .send_bytes(renderer.take_output().as_bytes())
                             .await?;
 
                         if let Ok(input) = connection.read_line().await
@@ -917,7 +913,7 @@ async fn show_file_area_management(
                             renderer.write_text("New security level (0-255): ");
                             renderer.reset();
                             connection
-                                .send_raw(renderer.take_output().as_bytes())
+                                .send_bytes(renderer.take_output().as_bytes())
                                 .await?;
                             let new_level: u8 = connection
                                 .read_line()
@@ -954,7 +950,7 @@ async fn show_file_area_management(
                         renderer.write_text("Enter area number to view: ");
                         renderer.reset();
                         connection
-                            .send_raw(renderer.take_output().as_bytes())
+                            .send_bytes(renderer.take_output().as_bytes())
                             .await?;
 
                         if let Ok(input) = connection.read_line().await
@@ -986,7 +982,7 @@ async fn show_file_area_management(
 
 /// Show system maintenance screen
 async fn show_system_maintenance(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     state: &ServerState,
     admin_user: &User,
     renderer: &mut AnsiRenderer,
@@ -1045,7 +1041,7 @@ async fn show_system_maintenance(
         renderer.reset();
 
         connection
-            .send_raw(renderer.take_output().as_bytes())
+            .send_bytes(renderer.take_output().as_bytes())
             .await?;
 
         match connection.read_char().await {
@@ -1061,7 +1057,7 @@ async fn show_system_maintenance(
                         renderer.write_text("Enter session number: ");
                         renderer.reset();
                         connection
-                            .send_raw(renderer.take_output().as_bytes())
+                            .send_bytes(renderer.take_output().as_bytes())
                             .await?;
 
                         if let Ok(input) = connection.read_line().await
@@ -1100,7 +1096,7 @@ async fn show_system_maintenance(
                         renderer.write_text("Enter session number to kick: ");
                         renderer.reset();
                         connection
-                            .send_raw(renderer.take_output().as_bytes())
+                            .send_bytes(renderer.take_output().as_bytes())
                             .await?;
 
                         if let Ok(input) = connection.read_line().await
@@ -1114,7 +1110,7 @@ async fn show_system_maintenance(
                             renderer.write_text("Reason for kick: ");
                             renderer.reset();
                             connection
-                                .send_raw(renderer.take_output().as_bytes())
+                                .send_bytes(renderer.take_output().as_bytes())
                                 .await?;
 
                             if let Ok(reason) = connection.read_line().await {
@@ -1150,7 +1146,7 @@ async fn show_system_maintenance(
                         renderer.write_text("Kick users idle for more than (minutes): ");
                         renderer.reset();
                         connection
-                            .send_raw(renderer.take_output().as_bytes())
+                            .send_bytes(renderer.take_output().as_bytes())
                             .await?;
 
                         if let Ok(input) = connection.read_line().await
@@ -1210,7 +1206,7 @@ async fn show_system_maintenance(
 
 /// Show audit log screen
 async fn show_audit_log(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     _user: &User,
     state: &ServerState,
     renderer: &mut AnsiRenderer,
@@ -1260,7 +1256,7 @@ async fn show_audit_log(
     renderer.write_line("Press any key to continue...");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
     connection.read_char().await.ok();
     Ok(())
@@ -1268,7 +1264,7 @@ async fn show_audit_log(
 
 /// Show broadcast message screen
 async fn show_broadcast(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     state: &ServerState,
     _admin_user: &User,
     renderer: &mut AnsiRenderer,
@@ -1301,7 +1297,7 @@ async fn show_broadcast(
     renderer.reset();
 
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
 
     if let Ok(ch) = connection.read_char().await {
@@ -1313,7 +1309,7 @@ async fn show_broadcast(
                 renderer.write_text("Enter broadcast message: ");
                 renderer.reset();
                 connection
-                    .send_raw(renderer.take_output().as_bytes())
+                    .send_bytes(renderer.take_output().as_bytes())
                     .await?;
 
                 if let Ok(message) = connection.read_line().await {
@@ -1351,7 +1347,7 @@ async fn show_broadcast(
                 renderer.write_text("Username to message: ");
                 renderer.reset();
                 connection
-                    .send_raw(renderer.take_output().as_bytes())
+                    .send_bytes(renderer.take_output().as_bytes())
                     .await?;
 
                 if let Ok(username) = connection.read_line().await {
@@ -1380,7 +1376,7 @@ async fn show_broadcast(
                             renderer.write_text("Message: ");
                             renderer.reset();
                             connection
-                                .send_raw(renderer.take_output().as_bytes())
+                                .send_bytes(renderer.take_output().as_bytes())
                                 .await?;
 
                             if let Ok(message) = connection.read_line().await {
@@ -1,22 +1,27 @@
 //! Door games handler
 
+use crate::menus::commands::time_left;
 use crate::state::ServerState;
 use anyhow::Result;
 use chrono::Utc;
-use impulse_door::{DoorExecutor, DoorSession};
-use impulse_telnet::TelnetConnection;
+use impulse_door::{DoorError, DoorExecutor, DoorIoMode, DoorResult, DoorSession};
+use impulse_session::{Connection, Session, SessionId, SessionManager, bridge};
 use impulse_terminal::{AnsiRenderer, Color};
+use impulse_types::security::SecurityLevel;
 use impulse_types::user::User;
 
 /// Handle doors menu
 pub async fn handle_doors(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     user: &User,
     state: &ServerState,
+    session_manager: &SessionManager,
+    session_id: SessionId,
     renderer: &mut AnsiRenderer,
 ) -> Result<()> {
     // Get available doors
-    let doors = state.door_manager.list_doors();
+    let door_manager = state.door_manager.read().await.clone();
+    let doors = door_manager.list_doors();
 
     loop {
         renderer.clear_screen();
@@ -51,7 +56,7 @@ pub async fn handle_doors(
             renderer.write_line("Press any key to continue...");
             renderer.reset();
             connection
-                .send_raw(renderer.take_output().as_bytes())
+                .send_bytes(renderer.take_output().as_bytes())
                 .await?;
             connection.read_char().await.ok();
             return Ok(());
@@ -88,12 +93,19 @@ pub async fn handle_doors(
             renderer.reset();
         }
 
+        let caller = session_manager.get_session(session_id).await?;
+        let time_left = time_budget(
+            state.max_session_minutes,
+            &caller,
+            user.stats.time_left_today,
+        );
+
         renderer.write_line("");
         renderer.set_foreground(Color::Yellow);
         renderer.write_line(&format!(
             "Your security level: {}  |  Time remaining: {} minutes",
             user.security_level().value(),
-            60 // Placeholder - would come from session
+            time_left / 60
         ));
         renderer.reset();
         renderer.write_line("");
@@ -102,7 +114,7 @@ pub async fn handle_doors(
         renderer.write_text(&format!("Select door (1-{}) or [Q] to quit: ", doors.len()));
         renderer.reset();
         connection
-            .send_raw(renderer.take_output().as_bytes())
+            .send_bytes(renderer.take_output().as_bytes())
             .await?;
 
         // Read selection
@@ -138,7 +150,17 @@ pub async fn handle_doors(
                     }
 
                     // Launch the door
-                    execute_door(connection, user, state, renderer, &door.name).await?;
+                    let caller = session_manager.get_session(session_id).await?;
+                    execute_door(
+                        connection,
+                        user,
+                        state,
+                        session_manager,
+                        &caller,
+                        renderer,
+                        &door.name,
+                    )
+                    .await?;
                 }
             }
             Err(_) => {
@@ -148,11 +170,23 @@ pub async fn handle_doors(
     }
 }
 
-/// Execute a door game
+/// Door time given to callers when the BBS has no session limit
+const UNLIMITED_DOOR_MINUTES: u32 = 24 * 60;
+
+/// Seconds the caller has left to spend in a door
+///
+/// Callers with no limit on their time get a day.
+fn time_budget(max_session_minutes: u32, caller: &Session, time_left_today: i16) -> u32 {
+    time_left(max_session_minutes, caller, time_left_today).unwrap_or(UNLIMITED_DOOR_MINUTES * 60)
+}
+
+/// Execute a door game on the caller's node
 async fn execute_door(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     user: &User,
     state: &ServerState,
+    session_manager: &SessionManager,
+    caller: &Session,
     renderer: &mut AnsiRenderer,
     door_name: &str,
 ) -> Result<()> {
@@ -163,7 +197,8 @@ async fn execute_door(
     renderer.write_line("");
 
     // Get door info
-    let door = match state.door_manager.get_door(door_name) {
+    let door_manager = state.door_manager.read().await.clone();
+    let door = match door_manager.get_door(door_name) {
         Some(d) => d,
         None => {
             renderer.set_foreground(Color::BrightRed);
@@ -184,12 +219,17 @@ async fn execute_door(
     renderer.write_line("Preparing door environment...");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
 
     // Create door session
+    let time_left = time_budget(
+        state.max_session_minutes,
+        caller,
+        user.stats.time_left_today,
+    );
     let mut door_session = DoorSession {
-        node_id: 1, // Would come from actual session
+        node_id: caller.node(),
         user_name: user
             .real_name
             .clone()
@@ -197,7 +237,7 @@ async fn execute_door(
         user_alias: Some(user.username().to_string()),
         location: "Online".to_string(),
         security_level: user.security_level().value(),
-        time_remaining_seconds: 3600, // 60 minutes default
+        time_remaining_seconds: time_left,
         ansi_enabled: true,
         login_time: Utc::now(),
         total_calls: user.stats.logins as u32,
@@ -205,24 +245,46 @@ async fn execute_door(
         upload_kb: user.stats.upload_kb as u64,
         download_kb: user.stats.download_kb as u64,
     };
+    let written = door_session.clone();
 
     // Create door executor
-    let executor = DoorExecutor::new(state.door_manager.clone());
+    let executor = DoorExecutor::new(door_manager.clone());
+
+    // Socket-mode doors talk to a telnet caller directly; callers without a
+    // socket to hand over have the door relayed like any other
+    #[cfg(unix)]
+    let on_socket = door.io_mode == DoorIoMode::Socket && connection.socket().is_some();
+    #[cfg(not(unix))]
+    let on_socket = false;
 
     renderer.write_line("  - Dropfile created");
     renderer.write_line("  - Environment configured");
     renderer.write_line("");
     renderer.set_foreground(Color::BrightCyan);
     renderer.write_line("Starting door game...");
-    renderer.write_line("(Press ESC to exit the door at any time)");
+    // Socket and DOS doors get ESC like any other key
+    if !on_socket && !door.use_dosbox {
+        renderer.write_line("(Press ESC to exit the door at any time)");
+    }
     renderer.reset();
     renderer.write_line("");
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
 
-    // Execute the door
-    match executor.execute(door_name, &mut door_session).await {
+    // The door owns the connection until it exits; the caller's keys go
+    // straight to it
+    let result = if on_socket {
+        execute_on_socket(connection, &executor, door_name, &mut door_session).await
+    } else {
+        let door_session = &mut door_session;
+        bridge(connection, |mut stream| async move {
+            executor.execute(door_name, door_session, &mut stream).await
+        })
+        .await?
+    };
+
+    match result {
         Ok(result) => {
             renderer.write_line("");
             renderer.set_foreground(Color::BrightGreen);
@@ -232,10 +294,41 @@ async fn execute_door(
                 result.runtime_seconds
             ));
             if result.user_stats_updated {
+                keep_door_changes(
+                    user,
+                    state,
+                    session_manager,
+                    caller,
+                    &written,
+                    &door_session,
+                    time_left.saturating_sub(result.runtime_seconds as u32),
+                )
+                .await?;
                 renderer.write_line("User statistics updated.");
             }
             renderer.reset();
         }
+        Err(DoorError::Aborted) => {
+            renderer.write_line("");
+            renderer.set_foreground(Color::BrightYellow);
+            renderer.write_line("Door closed. Returning to the BBS.");
+            renderer.reset();
+        }
+        Err(DoorError::DoorInUse { node, .. }) => {
+            renderer.write_line("");
+            renderer.set_foreground(Color::BrightYellow);
+            renderer.write_line(&format!(
+                "This door is in use by node {}. Please try again later.",
+                node
+            ));
+            renderer.reset();
+        }
+        Err(DoorError::Timeout(_)) => {
+            renderer.write_line("");
+            renderer.set_foreground(Color::BrightYellow);
+            renderer.write_line("Your time in this door is up. Returning to the BBS.");
+            renderer.reset();
+        }
         Err(e) => {
             renderer.write_line("");
             renderer.set_foreground(Color::BrightRed);
@@ -264,18 +357,211 @@ async fn execute_door(
     Ok(())
 }
 
-/// Helper to wait for key press
-async fn wait_for_key(
-    connection: &mut TelnetConnection,
-    renderer: &mut AnsiRenderer,
+/// Keep the changes a door made to the caller's DOOR.SYS
+///
+/// `written` is the session as the DOOR.SYS was written and `door_session`
+/// as the door left it. Only what the door changed is applied, to the
+/// account as it is now, so uploads and downloads credited during the call
+/// are kept. `expected_time` is what the caller would have had left without
+/// the door's changes; the difference stays with them for the rest of the
+/// call.
+async fn keep_door_changes(
+    user: &User,
+    state: &ServerState,
+    session_manager: &SessionManager,
+    caller: &Session,
+    written: &DoorSession,
+    door_session: &DoorSession,
+    expected_time: u32,
 ) -> Result<()> {
+    let time_change = i64::from(door_session.time_remaining_seconds) - i64::from(expected_time);
+    if time_change != 0 {
+        session_manager
+            .add_time_credit(caller.id(), time_change)
+            .await?;
+    }
+
+    let mut user_manager = state.user_manager.write().await;
+    let mut account = user_manager.get_user(user.id()).await?;
+    // Doors may only take security away
+    if door_session.security_level != written.security_level {
+        let level = door_session
+            .security_level
+            .min(account.security_level().value());
+        account.set_security_level(SecurityLevel::new(level));
+    }
+    account.stats.upload_kb = apply_change(
+        account.stats.upload_kb,
+        written.upload_kb,
+        door_session.upload_kb,
+    );
+    account.stats.download_kb = apply_change(
+        account.stats.download_kb,
+        written.download_kb,
+        door_session.download_kb,
+    );
+    user_manager.update_user(account).await?;
+    Ok(())
+}
+
+/// Add the change a door made to a total, from `before` to `after`
+fn apply_change(total: u32, before: u64, after: u64) -> u32 {
+    let total = i128::from(total) + i128::from(after) - i128::from(before);
+    total.clamp(0, i128::from(u32::MAX)) as u32
+}
+
+/// Run a door on the caller's own socket
+#[cfg(unix)]
+async fn execute_on_socket(
+    connection: &dyn Connection,
+    executor: &DoorExecutor,
+    door_name: &str,
+    session: &mut DoorSession,
+) -> impulse_door::Result<DoorResult> {
+    let socket = connection
+        .socket()
+        .ok_or_else(|| DoorError::InvalidConfig("Caller has no socket to hand over".to_string()))?;
+    executor
+        .execute_with_socket(door_name, session, socket)
+        .await
+}
+
+/// Socket-mode doors need Unix descriptor passing
+#[cfg(not(unix))]
+async fn execute_on_socket(
+    _connection: &dyn Connection,
+    _executor: &DoorExecutor,
+    _door_name: &str,
+    _session: &mut DoorSession,
+) -> impulse_door::Result<DoorResult> {
+    Err(DoorError::InvalidConfig(
+        "Socket doors are only supported on Unix".to_string(),
+    ))
+}
+
+/// Helper to wait for key press
+async fn wait_for_key(connection: &mut dyn Connection, renderer: &mut AnsiRenderer) -> Result<()> {
     renderer.write_line("\r\n");
     renderer.set_foreground(Color::BrightYellow);
     renderer.write_line("Press any key to continue...");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
     connection.read_char().await.ok();
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::test_config;
+    use impulse_session::SessionConfig;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_apply_change() {
+        assert_eq!(apply_change(150, 100, 100), 150);
+        assert_eq!(apply_change(150, 100, 120), 170);
+        assert_eq!(apply_change(150, 100, 0), 50);
+        assert_eq!(apply_change(10, 100, 0), 0);
+        assert_eq!(apply_change(u32::MAX, 0, 5), u32::MAX);
+    }
+
+    #[tokio::test]
+    async fn test_door_changes_keep_transfers_made_during_the_call() {
+        let dir = TempDir::new().unwrap();
+        let state = ServerState::new(&test_config(dir.path())).await.unwrap();
+        let session_manager = SessionManager::new(SessionConfig::default());
+        let session_id = session_manager.create_session("test").await.unwrap();
+        let caller = session_manager.get_session(session_id).await.unwrap();
+
+        // The caller logs in at level 100 with 100 KB uploaded
+        let mut user = User::new("Bob").unwrap();
+        user.set_security_level(SecurityLevel::new(100));
+        user.stats.upload_kb = 100;
+        user.stats.download_kb = 20;
+        state
+            .user_manager
+            .write()
+            .await
+            .create_user(user.clone())
+            .await
+            .unwrap();
+
+        // Then uploads 50 KB and is lowered to 50 by the SysOp
+        {
+            let mut user_manager = state.user_manager.write().await;
+            let mut account = user_manager.get_user(user.id()).await.unwrap();
+            account.stats.upload_kb = 150;
+            account.set_security_level(SecurityLevel::new(50));
+            user_manager.update_user(account).await.unwrap();
+        }
+
+        let written = DoorSession {
+            node_id: 1,
+            user_name: "Bob".to_string(),
+            user_alias: None,
+            location: "Online".to_string(),
+            security_level: 100,
+            time_remaining_seconds: 600,
+            ansi_enabled: true,
+            login_time: Utc::now(),
+            total_calls: 1,
+            last_call_date: String::new(),
+            upload_kb: 100,
+            download_kb: 20,
+        };
+
+        // A door that only changes the time leaves the account alone
+        let mut door_session = written.clone();
+        door_session.time_remaining_seconds = 300;
+        keep_door_changes(
+            &user,
+            &state,
+            &session_manager,
+            &caller,
+            &written,
+            &door_session,
+            600,
+        )
+        .await
+        .unwrap();
+        let account = state
+            .user_manager
+            .read()
+            .await
+            .get_user(user.id())
+            .await
+            .unwrap();
+        assert_eq!(account.stats.upload_kb, 150);
+        assert_eq!(account.stats.download_kb, 20);
+        assert_eq!(account.security_level().value(), 50);
+
+        // One that changes them has its changes added to the current totals,
+        // and cannot lift security past where it is now
+        door_session.security_level = 80;
+        door_session.download_kb = 25;
+        keep_door_changes(
+            &user,
+            &state,
+            &session_manager,
+            &caller,
+            &written,
+            &door_session,
+            600,
+        )
+        .await
+        .unwrap();
+        let account = state
+            .user_manager
+            .read()
+            .await
+            .get_user(user.id())
+            .await
+            .unwrap();
+        assert_eq!(account.stats.upload_kb, 150);
+        assert_eq!(account.stats.download_kb, 25);
+        assert_eq!(account.security_level().value(), 50);
+    }
+}
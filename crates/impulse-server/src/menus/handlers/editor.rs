@@ -0,0 +1,130 @@
+//! Message body entry
+//!
+//! Callers with ANSI get the full-screen editor, with a quote window when
+//! they are replying. Everyone else, and anyone who has turned the editor
+//! off in their settings, types the body a line at a time.
+
+use anyhow::Result;
+use impulse_message::FullMessage;
+use impulse_message::quote::{quote_lines, quote_message, wrap_quoted_text};
+use impulse_session::Connection;
+use impulse_terminal::{
+    AnsiRenderer, Color, ESCAPE_TIMEOUT, EditorEvent, FullScreenEditor, KeyDecoder,
+};
+use impulse_types::user::User;
+
+/// Let the caller write a message body
+///
+/// `title` is shown above the full-screen editor and `original` is the
+/// message being replied to, if any. Returns `None` when the caller aborts
+/// or writes nothing.
+pub async fn edit_body(
+    connection: &mut dyn Connection,
+    user: &User,
+    renderer: &mut AnsiRenderer,
+    title: &str,
+    original: Option<&FullMessage>,
+) -> Result<Option<String>> {
+    let body = if user.preferences.ansi_enabled && !user.preferences.skip_full_screen_editor {
+        full_screen(connection, renderer, title, original).await?
+    } else {
+        line_editor(connection, renderer, original).await?
+    };
+    Ok(body.filter(|body| !body.trim().is_empty()))
+}
+
+/// Edit with the full-screen editor
+async fn full_screen(
+    connection: &mut dyn Connection,
+    renderer: &mut AnsiRenderer,
+    title: &str,
+    original: Option<&FullMessage>,
+) -> Result<Option<String>> {
+    let (columns, rows) = connection.terminal_size();
+    let mut editor = FullScreenEditor::new(columns, rows).with_title(title);
+    if let Some(original) = original {
+        editor = editor.with_quote_source(&original.body);
+    }
+    let mut decoder = KeyDecoder::new();
+
+    let body = 'edit: loop {
+        // Draw once a whole key sequence has arrived
+        if !decoder.in_sequence() {
+            editor.render(renderer);
+            connection
+                .send_bytes(renderer.take_output().as_bytes())
+                .await?;
+        }
+
+        // A lone ESC is only the ESC key once nothing follows it in time
+        let key = if decoder.awaiting_escape() {
+            match tokio::time::timeout(ESCAPE_TIMEOUT, connection.read_char()).await {
+                Ok(ch) => decoder.feed(ch?),
+                Err(_) => decoder.flush(),
+            }
+        } else {
+            decoder.feed(connection.read_char().await?)
+        };
+
+        let pending = decoder.take_pending();
+        for key in key.into_iter().chain(pending) {
+            let quote = match (editor.handle_key(key), original) {
+                (EditorEvent::Save, _) => break 'edit Some(editor.text()),
+                (EditorEvent::Abort, _) => break 'edit None,
+                (EditorEvent::QuoteAll, Some(original)) => {
+                    quote_message(&original.body, Some(&original.header.from), true)
+                }
+                (EditorEvent::Quote { start, end }, Some(original)) => {
+                    quote_lines(&original.body, start, end)
+                }
+                _ => continue,
+            };
+            editor.insert_text(&wrap_quoted_text(&quote, editor.width()));
+        }
+    };
+
+    renderer.clear_screen();
+    connection
+        .send_bytes(renderer.take_output().as_bytes())
+        .await?;
+    Ok(body)
+}
+
+/// Read the body a line at a time, ending at a blank line
+///
+/// A reply has the whole original quoted below it.
+async fn line_editor(
+    connection: &mut dyn Connection,
+    renderer: &mut AnsiRenderer,
+    original: Option<&FullMessage>,
+) -> Result<Option<String>> {
+    renderer.write_line("");
+    renderer.set_foreground(Color::BrightWhite);
+    renderer.write_line("Enter message body (blank line to end):");
+    renderer.reset();
+    connection
+        .send_bytes(renderer.take_output().as_bytes())
+        .await?;
+
+    let mut body_lines = Vec::new();
+    while let Ok(line) = connection.read_line().await {
+        if line.trim().is_empty() {
+            break;
+        }
+        body_lines.push(line);
+    }
+    if body_lines.is_empty() {
+        return Ok(None);
+    }
+
+    let mut body = body_lines.join("\n");
+    if let Some(original) = original {
+        body.push_str("\n\n");
+        body.push_str(&quote_message(
+            &original.body,
+            Some(&original.header.from),
+            true,
+        ));
+    }
+    Ok(Some(body))
+}
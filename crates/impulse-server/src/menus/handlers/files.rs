@@ -2,16 +2,24 @@
 
 use crate::state::ServerState;
 use anyhow::Result;
+use impulse_file::FileError;
+use impulse_file::screens::progress::TransferProgressScreen;
 use impulse_file::screens::{AreaSelectionScreen, FileDetailsScreen, FileListScreen};
 use impulse_file::traits::FileAreaManager;
-use impulse_telnet::TelnetConnection;
+use impulse_file::transfer::{
+    DownloadManager, Protocol, TransferConfig, TransferStatus, UploadManager,
+};
+use impulse_file::upload::{FileStorage, PendingUpload};
+use impulse_session::{Connection, ConnectionType, bridge};
 use impulse_terminal::{AnsiRenderer, Color};
 use impulse_types::file::FileEntry;
 use impulse_types::user::User;
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// Handle files menu
 pub async fn handle_files(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     user: &User,
     state: &ServerState,
     renderer: &mut AnsiRenderer,
@@ -37,7 +45,7 @@ pub async fn handle_files(
         renderer.write_line("Press any key to continue...");
         renderer.reset();
         connection
-            .send_raw(renderer.take_output().as_bytes())
+            .send_bytes(renderer.take_output().as_bytes())
             .await?;
         connection.read_char().await.ok();
         return Ok(());
@@ -65,7 +73,7 @@ pub async fn handle_files(
     renderer.reset();
 
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
 
     // Read selection
@@ -95,7 +103,7 @@ pub async fn handle_files(
 
 /// Show file list for a specific area
 async fn show_file_list(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     user: &User,
     state: &ServerState,
     renderer: &mut AnsiRenderer,
@@ -138,7 +146,7 @@ async fn show_file_list(
         renderer.reset();
 
         connection
-            .send_raw(renderer.take_output().as_bytes())
+            .send_bytes(renderer.take_output().as_bytes())
             .await?;
 
         // Read command
@@ -161,7 +169,7 @@ async fn show_file_list(
                 {
                     // View file details and optionally download
                     let file = files[num - 1].clone();
-                    handle_file_details(connection, user, renderer, &file).await?;
+                    handle_file_details(connection, user, state, renderer, &file).await?;
                 }
             }
             Err(_) => {
@@ -173,8 +181,9 @@ async fn show_file_list(
 
 /// Handle file details and download
 async fn handle_file_details(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     user: &User,
+    state: &ServerState,
     renderer: &mut AnsiRenderer,
     file: &FileEntry,
 ) -> Result<()> {
@@ -197,14 +206,14 @@ async fn handle_file_details(
     renderer.reset();
 
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
 
     if let Ok(ch) = connection.read_char().await
         && ch.eq_ignore_ascii_case(&'D')
     {
         // Initiate download
-        handle_download(connection, user, renderer, file).await?;
+        handle_download(connection, user, state, renderer, file).await?;
     }
 
     Ok(())
@@ -212,8 +221,9 @@ async fn handle_file_details(
 
 /// Handle file download with protocol selection
 async fn handle_download(
-    connection: &mut TelnetConnection,
-    _user: &User,
+    connection: &mut dyn Connection,
+    user: &User,
+    state: &ServerState,
     renderer: &mut AnsiRenderer,
     file: &FileEntry,
 ) -> Result<()> {
@@ -229,6 +239,10 @@ async fn handle_download(
     renderer.reset();
     renderer.write_line("");
 
+    if !can_transfer(connection) {
+        return no_transfers(connection, renderer).await;
+    }
+
     // Note: File-level security is handled at the area level during listing
     // Individual files inherit security from their containing area
 
@@ -257,66 +271,154 @@ async fn handle_download(
     renderer.reset();
 
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
 
-    if let Ok(ch) = connection.read_char().await {
-        let protocol = match ch.to_ascii_uppercase() {
-            'Z' => Some(("Zmodem", "32-bit CRC, streaming, crash recovery")),
-            'Y' => Some(("Ymodem", "16-bit CRC, batch mode")),
-            'G' => Some(("Ymodem-G", "streaming, no error correction")),
-            'X' => Some(("Xmodem", "checksum/CRC, 128-byte blocks")),
-            'Q' => None,
+    let protocol = match connection.read_char().await {
+        Ok(ch) => match ch.to_ascii_uppercase() {
+            'Z' => Some((Protocol::Zmodem, "32-bit CRC, streaming, crash recovery")),
+            'Y' => Some((Protocol::Ymodem, "16-bit CRC, batch mode")),
+            'G' => Some((Protocol::YmodemG, "streaming, no error correction")),
+            'X' => Some((Protocol::Xmodem, "checksum/CRC, 128-byte blocks")),
             _ => None,
-        };
+        },
+        Err(_) => None,
+    };
 
-        if let Some((protocol_name, features)) = protocol {
-            renderer.write_line("\r\n");
-            renderer.set_foreground(Color::BrightGreen);
-            renderer.write_line(&format!("Protocol: {} ({})", protocol_name, features));
-            renderer.reset();
-            renderer.write_line("");
+    let Some((protocol, features)) = protocol else {
+        renderer.write_line("\r\n");
+        renderer.set_foreground(Color::BrightYellow);
+        renderer.write_line("Download cancelled.");
+        renderer.reset();
+        wait_for_key(connection, renderer).await?;
+        return Ok(());
+    };
 
-            // Simulate transfer initiation
-            renderer.set_foreground(Color::BrightYellow);
-            renderer.write_line("Initiating transfer...");
-            renderer.reset();
-            renderer.write_line("");
-
-            renderer.write_line("  - Preparing file for transfer");
-            renderer.write_line(&format!("  - File: {}", file.filename));
-            renderer.write_line(&format!("  - Size: {} bytes", file.size_bytes));
-            renderer.write_line(&format!("  - Protocol: {}", protocol_name));
-            renderer.write_line("");
-
-            // In a real implementation, this would start the actual protocol transfer
-            renderer.set_foreground(Color::BrightCyan);
-            renderer.write_line("Ready to send. Start your terminal's receive mode now.");
-            renderer.write_line("");
-            renderer.write_line("Transfer would begin here with actual protocol negotiation.");
-            renderer.reset();
+    renderer.write_line("\r\n");
+    renderer.set_foreground(Color::BrightGreen);
+    renderer.write_line(&format!("Protocol: {} ({})", protocol.name(), features));
+    renderer.reset();
+    renderer.write_line("");
 
-            // Increment download count (in real implementation)
-            renderer.write_line("");
-            renderer.set_foreground(Color::BrightGreen);
-            renderer.write_line("Download statistics will be updated upon completion.");
-            renderer.reset();
-        } else {
-            renderer.write_line("\r\n");
-            renderer.set_foreground(Color::BrightYellow);
-            renderer.write_line("Download cancelled.");
-            renderer.reset();
+    let path = download_path(state, file).await?;
+    if !path.is_file() {
+        tracing::warn!(file = %file.filename, path = ?path, "Download requested for missing file");
+        renderer.set_foreground(Color::BrightRed);
+        renderer.write_line("This file is not available right now. Please notify the SysOp.");
+        renderer.reset();
+        wait_for_key(connection, renderer).await?;
+        return Ok(());
+    }
+
+    renderer.set_foreground(Color::BrightCyan);
+    renderer.write_line("Ready to send. Start your terminal's receive mode now.");
+    renderer.reset();
+    renderer.write_line("");
+    connection
+        .send_bytes(renderer.take_output().as_bytes())
+        .await?;
+
+    // The transfer owns the connection until it finishes; the progress
+    // screen follows it and is shown to the caller afterwards
+    let mut progress = TransferProgressScreen::download(file.filename.clone());
+    let config = TransferConfig::default().with_protocol(protocol);
+    let result = bridge(connection, |stream| async {
+        DownloadManager::new(stream, config)
+            .download_file(&path, &mut progress)
+            .await
+    })
+    .await??;
+    discard_pending_input(connection).await;
+
+    renderer.write_line("\r\n");
+    renderer.write_text(&progress.render().replace('\n', "\r\n"));
+
+    if result.status == TransferStatus::Completed {
+        if result.was_resumed {
+            renderer.write_line(&format!("Resumed at byte {}.", result.resume_offset));
         }
+        record_download(state, user, file, result.bytes_transferred).await?;
+        tracing::info!(
+            username = %user.username(),
+            file = %file.filename,
+            protocol = protocol.name(),
+            bytes = result.bytes_transferred,
+            resumed = result.was_resumed,
+            "File downloaded"
+        );
+    } else {
+        tracing::info!(
+            username = %user.username(),
+            file = %file.filename,
+            protocol = protocol.name(),
+            error = ?progress.last_error,
+            "Download failed"
+        );
     }
 
     wait_for_key(connection, renderer).await?;
     Ok(())
 }
 
+/// Locate a file on disk
+///
+/// Areas configured with a path keep their files there; others use the
+/// upload storage layout under the files directory.
+pub(super) async fn download_path(state: &ServerState, file: &FileEntry) -> Result<PathBuf> {
+    let area = state
+        .file_manager
+        .read()
+        .await
+        .get_area(file.area_id)
+        .await?;
+    Ok(match area.and_then(|area| area.path) {
+        Some(area_path) => area_path.join(&file.filename),
+        None => FileStorage::new(&state.paths.files_dir).file_path(file.area_id, &file.filename),
+    })
+}
+
+/// Count a completed download against the file and the caller's account
+async fn record_download(
+    state: &ServerState,
+    user: &User,
+    file: &FileEntry,
+    bytes: u64,
+) -> Result<()> {
+    state
+        .file_manager
+        .write()
+        .await
+        .record_download(u64::from(file.id))
+        .await?;
+
+    let mut user_manager = state.user_manager.write().await;
+    let mut account = user_manager.get_user(user.id()).await?;
+    account
+        .stats
+        .record_download(1, u32::try_from(bytes.div_ceil(1024)).unwrap_or(u32::MAX));
+    user_manager.update_user(account).await?;
+    Ok(())
+}
+
+/// Discard whatever the caller's transfer program sent after the transfer
+///
+/// Receivers often answer the end of a session with a few more bytes,
+/// which would otherwise be taken as the next keypress.
+async fn discard_pending_input(connection: &mut dyn Connection) {
+    while let Ok(Ok(Some(_))) =
+        tokio::time::timeout(Duration::from_millis(500), connection.recv()).await
+    {}
+}
+
 /// Handle file upload
+///
+/// Receives with the caller's chosen protocol into their own incoming
+/// directory, then passes each received file through the upload
+/// processor, which validates, scans and catalogues it. Accepted files are
+/// credited to the caller's account.
 async fn handle_upload(
-    connection: &mut TelnetConnection,
-    _state: &ServerState,
+    connection: &mut dyn Connection,
+    state: &ServerState,
     user: &User,
     renderer: &mut AnsiRenderer,
     area_id: u32,
@@ -327,97 +429,202 @@ async fn handle_upload(
     renderer.reset();
     renderer.write_line("");
 
+    if !can_transfer(connection) {
+        return no_transfers(connection, renderer).await;
+    }
+
     // Show user's upload statistics
     renderer.set_foreground(Color::BrightWhite);
     renderer.write_line(&format!("Files uploaded: {}", user.stats.uploads));
     renderer.write_line(&format!("Uploaded: {} KB", user.stats.upload_kb));
+    renderer.write_line(&format!(
+        "Max file size: {}",
+        format_size(state.upload_processor.lock().await.config().max_file_size)
+    ));
+    renderer.reset();
+    renderer.write_line("");
+
+    // Select upload protocol
+    renderer.set_foreground(Color::BrightCyan);
+    renderer.write_line("Select upload protocol:");
     renderer.reset();
+    renderer.write_line("  [Z] Zmodem (Recommended)");
+    renderer.write_line("  [Y] Ymodem");
+    renderer.write_line("  [G] Ymodem-G");
+    renderer.write_line("  [X] Xmodem");
+    renderer.write_line("  [Q] Cancel upload");
     renderer.write_line("");
 
-    // Get filename
     renderer.set_foreground(Color::BrightYellow);
-    renderer.write_text("Filename to upload (or Q to cancel): ");
+    renderer.write_text("Protocol: ");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
-    let filename = connection.read_line().await?.trim().to_string();
 
-    if filename.is_empty() || filename.eq_ignore_ascii_case("q") {
+    let protocol = match connection.read_char().await {
+        Ok(ch) => match ch.to_ascii_uppercase() {
+            'Z' => Some(Protocol::Zmodem),
+            'Y' => Some(Protocol::Ymodem),
+            'G' => Some(Protocol::YmodemG),
+            'X' => Some(Protocol::Xmodem),
+            _ => None,
+        },
+        Err(_) => None,
+    };
+    renderer.write_line("\r\n");
+
+    let Some(protocol) = protocol else {
+        return upload_cancelled(connection, renderer).await;
+    };
+
+    // Xmodem does not carry the file name, so the caller names the file
+    let mut filename = None;
+    if protocol == Protocol::Xmodem {
         renderer.set_foreground(Color::BrightYellow);
-        renderer.write_line("\r\nUpload cancelled.");
+        renderer.write_text("Filename to upload (or Q to cancel): ");
         renderer.reset();
-        wait_for_key(connection, renderer).await?;
-        return Ok(());
+        connection
+            .send_bytes(renderer.take_output().as_bytes())
+            .await?;
+        let name = connection.read_line().await?.trim().to_string();
+        if name.is_empty() || name.eq_ignore_ascii_case("q") {
+            return upload_cancelled(connection, renderer).await;
+        }
+        filename = Some(name);
     }
 
-    // Get description
+    // A FILE_ID.DIZ in the upload takes precedence over this
     renderer.set_foreground(Color::BrightYellow);
-    renderer.write_text("Description: ");
+    renderer.write_text("Description (used if the file has no FILE_ID.DIZ): ");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
     let description = connection.read_line().await?.trim().to_string();
+    let description = (!description.is_empty()).then_some(description);
 
-    // Select upload protocol
     renderer.write_line("");
     renderer.set_foreground(Color::BrightCyan);
-    renderer.write_line("Select upload protocol:");
+    renderer.write_line(&format!(
+        "Ready to receive with {}. Start your terminal's send mode now.",
+        protocol.name()
+    ));
     renderer.reset();
-    renderer.write_line("  [Z] Zmodem (Recommended)");
-    renderer.write_line("  [Y] Ymodem");
-    renderer.write_line("  [X] Xmodem");
     renderer.write_line("");
-
-    renderer.set_foreground(Color::BrightYellow);
-    renderer.write_text("Protocol: ");
-    renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
 
-    if let Ok(ch) = connection.read_char().await {
-        let protocol = match ch.to_ascii_uppercase() {
-            'Z' => "Zmodem",
-            'Y' => "Ymodem",
-            'X' => "Xmodem",
-            _ => "Zmodem",
-        };
+    // Files arrive in the caller's own incoming directory, on the same
+    // filesystem as the areas so accepted files are moved, not copied.
+    // An interrupted Zmodem upload is resumed from what is left there.
+    let incoming_dir = state
+        .paths
+        .files_dir
+        .join(".incoming")
+        .join(user.id().as_uuid().to_string());
+    let mut progress =
+        TransferProgressScreen::upload(filename.clone().unwrap_or_else(|| "batch".to_string()));
+    let config = TransferConfig::default().with_protocol(protocol);
+    let results = bridge(connection, |stream| async {
+        let mut manager = UploadManager::new(stream, config, incoming_dir);
+        if let Some(filename) = filename {
+            manager.set_filename(filename);
+        }
+        manager.receive_batch(&mut progress).await
+    })
+    .await?;
+    discard_pending_input(connection).await;
 
-        renderer.write_line("\r\n");
-        renderer.set_foreground(Color::BrightWhite);
-        renderer.write_line("Upload Configuration:");
-        renderer.reset();
-        renderer.write_line(&format!("  - Target filename: {}", filename));
-        renderer.write_line(&format!("  - Description: {}", description));
-        renderer.write_line(&format!("  - Area ID: {}", area_id));
-        renderer.write_line(&format!("  - Protocol: {}", protocol));
-        renderer.write_line("");
+    renderer.write_line("\r\n");
+    renderer.write_text(&progress.render().replace('\n', "\r\n"));
+    renderer.write_line("");
 
-        renderer.set_foreground(Color::Yellow);
-        renderer.write_line("Upload Processing:");
-        renderer.reset();
-        renderer.write_line("  - Max file size: 50 MB");
-        renderer.write_line("  - Virus scanning: ClamAV integration available");
-        renderer.write_line("  - Validation: Extension checking, duplicate detection");
-        renderer.write_line("  - FILE_ID.DIZ: Auto-extraction from archives");
-        renderer.write_line("");
+    let mut accepted = Vec::new();
+    for result in results {
+        if result.status != TransferStatus::Completed {
+            tracing::info!(
+                username = %user.username(),
+                file = %result.filename,
+                protocol = protocol.name(),
+                error = ?progress.last_error,
+                "Upload failed"
+            );
+            renderer.set_foreground(Color::BrightRed);
+            renderer.write_line(&format!("  {}: transfer failed", result.filename));
+            renderer.reset();
+            continue;
+        }
 
-        renderer.set_foreground(Color::BrightCyan);
-        renderer.write_line("Ready to receive. Start your terminal's send mode now.");
-        renderer.write_line("");
-        renderer.write_line("Transfer would begin here with actual protocol negotiation.");
-        renderer.reset();
-        renderer.write_line("");
+        let upload = PendingUpload {
+            size_bytes: tokio::fs::metadata(&result.file_path).await?.len(),
+            temp_path: result.file_path,
+            filename: result.filename,
+            area_id,
+            uploader_id: user.id().as_u32(),
+            uploader_name: user.username().to_string(),
+            manual_description: description.clone(),
+        };
+        let filename = upload.filename.clone();
+
+        match state.upload_processor.lock().await.process(upload).await {
+            Ok(entry) => {
+                tracing::info!(
+                    username = %user.username(),
+                    file = %entry.filename,
+                    area = area_id,
+                    protocol = protocol.name(),
+                    bytes = entry.size_bytes,
+                    "File uploaded"
+                );
+                renderer.set_foreground(Color::BrightGreen);
+                renderer.write_line(&format!(
+                    "  {}: accepted ({})",
+                    entry.filename,
+                    format_size(entry.size_bytes)
+                ));
+                renderer.reset();
+                accepted.push(entry);
+            }
+            Err(e) => {
+                if matches!(e, FileError::VirusDetected(_)) {
+                    tracing::warn!(
+                        username = %user.username(),
+                        file = %filename,
+                        error = %e,
+                        "Infected upload quarantined"
+                    );
+                } else {
+                    tracing::info!(
+                        username = %user.username(),
+                        file = %filename,
+                        error = %e,
+                        "Upload rejected"
+                    );
+                }
+                let reason = match e {
+                    FileError::DuplicateFile(_) => "a copy is already on file".to_string(),
+                    e => e.to_string(),
+                };
+                renderer.set_foreground(Color::BrightRed);
+                renderer.write_line(&format!("  {}: rejected - {}", filename, reason));
+                renderer.reset();
+            }
+        }
+    }
 
-        renderer.set_foreground(Color::BrightGreen);
-        renderer.write_line("Upload will be processed upon completion:");
-        renderer.write_line("  1. File received and validated");
-        renderer.write_line("  2. Virus scan executed");
-        renderer.write_line("  3. Description extracted (if archive)");
-        renderer.write_line("  4. Added to file area catalog");
-        renderer.write_line("  5. Statistics updated");
+    if !accepted.is_empty() {
+        let points = credit_uploads(state, user, &accepted).await?;
+        renderer.write_line("");
+        renderer.set_foreground(Color::BrightWhite);
+        renderer.write_line(&format!(
+            "Thank you! {} file(s) credited to your account.",
+            accepted.len()
+        ));
+        if points > 0 {
+            renderer.write_line(&format!("You earned {} file point(s).", points));
+        }
         renderer.reset();
     }
 
@@ -425,9 +632,62 @@ async fn handle_upload(
     Ok(())
 }
 
+/// Tell the caller their upload was cancelled
+async fn upload_cancelled(
+    connection: &mut dyn Connection,
+    renderer: &mut AnsiRenderer,
+) -> Result<()> {
+    renderer.set_foreground(Color::BrightYellow);
+    renderer.write_line("Upload cancelled.");
+    renderer.reset();
+    wait_for_key(connection, renderer).await
+}
+
+/// Credit accepted uploads to the caller's account
+///
+/// Returns the file points awarded.
+async fn credit_uploads(state: &ServerState, user: &User, files: &[FileEntry]) -> Result<i16> {
+    let processor = state.upload_processor.lock().await;
+    let points = files.iter().fold(0i16, |total, file| {
+        total.saturating_add(processor.config().file_points(file.size_bytes))
+    });
+    drop(processor);
+
+    let kilobytes: u64 = files
+        .iter()
+        .map(|file| file.size_bytes.div_ceil(1024))
+        .sum();
+
+    let mut user_manager = state.user_manager.write().await;
+    let mut account = user_manager.get_user(user.id()).await?;
+    account.stats.record_upload(
+        u16::try_from(files.len()).unwrap_or(u16::MAX),
+        u32::try_from(kilobytes).unwrap_or(u32::MAX),
+    );
+    account.stats.award_file_points(points);
+    user_manager.update_user(account).await?;
+    Ok(points)
+}
+
+/// Whether the caller's terminal can run a transfer protocol
+///
+/// The browser terminal has no Zmodem, Ymodem or Xmodem of its own.
+fn can_transfer(connection: &dyn Connection) -> bool {
+    connection.connection_type() != ConnectionType::WebSocket
+}
+
+/// Tell a caller their terminal cannot transfer files
+async fn no_transfers(connection: &mut dyn Connection, renderer: &mut AnsiRenderer) -> Result<()> {
+    renderer.set_foreground(Color::BrightYellow);
+    renderer.write_line("File transfers need a terminal program such as SyncTERM.");
+    renderer.write_line("Connect over telnet or SSH to download or upload files.");
+    renderer.reset();
+    wait_for_key(connection, renderer).await
+}
+
 /// Handle file search
 async fn handle_search(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     state: &ServerState,
     user: &User,
     renderer: &mut AnsiRenderer,
@@ -450,7 +710,7 @@ async fn handle_search(
     renderer.write_text("Search pattern: ");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
 
     let pattern = connection.read_line().await?.trim().to_string();
@@ -543,7 +803,7 @@ async fn handle_search(
         renderer.write_text("Enter file # to download (or Q to return): ");
         renderer.reset();
         connection
-            .send_raw(renderer.take_output().as_bytes())
+            .send_bytes(renderer.take_output().as_bytes())
             .await?;
 
         if let Ok(input) = connection.read_line().await
@@ -553,7 +813,7 @@ async fn handle_search(
             && num <= matches.len()
         {
             let file = matches[num - 1];
-            handle_download(connection, user, renderer, file).await?;
+            handle_download(connection, user, state, renderer, file).await?;
             return Ok(());
         }
     }
@@ -574,16 +834,13 @@ fn format_size(bytes: u64) -> String {
 }
 
 /// Helper to wait for key press
-async fn wait_for_key(
-    connection: &mut TelnetConnection,
-    renderer: &mut AnsiRenderer,
-) -> Result<()> {
+async fn wait_for_key(connection: &mut dyn Connection, renderer: &mut AnsiRenderer) -> Result<()> {
     renderer.write_line("\r\n");
     renderer.set_foreground(Color::BrightYellow);
     renderer.write_line("Press any key to continue...");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
     connection.read_char().await.ok();
     Ok(())
@@ -1,28 +1,59 @@
 //! Message areas handler
 
-use crate::state::ServerState;
+use crate::menus::handlers::editor::edit_body;
+use crate::menus::handlers::netmail::{can_write_netmail, handle_netmail};
+use crate::state::{ServerState, SharedMessageBase};
 use anyhow::Result;
+use impulse_message::MessageArea;
 use impulse_message::screens::{MessageListConfig, MessageListScreen, MessageReadScreen};
-use impulse_message::traits::MessageBase;
+use impulse_message::types::MessageHeader;
 use impulse_message::{NewMessage, ReplyBuilder};
-use impulse_telnet::TelnetConnection;
+use impulse_session::Connection;
 use impulse_terminal::{AnsiRenderer, Color};
+use impulse_types::config::MessageBaseFormat;
 use impulse_types::user::User;
 
 /// Handle messages menu
 pub async fn handle_messages(
-    connection: &mut TelnetConnection,
-    _user: &User,
+    connection: &mut dyn Connection,
+    user: &User,
     state: &ServerState,
     renderer: &mut AnsiRenderer,
 ) -> Result<()> {
+    let level = user.security_level().value();
+    let registry = &state.message_areas.registry;
+
+    // Start in the first conference and area the caller may use
+    let mut conference = registry
+        .conferences(level)
+        .first()
+        .map(|conference| conference.tag.clone());
+    let Some(mut area) = first_area(state, conference.as_deref(), level) else {
+        return show_notice(
+            connection,
+            renderer,
+            Color::BrightRed,
+            "There are no message areas you can read.",
+        )
+        .await;
+    };
+    let mut page = 0;
+
     loop {
+        let Some(base) = state.message_areas.base(&area.tag) else {
+            return Ok(());
+        };
+
         // Create message list screen
         let mut list_screen = MessageListScreen::new(MessageListConfig::default());
 
         // Load messages from the message base
-        let message_base = state.message_base.read().await;
-        match list_screen.load_page(&*message_base, 0).await {
+        let message_base = base.read().await;
+        let last_read = message_base
+            .last_read(user.username(), user.id().as_u32())
+            .await
+            .unwrap_or(0);
+        match list_screen.load_page(&**message_base, page).await {
             Ok(_) => {
                 // Display message list
                 renderer.clear_screen();
@@ -37,23 +68,28 @@ pub async fn handle_messages(
                     "╚══════════════════════════════════════════════════════════════════════════╝",
                 );
                 renderer.reset();
+                write_location(renderer, state, conference.as_deref(), &area);
                 renderer.write_line("");
 
                 // Show message count
                 let total = list_screen.total_count();
-                let page = list_screen.current_page() + 1;
+                let shown_page = list_screen.current_page() + 1;
                 let max_page = list_screen.max_page() + 1;
 
                 renderer.write_line(&format!(
-                    "Total Messages: {}  |  Page {}/{}",
-                    total, page, max_page
+                    "Total Messages: {}  |  New: {}  |  Page {}/{}",
+                    total,
+                    total.saturating_sub(last_read),
+                    shown_page,
+                    max_page
                 ));
                 renderer.write_line("");
 
                 // Show messages
                 for msg in list_screen.messages() {
                     renderer.set_foreground(Color::BrightYellow);
-                    renderer.write_text(&format!("#{} ", msg.msg_num));
+                    let marker = if msg.msg_num > last_read { '*' } else { ' ' };
+                    renderer.write_text(&format!("{}#{} ", marker, msg.msg_num));
                     renderer.reset();
                     renderer.set_foreground(Color::BrightWhite);
                     renderer.write_text(&format!("From: {} ", msg.from));
@@ -68,6 +104,7 @@ pub async fn handle_messages(
                 renderer.write_line("Commands:");
                 renderer.write_line("  [R] Read message  [N] Next page  [P] Previous page");
                 renderer.write_line("  [W] Write new message  [Q] Return to main menu");
+                write_area_commands(renderer, state, user);
                 renderer.reset();
                 renderer.write_line("");
                 renderer.set_foreground(Color::BrightYellow);
@@ -75,7 +112,7 @@ pub async fn handle_messages(
                 renderer.reset();
 
                 connection
-                    .send_raw(renderer.take_output().as_bytes())
+                    .send_bytes(renderer.take_output().as_bytes())
                     .await?;
 
                 // Read command
@@ -89,91 +126,74 @@ pub async fn handle_messages(
                                 // Read message - prompt for message number
                                 renderer.write_line("\r\n");
                                 renderer.set_foreground(Color::BrightYellow);
-                                renderer.write_text("Enter message number to read: ");
+                                renderer.write_text(
+                                    "Enter message number to read, or N for the next new one: ",
+                                );
                                 renderer.reset();
                                 connection
-                                    .send_raw(renderer.take_output().as_bytes())
+                                    .send_bytes(renderer.take_output().as_bytes())
                                     .await?;
 
-                                // Read message number
-                                if let Ok(input) = connection.read_line().await
-                                    && let Ok(msg_num) = input.trim().parse::<u32>()
-                                {
-                                    // Load and display message
-                                    drop(message_base);
-                                    let message_base = state.message_base.read().await;
-
-                                    let mut read_screen = MessageReadScreen::default_config();
-                                    match read_screen.load_message(&*message_base, msg_num).await {
-                                        Ok(_) => {
-                                            renderer.clear_screen();
-                                            renderer.write_text(&read_screen.render());
-                                            renderer.write_line("");
-                                            renderer.write_line("");
-                                            renderer.set_foreground(Color::BrightYellow);
-                                            renderer.write_line(
-                                                "Commands: [P]revious  [N]ext  [R]eply  [Q]uit",
-                                            );
-                                            renderer.reset();
-                                            renderer.write_line("");
-                                            renderer.set_foreground(Color::BrightYellow);
-                                            renderer.write_text("Command: ");
-                                            renderer.reset();
-                                            connection
-                                                .send_raw(renderer.take_output().as_bytes())
-                                                .await?;
-
-                                            // Read command
-                                            if let Ok(ch) = connection.read_char().await {
-                                                let subcmd = ch.to_ascii_uppercase();
-                                                if subcmd == 'R' {
-                                                    // Reply to message
-                                                    if let Some(msg) = read_screen.message() {
-                                                        handle_reply(
-                                                            connection,
-                                                            state,
-                                                            _user,
-                                                            renderer,
-                                                            msg.header.msg_num,
-                                                        )
-                                                        .await?;
-                                                    }
-                                                }
-                                            }
-                                        }
-                                        Err(_) => {
-                                            renderer.write_line("\r\n");
-                                            renderer.set_foreground(Color::BrightRed);
-                                            renderer.write_line("Message not found.");
-                                            renderer.reset();
-                                            renderer.write_line("\r\n");
-                                            renderer.set_foreground(Color::BrightYellow);
-                                            renderer.write_line("Press any key to continue...");
-                                            renderer.reset();
-                                            connection
-                                                .send_raw(renderer.take_output().as_bytes())
-                                                .await?;
-                                            connection.read_char().await.ok();
-                                        }
+                                // Read message number, resuming after the last one read
+                                if let Ok(input) = connection.read_line().await {
+                                    let input = input.trim();
+                                    let msg_num =
+                                        if input.is_empty() || input.eq_ignore_ascii_case("n") {
+                                            Some(last_read + 1)
+                                        } else {
+                                            input.parse::<u32>().ok()
+                                        };
+                                    if let Some(msg_num) = msg_num {
+                                        drop(message_base);
+                                        read_messages(
+                                            connection, user, renderer, &area, &base, msg_num,
+                                        )
+                                        .await?;
                                     }
                                 }
                             }
                             'W' => {
                                 // Write new message
                                 drop(message_base);
-                                handle_new_message(connection, state, _user, renderer).await?;
+                                handle_new_message(connection, &area, &base, user, renderer)
+                                    .await?;
+                            }
+                            'F' if can_write_netmail(state, user) => {
+                                drop(message_base);
+                                handle_netmail(connection, user, state, renderer).await?;
+                            }
+                            'A' => {
+                                drop(message_base);
+                                if let Some(selected) = select_area(
+                                    connection,
+                                    renderer,
+                                    state,
+                                    conference.as_deref(),
+                                    level,
+                                )
+                                .await?
+                                {
+                                    area = selected;
+                                    page = 0;
+                                }
+                            }
+                            'J' if !registry.conferences(level).is_empty() => {
+                                drop(message_base);
+                                if let Some((joined, first)) =
+                                    select_conference(connection, renderer, state, level).await?
+                                {
+                                    conference = Some(joined);
+                                    area = first;
+                                    page = 0;
+                                }
                             }
                             'N' => {
                                 // Next page
-                                drop(message_base);
-                                let message_base = state.message_base.read().await;
-                                list_screen.next_page(&*message_base).await.ok();
+                                page = (page + 1).min(list_screen.max_page());
                             }
                             'P' => {
                                 // Previous page
-                                drop(message_base);
-                                let message_base = state.message_base.read().await;
-                                list_screen.prev_page(&*message_base).await.ok();
+                                page = page.saturating_sub(1);
                             }
                             'Q' => {
                                 // Return to main menu
@@ -190,7 +210,7 @@ pub async fn handle_messages(
                                 renderer.write_line("Press any key to continue...");
                                 renderer.reset();
                                 connection
-                                    .send_raw(renderer.take_output().as_bytes())
+                                    .send_bytes(renderer.take_output().as_bytes())
                                     .await?;
                                 connection.read_char().await.ok();
                             }
@@ -220,6 +240,7 @@ pub async fn handle_messages(
                     "╚══════════════════════════════════════════════════════════════════════════╝",
                 );
                 renderer.reset();
+                write_location(renderer, state, conference.as_deref(), &area);
                 renderer.write_line("");
 
                 if is_not_found {
@@ -242,6 +263,7 @@ pub async fn handle_messages(
                 renderer.set_foreground(Color::Yellow);
                 renderer.write_line("Commands:");
                 renderer.write_line("  [W] Write new message  [Q] Return to main menu");
+                write_area_commands(renderer, state, user);
                 renderer.reset();
                 renderer.write_line("");
                 renderer.set_foreground(Color::BrightYellow);
@@ -249,17 +271,40 @@ pub async fn handle_messages(
                 renderer.reset();
 
                 connection
-                    .send_raw(renderer.take_output().as_bytes())
+                    .send_bytes(renderer.take_output().as_bytes())
                     .await?;
 
                 // Read command
                 match connection.read_char().await {
                     Ok(ch) => {
                         let cmd = ch.to_ascii_uppercase();
+                        drop(message_base);
                         if cmd == 'W' {
                             // Write new message
-                            drop(message_base);
-                            handle_new_message(connection, state, _user, renderer).await?;
+                            handle_new_message(connection, &area, &base, user, renderer).await?;
+                        } else if cmd == 'F' && can_write_netmail(state, user) {
+                            handle_netmail(connection, user, state, renderer).await?;
+                        } else if cmd == 'A' {
+                            if let Some(selected) = select_area(
+                                connection,
+                                renderer,
+                                state,
+                                conference.as_deref(),
+                                level,
+                            )
+                            .await?
+                            {
+                                area = selected;
+                                page = 0;
+                            }
+                        } else if cmd == 'J' && !registry.conferences(level).is_empty() {
+                            if let Some((joined, first)) =
+                                select_conference(connection, renderer, state, level).await?
+                            {
+                                conference = Some(joined);
+                                area = first;
+                                page = 0;
+                            }
                         } else {
                             // Return to main menu
                             return Ok(());
@@ -274,13 +319,278 @@ pub async fn handle_messages(
     }
 }
 
+/// First area a caller may read in a conference
+fn first_area(state: &ServerState, conference: Option<&str>, level: u8) -> Option<MessageArea> {
+    state
+        .message_areas
+        .registry
+        .areas_in(conference, level)
+        .first()
+        .map(|area| (*area).clone())
+}
+
+/// Show the current conference and area under the menu title
+fn write_location(
+    renderer: &mut AnsiRenderer,
+    state: &ServerState,
+    conference: Option<&str>,
+    area: &MessageArea,
+) {
+    renderer.set_foreground(Color::Cyan);
+    if let Some(conference) =
+        conference.and_then(|tag| state.message_areas.registry.conference(tag))
+    {
+        renderer.write_text(&format!("Conference: {}  |  ", conference.name));
+    }
+    renderer.write_text(&format!("Area: {}", area.name));
+    if let Some(echo_tag) = &area.echo_tag {
+        renderer.write_text(&format!("  |  Echomail: {}", echo_tag));
+    }
+    renderer.write_line("");
+    renderer.reset();
+}
+
+/// List the area, conference and NetMail commands
+fn write_area_commands(renderer: &mut AnsiRenderer, state: &ServerState, user: &User) {
+    let level = user.security_level().value();
+    if state.message_areas.registry.conferences(level).is_empty() {
+        renderer.write_line("  [A] Change area");
+    } else {
+        renderer.write_line("  [A] Change area  [J] Join conference");
+    }
+    if can_write_netmail(state, user) {
+        renderer.write_line("  [F] Write FidoNet NetMail");
+    }
+}
+
+/// Let the caller pick an area in their conference
+async fn select_area(
+    connection: &mut dyn Connection,
+    renderer: &mut AnsiRenderer,
+    state: &ServerState,
+    conference: Option<&str>,
+    level: u8,
+) -> Result<Option<MessageArea>> {
+    let areas = state.message_areas.registry.areas_in(conference, level);
+
+    renderer.clear_screen();
+    renderer.set_foreground(Color::BrightCyan);
+    renderer.write_line("Message areas:");
+    renderer.reset();
+    renderer.write_line("");
+    for (number, area) in areas.iter().enumerate() {
+        renderer.set_foreground(Color::BrightYellow);
+        renderer.write_text(&format!("{:3}) ", number + 1));
+        renderer.set_foreground(Color::BrightWhite);
+        renderer.write_text(&area.name);
+        if let Some(echo_tag) = &area.echo_tag {
+            renderer.set_foreground(Color::Cyan);
+            renderer.write_text(&format!("  [{}]", echo_tag));
+        }
+        renderer.reset();
+        renderer.write_line("");
+    }
+
+    let choice = prompt_number(connection, renderer, "Area number (Enter to stay): ").await?;
+    Ok(choice
+        .and_then(|number| areas.get(number.wrapping_sub(1)))
+        .map(|area| (*area).clone()))
+}
+
+/// Let the caller join a conference
+///
+/// # Returns
+/// The conference tag and its first readable area
+async fn select_conference(
+    connection: &mut dyn Connection,
+    renderer: &mut AnsiRenderer,
+    state: &ServerState,
+    level: u8,
+) -> Result<Option<(String, MessageArea)>> {
+    let conferences = state.message_areas.registry.conferences(level);
+
+    renderer.clear_screen();
+    renderer.set_foreground(Color::BrightCyan);
+    renderer.write_line("Conferences:");
+    renderer.reset();
+    renderer.write_line("");
+    for (number, conference) in conferences.iter().enumerate() {
+        renderer.set_foreground(Color::BrightYellow);
+        renderer.write_text(&format!("{:3}) ", number + 1));
+        renderer.set_foreground(Color::BrightWhite);
+        renderer.write_line(&conference.name);
+        renderer.reset();
+    }
+
+    let choice = prompt_number(connection, renderer, "Conference number (Enter to stay): ").await?;
+    let Some(conference) = choice.and_then(|number| conferences.get(number.wrapping_sub(1))) else {
+        return Ok(None);
+    };
+    match first_area(state, Some(&conference.tag), level) {
+        Some(area) => Ok(Some((conference.tag.clone(), area))),
+        None => {
+            show_notice(
+                connection,
+                renderer,
+                Color::BrightRed,
+                "That conference has no areas you can read.",
+            )
+            .await?;
+            Ok(None)
+        }
+    }
+}
+
+/// Show a prompt and read a number
+async fn prompt_number(
+    connection: &mut dyn Connection,
+    renderer: &mut AnsiRenderer,
+    text: &str,
+) -> Result<Option<usize>> {
+    renderer.write_line("");
+    renderer.set_foreground(Color::BrightYellow);
+    renderer.write_text(text);
+    renderer.reset();
+    connection
+        .send_bytes(renderer.take_output().as_bytes())
+        .await?;
+    Ok(connection.read_line().await?.trim().parse().ok())
+}
+
+/// Show a message and wait for a key
+pub(super) async fn show_notice(
+    connection: &mut dyn Connection,
+    renderer: &mut AnsiRenderer,
+    color: Color,
+    text: &str,
+) -> Result<()> {
+    renderer.write_line("\r\n");
+    renderer.set_foreground(color);
+    renderer.write_line(text);
+    renderer.reset();
+    renderer.write_line("\r\n");
+    renderer.set_foreground(Color::BrightYellow);
+    renderer.write_line("Press any key to continue...");
+    renderer.reset();
+    connection
+        .send_bytes(renderer.take_output().as_bytes())
+        .await?;
+    connection.read_char().await.ok();
+    Ok(())
+}
+
+/// Read messages one at a time, starting at `msg_num`
+///
+/// Moves the caller's lastread pointer forward as they go and marks messages
+/// addressed to them as read.
+async fn read_messages(
+    connection: &mut dyn Connection,
+    user: &User,
+    renderer: &mut AnsiRenderer,
+    area: &MessageArea,
+    base: &SharedMessageBase,
+    mut msg_num: u32,
+) -> Result<()> {
+    loop {
+        let mut read_screen = MessageReadScreen::default_config();
+        let (loaded, total) = {
+            let message_base = base.read().await;
+            (
+                read_screen.load_message(&**message_base, msg_num).await,
+                message_base.message_count().await.unwrap_or(0),
+            )
+        };
+
+        if loaded.is_err() {
+            let notice = if msg_num > total {
+                "No new messages."
+            } else {
+                "Message not found."
+            };
+            return show_notice(connection, renderer, Color::BrightRed, notice).await;
+        }
+
+        if let Some(msg) = read_screen.message() {
+            mark_seen(area, base, user, &msg.header).await;
+        }
+
+        renderer.clear_screen();
+        renderer.write_text(&read_screen.render());
+        renderer.write_line("");
+        renderer.write_line("");
+        renderer.set_foreground(Color::BrightYellow);
+        renderer.write_line("Commands: [P]revious  [N]ext  [R]eply  [Q]uit");
+        renderer.reset();
+        renderer.write_line("");
+        renderer.set_foreground(Color::BrightYellow);
+        renderer.write_text("Command: ");
+        renderer.reset();
+        connection
+            .send_bytes(renderer.take_output().as_bytes())
+            .await?;
+
+        let Ok(ch) = connection.read_char().await else {
+            return Ok(());
+        };
+        match ch.to_ascii_uppercase() {
+            'N' if msg_num < total => msg_num += 1,
+            'P' if msg_num > 1 => msg_num -= 1,
+            'N' | 'P' => {}
+            'R' => handle_reply(connection, area, base, user, renderer, msg_num).await?,
+            _ => return Ok(()),
+        }
+    }
+}
+
+/// Record that a caller has seen a message
+///
+/// Hudson areas are read-only, so their lastread pointers are left alone.
+pub(super) async fn mark_seen(
+    area: &MessageArea,
+    base: &SharedMessageBase,
+    user: &User,
+    header: &MessageHeader,
+) {
+    let mut message_base = base.write().await;
+    let user_id = user.id().as_u32();
+    let last_read = message_base
+        .last_read(user.username(), user_id)
+        .await
+        .unwrap_or(0);
+    if area.format == MessageBaseFormat::Jam
+        && header.msg_num > last_read
+        && let Err(e) = message_base
+            .set_last_read(user.username(), user_id, header.msg_num)
+            .await
+    {
+        tracing::warn!(user = %user.username(), error = %e, "Failed to update lastread");
+    }
+    if !header.is_read
+        && header.to.eq_ignore_ascii_case(user.username())
+        && let Err(e) = message_base.mark_read(header.msg_num).await
+    {
+        tracing::warn!(msg_num = header.msg_num, error = %e, "Failed to mark message read");
+    }
+}
+
 /// Handle posting a new message
 async fn handle_new_message(
-    connection: &mut TelnetConnection,
-    state: &ServerState,
+    connection: &mut dyn Connection,
+    area: &MessageArea,
+    base: &SharedMessageBase,
     user: &User,
     renderer: &mut AnsiRenderer,
 ) -> Result<()> {
+    if !area.can_post(user.security_level().value()) {
+        return show_notice(
+            connection,
+            renderer,
+            Color::BrightRed,
+            "You cannot post in this area.",
+        )
+        .await;
+    }
+
     renderer.clear_screen();
     renderer.set_foreground(Color::BrightCyan);
     renderer
@@ -297,7 +607,7 @@ async fn handle_new_message(
     renderer.write_text("To: ");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
     let to = connection.read_line().await?.trim().to_string();
 
@@ -306,32 +616,20 @@ async fn handle_new_message(
     renderer.write_text("Subject: ");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
     let subject = connection.read_line().await?.trim().to_string();
 
     // Get message body
-    renderer.write_line("");
-    renderer.set_foreground(Color::BrightWhite);
-    renderer.write_line("Enter message body (blank line to end):");
-    renderer.reset();
-    connection
-        .send_raw(renderer.take_output().as_bytes())
-        .await?;
-
-    let mut body_lines = Vec::new();
-    while let Ok(line) = connection.read_line().await {
-        if line.trim().is_empty() {
-            break;
-        }
-        body_lines.push(line);
-    }
-    let body = body_lines.join("\n");
+    let title = format!("To: {}   Subject: {}", to, subject);
+    let Some(body) = edit_body(connection, user, renderer, &title, None).await? else {
+        return show_notice(connection, renderer, Color::BrightRed, "Message aborted.").await;
+    };
 
     // Post the message
     let message = NewMessage::new(user.username(), &to, &subject).with_body(&body);
 
-    let mut message_base = state.message_base.write().await;
+    let mut message_base = base.write().await;
     match message_base.post_message(message).await {
         Ok(msg_num) => {
             renderer.write_line("");
@@ -352,20 +650,31 @@ async fn handle_new_message(
     renderer.write_line("Press any key to continue...");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
     connection.read_char().await.ok();
     Ok(())
 }
 
 /// Handle replying to a message
-async fn handle_reply(
-    connection: &mut TelnetConnection,
-    state: &ServerState,
+pub(super) async fn handle_reply(
+    connection: &mut dyn Connection,
+    area: &MessageArea,
+    base: &SharedMessageBase,
     user: &User,
     renderer: &mut AnsiRenderer,
     original_msg_num: u32,
 ) -> Result<()> {
+    if !area.can_post(user.security_level().value()) {
+        return show_notice(
+            connection,
+            renderer,
+            Color::BrightRed,
+            "You cannot post in this area.",
+        )
+        .await;
+    }
+
     renderer.clear_screen();
     renderer.set_foreground(Color::BrightCyan);
     renderer
@@ -378,7 +687,7 @@ async fn handle_reply(
     renderer.write_line("");
 
     // Read original message
-    let message_base = state.message_base.read().await;
+    let message_base = base.read().await;
     let original = match message_base.read_message(original_msg_num).await {
         Ok(msg) => msg,
         Err(e) => {
@@ -390,7 +699,7 @@ async fn handle_reply(
             renderer.write_line("Press any key to continue...");
             renderer.reset();
             connection
-                .send_raw(renderer.take_output().as_bytes())
+                .send_bytes(renderer.take_output().as_bytes())
                 .await?;
             connection.read_char().await.ok();
             return Ok(());
@@ -408,30 +717,24 @@ async fn handle_reply(
     renderer.reset();
     renderer.write_line("");
 
-    // Get reply body
-    renderer.set_foreground(Color::BrightWhite);
-    renderer.write_line("Enter reply (blank line to end):");
-    renderer.reset();
-    connection
-        .send_raw(renderer.take_output().as_bytes())
-        .await?;
-
-    let mut body_lines = Vec::new();
-    while let Ok(line) = connection.read_line().await {
-        if line.trim().is_empty() {
-            break;
-        }
-        body_lines.push(line);
-    }
-    let reply_text = body_lines.join("\n");
+    // Get reply body; quoting is done in the editor
+    let subject = &original.header.subject;
+    let title = if subject.starts_with("Re: ") {
+        format!("To: {}   Subject: {}", original.header.from, subject)
+    } else {
+        format!("To: {}   Subject: Re: {}", original.header.from, subject)
+    };
+    let Some(reply_text) = edit_body(connection, user, renderer, &title, Some(&original)).await?
+    else {
+        return show_notice(connection, renderer, Color::BrightRed, "Reply aborted.").await;
+    };
 
-    // Build reply with quoting
     let reply_message = ReplyBuilder::new(original)
-        .quote_original(true)
+        .quote_original(false)
         .build(user.username(), &reply_text);
 
     // Post the reply
-    let mut message_base = state.message_base.write().await;
+    let mut message_base = base.write().await;
     match message_base.post_message(reply_message).await {
         Ok(msg_num) => {
             renderer.write_line("");
@@ -452,7 +755,7 @@ async fn handle_reply(
     renderer.write_line("Press any key to continue...");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
     connection.read_char().await.ok();
     Ok(())
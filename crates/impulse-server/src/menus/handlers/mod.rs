@@ -2,8 +2,11 @@
 
 pub mod admin;
 pub mod doors;
+pub mod editor;
 pub mod files;
 pub mod messages;
+pub mod netmail;
+pub mod newscan;
 pub mod stats;
 pub mod theme;
 pub mod user_profile;
@@ -13,6 +16,7 @@ pub use admin::handle_admin;
 pub use doors::handle_doors;
 pub use files::handle_files;
 pub use messages::handle_messages;
+pub use newscan::handle_new_scan;
 pub use stats::handle_system_stats;
 pub use theme::handle_theme_selection;
 pub use user_profile::handle_user_profile;
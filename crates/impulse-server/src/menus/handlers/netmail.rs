@@ -0,0 +1,404 @@
+//! NetMail writing handler
+
+use crate::menus::handlers::editor::edit_body;
+use crate::menus::handlers::files::download_path;
+use crate::state::{NetmailSettings, ServerState};
+use anyhow::Result;
+use impulse_file::FileAreaManager;
+use impulse_file::types::SearchCriteria;
+use impulse_message::addressing::FidoAddress;
+use impulse_message::formats::JamMessageBase;
+use impulse_message::ftn::{Flavour, NetMessage};
+use impulse_message::routing::{MessageRouter, RoutingDecision};
+use impulse_session::Connection;
+use impulse_terminal::{AnsiRenderer, Color};
+use impulse_types::user::User;
+use std::path::PathBuf;
+
+/// Whether a caller may write NetMail
+pub fn can_write_netmail(state: &ServerState, user: &User) -> bool {
+    state
+        .netmail
+        .as_ref()
+        .is_some_and(|netmail| user.security_level().value() >= netmail.min_security)
+}
+
+/// Handle writing a NetMail message
+pub async fn handle_netmail(
+    connection: &mut dyn Connection,
+    user: &User,
+    state: &ServerState,
+    renderer: &mut AnsiRenderer,
+) -> Result<()> {
+    let Some(netmail) = state
+        .netmail
+        .as_ref()
+        .filter(|_| can_write_netmail(state, user))
+    else {
+        return Ok(());
+    };
+    let privileged = user.security_level().value() >= netmail.crash_security;
+
+    renderer.clear_screen();
+    renderer.set_foreground(Color::BrightCyan);
+    renderer
+        .write_line("═══════════════════════════════════════════════════════════════════════════");
+    renderer
+        .write_line("                          WRITE NETMAIL                                    ");
+    renderer
+        .write_line("═══════════════════════════════════════════════════════════════════════════");
+    renderer.reset();
+    renderer.write_line("");
+
+    let to = prompt(connection, renderer, "To: ").await?;
+    if to.is_empty() {
+        return Ok(());
+    }
+
+    let address = prompt(connection, renderer, "Address (zone:net/node[.point]): ").await?;
+    let Ok(to_address) = address.parse::<FidoAddress>() else {
+        return finish(
+            connection,
+            renderer,
+            Color::BrightRed,
+            "Invalid FidoNet address.",
+        )
+        .await;
+    };
+
+    // Show the caller where the message is headed
+    let route = MessageRouter::new(netmail.ftn.router.clone()).route(to_address);
+    renderer.set_foreground(Color::Cyan);
+    renderer.write_line(&describe_route(&route));
+    renderer.reset();
+    if !route.is_routable() {
+        return finish(
+            connection,
+            renderer,
+            Color::BrightRed,
+            "That address cannot be reached from here.",
+        )
+        .await;
+    }
+
+    let subject = prompt(connection, renderer, "Subject: ").await?;
+
+    let choices = if privileged {
+        "Delivery: [N]ormal  [H]old for pickup  [C]rash: "
+    } else {
+        "Delivery: [N]ormal  [H]old for pickup: "
+    };
+    let flavour = match prompt(connection, renderer, choices)
+        .await?
+        .to_ascii_uppercase()
+        .as_str()
+    {
+        "H" => Flavour::Hold,
+        "C" if privileged => Flavour::Crash,
+        _ => Flavour::Normal,
+    };
+
+    let mut attachments = Vec::new();
+    if privileged {
+        let name = prompt(
+            connection,
+            renderer,
+            "Attach a file from the file areas (blank for none): ",
+        )
+        .await?;
+        if !name.is_empty() {
+            match find_attachment(state, user, &name).await? {
+                Some(path) => attachments.push(path),
+                None => {
+                    return finish(connection, renderer, Color::BrightRed, "File not found.").await;
+                }
+            }
+        }
+    }
+
+    let title = format!("NetMail to {} at {}   Subject: {}", to, to_address, subject);
+    let Some(body) = edit_body(connection, user, renderer, &title, None).await? else {
+        return finish(connection, renderer, Color::BrightRed, "Message aborted.").await;
+    };
+
+    let mut message = NetMessage::new(
+        user.username(),
+        netmail.ftn.address_for(to_address),
+        &to,
+        to_address,
+        &subject,
+    )
+    .with_body(body)
+    .with_flavour(flavour);
+    for path in attachments {
+        message = message.with_attachment(path);
+    }
+
+    match save_netmail(netmail, &message).await {
+        Ok(msg_num) => {
+            finish(
+                connection,
+                renderer,
+                Color::BrightGreen,
+                &format!(
+                    "NetMail #{} saved; it goes out with the next mail scan.",
+                    msg_num
+                ),
+            )
+            .await
+        }
+        Err(e) => {
+            finish(
+                connection,
+                renderer,
+                Color::BrightRed,
+                &format!("Error saving NetMail: {}", e),
+            )
+            .await
+        }
+    }
+}
+
+/// Store a message in the NetMail base
+async fn save_netmail(netmail: &NetmailSettings, message: &NetMessage) -> Result<u32> {
+    let Some(base_path) = &netmail.ftn.netmail else {
+        anyhow::bail!("no NetMail base is configured");
+    };
+    let base = JamMessageBase::new(base_path);
+    base.create_if_missing().await?;
+    Ok(base.add_message(&message.to_jam()).await?)
+}
+
+/// Find a file the caller could download by name
+///
+/// Only files in the areas the caller can see are found, so NetMail cannot
+/// send anything the caller could not download themselves.
+async fn find_attachment(state: &ServerState, user: &User, name: &str) -> Result<Option<PathBuf>> {
+    let (areas, files) = {
+        let file_manager = state.file_manager.read().await;
+        (
+            file_manager.list_areas(user.security_level()).await?,
+            file_manager
+                .search_files(&SearchCriteria::new().with_filename(name))
+                .await?,
+        )
+    };
+    match files.iter().find(|file| {
+        file.filename.eq_ignore_ascii_case(name)
+            && areas.iter().any(|area| area.area_id == file.area_id)
+    }) {
+        Some(file) => Ok(Some(download_path(state, file).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Describe how a message will be routed
+fn describe_route(route: &RoutingDecision) -> String {
+    match route {
+        RoutingDecision::Local => "Routing: delivered here".to_string(),
+        RoutingDecision::DirectRoute { destination } => {
+            format!("Routing: direct to {}", destination)
+        }
+        RoutingDecision::ViaHub { hub, .. } => format!("Routing: via hub {}", hub),
+        RoutingDecision::ViaGate { gate, .. } => format!("Routing: via gateway {}", gate),
+        RoutingDecision::Unroutable { reason } => format!("Routing: unroutable ({})", reason),
+    }
+}
+
+/// Show a prompt and read a trimmed line
+async fn prompt(
+    connection: &mut dyn Connection,
+    renderer: &mut AnsiRenderer,
+    text: &str,
+) -> Result<String> {
+    renderer.set_foreground(Color::BrightYellow);
+    renderer.write_text(text);
+    renderer.reset();
+    connection
+        .send_bytes(renderer.take_output().as_bytes())
+        .await?;
+    Ok(connection.read_line().await?.trim().to_string())
+}
+
+/// Show a closing message and wait for a key
+async fn finish(
+    connection: &mut dyn Connection,
+    renderer: &mut AnsiRenderer,
+    color: Color,
+    text: &str,
+) -> Result<()> {
+    renderer.write_line("");
+    renderer.set_foreground(color);
+    renderer.write_line(text);
+    renderer.reset();
+    renderer.write_line("\r\n");
+    renderer.set_foreground(Color::BrightYellow);
+    renderer.write_line("Press any key to continue...");
+    renderer.reset();
+    connection
+        .send_bytes(renderer.take_output().as_bytes())
+        .await?;
+    connection.read_char().await.ok();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::test_config;
+    use impulse_file::types::FileArea;
+    use impulse_message::formats::jam::{JamMessage, MessageAttributes};
+    use impulse_session::MemoryConnection;
+    use impulse_types::config::{FidonetConfig, FidonetLink};
+    use impulse_types::security::SecurityLevel;
+    use tempfile::TempDir;
+
+    /// A server at 2:5020/9999 with a hub at 2:5020/1 and two file areas:
+    /// "new files" open to everyone and "sysop" for level 200 and up
+    async fn test_state(dir: &TempDir) -> ServerState {
+        let mut config = test_config(dir.path());
+        config.fidonet = Some(FidonetConfig {
+            addresses: vec!["2:5020/9999".to_string()],
+            inbound: dir.path().join("inbound"),
+            outbound: dir.path().join("outbound"),
+            hub: Some("2:5020/1".to_string()),
+            links: vec![FidonetLink {
+                address: "2:5020/1".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        for (area, file) in [("area_1", "report 2026.zip"), ("area_2", "secret.zip")] {
+            let area_dir = config.paths.files_dir.join(area);
+            std::fs::create_dir_all(&area_dir).unwrap();
+            std::fs::write(area_dir.join(file), b"data").unwrap();
+        }
+
+        let state = ServerState::new(&config).await.unwrap();
+        state
+            .file_manager
+            .write()
+            .await
+            .add_area(
+                FileArea::new(2, "SysOp".to_string(), String::new())
+                    .with_path(config.paths.files_dir.join("area_2"))
+                    .with_security_level(SecurityLevel::new(200)),
+            )
+            .await
+            .unwrap();
+        state
+    }
+
+    fn caller(level: u8) -> User {
+        let mut user = User::new("Bob").unwrap();
+        user.set_security_level(SecurityLevel::new(level));
+        user.preferences.ansi_enabled = false;
+        user
+    }
+
+    async fn stored(state: &ServerState, msg_num: u32) -> Option<JamMessage> {
+        let netmail = state.netmail.as_ref().unwrap();
+        let base = JamMessageBase::new(netmail.ftn.netmail.as_ref().unwrap());
+        base.read_jam_message(msg_num).await.ok()
+    }
+
+    #[tokio::test]
+    async fn test_netmail_is_saved() {
+        let dir = TempDir::new().unwrap();
+        let state = test_state(&dir).await;
+        let user = caller(50);
+        let mut connection =
+            MemoryConnection::new("Carol\r2:5030/7\rHello there\rH\rFirst line\r\r\r");
+        let mut renderer = AnsiRenderer::new();
+
+        handle_netmail(&mut connection, &user, &state, &mut renderer)
+            .await
+            .unwrap();
+
+        let output = connection.output_text();
+        assert!(output.contains("Routing: via hub 2:5020/1"));
+        assert!(!output.contains("[C]rash") && !output.contains("Attach"));
+
+        let message = stored(&state, 1).await.unwrap();
+        assert_eq!(message.to, "Carol");
+        assert_eq!(message.subject, "Hello there");
+        assert_eq!(message.to_address.as_deref(), Some("2:5030/7"));
+        assert_eq!(message.body, "First line");
+        assert!(message.attributes().has(MessageAttributes::HOLD));
+    }
+
+    #[tokio::test]
+    async fn test_netmail_attachment() {
+        let dir = TempDir::new().unwrap();
+        let state = test_state(&dir).await;
+        let user = caller(100);
+        let mut connection = MemoryConnection::new(
+            "Carol\r2:5030/7\rThe report\rC\rreport 2026.zip\rSee attached\r\r\r",
+        );
+        let mut renderer = AnsiRenderer::new();
+
+        handle_netmail(&mut connection, &user, &state, &mut renderer)
+            .await
+            .unwrap();
+
+        let message = stored(&state, 1).await.unwrap();
+        assert_eq!(message.subject, "The report");
+        assert!(message.attributes().has(MessageAttributes::CRASH));
+        assert!(message.attributes().has(MessageAttributes::FILE_ATTACH));
+        let expected = state.paths.files_dir.join("area_1/report 2026.zip");
+        assert_eq!(message.attachments, [expected.to_string_lossy()]);
+    }
+
+    #[tokio::test]
+    async fn test_attachment_needs_download_access() {
+        let dir = TempDir::new().unwrap();
+        let state = test_state(&dir).await;
+
+        assert!(
+            find_attachment(&state, &caller(100), "secret.zip")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            find_attachment(&state, &caller(200), "secret.zip")
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        let user = caller(100);
+        let mut connection = MemoryConnection::new("Carol\r2:5030/7\rSecrets\rN\rsecret.zip\r\r");
+        let mut renderer = AnsiRenderer::new();
+        handle_netmail(&mut connection, &user, &state, &mut renderer)
+            .await
+            .unwrap();
+        assert!(connection.output_text().contains("File not found."));
+        assert!(stored(&state, 1).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_netmail_refused() {
+        let dir = TempDir::new().unwrap();
+        let state = test_state(&dir).await;
+
+        // Below the NetMail security level nothing is asked
+        let mut connection = MemoryConnection::new("Carol\r");
+        let mut renderer = AnsiRenderer::new();
+        handle_netmail(&mut connection, &caller(40), &state, &mut renderer)
+            .await
+            .unwrap();
+        assert!(connection.output().is_empty());
+
+        let mut connection = MemoryConnection::new("Carol\rnowhere\r\r");
+        handle_netmail(&mut connection, &caller(50), &state, &mut renderer)
+            .await
+            .unwrap();
+        assert!(
+            connection
+                .output_text()
+                .contains("Invalid FidoNet address.")
+        );
+        assert!(stored(&state, 1).await.is_none());
+    }
+}
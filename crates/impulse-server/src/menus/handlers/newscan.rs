@@ -0,0 +1,465 @@
+//! Global new-message scan
+//!
+//! Walks every message area the caller has flagged for scanning and reads
+//! what is new since their lastread pointer. The flags and the time each
+//! area was last scanned are kept in a `ZScanRec` per user, as ZSCAN.DAT
+//! did, under the board number each area is configured with. Areas are
+//! scanned in board order.
+
+use crate::menus::handlers::messages::{handle_reply, mark_seen, show_notice};
+use crate::state::{ServerState, SharedMessageBase};
+use anyhow::Result;
+use binrw::{BinRead, BinWrite};
+use chrono::{Datelike, Timelike, Utc};
+use impulse_message::screens::MessageReadScreen;
+use impulse_message::{MessageArea, MessageAreaRegistry};
+use impulse_session::Connection;
+use impulse_terminal::{AnsiRenderer, Color};
+use impulse_types::config::MessageBaseFormat;
+use impulse_types::pascal_aux::{MAX_BOARDS, PackedDateTime, ZScanRec};
+use impulse_types::user::User;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+/// An area taking part in the scan, with its new-message count
+struct ScanArea {
+    /// Board number in the newscan record
+    board: usize,
+    /// The area
+    area: MessageArea,
+    /// Its open base
+    base: SharedMessageBase,
+    /// The caller's lastread pointer
+    last_read: u32,
+    /// Highest message number
+    total: u32,
+}
+
+impl ScanArea {
+    /// Messages after the caller's lastread pointer
+    fn new_count(&self) -> u32 {
+        self.total.saturating_sub(self.last_read)
+    }
+}
+
+/// How reading an area ended
+enum AreaEnd {
+    /// Every new message was shown, or the caller moved on
+    Done,
+    /// The caller stopped the scan
+    Quit,
+}
+
+/// Handle the new-message scan
+pub async fn handle_new_scan(
+    connection: &mut dyn Connection,
+    user: &User,
+    state: &ServerState,
+    renderer: &mut AnsiRenderer,
+) -> Result<()> {
+    let path = zscan_path(state, user);
+    let mut zscan = load_zscan(&path).await?;
+
+    loop {
+        let areas = scan_areas(state, user).await;
+
+        renderer.clear_screen();
+        renderer.set_foreground(Color::BrightCyan);
+        renderer.write_line(
+            "╔══════════════════════════════════════════════════════════════════════════╗",
+        );
+        renderer.write_line(
+            "║                          NEW MESSAGE SCAN                                ║",
+        );
+        renderer.write_line(
+            "╚══════════════════════════════════════════════════════════════════════════╝",
+        );
+        renderer.reset();
+        renderer.write_line("");
+        renderer.set_foreground(Color::Yellow);
+        renderer.write_line(" Scan  Area                                      New");
+        renderer.reset();
+
+        let mut total_new = 0;
+        for scan_area in &areas {
+            let scanned = is_scanned(&zscan, scan_area.board);
+            if scanned {
+                total_new += scan_area.new_count();
+            }
+            renderer.set_foreground(if scanned {
+                Color::BrightWhite
+            } else {
+                Color::White
+            });
+            renderer.write_line(&format!(
+                " [{}]   {:40.40} {:5}",
+                if scanned { 'X' } else { ' ' },
+                scan_area.area.name,
+                scan_area.new_count()
+            ));
+            renderer.reset();
+        }
+
+        renderer.write_line("");
+        renderer.set_foreground(Color::BrightGreen);
+        renderer.write_line(&format!("New messages in scanned areas: {}", total_new));
+        renderer.reset();
+        renderer.write_line("");
+        renderer.set_foreground(Color::Yellow);
+        renderer.write_line("Commands:");
+        renderer.write_line("  [S] Start reading  [T] Toggle scanned areas  [Q] Quit");
+        renderer.reset();
+        renderer.write_line("");
+        renderer.set_foreground(Color::BrightYellow);
+        renderer.write_text("Command: ");
+        renderer.reset();
+        connection
+            .send_bytes(renderer.take_output().as_bytes())
+            .await?;
+
+        match read_key(connection).await {
+            Some('S') => {
+                read_new(connection, user, renderer, &areas, &mut zscan).await?;
+                save_zscan(&path, &zscan).await?;
+            }
+            Some('T') => {
+                toggle_areas(connection, renderer, &areas, &mut zscan).await?;
+                save_zscan(&path, &zscan).await?;
+            }
+            _ => return Ok(()),
+        }
+    }
+}
+
+/// Read the new messages of every scanned area in turn
+async fn read_new(
+    connection: &mut dyn Connection,
+    user: &User,
+    renderer: &mut AnsiRenderer,
+    areas: &[ScanArea],
+    zscan: &mut ZScanRec,
+) -> Result<()> {
+    for scan_area in areas {
+        if !is_scanned(zscan, scan_area.board) || scan_area.new_count() == 0 {
+            continue;
+        }
+        let end = read_area(connection, user, renderer, scan_area).await?;
+        zscan.set_msg_high_read(scan_area.board, packed_now());
+        if matches!(end, AreaEnd::Quit) {
+            return Ok(());
+        }
+    }
+
+    show_notice(
+        connection,
+        renderer,
+        Color::BrightGreen,
+        "New scan complete.",
+    )
+    .await
+}
+
+/// Read the new messages of one area
+async fn read_area(
+    connection: &mut dyn Connection,
+    user: &User,
+    renderer: &mut AnsiRenderer,
+    scan_area: &ScanArea,
+) -> Result<AreaEnd> {
+    let first = scan_area.last_read + 1;
+    for msg_num in first..=scan_area.total {
+        let mut read_screen = MessageReadScreen::default_config();
+        {
+            let message_base = scan_area.base.read().await;
+            if read_screen
+                .load_message(&**message_base, msg_num)
+                .await
+                .is_err()
+            {
+                continue;
+            }
+        }
+        if let Some(msg) = read_screen.message() {
+            mark_seen(&scan_area.area, &scan_area.base, user, &msg.header).await;
+        }
+
+        renderer.clear_screen();
+        renderer.set_foreground(Color::BrightCyan);
+        renderer.write_line(&format!(
+            "{}  ({} of {} new)",
+            scan_area.area.name,
+            msg_num - first + 1,
+            scan_area.new_count()
+        ));
+        renderer.reset();
+        renderer.write_text(&read_screen.render());
+        renderer.write_line("");
+        renderer.write_line("");
+        renderer.set_foreground(Color::BrightYellow);
+        renderer.write_line(
+            "Commands: [N]ext  [R]eply  [S]kip to next area  [M]ark area read  [Q]uit scan",
+        );
+        renderer.reset();
+        renderer.set_foreground(Color::BrightYellow);
+        renderer.write_text("Command: ");
+        renderer.reset();
+        connection
+            .send_bytes(renderer.take_output().as_bytes())
+            .await?;
+
+        match read_key(connection).await {
+            Some('R') => {
+                handle_reply(
+                    connection,
+                    &scan_area.area,
+                    &scan_area.base,
+                    user,
+                    renderer,
+                    msg_num,
+                )
+                .await?
+            }
+            Some('S') => return Ok(AreaEnd::Done),
+            Some('M') => {
+                mark_area_read(user, scan_area).await;
+                return Ok(AreaEnd::Done);
+            }
+            Some('Q') | None => return Ok(AreaEnd::Quit),
+            _ => {}
+        }
+    }
+    Ok(AreaEnd::Done)
+}
+
+/// Move the caller's lastread pointer to the end of an area
+async fn mark_area_read(user: &User, scan_area: &ScanArea) {
+    if scan_area.area.format != MessageBaseFormat::Jam {
+        return;
+    }
+    let mut message_base = scan_area.base.write().await;
+    if let Err(e) = message_base
+        .set_last_read(user.username(), user.id().as_u32(), scan_area.total)
+        .await
+    {
+        tracing::warn!(area = %scan_area.area.tag, error = %e, "Failed to update lastread");
+    }
+}
+
+/// Let the caller choose which areas take part in the scan
+async fn toggle_areas(
+    connection: &mut dyn Connection,
+    renderer: &mut AnsiRenderer,
+    areas: &[ScanArea],
+    zscan: &mut ZScanRec,
+) -> Result<()> {
+    loop {
+        renderer.clear_screen();
+        renderer.set_foreground(Color::BrightCyan);
+        renderer.write_line("Areas in the new scan:");
+        renderer.reset();
+        renderer.write_line("");
+        for (number, scan_area) in areas.iter().enumerate() {
+            renderer.set_foreground(Color::BrightYellow);
+            renderer.write_text(&format!("{:3}) ", number + 1));
+            renderer.set_foreground(Color::BrightWhite);
+            renderer.write_line(&format!(
+                "[{}] {}",
+                if is_scanned(zscan, scan_area.board) {
+                    'X'
+                } else {
+                    ' '
+                },
+                scan_area.area.name
+            ));
+            renderer.reset();
+        }
+        renderer.write_line("");
+        renderer.set_foreground(Color::BrightYellow);
+        renderer.write_text("Area number to toggle, or Q when done: ");
+        renderer.reset();
+        connection
+            .send_bytes(renderer.take_output().as_bytes())
+            .await?;
+
+        let Some(scan_area) = connection
+            .read_line()
+            .await?
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|number| areas.get(number.wrapping_sub(1)))
+        else {
+            return Ok(());
+        };
+
+        if !(1..=MAX_BOARDS).contains(&scan_area.board) {
+            show_notice(
+                connection,
+                renderer,
+                Color::BrightRed,
+                "That area is always scanned.",
+            )
+            .await?;
+        } else if zscan.is_msg_board_enabled(scan_area.board) {
+            zscan.disable_msg_board(scan_area.board);
+        } else {
+            zscan.enable_msg_board(scan_area.board);
+        }
+    }
+}
+
+/// The areas a caller can read, with their new-message counts
+async fn scan_areas(state: &ServerState, user: &User) -> Vec<ScanArea> {
+    let level = user.security_level().value();
+
+    let mut areas = Vec::new();
+    for area in scan_order(&state.message_areas.registry, level) {
+        let Some(base) = state.message_areas.base(&area.tag) else {
+            continue;
+        };
+        let (last_read, total) = {
+            let message_base = base.read().await;
+            let last_read = message_base
+                .last_read(user.username(), user.id().as_u32())
+                .await
+                .unwrap_or(0);
+            (last_read, message_base.message_count().await.unwrap_or(0))
+        };
+        areas.push(ScanArea {
+            board: usize::from(area.board),
+            area: area.clone(),
+            base,
+            last_read,
+            total,
+        });
+    }
+    areas
+}
+
+/// The areas a caller at `level` can read, in board order
+fn scan_order(registry: &MessageAreaRegistry, level: u8) -> Vec<&MessageArea> {
+    let mut areas = registry.areas_in(None, level);
+    areas.sort_by_key(|area| area.board);
+    areas
+}
+
+/// Whether a board takes part in the scan
+///
+/// Areas without a board in the record cannot be switched off.
+fn is_scanned(zscan: &ZScanRec, board: usize) -> bool {
+    !(1..=MAX_BOARDS).contains(&board) || zscan.is_msg_board_enabled(board)
+}
+
+/// Current time as a Pascal packed date
+fn packed_now() -> PackedDateTime {
+    let now = Utc::now();
+    PackedDateTime::new(
+        now.year() as u16,
+        now.month() as u8,
+        now.day() as u8,
+        now.hour() as u8,
+        now.minute() as u8,
+        now.second() as u8,
+    )
+}
+
+/// Path of a caller's newscan record
+fn zscan_path(state: &ServerState, user: &User) -> PathBuf {
+    state
+        .paths
+        .data_dir
+        .join("zscan")
+        .join(format!("{}.dat", user.id().as_uuid()))
+}
+
+/// Load a caller's newscan record
+///
+/// Callers without one scan every area.
+async fn load_zscan(path: &Path) -> Result<ZScanRec> {
+    match tokio::fs::read(path).await {
+        Ok(data) => Ok(ZScanRec::read_le(&mut Cursor::new(data))?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let mut zscan = ZScanRec::default();
+            for board in 1..=MAX_BOARDS {
+                zscan.enable_msg_board(board);
+            }
+            Ok(zscan)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Save a caller's newscan record
+async fn save_zscan(path: &Path, zscan: &ZScanRec) -> Result<()> {
+    let mut data = Cursor::new(Vec::new());
+    zscan.write_le(&mut data)?;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, data.into_inner()).await?;
+    Ok(())
+}
+
+/// Read a command key, skipping the line feed of a CR LF
+async fn read_key(connection: &mut dyn Connection) -> Option<char> {
+    loop {
+        match connection.read_char().await {
+            Ok('\n') => continue,
+            Ok(ch) => return Some(ch.to_ascii_uppercase()),
+            Err(_) => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scan_order_follows_board_numbers() {
+        let registry = MessageAreaRegistry::new()
+            .with_area(MessageArea::new("fido", "Fido", "/msg/fido").with_board(7))
+            .with_area(MessageArea::new("general", "General", "/msg/general").with_board(1))
+            .with_area(
+                MessageArea::new("sysop", "SysOp", "/msg/sysop")
+                    .with_board(2)
+                    .with_security(200, 200),
+            )
+            .with_area(MessageArea::new("local", "Local", "/msg/local").with_board(3));
+
+        let tags = |level| {
+            scan_order(&registry, level)
+                .into_iter()
+                .map(|area| area.tag.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(tags(10), ["general", "local", "fido"]);
+        assert_eq!(tags(255), ["general", "sysop", "local", "fido"]);
+    }
+
+    #[tokio::test]
+    async fn test_zscan_flags_persist() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("zscan/caller.dat");
+
+        // Callers without a record scan everything
+        let mut zscan = load_zscan(&path).await.unwrap();
+        assert!(is_scanned(&zscan, 1) && is_scanned(&zscan, MAX_BOARDS));
+
+        zscan.disable_msg_board(7);
+        zscan.set_msg_high_read(3, PackedDateTime::new(2026, 10, 18, 12, 30, 0));
+        save_zscan(&path, &zscan).await.unwrap();
+
+        let loaded = load_zscan(&path).await.unwrap();
+        assert!(!is_scanned(&loaded, 7));
+        assert!(is_scanned(&loaded, 1) && is_scanned(&loaded, 8));
+        assert_eq!(
+            loaded.get_msg_high_read(3),
+            Some(&PackedDateTime::new(2026, 10, 18, 12, 30, 0))
+        );
+
+        // Areas without a board are always scanned
+        assert!(is_scanned(&loaded, 0));
+    }
+}
@@ -1,13 +1,12 @@
 //! System statistics handler
 
 use anyhow::Result;
-use impulse_session::SessionManager;
-use impulse_telnet::TelnetConnection;
+use impulse_session::{Connection, SessionManager};
 use impulse_terminal::{AnsiRenderer, Color};
 
 /// Handle system statistics
 pub async fn handle_system_stats(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     session_manager: &SessionManager,
     renderer: &mut AnsiRenderer,
 ) -> Result<()> {
@@ -73,7 +72,7 @@ pub async fn handle_system_stats(
     renderer.write_line("Press any key to continue...");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
     connection.read_char().await.ok();
     Ok(())
@@ -2,14 +2,14 @@
 
 use crate::state::ServerState;
 use anyhow::Result;
-use impulse_telnet::TelnetConnection;
+use impulse_session::Connection;
 use impulse_terminal::theme::ThemePreview;
 use impulse_terminal::{AnsiRenderer, Color};
 use impulse_types::user::User;
 
 /// Handle theme selection
 pub async fn handle_theme_selection(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     _user: &User,
     state: &ServerState,
     renderer: &mut AnsiRenderer,
@@ -75,7 +75,7 @@ pub async fn handle_theme_selection(
             ));
             renderer.reset();
             connection
-                .send_raw(renderer.take_output().as_bytes())
+                .send_bytes(renderer.take_output().as_bytes())
                 .await?;
 
             // Read selection
@@ -101,7 +101,7 @@ pub async fn handle_theme_selection(
                                 renderer.write_line("Press any key to continue...");
                                 renderer.reset();
                                 connection
-                                    .send_raw(renderer.take_output().as_bytes())
+                                    .send_bytes(renderer.take_output().as_bytes())
                                     .await?;
                                 connection.read_char().await.ok();
                             }
@@ -116,7 +116,7 @@ pub async fn handle_theme_selection(
                                 renderer.write_line("Press any key to continue...");
                                 renderer.reset();
                                 connection
-                                    .send_raw(renderer.take_output().as_bytes())
+                                    .send_bytes(renderer.take_output().as_bytes())
                                     .await?;
                                 connection.read_char().await.ok();
                             }
@@ -142,7 +142,7 @@ pub async fn handle_theme_selection(
                                 renderer.write_line("Press any key to continue...");
                                 renderer.reset();
                                 connection
-                                    .send_raw(renderer.take_output().as_bytes())
+                                    .send_bytes(renderer.take_output().as_bytes())
                                     .await?;
                                 connection.read_char().await.ok();
                             }
@@ -157,7 +157,7 @@ pub async fn handle_theme_selection(
                                 renderer.write_line("Press any key to continue...");
                                 renderer.reset();
                                 connection
-                                    .send_raw(renderer.take_output().as_bytes())
+                                    .send_bytes(renderer.take_output().as_bytes())
                                     .await?;
                                 connection.read_char().await.ok();
                             }
@@ -173,7 +173,7 @@ pub async fn handle_theme_selection(
             renderer.write_line("Press any key to continue...");
             renderer.reset();
             connection
-                .send_raw(renderer.take_output().as_bytes())
+                .send_bytes(renderer.take_output().as_bytes())
                 .await?;
             connection.read_char().await.ok();
             return Ok(());
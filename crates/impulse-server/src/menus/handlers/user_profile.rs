@@ -2,13 +2,13 @@
 
 use crate::state::ServerState;
 use anyhow::Result;
-use impulse_telnet::TelnetConnection;
+use impulse_session::Connection;
 use impulse_terminal::{AnsiRenderer, Color};
 use impulse_types::user::User;
 
 /// Handle user profile
 pub async fn handle_user_profile(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     user: &User,
     state: &ServerState,
     renderer: &mut AnsiRenderer,
@@ -84,7 +84,7 @@ pub async fn handle_user_profile(
         renderer.reset();
 
         connection
-            .send_raw(renderer.take_output().as_bytes())
+            .send_bytes(renderer.take_output().as_bytes())
             .await?;
 
         match connection.read_char().await {
@@ -122,7 +122,7 @@ pub async fn handle_user_profile(
 
 /// Handle password change
 async fn handle_password_change(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     _user: &User,
     _state: &ServerState,
     renderer: &mut AnsiRenderer,
@@ -138,7 +138,7 @@ async fn handle_password_change(
     renderer.write_text("Current password: ");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
 
     let current_password = connection.read_line().await?.trim().to_string();
@@ -156,7 +156,7 @@ async fn handle_password_change(
     renderer.write_text("New password: ");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
 
     let new_password = connection.read_line().await?.trim().to_string();
@@ -174,7 +174,7 @@ async fn handle_password_change(
     renderer.write_text("Confirm new password: ");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
 
     let confirm_password = connection.read_line().await?.trim().to_string();
@@ -228,7 +228,7 @@ async fn handle_password_change(
 
 /// Handle email change
 async fn handle_email_change(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     user: &User,
     _state: &ServerState,
     renderer: &mut AnsiRenderer,
@@ -250,7 +250,7 @@ async fn handle_email_change(
     renderer.write_text("New email address: ");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
 
     let new_email = connection.read_line().await?.trim().to_string();
@@ -278,7 +278,7 @@ async fn handle_email_change(
 
 /// Handle real name change
 async fn handle_real_name_change(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     user: &User,
     _state: &ServerState,
     renderer: &mut AnsiRenderer,
@@ -300,7 +300,7 @@ async fn handle_real_name_change(
     renderer.write_text("New real name: ");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
 
     let new_name = connection.read_line().await?.trim().to_string();
@@ -323,7 +323,7 @@ async fn handle_real_name_change(
 
 /// Handle theme settings
 async fn handle_theme_settings(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     renderer: &mut AnsiRenderer,
 ) -> Result<()> {
     renderer.clear_screen();
@@ -366,7 +366,7 @@ async fn handle_theme_settings(
     renderer.reset();
 
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
 
     if let Ok(ch) = connection.read_char().await {
@@ -393,7 +393,7 @@ async fn handle_theme_settings(
 
 /// Handle transfer protocol settings
 async fn handle_protocol_settings(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     renderer: &mut AnsiRenderer,
 ) -> Result<()> {
     renderer.clear_screen();
@@ -441,7 +441,7 @@ async fn handle_protocol_settings(
     renderer.reset();
 
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
 
     if let Ok(ch) = connection.read_char().await {
@@ -468,7 +468,7 @@ async fn handle_protocol_settings(
 
 /// Handle privacy settings
 async fn handle_privacy_settings(
-    connection: &mut TelnetConnection,
+    connection: &mut dyn Connection,
     renderer: &mut AnsiRenderer,
 ) -> Result<()> {
     renderer.clear_screen();
@@ -500,7 +500,7 @@ async fn handle_privacy_settings(
     renderer.reset();
 
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
 
     if let Ok(ch) = connection.read_char().await {
@@ -526,16 +526,13 @@ async fn handle_privacy_settings(
 }
 
 /// Helper to wait for key press
-async fn wait_for_key(
-    connection: &mut TelnetConnection,
-    renderer: &mut AnsiRenderer,
-) -> Result<()> {
+async fn wait_for_key(connection: &mut dyn Connection, renderer: &mut AnsiRenderer) -> Result<()> {
     renderer.write_line("\r\n");
     renderer.set_foreground(Color::BrightYellow);
     renderer.write_line("Press any key to continue...");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
     connection.read_char().await.ok();
     Ok(())
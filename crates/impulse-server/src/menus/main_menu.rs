@@ -1,187 +1,249 @@
-//! Main menu for authenticated users
+//! Main menu loop for authenticated users
+//!
+//! Menus are defined in TOML files in the configured menu directory and
+//! rendered through `impulse_menu::MenuRenderer`, so the options a caller sees
+//! are filtered by their security level. Selected options are dispatched
+//! through a `CommandRouter` holding every server feature.
 
-use crate::menus::handlers;
-use crate::state::ServerState;
+use crate::menus::commands::{MenuSession, build_router};
 use anyhow::Result;
-use impulse_auth::SessionToken;
-use impulse_session::SessionManager;
-use impulse_telnet::TelnetConnection;
+use impulse_menu::{
+    CommandContext, CommandError, CommandResult, MenuDefinition, MenuMode, MenuRenderer,
+};
+use impulse_session::Connection;
 use impulse_terminal::{AnsiRenderer, Color};
-use impulse_types::user::User;
+use std::sync::Arc;
 use tracing::{info, warn};
 
-/// Display and handle the main menu
-pub async fn display_main_menu(
-    connection: &mut TelnetConnection,
-    user: &User,
-    _token: &SessionToken,
-    state: &ServerState,
-    session_manager: &SessionManager,
-) -> Result<bool> {
+/// Display menus and dispatch commands until the caller logs off
+pub async fn display_main_menu(session: Arc<MenuSession>) -> Result<()> {
+    let user = &session.user;
+    let security = user.security_level().value();
+    let router = build_router(&session);
+    let mut menus = session.state.menus.subscribe();
+    let mut menu_state = menus.borrow_and_update().clone();
     let mut renderer = AnsiRenderer::new();
 
     loop {
-        // Clear and render menu
-        renderer.clear_screen();
-        render_main_menu(&mut renderer, user);
-        connection
-            .send_raw(renderer.take_output().as_bytes())
+        session
+            .session_manager
+            .update_activity(session.session_id)
+            .await
+            .ok();
+
+        // Pick up reloaded menu definitions without losing the caller's place
+        if menus.has_changed().unwrap_or(false) {
+            menu_state.replace_menus(&menus.borrow_and_update());
+        }
+
+        let Some(menu) = menu_state.current().cloned() else {
+            anyhow::bail!("Menu '{}' is not defined", menu_state.current_name());
+        };
+
+        // Render the current menu and read the caller's selection
+        let input = {
+            let mut connection = session.connection.lock().await;
+            let (columns, _) = connection.terminal_size();
+            let menu_renderer = MenuRenderer::with_width(usize::from(columns));
+
+            renderer.clear_screen();
+            let time_left = session.time_left().await;
+            render_menu(&mut renderer, &menu_renderer, &menu, &session, time_left);
+            connection
+                .send_bytes(renderer.take_output().as_bytes())
+                .await?;
+
+            let input = match menu.menu.mode {
+                MenuMode::Hotkey => connection.read_char().await.map(String::from),
+                MenuMode::Fullmenu => connection.read_line().await,
+            };
+            match input {
+                Ok(input) => input.trim().to_string(),
+                Err(e) => {
+                    warn!("Error reading command: {}", e);
+                    return Ok(());
+                }
+            }
+        };
+
+        renderer.clear();
+        let Some(command) = find_command(&menu, &input, security) else {
+            show_message(
+                &session,
+                &mut renderer,
+                Color::BrightRed,
+                "Unknown command. Press ? for help.",
+            )
             .await?;
+            continue;
+        };
+
+        let mut ctx = CommandContext::with_user(
+            user.id().as_uuid().to_string(),
+            security,
+            menu_state.current_name().to_string(),
+        );
+        ctx.menu_stack = menu_state
+            .breadcrumbs()
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        ctx.menu_stack.pop();
 
-        // Read command
-        match connection.read_char().await {
-            Ok(ch) => {
-                let cmd = ch.to_ascii_uppercase();
-                renderer.clear();
-
-                match cmd {
-                    'M' => {
-                        // Message areas
-                        handlers::handle_messages(connection, user, state, &mut renderer).await?;
-                    }
-                    'F' => {
-                        // File areas
-                        handlers::handle_files(connection, user, state, &mut renderer).await?;
-                    }
-                    'D' => {
-                        // Door games
-                        handlers::handle_doors(connection, user, state, &mut renderer).await?;
-                    }
-                    'U' => {
-                        // User profile
-                        handlers::handle_user_profile(connection, user, state, &mut renderer)
-                            .await?;
-                    }
-                    'W' => {
-                        // Who's online
-                        handlers::handle_whos_online(connection, session_manager, &mut renderer)
-                            .await?;
-                    }
-                    'T' => {
-                        // Theme selection
-                        handlers::handle_theme_selection(connection, user, state, &mut renderer)
-                            .await?;
-                    }
-                    'A' => {
-                        // Administration (SysOp only)
-                        if user.security_level().value() >= 200 {
-                            handlers::handle_admin(connection, user, state, &mut renderer).await?;
-                        } else {
-                            renderer.write_line("\r\n");
-                            renderer.set_foreground(Color::BrightRed);
-                            renderer.write_line("Access denied. Insufficient security level.");
-                            renderer.reset();
-                            renderer.write_line("\r\n");
-                            renderer.set_foreground(Color::BrightYellow);
-                            renderer.write_line("Press any key to continue...");
-                            renderer.reset();
-                            connection
-                                .send_raw(renderer.take_output().as_bytes())
-                                .await?;
-                            connection.read_char().await.ok();
-                        }
-                    }
-                    'S' => {
-                        // System statistics
-                        handlers::handle_system_stats(connection, session_manager, &mut renderer)
-                            .await?;
-                    }
-                    'G' | 'Q' => {
-                        // Logout
-                        renderer.write_line("\r\n");
-                        renderer.set_foreground(Color::BrightYellow);
-                        renderer.write_line("Thank you for visiting Impulse BBS!");
-                        renderer.write_line("Come back soon!");
-                        renderer.reset();
-                        renderer.write_line("\r\n");
-                        connection
-                            .send_raw(renderer.take_output().as_bytes())
-                            .await?;
-
-                        info!(username = %user.username(), "User logged out");
-                        return Ok(false); // Signal logout
-                    }
-                    '?' | 'H' => {
-                        // Help
-                        handle_help(connection, &mut renderer).await?;
-                    }
-                    _ => {
-                        // Unknown command
-                        renderer.write_line("\r\n");
-                        renderer.set_foreground(Color::BrightRed);
-                        renderer.write_line("Unknown command. Press ? for help.");
-                        renderer.reset();
-                        renderer.write_line("\r\n");
-                        renderer.set_foreground(Color::BrightYellow);
-                        renderer.write_line("Press any key to continue...");
-                        renderer.reset();
-                        connection
-                            .send_raw(renderer.take_output().as_bytes())
-                            .await?;
-                        connection.read_char().await.ok();
-                    }
+        // Options naming another menu navigate to it unless a command claims the name
+        let result = if !router.has_handler(&command) && menu_state.has_menu(&command) {
+            Ok(CommandResult::ChangeMenu(command.clone()))
+        } else {
+            router.route(&command, &mut ctx).await
+        };
+
+        match result {
+            Ok(CommandResult::Continue) => {}
+            Ok(CommandResult::ChangeMenu(name)) => {
+                if let Err(e) = menu_state.navigate_to(&name) {
+                    warn!(menu = %name, error = %e, "Menu navigation failed");
+                    show_message(&session, &mut renderer, Color::BrightRed, &e.to_string()).await?;
                 }
             }
+            Ok(CommandResult::Back) => {
+                // Already at the top level when there is nothing to go back to
+                menu_state.go_back().ok();
+            }
+            Ok(CommandResult::MainMenu) => menu_state.go_main(),
+            Ok(CommandResult::Disconnect) => {
+                renderer.write_line("\r\n");
+                renderer.set_foreground(Color::BrightYellow);
+                renderer.write_line("Thank you for visiting Impulse BBS!");
+                renderer.write_line("Come back soon!");
+                renderer.reset();
+                renderer.write_line("\r\n");
+                session
+                    .connection
+                    .lock()
+                    .await
+                    .send_bytes(renderer.take_output().as_bytes())
+                    .await?;
+
+                info!(username = %user.username(), "User logged out");
+                return Ok(());
+            }
+            Ok(CommandResult::Message(text)) => {
+                show_message(&session, &mut renderer, Color::BrightWhite, &text).await?;
+            }
+            Err(CommandError::InsufficientPrivileges { .. }) => {
+                show_message(
+                    &session,
+                    &mut renderer,
+                    Color::BrightRed,
+                    "Access denied. Insufficient security level.",
+                )
+                .await?;
+            }
+            Err(CommandError::ExecutionFailed { source }) => return Err(source),
             Err(e) => {
-                warn!("Error reading command: {}", e);
-                return Ok(false);
+                warn!(command = %command, error = %e, "Menu command failed");
+                show_message(
+                    &session,
+                    &mut renderer,
+                    Color::BrightRed,
+                    "That option is not available yet.",
+                )
+                .await?;
             }
         }
     }
 }
 
-/// Render the main menu
-fn render_main_menu(renderer: &mut AnsiRenderer, user: &User) {
-    renderer.clear_screen();
+/// Find the command bound to `input` among the options visible to the caller
+///
+/// Hotkey menus match on the option key; full menus also accept the command
+/// name typed out.
+fn find_command(menu: &MenuDefinition, input: &str, security: u8) -> Option<String> {
+    if input.is_empty() {
+        return None;
+    }
 
-    renderer.set_foreground(Color::BrightCyan);
-    renderer.write_line("╔══════════════════════════════════════════════════╗");
-    renderer.write_line("║         IMPULSE BBS - MAIN MENU                  ║");
-    renderer.write_line("╠══════════════════════════════════════════════════╣");
-    renderer.reset();
+    MenuRenderer::new()
+        .filter_options(&menu.option, security)
+        .into_iter()
+        .find(|opt| {
+            opt.key.eq_ignore_ascii_case(input)
+                || (menu.menu.mode == MenuMode::Fullmenu && opt.command.eq_ignore_ascii_case(input))
+        })
+        .map(|opt| opt.command.clone())
+}
 
-    renderer.set_foreground(Color::BrightGreen);
-    renderer.write_line("║  [M] Message Areas                               ║");
-    renderer.write_line("║  [F] File Areas                                  ║");
-    renderer.write_line("║  [D] Door Games                                  ║");
-    renderer.write_line("║  [U] User Profile & Settings                     ║");
-    renderer.write_line("║  [W] Who's Online                                ║");
-    renderer.write_line("║  [T] Change Theme                                ║");
-    renderer.write_line("║  [S] System Statistics                           ║");
-    renderer.reset();
+/// Render a menu with the caller's status line and prompt
+fn render_menu(
+    renderer: &mut AnsiRenderer,
+    menu_renderer: &MenuRenderer,
+    menu: &MenuDefinition,
+    session: &MenuSession,
+    time_left: Option<u32>,
+) {
+    let user = &session.user;
+    let rendered = menu_renderer.render(menu, user.security_level().value());
 
-    // Admin option (SysOp only)
-    if user.security_level().value() >= 200 {
-        renderer.set_foreground(Color::BrightMagenta);
-        renderer.write_line("║  [A] Administration                [SYSOP]       ║");
-        renderer.reset();
+    for line in rendered.content.lines() {
+        if line.starts_with('(') || line.contains(" - ") {
+            renderer.set_foreground(Color::BrightGreen);
+        } else {
+            renderer.set_foreground(Color::BrightCyan);
+        }
+        renderer.write_line(line);
     }
-
-    renderer.set_foreground(Color::Yellow);
-    renderer.write_line("║  [G] Goodbye / Logout                            ║");
-    renderer.write_line("║  [?] Help                                        ║");
-    renderer.reset();
-
-    renderer.set_foreground(Color::BrightCyan);
-    renderer.write_line("╚══════════════════════════════════════════════════╝");
     renderer.reset();
 
     renderer.write_line("");
     renderer.set_foreground(Color::BrightWhite);
+    let time_left = match time_left {
+        Some(seconds) => format!("{} min", seconds / 60),
+        None => "Unlimited".to_string(),
+    };
     renderer.write_line(&format!(
-        "User: {}  |  Security: {}  |  Time Left: 58 min",
+        "User: {}  |  Security: {}  |  Time Left: {}",
         user.username(),
-        user.security_level().value()
+        user.security_level().value(),
+        time_left
     ));
     renderer.reset();
     renderer.write_line("");
     renderer.set_foreground(Color::BrightYellow);
-    renderer.write_text("Command: ");
+    renderer.write_text(&rendered.prompt);
     renderer.reset();
 }
 
+/// Show a one-line message and wait for a keypress
+async fn show_message(
+    session: &MenuSession,
+    renderer: &mut AnsiRenderer,
+    color: Color,
+    text: &str,
+) -> Result<()> {
+    renderer.write_line("\r\n");
+    renderer.set_foreground(color);
+    for line in text.lines() {
+        renderer.write_line(line);
+    }
+    renderer.reset();
+    renderer.write_line("\r\n");
+    renderer.set_foreground(Color::BrightYellow);
+    renderer.write_line("Press any key to continue...");
+    renderer.reset();
+
+    let mut connection = session.connection.lock().await;
+    connection
+        .send_bytes(renderer.take_output().as_bytes())
+        .await?;
+    connection.read_char().await.ok();
+    Ok(())
+}
+
 /// Handle help screen
-async fn handle_help(connection: &mut TelnetConnection, renderer: &mut AnsiRenderer) -> Result<()> {
+pub async fn handle_help(
+    connection: &mut dyn Connection,
+    renderer: &mut AnsiRenderer,
+) -> Result<()> {
     renderer.write_line("\r\n");
     renderer.set_foreground(Color::BrightWhite);
     renderer.write_line("=== HELP ===");
@@ -192,6 +254,7 @@ async fn handle_help(connection: &mut TelnetConnection, renderer: &mut AnsiRende
     renderer.write_line("");
     renderer.write_line("Available features:");
     renderer.write_line("  • Message Areas - JAM/Hudson formats, QWK mail");
+    renderer.write_line("  • New Message Scan - Read what is new in every area");
     renderer.write_line("  • File Areas - Browse, upload, download");
     renderer.write_line("  • Door Games - Classic BBS door games");
     renderer.write_line("  • User Profiles - Statistics and achievements");
@@ -206,8 +269,61 @@ async fn handle_help(connection: &mut TelnetConnection, renderer: &mut AnsiRende
     renderer.write_line("Press any key to continue...");
     renderer.reset();
     connection
-        .send_raw(renderer.take_output().as_bytes())
+        .send_bytes(renderer.take_output().as_bytes())
         .await?;
     connection.read_char().await.ok();
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use impulse_menu::parser::MenuParser;
+
+    const MENU: &str = r#"
+[menu]
+name = "main"
+title = "Main Menu"
+mode = "hotkey"
+
+[[option]]
+key = "M"
+command = "messages"
+description = "Message Areas"
+
+[[option]]
+key = "A"
+command = "admin"
+description = "Administration"
+min_security = 255
+"#;
+
+    #[test]
+    fn test_find_command_hotkey() {
+        let menu = MenuParser::parse(MENU).unwrap();
+        assert_eq!(find_command(&menu, "m", 10), Some("messages".to_string()));
+        assert_eq!(find_command(&menu, "M", 10), Some("messages".to_string()));
+        assert_eq!(find_command(&menu, "X", 10), None);
+        assert_eq!(find_command(&menu, "", 10), None);
+
+        // Hotkey menus do not take command names
+        assert_eq!(find_command(&menu, "messages", 10), None);
+    }
+
+    #[test]
+    fn test_find_command_security() {
+        let menu = MenuParser::parse(MENU).unwrap();
+        assert_eq!(find_command(&menu, "A", 10), None);
+        assert_eq!(find_command(&menu, "A", 255), Some("admin".to_string()));
+    }
+
+    #[test]
+    fn test_find_command_fullmenu() {
+        let menu = MenuParser::parse(&MENU.replace("hotkey", "fullmenu")).unwrap();
+        assert_eq!(find_command(&menu, "M", 10), Some("messages".to_string()));
+        assert_eq!(
+            find_command(&menu, "Messages", 10),
+            Some("messages".to_string())
+        );
+    }
+}
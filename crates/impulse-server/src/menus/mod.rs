@@ -1,6 +1,8 @@
 //! Menu system for the BBS
 
+pub mod commands;
 pub mod handlers;
 pub mod main_menu;
 
+pub use commands::MenuSession;
 pub use main_menu::display_main_menu;
@@ -0,0 +1,240 @@
+//! Configuration hot-reload
+//!
+//! Watches the configuration file and re-applies the settings that can change
+//! while callers are online: session limits and timeouts, themes, doors and
+//! menus. Listener and data path changes still require a restart.
+
+use crate::state::{ServerState, auth_session_timeout, load_menus, session_config};
+use anyhow::Result;
+use impulse_config::reload::{ConfigEvent, ReloadNotifier};
+use impulse_config::watcher::ConfigWatcher;
+use impulse_config::{Config, ValidationOptions, validate_config};
+use impulse_door::DoorManager;
+use impulse_menu::MenuState;
+use impulse_terminal::theme::ThemeManager;
+use impulse_types::config::BbsConfig;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info, warn};
+
+/// Start watching `path` and apply changes to the running server
+///
+/// `config` is the configuration the server was started with.
+pub async fn spawn_config_reload(
+    path: PathBuf,
+    config: Config,
+    state: &Arc<ServerState>,
+) -> Result<()> {
+    let (watcher, mut changes) = ConfigWatcher::new(&path)?;
+    tokio::spawn(watcher.watch());
+
+    let notifier = ReloadNotifier::new();
+    tokio::spawn(log_reload_events(notifier.subscribe()));
+
+    info!("Watching {} for configuration changes", path.display());
+
+    let state = Arc::clone(state);
+    tokio::spawn(async move {
+        let mut current = config;
+        while changes.recv().await.is_some() {
+            if let Some(new_config) = reload(&path, &current, &notifier, &state).await {
+                current = new_config;
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Load, validate and apply the configuration at `path`
+///
+/// Themes, doors and menus are all loaded before anything is swapped in, so
+/// a configuration is either applied completely or not at all. On failure a
+/// `ConfigEvent::ReloadFailed` is sent and `None` is returned, and the next
+/// change is compared against the last applied configuration.
+async fn reload(
+    path: &Path,
+    current: &Config,
+    notifier: &ReloadNotifier,
+    state: &ServerState,
+) -> Option<Config> {
+    notifier.notify_reloading();
+
+    let new_config = match Config::load(path).and_then(|config| {
+        validate_config(config.inner(), &ValidationOptions::deployment()).map(|()| config)
+    }) {
+        Ok(config) => config,
+        Err(e) => {
+            notifier.notify_reload_failed(e.to_string());
+            return None;
+        }
+    };
+
+    warn_restart_required(current.inner(), new_config.inner());
+
+    match Reloaded::load(new_config.inner(), state).await {
+        Ok(reloaded) => reloaded.apply(new_config.inner(), state).await,
+        Err(e) => {
+            notifier.notify_reload_failed(e.to_string());
+            return None;
+        }
+    }
+
+    notifier.notify_reloaded(current.inner().clone(), new_config.inner().clone());
+    Some(new_config)
+}
+
+/// Themes, doors and menus loaded from a new configuration
+struct Reloaded {
+    themes: ThemeManager,
+    doors: DoorManager,
+    menus: MenuState,
+}
+
+impl Reloaded {
+    /// Load everything that can fail without touching the running server
+    async fn load(config: &BbsConfig, state: &ServerState) -> Result<Self> {
+        let themes = ThemeManager::new(config.paths.themes_dir.clone()).await?;
+
+        // Keep the active theme if the new themes have it
+        let current = state.theme_manager.read().await.current_theme_name().await;
+        if themes.theme_exists(&current).await {
+            themes.switch_theme(&current).await?;
+        }
+
+        // Reload into a copy so doors already running are unaffected
+        let mut doors = DoorManager::clone(&*state.door_manager.read().await);
+        doors.reload_doors().await?;
+
+        let menus = load_menus(&config.paths.menus_dir)?;
+
+        Ok(Self {
+            themes,
+            doors,
+            menus,
+        })
+    }
+
+    /// Swap the new settings into the running server
+    async fn apply(self, config: &BbsConfig, state: &ServerState) {
+        state
+            .session_manager
+            .update_config(session_config(&config.limits));
+        state
+            .auth_service
+            .set_session_timeout(auth_session_timeout(&config.limits));
+
+        *state.theme_manager.write().await = self.themes;
+
+        info!("Loaded {} doors", self.doors.door_count());
+        *state.door_manager.write().await = Arc::new(self.doors);
+
+        info!("Menus loaded: {:?}", self.menus.menu_names());
+        state.menus.send_replace(self.menus);
+    }
+}
+
+/// Warn about changed settings that only take effect after a restart
+fn warn_restart_required(old: &BbsConfig, new: &BbsConfig) {
+    let listeners = |config: &BbsConfig| {
+        config
+            .servers
+            .iter()
+            .map(|s| (s.bind_address.clone(), s.port, s.protocol, s.enable_tls))
+            .collect::<Vec<_>>()
+    };
+    if listeners(old) != listeners(new) {
+        warn!("Listener changes take effect after a restart");
+    }
+
+    let (old_paths, new_paths) = (&old.paths, &new.paths);
+    if old_paths.data_dir != new_paths.data_dir
+        || old_paths.users_dir != new_paths.users_dir
+        || old_paths.messages_dir != new_paths.messages_dir
+        || old_paths.files_dir != new_paths.files_dir
+        || old_paths.doors_dir != new_paths.doors_dir
+    {
+        warn!("Data path changes take effect after a restart");
+    }
+}
+
+/// Log configuration reload events
+async fn log_reload_events(mut events: broadcast::Receiver<ConfigEvent>) {
+    loop {
+        match events.recv().await {
+            Ok(ConfigEvent::Reloading) => info!("Reloading configuration"),
+            Ok(ConfigEvent::Reloaded { .. }) => info!("Configuration reloaded"),
+            Ok(ConfigEvent::ReloadFailed { error }) => {
+                error!("Configuration reload failed: {}", error)
+            }
+            Err(RecvError::Lagged(missed)) => warn!("Missed {} configuration events", missed),
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::test_config;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    /// Save `config` to `path` as the next version of the configuration file
+    fn save(config: &BbsConfig, path: &Path) -> Config {
+        let mut saved = Config::with_defaults();
+        *saved.inner_mut() = config.clone();
+        saved.save(path).unwrap();
+        saved
+    }
+
+    #[tokio::test]
+    async fn test_reload_applies_session_limits() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        let mut config = test_config(dir.path());
+        let current = save(&config, &path);
+        let state = ServerState::new(&config).await.unwrap();
+
+        config.limits.max_time_per_session += 7;
+        config.limits.idle_timeout_minutes += 3;
+        save(&config, &path);
+
+        let reloaded = reload(&path, &current, &ReloadNotifier::new(), &state).await;
+        assert!(reloaded.is_some());
+        assert_eq!(
+            state.auth_service.session_timeout(),
+            Duration::from_secs(u64::from(config.limits.max_time_per_session) * 60)
+        );
+        assert_eq!(
+            state.session_manager.config().idle_timeout,
+            Duration::from_secs(u64::from(config.limits.idle_timeout_minutes) * 60)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_reload_changes_nothing() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        let mut config = test_config(dir.path());
+        let current = save(&config, &path);
+        let state = ServerState::new(&config).await.unwrap();
+        let auth_timeout = state.auth_service.session_timeout();
+        let idle_timeout = state.session_manager.config().idle_timeout;
+
+        // The limits are fine but the menus cannot be loaded
+        config.limits.max_time_per_session += 7;
+        config.limits.idle_timeout_minutes += 3;
+        config.paths.menus_dir = dir.path().join("no-menus");
+        std::fs::create_dir_all(&config.paths.menus_dir).unwrap();
+        save(&config, &path);
+
+        let reloaded = reload(&path, &current, &ReloadNotifier::new(), &state).await;
+        assert!(reloaded.is_none());
+        assert_eq!(state.auth_service.session_timeout(), auth_timeout);
+        assert_eq!(state.session_manager.config().idle_timeout, idle_timeout);
+        assert!(state.menus.borrow().has_menu("main"));
+    }
+}
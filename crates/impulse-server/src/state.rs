@@ -2,19 +2,39 @@
 //!
 //! Holds all the managers, services, and shared state for the BBS server.
 
+use crate::mailer::ftn_config;
 use anyhow::Result;
+use chrono::Utc;
 use impulse_admin::{AdminAccessControl, AuditLogger};
-use impulse_auth::AuthService;
+use impulse_auth::lockout::AccountLockout;
+use impulse_auth::rate_limit::RateLimiter;
+use impulse_auth::validation::PasswordStrength;
+use impulse_auth::{AuthService, PasswordHasher};
 use impulse_door::DoorManager;
-use impulse_file::InMemoryFileAreaManager;
+#[cfg(unix)]
+use impulse_file::scanning::ClamAvScanner;
+#[cfg(not(unix))]
+use impulse_file::scanning::MockScanner;
+use impulse_file::scanning::VirusScanner;
+use impulse_file::types::FileArea;
+use impulse_file::{FileAreaManager, InMemoryFileAreaManager, UploadConfig, UploadProcessor};
+use impulse_menu::MenuState;
 use impulse_message::formats::JamMessageBase;
+use impulse_message::ftn::FtnConfig;
+use impulse_message::{MessageArea, MessageAreaRegistry, MessageBase};
 use impulse_session::{SessionConfig, SessionManager};
 use impulse_terminal::theme::ThemeManager;
-use impulse_user::{InMemoryUserManager, UserManager};
-use std::path::PathBuf;
+use impulse_types::config::{BbsConfig, BbsPaths, MessageBaseFormat, SystemLimits, UserStorage};
+use impulse_types::file::FileEntry;
+use impulse_types::security::SecurityLevel;
+use impulse_types::user::User;
+use impulse_user::{JsonUserManager, UserManager};
+use rand::distr::{Alphanumeric, SampleString};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock, watch};
 
 /// Server state containing all managers and services
 ///
@@ -26,15 +46,18 @@ pub struct ServerState {
     /// Authentication service
     pub auth_service: Arc<AuthService>,
 
-    /// User manager
-    pub user_manager: Arc<RwLock<InMemoryUserManager>>,
+    /// User manager (persistent backend chosen by configuration)
+    pub user_manager: Arc<RwLock<Box<dyn UserManager>>>,
 
-    /// Message base manager (simplified for now - single base)
-    pub message_base: Arc<RwLock<JamMessageBase>>,
+    /// Message conferences and areas, with their open bases
+    pub message_areas: Arc<MessageAreas>,
 
     /// File area manager
     pub file_manager: Arc<RwLock<InMemoryFileAreaManager>>,
 
+    /// Upload pipeline: validation, virus scanning and cataloguing
+    pub upload_processor: Arc<Mutex<UploadProcessor>>,
+
     /// Admin access control
     pub admin_access: Arc<AdminAccessControl>,
 
@@ -42,24 +65,97 @@ pub struct ServerState {
     pub audit_logger: Arc<AuditLogger>,
 
     /// Door manager
-    pub door_manager: Arc<DoorManager>,
+    ///
+    /// Replaced as a whole on reload so that running doors keep the
+    /// configuration they were launched with.
+    pub door_manager: Arc<RwLock<Arc<DoorManager>>>,
 
     /// Theme manager
     pub theme_manager: Arc<RwLock<ThemeManager>>,
 
+    /// Menu definitions loaded from the menu directory
+    ///
+    /// Each caller keeps a subscription and picks up reloaded menus on their
+    /// next menu prompt.
+    pub menus: Arc<watch::Sender<MenuState>>,
+
     /// Session manager
     pub session_manager: Arc<SessionManager>,
 
+    /// Minutes a caller may stay on per call (0 for no limit)
+    pub max_session_minutes: u32,
+
+    /// Failed logins allowed on one connection before it is dropped
+    pub max_login_attempts: u8,
+
+    /// Minimum password strength for new accounts
+    pub min_password_strength: PasswordStrength,
+
+    /// NetMail settings (none when FidoNet is not configured)
+    pub netmail: Option<NetmailSettings>,
+
     /// Base paths
     pub paths: ServerPaths,
 }
 
+/// A message base shared between callers
+pub type SharedMessageBase = Arc<RwLock<Box<dyn MessageBase>>>;
+
+/// The message area registry and an open base for each area
+pub struct MessageAreas {
+    /// Conferences and areas
+    pub registry: MessageAreaRegistry,
+
+    /// Open bases by lowercased area tag
+    bases: HashMap<String, SharedMessageBase>,
+}
+
+impl MessageAreas {
+    /// Open every area in the registry, creating missing JAM bases
+    pub async fn open(registry: MessageAreaRegistry) -> Result<Self> {
+        let mut bases = HashMap::new();
+        for area in registry.areas() {
+            if area.format == MessageBaseFormat::Jam {
+                JamMessageBase::new(&area.path).create_if_missing().await?;
+            }
+            bases.insert(area.tag.to_lowercase(), Arc::new(RwLock::new(area.open())));
+        }
+        Ok(Self { registry, bases })
+    }
+
+    /// The open base of an area
+    pub fn base(&self, tag: &str) -> Option<SharedMessageBase> {
+        self.bases.get(&tag.to_lowercase()).cloned()
+    }
+
+    /// Every open base
+    pub fn bases(&self) -> impl Iterator<Item = &SharedMessageBase> {
+        self.bases.values()
+    }
+}
+
+/// What callers need to write NetMail
+#[derive(Clone, Debug)]
+pub struct NetmailSettings {
+    /// Tosser, scanner and mailer configuration, with the NetMail base
+    pub ftn: FtnConfig,
+
+    /// Security level needed to write NetMail
+    pub min_security: u8,
+
+    /// Security level needed to send crash mail or attach files
+    pub crash_security: u8,
+}
+
 /// Server paths configuration
 #[derive(Clone, Debug)]
 pub struct ServerPaths {
     /// Data directory
     pub data_dir: PathBuf,
 
+    /// User account directory
+    pub users_dir: PathBuf,
+
     /// Message base directory
     pub message_dir: PathBuf,
 
@@ -74,38 +170,36 @@ pub struct ServerPaths {
 
     /// Theme directory
     pub theme_dir: PathBuf,
-}
-
-impl Default for ServerPaths {
-    fn default() -> Self {
-        let data_dir = PathBuf::from("/tmp/impulse-next-bbs/data");
 
-        // Use project themes directory for built-in themes
-        // In production, this would be configurable
-        let project_themes = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-            .parent()
-            .and_then(|p| p.parent())
-            .map(|p| p.join("themes"))
-            .unwrap_or_else(|| data_dir.join("themes"));
+    /// Menu definition directory
+    pub menu_dir: PathBuf,
+}
 
+impl ServerPaths {
+    /// Take the server paths from the configured BBS paths
+    pub fn from_config(paths: &BbsPaths) -> Self {
         Self {
-            data_dir: data_dir.clone(),
-            message_dir: data_dir.join("messages"),
-            files_dir: data_dir.join("files"),
-            doors_dir: data_dir.join("doors"),
-            nodes_dir: data_dir.join("nodes"),
-            theme_dir: project_themes,
+            data_dir: paths.data_dir.clone(),
+            users_dir: paths.users_dir.clone(),
+            message_dir: paths.messages_dir.clone(),
+            files_dir: paths.files_dir.clone(),
+            doors_dir: paths.doors_dir.clone(),
+            nodes_dir: paths.data_dir.join("nodes"),
+            theme_dir: paths.themes_dir.clone(),
+            menu_dir: paths.menus_dir.clone(),
         }
     }
 }
 
 impl ServerState {
-    /// Create a new server state with default configuration
-    pub async fn new() -> Result<Self> {
-        let paths = ServerPaths::default();
+    /// Create a new server state from the BBS configuration
+    pub async fn new(config: &BbsConfig) -> Result<Self> {
+        let paths = ServerPaths::from_config(&config.paths);
+        let limits = &config.limits;
 
         // Create directories if they don't exist
         std::fs::create_dir_all(&paths.data_dir)?;
+        std::fs::create_dir_all(&paths.users_dir)?;
         std::fs::create_dir_all(&paths.message_dir)?;
         std::fs::create_dir_all(&paths.files_dir)?;
         std::fs::create_dir_all(&paths.doors_dir)?;
@@ -113,51 +207,60 @@ impl ServerState {
         std::fs::create_dir_all(&paths.theme_dir)?;
 
         // Initialize auth service
-        let auth_service = Arc::new(AuthService::new(Duration::from_secs(1800))); // 30 min sessions
-
-        // Initialize user manager with a default sysop user
-        let mut user_manager = InMemoryUserManager::new();
-
-        // Create default sysop user
-        let mut sysop = impulse_types::user::User::new("sysop")
-            .map_err(|e| anyhow::anyhow!("Failed to create sysop user: {}", e))?;
-        sysop.set_security_level(impulse_types::security::SecurityLevel::new(255)); // Max security
-        user_manager.create_user(sysop).await?;
+        let auth_service = Arc::new(auth_service(config));
 
-        // Create a test user for demonstration
-        let mut testuser = impulse_types::user::User::new("testuser")
-            .map_err(|e| anyhow::anyhow!("Failed to create test user: {}", e))?;
-        testuser.set_security_level(impulse_types::security::SecurityLevel::new(10)); // Normal user
-        user_manager.create_user(testuser).await?;
+        // Open the persistent user store, seeding it on first start
+        let mut user_manager = open_user_store(config).await?;
+        if user_manager.count_users().await? == 0 {
+            seed_default_users(user_manager.as_mut(), &config.sysop, &paths.users_dir).await?;
+        }
+        let user_count = user_manager.count_users().await?;
 
         let user_manager = Arc::new(RwLock::new(user_manager));
 
-        // Initialize message base
-        let message_base_path = paths.message_dir.join("general");
-        let message_base = Arc::new(RwLock::new(JamMessageBase::new(message_base_path)));
-
-        // Initialize file area manager
-        let file_manager = Arc::new(RwLock::new(InMemoryFileAreaManager::new()));
+        // Open the message areas
+        let message_areas = Arc::new(MessageAreas::open(message_area_registry(config)).await?);
+
+        // NetMail is written to the base the scanner exports from
+        let netmail = match (ftn_config(config)?, &config.fidonet) {
+            (Some(ftn), Some(fidonet)) => Some(NetmailSettings {
+                ftn,
+                min_security: fidonet.netmail_security,
+                crash_security: fidonet.netmail_crash_security,
+            }),
+            _ => None,
+        };
+
+        // Catalog the file areas found in the files directory
+        let file_manager = load_file_areas(&paths.files_dir, &config.sysop).await?;
+        let area_count = file_manager
+            .list_areas(SecurityLevel::new(255))
+            .await?
+            .len();
+        let file_manager = Arc::new(RwLock::new(file_manager));
+        let upload_processor = Arc::new(Mutex::new(
+            upload_processor(config, &paths, file_manager.clone()).await,
+        ));
 
         // Initialize admin components
         let admin_access = Arc::new(AdminAccessControl::new(200, 200)); // SysOp level: 200
         let audit_logger = Arc::new(AuditLogger::new());
 
         // Initialize door manager
-        let door_manager =
-            Arc::new(DoorManager::new(paths.doors_dir.clone(), paths.nodes_dir.clone()).await?);
+        let door_manager = Arc::new(RwLock::new(Arc::new(
+            DoorManager::new(paths.doors_dir.clone(), paths.nodes_dir.clone()).await?,
+        )));
 
         // Initialize theme manager
         let theme_manager = Arc::new(RwLock::new(
             ThemeManager::new(paths.theme_dir.clone()).await?,
         ));
 
+        // Load menu definitions
+        let menus = Arc::new(watch::Sender::new(load_menus(&paths.menu_dir)?));
+
         // Initialize session manager
-        let session_config = SessionConfig::default()
-            .with_idle_timeout(Duration::from_secs(900)) // 15 min idle timeout
-            .with_max_sessions_per_user(3)
-            .with_max_total_sessions(100);
-        let session_manager = Arc::new(SessionManager::new(session_config));
+        let session_manager = Arc::new(SessionManager::new(session_config(limits)));
 
         // Log loaded themes
         let theme_list = theme_manager.read().await.list_themes().await;
@@ -167,27 +270,306 @@ impl ServerState {
         tracing::info!("  Data directory: {:?}", paths.data_dir);
         tracing::info!("  Theme directory: {:?}", paths.theme_dir);
         tracing::info!("  Themes loaded: {:?}", theme_names);
-        tracing::info!("  Default users created: sysop (255), testuser (10)");
+        tracing::info!("  Menus loaded: {:?}", menus.borrow().menu_names());
+        tracing::info!("  File areas: {}", area_count);
+        tracing::info!("  Message areas: {}", message_areas.registry.areas().len());
+        tracing::info!(
+            "  Users: {} ({:?} storage)",
+            user_count,
+            config.user_storage
+        );
 
         Ok(Self {
             auth_service,
             user_manager,
-            message_base,
+            message_areas,
             file_manager,
+            upload_processor,
             admin_access,
             audit_logger,
             door_manager,
             theme_manager,
+            menus,
             session_manager,
+            max_session_minutes: limits.max_time_per_session,
+            max_login_attempts: limits.max_password_attempts.max(1),
+            min_password_strength: if config.security.require_strong_passwords {
+                PasswordStrength::Fair
+            } else {
+                PasswordStrength::Weak
+            },
+            netmail,
             paths,
         })
     }
+}
+
+/// Build the message area registry from the configuration
+///
+/// Without configured areas there is a single "general" area, board 1, in
+/// the messages directory that anyone can read and post in.
+fn message_area_registry(config: &BbsConfig) -> MessageAreaRegistry {
+    if config.message_areas.is_empty() {
+        return MessageAreaRegistry::new().with_area(
+            MessageArea::new(
+                "general",
+                "General",
+                config.paths.messages_dir.join("general"),
+            )
+            .with_board(1),
+        );
+    }
+    MessageAreaRegistry::from_config(&config.message_conferences, &config.message_areas)
+}
+
+/// Build the authentication service from the security settings
+fn auth_service(config: &BbsConfig) -> AuthService {
+    let security = &config.security;
+    let mut auth_service = AuthService::new(auth_session_timeout(&config.limits));
+
+    if security.enable_rate_limiting {
+        auth_service = auth_service.with_rate_limiter(RateLimiter::new(
+            security.rate_limit_per_minute as usize,
+            Duration::from_secs(60),
+        ));
+    }
+    if security.enable_account_lockout {
+        auth_service = auth_service.with_lockout(AccountLockout::new(
+            u32::from(config.limits.max_password_attempts.max(1)),
+            Duration::from_secs(u64::from(security.lockout_duration_minutes) * 60),
+        ));
+    }
+
+    auth_service
+}
+
+/// Open the user store selected by `config.user_storage`
+///
+/// The store lives in the users directory, which must already exist. A
+/// missing store file is treated as an empty user base. `USER.LST` storage
+/// is refused: it has nowhere to keep password hashes, so nobody could log
+/// in after a restart.
+pub async fn open_user_store(config: &BbsConfig) -> Result<Box<dyn UserManager>> {
+    let users_dir = &config.paths.users_dir;
+
+    let manager: Box<dyn UserManager> = match config.user_storage {
+        UserStorage::Json => Box::new(JsonUserManager::open(users_dir.join("users.json")).await?),
+        UserStorage::UserLst => anyhow::bail!(
+            "user_storage = \"UserLst\" cannot keep password hashes; use \"Json\" and \
+             bring legacy accounts over with --import-user-lst"
+        ),
+    };
+
+    Ok(manager)
+}
+
+/// Create the SysOp account in an empty user store
+///
+/// The account gets a random password, which is written to
+/// `sysop-password.txt` in the users directory (readable by the owner only)
+/// and never to the log or the console.
+async fn seed_default_users(
+    user_manager: &mut dyn UserManager,
+    sysop_name: &str,
+    users_dir: &Path,
+) -> Result<()> {
+    let mut sysop = User::new(sysop_name)
+        .or_else(|_| User::new("sysop"))
+        .map_err(|e| anyhow::anyhow!("Failed to create sysop user: {}", e))?;
+    sysop.set_security_level(SecurityLevel::new(255)); // Max security
+
+    let password = Alphanumeric.sample_string(&mut rand::rng(), 16);
+    sysop.password_hash = Some(PasswordHasher::new().hash_password(&password)?);
+    let username = sysop.username().to_string();
+
+    let password_path = users_dir.join("sysop-password.txt");
+    write_private(
+        &password_path,
+        &format!("Username: {}\nPassword: {}\n", username, password),
+    )?;
+    user_manager.create_user(sysop).await?;
+
+    tracing::warn!(
+        username = %username,
+        path = %password_path.display(),
+        "Empty user store seeded with a SysOp account; its password is in the file shown, delete it once read"
+    );
+    Ok(())
+}
+
+/// Write a file only its owner can read
+fn write_private(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    std::io::Write::write_all(&mut options.open(path)?, contents.as_bytes())
+}
+
+/// Build the file area catalog from the files directory
+///
+/// Every `area_NNN` directory (the layout uploads are stored in) becomes
+/// file area NNN, open for uploads and listing the files it holds. Other
+/// entries are ignored.
+async fn load_file_areas(files_dir: &Path, sysop_name: &str) -> Result<InMemoryFileAreaManager> {
+    let mut manager = InMemoryFileAreaManager::new();
+    let mut next_file_id = 1;
+
+    let mut area_dirs: Vec<(u32, PathBuf)> = std::fs::read_dir(files_dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let area_id = name.to_str()?.strip_prefix("area_")?.parse().ok()?;
+            Some((area_id, entry.path()))
+        })
+        .collect();
+    area_dirs.sort();
+
+    for (area_id, area_dir) in area_dirs {
+        manager
+            .add_area(
+                FileArea::new(area_id, format!("File Area {}", area_id), String::new())
+                    .with_path(area_dir.clone())
+                    .allow_uploads(),
+            )
+            .await?;
+
+        let mut files: Vec<_> = std::fs::read_dir(&area_dir)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                Some((
+                    entry.file_name().into_string().ok()?,
+                    entry.metadata().ok()?,
+                ))
+            })
+            .filter(|(_, metadata)| metadata.is_file())
+            .collect();
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (filename, metadata) in files {
+            manager
+                .add_file(FileEntry {
+                    id: next_file_id,
+                    filename,
+                    description: String::new(),
+                    uploader: sysop_name.to_string(),
+                    uploader_id: 0,
+                    size_bytes: metadata.len(),
+                    upload_date: metadata
+                        .modified()
+                        .map(Into::into)
+                        .unwrap_or_else(|_| Utc::now()),
+                    area_id,
+                    download_count: 0,
+                    is_offline: false,
+                    is_missing: false,
+                    password: None,
+                    cost_credits: None,
+                })
+                .await?;
+            next_file_id += 1;
+        }
+    }
 
-    /// Create server state with custom paths
-    #[allow(dead_code)]
-    pub async fn with_paths(paths: ServerPaths) -> Result<Self> {
-        let mut state = Self::new().await?;
-        state.paths = paths;
-        Ok(state)
+    Ok(manager)
+}
+
+/// Build the upload pipeline from the system limits
+///
+/// Uploads are scanned by the ClamAV daemon when it answers on its socket;
+/// otherwise scanning is disabled with a warning. Infected uploads are
+/// quarantined under the data directory.
+async fn upload_processor(
+    config: &BbsConfig,
+    paths: &ServerPaths,
+    file_manager: Arc<RwLock<InMemoryFileAreaManager>>,
+) -> UploadProcessor {
+    let limits = &config.limits;
+    let mut upload_config = UploadConfig::new()
+        .with_max_file_size(limits.max_upload_size)
+        .with_quarantine_dir(paths.data_dir.join("quarantine").to_string_lossy())
+        .with_file_points(
+            limits.upload_file_point_ratio,
+            limits.upload_file_point_base_kb,
+        );
+
+    #[cfg(unix)]
+    let scanner: Arc<dyn VirusScanner> =
+        Arc::new(ClamAvScanner::new(upload_config.clamav_socket.clone()));
+    #[cfg(not(unix))]
+    let scanner: Arc<dyn VirusScanner> = Arc::new(MockScanner::unavailable());
+
+    if !scanner.is_available().await {
+        tracing::warn!(
+            socket = %upload_config.clamav_socket,
+            "ClamAV is not available; uploads will not be virus scanned"
+        );
+        upload_config = upload_config.disable_virus_scan();
+    }
+
+    UploadProcessor::new(scanner, file_manager, upload_config, &paths.files_dir)
+}
+
+/// Build the session manager configuration from the system limits
+pub fn session_config(limits: &SystemLimits) -> SessionConfig {
+    SessionConfig::default()
+        .with_idle_timeout(Duration::from_secs(
+            u64::from(limits.idle_timeout_minutes) * 60,
+        ))
+        .with_max_sessions_per_user(3)
+        .with_max_total_sessions(limits.max_connections as usize)
+}
+
+/// How long an authenticated session lasts
+pub fn auth_session_timeout(limits: &SystemLimits) -> Duration {
+    Duration::from_secs(u64::from(limits.max_time_per_session) * 60)
+}
+
+/// Name of the menu every caller starts in
+pub const MAIN_MENU: &str = "main";
+
+/// Load and validate all menu definitions in `menu_dir`
+///
+/// Fails if any menu file is invalid or if no main menu is defined, since
+/// callers would otherwise have nowhere to land after logging in.
+pub fn load_menus(menu_dir: &Path) -> Result<MenuState> {
+    let mut menus = MenuState::new(MAIN_MENU);
+    menus
+        .load_menus(menu_dir)
+        .map_err(|e| anyhow::anyhow!("Failed to load menus from {:?}: {}", menu_dir, e))?;
+
+    if !menus.has_menu(MAIN_MENU) {
+        anyhow::bail!("Menu directory {:?} has no '{}' menu", menu_dir, MAIN_MENU);
+    }
+
+    Ok(menus)
+}
+
+/// A configuration kept entirely inside `dir`, with the stock menus
+#[cfg(test)]
+pub(crate) fn test_config(dir: &Path) -> BbsConfig {
+    let data_dir = dir.join("data");
+    let menus_dir = dir.join("menus");
+    std::fs::create_dir_all(&menus_dir).unwrap();
+    let stock_menus = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../config/menus");
+    for entry in std::fs::read_dir(stock_menus).unwrap() {
+        let path = entry.unwrap().path();
+        std::fs::copy(&path, menus_dir.join(path.file_name().unwrap())).unwrap();
+    }
+
+    BbsConfig {
+        paths: BbsPaths {
+            users_dir: data_dir.join("users"),
+            messages_dir: data_dir.join("messages"),
+            files_dir: data_dir.join("files"),
+            logs_dir: data_dir.join("logs"),
+            temp_dir: data_dir.join("temp"),
+            doors_dir: data_dir.join("doors"),
+            themes_dir: dir.join("themes"),
+            menus_dir,
+            data_dir,
+        },
+        ..Default::default()
     }
 }
@@ -0,0 +1,105 @@
+//! Byte-stream bridge over a caller's connection
+//!
+//! File transfer protocols are written against tokio's `AsyncRead` and
+//! `AsyncWrite`, while callers arrive on a [`Connection`]. [`bridge`] hands
+//! the protocol one end of an in-memory duplex pipe and shuttles bytes
+//! between the other end and the connection until the protocol finishes.
+
+use crate::connection::{Connection, ConnectionError};
+use std::future::Future;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+/// Size of the in-memory pipe between the protocol and the connection
+const BRIDGE_BUFFER_SIZE: usize = 16 * 1024;
+
+/// Run `transfer` over a byte stream connected to `connection`
+///
+/// Everything the transfer writes is sent to the caller with
+/// [`send_bytes`](Connection::send_bytes), and everything the caller sends
+/// is readable from the stream. Output the transfer wrote before finishing
+/// is delivered before this returns.
+///
+/// Fails with [`ConnectionError::Closed`] if the caller hangs up first; the
+/// transfer is dropped in that case.
+pub async fn bridge<F, Fut, T>(
+    connection: &mut dyn Connection,
+    transfer: F,
+) -> Result<T, ConnectionError>
+where
+    F: FnOnce(DuplexStream) -> Fut,
+    Fut: Future<Output = T>,
+{
+    let (protocol_end, mut caller_end) = tokio::io::duplex(BRIDGE_BUFFER_SIZE);
+    let transfer = transfer(protocol_end);
+    tokio::pin!(transfer);
+
+    let mut buffer = vec![0u8; BRIDGE_BUFFER_SIZE];
+    let mut output_open = true;
+
+    loop {
+        tokio::select! {
+            biased;
+
+            result = &mut transfer => {
+                // The transfer's end of the pipe has been dropped, so this
+                // drains what is left and stops at end of stream
+                loop {
+                    let n = caller_end.read(&mut buffer).await?;
+                    if n == 0 {
+                        break;
+                    }
+                    connection.send_bytes(&buffer[..n]).await?;
+                }
+                return Ok(result);
+            }
+            read = caller_end.read(&mut buffer), if output_open => {
+                match read? {
+                    0 => output_open = false,
+                    n => connection.send_bytes(&buffer[..n]).await?,
+                }
+            }
+            received = connection.recv() => {
+                match received? {
+                    Some(data) => caller_end.write_all(&data).await?,
+                    None => return Err(ConnectionError::Closed),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryConnection;
+
+    #[tokio::test]
+    async fn test_bridge_relays_both_directions() {
+        let mut connection = MemoryConnection::new(b"ping");
+
+        let reply = bridge(&mut connection, |mut stream| async move {
+            let mut request = [0u8; 4];
+            stream.read_exact(&mut request).await.unwrap();
+            stream.write_all(b"pong\xff").await.unwrap();
+            request
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(&reply, b"ping");
+        assert_eq!(connection.output(), b"pong\xff");
+    }
+
+    #[tokio::test]
+    async fn test_bridge_fails_when_caller_hangs_up() {
+        let mut connection = MemoryConnection::new(b"");
+
+        let result = bridge(&mut connection, |mut stream| async move {
+            let mut request = [0u8; 1];
+            stream.read_exact(&mut request).await
+        })
+        .await;
+
+        assert!(matches!(result, Err(ConnectionError::Closed)));
+    }
+}
@@ -1,8 +1,12 @@
 //! Connection abstraction for different transport types
 
 use async_trait::async_trait;
+use std::collections::VecDeque;
 use std::fmt;
 
+/// Maximum length of a line or password read from a client (64KB)
+const MAX_LINE_LENGTH: usize = 65536;
+
 /// Connection type identifier
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ConnectionType {
@@ -12,6 +16,8 @@ pub enum ConnectionType {
     WebSocket,
     /// SSH connection
     Ssh,
+    /// Local connection (sysop console or in-memory test transport)
+    Local,
 }
 
 impl fmt::Display for ConnectionType {
@@ -20,6 +26,7 @@ impl fmt::Display for ConnectionType {
             Self::Telnet => write!(f, "Telnet"),
             Self::WebSocket => write!(f, "WebSocket"),
             Self::Ssh => write!(f, "SSH"),
+            Self::Local => write!(f, "Local"),
         }
     }
 }
@@ -52,6 +59,145 @@ pub trait Connection: Send + Sync {
 
     /// Check if the connection is still alive
     fn is_connected(&self) -> bool;
+
+    /// Read a single character from the client
+    ///
+    /// Must be cancel safe: if the future is dropped before it completes, no
+    /// input may be lost. Callers race it against other events, such as
+    /// system notices, and read again afterwards.
+    async fn read_char(&mut self) -> Result<char, ConnectionError>;
+
+    /// Read a line of text from the client (blocking until CR or LF)
+    ///
+    /// Typed characters are echoed back and backspace erases the previous
+    /// character. The terminating newline is not echoed. The default
+    /// implementation builds on [`read_char`](Self::read_char).
+    async fn read_line(&mut self) -> Result<String, ConnectionError> {
+        let mut line = String::new();
+
+        loop {
+            let ch = self.read_char().await?;
+
+            // Skip leading CR/LF characters (handles leftover LF from previous CRLF)
+            if line.is_empty() && (ch == '\r' || ch == '\n') {
+                continue;
+            }
+
+            match ch {
+                '\r' | '\n' => return Ok(line),
+                '\x08' | '\x7f' => {
+                    let erased = line.pop().is_some();
+                    if erased {
+                        self.send_bytes(b"\x08 \x08").await?;
+                    }
+                }
+                ' '..='~' | '\t' => {
+                    line.push(ch);
+                    self.send_bytes(&[ch as u8]).await?;
+                }
+                _ => {}
+            }
+
+            if line.len() > MAX_LINE_LENGTH {
+                return Err(ConnectionError::Protocol(format!(
+                    "Line exceeds maximum length of {} bytes",
+                    MAX_LINE_LENGTH
+                )));
+            }
+        }
+    }
+
+    /// Read a password from the client without echoing it
+    ///
+    /// When `show_asterisks` is true, a `*` is echoed for each character
+    /// typed. A CRLF is echoed once the password has been entered.
+    async fn read_password(&mut self, show_asterisks: bool) -> Result<String, ConnectionError> {
+        let mut password = String::new();
+
+        loop {
+            let ch = self.read_char().await?;
+
+            if password.is_empty() && (ch == '\r' || ch == '\n') {
+                continue;
+            }
+
+            match ch {
+                '\r' | '\n' => {
+                    self.send_bytes(b"\r\n").await?;
+                    return Ok(password);
+                }
+                '\x08' | '\x7f' => {
+                    let erased = password.pop().is_some();
+                    if erased && show_asterisks {
+                        self.send_bytes(b"\x08 \x08").await?;
+                    }
+                }
+                ' '..='~' | '\t' => {
+                    password.push(ch);
+                    if show_asterisks {
+                        self.send_bytes(b"*").await?;
+                    }
+                }
+                _ => {}
+            }
+
+            if password.len() > MAX_LINE_LENGTH {
+                return Err(ConnectionError::Protocol(format!(
+                    "Password exceeds maximum length of {} bytes",
+                    MAX_LINE_LENGTH
+                )));
+            }
+        }
+    }
+
+    /// Get the client's terminal dimensions as (columns, rows)
+    ///
+    /// Transports that cannot negotiate a window size report 80x24.
+    fn terminal_size(&self) -> (u16, u16) {
+        (80, 24)
+    }
+
+    /// Get the caller's TCP socket, for doors that talk to it directly
+    ///
+    /// Only transports that carry the caller's bytes unchanged over a plain
+    /// socket have one to share; the rest report `None`.
+    #[cfg(unix)]
+    fn socket(&self) -> Option<std::os::fd::BorrowedFd<'_>> {
+        None
+    }
+}
+
+/// Take one UTF-8 character from the front of a transport's input buffer
+///
+/// Returns `None` when the buffer is empty or holds only the start of a
+/// character whose remaining bytes have not arrived yet, so a character
+/// split between reads is put back together. A byte that cannot be part of
+/// a character is taken on its own as U+FFFD.
+pub fn take_utf8_char(input: &mut VecDeque<u8>) -> Option<char> {
+    let &lead = input.front()?;
+    let length = match lead {
+        0x00..=0x7f => return input.pop_front().map(char::from),
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => 0,
+    };
+
+    let bytes: Vec<u8> = input.iter().take(length).copied().collect();
+    let continues = bytes.iter().skip(1).all(|&byte| byte & 0xc0 == 0x80);
+    if length > 0 && continues && bytes.len() < length {
+        return None;
+    }
+    match std::str::from_utf8(&bytes) {
+        Ok(text) if length > 0 => {
+            input.drain(..length);
+            text.chars().next()
+        }
+        _ => {
+            input.pop_front();
+            Some(char::REPLACEMENT_CHARACTER)
+        }
+    }
 }
 
 /// Errors that can occur with connections
@@ -88,6 +234,40 @@ mod tests {
         assert_eq!(ConnectionType::Telnet.to_string(), "Telnet");
         assert_eq!(ConnectionType::WebSocket.to_string(), "WebSocket");
         assert_eq!(ConnectionType::Ssh.to_string(), "SSH");
+        assert_eq!(ConnectionType::Local.to_string(), "Local");
+    }
+
+    #[test]
+    fn test_take_utf8_char() {
+        let mut input: VecDeque<u8> = "aé✓".bytes().collect();
+        assert_eq!(take_utf8_char(&mut input), Some('a'));
+        assert_eq!(take_utf8_char(&mut input), Some('é'));
+        assert_eq!(take_utf8_char(&mut input), Some('✓'));
+        assert_eq!(take_utf8_char(&mut input), None);
+    }
+
+    #[test]
+    fn test_take_utf8_char_split() {
+        let mut input: VecDeque<u8> = "✓".bytes().take(2).collect();
+        assert_eq!(take_utf8_char(&mut input), None);
+        assert_eq!(input.len(), 2);
+
+        input.push_back("✓".as_bytes()[2]);
+        assert_eq!(take_utf8_char(&mut input), Some('✓'));
+    }
+
+    #[test]
+    fn test_take_utf8_char_invalid() {
+        let mut input: VecDeque<u8> = [0xb0, 0xc9, b'A'].into_iter().collect();
+        assert_eq!(
+            take_utf8_char(&mut input),
+            Some(char::REPLACEMENT_CHARACTER)
+        );
+        assert_eq!(
+            take_utf8_char(&mut input),
+            Some(char::REPLACEMENT_CHARACTER)
+        );
+        assert_eq!(take_utf8_char(&mut input), Some('A'));
     }
 
     #[test]
@@ -11,6 +11,8 @@
 //! - Terminal capability tracking
 //! - Concurrent session management
 //! - Activity monitoring
+//! - Transport-agnostic [`Connection`] trait with an in-memory implementation
+//! - Byte-stream [`bridge`] for running file transfer protocols over a connection
 //!
 //! # Example
 //!
@@ -34,19 +36,23 @@
 //! }
 //! ```
 
+mod bridge;
 mod config;
 mod connection;
 mod error;
 mod manager;
+mod memory;
 mod session;
 
 #[cfg(feature = "websocket")]
 mod websocket;
 
+pub use bridge::bridge;
 pub use config::{ConflictPolicy, SessionConfig};
-pub use connection::{Connection, ConnectionError, ConnectionType};
+pub use connection::{Connection, ConnectionError, ConnectionType, take_utf8_char};
 pub use error::{Result, SessionError};
 pub use manager::SessionManager;
+pub use memory::MemoryConnection;
 pub use session::{Session, SessionId, SessionState};
 
 #[cfg(feature = "websocket")]
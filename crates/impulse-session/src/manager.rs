@@ -3,16 +3,16 @@
 use crate::config::SessionConfig;
 use crate::error::{Result, SessionError};
 use crate::session::{Session, SessionId, SessionState};
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, PoisonError};
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
 /// Thread-safe session manager
 #[derive(Clone)]
 pub struct SessionManager {
-    /// Configuration
-    config: Arc<SessionConfig>,
+    /// Configuration (replaceable at runtime)
+    config: Arc<std::sync::RwLock<Arc<SessionConfig>>>,
     /// Active sessions (by ID)
     sessions: Arc<RwLock<HashMap<SessionId, Session>>>,
     /// User session mapping (username -> Vec<SessionId>)
@@ -23,12 +23,30 @@ impl SessionManager {
     /// Create a new session manager
     pub fn new(config: SessionConfig) -> Self {
         Self {
-            config: Arc::new(config),
+            config: Arc::new(std::sync::RwLock::new(Arc::new(config))),
             sessions: Arc::new(RwLock::new(HashMap::new())),
             user_sessions: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
+    /// Get the current configuration
+    pub fn config(&self) -> Arc<SessionConfig> {
+        self.config
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Replace the configuration without disturbing existing sessions
+    ///
+    /// New limits apply to sessions created afterwards, and the new timeouts
+    /// apply from the next timeout check. The cleanup interval of an already
+    /// running cleanup task is not changed.
+    pub fn update_config(&self, config: SessionConfig) {
+        *self.config.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(config);
+        info!("Session configuration updated");
+    }
+
     /// Create a new session
     ///
     /// # Example
@@ -45,24 +63,31 @@ impl SessionManager {
     /// }
     /// ```
     pub async fn create_session(&self, remote_addr: impl Into<String>) -> Result<SessionId> {
-        let session = Session::new(remote_addr.into());
+        let mut session = Session::new(remote_addr.into());
         let session_id = session.id();
 
         // Check total session limit
+        let config = self.config();
         let sessions = self.sessions.read().await;
-        if sessions.len() >= self.config.max_total_sessions {
+        if sessions.len() >= config.max_total_sessions {
             return Err(SessionError::TooManySessions {
-                limit: self.config.max_total_sessions,
+                limit: config.max_total_sessions,
             });
         }
         drop(sessions);
 
-        // Insert session
+        // Insert session on the lowest free node
         let mut sessions = self.sessions.write().await;
+        let nodes: HashSet<u16> = sessions.values().map(Session::node).collect();
+        let node = (1..=u16::MAX)
+            .find(|node| !nodes.contains(node))
+            .unwrap_or(u16::MAX);
+        session.set_node(node);
         sessions.insert(session_id, session.clone());
 
         info!(
             session_id = %session_id,
+            node,
             remote_addr = %session.remote_addr(),
             "Created new session"
         );
@@ -101,17 +126,19 @@ impl SessionManager {
     ) -> Result<()> {
         use crate::config::ConflictPolicy;
 
+        let config = self.config();
+
         // Check sessions per user limit and handle conflicts
         {
             let user_sessions = self.user_sessions.read().await;
             if let Some(sessions) = user_sessions.get(&username)
-                && sessions.len() >= self.config.max_sessions_per_user
+                && sessions.len() >= config.max_sessions_per_user
             {
-                match self.config.conflict_policy {
+                match config.conflict_policy {
                     ConflictPolicy::Allow => {
                         // Should not reach here, but handle gracefully
                         return Err(SessionError::TooManySessions {
-                            limit: self.config.max_sessions_per_user,
+                            limit: config.max_sessions_per_user,
                         });
                     }
                     ConflictPolicy::KickOldest => {
@@ -128,7 +155,7 @@ impl SessionManager {
                     }
                     ConflictPolicy::DenyNew => {
                         return Err(SessionError::TooManySessions {
-                            limit: self.config.max_sessions_per_user,
+                            limit: config.max_sessions_per_user,
                         });
                     }
                 }
@@ -281,6 +308,7 @@ impl SessionManager {
     ///
     /// Returns a tuple of (sessions_needing_idle_warning, sessions_needing_absolute_warning)
     pub async fn check_timeout_warnings(&self) -> (Vec<SessionId>, Vec<SessionId>) {
+        let config = self.config();
         let sessions = self.sessions.read().await;
         let mut idle_warnings = Vec::new();
         let mut absolute_warnings = Vec::new();
@@ -288,25 +316,21 @@ impl SessionManager {
         for (id, session) in sessions.iter() {
             // Skip unlimited users
             if let Some(username) = session.username()
-                && self.config.is_unlimited_user(username)
+                && config.is_unlimited_user(username)
             {
                 continue;
             }
 
             // Check idle warning
-            if session.should_send_idle_warning(
-                self.config.idle_timeout,
-                self.config.warning_before_timeout,
-            ) {
+            if session.should_send_idle_warning(config.idle_timeout, config.warning_before_timeout)
+            {
                 idle_warnings.push(*id);
             }
 
             // Check absolute warning (if configured)
-            if let Some(absolute_timeout) = self.config.absolute_timeout
-                && session.should_send_absolute_warning(
-                    absolute_timeout,
-                    self.config.warning_before_timeout,
-                )
+            if let Some(absolute_timeout) = config.absolute_timeout
+                && session
+                    .should_send_absolute_warning(absolute_timeout, config.warning_before_timeout)
             {
                 absolute_warnings.push(*id);
             }
@@ -337,10 +361,22 @@ impl SessionManager {
         Ok(())
     }
 
+    /// Give a session's caller extra time this call, or take it away
+    pub async fn add_time_credit(&self, session_id: SessionId, seconds: i64) -> Result<()> {
+        let mut sessions = self.sessions.write().await;
+        let session = sessions
+            .get_mut(&session_id)
+            .ok_or(SessionError::NotFound(session_id.to_string()))?;
+
+        session.add_time_credit(seconds);
+        Ok(())
+    }
+
     /// Clean up expired sessions
     ///
     /// Checks for idle timeouts and absolute timeouts (respecting unlimited users)
     pub async fn cleanup_expired_sessions(&self) -> usize {
+        let config = self.config();
         let mut expired = Vec::new();
 
         {
@@ -349,7 +385,7 @@ impl SessionManager {
                 // Skip unlimited users for absolute timeout
                 let is_unlimited = session
                     .username()
-                    .map(|u| self.config.is_unlimited_user(u))
+                    .map(|u| config.is_unlimited_user(u))
                     .unwrap_or(false);
 
                 // Check termination state
@@ -359,14 +395,14 @@ impl SessionManager {
                 }
 
                 // Check idle timeout
-                if session.is_idle(self.config.idle_timeout) {
+                if session.is_idle(config.idle_timeout) {
                     expired.push(*id);
                     continue;
                 }
 
                 // Check absolute timeout (only for non-unlimited users)
                 if !is_unlimited
-                    && let Some(absolute_timeout) = self.config.absolute_timeout
+                    && let Some(absolute_timeout) = config.absolute_timeout
                     && session.is_absolute_timeout(absolute_timeout)
                 {
                     expired.push(*id);
@@ -393,7 +429,7 @@ impl SessionManager {
     pub fn spawn_cleanup_task(&self) -> tokio::task::JoinHandle<()> {
         let manager = self.clone();
         tokio::spawn(async move {
-            let mut interval = tokio::time::interval(manager.config.cleanup_interval);
+            let mut interval = tokio::time::interval(manager.config().cleanup_interval);
 
             loop {
                 interval.tick().await;
@@ -420,6 +456,23 @@ mod tests {
         assert_eq!(session.remote_addr(), "192.168.1.1:1234");
     }
 
+    #[tokio::test]
+    async fn test_sessions_get_lowest_free_node() {
+        let manager = SessionManager::new(SessionConfig::default());
+        let first = manager.create_session("10.0.0.1:1000").await.unwrap();
+        let second = manager.create_session("10.0.0.2:1000").await.unwrap();
+        let third = manager.create_session("10.0.0.3:1000").await.unwrap();
+
+        assert_eq!(manager.get_session(first).await.unwrap().node(), 1);
+        assert_eq!(manager.get_session(second).await.unwrap().node(), 2);
+        assert_eq!(manager.get_session(third).await.unwrap().node(), 3);
+
+        // A node is reused once its caller leaves
+        manager.terminate_session(second).await.unwrap();
+        let fourth = manager.create_session("10.0.0.4:1000").await.unwrap();
+        assert_eq!(manager.get_session(fourth).await.unwrap().node(), 2);
+    }
+
     #[tokio::test]
     async fn test_authenticate_session() {
         let manager = SessionManager::new(SessionConfig::default());
@@ -553,6 +606,20 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_update_config_applies_to_new_sessions() {
+        let manager = SessionManager::new(SessionConfig::default().with_max_total_sessions(1));
+        let first = manager.create_session("192.168.1.1:1234").await.unwrap();
+        assert!(manager.create_session("192.168.1.2:1234").await.is_err());
+
+        manager.update_config(SessionConfig::default().with_max_total_sessions(2));
+        assert_eq!(manager.config().max_total_sessions, 2);
+
+        // Existing sessions are kept and the new limit applies immediately
+        assert!(manager.get_session(first).await.is_ok());
+        assert!(manager.create_session("192.168.1.2:1234").await.is_ok());
+    }
+
     #[tokio::test]
     async fn test_list_all_sessions() {
         let manager = SessionManager::new(SessionConfig::default());
@@ -0,0 +1,204 @@
+//! In-memory connection for tests and local sessions
+
+use crate::connection::{Connection, ConnectionError, ConnectionType};
+use async_trait::async_trait;
+use std::collections::VecDeque;
+
+/// A connection backed by in-memory buffers
+///
+/// Input is scripted up front (or pushed later with [`push_input`]) and
+/// everything the BBS sends is captured for inspection. Reads fail with
+/// [`ConnectionError::Closed`] once the scripted input is exhausted, which
+/// mirrors a caller hanging up.
+///
+/// [`push_input`]: MemoryConnection::push_input
+///
+/// # Example
+///
+/// ```
+/// use impulse_session::{Connection, MemoryConnection};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), impulse_session::ConnectionError> {
+/// let mut conn = MemoryConnection::new("sysop\r");
+/// assert_eq!(conn.read_line().await?, "sysop");
+/// assert_eq!(conn.output_text(), "sysop");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MemoryConnection {
+    /// Remote address reported to the BBS
+    remote_addr: String,
+    /// Bytes not yet read by the BBS
+    input: VecDeque<u8>,
+    /// Bytes sent by the BBS
+    output: Vec<u8>,
+    /// Reported terminal dimensions
+    terminal_size: (u16, u16),
+    /// Whether the connection is still open
+    connected: bool,
+}
+
+impl MemoryConnection {
+    /// Create a connection that will deliver `input` to the BBS
+    pub fn new(input: impl AsRef<[u8]>) -> Self {
+        Self {
+            remote_addr: "127.0.0.1:0".to_string(),
+            input: input.as_ref().iter().copied().collect(),
+            output: Vec::new(),
+            terminal_size: (80, 24),
+            connected: true,
+        }
+    }
+
+    /// Set the reported remote address
+    pub fn with_remote_addr(mut self, addr: impl Into<String>) -> Self {
+        self.remote_addr = addr.into();
+        self
+    }
+
+    /// Set the reported terminal dimensions
+    pub fn with_terminal_size(mut self, columns: u16, rows: u16) -> Self {
+        self.terminal_size = (columns, rows);
+        self
+    }
+
+    /// Queue more input for the BBS to read
+    pub fn push_input(&mut self, data: impl AsRef<[u8]>) {
+        self.input.extend(data.as_ref());
+    }
+
+    /// Get everything sent by the BBS so far
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// Get everything sent by the BBS so far as (lossy) UTF-8 text
+    pub fn output_text(&self) -> String {
+        String::from_utf8_lossy(&self.output).into_owned()
+    }
+
+    /// Take and clear the captured output
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+
+    fn ensure_connected(&self) -> Result<(), ConnectionError> {
+        if self.connected {
+            Ok(())
+        } else {
+            Err(ConnectionError::Closed)
+        }
+    }
+}
+
+#[async_trait]
+impl Connection for MemoryConnection {
+    fn connection_type(&self) -> ConnectionType {
+        ConnectionType::Local
+    }
+
+    fn remote_addr(&self) -> String {
+        self.remote_addr.clone()
+    }
+
+    async fn send_text(&mut self, data: &str) -> Result<(), ConnectionError> {
+        self.send_bytes(data.as_bytes()).await
+    }
+
+    async fn send_bytes(&mut self, data: &[u8]) -> Result<(), ConnectionError> {
+        self.ensure_connected()?;
+        self.output.extend_from_slice(data);
+        Ok(())
+    }
+
+    async fn recv(&mut self) -> Result<Option<Vec<u8>>, ConnectionError> {
+        if !self.connected || self.input.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(self.input.drain(..).collect()))
+    }
+
+    async fn close(&mut self) -> Result<(), ConnectionError> {
+        self.connected = false;
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    async fn read_char(&mut self) -> Result<char, ConnectionError> {
+        self.ensure_connected()?;
+        self.input
+            .pop_front()
+            .map(char::from)
+            .ok_or(ConnectionError::Closed)
+    }
+
+    fn terminal_size(&self) -> (u16, u16) {
+        self.terminal_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_char_and_exhaustion() {
+        let mut conn = MemoryConnection::new("ab");
+        assert_eq!(conn.read_char().await.unwrap(), 'a');
+        assert_eq!(conn.read_char().await.unwrap(), 'b');
+        assert!(matches!(
+            conn.read_char().await,
+            Err(ConnectionError::Closed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_line_echoes_and_handles_backspace() {
+        let mut conn = MemoryConnection::new("\r\nhellp\x08o\r\nnext\r");
+        assert_eq!(conn.read_line().await.unwrap(), "hello");
+        assert_eq!(conn.output(), b"hellp\x08 \x08o");
+
+        conn.take_output();
+        assert_eq!(conn.read_line().await.unwrap(), "next");
+        assert_eq!(conn.output_text(), "next");
+    }
+
+    #[tokio::test]
+    async fn test_read_password_masks_input() {
+        let mut conn = MemoryConnection::new("s3cret\r");
+        assert_eq!(conn.read_password(true).await.unwrap(), "s3cret");
+        assert_eq!(conn.output_text(), "******\r\n");
+
+        let mut conn = MemoryConnection::new("s3cret\r");
+        assert_eq!(conn.read_password(false).await.unwrap(), "s3cret");
+        assert_eq!(conn.output_text(), "\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_send_and_close() {
+        let mut conn = MemoryConnection::new("").with_terminal_size(132, 50);
+        assert_eq!(conn.terminal_size(), (132, 50));
+        assert_eq!(conn.connection_type(), ConnectionType::Local);
+
+        conn.send_text("Welcome").await.unwrap();
+        assert_eq!(conn.output_text(), "Welcome");
+
+        conn.close().await.unwrap();
+        assert!(!conn.is_connected());
+        assert!(conn.send_text("again").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_push_input_and_recv() {
+        let mut conn = MemoryConnection::new("");
+        assert!(conn.recv().await.unwrap().is_none());
+
+        conn.push_input("Y");
+        assert_eq!(conn.recv().await.unwrap(), Some(b"Y".to_vec()));
+    }
+}
@@ -85,6 +85,10 @@ pub struct Session {
     user_id: Option<u32>,
     /// Remote address
     remote_addr: String,
+    /// Node number (0 until assigned)
+    node: u16,
+    /// Seconds added to (or taken from) the caller's time this call
+    time_credit: i64,
     /// Session state
     state: SessionState,
     /// When the session was created
@@ -112,6 +116,8 @@ impl Session {
             username: None,
             user_id: None,
             remote_addr,
+            node: 0,
+            time_credit: 0,
             state: SessionState::Connected,
             created_at: now,
             last_activity: now,
@@ -143,6 +149,23 @@ impl Session {
         &self.remote_addr
     }
 
+    /// Get the node number
+    ///
+    /// The [`SessionManager`](crate::SessionManager) gives every session the
+    /// lowest node number not already in use, starting at 1; a session
+    /// created outside the manager is on node 0.
+    pub fn node(&self) -> u16 {
+        self.node
+    }
+
+    /// Seconds of extra time the caller has been given this call
+    ///
+    /// Negative when time has been taken away, for example deposited in a
+    /// door's time bank.
+    pub fn time_credit(&self) -> i64 {
+        self.time_credit
+    }
+
     /// Get current state
     pub fn state(&self) -> SessionState {
         self.state
@@ -182,6 +205,16 @@ impl Session {
         self.idle_time() >= idle_timeout
     }
 
+    /// Put the session on a node
+    pub fn set_node(&mut self, node: u16) {
+        self.node = node;
+    }
+
+    /// Give the caller extra time this call, or take it away
+    pub fn add_time_credit(&mut self, seconds: i64) {
+        self.time_credit = self.time_credit.saturating_add(seconds);
+    }
+
     /// Set session state
     pub fn set_state(&mut self, state: SessionState) {
         self.state = state;
@@ -355,6 +388,16 @@ mod tests {
         assert!(!session.should_send_idle_warning(idle_timeout, warning_before));
     }
 
+    #[test]
+    fn test_time_credit() {
+        let mut session = Session::new("192.168.1.1:1234".to_string());
+        assert_eq!(session.time_credit(), 0);
+
+        session.add_time_credit(600);
+        session.add_time_credit(-900);
+        assert_eq!(session.time_credit(), -300);
+    }
+
     #[test]
     fn test_absolute_timeout_check() {
         let session = Session::new("192.168.1.1:1234".to_string());
@@ -4,6 +4,7 @@ use crate::connection::{Connection, ConnectionError, ConnectionType};
 use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::sync::Arc;
 use tokio::net::TcpStream;
 use tokio::sync::Mutex;
@@ -18,6 +19,8 @@ pub struct WebSocketConnection {
     remote_addr: String,
     /// Whether the connection is still active
     connected: Arc<Mutex<bool>>,
+    /// Input received but not yet consumed by character reads
+    pending: VecDeque<u8>,
 }
 
 impl WebSocketConnection {
@@ -31,6 +34,7 @@ impl WebSocketConnection {
             stream: Arc::new(Mutex::new(ws_stream)),
             remote_addr,
             connected: Arc::new(Mutex::new(true)),
+            pending: VecDeque::new(),
         })
     }
 
@@ -166,6 +170,19 @@ impl Connection for WebSocketConnection {
     fn is_connected(&self) -> bool {
         *self.connected.blocking_lock()
     }
+
+    async fn read_char(&mut self) -> Result<char, ConnectionError> {
+        loop {
+            if let Some(byte) = self.pending.pop_front() {
+                return Ok(byte as char);
+            }
+
+            match self.recv().await? {
+                Some(data) => self.pending.extend(data),
+                None => return Err(ConnectionError::Closed),
+            }
+        }
+    }
 }
 
 /// WebSocket message protocol for BBS communication
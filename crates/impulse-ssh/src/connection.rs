@@ -0,0 +1,283 @@
+//! SSH connection handling
+
+use crate::error::{Result, SshError};
+use async_trait::async_trait;
+use impulse_session::{Connection, ConnectionError, ConnectionType};
+use russh::server::Msg;
+use russh::{Channel, ChannelMsg};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use tokio::sync::watch;
+
+/// Maximum buffer size for incoming data (64KB)
+const MAX_BUFFER_SIZE: usize = 65536;
+
+/// An interactive shell session over an authenticated SSH channel
+///
+/// Exposes the same line/character oriented API as a telnet connection so the
+/// BBS menu system can drive either transport. The client's PTY puts its
+/// terminal in raw mode, so input echo is performed by the server.
+pub struct SshConnection {
+    /// Session channel carrying the interactive shell
+    channel: Channel<Msg>,
+    /// Remote address
+    peer_addr: SocketAddr,
+    /// Username the client authenticated as
+    username: String,
+    /// Terminal type requested by the client's PTY (e.g. `xterm-256color`)
+    terminal_type: String,
+    /// Terminal dimensions, updated by PTY and window-change requests
+    window_size: watch::Receiver<(u16, u16)>,
+    /// Bytes received from the client but not yet consumed
+    pending: VecDeque<u8>,
+    /// Whether the channel is still open
+    connected: bool,
+}
+
+impl SshConnection {
+    /// Create a new connection from an accepted session channel
+    pub(crate) fn new(
+        channel: Channel<Msg>,
+        peer_addr: SocketAddr,
+        username: String,
+        terminal_type: String,
+        window_size: watch::Receiver<(u16, u16)>,
+    ) -> Self {
+        Self {
+            channel,
+            peer_addr,
+            username,
+            terminal_type,
+            window_size,
+            pending: VecDeque::new(),
+            connected: true,
+        }
+    }
+
+    /// Get the remote peer address
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+
+    /// Get the username the client authenticated as
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Get the terminal type requested by the client
+    pub fn terminal_type(&self) -> &str {
+        &self.terminal_type
+    }
+
+    /// Get terminal dimensions
+    ///
+    /// Reflects the most recent PTY or window-change request from the client,
+    /// defaulting to 80x24 when the client did not request a PTY.
+    pub fn terminal_size(&self) -> (u16, u16) {
+        *self.window_size.borrow()
+    }
+
+    /// Send raw bytes to the client
+    pub async fn send_raw(&mut self, data: &[u8]) -> Result<()> {
+        self.channel.data_bytes(data.to_vec()).await?;
+        Ok(())
+    }
+
+    /// Send text to the client
+    pub async fn send_text(&mut self, text: &str) -> Result<()> {
+        self.send_raw(text.as_bytes()).await
+    }
+
+    /// Send a line of text with CRLF
+    pub async fn send_line(&mut self, text: &str) -> Result<()> {
+        self.send_text(text).await?;
+        self.send_text("\r\n").await
+    }
+
+    /// Read a line of text from the client (blocking until CR or LF)
+    pub async fn read_line(&mut self) -> Result<String> {
+        let mut line = Vec::new();
+        let mut started = false;
+
+        loop {
+            let byte = self.next_byte().await?;
+
+            // Skip leading CR/LF characters (handles leftover LF from previous CRLF)
+            if !started && (byte == b'\n' || byte == b'\r') {
+                continue;
+            }
+
+            if byte == b'\n' || byte == b'\r' {
+                break;
+            }
+
+            started = true;
+
+            if (32..127).contains(&byte) || byte == b'\t' {
+                line.push(byte);
+                self.send_raw(&[byte]).await?;
+            } else if (byte == 8 || byte == 127) && !line.is_empty() {
+                line.pop();
+                self.send_raw(b"\x08 \x08").await?;
+            }
+
+            if line.len() > MAX_BUFFER_SIZE {
+                return Err(SshError::BufferOverflow {
+                    max: MAX_BUFFER_SIZE,
+                });
+            }
+        }
+
+        String::from_utf8(line).map_err(SshError::InvalidUtf8)
+    }
+
+    /// Read a password from the client without echoing
+    ///
+    /// # Arguments
+    ///
+    /// * `show_asterisks` - If true, displays '*' for each character typed
+    pub async fn read_password(&mut self, show_asterisks: bool) -> Result<String> {
+        let mut password = Vec::new();
+        let mut started = false;
+
+        loop {
+            let byte = self.next_byte().await?;
+
+            if !started && (byte == b'\n' || byte == b'\r') {
+                continue;
+            }
+
+            if byte == b'\n' || byte == b'\r' {
+                self.send_raw(b"\r\n").await?;
+                break;
+            }
+
+            started = true;
+
+            if (32..127).contains(&byte) || byte == b'\t' {
+                password.push(byte);
+                if show_asterisks {
+                    self.send_raw(b"*").await?;
+                }
+            } else if (byte == 8 || byte == 127) && !password.is_empty() {
+                password.pop();
+                if show_asterisks {
+                    self.send_raw(b"\x08 \x08").await?;
+                }
+            }
+
+            if password.len() > MAX_BUFFER_SIZE {
+                return Err(SshError::BufferOverflow {
+                    max: MAX_BUFFER_SIZE,
+                });
+            }
+        }
+
+        String::from_utf8(password).map_err(SshError::InvalidUtf8)
+    }
+
+    /// Read a single character from the client
+    pub async fn read_char(&mut self) -> Result<char> {
+        let byte = self.next_byte().await?;
+        Ok(byte as char)
+    }
+
+    /// Close the connection gracefully
+    pub async fn close(self) -> Result<()> {
+        self.channel.eof().await.ok();
+        self.channel.close().await?;
+        Ok(())
+    }
+
+    /// Take the next input byte, waiting for channel data if none is buffered
+    async fn next_byte(&mut self) -> Result<u8> {
+        loop {
+            if let Some(byte) = self.pending.pop_front() {
+                return Ok(byte);
+            }
+
+            match self.channel.wait().await {
+                Some(ChannelMsg::Data { data }) => self.pending.extend(data.iter()),
+                Some(ChannelMsg::Eof) | Some(ChannelMsg::Close) | None => {
+                    self.connected = false;
+                    return Err(SshError::ConnectionClosed);
+                }
+                // PTY, window-change and shell requests are handled by the
+                // session handler; anything else is not meaningful here.
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+impl From<SshError> for ConnectionError {
+    fn from(err: SshError) -> Self {
+        match err {
+            SshError::Io(e) => ConnectionError::Io(e),
+            SshError::ConnectionClosed => ConnectionError::Closed,
+            SshError::InvalidUtf8(e) => ConnectionError::Encoding(e.to_string()),
+            other => ConnectionError::Protocol(other.to_string()),
+        }
+    }
+}
+
+#[async_trait]
+impl Connection for SshConnection {
+    fn connection_type(&self) -> ConnectionType {
+        ConnectionType::Ssh
+    }
+
+    fn remote_addr(&self) -> String {
+        self.peer_addr.to_string()
+    }
+
+    async fn send_text(&mut self, data: &str) -> std::result::Result<(), ConnectionError> {
+        Ok(self.send_raw(data.as_bytes()).await?)
+    }
+
+    async fn send_bytes(&mut self, data: &[u8]) -> std::result::Result<(), ConnectionError> {
+        Ok(self.send_raw(data).await?)
+    }
+
+    async fn recv(&mut self) -> std::result::Result<Option<Vec<u8>>, ConnectionError> {
+        match self.next_byte().await {
+            Ok(byte) => {
+                let mut data = vec![byte];
+                data.extend(self.pending.drain(..));
+                Ok(Some(data))
+            }
+            Err(SshError::ConnectionClosed) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn close(&mut self) -> std::result::Result<(), ConnectionError> {
+        self.connected = false;
+        self.channel.eof().await.ok();
+        self.channel.close().await.map_err(SshError::from)?;
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    async fn read_char(&mut self) -> std::result::Result<char, ConnectionError> {
+        Ok(SshConnection::read_char(self).await?)
+    }
+
+    async fn read_line(&mut self) -> std::result::Result<String, ConnectionError> {
+        Ok(SshConnection::read_line(self).await?)
+    }
+
+    async fn read_password(
+        &mut self,
+        show_asterisks: bool,
+    ) -> std::result::Result<String, ConnectionError> {
+        Ok(SshConnection::read_password(self, show_asterisks).await?)
+    }
+
+    fn terminal_size(&self) -> (u16, u16) {
+        *self.window_size.borrow()
+    }
+}
@@ -0,0 +1,39 @@
+//! Error types for SSH server operations
+
+use std::io;
+use thiserror::Error;
+
+/// Result type alias for SSH operations
+pub type Result<T> = std::result::Result<T, SshError>;
+
+/// Errors that can occur during SSH operations
+#[derive(Error, Debug)]
+pub enum SshError {
+    /// I/O error occurred
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    /// SSH protocol error reported by the transport
+    #[error("SSH protocol error: {0}")]
+    Protocol(#[from] russh::Error),
+
+    /// Host key could not be loaded, generated, or saved
+    #[error("Host key error: {0}")]
+    HostKey(String),
+
+    /// Connection closed by remote host
+    #[error("Connection closed by remote host")]
+    ConnectionClosed,
+
+    /// The server stopped accepting connections
+    #[error("SSH server is no longer accepting connections")]
+    ServerClosed,
+
+    /// Invalid UTF-8 data received
+    #[error("Invalid UTF-8 data")]
+    InvalidUtf8(#[from] std::string::FromUtf8Error),
+
+    /// Buffer overflow
+    #[error("Buffer overflow: maximum size {max} exceeded")]
+    BufferOverflow { max: usize },
+}
@@ -0,0 +1,99 @@
+//! Host key generation and persistence
+//!
+//! The server identifies itself to clients with a long-lived Ed25519 host key.
+//! The key is generated on first start and written to disk in OpenSSH format so
+//! that callers do not see a "host key changed" warning after every restart.
+
+use crate::error::{Result, SshError};
+use russh::keys::PrivateKey;
+use russh::keys::ssh_key::private::Ed25519Keypair;
+use russh::keys::ssh_key::{HashAlg, LineEnding};
+use std::path::Path;
+use tracing::info;
+
+/// Load the host key at `path`, generating and saving a new one if it does not exist
+///
+/// Parent directories are created as needed. Newly written keys are readable
+/// only by the owner on Unix systems.
+///
+/// # Example
+///
+/// ```no_run
+/// use impulse_ssh::load_or_generate_host_key;
+///
+/// let key = load_or_generate_host_key("data/ssh_host_ed25519_key").unwrap();
+/// ```
+pub fn load_or_generate_host_key(path: impl AsRef<Path>) -> Result<PrivateKey> {
+    let path = path.as_ref();
+
+    if path.exists() {
+        let key = PrivateKey::read_openssh_file(path)
+            .map_err(|e| SshError::HostKey(format!("Failed to read host key {:?}: {}", path, e)))?;
+        info!(
+            path = ?path,
+            fingerprint = %key.public_key().fingerprint(HashAlg::Sha256),
+            "Loaded SSH host key"
+        );
+        return Ok(key);
+    }
+
+    let key = generate_host_key()?;
+
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+    key.write_openssh_file(path, LineEnding::LF)
+        .map_err(|e| SshError::HostKey(format!("Failed to write host key {:?}: {}", path, e)))?;
+
+    info!(
+        path = ?path,
+        fingerprint = %key.public_key().fingerprint(HashAlg::Sha256),
+        "Generated new SSH host key"
+    );
+    Ok(key)
+}
+
+/// Generate a fresh Ed25519 host key
+pub fn generate_host_key() -> Result<PrivateKey> {
+    let seed: [u8; 32] = rand::random();
+    Ok(PrivateKey::from(Ed25519Keypair::from_seed(&seed)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_key_is_generated_and_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys").join("ssh_host_ed25519_key");
+
+        let first = load_or_generate_host_key(&path).unwrap();
+        assert!(path.exists());
+
+        let second = load_or_generate_host_key(&path).unwrap();
+        assert_eq!(
+            first.public_key().fingerprint(HashAlg::Sha256),
+            second.public_key().fingerprint(HashAlg::Sha256)
+        );
+    }
+
+    #[test]
+    fn test_generated_keys_are_unique() {
+        let a = generate_host_key().unwrap();
+        let b = generate_host_key().unwrap();
+        assert_ne!(a.public_key(), b.public_key());
+    }
+
+    #[test]
+    fn test_invalid_host_key_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken_key");
+        std::fs::write(&path, "not a key").unwrap();
+
+        let result = load_or_generate_host_key(&path);
+        assert!(matches!(result, Err(SshError::HostKey(_))));
+    }
+}
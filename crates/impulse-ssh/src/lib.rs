@@ -1,14 +1,57 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+//! SSH-2 server implementation for Impulse BBS
+//!
+//! This crate provides an SSH server that gives callers an encrypted
+//! alternative to telnet. Clients authenticate with their BBS password and
+//! receive an interactive shell session exposing the same line/character API
+//! as a telnet connection.
+//!
+//! # Features
+//!
+//! - SSH-2 transport via `russh`
+//! - Persistent Ed25519 host key, generated on first start
+//! - Password authentication through a pluggable [`PasswordAuthenticator`]
+//! - PTY and window-change requests mapped to terminal dimensions
+//! - Async/await based on Tokio
+//! - Implements the transport-agnostic `impulse_session::Connection` trait
+//!
+//! # Example
+//!
+//! ```no_run
+//! use impulse_ssh::{PasswordAuthenticator, Result, SshServer, SshServerConfig};
+//! use std::net::SocketAddr;
+//! use std::sync::Arc;
+//!
+//! struct AllowGuest;
+//!
+//! #[async_trait::async_trait]
+//! impl PasswordAuthenticator for AllowGuest {
+//!     type Identity = String;
+//!
+//!     async fn authenticate(&self, user: &str, _pass: &str, _: SocketAddr) -> Option<String> {
+//!         (user == "guest").then(|| user.to_string())
+//!     }
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     let config = SshServerConfig::new("data/ssh_host_ed25519_key");
+//!     let server = SshServer::bind("0.0.0.0:2222", config, Arc::new(AllowGuest)).await?;
+//!
+//!     loop {
+//!         let (mut connection, _user) = server.accept().await?;
+//!         tokio::spawn(async move {
+//!             // Handle connection
+//!         });
+//!     }
+//! }
+//! ```
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+mod connection;
+mod error;
+mod host_key;
+mod server;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use connection::SshConnection;
+pub use error::{Result, SshError};
+pub use host_key::{generate_host_key, load_or_generate_host_key};
+pub use server::{PasswordAuthenticator, SshServer, SshServerConfig};
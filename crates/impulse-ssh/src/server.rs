@@ -0,0 +1,577 @@
+//! SSH server implementation
+
+use crate::connection::SshConnection;
+use crate::error::{Result, SshError};
+use crate::host_key::load_or_generate_host_key;
+use async_trait::async_trait;
+use russh::server::{Auth, ChannelOpenHandle, Msg, Session};
+use russh::{Channel, ChannelId, MethodKind, MethodSet};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{Mutex, mpsc, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Number of authenticated shells that may wait in the accept queue
+const ACCEPT_QUEUE_SIZE: usize = 32;
+
+/// Sent to a caller turned away because the accept queue is full
+const BUSY_MESSAGE: &[u8] = b"The system is busy. Please try again later.\r\n";
+
+/// Default terminal dimensions used until the client requests a PTY
+const DEFAULT_TERMINAL_SIZE: (u16, u16) = (80, 24);
+
+/// Verifies SSH password logins
+///
+/// The server calls this for every password attempt. Returning `Some` accepts
+/// the login and hands the identity back to the caller of
+/// [`SshServer::accept`] alongside the connection, so credentials are only
+/// checked once per session.
+#[async_trait]
+pub trait PasswordAuthenticator: Send + Sync + 'static {
+    /// Identity produced by a successful login (e.g. user record and session token)
+    type Identity: Send + 'static;
+
+    /// Check a username/password pair from the given peer
+    async fn authenticate(
+        &self,
+        username: &str,
+        password: &str,
+        peer_addr: SocketAddr,
+    ) -> Option<Self::Identity>;
+}
+
+/// SSH server configuration
+#[derive(Debug, Clone)]
+pub struct SshServerConfig {
+    /// Path of the persistent Ed25519 host key (generated if missing)
+    pub host_key_path: PathBuf,
+    /// Disconnect clients that send nothing for this long
+    pub inactivity_timeout: Option<Duration>,
+    /// Constant delay applied to rejected authentication attempts
+    pub auth_rejection_time: Duration,
+    /// Maximum authentication attempts per connection
+    pub max_auth_attempts: usize,
+    /// Shells that may wait to be accepted before callers are turned away
+    pub accept_queue_size: usize,
+}
+
+impl SshServerConfig {
+    /// Create a configuration using the given host key path
+    pub fn new(host_key_path: impl Into<PathBuf>) -> Self {
+        Self {
+            host_key_path: host_key_path.into(),
+            inactivity_timeout: Some(Duration::from_secs(3600)),
+            auth_rejection_time: Duration::from_secs(1),
+            max_auth_attempts: 3,
+            accept_queue_size: ACCEPT_QUEUE_SIZE,
+        }
+    }
+
+    /// Set the inactivity timeout
+    pub fn with_inactivity_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.inactivity_timeout = timeout;
+        self
+    }
+
+    /// Set the delay applied to rejected authentication attempts
+    pub fn with_auth_rejection_time(mut self, delay: Duration) -> Self {
+        self.auth_rejection_time = delay;
+        self
+    }
+
+    /// Set the maximum authentication attempts per connection
+    pub fn with_max_auth_attempts(mut self, attempts: usize) -> Self {
+        self.max_auth_attempts = attempts;
+        self
+    }
+
+    /// Set how many shells may wait to be accepted
+    pub fn with_accept_queue_size(mut self, size: usize) -> Self {
+        self.accept_queue_size = size;
+        self
+    }
+}
+
+/// SSH server that accepts password-authenticated interactive shells
+///
+/// Protocol handling runs in background tasks; [`accept`](Self::accept) yields
+/// a connection once a client has authenticated and requested a shell.
+pub struct SshServer<I> {
+    /// Local bind address
+    local_addr: SocketAddr,
+    /// Authenticated shells waiting to be accepted
+    incoming: Mutex<mpsc::Receiver<(SshConnection, I)>>,
+    /// Background task accepting TCP connections
+    accept_task: JoinHandle<()>,
+}
+
+impl<I: Send + 'static> SshServer<I> {
+    /// Bind to a local address
+    ///
+    /// Loads the host key from `config.host_key_path`, generating it on first
+    /// start.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use impulse_ssh::{PasswordAuthenticator, Result, SshServer, SshServerConfig};
+    /// use std::net::SocketAddr;
+    /// use std::sync::Arc;
+    ///
+    /// struct Sysop;
+    ///
+    /// #[async_trait::async_trait]
+    /// impl PasswordAuthenticator for Sysop {
+    ///     type Identity = String;
+    ///
+    ///     async fn authenticate(&self, user: &str, pass: &str, _: SocketAddr) -> Option<String> {
+    ///         (user == "sysop" && pass == "secret").then(|| user.to_string())
+    ///     }
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let config = SshServerConfig::new("data/ssh_host_ed25519_key");
+    ///     let server = SshServer::bind("0.0.0.0:2222", config, Arc::new(Sysop)).await?;
+    ///
+    ///     loop {
+    ///         let (mut conn, user) = server.accept().await?;
+    ///         tokio::spawn(async move {
+    ///             conn.send_line(&format!("Welcome, {}!", user)).await.ok();
+    ///         });
+    ///     }
+    /// }
+    /// ```
+    pub async fn bind<A>(addr: &str, config: SshServerConfig, authenticator: Arc<A>) -> Result<Self>
+    where
+        A: PasswordAuthenticator<Identity = I>,
+    {
+        let host_key = load_or_generate_host_key(&config.host_key_path)?;
+
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+
+        let russh_config = Arc::new(russh::server::Config {
+            methods: MethodSet::from(&[MethodKind::Password][..]),
+            auth_rejection_time: config.auth_rejection_time,
+            auth_rejection_time_initial: Some(Duration::ZERO),
+            keys: vec![host_key],
+            max_auth_attempts: config.max_auth_attempts,
+            inactivity_timeout: config.inactivity_timeout,
+            nodelay: true,
+            ..Default::default()
+        });
+
+        let (sender, receiver) = mpsc::channel(config.accept_queue_size.max(1));
+        let accept_task = tokio::spawn(accept_loop(listener, russh_config, authenticator, sender));
+
+        Ok(Self {
+            local_addr,
+            incoming: Mutex::new(receiver),
+            accept_task,
+        })
+    }
+
+    /// Get the local address the server is bound to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Accept the next authenticated shell session
+    ///
+    /// Returns the connection together with the identity produced by the
+    /// [`PasswordAuthenticator`] when the client logged in.
+    pub async fn accept(&self) -> Result<(SshConnection, I)> {
+        self.incoming
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or(SshError::ServerClosed)
+    }
+}
+
+impl<I> Drop for SshServer<I> {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
+
+/// Accept TCP connections and run the SSH protocol for each one
+async fn accept_loop<A: PasswordAuthenticator>(
+    listener: TcpListener,
+    config: Arc<russh::server::Config>,
+    authenticator: Arc<A>,
+    sender: mpsc::Sender<(SshConnection, A::Identity)>,
+) {
+    loop {
+        let (stream, peer_addr) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!(error = %e, "Failed to accept SSH connection");
+                continue;
+            }
+        };
+
+        if sender.is_closed() {
+            break;
+        }
+
+        let handler = SessionHandler::new(authenticator.clone(), peer_addr, sender.clone());
+        tokio::spawn(run_session(config.clone(), stream, peer_addr, handler));
+    }
+}
+
+/// Drive a single SSH connection until the client disconnects
+async fn run_session<A: PasswordAuthenticator>(
+    config: Arc<russh::server::Config>,
+    stream: TcpStream,
+    peer_addr: SocketAddr,
+    handler: SessionHandler<A>,
+) {
+    debug!(peer = %peer_addr, "SSH connection opened");
+    match russh::server::run_stream(config, stream, handler).await {
+        Ok(session) => {
+            if let Err(e) = session.await {
+                debug!(peer = %peer_addr, error = %e, "SSH session ended with error");
+            }
+        }
+        Err(e) => {
+            debug!(peer = %peer_addr, error = %e, "SSH handshake failed");
+        }
+    }
+    debug!(peer = %peer_addr, "SSH connection closed");
+}
+
+/// Per-connection protocol handler
+struct SessionHandler<A: PasswordAuthenticator> {
+    authenticator: Arc<A>,
+    peer_addr: SocketAddr,
+    /// Authenticated username
+    username: Option<String>,
+    /// Identity from a successful login, handed over with the shell
+    identity: Option<A::Identity>,
+    /// Session channels opened but not yet turned into a shell
+    channels: HashMap<ChannelId, Channel<Msg>>,
+    /// Terminal type from the PTY request
+    terminal_type: String,
+    /// Current terminal dimensions
+    window_size: watch::Sender<(u16, u16)>,
+    /// Queue feeding [`SshServer::accept`]
+    incoming: mpsc::Sender<(SshConnection, A::Identity)>,
+}
+
+impl<A: PasswordAuthenticator> SessionHandler<A> {
+    fn new(
+        authenticator: Arc<A>,
+        peer_addr: SocketAddr,
+        incoming: mpsc::Sender<(SshConnection, A::Identity)>,
+    ) -> Self {
+        Self {
+            authenticator,
+            peer_addr,
+            username: None,
+            identity: None,
+            channels: HashMap::new(),
+            terminal_type: "ansi".to_string(),
+            window_size: watch::channel(DEFAULT_TERMINAL_SIZE).0,
+            incoming,
+        }
+    }
+
+    fn set_window_size(&self, cols: u32, rows: u32) {
+        if cols == 0 || rows == 0 {
+            return;
+        }
+        let size = (
+            u16::try_from(cols).unwrap_or(u16::MAX),
+            u16::try_from(rows).unwrap_or(u16::MAX),
+        );
+        self.window_size.send_replace(size);
+    }
+}
+
+impl<A: PasswordAuthenticator> russh::server::Handler for SessionHandler<A> {
+    type Error = russh::Error;
+
+    async fn auth_password(
+        &mut self,
+        user: &str,
+        password: &str,
+    ) -> std::result::Result<Auth, Self::Error> {
+        match self
+            .authenticator
+            .authenticate(user, password, self.peer_addr)
+            .await
+        {
+            Some(identity) => {
+                info!(peer = %self.peer_addr, username = %user, "SSH password authentication accepted");
+                self.username = Some(user.to_string());
+                self.identity = Some(identity);
+                Ok(Auth::Accept)
+            }
+            None => {
+                warn!(peer = %self.peer_addr, username = %user, "SSH password authentication rejected");
+                Ok(Auth::reject())
+            }
+        }
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        channel: Channel<Msg>,
+        reply: ChannelOpenHandle,
+        _session: &mut Session,
+    ) -> std::result::Result<(), Self::Error> {
+        self.channels.insert(channel.id(), channel);
+        reply.accept().await;
+        Ok(())
+    }
+
+    async fn pty_request(
+        &mut self,
+        channel: ChannelId,
+        term: &str,
+        col_width: u32,
+        row_height: u32,
+        _pix_width: u32,
+        _pix_height: u32,
+        _modes: &[(russh::Pty, u32)],
+        session: &mut Session,
+    ) -> std::result::Result<(), Self::Error> {
+        self.terminal_type = term.to_string();
+        self.set_window_size(col_width, row_height);
+        session.channel_success(channel)
+    }
+
+    async fn window_change_request(
+        &mut self,
+        _channel: ChannelId,
+        col_width: u32,
+        row_height: u32,
+        _pix_width: u32,
+        _pix_height: u32,
+        _session: &mut Session,
+    ) -> std::result::Result<(), Self::Error> {
+        self.set_window_size(col_width, row_height);
+        Ok(())
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> std::result::Result<(), Self::Error> {
+        let (Some(shell), Some(identity), Some(username)) = (
+            self.channels.remove(&channel),
+            self.identity.take(),
+            self.username.clone(),
+        ) else {
+            // Only one interactive shell per authenticated connection
+            return session.channel_failure(channel);
+        };
+
+        let connection = SshConnection::new(
+            shell,
+            self.peer_addr,
+            username,
+            self.terminal_type.clone(),
+            self.window_size.subscribe(),
+        );
+
+        // Waiting for room here would stall this connection's protocol
+        // handling, keepalives included, so a full queue turns the caller away
+        session.channel_success(channel)?;
+        match self.incoming.try_send((connection, identity)) {
+            Ok(()) => Ok(()),
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                warn!(peer = %self.peer_addr, "SSH accept queue full, turning caller away");
+                session.data(channel, BUSY_MESSAGE)?;
+                session.eof(channel)?;
+                session.close(channel)
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => Err(russh::Error::Disconnect),
+        }
+    }
+
+    async fn exec_request(
+        &mut self,
+        channel: ChannelId,
+        _data: &[u8],
+        session: &mut Session,
+    ) -> std::result::Result<(), Self::Error> {
+        session.channel_failure(channel)
+    }
+
+    async fn subsystem_request(
+        &mut self,
+        channel: ChannelId,
+        _name: &str,
+        session: &mut Session,
+    ) -> std::result::Result<(), Self::Error> {
+        session.channel_failure(channel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use russh::keys::PublicKey;
+
+    struct TestAuthenticator;
+
+    #[async_trait]
+    impl PasswordAuthenticator for TestAuthenticator {
+        type Identity = String;
+
+        async fn authenticate(
+            &self,
+            username: &str,
+            password: &str,
+            _peer_addr: SocketAddr,
+        ) -> Option<String> {
+            (username == "sysop" && password == "secret").then(|| username.to_string())
+        }
+    }
+
+    struct AcceptAnyHostKey;
+
+    impl russh::client::Handler for AcceptAnyHostKey {
+        type Error = russh::Error;
+
+        async fn check_server_key(
+            &mut self,
+            _key: &PublicKey,
+        ) -> std::result::Result<bool, Self::Error> {
+            Ok(true)
+        }
+    }
+
+    async fn bind_test_server(dir: &tempfile::TempDir) -> SshServer<String> {
+        bind_with_config(
+            SshServerConfig::new(dir.path().join("host_key"))
+                .with_auth_rejection_time(Duration::from_millis(10)),
+        )
+        .await
+    }
+
+    async fn bind_with_config(config: SshServerConfig) -> SshServer<String> {
+        SshServer::bind("127.0.0.1:0", config, Arc::new(TestAuthenticator))
+            .await
+            .unwrap()
+    }
+
+    async fn connect(
+        addr: SocketAddr,
+        password: &str,
+    ) -> (russh::client::Handle<AcceptAnyHostKey>, bool) {
+        let config = Arc::new(russh::client::Config::default());
+        let mut handle = russh::client::connect(config, addr, AcceptAnyHostKey)
+            .await
+            .unwrap();
+        let result = handle
+            .authenticate_password("sysop", password)
+            .await
+            .unwrap();
+        (handle, result.success())
+    }
+
+    #[tokio::test]
+    async fn test_bind_server() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = bind_test_server(&dir).await;
+
+        assert_ne!(server.local_addr().port(), 0);
+        assert!(dir.path().join("host_key").exists());
+    }
+
+    #[tokio::test]
+    async fn test_wrong_password_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = bind_test_server(&dir).await;
+
+        let (_handle, authenticated) = connect(server.local_addr(), "wrong").await;
+        assert!(!authenticated);
+    }
+
+    #[tokio::test]
+    async fn test_shell_session_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = bind_test_server(&dir).await;
+
+        let (handle, authenticated) = connect(server.local_addr(), "secret").await;
+        assert!(authenticated);
+
+        let mut channel = handle.channel_open_session().await.unwrap();
+        channel
+            .request_pty(true, "xterm", 132, 50, 0, 0, &[])
+            .await
+            .unwrap();
+        channel.request_shell(true).await.unwrap();
+
+        let (mut conn, identity) = server.accept().await.unwrap();
+        assert_eq!(identity, "sysop");
+        assert_eq!(conn.username(), "sysop");
+        assert_eq!(conn.terminal_type(), "xterm");
+        assert_eq!(conn.terminal_size(), (132, 50));
+
+        conn.send_line("Welcome").await.unwrap();
+        channel.data_bytes(&b"hello\r"[..]).await.unwrap();
+        assert_eq!(conn.read_line().await.unwrap(), "hello");
+
+        channel.window_change(100, 40, 0, 0).await.unwrap();
+        channel.data_bytes(&b"x"[..]).await.unwrap();
+        assert_eq!(conn.read_char().await.unwrap(), 'x');
+        assert_eq!(conn.terminal_size(), (100, 40));
+
+        // Client sees the greeting followed by the echoed input
+        let mut received = Vec::new();
+        while let Some(msg) = channel.wait().await {
+            if let russh::ChannelMsg::Data { data } = msg {
+                received.extend_from_slice(&data);
+            }
+            if received.ends_with(b"hello") {
+                break;
+            }
+        }
+        assert!(received.starts_with(b"Welcome\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_full_accept_queue_turns_caller_away() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = bind_with_config(
+            SshServerConfig::new(dir.path().join("host_key"))
+                .with_auth_rejection_time(Duration::from_millis(10))
+                .with_accept_queue_size(1),
+        )
+        .await;
+
+        // The first shell fills the queue
+        let (first, _) = connect(server.local_addr(), "secret").await;
+        let first = first.channel_open_session().await.unwrap();
+        first.request_shell(true).await.unwrap();
+
+        let (second, authenticated) = connect(server.local_addr(), "secret").await;
+        assert!(authenticated);
+        let mut channel = second.channel_open_session().await.unwrap();
+        channel.request_shell(true).await.unwrap();
+
+        let mut received = Vec::new();
+        while let Some(msg) = channel.wait().await {
+            if let russh::ChannelMsg::Data { data } = msg {
+                received.extend_from_slice(&data);
+            }
+        }
+        assert_eq!(received, BUSY_MESSAGE);
+
+        // The waiting shell is still handed over
+        let (_, identity) = server.accept().await.unwrap();
+        assert_eq!(identity, "sysop");
+    }
+}
@@ -2,6 +2,9 @@
 
 use crate::error::{Result, TelnetError};
 use crate::iac::{self, IAC, IacCommand, TelnetOption};
+use async_trait::async_trait;
+use impulse_session::{Connection, ConnectionError, ConnectionType};
+use std::collections::VecDeque;
 use std::net::SocketAddr;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
@@ -9,14 +12,15 @@ use tokio::net::TcpStream;
 /// Maximum buffer size for incoming data (64KB)
 const MAX_BUFFER_SIZE: usize = 65536;
 
+/// Longest subnegotiation accepted from the client
+const MAX_SUBNEGOTIATION: usize = 1024;
+
 /// A telnet connection to a remote client
 pub struct TelnetConnection {
     /// Underlying TCP stream
     stream: TcpStream,
     /// Remote address
     peer_addr: SocketAddr,
-    /// Input buffer for processing IAC sequences
-    buffer: Vec<u8>,
     /// Whether echo is enabled (server echoes back to client)
     echo_enabled: bool,
     /// Whether suppress go ahead is enabled
@@ -25,6 +29,60 @@ pub struct TelnetConnection {
     terminal_width: u16,
     /// Terminal height (from NAWS negotiation)
     terminal_height: u16,
+    /// Whether the connection is still open
+    connected: bool,
+    /// Bytes read from the stream but not consumed yet
+    input: VecDeque<u8>,
+}
+
+/// What the front of the input buffer holds
+#[derive(Debug, PartialEq, Eq)]
+enum Input {
+    /// Nothing, or a command that has not fully arrived
+    Incomplete,
+    /// A data byte and the number of buffered bytes that encode it
+    Data(u8, usize),
+    /// A command of the given length, starting with IAC
+    Command(usize),
+}
+
+/// Find the next data byte or command at the start of `input`
+fn next_input(input: &[u8]) -> Result<Input> {
+    let negotiation = [
+        IacCommand::WILL,
+        IacCommand::WONT,
+        IacCommand::DO,
+        IacCommand::DONT,
+    ]
+    .map(IacCommand::to_byte);
+
+    Ok(match input {
+        [] | [IAC] => Input::Incomplete,
+        [IAC, IAC, ..] => Input::Data(IAC, 2),
+        [IAC, cmd] if negotiation.contains(cmd) => Input::Incomplete,
+        [IAC, cmd, _, ..] if negotiation.contains(cmd) => Input::Command(3),
+        [IAC, cmd, body @ ..] if *cmd == IacCommand::SB.to_byte() => {
+            // Runs until IAC SE; IAC IAC inside is an escaped data byte
+            let mut i = 0;
+            loop {
+                match body.get(i..i + 2) {
+                    Some([IAC, end]) if *end == IacCommand::SE.to_byte() => {
+                        break Input::Command(i + 4);
+                    }
+                    Some([IAC, _]) => i += 2,
+                    Some(_) => i += 1,
+                    None if body.len() > MAX_SUBNEGOTIATION => {
+                        return Err(TelnetError::BufferOverflow {
+                            max: MAX_SUBNEGOTIATION,
+                        });
+                    }
+                    None => break Input::Incomplete,
+                }
+            }
+        }
+        [IAC, _, ..] => Input::Command(2),
+        [byte, ..] => Input::Data(*byte, 1),
+    })
 }
 
 impl TelnetConnection {
@@ -33,11 +91,12 @@ impl TelnetConnection {
         Self {
             stream,
             peer_addr,
-            buffer: Vec::with_capacity(4096),
             echo_enabled: false,
             suppress_ga: true,
             terminal_width: 80,
             terminal_height: 24,
+            connected: true,
+            input: VecDeque::new(),
         }
     }
 
@@ -79,8 +138,16 @@ impl TelnetConnection {
 
     /// Send text to the client (escaping IAC bytes)
     pub async fn send_text(&mut self, text: &str) -> Result<()> {
-        let mut escaped = Vec::with_capacity(text.len());
-        for byte in text.as_bytes() {
+        self.send_data(text.as_bytes()).await
+    }
+
+    /// Send binary data to the client (escaping IAC bytes)
+    ///
+    /// Unlike [`send_raw`](Self::send_raw), a 0xFF data byte reaches the
+    /// client intact, so file transfer protocols can run over the session.
+    pub async fn send_data(&mut self, data: &[u8]) -> Result<()> {
+        let mut escaped = Vec::with_capacity(data.len());
+        for byte in data {
             escaped.push(*byte);
             // Escape IAC by sending IAC IAC
             if *byte == IAC {
@@ -99,22 +166,10 @@ impl TelnetConnection {
     /// Read a line of text from the client (blocking until CRLF or LF)
     pub async fn read_line(&mut self) -> Result<String> {
         let mut line = Vec::new();
-        let mut buf = [0u8; 1];
         let mut started = false; // Track if we've received any non-line-ending characters
 
         loop {
-            let n = self.stream.read(&mut buf).await?;
-            if n == 0 {
-                return Err(TelnetError::ConnectionClosed);
-            }
-
-            let byte = buf[0];
-
-            // Handle IAC sequences
-            if byte == IAC {
-                self.handle_iac_sequence().await?;
-                continue;
-            }
+            let byte = self.read_byte().await?;
 
             // Skip leading CR/LF characters (handles leftover LF from previous CRLF)
             if !started && (byte == b'\n' || byte == b'\r') {
@@ -172,7 +227,6 @@ impl TelnetConnection {
     /// * `show_asterisks` - If true, displays '*' for each character typed
     pub async fn read_password(&mut self, show_asterisks: bool) -> Result<String> {
         let mut password = Vec::new();
-        let mut buf = [0u8; 1];
         let mut started = false;
 
         // Save current echo state
@@ -183,19 +237,13 @@ impl TelnetConnection {
         self.echo_enabled = false;
 
         loop {
-            let n = self.stream.read(&mut buf).await?;
-            if n == 0 {
-                self.echo_enabled = original_echo;
-                return Err(TelnetError::ConnectionClosed);
-            }
-
-            let byte = buf[0];
-
-            // Handle IAC sequences
-            if byte == IAC {
-                self.handle_iac_sequence().await?;
-                continue;
-            }
+            let byte = match self.read_byte().await {
+                Ok(byte) => byte,
+                Err(e) => {
+                    self.echo_enabled = original_echo;
+                    return Err(e);
+                }
+            };
 
             // Skip leading CR/LF characters
             if !started && (byte == b'\n' || byte == b'\r') {
@@ -250,49 +298,53 @@ impl TelnetConnection {
 
     /// Read a single character from the client
     pub async fn read_char(&mut self) -> Result<char> {
-        let mut buf = [0u8; 1];
+        Ok(self.read_byte().await? as char)
+    }
 
+    /// Read a single data byte from the client
+    ///
+    /// Telnet commands are handled and skipped; an escaped IAC IAC is
+    /// returned as a literal 0xFF. Cancel safe: input stays buffered until a
+    /// whole command or data byte has arrived, so dropping the future loses
+    /// nothing.
+    pub async fn read_byte(&mut self) -> Result<u8> {
         loop {
-            let n = self.stream.read(&mut buf).await?;
-            if n == 0 {
-                return Err(TelnetError::ConnectionClosed);
-            }
-
-            let byte = buf[0];
-
-            // Handle IAC sequences
-            if byte == IAC {
-                self.handle_iac_sequence().await?;
-                continue;
-            }
-
-            // Return printable character
-            if (32..127).contains(&byte) {
-                return Ok(byte as char);
+            match next_input(self.input.make_contiguous())? {
+                Input::Incomplete => self.fill().await?,
+                Input::Data(byte, len) => {
+                    self.input.drain(..len);
+                    return Ok(byte);
+                }
+                Input::Command(len) => {
+                    let command: Vec<u8> = self.input.drain(..len).collect();
+                    self.handle_command(&command).await?;
+                }
             }
-            // Return control characters directly
-            return Ok(byte as char);
         }
     }
 
-    /// Handle an IAC sequence from the stream
-    async fn handle_iac_sequence(&mut self) -> Result<()> {
-        let mut buf = [0u8; 2];
-        self.stream.read_exact(&mut buf).await?;
-
-        let cmd_byte = buf[0];
-
-        // IAC IAC means literal 255
-        if cmd_byte == IAC {
-            self.buffer.push(IAC);
-            return Ok(());
+    /// Read more input from the stream into the buffer
+    ///
+    /// Reads a byte at a time so nothing is read ahead of a door that takes
+    /// over the socket.
+    async fn fill(&mut self) -> Result<()> {
+        let mut buf = [0u8; 1];
+        let n = self.stream.read(&mut buf).await?;
+        if n == 0 {
+            return Err(TelnetError::ConnectionClosed);
         }
+        self.input.push_back(buf[0]);
+        Ok(())
+    }
 
+    /// Handle a complete telnet command, starting with IAC
+    async fn handle_command(&mut self, command: &[u8]) -> Result<()> {
+        let cmd_byte = command[1];
         let cmd = IacCommand::from_byte(cmd_byte).ok_or(TelnetError::InvalidCommand(cmd_byte))?;
 
         match cmd {
             IacCommand::WILL | IacCommand::WONT | IacCommand::DO | IacCommand::DONT => {
-                let option_byte = buf[1];
+                let option_byte = command[2];
                 if let Some(option) = TelnetOption::from_byte(option_byte) {
                     self.handle_option_negotiation(cmd, option).await?;
                 } else {
@@ -311,8 +363,8 @@ impl TelnetConnection {
                 }
             }
             IacCommand::SB => {
-                // Subnegotiation - read until IAC SE
-                self.handle_subnegotiation().await?;
+                // Subnegotiation between IAC SB and IAC SE
+                self.handle_subnegotiation(&command[2..command.len() - 2]);
             }
             IacCommand::NOP => {
                 // No operation - ignore
@@ -369,33 +421,15 @@ impl TelnetConnection {
         Ok(())
     }
 
-    /// Handle subnegotiation sequences
-    async fn handle_subnegotiation(&mut self) -> Result<()> {
-        let mut sub_buffer = Vec::new();
-        let mut buf = [0u8; 1];
-
-        // Read until IAC SE
-        loop {
-            self.stream.read_exact(&mut buf).await?;
-
-            if buf[0] == IAC {
-                self.stream.read_exact(&mut buf).await?;
-                if buf[0] == IacCommand::SE.to_byte() {
-                    break;
-                }
-                // IAC IAC in subnegotiation means literal IAC
-                if buf[0] == IAC {
-                    sub_buffer.push(IAC);
-                } else {
-                    sub_buffer.push(IAC);
-                    sub_buffer.push(buf[0]);
-                }
-            } else {
-                sub_buffer.push(buf[0]);
-            }
-
-            if sub_buffer.len() > 1024 {
-                return Err(TelnetError::BufferOverflow { max: 1024 });
+    /// Handle a subnegotiation body
+    fn handle_subnegotiation(&mut self, body: &[u8]) {
+        // Unescape IAC IAC
+        let mut sub_buffer = Vec::with_capacity(body.len());
+        let mut bytes = body.iter();
+        while let Some(&byte) = bytes.next() {
+            sub_buffer.push(byte);
+            if byte == IAC {
+                bytes.next();
             }
         }
 
@@ -413,8 +447,6 @@ impl TelnetConnection {
                 }
             }
         }
-
-        Ok(())
     }
 
     /// Close the connection gracefully
@@ -424,6 +456,82 @@ impl TelnetConnection {
     }
 }
 
+impl From<TelnetError> for ConnectionError {
+    fn from(err: TelnetError) -> Self {
+        match err {
+            TelnetError::Io(e) => ConnectionError::Io(e),
+            TelnetError::ConnectionClosed => ConnectionError::Closed,
+            TelnetError::InvalidUtf8(e) => ConnectionError::Encoding(e.to_string()),
+            other => ConnectionError::Protocol(other.to_string()),
+        }
+    }
+}
+
+#[async_trait]
+impl Connection for TelnetConnection {
+    fn connection_type(&self) -> ConnectionType {
+        ConnectionType::Telnet
+    }
+
+    fn remote_addr(&self) -> String {
+        self.peer_addr.to_string()
+    }
+
+    async fn send_text(&mut self, data: &str) -> std::result::Result<(), ConnectionError> {
+        Ok(TelnetConnection::send_text(self, data).await?)
+    }
+
+    async fn send_bytes(&mut self, data: &[u8]) -> std::result::Result<(), ConnectionError> {
+        Ok(self.send_data(data).await?)
+    }
+
+    async fn recv(&mut self) -> std::result::Result<Option<Vec<u8>>, ConnectionError> {
+        match self.read_byte().await {
+            Ok(byte) => Ok(Some(vec![byte])),
+            Err(TelnetError::ConnectionClosed) => {
+                self.connected = false;
+                Ok(None)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn close(&mut self) -> std::result::Result<(), ConnectionError> {
+        self.connected = false;
+        self.stream.shutdown().await?;
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    async fn read_char(&mut self) -> std::result::Result<char, ConnectionError> {
+        Ok(TelnetConnection::read_char(self).await?)
+    }
+
+    async fn read_line(&mut self) -> std::result::Result<String, ConnectionError> {
+        Ok(TelnetConnection::read_line(self).await?)
+    }
+
+    async fn read_password(
+        &mut self,
+        show_asterisks: bool,
+    ) -> std::result::Result<String, ConnectionError> {
+        Ok(TelnetConnection::read_password(self, show_asterisks).await?)
+    }
+
+    fn terminal_size(&self) -> (u16, u16) {
+        (self.terminal_width, self.terminal_height)
+    }
+
+    #[cfg(unix)]
+    fn socket(&self) -> Option<std::os::fd::BorrowedFd<'_>> {
+        use std::os::fd::AsFd;
+        Some(self.stream.as_fd())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -439,4 +547,87 @@ mod tests {
     fn test_buffer_size_constant() {
         assert_eq!(MAX_BUFFER_SIZE, 65536);
     }
+
+    #[tokio::test]
+    async fn test_binary_data_escapes_iac() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (stream, peer_addr) = listener.accept().await.unwrap();
+        let mut connection = TelnetConnection::new(stream, peer_addr);
+
+        // Escaped 0xFF and a NOP command in the middle of binary data
+        client
+            .write_all(&[IAC, IAC, b'A', IAC, IacCommand::NOP.to_byte(), b'B'])
+            .await
+            .unwrap();
+        assert_eq!(connection.read_byte().await.unwrap(), 0xFF);
+        assert_eq!(connection.read_byte().await.unwrap(), b'A');
+        assert_eq!(connection.read_byte().await.unwrap(), b'B');
+
+        Connection::send_bytes(&mut connection, &[0x01, 0xFF, 0x02])
+            .await
+            .unwrap();
+        let mut received = [0u8; 4];
+        client.read_exact(&mut received).await.unwrap();
+        assert_eq!(received, [0x01, IAC, IAC, 0x02]);
+    }
+
+    #[test]
+    fn test_next_input() {
+        let sb = IacCommand::SB.to_byte();
+        let se = IacCommand::SE.to_byte();
+        let will = IacCommand::WILL.to_byte();
+
+        assert_eq!(next_input(&[]).unwrap(), Input::Incomplete);
+        assert_eq!(next_input(b"ab").unwrap(), Input::Data(b'a', 1));
+        assert_eq!(next_input(&[IAC]).unwrap(), Input::Incomplete);
+        assert_eq!(next_input(&[IAC, IAC]).unwrap(), Input::Data(IAC, 2));
+        assert_eq!(next_input(&[IAC, will]).unwrap(), Input::Incomplete);
+        assert_eq!(
+            next_input(&[IAC, will, 1, b'a']).unwrap(),
+            Input::Command(3)
+        );
+        assert_eq!(
+            next_input(&[IAC, sb, 31, 0, IAC]).unwrap(),
+            Input::Incomplete
+        );
+        assert_eq!(
+            next_input(&[IAC, sb, 31, IAC, IAC, IAC, se, b'a']).unwrap(),
+            Input::Command(7)
+        );
+
+        let mut runaway = vec![IAC, sb];
+        runaway.resize(MAX_SUBNEGOTIATION + 3, 0);
+        assert!(next_input(&runaway).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_byte_is_cancel_safe() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (stream, peer_addr) = listener.accept().await.unwrap();
+        let mut connection = TelnetConnection::new(stream, peer_addr);
+
+        // Give up on a read while a window size report is half sent
+        let naws = TelnetOption::WindowSize.to_byte();
+        client
+            .write_all(&[IAC, IacCommand::SB.to_byte(), naws, 0, 100])
+            .await
+            .unwrap();
+        let read =
+            tokio::time::timeout(std::time::Duration::from_millis(50), connection.read_byte())
+                .await;
+        assert!(read.is_err());
+
+        client
+            .write_all(&[0, 40, IAC, IacCommand::SE.to_byte(), b'x'])
+            .await
+            .unwrap();
+        assert_eq!(connection.read_byte().await.unwrap(), b'x');
+        assert_eq!(TelnetConnection::terminal_size(&connection), (100, 40));
+    }
 }
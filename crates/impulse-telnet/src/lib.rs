@@ -12,6 +12,7 @@
 //! - RFC 1073 Window Size Negotiation
 //! - Async/await based on Tokio
 //! - Connection lifecycle management
+//! - Implements the transport-agnostic `impulse_session::Connection` trait
 //!
 //! # Example
 //!
@@ -0,0 +1,1049 @@
+//! Full-screen text editor
+//!
+//! [`FullScreenEditor`] holds the text being written, one line per screen
+//! row, and draws itself through an [`AnsiRenderer`]. It does no I/O of its
+//! own: the caller turns what the terminal sends into [`Key`]s with a
+//! [`KeyDecoder`], passes each one to [`FullScreenEditor::handle_key`], sends
+//! what [`FullScreenEditor::render`] wrote, and acts on the returned
+//! [`EditorEvent`].
+
+use crate::color::Color;
+use crate::renderer::AnsiRenderer;
+use std::time::Duration;
+
+/// Lines a message may have unless set otherwise
+pub const DEFAULT_MAX_LINES: usize = 500;
+
+/// How long to wait after an ESC for the rest of a key sequence before
+/// taking it as the ESC key
+pub const ESCAPE_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// Screen rows that are not text: the title, the rule under it and the
+/// status line
+const CHROME_ROWS: usize = 3;
+
+/// Columns between tab stops
+const TAB_WIDTH: usize = 8;
+
+/// Help shown in the text area
+const HELP: &[&str] = &[
+    "Arrows, Home, End, PgUp, PgDn   Move around the message",
+    "Insert or ^V                    Switch between insert and overwrite",
+    "Backspace, Del                  Delete characters, joining lines",
+    "^Y                              Delete the current line",
+    "^Q                              Open the quote window",
+    "^Z                              Save the message",
+    "^A                              Abort the message",
+    "ESC                             Menu: Save, Abort, Quote, Help",
+    "^G or F1                        This help",
+    "",
+    "Words that run past the right margin wrap onto the next line.",
+];
+
+/// A key pressed on the caller's terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    /// A printable character
+    Char(char),
+    /// A control character, given as its letter (`Ctrl('Z')` for ^Z)
+    Ctrl(char),
+    /// Enter
+    Enter,
+    /// Backspace
+    Backspace,
+    /// Delete
+    Delete,
+    /// Tab
+    Tab,
+    /// A lone ESC
+    Escape,
+    /// Insert
+    Insert,
+    /// Cursor up
+    Up,
+    /// Cursor down
+    Down,
+    /// Cursor left
+    Left,
+    /// Cursor right
+    Right,
+    /// Home
+    Home,
+    /// End
+    End,
+    /// Page up
+    PageUp,
+    /// Page down
+    PageDown,
+    /// F1
+    F1,
+}
+
+/// Decoder state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum DecodeState {
+    /// Between keys
+    #[default]
+    Normal,
+    /// After an ESC
+    Escape,
+    /// Inside an `ESC [` sequence
+    Csi,
+    /// After `ESC O`
+    Ss3,
+}
+
+/// Turns the characters a terminal sends into keys
+///
+/// Cursor and editing keys arrive as ANSI (`ESC [`) or VT100 (`ESC O`)
+/// sequences. An ESC is held until the next character shows whether it
+/// starts one; when nothing follows within [`ESCAPE_TIMEOUT`] the caller
+/// calls [`KeyDecoder::flush`] to get it as [`Key::Escape`].
+#[derive(Debug, Default)]
+pub struct KeyDecoder {
+    /// Where in a sequence the decoder is
+    state: DecodeState,
+    /// Parameters of a CSI sequence
+    params: String,
+    /// Whether the last character was a CR, so a following LF is dropped
+    after_cr: bool,
+    /// Key typed straight after an ESC, reported after it
+    pending: Option<Key>,
+}
+
+impl KeyDecoder {
+    /// Create a decoder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the decoder is inside an escape sequence
+    pub fn in_sequence(&self) -> bool {
+        self.state != DecodeState::Normal
+    }
+
+    /// Whether an ESC is waiting to see what follows it
+    pub fn awaiting_escape(&self) -> bool {
+        self.state == DecodeState::Escape
+    }
+
+    /// Give up waiting for the rest of a sequence after an ESC
+    ///
+    /// Returns [`Key::Escape`] if an ESC was being held.
+    pub fn flush(&mut self) -> Option<Key> {
+        self.awaiting_escape().then(|| {
+            self.state = DecodeState::Normal;
+            Key::Escape
+        })
+    }
+
+    /// Take the key that arrived along with the last one fed
+    ///
+    /// A character that follows an ESC without starting a sequence is
+    /// reported here, after the [`Key::Escape`] that `feed` returned.
+    pub fn take_pending(&mut self) -> Option<Key> {
+        self.pending.take()
+    }
+
+    /// Feed one character, returning the key it completes
+    pub fn feed(&mut self, ch: char) -> Option<Key> {
+        let after_cr = std::mem::take(&mut self.after_cr);
+
+        match self.state {
+            DecodeState::Normal => {}
+            DecodeState::Escape => match ch {
+                '[' => {
+                    self.state = DecodeState::Csi;
+                    self.params.clear();
+                    return None;
+                }
+                'O' => {
+                    self.state = DecodeState::Ss3;
+                    return None;
+                }
+                // A second ESC starts its own wait
+                '\x1b' => return Some(Key::Escape),
+                _ => {
+                    self.state = DecodeState::Normal;
+                    self.pending = self.feed(ch);
+                    return Some(Key::Escape);
+                }
+            },
+            DecodeState::Csi => {
+                if (ch.is_ascii_digit() || ch == ';') && self.params.len() < 8 {
+                    self.params.push(ch);
+                    return None;
+                }
+                self.state = DecodeState::Normal;
+                if ch != '~' {
+                    return cursor_key(ch);
+                }
+                return match self.params.split(';').next() {
+                    Some("1" | "7") => Some(Key::Home),
+                    Some("2") => Some(Key::Insert),
+                    Some("3") => Some(Key::Delete),
+                    Some("4" | "8") => Some(Key::End),
+                    Some("5") => Some(Key::PageUp),
+                    Some("6") => Some(Key::PageDown),
+                    Some("11") => Some(Key::F1),
+                    _ => None,
+                };
+            }
+            DecodeState::Ss3 => {
+                self.state = DecodeState::Normal;
+                return match ch {
+                    'P' => Some(Key::F1),
+                    _ => cursor_key(ch),
+                };
+            }
+        }
+
+        match ch {
+            '\x1b' => {
+                self.state = DecodeState::Escape;
+                None
+            }
+            '\r' => {
+                self.after_cr = true;
+                Some(Key::Enter)
+            }
+            '\n' if !after_cr => Some(Key::Enter),
+            '\n' | '\0' => None,
+            '\x08' | '\x7f' => Some(Key::Backspace),
+            '\t' => Some(Key::Tab),
+            ' '..='~' => Some(Key::Char(ch)),
+            '\x01'..='\x1f' => Some(Key::Ctrl((ch as u8 + b'@') as char)),
+            _ if !ch.is_control() => Some(Key::Char(ch)),
+            _ => None,
+        }
+    }
+}
+
+/// The key a cursor sequence's final character stands for
+fn cursor_key(ch: char) -> Option<Key> {
+    match ch {
+        'A' => Some(Key::Up),
+        'B' => Some(Key::Down),
+        'C' => Some(Key::Right),
+        'D' => Some(Key::Left),
+        'H' => Some(Key::Home),
+        'F' => Some(Key::End),
+        _ => None,
+    }
+}
+
+/// What the caller should do after a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorEvent {
+    /// Keep editing
+    Continue,
+    /// The text is finished and should be saved
+    Save,
+    /// The text should be thrown away
+    Abort,
+    /// Insert a quote of the whole original
+    QuoteAll,
+    /// Insert a quote of the original's lines `start..end`
+    Quote {
+        /// First line, counting from 0
+        start: usize,
+        /// Line after the last one
+        end: usize,
+    },
+}
+
+/// The quote window's position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct QuoteWindow {
+    /// First line shown
+    top: usize,
+    /// Highlighted line
+    cursor: usize,
+    /// Where the selection starts, once marked
+    mark: Option<usize>,
+}
+
+/// What is shown over the editor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Overlay {
+    /// Nothing
+    None,
+    /// The ESC menu on the status line
+    Menu,
+    /// The abort question on the status line
+    ConfirmAbort,
+    /// Help in the text area
+    Help,
+    /// The original's lines in the text area
+    Quote(QuoteWindow),
+}
+
+/// What has to be drawn again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Damage {
+    /// Only the status line and the cursor
+    Cursor,
+    /// One line
+    Line(usize),
+    /// A line and every line below it
+    Below(usize),
+    /// Everything
+    Screen,
+}
+
+impl Damage {
+    /// Damage covering both
+    fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Screen, _) | (_, Self::Screen) => Self::Screen,
+            (Self::Cursor, damage) | (damage, Self::Cursor) => damage,
+            (Self::Line(a), Self::Line(b)) if a == b => Self::Line(a),
+            (Self::Line(a) | Self::Below(a), Self::Line(b) | Self::Below(b)) => {
+                Self::Below(a.min(b))
+            }
+        }
+    }
+}
+
+/// A full-screen editor for message text
+///
+/// Lines hold printable characters, each taking one column, and are never
+/// wider than the text area, which leaves the terminal's last column free so
+/// a full line cannot make it scroll. Columns count characters, not bytes.
+pub struct FullScreenEditor {
+    /// The text, one entry per line
+    lines: Vec<String>,
+    /// Cursor line
+    row: usize,
+    /// Cursor column
+    col: usize,
+    /// First line shown
+    top: usize,
+    /// Text area width
+    width: usize,
+    /// Screen height
+    height: usize,
+    /// Insert mode, as opposed to overwrite
+    insert: bool,
+    /// Most lines the text may have
+    max_lines: usize,
+    /// Title line
+    title: String,
+    /// Lines of the message being replied to
+    quote_source: Vec<String>,
+    /// What is shown over the text
+    overlay: Overlay,
+    /// One-off message for the status line
+    notice: Option<String>,
+    /// What the next render has to draw
+    damage: Damage,
+}
+
+impl FullScreenEditor {
+    /// Create an empty editor for a terminal of `columns` by `rows`
+    pub fn new(columns: u16, rows: u16) -> Self {
+        Self {
+            lines: vec![String::new()],
+            row: 0,
+            col: 0,
+            top: 0,
+            width: usize::from(columns).saturating_sub(1).max(20),
+            height: usize::from(rows).max(CHROME_ROWS + 4),
+            insert: true,
+            max_lines: DEFAULT_MAX_LINES,
+            title: String::new(),
+            quote_source: Vec::new(),
+            overlay: Overlay::None,
+            notice: None,
+            damage: Damage::Screen,
+        }
+    }
+
+    /// Set the title shown above the text
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Set the text offered in the quote window
+    pub fn with_quote_source(mut self, text: &str) -> Self {
+        self.quote_source = text.lines().map(str::to_string).collect();
+        self
+    }
+
+    /// Set the most lines the text may have
+    pub fn with_max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = max_lines.max(1);
+        self
+    }
+
+    /// Width of the text area
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Whether typing inserts rather than overwrites
+    pub fn is_insert_mode(&self) -> bool {
+        self.insert
+    }
+
+    /// Cursor position as (line, column), counting from 0
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    /// The lines of text
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// The finished text, without trailing blank lines
+    pub fn text(&self) -> String {
+        let end = self
+            .lines
+            .iter()
+            .rposition(|line| !line.trim().is_empty())
+            .map_or(0, |last| last + 1);
+        self.lines[..end]
+            .iter()
+            .map(|line| line.trim_end())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Insert lines of text at the cursor, such as a quote
+    ///
+    /// The text goes in below the cursor line, or in its place when that
+    /// line is empty, and the cursor lands on a blank line after it. Lines
+    /// wider than the text area are wrapped.
+    pub fn insert_text(&mut self, text: &str) {
+        let mut at = if self.lines[self.row].is_empty() {
+            self.lines.remove(self.row);
+            self.row
+        } else {
+            self.row + 1
+        };
+        self.damage(Damage::Below(at.min(self.row)));
+
+        for line in text.lines() {
+            if self.lines.len() >= self.max_lines {
+                self.notice = Some("The message is full.".to_string());
+                break;
+            }
+            let line = line
+                .chars()
+                .map(|ch| match ch {
+                    '\t' => ' ',
+                    _ if ch.is_control() => '?',
+                    _ => ch,
+                })
+                .collect();
+            let before = self.lines.len();
+            self.lines.insert(at, line);
+            self.row = at;
+            self.col = 0;
+            self.wrap(at);
+            at += self.lines.len() - before;
+        }
+
+        if self.lines.len() < self.max_lines {
+            self.lines.insert(at, String::new());
+        }
+        self.row = at.min(self.lines.len() - 1);
+        self.col = 0;
+        self.scroll();
+    }
+
+    /// Act on a key
+    pub fn handle_key(&mut self, key: Key) -> EditorEvent {
+        self.notice = None;
+
+        match std::mem::replace(&mut self.overlay, Overlay::None) {
+            Overlay::None => self.edit(key),
+            Overlay::Menu => match key {
+                Key::Char(ch) => {
+                    match ch.to_ascii_uppercase() {
+                        'S' => return EditorEvent::Save,
+                        'A' => self.overlay = Overlay::ConfirmAbort,
+                        'Q' => self.open_quote_window(),
+                        'H' | '?' => self.overlay = Overlay::Help,
+                        _ => {}
+                    }
+                    EditorEvent::Continue
+                }
+                Key::Escape => EditorEvent::Continue,
+                key => self.edit(key),
+            },
+            Overlay::ConfirmAbort => match key {
+                Key::Char('y' | 'Y') => EditorEvent::Abort,
+                _ => EditorEvent::Continue,
+            },
+            Overlay::Help => {
+                self.damage(Damage::Below(self.top));
+                EditorEvent::Continue
+            }
+            Overlay::Quote(window) => self.quote_key(window, key),
+        }
+    }
+
+    /// Draw what changed since the last call and place the cursor
+    pub fn render(&mut self, renderer: &mut AnsiRenderer) {
+        let damage = std::mem::replace(&mut self.damage, Damage::Cursor);
+
+        if damage == Damage::Screen {
+            renderer.clear_screen();
+            renderer.set_foreground(Color::BrightCyan);
+            renderer.write_text(&clip(&self.title, self.width));
+            renderer.move_cursor(2, 1);
+            renderer.set_foreground(Color::Blue);
+            renderer.write_text(&"─".repeat(self.width));
+            renderer.reset();
+        }
+
+        match self.overlay {
+            Overlay::Help => self.draw_help(renderer),
+            Overlay::Quote(window) => self.draw_quote_window(renderer, window),
+            _ => {
+                let first = match damage {
+                    Damage::Cursor => None,
+                    Damage::Line(row) => {
+                        self.draw_line(renderer, row);
+                        None
+                    }
+                    Damage::Below(row) => Some(row.max(self.top)),
+                    Damage::Screen => Some(self.top),
+                };
+                if let Some(first) = first {
+                    for row in first..self.top + self.text_rows() {
+                        self.draw_line(renderer, row);
+                    }
+                }
+            }
+        }
+
+        self.draw_status(renderer);
+
+        match self.overlay {
+            Overlay::None => {
+                renderer.move_cursor(self.screen_row(self.row - self.top), (self.col + 1) as u16)
+            }
+            Overlay::Quote(window) => {
+                renderer.move_cursor(self.screen_row(window.cursor - window.top), 1)
+            }
+            _ => {}
+        }
+    }
+
+    /// Rows of text shown at once
+    fn text_rows(&self) -> usize {
+        self.height - CHROME_ROWS
+    }
+
+    /// Screen row of a text area row
+    fn screen_row(&self, row: usize) -> u16 {
+        (CHROME_ROWS + row) as u16
+    }
+
+    /// Record what needs drawing
+    fn damage(&mut self, damage: Damage) {
+        self.damage = self.damage.merge(damage);
+    }
+
+    /// Act on a key while editing
+    fn edit(&mut self, key: Key) -> EditorEvent {
+        match key {
+            Key::Char(ch) => self.type_char(ch),
+            Key::Tab => {
+                for _ in 0..TAB_WIDTH - self.col % TAB_WIDTH {
+                    self.type_char(' ');
+                }
+            }
+            Key::Enter => self.split_line(),
+            Key::Backspace => self.backspace(),
+            Key::Delete => self.delete(),
+            Key::Up => self.move_to(self.row.saturating_sub(1)),
+            Key::Down => self.move_to(self.row + 1),
+            Key::PageUp => self.move_to(self.row.saturating_sub(self.text_rows())),
+            Key::PageDown => self.move_to(self.row + self.text_rows()),
+            Key::Left => {
+                if self.col > 0 {
+                    self.col -= 1;
+                } else if self.row > 0 {
+                    self.row -= 1;
+                    self.col = char_len(&self.lines[self.row]);
+                }
+            }
+            Key::Right => {
+                if self.col < char_len(&self.lines[self.row]) {
+                    self.col += 1;
+                } else if self.row + 1 < self.lines.len() {
+                    self.row += 1;
+                    self.col = 0;
+                }
+            }
+            Key::Home => self.col = 0,
+            Key::End => self.col = char_len(&self.lines[self.row]),
+            Key::Insert | Key::Ctrl('V') => self.insert = !self.insert,
+            Key::Escape => self.overlay = Overlay::Menu,
+            Key::F1 | Key::Ctrl('G') => self.overlay = Overlay::Help,
+            Key::Ctrl('Q') => self.open_quote_window(),
+            Key::Ctrl('Y') => self.delete_line(),
+            Key::Ctrl('Z') => return EditorEvent::Save,
+            Key::Ctrl('A') => self.overlay = Overlay::ConfirmAbort,
+            Key::Ctrl(_) => {}
+        }
+        self.scroll();
+        EditorEvent::Continue
+    }
+
+    /// Act on a key in the quote window
+    fn quote_key(&mut self, mut window: QuoteWindow, key: Key) -> EditorEvent {
+        let last = self.quote_source.len().saturating_sub(1);
+        let page = self.text_rows();
+
+        match key {
+            Key::Up => window.cursor = window.cursor.saturating_sub(1),
+            Key::Down => window.cursor = (window.cursor + 1).min(last),
+            Key::PageUp => window.cursor = window.cursor.saturating_sub(page),
+            Key::PageDown => window.cursor = (window.cursor + page).min(last),
+            Key::Home => window.cursor = 0,
+            Key::End => window.cursor = last,
+            Key::Char(' ') | Key::Enter => match window.mark {
+                None => window.mark = Some(window.cursor),
+                Some(mark) => {
+                    self.damage(Damage::Below(self.top));
+                    return EditorEvent::Quote {
+                        start: mark.min(window.cursor),
+                        end: mark.max(window.cursor) + 1,
+                    };
+                }
+            },
+            Key::Char(ch) => match ch.to_ascii_uppercase() {
+                'A' => {
+                    self.damage(Damage::Below(self.top));
+                    return EditorEvent::QuoteAll;
+                }
+                'Q' | 'X' => {
+                    self.damage(Damage::Below(self.top));
+                    return EditorEvent::Continue;
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+
+        if window.cursor < window.top {
+            window.top = window.cursor;
+        } else if window.cursor >= window.top + page {
+            window.top = window.cursor + 1 - page;
+        }
+        self.overlay = Overlay::Quote(window);
+        EditorEvent::Continue
+    }
+
+    /// Show the quote window, if there is anything to quote
+    fn open_quote_window(&mut self) {
+        if self.quote_source.is_empty() {
+            self.notice = Some("There is nothing to quote.".to_string());
+            return;
+        }
+        self.overlay = Overlay::Quote(QuoteWindow {
+            top: 0,
+            cursor: 0,
+            mark: None,
+        });
+    }
+
+    /// Type a character at the cursor
+    fn type_char(&mut self, ch: char) {
+        if ch.is_control() {
+            return;
+        }
+        let line = &mut self.lines[self.row];
+        let at = byte_at(line, self.col);
+        if !self.insert && self.col < char_len(line) {
+            let end = byte_at(line, self.col + 1);
+            line.replace_range(at..end, ch.encode_utf8(&mut [0; 4]));
+        } else {
+            line.insert(at, ch);
+        }
+        self.col += 1;
+        self.damage(Damage::Line(self.row));
+        self.wrap(self.row);
+    }
+
+    /// Move the words past the right margin of a line onto new lines
+    fn wrap(&mut self, mut row: usize) {
+        while char_len(&self.lines[row]) > self.width {
+            let space = self.lines[row]
+                .chars()
+                .take(self.width + 1)
+                .collect::<Vec<_>>()
+                .iter()
+                .rposition(|&ch| ch == ' ');
+            let (head_end, tail_start) = match space {
+                Some(space) if space > 0 => (space, space + 1),
+                _ => (self.width, self.width),
+            };
+            if self.lines.len() >= self.max_lines {
+                let end = byte_at(&self.lines[row], self.width);
+                self.lines[row].truncate(end);
+                self.col = self.col.min(char_len(&self.lines[self.row]));
+                self.notice = Some("The message is full.".to_string());
+                return;
+            }
+
+            let line = &mut self.lines[row];
+            let tail = line.split_off(byte_at(line, tail_start));
+            line.truncate(byte_at(line, head_end));
+            self.lines.insert(row + 1, tail);
+            if self.row == row && self.col > head_end {
+                self.row += 1;
+                self.col = self.col.saturating_sub(tail_start);
+            } else if self.row > row {
+                self.row += 1;
+            }
+            self.damage(Damage::Below(row));
+            row += 1;
+        }
+    }
+
+    /// Break the line at the cursor
+    fn split_line(&mut self) {
+        if self.lines.len() >= self.max_lines {
+            self.notice = Some("The message is full.".to_string());
+            return;
+        }
+        let line = &mut self.lines[self.row];
+        let tail = line.split_off(byte_at(line, self.col));
+        self.lines.insert(self.row + 1, tail);
+        self.damage(Damage::Below(self.row));
+        self.row += 1;
+        self.col = 0;
+    }
+
+    /// Delete the character before the cursor, joining lines at the start
+    fn backspace(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+            let line = &mut self.lines[self.row];
+            line.remove(byte_at(line, self.col));
+            self.damage(Damage::Line(self.row));
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = char_len(&self.lines[self.row]);
+            self.lines[self.row].push_str(&line);
+            self.damage(Damage::Below(self.row));
+            self.wrap(self.row);
+        }
+    }
+
+    /// Delete the character under the cursor, joining lines at the end
+    fn delete(&mut self) {
+        if self.col < char_len(&self.lines[self.row]) {
+            let line = &mut self.lines[self.row];
+            line.remove(byte_at(line, self.col));
+            self.damage(Damage::Line(self.row));
+        } else if self.row + 1 < self.lines.len() {
+            let next = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&next);
+            self.damage(Damage::Below(self.row));
+            self.wrap(self.row);
+        }
+    }
+
+    /// Delete the cursor line
+    fn delete_line(&mut self) {
+        if self.lines.len() > 1 {
+            self.lines.remove(self.row);
+            self.row = self.row.min(self.lines.len() - 1);
+        } else {
+            self.lines[0].clear();
+        }
+        self.col = 0;
+        self.damage(Damage::Below(self.row));
+    }
+
+    /// Move the cursor to a line, keeping its column where it can
+    fn move_to(&mut self, row: usize) {
+        self.row = row.min(self.lines.len() - 1);
+        self.col = self.col.min(char_len(&self.lines[self.row]));
+    }
+
+    /// Scroll so the cursor line is shown
+    fn scroll(&mut self) {
+        let rows = self.text_rows();
+        if self.row < self.top {
+            self.top = self.row;
+            self.damage(Damage::Below(self.top));
+        } else if self.row >= self.top + rows {
+            self.top = self.row + 1 - rows;
+            self.damage(Damage::Below(self.top));
+        }
+    }
+
+    /// Draw a line of text if it is on screen
+    fn draw_line(&self, renderer: &mut AnsiRenderer, row: usize) {
+        if row < self.top || row >= self.top + self.text_rows() {
+            return;
+        }
+        renderer.move_cursor(self.screen_row(row - self.top), 1);
+        if let Some(line) = self.lines.get(row) {
+            if line.trim_start().starts_with('>') {
+                renderer.set_foreground(Color::Cyan);
+            }
+            renderer.write_text(line);
+            renderer.reset();
+        }
+        renderer.erase_to_end_of_line();
+    }
+
+    /// Draw the help text over the text area
+    fn draw_help(&self, renderer: &mut AnsiRenderer) {
+        for row in 0..self.text_rows() {
+            renderer.move_cursor(self.screen_row(row), 1);
+            if let Some(line) = HELP.get(row) {
+                renderer.set_foreground(Color::BrightWhite);
+                renderer.write_text(&clip(line, self.width));
+                renderer.reset();
+            }
+            renderer.erase_to_end_of_line();
+        }
+    }
+
+    /// Draw the original's lines over the text area
+    fn draw_quote_window(&self, renderer: &mut AnsiRenderer, window: QuoteWindow) {
+        let selected = |index: usize| {
+            window.mark.is_some_and(|mark| {
+                (mark.min(window.cursor)..=mark.max(window.cursor)).contains(&index)
+            })
+        };
+
+        for row in 0..self.text_rows() {
+            renderer.move_cursor(self.screen_row(row), 1);
+            let index = window.top + row;
+            if let Some(line) = self.quote_source.get(index) {
+                renderer.set_foreground(if selected(index) {
+                    Color::BrightGreen
+                } else {
+                    Color::Cyan
+                });
+                renderer.set_reverse(index == window.cursor);
+                let marker = if selected(index) { '*' } else { ':' };
+                renderer.write_text(&clip(
+                    &format!("{:4}{} {}", index + 1, marker, line),
+                    self.width,
+                ));
+                renderer.reset();
+            }
+            renderer.erase_to_end_of_line();
+        }
+    }
+
+    /// Draw the status line
+    fn draw_status(&self, renderer: &mut AnsiRenderer) {
+        let status = match (self.overlay, &self.notice) {
+            (Overlay::Menu, _) => {
+                "(S)ave  (A)bort  (Q)uote  (H)elp  any other key returns: ".into()
+            }
+            (Overlay::ConfirmAbort, _) => "Abort this message? (Y/N) ".into(),
+            (Overlay::Help, _) => "Press any key to go back to the message".into(),
+            (Overlay::Quote(window), _) => match window.mark {
+                None => "Quote: Up/Down to move, Space marks the first line, (A)ll, (Q)uit".into(),
+                Some(_) => "Quote: Space marks the last line, (Q)uit".into(),
+            },
+            (Overlay::None, Some(notice)) => notice.clone(),
+            (Overlay::None, None) => format!(
+                "{}  Line {} Col {}   ESC Menu  ^Z Save  ^G Help",
+                if self.insert { "Insert" } else { "Overwrite" },
+                self.row + 1,
+                self.col + 1
+            ),
+        };
+
+        renderer.move_cursor(self.height as u16, 1);
+        renderer.set_foreground(Color::BrightWhite);
+        renderer.set_background(Color::Blue);
+        renderer.erase_to_end_of_line();
+        renderer.write_text(&clip(&status, self.width));
+        renderer.reset();
+    }
+}
+
+/// Characters in a line
+fn char_len(line: &str) -> usize {
+    line.chars().count()
+}
+
+/// Byte offset of the character at column `col` (the end past the last)
+fn byte_at(line: &str, col: usize) -> usize {
+    line.char_indices()
+        .nth(col)
+        .map_or(line.len(), |(at, _)| at)
+}
+
+/// The first `width` characters of a string
+fn clip(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(input: &str) -> Vec<Key> {
+        let mut decoder = KeyDecoder::new();
+        let mut keys: Vec<Key> = input
+            .chars()
+            .flat_map(|ch| [decoder.feed(ch), decoder.take_pending()])
+            .flatten()
+            .collect();
+        keys.extend(decoder.flush());
+        keys
+    }
+
+    fn type_text(editor: &mut FullScreenEditor, text: &str) {
+        for ch in text.chars() {
+            editor.handle_key(Key::Char(ch));
+        }
+    }
+
+    #[test]
+    fn test_decode_keys() {
+        assert_eq!(
+            decode("a\r\n\x1b[A\x1bOB\x1b[3~\x7f\x1a"),
+            [
+                Key::Char('a'),
+                Key::Enter,
+                Key::Up,
+                Key::Down,
+                Key::Delete,
+                Key::Backspace,
+                Key::Ctrl('Z'),
+            ]
+        );
+        assert_eq!(decode("\x1bs"), [Key::Escape, Key::Char('s')]);
+        assert_eq!(decode("\x1b\x1b[B"), [Key::Escape, Key::Down]);
+        assert_eq!(decode("é€"), [Key::Char('é'), Key::Char('€')]);
+    }
+
+    #[test]
+    fn test_lone_escape_waits() {
+        let mut decoder = KeyDecoder::new();
+        assert_eq!(decoder.feed('\x1b'), None);
+        assert!(decoder.awaiting_escape() && decoder.in_sequence());
+
+        // Nothing more arrived in time
+        assert_eq!(decoder.flush(), Some(Key::Escape));
+        assert!(!decoder.awaiting_escape());
+        assert_eq!(decoder.flush(), None);
+        assert_eq!(decoder.feed('['), Some(Key::Char('[')));
+    }
+
+    #[test]
+    fn test_non_ascii_text() {
+        let mut editor = FullScreenEditor::new(21, 24);
+        type_text(&mut editor, "café crème brûlée über");
+        assert_eq!(editor.lines(), ["café crème brûlée", "über"]);
+        assert_eq!(editor.cursor(), (1, 4));
+
+        editor.handle_key(Key::Left);
+        editor.handle_key(Key::Backspace);
+        editor.handle_key(Key::Up);
+        editor.handle_key(Key::End);
+        editor.handle_key(Key::Backspace);
+        assert_eq!(editor.lines(), ["café crème brûlé", "übr"]);
+        assert_eq!(editor.cursor(), (0, 16));
+
+        editor.insert_text("> Grüße\tfrom\x07 Zürich");
+        assert_eq!(editor.lines()[1], "> Grüße from? Zürich");
+    }
+
+    #[test]
+    fn test_word_wrap() {
+        let mut editor = FullScreenEditor::new(21, 24);
+        type_text(&mut editor, "the quick brown fox jumps");
+
+        assert_eq!(editor.lines(), ["the quick brown fox", "jumps"]);
+        assert_eq!(editor.cursor(), (1, 5));
+
+        // Joining the lines rewraps them
+        editor.handle_key(Key::Home);
+        editor.handle_key(Key::Backspace);
+        assert_eq!(editor.lines(), ["the quick brown", "foxjumps"]);
+        assert_eq!(editor.cursor(), (1, 3));
+        assert_eq!(editor.text(), "the quick brown\nfoxjumps");
+    }
+
+    #[test]
+    fn test_insert_and_overwrite() {
+        let mut editor = FullScreenEditor::new(80, 24);
+        type_text(&mut editor, "hello world");
+        editor.handle_key(Key::Home);
+        type_text(&mut editor, "J");
+        assert_eq!(editor.lines()[0], "Jhello world");
+
+        editor.handle_key(Key::Insert);
+        assert!(!editor.is_insert_mode());
+        type_text(&mut editor, "ELLO,");
+        assert_eq!(editor.lines()[0], "JELLO, world");
+
+        editor.handle_key(Key::End);
+        editor.handle_key(Key::Enter);
+        type_text(&mut editor, "bye");
+        editor.handle_key(Key::Home);
+        editor.handle_key(Key::Backspace);
+        assert_eq!(editor.lines(), ["JELLO, worldbye"]);
+        assert_eq!(editor.cursor(), (0, 12));
+    }
+
+    #[test]
+    fn test_quote_window() {
+        let mut editor = FullScreenEditor::new(80, 24).with_quote_source("one\ntwo\nthree\nfour");
+        assert_eq!(editor.handle_key(Key::Ctrl('Q')), EditorEvent::Continue);
+        editor.handle_key(Key::Down);
+        editor.handle_key(Key::Char(' '));
+        editor.handle_key(Key::Down);
+        editor.handle_key(Key::Down);
+        assert_eq!(
+            editor.handle_key(Key::Enter),
+            EditorEvent::Quote { start: 1, end: 4 }
+        );
+
+        editor.insert_text("> two\n> three\n> four\n");
+        assert_eq!(editor.lines(), ["> two", "> three", "> four", ""]);
+        assert_eq!(editor.cursor(), (3, 0));
+    }
+
+    #[test]
+    fn test_menu_and_abort() {
+        let mut editor = FullScreenEditor::new(80, 24);
+        editor.handle_key(Key::Escape);
+        assert_eq!(editor.handle_key(Key::Char('s')), EditorEvent::Save);
+
+        editor.handle_key(Key::Ctrl('A'));
+        assert_eq!(editor.handle_key(Key::Char('n')), EditorEvent::Continue);
+        editor.handle_key(Key::Ctrl('A'));
+        assert_eq!(editor.handle_key(Key::Char('y')), EditorEvent::Abort);
+
+        // A cursor key after ESC closes the menu and still moves
+        type_text(&mut editor, "ab");
+        editor.handle_key(Key::Escape);
+        editor.handle_key(Key::Left);
+        assert_eq!(editor.cursor(), (0, 1));
+    }
+
+    #[test]
+    fn test_render_places_cursor() {
+        let mut editor = FullScreenEditor::new(80, 24).with_title("To: All");
+        type_text(&mut editor, "hi");
+        let mut renderer = AnsiRenderer::new();
+        editor.render(&mut renderer);
+
+        let output = renderer.take_output();
+        assert!(output.contains("To: All"));
+        assert!(output.contains("hi"));
+        assert!(output.ends_with("\x1b[3;3H"));
+    }
+}
@@ -12,6 +12,7 @@
 //! - ANSI file rendering (.ANS files)
 //! - Terminal capability detection
 //! - Theme system with hot-reload support
+//! - Full-screen text editor
 //!
 //! # Example
 //!
@@ -27,11 +28,15 @@
 
 mod ansi;
 mod color;
+mod editor;
 mod error;
 mod renderer;
 pub mod theme;
 
 pub use ansi::{AnsiCode, AnsiSequence};
 pub use color::{AnsiColor, Color};
+pub use editor::{
+    DEFAULT_MAX_LINES, ESCAPE_TIMEOUT, EditorEvent, FullScreenEditor, Key, KeyDecoder,
+};
 pub use error::{Result, TerminalError};
 pub use renderer::AnsiRenderer;
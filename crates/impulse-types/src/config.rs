@@ -4,7 +4,9 @@
 //! including server settings, paths, limits, and security policies.
 
 use crate::error::{Error, Result};
+use crate::pascal_aux::MAX_BOARDS;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::path::PathBuf;
 
 /// Network protocol type
@@ -16,6 +18,8 @@ pub enum Protocol {
     Ssh,
     /// Raw TCP
     Raw,
+    /// Browser terminal over HTTP and WebSockets
+    Web,
 }
 
 /// BBS system limits
@@ -37,6 +41,23 @@ pub struct SystemLimits {
     pub min_password_length: u8,
     /// Maximum password attempts before lockout
     pub max_password_attempts: u8,
+    /// Minutes of inactivity before an idle session is disconnected
+    #[serde(default = "default_idle_timeout_minutes")]
+    pub idle_timeout_minutes: u32,
+    /// File points awarded per `upload_file_point_base_kb` uploaded (0 = none)
+    #[serde(default)]
+    pub upload_file_point_ratio: u8,
+    /// Upload size (KB) that earns `upload_file_point_ratio` file points
+    #[serde(default = "default_upload_file_point_base_kb")]
+    pub upload_file_point_base_kb: u32,
+}
+
+fn default_idle_timeout_minutes() -> u32 {
+    15
+}
+
+fn default_upload_file_point_base_kb() -> u32 {
+    100
 }
 
 impl Default for SystemLimits {
@@ -49,6 +70,9 @@ impl Default for SystemLimits {
             max_message_length: 65536,         // 64 KB
             min_password_length: 6,
             max_password_attempts: 3,
+            idle_timeout_minutes: default_idle_timeout_minutes(),
+            upload_file_point_ratio: 0,
+            upload_file_point_base_kb: default_upload_file_point_base_kb(),
         }
     }
 }
@@ -107,6 +131,20 @@ pub struct BbsPaths {
     pub temp_dir: PathBuf,
     /// Door games directory
     pub doors_dir: PathBuf,
+    /// Theme directory
+    #[serde(default = "default_themes_dir")]
+    pub themes_dir: PathBuf,
+    /// Menu definition directory
+    #[serde(default = "default_menus_dir")]
+    pub menus_dir: PathBuf,
+}
+
+fn default_themes_dir() -> PathBuf {
+    PathBuf::from("./themes")
+}
+
+fn default_menus_dir() -> PathBuf {
+    PathBuf::from("./config/menus")
 }
 
 impl Default for BbsPaths {
@@ -119,11 +157,27 @@ impl Default for BbsPaths {
             logs_dir: base.join("logs"),
             temp_dir: base.join("temp"),
             doors_dir: base.join("doors"),
+            themes_dir: default_themes_dir(),
+            menus_dir: default_menus_dir(),
             data_dir: base,
         }
     }
 }
 
+/// User account storage backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum UserStorage {
+    /// Complete user records in `users.json` in the users directory
+    #[default]
+    Json,
+    /// Impulse 7.1 `USER.LST` in the users directory
+    ///
+    /// Fields without a Pascal equivalent are not preserved. That includes
+    /// password hashes, so the server refuses to start with this storage;
+    /// import a legacy `USER.LST` into `Json` storage instead.
+    UserLst,
+}
+
 /// Network server configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
@@ -148,6 +202,161 @@ impl Default for ServerConfig {
     }
 }
 
+/// FidoNet configuration
+///
+/// Addresses are written as `zone:net/node[.point]`. Without this section the
+/// server runs no mailer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FidonetConfig {
+    /// Our addresses, main address first
+    pub addresses: Vec<String>,
+    /// Directory received packets and bundles are stored in
+    pub inbound: PathBuf,
+    /// BinkleyTerm-style outbound directory for our main zone
+    pub outbound: PathBuf,
+    /// Address to accept BinkP calls on, such as "0.0.0.0:24554" (none to
+    /// only call out)
+    #[serde(default)]
+    pub binkp_bind: Option<String>,
+    /// Hub mail without a direct link is routed through
+    #[serde(default)]
+    pub hub: Option<String>,
+    /// Origin line text for exported echomail (defaults to the BBS name)
+    #[serde(default)]
+    pub origin: Option<String>,
+    /// JAM base for NetMail (defaults to "netmail" in the messages directory)
+    #[serde(default)]
+    pub netmail_path: Option<PathBuf>,
+    /// Security level needed to write NetMail
+    #[serde(default = "default_netmail_security")]
+    pub netmail_security: u8,
+    /// Security level needed to send NetMail crash or attach files to it
+    #[serde(default = "default_netmail_crash_security")]
+    pub netmail_crash_security: u8,
+    /// Systems mail is exchanged with
+    #[serde(default)]
+    pub links: Vec<FidonetLink>,
+}
+
+fn default_netmail_security() -> u8 {
+    50
+}
+
+fn default_netmail_crash_security() -> u8 {
+    100
+}
+
+impl Default for FidonetConfig {
+    fn default() -> Self {
+        Self {
+            addresses: Vec::new(),
+            inbound: PathBuf::from("./data/fidonet/inbound"),
+            outbound: PathBuf::from("./data/fidonet/outbound"),
+            binkp_bind: None,
+            hub: None,
+            origin: None,
+            netmail_path: None,
+            netmail_security: default_netmail_security(),
+            netmail_crash_security: default_netmail_crash_security(),
+            links: Vec::new(),
+        }
+    }
+}
+
+/// A FidoNet link
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FidonetLink {
+    /// Link address
+    pub address: String,
+    /// Host name, or "host:port", to call the link at (none to never call)
+    #[serde(default)]
+    pub host: Option<String>,
+    /// BinkP session password
+    #[serde(default)]
+    pub session_password: Option<String>,
+    /// Packet password
+    #[serde(default)]
+    pub packet_password: Option<String>,
+    /// Minutes between calls (0 = only call when crash mail is waiting)
+    #[serde(default)]
+    pub poll_minutes: u32,
+}
+
+/// Storage format of a message area
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MessageBaseFormat {
+    /// JAM base (.jhr/.jdt/.jdx)
+    #[default]
+    Jam,
+    /// Hudson base (.msg/.idx), read-only
+    Hudson,
+}
+
+/// A message conference, grouping message areas as MCONF.DAT did
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MessageConferenceConfig {
+    /// Short tag areas refer to the conference by
+    pub tag: String,
+    /// Name shown to callers
+    pub name: String,
+    /// Security level needed to join the conference
+    #[serde(default)]
+    pub security: u8,
+}
+
+/// A message area, as a board in BOARDS.DAT
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageAreaConfig {
+    /// Short unique tag
+    pub tag: String,
+    /// Board number (1-254) the area keeps in each caller's newscan record
+    pub board: u8,
+    /// Name shown to callers
+    pub name: String,
+    /// Base path without extension
+    pub path: PathBuf,
+    /// Storage format
+    #[serde(default)]
+    pub format: MessageBaseFormat,
+    /// Security level needed to read the area
+    #[serde(default)]
+    pub read_security: u8,
+    /// Security level needed to post in the area
+    #[serde(default = "default_post_security")]
+    pub post_security: u8,
+    /// Echomail tag, if the area is exchanged over FidoNet
+    #[serde(default)]
+    pub echo_tag: Option<String>,
+    /// Addresses of the FidoNet links the echomail area is exchanged with
+    #[serde(default)]
+    pub echo_links: Vec<String>,
+    /// Tag of the conference the area belongs to (none for areas shown in
+    /// every conference)
+    #[serde(default)]
+    pub conference: Option<String>,
+}
+
+fn default_post_security() -> u8 {
+    10
+}
+
+impl Default for MessageAreaConfig {
+    fn default() -> Self {
+        Self {
+            tag: String::new(),
+            board: 0,
+            name: String::new(),
+            path: PathBuf::new(),
+            format: MessageBaseFormat::default(),
+            read_security: 0,
+            post_security: default_post_security(),
+            echo_tag: None,
+            echo_links: Vec::new(),
+            conference: None,
+        }
+    }
+}
+
 /// Complete BBS system configuration
 ///
 /// The main configuration structure for the Impulse 7.1 BBS system.
@@ -191,6 +400,23 @@ pub struct BbsConfig {
     /// Security settings
     pub security: SecuritySettings,
 
+    /// User account storage backend
+    #[serde(default)]
+    pub user_storage: UserStorage,
+
+    /// FidoNet mailer, tosser and scanner (none to disable)
+    #[serde(default)]
+    pub fidonet: Option<FidonetConfig>,
+
+    /// Message conferences
+    #[serde(default)]
+    pub message_conferences: Vec<MessageConferenceConfig>,
+
+    /// Message areas (none for a single "general" area in the messages
+    /// directory)
+    #[serde(default)]
+    pub message_areas: Vec<MessageAreaConfig>,
+
     /// Enable web admin panel
     pub enable_web_admin: bool,
 
@@ -218,6 +444,10 @@ impl Default for BbsConfig {
             paths: BbsPaths::default(),
             limits: SystemLimits::default(),
             security: SecuritySettings::default(),
+            user_storage: UserStorage::default(),
+            fidonet: None,
+            message_conferences: Vec::new(),
+            message_areas: Vec::new(),
             enable_web_admin: true,
             web_admin_port: 8080,
             enable_ansi: true,
@@ -324,6 +554,50 @@ impl BbsConfig {
             ));
         }
 
+        // Validate FidoNet settings
+        if let Some(fidonet) = &self.fidonet
+            && fidonet.addresses.is_empty()
+        {
+            return Err(Error::Config(
+                "FidoNet needs at least one address".to_string(),
+            ));
+        }
+
+        // Validate message conferences and areas
+        let mut conference_tags = HashSet::new();
+        for conference in &self.message_conferences {
+            if conference.tag.is_empty() || !conference_tags.insert(conference.tag.as_str()) {
+                return Err(Error::Config(format!(
+                    "Message conference tag '{}' is empty or used twice",
+                    conference.tag
+                )));
+            }
+        }
+        let mut area_tags = HashSet::new();
+        let mut boards = HashSet::new();
+        for area in &self.message_areas {
+            if area.tag.is_empty() || !area_tags.insert(area.tag.as_str()) {
+                return Err(Error::Config(format!(
+                    "Message area tag '{}' is empty or used twice",
+                    area.tag
+                )));
+            }
+            if !(1..=MAX_BOARDS).contains(&usize::from(area.board)) || !boards.insert(area.board) {
+                return Err(Error::Config(format!(
+                    "Message area '{}' needs a board number from 1 to {} that no other area uses",
+                    area.tag, MAX_BOARDS
+                )));
+            }
+            if let Some(conference) = &area.conference
+                && !conference_tags.contains(conference.as_str())
+            {
+                return Err(Error::Config(format!(
+                    "Message area '{}' is in unknown conference '{}'",
+                    area.tag, conference
+                )));
+            }
+        }
+
         Ok(())
     }
 
@@ -491,6 +765,57 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_fidonet_needs_address() {
+        let mut config = BbsConfig {
+            fidonet: Some(FidonetConfig::default()),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+
+        if let Some(fidonet) = &mut config.fidonet {
+            fidonet.addresses.push("1:234/5".to_string());
+        }
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_message_area_tags() {
+        let area = |tag: &str, board: u8, conference: Option<&str>| MessageAreaConfig {
+            tag: tag.to_string(),
+            board,
+            name: tag.to_string(),
+            path: PathBuf::from(tag),
+            conference: conference.map(str::to_string),
+            ..Default::default()
+        };
+        let mut config = BbsConfig {
+            message_conferences: vec![MessageConferenceConfig {
+                tag: "fido".to_string(),
+                name: "FidoNet".to_string(),
+                security: 20,
+            }],
+            message_areas: vec![area("general", 1, None), area("fidotest", 2, Some("fido"))],
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(config.message_areas[0].post_security, 10);
+
+        config.message_areas.push(area("general", 3, None));
+        assert!(config.validate().is_err());
+
+        config.message_areas[2] = area("other", 3, Some("missing"));
+        assert!(config.validate().is_err());
+
+        // Board numbers must be in range and not shared
+        config.message_areas[2] = area("other", 2, None);
+        assert!(config.validate().is_err());
+        config.message_areas[2] = area("other", 0, None);
+        assert!(config.validate().is_err());
+        config.message_areas[2] = area("other", 255, None);
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_primary_server() {
         let config = BbsConfig::default();
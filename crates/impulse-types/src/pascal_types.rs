@@ -205,21 +205,21 @@ impl ArFlags {
 /// Download scan flags (Pascal: `dlnscan = set of 0..96`)
 ///
 /// Represents which file areas should be scanned for new files.
-/// Stored as a 128-bit bitset (16 bytes) for 97 possible file areas (0-96).
+/// Stored as a 13-byte bitset, the size Turbo Pascal uses for a set of 0..96.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
 pub struct DownloadScanFlags {
-    /// Bitset for file areas 0-96 (stored as 16 bytes in Pascal)
-    bits: [u8; 16],
+    /// Bitset for file areas 0-96 (stored as 13 bytes in Pascal)
+    bits: [u8; 13],
 }
 
 impl DownloadScanFlags {
-    /// Create from Pascal byte array (16 bytes)
-    pub fn from_pascal_bytes(bytes: [u8; 16]) -> Self {
+    /// Create from Pascal byte array (13 bytes)
+    pub fn from_pascal_bytes(bytes: [u8; 13]) -> Self {
         DownloadScanFlags { bits: bytes }
     }
 
-    /// Convert to Pascal byte array (16 bytes)
-    pub fn to_pascal_bytes(&self) -> [u8; 16] {
+    /// Convert to Pascal byte array (13 bytes)
+    pub fn to_pascal_bytes(&self) -> [u8; 13] {
         self.bits
     }
 
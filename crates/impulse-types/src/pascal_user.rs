@@ -23,12 +23,18 @@ use crate::user_flags::UserFlags;
 pub struct PascalString<const N: usize> {
     /// Length of the string (0..=N)
     #[br(temp)]
-    #[bw(calc = data.len().min(N) as u8)]
+    #[bw(calc = data.iter().position(|&b| b == 0).unwrap_or(N).min(N) as u8)]
     #[allow(unused)]
     length: u8,
 
     /// String data (exactly N bytes, padded with zeros)
-    #[br(count = N)]
+    ///
+    /// Bytes past the length are leftovers from earlier, longer values in
+    /// files written by Turbo Pascal, so they are cleared on read.
+    #[br(count = N, map = |mut data: Vec<u8>| {
+        data[usize::from(length).min(N)..].fill(0);
+        data
+    })]
     data: Vec<u8>,
 }
 
@@ -89,7 +95,7 @@ impl<const N: usize> Default for PascalString<N> {
 ///
 /// # Binary Size
 ///
-/// The total size must match the Pascal record size (approximately 1200-1400 bytes).
+/// The total size must match the Pascal record size of 843 bytes.
 #[binrw]
 #[derive(Debug, Clone)]
 pub struct PascalUserRec {
@@ -190,7 +196,7 @@ pub struct PascalUserRec {
     pub zzqscn: [bool; 64],
 
     /// Download scan flags (Pascal: dlnscan, 16 bytes for set of 0..96)
-    #[br(map = |bytes: [u8; 16]| DownloadScanFlags::from_pascal_bytes(bytes))]
+    #[br(map = |bytes: [u8; 13]| DownloadScanFlags::from_pascal_bytes(bytes))]
     #[bw(map = |flags: &DownloadScanFlags| flags.to_pascal_bytes())]
     pub zzdlnscn: DownloadScanFlags,
 
@@ -485,6 +491,20 @@ mod tests {
         assert_eq!(ps2.to_string(), "Hello");
     }
 
+    #[test]
+    fn test_pascal_string_honors_length_byte() {
+        use binrw::{BinRead, BinWrite};
+
+        // Stale bytes after the length are ignored, as in Turbo Pascal
+        let mut raw = std::io::Cursor::new(b"\x05SYSOPDD\0".to_vec());
+        let ps = PascalString::<8>::read_le(&mut raw).unwrap();
+        assert_eq!(ps.to_string(), "SYSOP");
+
+        let mut out = std::io::Cursor::new(Vec::new());
+        ps.write_le(&mut out).unwrap();
+        assert_eq!(out.into_inner(), b"\x05SYSOP\0\0\0");
+    }
+
     #[test]
     fn test_pascal_string_default() {
         let ps: PascalString<20> = PascalString::default();
@@ -500,6 +520,15 @@ mod tests {
         assert_eq!(user.sl, 10); // New user level
     }
 
+    #[test]
+    fn test_pascal_user_rec_size() {
+        use binrw::BinWrite;
+
+        let mut buf = std::io::Cursor::new(Vec::new());
+        PascalUserRec::default().write_le(&mut buf).unwrap();
+        assert_eq!(buf.into_inner().len(), 843);
+    }
+
     #[test]
     fn test_pascal_user_rec_new() {
         let user = PascalUserRec::new("TestUser");
@@ -82,6 +82,28 @@ impl UserId {
     pub const fn as_uuid(&self) -> &Uuid {
         &self.0
     }
+
+    /// Fold the ID into the 32-bit number used by legacy records
+    ///
+    /// JAM lastread records and upload statistics key users by number. All
+    /// four 32-bit words of the UUID are folded together, but 128 bits do
+    /// not fit in 32, so two users can share a number; records keyed by it
+    /// must also check something else, such as the user's name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use impulse_types::user::UserId;
+    /// use uuid::Uuid;
+    ///
+    /// let id = UserId::from_uuid(Uuid::from_u128(0x1_0000_0002_0000_0004_0000_0008));
+    /// assert_eq!(id.as_u32(), 0x1 ^ 0x2 ^ 0x4 ^ 0x8);
+    /// ```
+    #[must_use]
+    pub const fn as_u32(&self) -> u32 {
+        let value = self.0.as_u128();
+        (value ^ (value >> 32) ^ (value >> 64) ^ (value >> 96)) as u32
+    }
 }
 
 impl Default for UserId {
@@ -160,6 +182,12 @@ pub struct User {
 
     /// SysOp notes about this user
     pub sysop_note: Option<String>,
+
+    /// Argon2 password hash in PHC string format
+    ///
+    /// `None` until a password is set; such accounts cannot log in.
+    #[serde(default)]
+    pub password_hash: Option<String>,
 }
 
 impl User {
@@ -202,6 +230,7 @@ impl User {
             is_active: true,
             is_locked: false,
             sysop_note: None,
+            password_hash: None,
         })
     }
 
@@ -555,6 +584,8 @@ impl User {
                 let note = rec.note.to_string();
                 if note.is_empty() { None } else { Some(note) }
             },
+            // Legacy passwords are plain text and must be hashed by the caller
+            password_hash: None,
         })
     }
 }
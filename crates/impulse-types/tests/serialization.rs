@@ -4,13 +4,16 @@
 //! in both JSON and binary (bincode) formats.
 
 use impulse_types::{
-    config::{BbsConfig, BbsPaths, Protocol, SecuritySettings, ServerConfig, SystemLimits},
+    config::{
+        BbsConfig, BbsPaths, Protocol, SecuritySettings, ServerConfig, SystemLimits, UserStorage,
+    },
     file::FileEntry,
     message::Message,
     security::SecurityLevel,
     user::User,
     user_stats::UserStats,
 };
+use std::path::PathBuf;
 
 #[test]
 fn test_user_json_roundtrip() {
@@ -28,6 +31,22 @@ fn test_user_json_roundtrip() {
     assert_eq!(user.security_level(), deserialized.security_level());
 }
 
+#[test]
+fn test_user_json_without_password_hash() {
+    let mut user = User::new("TestUser").expect("Valid username");
+    user.password_hash = Some("$argon2id$v=19$m=19456,t=2,p=1$c2FsdA$aGFzaA".to_string());
+
+    let json = serde_json::to_string(&user).expect("Failed to serialize to JSON");
+    let deserialized: User = serde_json::from_str(&json).expect("Failed to deserialize from JSON");
+    assert_eq!(deserialized.password_hash, user.password_hash);
+
+    // Records written before passwords were stored still load
+    let mut value = serde_json::to_value(&user).unwrap();
+    value.as_object_mut().unwrap().remove("password_hash");
+    let legacy: User = serde_json::from_value(value).expect("Failed to deserialize");
+    assert_eq!(legacy.password_hash, None);
+}
+
 #[test]
 fn test_user_bincode_roundtrip() {
     let user = User::new("TestUser").expect("Valid username");
@@ -195,6 +214,8 @@ fn test_bbs_config_json_roundtrip() {
             logs_dir: "/tmp/bbs/logs".into(),
             temp_dir: "/tmp/bbs/temp".into(),
             doors_dir: "/tmp/bbs/doors".into(),
+            themes_dir: "/tmp/bbs/themes".into(),
+            menus_dir: "/tmp/bbs/menus".into(),
         },
         limits: SystemLimits {
             max_connections: 32,
@@ -204,6 +225,9 @@ fn test_bbs_config_json_roundtrip() {
             max_message_length: 4096,
             min_password_length: 8,
             max_password_attempts: 3,
+            idle_timeout_minutes: 15,
+            upload_file_point_ratio: 1,
+            upload_file_point_base_kb: 100,
         },
         security: SecuritySettings {
             require_strong_passwords: true,
@@ -214,6 +238,10 @@ fn test_bbs_config_json_roundtrip() {
             enable_audit_logging: true,
             require_email_verification: false,
         },
+        user_storage: UserStorage::Json,
+        fidonet: None,
+        message_conferences: Vec::new(),
+        message_areas: Vec::new(),
         enable_web_admin: true,
         web_admin_port: 8080,
         enable_ansi: true,
@@ -242,6 +270,33 @@ fn test_bbs_config_json_roundtrip() {
     );
 }
 
+#[test]
+fn test_bbs_config_missing_new_fields_use_defaults() {
+    // Configurations written before these fields existed must still load
+    let mut value = serde_json::to_value(BbsConfig::default()).unwrap();
+    let paths = value["paths"].as_object_mut().unwrap();
+    paths.remove("themes_dir");
+    paths.remove("menus_dir");
+    value["limits"]
+        .as_object_mut()
+        .unwrap()
+        .remove("idle_timeout_minutes");
+
+    let config: BbsConfig = serde_json::from_value(value).expect("Failed to deserialize");
+    assert_eq!(config.paths.themes_dir, PathBuf::from("./themes"));
+    assert_eq!(config.paths.menus_dir, PathBuf::from("./config/menus"));
+    assert_eq!(config.limits.idle_timeout_minutes, 15);
+}
+
+#[test]
+fn test_bbs_config_user_storage_defaults_to_json() {
+    let mut value = serde_json::to_value(BbsConfig::default()).unwrap();
+    value.as_object_mut().unwrap().remove("user_storage");
+
+    let config: BbsConfig = serde_json::from_value(value).expect("Failed to deserialize");
+    assert_eq!(config.user_storage, UserStorage::Json);
+}
+
 #[test]
 fn test_security_level_json_roundtrip() {
     let levels = vec![
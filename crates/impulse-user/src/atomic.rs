@@ -0,0 +1,68 @@
+//! Crash-safe file replacement
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Replace the contents of `path` with `data` atomically
+///
+/// The data is written and synced to a temporary file in the same directory,
+/// which is then renamed over `path`. A crash at any point leaves either the
+/// old or the new file in place, never a partially written one.
+pub(crate) fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+
+    let mut tmp_name = file_name.to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = dir.join(tmp_name);
+
+    let result = (|| {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+
+        // Persist the rename itself; directories cannot be opened on Windows
+        #[cfg(unix)]
+        File::open(dir)?.sync_all()?;
+
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic_replaces_contents() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("users.json");
+
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert!(!dir.path().join("users.json.tmp").exists());
+    }
+
+    #[test]
+    fn test_write_atomic_missing_directory() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("missing").join("users.json");
+
+        assert!(write_atomic(&path, b"data").is_err());
+        assert!(!path.exists());
+    }
+}
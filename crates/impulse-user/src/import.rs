@@ -0,0 +1,174 @@
+//! Import of legacy Impulse 7.1 user files
+
+use crate::{UserManager, read_user_lst};
+use impulse_auth::PasswordHasher;
+use impulse_types::error::Result;
+use impulse_types::user::User;
+use std::path::Path;
+
+/// Outcome of a `USER.LST` import
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserLstImport {
+    /// Users added to the target manager
+    pub imported: usize,
+    /// Records skipped because the username already exists
+    pub skipped_existing: usize,
+    /// Records skipped because they are marked deleted
+    pub skipped_deleted: usize,
+}
+
+/// Import users from a legacy Impulse 7.1 `USER.LST` into `manager`
+///
+/// Records are decoded with the `PascalUserRec` layout. Deleted records and
+/// usernames that already exist in `manager` are skipped, so running an
+/// import twice does not duplicate users. Records that cannot be converted
+/// are logged and skipped.
+///
+/// `USER.LST` keeps passwords in plain text; they are stored as Argon2
+/// hashes. Impulse 7.1 upper-cased passwords as they were typed, so imported
+/// callers log in with their old password in capitals.
+///
+/// # Errors
+///
+/// Returns `Error::UserManagement` if the file cannot be read, or any error
+/// from `manager` while creating users.
+///
+/// # Examples
+///
+/// ```no_run
+/// use impulse_user::{JsonUserManager, import_user_lst};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut manager = JsonUserManager::open("./data/users/users.json").await?;
+/// let summary = import_user_lst(&mut manager, "/old-bbs/USER.LST").await?;
+/// println!("Imported {} users", summary.imported);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn import_user_lst<M>(manager: &mut M, path: impl AsRef<Path>) -> Result<UserLstImport>
+where
+    M: UserManager + ?Sized,
+{
+    let path = path.as_ref();
+    let hasher = PasswordHasher::new();
+    let mut summary = UserLstImport::default();
+
+    for (pos, rec) in read_user_lst(path)? {
+        let mut user = match User::from_pascal(&rec) {
+            Ok(user) => user,
+            Err(e) => {
+                tracing::warn!(
+                    file_path = ?path,
+                    position = pos,
+                    error = %e,
+                    "Failed to convert user record, skipping"
+                );
+                continue;
+            }
+        };
+
+        if !user.is_active {
+            summary.skipped_deleted += 1;
+            continue;
+        }
+        if manager.find_by_username(user.username()).await?.is_some() {
+            tracing::info!(username = %user.username(), "Skipping existing user");
+            summary.skipped_existing += 1;
+            continue;
+        }
+
+        let password = rec.pw.to_string();
+        if password.is_empty() {
+            tracing::warn!(
+                username = %user.username(),
+                "Legacy user has no password and cannot log in until one is set"
+            );
+        } else {
+            user.password_hash = Some(hasher.hash_password(&password)?);
+        }
+
+        manager.create_user(user).await?;
+        summary.imported += 1;
+    }
+
+    tracing::info!(
+        file_path = ?path,
+        imported = summary.imported,
+        skipped_existing = summary.skipped_existing,
+        skipped_deleted = summary.skipped_deleted,
+        "Imported legacy user file"
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryUserManager;
+    use binrw::BinWrite;
+    use impulse_types::pascal_user::{PascalString, PascalUserRec};
+
+    fn write_user_lst(path: &Path, records: &[PascalUserRec]) {
+        let mut buf = std::io::Cursor::new(Vec::new());
+        for rec in records {
+            rec.write_le(&mut buf).unwrap();
+        }
+        std::fs::write(path, buf.into_inner()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_import_user_lst() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("USER.LST");
+
+        // Write a legacy user file
+        let mut sysop = PascalUserRec::new("SysOp");
+        sysop.pw = PascalString::from_string("FLARE");
+        sysop.sl = 255;
+        sysop.uploads = 42;
+        let caller = PascalUserRec::new("Caller");
+        let mut gone = PascalUserRec::new("Gone");
+        gone.deleted = true;
+        write_user_lst(&path, &[sysop, caller, gone]);
+
+        let mut manager = InMemoryUserManager::new();
+        manager
+            .create_user(User::new("caller").unwrap())
+            .await
+            .unwrap();
+
+        let summary = import_user_lst(&mut manager, &path).await.unwrap();
+        assert_eq!(
+            summary,
+            UserLstImport {
+                imported: 1,
+                skipped_existing: 1,
+                skipped_deleted: 1,
+            }
+        );
+
+        let sysop = manager.find_by_username("sysop").await.unwrap().unwrap();
+        assert_eq!(sysop.security_level().value(), 255);
+        assert_eq!(sysop.stats.uploads, 42);
+
+        // The legacy password is kept only as a hash
+        let hash = sysop.password_hash.as_deref().unwrap();
+        assert!(hash.starts_with("$argon2"));
+        assert!(PasswordHasher::new().verify_password("FLARE", hash).is_ok());
+
+        // A second import changes nothing
+        let again = import_user_lst(&mut manager, &path).await.unwrap();
+        assert_eq!(again.imported, 0);
+        assert_eq!(manager.count_users().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_import_missing_file() {
+        let mut manager = InMemoryUserManager::new();
+        assert!(
+            import_user_lst(&mut manager, "/nonexistent/USER.LST")
+                .await
+                .is_err()
+        );
+    }
+}
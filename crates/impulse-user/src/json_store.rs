@@ -0,0 +1,311 @@
+//! JSON-file user storage
+
+use crate::UserManager;
+use crate::atomic::write_atomic;
+use async_trait::async_trait;
+use impulse_types::{
+    error::{Error, Result},
+    user::{User, UserId},
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// Durable user manager storing complete user records as JSON
+///
+/// Unlike [`FileUserManager`](crate::FileUserManager), which is limited to the
+/// fields of the Pascal `USER.LST` record, every field of [`User`] is kept,
+/// including its ID. Each change rewrites the file atomically so a crash never
+/// leaves a truncated user base behind.
+///
+/// # Examples
+///
+/// ```no_run
+/// use impulse_user::{JsonUserManager, UserManager};
+/// use impulse_types::user::User;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut manager = JsonUserManager::open("./data/users/users.json").await?;
+///
+/// // Changes are written to disk immediately
+/// manager.create_user(User::new("johndoe")?).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct JsonUserManager {
+    path: PathBuf,
+    users: Arc<RwLock<HashMap<UserId, User>>>,
+}
+
+impl JsonUserManager {
+    /// Create an empty manager that will store users at `path`
+    ///
+    /// Nothing is read or written until [`load`](Self::load) or a change.
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            users: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Open the user store at `path`, loading it if it exists
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::UserManagement` if an existing file cannot be read or
+    /// parsed.
+    pub async fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let mut manager = Self::new(path);
+        if manager.path.exists() {
+            manager.load().await?;
+        }
+        Ok(manager)
+    }
+
+    /// Load users from the JSON file, replacing any users in memory
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::UserManagement` if the file cannot be read or parsed.
+    pub async fn load(&mut self) -> Result<()> {
+        let data = tokio::fs::read(&self.path).await.map_err(|e| {
+            Error::UserManagement(format!("Failed to read users from {:?}: {}", self.path, e))
+        })?;
+
+        let users: Vec<User> = serde_json::from_slice(&data).map_err(|e| {
+            tracing::error!(
+                file_path = ?self.path,
+                error = %e,
+                "Failed to parse user store"
+            );
+            Error::UserManagement(format!("Failed to parse users in {:?}: {}", self.path, e))
+        })?;
+
+        let user_count = users.len();
+        *self.users.write().unwrap() = users.into_iter().map(|u| (u.id(), u)).collect();
+
+        tracing::info!(
+            file_path = ?self.path,
+            user_count = user_count,
+            "Successfully loaded users from file"
+        );
+        Ok(())
+    }
+
+    /// Write all users to the JSON file atomically
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::UserManagement` if the file cannot be written.
+    pub async fn save(&self) -> Result<()> {
+        let data = {
+            let users = self.users.read().unwrap();
+            let mut records: Vec<&User> = users.values().collect();
+            records.sort_by(|a, b| a.username().cmp(b.username()));
+            serde_json::to_vec_pretty(&records)
+                .map_err(|e| Error::UserManagement(format!("Failed to serialize users: {}", e)))?
+        };
+
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || write_atomic(&path, &data))
+            .await
+            .map_err(|e| Error::Internal(format!("User store write task failed: {}", e)))?
+            .map_err(|e| {
+                tracing::error!(
+                    file_path = ?self.path,
+                    error = %e,
+                    "Failed to write user store"
+                );
+                Error::UserManagement(format!("Failed to write users to {:?}: {}", self.path, e))
+            })
+    }
+
+    /// Get the file path
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[async_trait]
+impl UserManager for JsonUserManager {
+    async fn create_user(&mut self, user: User) -> Result<()> {
+        let user_id = user.id();
+        let username = user.username().to_string();
+
+        {
+            let mut users = self.users.write().unwrap();
+            if users
+                .values()
+                .any(|u| u.username().eq_ignore_ascii_case(&username))
+            {
+                return Err(Error::AlreadyExists(format!(
+                    "User '{}' already exists",
+                    username
+                )));
+            }
+            users.insert(user_id, user);
+        }
+
+        if let Err(e) = self.save().await {
+            // Keep memory consistent with what is on disk
+            self.users.write().unwrap().remove(&user_id);
+            return Err(e);
+        }
+
+        tracing::info!(user_id = ?user_id, username = %username, "User created");
+        Ok(())
+    }
+
+    async fn get_user(&self, id: UserId) -> Result<User> {
+        let users = self.users.read().unwrap();
+        users
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| Error::NotFound(format!("User with ID {:?} not found", id)))
+    }
+
+    async fn find_by_username(&self, username: &str) -> Result<Option<User>> {
+        let users = self.users.read().unwrap();
+        Ok(users
+            .values()
+            .find(|u| u.username().eq_ignore_ascii_case(username))
+            .cloned())
+    }
+
+    async fn update_user(&mut self, user: User) -> Result<()> {
+        let user_id = user.id();
+
+        let previous = {
+            let mut users = self.users.write().unwrap();
+            if !users.contains_key(&user_id) {
+                return Err(Error::NotFound(format!(
+                    "User with ID {:?} not found",
+                    user_id
+                )));
+            }
+            users.insert(user_id, user)
+        };
+
+        if let Err(e) = self.save().await {
+            if let Some(previous) = previous {
+                self.users.write().unwrap().insert(user_id, previous);
+            }
+            return Err(e);
+        }
+
+        tracing::debug!(user_id = ?user_id, "User updated");
+        Ok(())
+    }
+
+    async fn delete_user(&mut self, id: UserId) -> Result<()> {
+        let removed = self
+            .users
+            .write()
+            .unwrap()
+            .remove(&id)
+            .ok_or_else(|| Error::NotFound(format!("User with ID {:?} not found", id)))?;
+
+        if let Err(e) = self.save().await {
+            self.users.write().unwrap().insert(id, removed);
+            return Err(e);
+        }
+
+        tracing::info!(user_id = ?id, username = %removed.username(), "User deleted");
+        Ok(())
+    }
+
+    async fn list_users(&self) -> Result<Vec<User>> {
+        let users = self.users.read().unwrap();
+        Ok(users.values().cloned().collect())
+    }
+
+    async fn count_users(&self) -> Result<usize> {
+        let users = self.users.read().unwrap();
+        Ok(users.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use impulse_types::security::SecurityLevel;
+
+    #[tokio::test]
+    async fn test_changes_survive_reopen() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("users.json");
+
+        let mut manager = JsonUserManager::open(&path).await.unwrap();
+        let mut user = User::new("alice").unwrap();
+        let user_id = user.id();
+        manager.create_user(user.clone()).await.unwrap();
+        manager
+            .create_user(User::new("bob").unwrap())
+            .await
+            .unwrap();
+
+        user.set_security_level(SecurityLevel::new(100));
+        user.sysop_note = Some("Trusted".to_string());
+        manager.update_user(user).await.unwrap();
+
+        let bob = manager.find_by_username("BOB").await.unwrap().unwrap();
+        manager.delete_user(bob.id()).await.unwrap();
+
+        let reopened = JsonUserManager::open(&path).await.unwrap();
+        assert_eq!(reopened.count_users().await.unwrap(), 1);
+
+        // IDs and fields without a Pascal equivalent are preserved
+        let alice = reopened.get_user(user_id).await.unwrap();
+        assert_eq!(alice.security_level().value(), 100);
+        assert_eq!(alice.sysop_note.as_deref(), Some("Trusted"));
+    }
+
+    #[tokio::test]
+    async fn test_open_missing_file_is_empty() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let manager = JsonUserManager::open(dir.path().join("users.json"))
+            .await
+            .unwrap();
+        assert_eq!(manager.count_users().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_username_is_case_insensitive() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut manager = JsonUserManager::new(dir.path().join("users.json"));
+
+        manager
+            .create_user(User::new("Alice").unwrap())
+            .await
+            .unwrap();
+        let result = manager.create_user(User::new("alice").unwrap()).await;
+        assert!(matches!(result, Err(Error::AlreadyExists(_))));
+    }
+
+    #[tokio::test]
+    async fn test_failed_write_leaves_memory_unchanged() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut manager = JsonUserManager::new(dir.path().join("missing").join("users.json"));
+
+        assert!(
+            manager
+                .create_user(User::new("alice").unwrap())
+                .await
+                .is_err()
+        );
+        assert_eq!(manager.count_users().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_file_is_an_error() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("users.json");
+        std::fs::write(&path, "not json").unwrap();
+
+        let result = JsonUserManager::open(&path).await;
+        assert!(matches!(result, Err(Error::UserManagement(_))));
+    }
+}
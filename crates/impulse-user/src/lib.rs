@@ -4,6 +4,8 @@
 //! - UserManager trait defining the API contract
 //! - InMemoryUserManager for testing and development
 //! - FileUserManager for Pascal .DAT file I/O
+//! - JsonUserManager for durable storage of complete user records
+//! - Import of legacy Impulse 7.1 USER.LST files
 //! - Statistics tracking and display
 //! - User settings and preferences management
 //! - User profile display with privacy enforcement
@@ -40,7 +42,10 @@
 //! ```
 
 pub mod achievements;
+mod atomic;
 pub mod directory;
+mod import;
+mod json_store;
 pub mod privacy;
 pub mod profile;
 pub mod settings;
@@ -49,10 +54,11 @@ pub mod stats;
 use async_trait::async_trait;
 use impulse_types::{
     error::{Error, Result},
+    pascal_user::PascalUserRec,
     user::{User, UserId},
 };
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 
 /// User management trait
@@ -371,69 +377,26 @@ impl FileUserManager {
     /// # }
     /// ```
     pub async fn load(&mut self) -> Result<()> {
-        use binrw::BinRead;
-        use impulse_types::pascal_user::PascalUserRec;
-        use std::fs::File;
-        use std::io::{BufReader, Seek};
-
         tracing::debug!(
             file_path = ?self.path,
             "Loading users from file"
         );
 
-        let file = File::open(&self.path).map_err(|e| {
-            tracing::error!(
-                file_path = ?self.path,
-                error = %e,
-                "Failed to open USER.LST file"
-            );
-            Error::UserManagement(format!("Failed to open USER.LST at {:?}: {}", self.path, e))
-        })?;
-
-        let mut reader = BufReader::new(file);
         let mut users_map = HashMap::new();
-
-        // Read records until EOF
-        loop {
-            // Save position before read attempt
-            let pos = reader.stream_position().map_err(|e| {
-                Error::UserManagement(format!("Failed to get stream position: {}", e))
-            })?;
-
-            match PascalUserRec::read_le(&mut reader) {
-                Ok(rec) => {
-                    // Convert to modern User
-                    match User::from_pascal(&rec) {
-                        Ok(user) => {
-                            users_map.insert(user.id(), user);
-                        }
-                        Err(e) => {
-                            // Log warning but continue (some records might be corrupted)
-                            tracing::warn!(
-                                file_path = ?self.path,
-                                position = pos,
-                                error = %e,
-                                "Failed to convert user record, skipping"
-                            );
-                        }
-                    }
+        for (pos, rec) in read_user_lst(&self.path)? {
+            // Convert to modern User
+            match User::from_pascal(&rec) {
+                Ok(user) => {
+                    users_map.insert(user.id(), user);
                 }
                 Err(e) => {
-                    // Check if EOF (normal termination)
-                    if reader.stream_position().map(|p| p == pos).unwrap_or(true) {
-                        break; // EOF reached
-                    } else {
-                        tracing::error!(
-                            file_path = ?self.path,
-                            position = pos,
-                            error = %e,
-                            "Failed to read user record"
-                        );
-                        return Err(Error::UserManagement(format!(
-                            "Failed to read user record at position {}: {}",
-                            pos, e
-                        )));
-                    }
+                    // Log warning but continue (some records might be corrupted)
+                    tracing::warn!(
+                        file_path = ?self.path,
+                        position = pos,
+                        error = %e,
+                        "Failed to convert user record, skipping"
+                    );
                 }
             }
         }
@@ -471,47 +434,50 @@ impl FileUserManager {
     /// ```
     pub async fn save(&self) -> Result<()> {
         use binrw::BinWrite;
-        use std::fs::File;
-        use std::io::BufWriter;
+        use std::io::Cursor;
 
         tracing::debug!(
             file_path = ?self.path,
             "Saving users to file"
         );
 
-        let file = File::create(&self.path).map_err(|e| {
+        let mut writer = Cursor::new(Vec::new());
+        let user_count = {
+            let users = self.users.read().unwrap();
+
+            for user in users.values() {
+                let rec = user.to_pascal();
+                rec.write_le(&mut writer).map_err(|e| {
+                    tracing::error!(
+                        file_path = ?self.path,
+                        username = %user.username(),
+                        error = %e,
+                        "Failed to write user record"
+                    );
+                    Error::UserManagement(format!(
+                        "Failed to write user record for {}: {}",
+                        user.username(),
+                        e
+                    ))
+                })?;
+            }
+
+            users.len()
+        };
+
+        // Replace the file atomically so a crash never truncates USER.LST
+        atomic::write_atomic(&self.path, writer.get_ref()).map_err(|e| {
             tracing::error!(
                 file_path = ?self.path,
                 error = %e,
-                "Failed to create USER.LST file"
+                "Failed to write USER.LST file"
             );
             Error::UserManagement(format!(
-                "Failed to create USER.LST at {:?}: {}",
+                "Failed to write USER.LST at {:?}: {}",
                 self.path, e
             ))
         })?;
 
-        let mut writer = BufWriter::new(file);
-        let users = self.users.read().unwrap();
-        let user_count = users.len();
-
-        for user in users.values() {
-            let rec = user.to_pascal();
-            rec.write_le(&mut writer).map_err(|e| {
-                tracing::error!(
-                    file_path = ?self.path,
-                    username = %user.username(),
-                    error = %e,
-                    "Failed to write user record"
-                );
-                Error::UserManagement(format!(
-                    "Failed to write user record for {}: {}",
-                    user.username(),
-                    e
-                ))
-            })?;
-        }
-
         tracing::info!(
             file_path = ?self.path,
             user_count = user_count,
@@ -538,6 +504,56 @@ impl FileUserManager {
     }
 }
 
+/// Read every record of a USER.LST file with its byte offset
+pub(crate) fn read_user_lst(path: &Path) -> Result<Vec<(u64, PascalUserRec)>> {
+    use binrw::BinRead;
+    use std::fs::File;
+    use std::io::{BufReader, Seek};
+
+    let file = File::open(path).map_err(|e| {
+        tracing::error!(
+            file_path = ?path,
+            error = %e,
+            "Failed to open USER.LST file"
+        );
+        Error::UserManagement(format!("Failed to open USER.LST at {:?}: {}", path, e))
+    })?;
+
+    let mut reader = BufReader::new(file);
+    let mut records = Vec::new();
+
+    // Read records until EOF
+    loop {
+        // Save position before read attempt
+        let pos = reader
+            .stream_position()
+            .map_err(|e| Error::UserManagement(format!("Failed to get stream position: {}", e)))?;
+
+        match PascalUserRec::read_le(&mut reader) {
+            Ok(rec) => records.push((pos, rec)),
+            Err(e) => {
+                // Check if EOF (normal termination)
+                if reader.stream_position().map(|p| p == pos).unwrap_or(true) {
+                    break; // EOF reached
+                } else {
+                    tracing::error!(
+                        file_path = ?path,
+                        position = pos,
+                        error = %e,
+                        "Failed to read user record"
+                    );
+                    return Err(Error::UserManagement(format!(
+                        "Failed to read user record at position {}: {}",
+                        pos, e
+                    )));
+                }
+            }
+        }
+    }
+
+    Ok(records)
+}
+
 #[async_trait]
 impl UserManager for FileUserManager {
     async fn create_user(&mut self, user: User) -> Result<()> {
@@ -665,6 +681,8 @@ impl UserManager for FileUserManager {
 // Re-export commonly used types
 pub use achievements::{Achievement, AchievementChecker, AchievementProgress, UserAchievement};
 pub use directory::UserDirectory;
+pub use import::{UserLstImport, import_user_lst};
+pub use json_store::JsonUserManager;
 pub use privacy::PrivacySettings;
 pub use profile::{ProfileDisplayOptions, ProfileViewer};
 pub use settings::{PasswordStrength, ProtocolSettings, SettingsManager, Theme};
@@ -0,0 +1,187 @@
+//! Browser terminal connection handling
+
+use async_trait::async_trait;
+use axum::extract::ws::{Message, WebSocket};
+use impulse_session::{
+    BbsMessage, Connection, ConnectionError, ConnectionType, SessionEvent, take_utf8_char,
+};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+/// A browser terminal session over an upgraded WebSocket
+///
+/// Speaks the JSON [`BbsMessage`] protocol: text is sent as `output`
+/// messages and keystrokes arrive as `input` messages, so the menu system
+/// can drive a browser exactly like a telnet caller. Raw bytes, such as
+/// CP437 ANSI art or a file transfer, go out unchanged in binary frames.
+/// The page does no local echo; input echo is performed by the server.
+pub struct WebConnection {
+    /// Upgraded WebSocket (behind a mutex only because it is not `Sync`)
+    socket: Mutex<WebSocket>,
+    /// Remote address
+    peer_addr: SocketAddr,
+    /// Bytes received from the browser but not yet consumed
+    pending: VecDeque<u8>,
+    /// Whether the socket is still open
+    connected: bool,
+}
+
+impl WebConnection {
+    /// Create a new connection from an upgraded WebSocket
+    pub(crate) fn new(socket: WebSocket, peer_addr: SocketAddr) -> Self {
+        Self {
+            socket: Mutex::new(socket),
+            peer_addr,
+            pending: VecDeque::new(),
+            connected: true,
+        }
+    }
+
+    /// Get the remote peer address
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+
+    /// Send a protocol message to the browser
+    pub async fn send_message(&mut self, message: &BbsMessage) -> Result<(), ConnectionError> {
+        let json =
+            serde_json::to_string(message).map_err(|e| ConnectionError::Encoding(e.to_string()))?;
+
+        if let Err(e) = self.socket.get_mut().send(Message::Text(json.into())).await {
+            self.connected = false;
+            return Err(ConnectionError::WebSocket(format!("Send error: {}", e)));
+        }
+        Ok(())
+    }
+
+    /// Wait for the next input from the browser
+    ///
+    /// Keep-alive pings are answered here; returns `None` once the browser
+    /// closes or drops the socket.
+    async fn next_input(&mut self) -> Result<Option<Vec<u8>>, ConnectionError> {
+        loop {
+            let message = match self.socket.get_mut().recv().await {
+                Some(Ok(message)) => message,
+                // A socket that fails is unusable; treat it like a hangup
+                Some(Err(e)) => {
+                    debug!(peer = %self.peer_addr, error = %e, "WebSocket dropped");
+                    self.connected = false;
+                    return Ok(None);
+                }
+                None => {
+                    self.connected = false;
+                    return Ok(None);
+                }
+            };
+
+            match message {
+                Message::Text(json) => match serde_json::from_str::<BbsMessage>(&json) {
+                    Ok(BbsMessage::Input { text }) => {
+                        if !text.is_empty() {
+                            return Ok(Some(text.into_bytes()));
+                        }
+                    }
+                    Ok(BbsMessage::Ping) => self.send_message(&BbsMessage::Pong).await?,
+                    Ok(other) => {
+                        debug!(peer = %self.peer_addr, message = ?other, "Ignoring browser message");
+                    }
+                    Err(e) => {
+                        warn!(peer = %self.peer_addr, error = %e, "Invalid browser message");
+                    }
+                },
+                // Raw input from clients that do not speak the JSON protocol
+                Message::Binary(data) => {
+                    if !data.is_empty() {
+                        return Ok(Some(data.to_vec()));
+                    }
+                }
+                Message::Close(_) => {
+                    self.connected = false;
+                    return Ok(None);
+                }
+                // WebSocket-level pings are answered by axum
+                Message::Ping(_) | Message::Pong(_) => {}
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Connection for WebConnection {
+    fn connection_type(&self) -> ConnectionType {
+        ConnectionType::WebSocket
+    }
+
+    fn remote_addr(&self) -> String {
+        self.peer_addr.to_string()
+    }
+
+    async fn send_text(&mut self, data: &str) -> Result<(), ConnectionError> {
+        self.send_message(&BbsMessage::Output {
+            text: data.to_string(),
+        })
+        .await
+    }
+
+    async fn send_bytes(&mut self, data: &[u8]) -> Result<(), ConnectionError> {
+        if let Err(e) = self
+            .socket
+            .get_mut()
+            .send(Message::Binary(data.to_vec().into()))
+            .await
+        {
+            self.connected = false;
+            return Err(ConnectionError::WebSocket(format!("Send error: {}", e)));
+        }
+        Ok(())
+    }
+
+    async fn recv(&mut self) -> Result<Option<Vec<u8>>, ConnectionError> {
+        if !self.pending.is_empty() {
+            return Ok(Some(self.pending.drain(..).collect()));
+        }
+        self.next_input().await
+    }
+
+    async fn close(&mut self) -> Result<(), ConnectionError> {
+        if !self.connected {
+            return Ok(());
+        }
+
+        // Tell the page why the socket is going away before closing it
+        let terminated = BbsMessage::Event {
+            event: SessionEvent::Terminated {
+                reason: "Disconnected".to_string(),
+            },
+        };
+        self.send_message(&terminated).await.ok();
+
+        self.connected = false;
+        self.socket
+            .get_mut()
+            .send(Message::Close(None))
+            .await
+            .map_err(|e| ConnectionError::WebSocket(format!("Failed to close: {}", e)))?;
+        debug!(peer = %self.peer_addr, "Browser connection closed");
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    async fn read_char(&mut self) -> Result<char, ConnectionError> {
+        loop {
+            if let Some(ch) = take_utf8_char(&mut self.pending) {
+                return Ok(ch);
+            }
+
+            match self.next_input().await? {
+                Some(data) => self.pending.extend(data),
+                None => return Err(ConnectionError::Closed),
+            }
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//! Error types for web server operations
+
+use std::io;
+use thiserror::Error;
+
+/// Result type alias for web server operations
+pub type Result<T> = std::result::Result<T, WebError>;
+
+/// Errors that can occur during web server operations
+#[derive(Error, Debug)]
+pub enum WebError {
+    /// I/O error occurred
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    /// The server stopped accepting connections
+    #[error("Web server is no longer accepting connections")]
+    ServerClosed,
+}
@@ -1,14 +1,39 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+//! Web frontend for Impulse BBS
+//!
+//! This crate lets visitors call the BBS from a web browser with no client
+//! install. It serves a self-contained terminal page and bridges the page's
+//! WebSocket into the same session and menu pipeline telnet callers use.
+//!
+//! # Features
+//!
+//! - HTTP server via `axum`
+//! - Embedded 80x24 ANSI terminal page at `/`, with no external assets
+//! - WebSocket endpoint at `/ws` speaking the JSON `BbsMessage` protocol
+//! - Async/await based on Tokio
+//! - Implements the transport-agnostic `impulse_session::Connection` trait
+//!
+//! # Example
+//!
+//! ```no_run
+//! use impulse_web::{Result, WebServer};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     let server = WebServer::bind("0.0.0.0:8080").await?;
+//!
+//!     loop {
+//!         let mut connection = server.accept().await?;
+//!         tokio::spawn(async move {
+//!             // Handle connection
+//!         });
+//!     }
+//! }
+//! ```
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+mod connection;
+mod error;
+mod server;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use connection::WebConnection;
+pub use error::{Result, WebError};
+pub use server::WebServer;
@@ -0,0 +1,251 @@
+//! HTTP server for the browser terminal
+
+use crate::connection::WebConnection;
+use crate::error::{Result, WebError};
+use axum::Router;
+use axum::extract::ws::WebSocketUpgrade;
+use axum::extract::{ConnectInfo, State};
+use axum::response::{Html, Response};
+use axum::routing::get;
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+use tokio::sync::{Mutex, mpsc};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Number of upgraded sockets that may wait in the accept queue
+const ACCEPT_QUEUE_SIZE: usize = 32;
+
+/// Browser terminal page served at `/`
+const INDEX_HTML: &str = include_str!("../static/index.html");
+
+/// HTTP server that serves the browser terminal and accepts its WebSockets
+///
+/// `GET /` returns a self-contained terminal page; the page connects back to
+/// `/ws`, and each upgraded socket is yielded by [`accept`](Self::accept).
+pub struct WebServer {
+    /// Local bind address
+    local_addr: SocketAddr,
+    /// Upgraded sockets waiting to be accepted
+    incoming: Mutex<mpsc::Receiver<WebConnection>>,
+    /// Background task serving HTTP
+    serve_task: JoinHandle<()>,
+}
+
+impl WebServer {
+    /// Bind to a local address
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use impulse_session::Connection;
+    /// use impulse_web::{Result, WebServer};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let server = WebServer::bind("0.0.0.0:8080").await?;
+    ///
+    ///     loop {
+    ///         let mut conn = server.accept().await?;
+    ///         tokio::spawn(async move {
+    ///             conn.send_text("Welcome!\r\n").await.ok();
+    ///         });
+    ///     }
+    /// }
+    /// ```
+    pub async fn bind(addr: &str) -> Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+
+        let (sender, receiver) = mpsc::channel(ACCEPT_QUEUE_SIZE);
+        let router = Router::new()
+            .route("/", get(index))
+            .route("/ws", get(upgrade))
+            .with_state(sender);
+
+        let serve_task = tokio::spawn(async move {
+            let service = router.into_make_service_with_connect_info::<SocketAddr>();
+            if let Err(e) = axum::serve(listener, service).await {
+                warn!(error = %e, "Web server stopped");
+            }
+        });
+
+        info!(address = %local_addr, "Web server listening");
+        Ok(Self {
+            local_addr,
+            incoming: Mutex::new(receiver),
+            serve_task,
+        })
+    }
+
+    /// Get the local address the server is bound to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Accept the next browser connection
+    pub async fn accept(&self) -> Result<WebConnection> {
+        self.incoming
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or(WebError::ServerClosed)
+    }
+}
+
+impl Drop for WebServer {
+    fn drop(&mut self) {
+        self.serve_task.abort();
+    }
+}
+
+/// Serve the terminal page
+async fn index() -> Html<&'static str> {
+    Html(INDEX_HTML)
+}
+
+/// Upgrade `/ws` requests and queue the socket for [`WebServer::accept`]
+async fn upgrade(
+    ws: WebSocketUpgrade,
+    ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
+    State(incoming): State<mpsc::Sender<WebConnection>>,
+) -> Response {
+    ws.on_upgrade(move |socket| async move {
+        debug!(peer = %peer_addr, "WebSocket connection opened");
+        if incoming
+            .send(WebConnection::new(socket, peer_addr))
+            .await
+            .is_err()
+        {
+            debug!(peer = %peer_addr, "Web server closed, dropping connection");
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::{SinkExt, StreamExt};
+    use impulse_session::{BbsMessage, Connection, ConnectionType};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+    type Client = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+    async fn connect(server: &WebServer) -> (Client, WebConnection) {
+        let url = format!("ws://{}/ws", server.local_addr());
+        let (client, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        let conn = server.accept().await.unwrap();
+        (client, conn)
+    }
+
+    async fn next_message(client: &mut Client) -> BbsMessage {
+        match client.next().await.unwrap().unwrap() {
+            Message::Text(json) => serde_json::from_str(&json).unwrap(),
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_serves_terminal_page() {
+        let server = WebServer::bind("127.0.0.1:0").await.unwrap();
+
+        let mut stream = tokio::net::TcpStream::connect(server.local_addr())
+            .await
+            .unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("text/html"));
+        assert!(response.contains("/ws"));
+    }
+
+    #[tokio::test]
+    async fn test_output_and_input() {
+        let server = WebServer::bind("127.0.0.1:0").await.unwrap();
+        let (mut client, mut conn) = connect(&server).await;
+
+        assert_eq!(conn.connection_type(), ConnectionType::WebSocket);
+        assert!(conn.is_connected());
+
+        conn.send_text("Welcome").await.unwrap();
+        match next_message(&mut client).await {
+            BbsMessage::Output { text } => assert_eq!(text, "Welcome"),
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        // Keystrokes arrive one input message at a time
+        for key in ["h", "i", "\r"] {
+            let input = serde_json::to_string(&BbsMessage::Input {
+                text: key.to_string(),
+            })
+            .unwrap();
+            client.send(Message::Text(input)).await.unwrap();
+        }
+        assert_eq!(conn.read_line().await.unwrap(), "hi");
+
+        // Typed characters are echoed by the server
+        let mut echoed = Vec::new();
+        while echoed.len() < 2 {
+            match client.next().await.unwrap().unwrap() {
+                Message::Binary(data) => echoed.extend_from_slice(&data),
+                other => panic!("unexpected message: {:?}", other),
+            }
+        }
+        assert_eq!(echoed, b"hi");
+    }
+
+    #[tokio::test]
+    async fn test_raw_output_and_split_input() {
+        let server = WebServer::bind("127.0.0.1:0").await.unwrap();
+        let (mut client, mut conn) = connect(&server).await;
+
+        // CP437 box drawing is not UTF-8 and must arrive unchanged
+        conn.send_bytes(&[0xc9, 0xcd, 0xbb]).await.unwrap();
+        match client.next().await.unwrap().unwrap() {
+            Message::Binary(data) => assert_eq!(&data[..], [0xc9, 0xcd, 0xbb]),
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        // A character split between frames is read whole
+        let bytes = "é!".as_bytes();
+        client
+            .send(Message::Binary(bytes[..1].to_vec()))
+            .await
+            .unwrap();
+        client
+            .send(Message::Binary(bytes[1..].to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(conn.read_char().await.unwrap(), 'é');
+        assert_eq!(conn.read_char().await.unwrap(), '!');
+    }
+
+    #[tokio::test]
+    async fn test_ping_and_close() {
+        let server = WebServer::bind("127.0.0.1:0").await.unwrap();
+        let (mut client, mut conn) = connect(&server).await;
+
+        let ping = serde_json::to_string(&BbsMessage::Ping).unwrap();
+        client.send(Message::Text(ping)).await.unwrap();
+        let input = serde_json::to_string(&BbsMessage::Input {
+            text: "x".to_string(),
+        })
+        .unwrap();
+        client.send(Message::Text(input)).await.unwrap();
+
+        assert_eq!(conn.read_char().await.unwrap(), 'x');
+        assert!(matches!(next_message(&mut client).await, BbsMessage::Pong));
+
+        client.close(None).await.unwrap();
+        assert!(conn.recv().await.unwrap().is_none());
+        assert!(!conn.is_connected());
+    }
+}